@@ -0,0 +1,31 @@
+//! Times the generation of Gaussian samples with each `GaussianTransform`, which backs the ordering stated in its documentation.
+//!
+//! Run with `cargo bench --bench gaussian_transforms`.
+
+use derivative_pricer::random_number_generator::{GaussianTransform, RandomNumberGenerator, RandomNumberGeneratorTrait};
+use derivative_pricer::utils::InverseCumulativeNormalAlgorithm;
+use std::hint::black_box;
+use std::time::Instant;
+
+const SAMPLES_PER_BATCH: usize = 1_000;
+const BATCHES: usize = 2_000;
+
+fn main(){
+    let transforms = [
+        ("inverse cumulative normal (Moro)", GaussianTransform::InverseCumulativeNormal(InverseCumulativeNormalAlgorithm::Moro)),
+        ("inverse cumulative normal (Wichura)", GaussianTransform::InverseCumulativeNormal(InverseCumulativeNormalAlgorithm::Wichura)),
+        ("Box-Muller", GaussianTransform::BoxMuller),
+        ("Ziggurat", GaussianTransform::Ziggurat),
+    ];
+    for (name, transform) in transforms {
+        let mut rng = RandomNumberGenerator::with_gaussian_transform(Some(1), transform);
+        // Warms up the caches and the branch predictors before timing.
+        black_box(rng.get_gaussians(SAMPLES_PER_BATCH));
+        let start = Instant::now();
+        for _ in 0..BATCHES {
+            black_box(rng.get_gaussians(SAMPLES_PER_BATCH));
+        }
+        let nanoseconds = start.elapsed().as_nanos() as f64/(SAMPLES_PER_BATCH*BATCHES) as f64;
+        println!("{name:<36} {nanoseconds:>6.2} ns per sample");
+    }
+}
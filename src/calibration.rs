@@ -0,0 +1,231 @@
+//! Provides the calibration of the Heston model to market prices of european vanilla options.
+//!
+//! The parameters are fitted with the Levenberg-Marquardt method, minimizing the sum of the squared differences between the
+//! model prices (computed with the `CarrMadanPricer` of the `fft_pricer` module) and the market prices. The positive
+//! parameters are optimized in log space and the correlation through `tanh`, so every iterate is a valid model.
+
+use crate::error::PricingError;
+use crate::fft_pricer::CarrMadanPricer;
+use crate::models::HestonModel;
+
+/// The maximal number of Levenberg-Marquardt iterations.
+const MAXIMAL_ITERATIONS: usize = 200;
+
+pub use crate::payoff::VanillaType;
+
+/// A market quote of a european vanilla option.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VanillaQuote{
+    /// The type of the option.
+    pub vanilla_type: VanillaType,
+    /// The strike of the option.
+    pub strike: f64,
+    /// The time to expiry of the option.
+    pub time_to_expiry: f64,
+    /// The market price of the option.
+    pub price: f64,
+}
+
+/// The result of a calibration.
+#[derive(Clone, Debug)]
+pub struct HestonCalibration{
+    /// The calibrated model.
+    model: HestonModel,
+    /// The model price minus the market price of every quote, in the order of the quotes.
+    errors: Vec<f64>,
+    /// The number of iterations performed.
+    iterations: usize,
+}
+
+impl HestonCalibration {
+    /// Returns the calibrated model.
+    pub fn get_model(&self)->&HestonModel{
+        &self.model
+    }
+
+    /// Returns the model price minus the market price of every quote, in the order of the quotes.
+    pub fn get_errors(&self)->&[f64]{
+        &self.errors
+    }
+
+    /// Returns the root mean square of the pricing errors.
+    pub fn get_root_mean_square_error(&self)->f64{
+        (self.errors.iter().map(|e| e*e).sum::<f64>()/self.errors.len() as f64).sqrt()
+    }
+
+    /// Returns the number of iterations performed.
+    pub fn get_iterations(&self)->usize{
+        self.iterations
+    }
+}
+
+/// Fits the Heston model to market quotes of european vanilla options.
+///
+/// # Parameters
+///
+/// - `quotes` - The market quotes. Quotes may have different expiries.
+/// - `spot` - The current spot of the stock.
+/// - `short_rate_of_interest` - The short rate of interest.
+/// - `divident_rate` - The continuous divident rate of the stock.
+/// - `initial_guess` - The model from which the optimization starts.
+/// - `pricer` - The FFT pricer used to price the quotes.
+///
+/// # Errors
+///
+/// Returns `PricingError::InvalidMarketData` if there are less quotes than the five parameters, or if a quote has a negative
+/// price, strike or time to expiry.
+pub fn calibrate_heston(quotes: &[VanillaQuote], spot: f64, short_rate_of_interest: f64, divident_rate: f64, initial_guess: &HestonModel,
+    pricer: &CarrMadanPricer)->Result<HestonCalibration, PricingError>{
+    if quotes.len() < 5 || quotes.iter().any(|q| !(q.strike > 0.0 && q.time_to_expiry > 0.0 && q.price >= 0.0)) {
+        return Err(PricingError::InvalidMarketData);
+    }
+    let residuals = |x: &[f64]| -> Vec<f64> {
+        let model = to_model(x, divident_rate);
+        model_prices(&model, quotes, spot, short_rate_of_interest, pricer).iter().zip(quotes).map(|(p, q)| p-q.price).collect()
+    };
+    let mut x = from_model(initial_guess);
+    let mut r = residuals(&x);
+    let mut cost: f64 = r.iter().map(|e| e*e).sum();
+    let mut damping = 1e-3;
+    let mut iterations = 0;
+    while iterations < MAXIMAL_ITERATIONS {
+        iterations += 1;
+        // Forward difference Jacobian of the residuals.
+        let jacobian: Vec<Vec<f64>> = (0..x.len()).map(|j| {
+            let step = 1e-6*x[j].abs().max(1.0);
+            let mut bumped = x.clone();
+            bumped[j] += step;
+            residuals(&bumped).iter().zip(&r).map(|(b, e)| (b-e)/step).collect()
+        }).collect();
+        let gradient: Vec<f64> = jacobian.iter().map(|column| column.iter().zip(&r).map(|(j, e)| j*e).sum()).collect();
+        let normal_matrix: Vec<Vec<f64>> = jacobian.iter()
+            .map(|a| jacobian.iter().map(|b| a.iter().zip(b).map(|(x, y)| x*y).sum()).collect()).collect();
+        let mut improved = false;
+        while damping < 1e10 {
+            let mut matrix = normal_matrix.clone();
+            for (i, row) in matrix.iter_mut().enumerate() {
+                row[i] += damping*normal_matrix[i][i].max(1e-12);
+            }
+            let step = solve_linear_system(matrix, gradient.iter().map(|g| -g).collect());
+            let candidate: Vec<f64> = x.iter().zip(&step).map(|(x, s)| x+s).collect();
+            let candidate_residuals = residuals(&candidate);
+            let candidate_cost: f64 = candidate_residuals.iter().map(|e| e*e).sum();
+            if candidate_cost.is_finite() && candidate_cost < cost {
+                let relative_improvement = (cost-candidate_cost)/cost.max(f64::MIN_POSITIVE);
+                x = candidate;
+                r = candidate_residuals;
+                cost = candidate_cost;
+                damping = f64::max(damping/10.0, 1e-12);
+                improved = relative_improvement > 1e-12;
+                break;
+            }
+            damping *= 10.0;
+        }
+        if !improved {
+            break;
+        }
+    }
+    Ok(HestonCalibration{
+        model: to_model(&x, divident_rate),
+        errors: r,
+        iterations,
+    })
+}
+
+/// Returns the model prices of the quotes, pricing each expiry with one transform per option type.
+fn model_prices(model: &HestonModel, quotes: &[VanillaQuote], spot: f64, short_rate_of_interest: f64, pricer: &CarrMadanPricer)->Vec<f64>{
+    let mut prices = vec![0.0; quotes.len()];
+    let mut expiries: Vec<f64> = quotes.iter().map(|q| q.time_to_expiry).collect();
+    expiries.sort_by(|a, b| a.total_cmp(b));
+    expiries.dedup();
+    for time_to_expiry in expiries {
+        for vanilla_type in [VanillaType::Call, VanillaType::Put] {
+            let indices: Vec<usize> = (0..quotes.len())
+                .filter(|i| quotes[*i].time_to_expiry == time_to_expiry && quotes[*i].vanilla_type == vanilla_type).collect();
+            if indices.is_empty() {
+                continue;
+            }
+            let strikes: Vec<f64> = indices.iter().map(|i| quotes[*i].strike).collect();
+            let slice_prices = match vanilla_type {
+                VanillaType::Call => pricer.call_prices(model, spot, short_rate_of_interest, time_to_expiry, &strikes),
+                VanillaType::Put => pricer.put_prices(model, spot, short_rate_of_interest, time_to_expiry, &strikes),
+            };
+            for (i, price) in indices.into_iter().zip(slice_prices) {
+                prices[i] = price;
+            }
+        }
+    }
+    prices
+}
+
+/// Returns the unconstrained coordinates of a Heston model.
+fn from_model(model: &HestonModel)->Vec<f64>{
+    vec![model.get_initial_variance().max(1e-8).ln(), model.get_mean_reversion_speed().max(1e-8).ln(),
+        model.get_long_term_variance().max(1e-8).ln(), model.get_volatility_of_variance().max(1e-8).ln(),
+        model.get_correlation().clamp(-0.999, 0.999).atanh()]
+}
+
+/// Returns the Heston model with the given unconstrained coordinates.
+fn to_model(x: &[f64], divident_rate: f64)->HestonModel{
+    HestonModel::new(x[0].exp(), x[1].exp(), x[2].exp(), x[3].exp().max(1e-8), x[4].tanh(), divident_rate)
+}
+
+/// Solves the linear system `matrix*x = right_hand_side` by Gaussian elimination with partial pivoting.
+fn solve_linear_system(mut matrix: Vec<Vec<f64>>, mut right_hand_side: Vec<f64>)->Vec<f64>{
+    let n = right_hand_side.len();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|a, b| matrix[*a][column].abs().total_cmp(&matrix[*b][column].abs())).unwrap_or(column);
+        matrix.swap(column, pivot);
+        right_hand_side.swap(column, pivot);
+        for row in column+1..n {
+            let factor = matrix[row][column]/matrix[column][column];
+            let pivot_row = matrix[column].clone();
+            for (entry, pivot_entry) in matrix[row].iter_mut().zip(&pivot_row).skip(column) {
+                *entry -= factor*pivot_entry;
+            }
+            right_hand_side[row] -= factor*right_hand_side[column];
+        }
+    }
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row+1..n).map(|j| matrix[row][j]*solution[j]).sum();
+        solution[row] = (right_hand_side[row]-sum)/matrix[row][row];
+    }
+    solution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_system_test(){
+        let solution = solve_linear_system(vec![vec![0.0, 2.0, 1.0], vec![1.0, 1.0, 0.0], vec![3.0, 0.0, 1.0]], vec![5.0, 3.0, 4.0]);
+        for (x, expected) in solution.iter().zip([1.0, 2.0, 1.0]) {
+            assert!((x-expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn heston_calibration_test(){
+        let pricer = CarrMadanPricer::default();
+        let true_model = HestonModel::new(0.04, 1.5, 0.06, 0.5, -0.6, 0.01);
+        let mut quotes = Vec::new();
+        for time_to_expiry in [0.5, 1.0, 2.0] {
+            for strike in [80.0, 90.0, 100.0, 110.0, 120.0] {
+                let vanilla_type = if strike < 100.0 { VanillaType::Put } else { VanillaType::Call };
+                let quote = VanillaQuote{vanilla_type, strike, time_to_expiry, price: 0.0};
+                let price = model_prices(&true_model, &[quote], 100.0, 0.03, &pricer)[0];
+                quotes.push(VanillaQuote{price, ..quote});
+            }
+        }
+        let initial_guess = HestonModel::new(0.09, 1.0, 0.09, 0.3, -0.2, 0.01);
+        let calibration = calibrate_heston(&quotes, 100.0, 0.03, 0.01, &initial_guess, &pricer).unwrap();
+        assert!(calibration.get_root_mean_square_error() < 1e-4);
+        assert_eq!(calibration.get_errors().len(), quotes.len());
+        let model = calibration.get_model();
+        assert!((model.get_initial_variance()-0.04).abs() < 1e-3);
+        assert!((model.get_correlation()+0.6).abs() < 0.05);
+        assert_eq!(calibrate_heston(&quotes[..3], 100.0, 0.03, 0.01, &initial_guess, &pricer).err(), Some(PricingError::InvalidMarketData));
+    }
+}
@@ -0,0 +1,205 @@
+//! Provides the prices and greeks of whole option chains, i.e. of european calls and puts on a grid of strikes and expiries
+//! with a common spot, volatility, rate and divident rate.
+//!
+//! Every grid point is evaluated by a `raw_formulas::BsCalculator`, so `d1`, `d2`, the discount factors and the normal
+//! distribution terms are computed once and shared by the call, the put and all their greeks, which is much cheaper than calling
+//! the formulas of `raw_formulas` one by one.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::chain::Chain;
+//! use derivative_pricer::raw_formulas;
+//! let chain = Chain::new(100.0, 0.2, 0.05, 0.01);
+//! let grid = chain.greeks(&[90.0, 100.0, 110.0], &[0.5, 1.0]);
+//! let call = grid.get_call(1, 0);
+//! assert!((call.price-raw_formulas::european_call_option_price(100.0, 90.0, 0.05, 1.0, 0.2, 0.01)).abs() < 1e-12);
+//! ```
+
+use crate::error::PricingError;
+use crate::greeks::Greeks;
+use crate::raw_formulas::BsCalculator;
+
+/// The market data shared by the options of a chain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Chain{
+    spot: f64,
+    volatility: f64,
+    short_rate_of_interest: f64,
+    divident_rate: f64,
+}
+
+/// The prices and greeks of the calls and puts of a chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainGreeks{
+    strikes: Vec<f64>,
+    expiries: Vec<f64>,
+    /// The greeks of the calls, with the strikes of an expiry stored contiguously.
+    calls: Vec<Greeks>,
+    /// The greeks of the puts, in the same order as `calls`.
+    puts: Vec<Greeks>,
+}
+
+impl ChainGreeks {
+    /// Returns the strikes of the grid.
+    pub fn get_strikes(&self)->&[f64]{
+        &self.strikes
+    }
+
+    /// Returns the expiries of the grid.
+    pub fn get_expiries(&self)->&[f64]{
+        &self.expiries
+    }
+
+    /// Returns the price and greeks of the call with expiry `self.get_expiries()[expiry_index]` and strike
+    /// `self.get_strikes()[strike_index]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the indices is out of range.
+    pub fn get_call(&self, expiry_index: usize, strike_index: usize)->Greeks{
+        self.calls[self.index(expiry_index, strike_index)]
+    }
+
+    /// Returns the price and greeks of the put with expiry `self.get_expiries()[expiry_index]` and strike
+    /// `self.get_strikes()[strike_index]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the indices is out of range.
+    pub fn get_put(&self, expiry_index: usize, strike_index: usize)->Greeks{
+        self.puts[self.index(expiry_index, strike_index)]
+    }
+
+    /// Returns the greeks of the calls with expiry `self.get_expiries()[expiry_index]`, in the order of the strikes.
+    pub fn get_calls_at_expiry(&self, expiry_index: usize)->&[Greeks]{
+        let n = self.strikes.len();
+        &self.calls[expiry_index*n..(expiry_index+1)*n]
+    }
+
+    /// Returns the greeks of the puts with expiry `self.get_expiries()[expiry_index]`, in the order of the strikes.
+    pub fn get_puts_at_expiry(&self, expiry_index: usize)->&[Greeks]{
+        let n = self.strikes.len();
+        &self.puts[expiry_index*n..(expiry_index+1)*n]
+    }
+
+    fn index(&self, expiry_index: usize, strike_index: usize)->usize{
+        assert!(expiry_index < self.expiries.len() && strike_index < self.strikes.len(), "Index out of range");
+        expiry_index*self.strikes.len()+strike_index
+    }
+}
+
+impl Chain {
+    /// Returns a chain with the given market data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the spot, the volatility or the divident rate is negative.
+    pub fn new(spot: f64, volatility: f64, short_rate_of_interest: f64, divident_rate: f64)->Chain{
+        match Chain::try_new(spot, volatility, short_rate_of_interest, divident_rate) {
+            Ok(chain) => chain,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `new`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_new(spot: f64, volatility: f64, short_rate_of_interest: f64, divident_rate: f64)->Result<Chain, PricingError>{
+        if [spot, volatility, divident_rate].iter().any(|x| *x < 0.0 || x.is_nan()) {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(Chain{spot, volatility, short_rate_of_interest, divident_rate})
+    }
+
+    /// Returns the spot.
+    pub fn get_spot(&self)->f64{
+        self.spot
+    }
+
+    /// Returns the volatility.
+    pub fn get_volatility(&self)->f64{
+        self.volatility
+    }
+
+    /// Returns the short rate of interest.
+    pub fn get_short_rate_of_interest(&self)->f64{
+        self.short_rate_of_interest
+    }
+
+    /// Returns the divident rate.
+    pub fn get_divident_rate(&self)->f64{
+        self.divident_rate
+    }
+
+    /// Returns the prices and greeks of the calls and puts with every pair of strike in `strikes` and expiry in `expiries`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the strikes or expiries is negative.
+    pub fn greeks(&self, strikes: &[f64], expiries: &[f64])->ChainGreeks{
+        match self.try_greeks(strikes, expiries) {
+            Ok(greeks) => greeks,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `greeks`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_greeks(&self, strikes: &[f64], expiries: &[f64])->Result<ChainGreeks, PricingError>{
+        if strikes.iter().chain(expiries).any(|x| *x < 0.0 || x.is_nan()) {
+            return Err(PricingError::NegativeInput);
+        }
+        let (spot, volatility, r, q) = (self.spot, self.volatility, self.short_rate_of_interest, self.divident_rate);
+        let mut calls = Vec::with_capacity(strikes.len()*expiries.len());
+        let mut puts = Vec::with_capacity(strikes.len()*expiries.len());
+        for &t in expiries {
+            for &k in strikes {
+                if t == 0.0 {
+                    let in_the_money = if spot > k { 1.0 } else { 0.0 };
+                    calls.push(intrinsic_greeks(f64::max(spot-k, 0.0), in_the_money));
+                    puts.push(intrinsic_greeks(f64::max(k-spot, 0.0), in_the_money-1.0));
+                    continue;
+                }
+                let calculator = BsCalculator::new(spot, k, r, t, volatility, q);
+                calls.push(calculator.call_greeks());
+                puts.push(calculator.put_greeks());
+            }
+        }
+        Ok(ChainGreeks{strikes: strikes.to_vec(), expiries: expiries.to_vec(), calls, puts})
+    }
+}
+
+/// Returns the greeks of an expiring option with the given payoff and delta.
+fn intrinsic_greeks(price: f64, delta: f64)->Greeks{
+    Greeks{price, delta, gamma: 0.0, vega: 0.0, theta: 0.0, rho: 0.0}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw_formulas;
+
+    #[test]
+    fn chain_test(){
+        let (spot, vol, r, q) = (101.2, 0.15, 0.07, 0.03);
+        let strikes = [80.0, 95.0, 123.0, 150.0];
+        let expiries = [0.0, 0.25, 1.43];
+        let grid = Chain::new(spot, vol, r, q).greeks(&strikes, &expiries);
+        for (i, &t) in expiries.iter().enumerate().skip(1) {
+            for (j, &k) in strikes.iter().enumerate() {
+                let (call, put) = (grid.get_call(i, j), grid.get_put(i, j));
+                assert!((call.price-raw_formulas::european_call_option_price(spot, k, r, t, vol, q)).abs() < 1e-10);
+                assert!((put.price-raw_formulas::european_put_option_price(spot, k, r, t, vol, q)).abs() < 1e-10);
+                assert!((call.delta-raw_formulas::call_delta(spot, k, r, t, vol, q)).abs() < 1e-12);
+                assert!((put.delta-raw_formulas::put_delta(spot, k, r, t, vol, q)).abs() < 1e-12);
+                assert!((call.gamma-raw_formulas::call_gamma(spot, k, r, t, vol, q)).abs() < 1e-12);
+                assert!((call.vega-raw_formulas::call_vega(spot, k, r, t, vol, q)).abs() < 1e-10);
+                assert!((call.theta-raw_formulas::call_theta(spot, k, r, t, vol, q)).abs() < 1e-10);
+                assert!((put.theta-raw_formulas::put_theta(spot, k, r, t, vol, q)).abs() < 1e-10);
+                assert!((call.rho-raw_formulas::call_rho(spot, k, r, t, vol, q)).abs() < 1e-10);
+                assert!((put.rho-raw_formulas::put_rho(spot, k, r, t, vol, q)).abs() < 1e-10);
+            }
+        }
+        assert_eq!(grid.get_call(0, 0).price, spot-80.0);
+        assert_eq!(grid.get_puts_at_expiry(0)[2].delta, -1.0);
+        assert_eq!(Chain::new(spot, vol, r, q).try_greeks(&[-1.0], &[1.0]), Err(PricingError::NegativeInput));
+    }
+}
@@ -0,0 +1,282 @@
+//! Provides the one-factor commodity model of Schwartz (1997), in which the log of the spot price is mean reverting.
+//!
+//! Under the pricing measure the spot price is `S_t = exp(X_t+s(t))`, where `X` follows the Ornstein-Uhlenbeck SDE
+//! `dX = kappa*(alpha-X)*dt + sigma*dW` and `s` is a deterministic seasonality (zero unless given). Futures prices are
+//! lognormal, so futures and european options have closed form prices, and the seasonality can be fitted so that the model
+//! reproduces an observed futures curve exactly.
+//!
+//! Times are measured in years from the valuation time, which is time 0.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::payoff::VanillaType;
+//! use derivative_pricer::commodity::SchwartzModel;
+//! let model = SchwartzModel::new(50.0, 1.2, 50.0_f64.ln(), 0.35)
+//!     .fit_to_futures_curve(&[0.5, 1.0, 2.0], &[52.0, 55.0, 53.5]).unwrap();
+//! assert!((model.futures_price(1.0)-55.0).abs() < 1e-10);
+//! let call = model.futures_option_price(VanillaType::Call, 55.0, 0.75, 1.0, 0.03);
+//! assert!(call > 0.0);
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::payoff::VanillaType;
+use crate::error::PricingError;
+use crate::ornstein_uhlenbeck::OrnsteinUhlenbeckProcess;
+use crate::utils::{cumulative_normal_function, NonNegativeFloat, TimeStamp};
+
+/// The Schwartz one-factor model of a commodity spot price.
+#[derive(Clone)]
+pub struct SchwartzModel{
+    /// The current spot price.
+    spot: f64,
+    /// The speed `kappa` at which the log price reverts.
+    mean_reversion_speed: f64,
+    /// The long term level `alpha` of the log price (without seasonality) under the pricing measure.
+    long_term_log_price: f64,
+    /// The volatility `sigma` of the log price.
+    volatility: f64,
+    /// The seasonality `s(t)` added to the log price, `None` for no seasonality.
+    seasonality: Option<Arc<dyn Fn(f64)->f64+Send+Sync>>,
+}
+
+impl fmt::Debug for SchwartzModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchwartzModel")
+            .field("spot", &self.spot)
+            .field("mean_reversion_speed", &self.mean_reversion_speed)
+            .field("long_term_log_price", &self.long_term_log_price)
+            .field("volatility", &self.volatility)
+            .field("seasonal", &self.seasonality.is_some())
+            .finish()
+    }
+}
+
+impl SchwartzModel {
+    /// Returns a new Schwartz model without seasonality.
+    /// # Parameters
+    /// - `spot` - The current spot price.
+    /// - `mean_reversion_speed` - The speed `kappa` at which the log price reverts.
+    /// - `long_term_log_price` - The long term level `alpha` of the log price under the pricing measure, i.e. the long term
+    ///   level under the real measure minus the market price of risk over `kappa`.
+    /// - `volatility` - The volatility `sigma` of the log price.
+    /// # Panics
+    /// Panics if `spot`, `mean_reversion_speed` or `volatility` is negative, or if `spot` is zero.
+    pub fn new(spot: f64, mean_reversion_speed: f64, long_term_log_price: f64, volatility: f64)->SchwartzModel{
+        match SchwartzModel::try_new(spot, mean_reversion_speed, long_term_log_price, volatility) {
+            Ok(model) => model,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `new`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_new(spot: f64, mean_reversion_speed: f64, long_term_log_price: f64, volatility: f64)->Result<SchwartzModel, PricingError>{
+        if !(spot > 0.0 && mean_reversion_speed >= 0.0 && volatility >= 0.0) || long_term_log_price.is_nan() {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(SchwartzModel{spot, mean_reversion_speed, long_term_log_price, volatility, seasonality: None})
+    }
+
+    /// Returns the model with the seasonality `seasonality(t)` added to the log price at time `t`. The current spot is kept,
+    /// i.e. the seasonality is measured relative to its value at time 0.
+    pub fn with_seasonality(self, seasonality: impl Fn(f64)->f64+Send+Sync+'static)->SchwartzModel{
+        SchwartzModel{seasonality: Some(Arc::new(seasonality)), ..self}
+    }
+
+    /// Returns the model with the seasonality chosen so that its futures prices are the market futures prices `futures` at
+    /// the maturities `maturities`, interpolating the seasonality linearly in between (and keeping it flat after the last
+    /// maturity). Any previous seasonality is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PricingError::InvalidMarketData` if the slices have different lengths or are empty, if the maturities are not
+    /// positive and strictly increasing, or if a futures price is not positive.
+    pub fn fit_to_futures_curve(&self, maturities: &[f64], futures: &[f64])->Result<SchwartzModel, PricingError>{
+        if maturities.len() != futures.len() || maturities.is_empty() || !maturities.iter().chain(futures).all(|x| *x > 0.0)
+            || maturities.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(PricingError::InvalidMarketData);
+        }
+        let unseasonal = SchwartzModel{seasonality: None, ..self.clone()};
+        let mut times = vec![0.0];
+        let mut shifts = vec![0.0];
+        for (maturity, price) in maturities.iter().zip(futures) {
+            times.push(*maturity);
+            shifts.push(price.ln()-unseasonal.futures_price(*maturity).ln());
+        }
+        Ok(unseasonal.with_seasonality(move |t| {
+            let i = times.partition_point(|s| *s <= t);
+            if i == times.len() {
+                return shifts[i-1];
+            }
+            let weight = (t-times[i-1])/(times[i]-times[i-1]);
+            shifts[i-1]+weight*(shifts[i]-shifts[i-1])
+        }))
+    }
+
+    /// Returns the current spot price.
+    pub fn get_spot(&self)->f64{
+        self.spot
+    }
+
+    /// Returns the speed of mean reversion of the log price.
+    pub fn get_mean_reversion_speed(&self)->f64{
+        self.mean_reversion_speed
+    }
+
+    /// Returns the long term level of the log price under the pricing measure.
+    pub fn get_long_term_log_price(&self)->f64{
+        self.long_term_log_price
+    }
+
+    /// Returns the volatility of the log price.
+    pub fn get_volatility(&self)->f64{
+        self.volatility
+    }
+
+    /// Returns the seasonality of the log price at time `time`, zero if the model has none.
+    pub fn seasonality(&self, time: f64)->f64{
+        self.seasonality.as_ref().map_or(0.0, |s| s(time))
+    }
+
+    /// Returns the futures price for delivery at `maturity`, i.e. the expected spot price at `maturity`.
+    pub fn futures_price(&self, maturity: f64)->f64{
+        let (mean, variance) = self.log_price_moments(maturity);
+        (mean+0.5*variance).exp()
+    }
+
+    /// Returns the price of a european option expiring at `expiry` on the futures contract for delivery at `maturity`.
+    ///
+    /// # Parameters
+    /// - `vanilla_type` - Call or put.
+    /// - `strike` - The strike of the option.
+    /// - `expiry` - The expiry of the option, not after `maturity`.
+    /// - `maturity` - The delivery time of the futures contract.
+    /// - `short_rate_of_interest` - The short rate of interest, used for discounting the payoff at `expiry`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `strike` or `expiry` is negative, or if `expiry` is after `maturity`.
+    pub fn futures_option_price(&self, vanilla_type: VanillaType, strike: f64, expiry: f64, maturity: f64, short_rate_of_interest: f64)->f64{
+        match self.try_futures_option_price(vanilla_type, strike, expiry, maturity, short_rate_of_interest) {
+            Ok(price) => price,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `futures_option_price`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_futures_option_price(&self, vanilla_type: VanillaType, strike: f64, expiry: f64, maturity: f64, short_rate_of_interest: f64)
+        ->Result<f64, PricingError>{
+        if !(strike >= 0.0 && expiry >= 0.0 && maturity >= expiry) {
+            return Err(PricingError::NegativeInput);
+        }
+        let forward = self.futures_price(maturity);
+        // The log of the futures price at expiry has the variance of X at expiry, damped by the reversion until maturity.
+        let variance = self.log_variance(expiry)*(-2.0*self.mean_reversion_speed*(maturity-expiry)).exp();
+        let discount_factor = (-short_rate_of_interest*expiry).exp();
+        let (call_part, put_part) = if variance > 0.0 && strike > 0.0 {
+            let deviation = variance.sqrt();
+            let d1 = ((forward/strike).ln()+0.5*variance)/deviation;
+            let d2 = d1-deviation;
+            (forward*cumulative_normal_function(d1)-strike*cumulative_normal_function(d2),
+                strike*cumulative_normal_function(-d2)-forward*cumulative_normal_function(-d1))
+        }
+        else {
+            (f64::max(forward-strike, 0.0), f64::max(strike-forward, 0.0))
+        };
+        Ok(discount_factor*match vanilla_type {
+            VanillaType::Call => call_part,
+            VanillaType::Put => put_part,
+        })
+    }
+
+    /// Returns the price of a european option on the spot price at `expiry`, which is the option on the futures contract
+    /// delivering at expiry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `strike` or `expiry` is negative.
+    pub fn european_option_price(&self, vanilla_type: VanillaType, strike: f64, expiry: f64, short_rate_of_interest: f64)->f64{
+        self.futures_option_price(vanilla_type, strike, expiry, expiry, short_rate_of_interest)
+    }
+
+    /// Generates the spot prices at the given times under the pricing measure, simulating the log price exactly.
+    /// # Parameters
+    /// - `gaussians` - iid samples of N(0,1). Must be the same size or larger than `times`.
+    /// - `times` - Increasing, non negative times.
+    /// # Errors
+    /// Returns `PricingError::InsufficientSamples` if there are less samples than times, and `PricingError::InvalidTimeGrid`
+    /// if the times are empty, negative or decreasing.
+    pub fn generate_path(&self, gaussians: &[f64], times: &[f64])->Result<Vec<f64>, PricingError>{
+        if !times.iter().all(|t| *t >= 0.0) {
+            return Err(PricingError::InvalidTimeGrid);
+        }
+        let time_stamps: Vec<TimeStamp> = times.iter().map(|t| TimeStamp::from(*t)).collect();
+        let path = self.log_price_process().try_generate_path_from_time_stamps(gaussians, &time_stamps)?;
+        Ok(path.iter().zip(times).map(|(state, t)| (state.get_value()+self.seasonality(*t)).exp()).collect())
+    }
+
+    /// Returns the Ornstein-Uhlenbeck process `X`, started at time 0.
+    fn log_price_process(&self)->OrnsteinUhlenbeckProcess{
+        OrnsteinUhlenbeckProcess::new(self.spot.ln()-self.seasonality(0.0), TimeStamp::from(0.0), NonNegativeFloat::from(self.mean_reversion_speed),
+            self.long_term_log_price, NonNegativeFloat::from(self.volatility))
+    }
+
+    /// Returns the variance of the log price at `time`.
+    fn log_variance(&self, time: f64)->f64{
+        self.log_price_process().get_variance(time.into())
+    }
+
+    /// Returns the mean and the variance of the log price at `time`.
+    fn log_price_moments(&self, time: f64)->(f64, f64){
+        let process = self.log_price_process();
+        (process.get_mean(time.into())+self.seasonality(time), process.get_variance(time.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random_number_generator::{RandomNumberGenerator, RandomNumberGeneratorTrait};
+
+    #[test]
+    fn schwartz_futures_test(){
+        let model = SchwartzModel::new(40.0, 0.8, 45.0_f64.ln(), 0.3);
+        assert_eq!(model.futures_price(0.0), 40.0);
+        // The futures price tends to exp(alpha+sigma^2/(4*kappa)).
+        assert!((model.futures_price(100.0)-(45.0_f64.ln()+0.09/3.2).exp()).abs() < 1e-8);
+        let gaussians = RandomNumberGenerator::new(Some(3)).get_gaussians(50000);
+        let mean = gaussians.iter().map(|z| model.generate_path(&[*z], &[1.5]).unwrap()[0]).sum::<f64>()/50000.0;
+        assert!((mean-model.futures_price(1.5)).abs() < 0.1);
+        assert_eq!(model.generate_path(&[0.0], &[-1.0]), Err(PricingError::InvalidTimeGrid));
+    }
+
+    #[test]
+    fn schwartz_options_test(){
+        let model = SchwartzModel::new(40.0, 0.8, 45.0_f64.ln(), 0.3).with_seasonality(|t| 0.1*(2.0*std::f64::consts::PI*t).sin());
+        let (strike, expiry, maturity, r) = (42.0, 0.5, 1.25, 0.04);
+        let call = model.futures_option_price(VanillaType::Call, strike, expiry, maturity, r);
+        let put = model.futures_option_price(VanillaType::Put, strike, expiry, maturity, r);
+        assert!((call-put-(-r*expiry).exp()*(model.futures_price(maturity)-strike)).abs() < 1e-10);
+        // Without mean reversion the model is Black's model with volatility sigma.
+        let black = SchwartzModel::new(40.0, 0.0, 0.0, 0.3);
+        let forward = black.futures_price(expiry);
+        let expected = crate::raw_formulas::european_call_option_price(forward, strike, 0.0, expiry, 0.3, 0.0)*(-r*expiry).exp();
+        assert!((black.european_option_price(VanillaType::Call, strike, expiry, r)-expected).abs() < 1e-10);
+        assert_eq!(model.try_futures_option_price(VanillaType::Put, strike, 2.0, 1.0, r), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn schwartz_fit_test(){
+        let maturities = [0.25, 0.5, 1.0];
+        let futures = [41.0, 39.5, 43.0];
+        let model = SchwartzModel::new(40.0, 1.0, 40.0_f64.ln(), 0.25).fit_to_futures_curve(&maturities, &futures).unwrap();
+        for (maturity, price) in maturities.iter().zip(futures) {
+            assert!((model.futures_price(*maturity)-price).abs() < 1e-10);
+        }
+        assert_eq!(model.futures_price(0.0), 40.0);
+        assert!(model.futures_price(0.75) > 39.5 && model.futures_price(0.75) < 43.0);
+        assert!(model.fit_to_futures_curve(&[0.5, 0.25], &[1.0, 1.0]).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Provides the distribution of the price of a `GeometricBrownianMotionStock` at a horizon, under the real world or the risk
+//! neutral measure, to compute densities, probabilities, moments and quantiles of the terminal price, or custom expectations
+//! of it, without simulating paths.
+//!
+//! The log of the terminal price is normally distributed, with the drift (or the short rate of interest), the divident rate and
+//! the volatility of the stock integrated up to the horizon. This also holds for term structures and for a stochastic
+//! Ornstein-Uhlenbeck divident rate, but not for cash dividents, which are therefore not allowed before the horizon.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::distributions::TerminalDistribution;
+//! use derivative_pricer::stock::GeometricBrownianMotionStock;
+//! use derivative_pricer::utils::{Duration, NonNegativeFloat, TimeStamp};
+//! let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 0.08,
+//!     NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
+//! let distribution = TerminalDistribution::risk_neutral(&stock, Duration::from(1.0), 0.05);
+//! assert!((distribution.mean()-100.0*0.05_f64.exp()).abs() < 1e-10);
+//! assert!((distribution.cdf(distribution.quantile(0.3))-0.3).abs() < 1e-8);
+//! ```
+
+use crate::error::PricingError;
+use crate::numerics::integration;
+use crate::random_number_generator::RandomNumberGeneratorTrait;
+use crate::stock::GeometricBrownianMotionStock;
+use crate::term_structure::RateCurve;
+use crate::utils::{self, Duration};
+
+/// The lognormal distribution of the price of a stock at a horizon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerminalDistribution{
+    /// The mean of the log price.
+    log_mean: f64,
+    /// The standard deviation of the log price.
+    log_deviation: f64,
+}
+
+impl TerminalDistribution {
+    /// Returns the distribution of the price of `stock` `time_to_horizon` after its current time, under the real world measure,
+    /// i.e. with the drift of the stock.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stock pays a cash divident before the horizon, see `try_new`.
+    pub fn new(stock: &GeometricBrownianMotionStock, time_to_horizon: Duration)->TerminalDistribution{
+        TerminalDistribution::try_new(stock, time_to_horizon).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `new`, but returns `PricingError::InvalidMarketData` if the stock pays a cash divident before the horizon, as the
+    /// price is then not lognormally distributed.
+    pub fn try_new(stock: &GeometricBrownianMotionStock, time_to_horizon: Duration)->Result<TerminalDistribution, PricingError>{
+        let now = f64::from(stock.get_current_state().get_time());
+        TerminalDistribution::with_growth(stock, time_to_horizon, stock.get_drift_curve().integral(now, now+f64::from(time_to_horizon)))
+    }
+
+    /// Returns the distribution of the price of `stock` `time_to_horizon` after its current time, under the risk neutral
+    /// measure, i.e. with the short rate of interest `r`, either constant (an `f64`) or a `RateCurve`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stock pays a cash divident before the horizon, see `try_new`.
+    pub fn risk_neutral(stock: &GeometricBrownianMotionStock, time_to_horizon: Duration, r: impl RateCurve)->TerminalDistribution{
+        TerminalDistribution::try_risk_neutral(stock, time_to_horizon, r).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `risk_neutral`, but returns `PricingError::InvalidMarketData` if the stock pays a cash divident before the horizon.
+    pub fn try_risk_neutral(stock: &GeometricBrownianMotionStock, time_to_horizon: Duration, r: impl RateCurve)
+        ->Result<TerminalDistribution, PricingError>{
+        let now = f64::from(stock.get_current_state().get_time());
+        TerminalDistribution::with_growth(stock, time_to_horizon, r.integral(now, now+f64::from(time_to_horizon)))
+    }
+
+    /// Returns the distribution of the price of `stock` at the horizon when the integral of its drift up to it is `growth`.
+    fn with_growth(stock: &GeometricBrownianMotionStock, time_to_horizon: Duration, growth: f64)->Result<TerminalDistribution, PricingError>{
+        let state = stock.get_current_state();
+        let horizon = state.get_time()+time_to_horizon;
+        if stock.get_cash_dividents().iter().any(|(time, _)| *time > state.get_time() && *time <= horizon) {
+            return Err(PricingError::InvalidMarketData);
+        }
+        let tau = f64::from(time_to_horizon);
+        let volatility = f64::from(stock.get_root_mean_square_volatility(time_to_horizon));
+        let divident_rate = stock.get_average_divident_rate(time_to_horizon);
+        let log_mean = f64::from(state.get_value()).ln()+growth-(divident_rate+0.5*volatility*volatility)*tau;
+        Ok(TerminalDistribution{log_mean, log_deviation: volatility*tau.sqrt()})
+    }
+
+    /// Returns the mean of the log of the terminal price.
+    pub fn get_log_mean(&self)->f64{
+        self.log_mean
+    }
+
+    /// Returns the standard deviation of the log of the terminal price.
+    pub fn get_log_deviation(&self)->f64{
+        self.log_deviation
+    }
+
+    /// Returns the probability density of the terminal price at `price`. It is zero for non positive prices, and for all prices
+    /// but the median if the distribution is degenerate.
+    pub fn density(&self, price: f64)->f64{
+        if price <= 0.0 || self.log_deviation == 0.0 {
+            return 0.0;
+        }
+        utils::normal_probability_density_function((price.ln()-self.log_mean)/self.log_deviation)/(price*self.log_deviation)
+    }
+
+    /// Returns the probability that the terminal price is at most `price`.
+    pub fn cdf(&self, price: f64)->f64{
+        if price <= 0.0 {
+            return 0.0;
+        }
+        if self.log_deviation == 0.0 {
+            return if price.ln() >= self.log_mean { 1.0 } else { 0.0 };
+        }
+        utils::cumulative_normal_function((price.ln()-self.log_mean)/self.log_deviation)
+    }
+
+    /// Returns the terminal price below which it lies with probability `probability`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` is not in [0,1].
+    pub fn quantile(&self, probability: f64)->f64{
+        if !(0.0..=1.0).contains(&probability) {
+            panic!("The probability must be between 0 and 1.");
+        }
+        if probability == 0.0 {
+            return 0.0;
+        }
+        if probability == 1.0 {
+            return f64::INFINITY;
+        }
+        (self.log_mean+self.log_deviation*utils::inverse_cumulative_normal_function(probability)).exp()
+    }
+
+    /// Returns the `n`-th raw moment `E[S^n]` of the terminal price.
+    pub fn moment(&self, n: f64)->f64{
+        (n*self.log_mean+0.5*n*n*self.log_deviation*self.log_deviation).exp()
+    }
+
+    /// Returns the mean of the terminal price, under the risk neutral measure its forward.
+    pub fn mean(&self)->f64{
+        self.moment(1.0)
+    }
+
+    /// Returns the variance of the terminal price.
+    pub fn variance(&self)->f64{
+        let mean = self.mean();
+        mean*mean*(self.log_deviation*self.log_deviation).exp_m1()
+    }
+
+    /// Returns the median of the terminal price.
+    pub fn median(&self)->f64{
+        self.log_mean.exp()
+    }
+
+    /// Returns the terminal price corresponding to the standard normal sample `gaussian`, i.e. `exp(m+s*gaussian)`.
+    pub fn sample(&self, gaussian: f64)->f64{
+        (self.log_mean+self.log_deviation*gaussian).exp()
+    }
+
+    /// Returns `number_of_samples` independent samples of the terminal price drawn with `rng`.
+    pub fn samples(&self, rng: &mut impl RandomNumberGeneratorTrait, number_of_samples: usize)->Vec<f64>{
+        rng.get_gaussians(number_of_samples).into_iter().map(|gaussian| self.sample(gaussian)).collect()
+    }
+
+    /// Returns the expectation of `f` of the terminal price, with the `number_of_nodes` point Gauss-Hermite rule. It is exact
+    /// for polynomials in the log price of degree less than `2*number_of_nodes`, and accurate for smooth payoffs. Payoffs with
+    /// kinks, like calls, converge slowly, and the rule loses accuracy beyond about a hundred nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `number_of_nodes` is zero.
+    pub fn expectation(&self, f: &dyn Fn(f64)->f64, number_of_nodes: usize)->f64{
+        let sqrt_two = core::f64::consts::SQRT_2;
+        integration::gauss_hermite(&|x| f(self.sample(sqrt_two*x)), number_of_nodes)/core::f64::consts::PI.sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random_number_generator::RandomNumberGenerator;
+    use crate::raw_formulas;
+    use crate::utils::{NonNegativeFloat, TimeStamp};
+
+    #[test]
+    fn terminal_distribution_test(){
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.5), 0.08,
+            NonNegativeFloat::from(0.25), NonNegativeFloat::from(0.02));
+        let real_world = TerminalDistribution::new(&stock, Duration::from(2.0));
+        assert!((real_world.mean()-100.0*(2.0*(0.08-0.02_f64)).exp()).abs() < 1e-9);
+        assert!((real_world.variance()-real_world.moment(2.0)+real_world.mean().powi(2)).abs() < 1e-8);
+        let risk_neutral = TerminalDistribution::risk_neutral(&stock, Duration::from(2.0), 0.05);
+        // The probability of finishing above the strike and the discounted expected call payoff are the Black-Scholes ones.
+        let itm = 1.0-risk_neutral.cdf(110.0);
+        assert!((itm-raw_formulas::call_in_the_money_probability(100.0, 110.0, 0.05, 2.0, 0.25, 0.02)).abs() < 1e-12);
+        assert!((risk_neutral.expectation(&|s| s.ln(), 2)-risk_neutral.get_log_mean()).abs() < 1e-12);
+        assert!((risk_neutral.expectation(&|s| s*s, 10)-risk_neutral.moment(2.0)).abs() < 1e-8);
+        assert!((risk_neutral.quantile(0.5)-risk_neutral.median()).abs() < 1e-10);
+        // The density integrates to the cdf.
+        let integral = integration::adaptive_simpson(&|s| risk_neutral.density(s), 1e-9, 120.0, 1e-10);
+        assert!((integral-risk_neutral.cdf(120.0)).abs() < 1e-8);
+        let mut rng = RandomNumberGenerator::new(Some(7));
+        let samples = risk_neutral.samples(&mut rng, 100_000);
+        let sample_mean = samples.iter().sum::<f64>()/samples.len() as f64;
+        assert!((sample_mean-risk_neutral.mean()).abs() < 4.0*(risk_neutral.variance()/samples.len() as f64).sqrt());
+        let with_divident = stock.with_cash_dividents(&[(TimeStamp::from(1.0), 2.0)]);
+        assert_eq!(TerminalDistribution::try_new(&with_divident, Duration::from(1.0)), Err(PricingError::InvalidMarketData));
+        assert!(TerminalDistribution::try_new(&with_divident, Duration::from(0.25)).is_ok());
+    }
+
+    #[test]
+    fn degenerate_terminal_distribution_test(){
+        // At the current time the price is known, so the distribution is a point mass at the spot.
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 0.08,
+            NonNegativeFloat::from(0.25), NonNegativeFloat::from(0.0));
+        let now = TerminalDistribution::risk_neutral(&stock, Duration::from(0.0), 0.05);
+        assert_eq!(now.get_log_deviation(), 0.0);
+        assert!((now.median()-100.0).abs() < 1e-12);
+        assert_eq!((now.density(100.0), now.cdf(99.0), now.cdf(101.0)), (0.0, 0.0, 1.0));
+        assert_eq!(now.variance(), 0.0);
+        assert_eq!((now.quantile(0.0), now.quantile(1.0)), (0.0, f64::INFINITY));
+        assert_eq!((now.density(-1.0), now.cdf(0.0)), (0.0, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "The probability must be between 0 and 1.")]
+    fn quantile_out_of_range_test(){
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 0.08,
+            NonNegativeFloat::from(0.25), NonNegativeFloat::from(0.0));
+        TerminalDistribution::new(&stock, Duration::from(1.0)).quantile(1.5);
+    }
+}
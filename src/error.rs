@@ -0,0 +1,55 @@
+//! Provides the error type returned by the non-panicking (`try_`) variants of the functions in this library.
+
+/// The ways in which pricing can fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PricingError{
+    /// A parameter that must be non-negative was negative (or NaN).
+    NegativeInput,
+    /// The option expired before the current time of its underlying.
+    ExpiredOption,
+    /// Not enough random samples were provided to generate a path.
+    InsufficientSamples,
+    /// A vector of time stamps was empty, not increasing, or started before the current time of the underlying.
+    InvalidTimeGrid,
+    /// Market data, such as a strip of option prices, was incomplete or inconsistent.
+    InvalidMarketData,
+    /// A root finder was given an interval on whose end points the function has the same sign.
+    RootNotBracketed,
+    /// An iterative method did not converge within its maximal number of iterations.
+    NoConvergence,
+    /// A saved state, such as the state of a random number generator or a Monte Carlo configuration, could not be parsed or was invalid.
+    InvalidState,
+    /// A calendar date does not exist.
+    InvalidDate,
+    /// A parameter was outside of its domain, such as a confidence level outside of (0,1).
+    InvalidInput,
+}
+
+impl core::fmt::Display for PricingError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PricingError::NegativeInput => write!(f, "One of the parameters is negative"),
+            PricingError::ExpiredOption => write!(f, "The option expiered!"),
+            PricingError::InsufficientSamples => write!(f, "Not enough random samples."),
+            PricingError::InvalidTimeGrid => write!(f, "Invalid time_stamp vector."),
+            PricingError::InvalidMarketData => write!(f, "Invalid market data."),
+            PricingError::RootNotBracketed => write!(f, "The root is not bracketed."),
+            PricingError::NoConvergence => write!(f, "The iteration did not converge."),
+            PricingError::InvalidState => write!(f, "Invalid saved state."),
+            PricingError::InvalidDate => write!(f, "Invalid date."),
+            PricingError::InvalidInput => write!(f, "Invalid input."),
+        }
+    }
+}
+
+impl core::error::Error for PricingError { }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_test(){
+        assert_eq!(PricingError::NegativeInput.to_string(), "One of the parameters is negative");
+    }
+}
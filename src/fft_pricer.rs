@@ -0,0 +1,319 @@
+//! Provides the Carr-Madan pricer, which prices european options on a whole slice of strikes at once with a fast Fourier
+//! transform of the characteristic function of the log of the stock price at expiry.
+//!
+//! Any model implementing the `CharacteristicFunction` trait of the `models` module can be priced. The pricer also returns
+//! the greeks of the options: delta and gamma by differentiating the transform with respect to the spot, vega by bumping the
+//! volatility of models implementing `VolatilityBump`, and theta and rho by bumping the time to expiry and the rate.
+
+use crate::greeks::Greeks;
+use crate::models::{CharacteristicFunction, VolatilityBump};
+use crate::numerics::integration::truncated_simpson_weights;
+use num_complex::Complex64;
+use std::f64::consts::PI;
+
+/// The Carr-Madan pricer. The damped call price is transformed on a grid of `number_of_points` points spaced `grid_spacing`
+/// apart in the Fourier domain, which gives prices on a grid of log strikes centered at 0 with spacing
+/// `2*pi/(number_of_points*grid_spacing)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CarrMadanPricer{
+    /// The number of points of the transform. A power of 2.
+    number_of_points: usize,
+    /// The spacing of the grid in the Fourier domain.
+    grid_spacing: f64,
+    /// The damping factor `alpha` of the call price, which is multiplied by `exp(alpha*k)`.
+    damping: f64,
+}
+
+impl Default for CarrMadanPricer {
+    fn default() -> Self {
+        CarrMadanPricer::new(4096, 0.25, 1.5)
+    }
+}
+
+impl CarrMadanPricer {
+    /// Returns a new pricer.
+    /// # Parameters
+    /// - `number_of_points` - The number of points of the transform.
+    /// - `grid_spacing` - The spacing of the grid in the Fourier domain.
+    /// - `damping` - The damping factor of the call price, usually between 1 and 2.
+    /// # Panics
+    /// Panics if `number_of_points` is not a power of 2, or if `grid_spacing` or `damping` are not positive.
+    pub fn new(number_of_points: usize, grid_spacing: f64, damping: f64)->CarrMadanPricer{
+        if !number_of_points.is_power_of_two() {
+            panic!("The number of points must be a power of 2.");
+        }
+        if grid_spacing <= 0.0 || damping <= 0.0 {
+            panic!("The grid spacing and the damping must be positive.");
+        }
+        CarrMadanPricer{
+            number_of_points,
+            grid_spacing,
+            damping,
+        }
+    }
+
+    /// Returns the strikes and the prices of european call options on the whole slice of strikes of the transform.
+    /// # Parameters
+    /// - `model` - the model of the stock.
+    /// - `spot` - the current spot of the stock.
+    /// - `short_rate_of_interest` - the short rate of interest.
+    /// - `time_to_expiry` - the time to expiry of the options.
+    pub fn call_price_slice(&self, model: &impl CharacteristicFunction, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64)
+        ->(Vec<f64>, Vec<f64>){
+        self.transform_slice(model, spot, short_rate_of_interest, time_to_expiry, |_| Complex64::new(1.0, 0.0))
+    }
+
+    /// Returns the strikes and the deltas and gammas of european call options on the whole slice of strikes of the transform.
+    ///
+    /// The characteristic function of a model of the log return depends on the spot only through the factor `spot^(i*u)`, so
+    /// the derivatives of the prices with respect to the spot are transforms of the characteristic function multiplied by
+    /// `i*u/spot` and `i*u*(i*u-1)/spot^2`. This holds for all the models of the `models` module.
+    pub fn call_delta_gamma_slice(&self, model: &impl CharacteristicFunction, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64)
+        ->(Vec<f64>, Vec<f64>, Vec<f64>){
+        let (strikes, deltas) = self.transform_slice(model, spot, short_rate_of_interest, time_to_expiry, |iu| iu/spot);
+        let (_, gammas) = self.transform_slice(model, spot, short_rate_of_interest, time_to_expiry, |iu| iu*(iu-1.0)/(spot*spot));
+        (strikes, deltas, gammas)
+    }
+
+    /// Returns the prices and the greeks of european call options with the given strikes, interpolated linearly in log strike.
+    /// Delta and gamma are computed as in `call_delta_gamma_slice`, the vega by central differences of the prices with the
+    /// volatility of `model` bumped by 0.0001, and the theta and the rho by central differences with the time to expiry
+    /// and the short rate of interest bumped by 0.0001 (the time to expiry by at most half of it).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use derivative_pricer::fft_pricer::CarrMadanPricer;
+    /// use derivative_pricer::models::HestonModel;
+    /// let model = HestonModel::new(0.04, 1.5, 0.04, 0.3, -0.7, 0.0);
+    /// let greeks = CarrMadanPricer::default().call_greeks(&model, 100.0, 0.03, 1.0, &[90.0, 100.0, 110.0]);
+    /// assert!(greeks[0].delta > greeks[1].delta && greeks[1].delta > greeks[2].delta);
+    /// assert!(greeks.iter().all(|g| g.gamma > 0.0 && g.vega > 0.0 && g.theta < 0.0));
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if one of the strikes is outside the slice.
+    pub fn call_greeks(&self, model: &impl VolatilityBump, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64,
+        strikes: &[f64])->Vec<Greeks>{
+        self.greeks(model, spot, short_rate_of_interest, time_to_expiry, strikes, false)
+    }
+
+    /// Same as `call_greeks`, but for european put options, whose greeks follow from put-call parity.
+    /// # Panics
+    /// Panics if one of the strikes is outside the slice.
+    pub fn put_greeks(&self, model: &impl VolatilityBump, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64,
+        strikes: &[f64])->Vec<Greeks>{
+        self.greeks(model, spot, short_rate_of_interest, time_to_expiry, strikes, true)
+    }
+
+    /// Computes the greeks of `call_greeks` and `put_greeks`.
+    fn greeks(&self, model: &impl VolatilityBump, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64, strikes: &[f64],
+        put: bool)->Vec<Greeks>{
+        const BUMP: f64 = 1e-4;
+        let prices = |model: &_, r, t| if put {
+            self.put_prices(model, spot, r, t, strikes)
+        } else {
+            self.call_prices(model, spot, r, t, strikes)
+        };
+        let (slice_strikes, deltas, gammas) = self.call_delta_gamma_slice(model, spot, short_rate_of_interest, time_to_expiry);
+        let deltas = interpolate_slice(&slice_strikes, &deltas, strikes);
+        let gammas = interpolate_slice(&slice_strikes, &gammas, strikes);
+        // By put-call parity the put delta is lower by the derivative of the discounted forward, which is linear in the spot.
+        let forward_delta = if put {
+            (-short_rate_of_interest*time_to_expiry).exp()
+                *model.characteristic_function(-Complex64::i(), spot, short_rate_of_interest, time_to_expiry).re/spot
+        } else {
+            0.0
+        };
+        let time_bump = BUMP.min(0.5*time_to_expiry);
+        let price = prices(model, short_rate_of_interest, time_to_expiry);
+        let vega = central_differences(&prices(&model.with_volatility_bump(BUMP), short_rate_of_interest, time_to_expiry),
+            &prices(&model.with_volatility_bump(-BUMP), short_rate_of_interest, time_to_expiry), BUMP);
+        let theta = central_differences(&prices(model, short_rate_of_interest, time_to_expiry-time_bump),
+            &prices(model, short_rate_of_interest, time_to_expiry+time_bump), time_bump);
+        let rho = central_differences(&prices(model, short_rate_of_interest+BUMP, time_to_expiry),
+            &prices(model, short_rate_of_interest-BUMP, time_to_expiry), BUMP);
+        (0..strikes.len()).map(|j| Greeks{
+            price: price[j],
+            delta: deltas[j]-forward_delta,
+            gamma: gammas[j],
+            vega: vega[j],
+            theta: theta[j],
+            rho: rho[j],
+        }).collect()
+    }
+
+    /// Returns the strikes and the values on the slice of the transform of the damped call price, with the characteristic
+    /// function multiplied by `weight(i*u)`.
+    fn transform_slice(&self, model: &impl CharacteristicFunction, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64,
+        weight: impl Fn(Complex64)->Complex64)->(Vec<f64>, Vec<f64>){
+        let n = self.number_of_points;
+        let eta = self.grid_spacing;
+        let alpha = self.damping;
+        let log_strike_spacing = 2.0*PI/(n as f64*eta);
+        let lowest_log_strike = -0.5*n as f64*log_strike_spacing;
+        let discount = (-short_rate_of_interest*time_to_expiry).exp();
+        let i = Complex64::i();
+        let simpson = truncated_simpson_weights(n);
+        let mut transformed: Vec<Complex64> = (0..n).map(|j| {
+            let v = eta*j as f64;
+            let u = Complex64::new(v, -(alpha+1.0));
+            let phi = model.characteristic_function(u, spot, short_rate_of_interest, time_to_expiry)*weight(i*u);
+            let psi = discount*phi/Complex64::new(alpha*alpha+alpha-v*v, (2.0*alpha+1.0)*v);
+            (-i*v*lowest_log_strike).exp()*psi*eta*simpson[j]
+        }).collect();
+        fast_fourier_transform(&mut transformed);
+        let strikes = (0..n).map(|u| (lowest_log_strike+log_strike_spacing*u as f64).exp()).collect();
+        let prices = transformed.iter().enumerate()
+            .map(|(u, x)| (-alpha*(lowest_log_strike+log_strike_spacing*u as f64)).exp()/PI*x.re).collect();
+        (strikes, prices)
+    }
+
+    /// Returns the prices of european call options with the given strikes, interpolated linearly in log strike from the slice
+    /// returned by `call_price_slice`.
+    /// # Panics
+    /// Panics if one of the strikes is outside the slice.
+    pub fn call_prices(&self, model: &impl CharacteristicFunction, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64,
+        strikes: &[f64])->Vec<f64>{
+        let (slice_strikes, prices) = self.call_price_slice(model, spot, short_rate_of_interest, time_to_expiry);
+        interpolate_slice(&slice_strikes, &prices, strikes)
+    }
+
+    /// Returns the prices of european put options with the given strikes, by put-call parity with `call_prices`.
+    /// The forward is taken from the characteristic function, so no divident rate is needed.
+    /// # Panics
+    /// Panics if one of the strikes is outside the slice.
+    pub fn put_prices(&self, model: &impl CharacteristicFunction, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64,
+        strikes: &[f64])->Vec<f64>{
+        let forward = model.characteristic_function(-Complex64::i(), spot, short_rate_of_interest, time_to_expiry).re;
+        let discount = (-short_rate_of_interest*time_to_expiry).exp();
+        self.call_prices(model, spot, short_rate_of_interest, time_to_expiry, strikes).iter().zip(strikes)
+            .map(|(call, k)| call-discount*(forward-k)).collect()
+    }
+}
+
+/// Interpolates `values` on the slice of strikes `slice_strikes` linearly in log strike at `strikes`.
+/// # Panics
+/// Panics if one of the strikes is outside the slice.
+fn interpolate_slice(slice_strikes: &[f64], values: &[f64], strikes: &[f64])->Vec<f64>{
+    let log_strikes: Vec<f64> = slice_strikes.iter().map(|k| k.ln()).collect();
+    strikes.iter().map(|k| {
+        let log_strike = k.ln();
+        let index = log_strikes.partition_point(|x| *x <= log_strike);
+        if index == 0 || index == log_strikes.len() {
+            panic!("The strike is outside the slice of the transform.");
+        }
+        let weight = (log_strike-log_strikes[index-1])/(log_strikes[index]-log_strikes[index-1]);
+        (1.0-weight)*values[index-1]+weight*values[index]
+    }).collect()
+}
+
+/// Returns the central differences `(up-down)/(2*bump)`.
+fn central_differences(up: &[f64], down: &[f64], bump: f64)->Vec<f64>{
+    up.iter().zip(down).map(|(u, d)| (u-d)/(2.0*bump)).collect()
+}
+
+/// Computes `x_u = sum_j exp(-2*pi*i*j*u/n) x_j` in place, with the iterative radix 2 Cooley-Tukey algorithm.
+/// The length of `values` must be a power of 2. Transforms of length 0 and 1 leave `values` unchanged.
+pub(crate) fn fast_fourier_transform(values: &mut [Complex64]){
+    let n = values.len();
+    if n <= 1 {
+        return;
+    }
+    let bits = n.trailing_zeros();
+    for j in 0..n{
+        let reversed = j.reverse_bits() >> (usize::BITS-bits) as usize;
+        if j < reversed {
+            values.swap(j, reversed);
+        }
+    }
+    let mut length = 2;
+    while length <= n{
+        let root = Complex64::from_polar(1.0, -2.0*PI/length as f64);
+        for start in (0..n).step_by(length){
+            let mut twiddle = Complex64::new(1.0, 0.0);
+            for k in 0..length/2{
+                let even = values[start+k];
+                let odd = values[start+k+length/2]*twiddle;
+                values[start+k] = even+odd;
+                values[start+k+length/2] = even-odd;
+                twiddle *= root;
+            }
+        }
+        length *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BlackScholesModel, HestonModel};
+    use crate::raw_formulas;
+
+    #[test]
+    fn fast_fourier_transform_test(){
+        let mut values: Vec<Complex64> = (0..8).map(|j| Complex64::new(j as f64, 1.0-j as f64)).collect();
+        let expected: Vec<Complex64> = (0..8).map(|u| (0..8).map(|j| values[j]*Complex64::from_polar(1.0, -2.0*PI*(j*u) as f64/8.0)).sum())
+            .collect();
+        fast_fourier_transform(&mut values);
+        for (x, y) in values.iter().zip(expected){
+            assert!((x-y).norm()<1e-12);
+        }
+        let mut single = [Complex64::new(2.0, -1.0)];
+        fast_fourier_transform(&mut single);
+        assert_eq!(single, [Complex64::new(2.0, -1.0)]);
+        fast_fourier_transform(&mut []);
+    }
+
+    #[test]
+    fn black_scholes_test(){
+        let model = BlackScholesModel::new(0.25, 0.02);
+        let pricer = CarrMadanPricer::default();
+        let strikes = [80.0, 100.0, 125.0];
+        let calls = pricer.call_prices(&model, 100.0, 0.05, 0.75, &strikes);
+        let puts = pricer.put_prices(&model, 100.0, 0.05, 0.75, &strikes);
+        for (i, k) in strikes.iter().enumerate(){
+            assert!((calls[i]-raw_formulas::european_call_option_price(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<0.01);
+            assert!((puts[i]-raw_formulas::european_put_option_price(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<0.01);
+        }
+    }
+
+    #[test]
+    fn black_scholes_greeks_test(){
+        let model = BlackScholesModel::new(0.25, 0.02);
+        let pricer = CarrMadanPricer::default();
+        let strikes = [80.0, 100.0, 125.0];
+        let calls = pricer.call_greeks(&model, 100.0, 0.05, 0.75, &strikes);
+        let puts = pricer.put_greeks(&model, 100.0, 0.05, 0.75, &strikes);
+        for (i, k) in strikes.iter().enumerate(){
+            let (call, put) = (calls[i], puts[i]);
+            assert!((call.delta-raw_formulas::call_delta(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<1e-3);
+            assert!((put.delta-raw_formulas::put_delta(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<1e-3);
+            assert!((call.gamma-raw_formulas::call_gamma(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<1e-4);
+            assert!((put.gamma-call.gamma).abs()<1e-12);
+            assert!((call.vega-raw_formulas::call_vega(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<0.01);
+            assert!((put.vega-raw_formulas::put_vega(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<0.01);
+            assert!((call.theta-raw_formulas::call_theta(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<0.01);
+            assert!((put.theta-raw_formulas::put_theta(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<0.01);
+            assert!((call.rho-raw_formulas::call_rho(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<0.01);
+            assert!((put.rho-raw_formulas::put_rho(100.0, *k, 0.05, 0.75, 0.25, 0.02)).abs()<0.01);
+        }
+    }
+
+    #[test]
+    fn heston_greeks_test(){
+        let model = HestonModel::new(0.05, 2.0, 0.04, 0.4, -0.6, 0.01);
+        let pricer = CarrMadanPricer::default();
+        let strikes = [90.0, 110.0];
+        let greeks = pricer.call_greeks(&model, 100.0, 0.03, 0.5, &strikes);
+        let price = |spot| pricer.call_prices(&model, spot, 0.03, 0.5, &strikes);
+        let (up, mid, down) = (price(100.5), price(100.0), price(99.5));
+        for j in 0..2 {
+            assert_eq!(greeks[j].price, mid[j]);
+            assert!((greeks[j].delta-(up[j]-down[j])/1.0).abs()<1e-3);
+            assert!((greeks[j].gamma-(up[j]-2.0*mid[j]+down[j])/0.25).abs()<1e-3);
+        }
+        let bumped = pricer.call_prices(&model.with_volatility_bump(0.01), 100.0, 0.03, 0.5, &strikes);
+        assert!((greeks[1].vega-(bumped[1]-mid[1])/0.01).abs()<0.5);
+    }
+}
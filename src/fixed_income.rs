@@ -0,0 +1,578 @@
+//! Provides fixed rate coupon bonds: prices from a discount curve or from a yield, yields to maturity, durations and convexity,
+//! fixed for floating interest rate swaps, and european swaptions.
+//!
+//! Times are measured in years from the valuation time, which is time 0. Yields are compounded at the coupon frequency, as is
+//! the market convention, while discount and forward curves are `RateCurve`s, i.e. continuously compounded short rates.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::fixed_income::CouponBond;
+//! let bond = CouponBond::new(100.0, 0.05, 2, 4.75);
+//! let price = bond.dirty_price(0.04);
+//! let bond_yield = bond.yield_to_maturity(price).unwrap();
+//! assert!((bond.dirty_price_from_yield(bond_yield)-price).abs() < 1e-9);
+//! assert!(bond.modified_duration(bond_yield) < bond.macaulay_duration(bond_yield));
+//! ```
+
+use crate::error::PricingError;
+use crate::numerics::roots::brent;
+use crate::term_structure::RateCurve;
+use crate::utils::{cumulative_normal_function, normal_probability_density_function};
+
+/// The tolerance to which yields to maturity are found.
+const YIELD_TOLERANCE: f64 = 1e-13;
+
+/// One basis point.
+const BASIS_POINT: f64 = 1e-4;
+
+/// A bond paying a fixed coupon at a regular frequency, and its face value at maturity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CouponBond{
+    face_value: f64,
+    /// The yearly coupon rate, paid in `frequency` equal parts.
+    coupon_rate: f64,
+    /// The number of coupons paid per year.
+    frequency: u32,
+    /// The time to maturity, in years.
+    maturity: f64,
+}
+
+impl CouponBond {
+    /// Returns a new coupon bond, whose coupons are paid every `1/frequency` years counting back from `maturity`.
+    /// # Parameters
+    /// - `face_value` - The principal, repaid at maturity.
+    /// - `coupon_rate` - The yearly coupon rate, e.g. 0.05 for coupons of 2.5% of the face value twice a year.
+    /// - `frequency` - The number of coupons per year.
+    /// - `maturity` - The time to maturity.
+    /// # Panics
+    /// Panics if one of the parameters is negative or if `frequency` is zero.
+    pub fn new(face_value: f64, coupon_rate: f64, frequency: u32, maturity: f64)->CouponBond{
+        match CouponBond::try_new(face_value, coupon_rate, frequency, maturity) {
+            Ok(bond) => bond,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `new`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_new(face_value: f64, coupon_rate: f64, frequency: u32, maturity: f64)->Result<CouponBond, PricingError>{
+        if [face_value, coupon_rate, maturity].iter().any(|x| *x < 0.0 || x.is_nan()) || frequency == 0 {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(CouponBond{face_value, coupon_rate, frequency, maturity})
+    }
+
+    /// Returns the face value.
+    pub fn get_face_value(&self)->f64{
+        self.face_value
+    }
+
+    /// Returns the yearly coupon rate.
+    pub fn get_coupon_rate(&self)->f64{
+        self.coupon_rate
+    }
+
+    /// Returns the number of coupons per year.
+    pub fn get_frequency(&self)->u32{
+        self.frequency
+    }
+
+    /// Returns the time to maturity.
+    pub fn get_maturity(&self)->f64{
+        self.maturity
+    }
+
+    /// Returns the remaining cash flows as pairs of payment time and amount, in the order of payment. The cash flow at
+    /// maturity includes the face value.
+    pub fn cash_flows(&self)->Vec<(f64, f64)>{
+        let period = 1.0/self.frequency as f64;
+        let coupon = self.face_value*self.coupon_rate*period;
+        // The number of remaining coupons, ignoring a coupon falling within rounding of time 0.
+        let count = ((self.maturity/period)-1e-9).ceil().max(1.0) as usize;
+        let mut flows: Vec<(f64, f64)> = (0..count).rev().map(|k| (self.maturity-k as f64*period, coupon)).collect();
+        if let Some(last) = flows.last_mut() {
+            last.1 += self.face_value;
+        }
+        flows
+    }
+
+    /// Returns the accrued interest, i.e. the part of the next coupon earned since the previous coupon date.
+    pub fn accrued_interest(&self)->f64{
+        let period = 1.0/self.frequency as f64;
+        let time_to_next_coupon = self.cash_flows()[0].0;
+        self.face_value*self.coupon_rate*(period-time_to_next_coupon).max(0.0)
+    }
+
+    /// Returns the dirty price, i.e. the value of the remaining cash flows discounted with `curve`.
+    pub fn dirty_price(&self, curve: impl RateCurve)->f64{
+        self.cash_flows().iter().map(|(t, c)| c*curve.discount_factor(0.0, *t)).sum()
+    }
+
+    /// Returns the clean price, i.e. the dirty price minus the accrued interest, discounting with `curve`.
+    pub fn clean_price(&self, curve: impl RateCurve)->f64{
+        self.dirty_price(curve)-self.accrued_interest()
+    }
+
+    /// Returns the dirty price of the bond if its yield is `bond_yield`, compounded at the coupon frequency.
+    pub fn dirty_price_from_yield(&self, bond_yield: f64)->f64{
+        let (growth, frequency) = (1.0+bond_yield/self.frequency as f64, self.frequency as f64);
+        self.cash_flows().iter().map(|(t, c)| c*growth.powf(-frequency*t)).sum()
+    }
+
+    /// Returns the clean price of the bond if its yield is `bond_yield`.
+    pub fn clean_price_from_yield(&self, bond_yield: f64)->f64{
+        self.dirty_price_from_yield(bond_yield)-self.accrued_interest()
+    }
+
+    /// Returns the yield, compounded at the coupon frequency, at which the bond has the dirty price `dirty_price`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PricingError::InvalidMarketData` if `dirty_price` is not positive, and the errors of `numerics::roots::brent` if
+    /// no yield is found.
+    pub fn yield_to_maturity(&self, dirty_price: f64)->Result<f64, PricingError>{
+        if dirty_price <= 0.0 || dirty_price.is_nan() {
+            return Err(PricingError::InvalidMarketData);
+        }
+        // The price decreases in the yield, which is above -frequency.
+        let frequency = self.frequency as f64;
+        let excess = |y: f64| self.dirty_price_from_yield(y)-dirty_price;
+        let mut low = -0.5*frequency;
+        while excess(low) < 0.0 && low > -frequency*(1.0-1e-12) {
+            low = 0.5*(low-frequency);
+        }
+        let mut high = 1.0;
+        while excess(high) > 0.0 && high < 1e6 {
+            high *= 2.0;
+        }
+        brent(&excess, low, high, YIELD_TOLERANCE)
+    }
+
+    /// Returns the Macaulay duration, i.e. the average time of the cash flows weighted by their present values at the yield
+    /// `bond_yield`.
+    pub fn macaulay_duration(&self, bond_yield: f64)->f64{
+        let (growth, frequency) = (1.0+bond_yield/self.frequency as f64, self.frequency as f64);
+        let flows = self.cash_flows();
+        let weighted: f64 = flows.iter().map(|(t, c)| t*c*growth.powf(-frequency*t)).sum();
+        weighted/self.dirty_price_from_yield(bond_yield)
+    }
+
+    /// Returns the modified duration, i.e. minus the relative sensitivity of the dirty price to the yield.
+    pub fn modified_duration(&self, bond_yield: f64)->f64{
+        self.macaulay_duration(bond_yield)/(1.0+bond_yield/self.frequency as f64)
+    }
+
+    /// Returns the convexity, i.e. the second derivative of the dirty price with respect to the yield over the price.
+    pub fn convexity(&self, bond_yield: f64)->f64{
+        let (growth, frequency) = (1.0+bond_yield/self.frequency as f64, self.frequency as f64);
+        let second_derivative: f64 = self.cash_flows().iter()
+            .map(|(t, c)| c*t*(t+1.0/frequency)*growth.powf(-frequency*t-2.0)).sum();
+        second_derivative/self.dirty_price_from_yield(bond_yield)
+    }
+}
+
+/// The side of an interest rate swap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapType{
+    /// Pays the fixed rate and receives the floating rate.
+    Payer,
+    /// Receives the fixed rate and pays the floating rate.
+    Receiver,
+}
+
+/// A plain vanilla interest rate swap, exchanging a fixed rate for the floating rate set at the start of each floating period.
+///
+/// The floating rates are the simple forward rates of a forward curve, and the cash flows are discounted with a discount curve,
+/// which may be the same curve.
+///
+/// # Examples
+///
+/// ```
+/// use derivative_pricer::fixed_income::{InterestRateSwap, SwapType};
+/// let swap = InterestRateSwap::new(SwapType::Payer, 1e6, 0.03, 0.0, 5.0, 1, 4);
+/// let par_rate = swap.par_rate(0.035, 0.035);
+/// assert!(par_rate > 0.03 && swap.value(0.035, 0.035) > 0.0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InterestRateSwap{
+    swap_type: SwapType,
+    notional: f64,
+    fixed_rate: f64,
+    /// The time at which the first periods start.
+    start: f64,
+    maturity: f64,
+    /// The number of fixed payments per year.
+    fixed_frequency: u32,
+    /// The number of floating payments per year.
+    floating_frequency: u32,
+}
+
+impl InterestRateSwap {
+    /// Returns a new swap.
+    /// # Parameters
+    /// - `swap_type` - Payer or receiver of the fixed rate.
+    /// - `notional` - The notional on which the rates are paid.
+    /// - `fixed_rate` - The yearly fixed rate.
+    /// - `start` - The start of the swap, 0 for a spot starting swap.
+    /// - `maturity` - The end of the swap.
+    /// - `fixed_frequency` - The number of fixed payments per year.
+    /// - `floating_frequency` - The number of floating payments per year.
+    ///
+    /// The payment dates are counted back from `maturity`, so that a first period may be shorter.
+    /// # Panics
+    /// Panics if `notional` or `start` is negative, if `maturity` is before `start`, or if a frequency is zero.
+    pub fn new(swap_type: SwapType, notional: f64, fixed_rate: f64, start: f64, maturity: f64, fixed_frequency: u32, floating_frequency: u32)
+        ->InterestRateSwap{
+        match InterestRateSwap::try_new(swap_type, notional, fixed_rate, start, maturity, fixed_frequency, floating_frequency) {
+            Ok(swap) => swap,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `new`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_new(swap_type: SwapType, notional: f64, fixed_rate: f64, start: f64, maturity: f64, fixed_frequency: u32,
+        floating_frequency: u32)->Result<InterestRateSwap, PricingError>{
+        if notional < 0.0 || start < 0.0 || notional.is_nan() || start.is_nan() || fixed_rate.is_nan() || maturity <= start || maturity.is_nan()
+            || fixed_frequency == 0 || floating_frequency == 0 {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(InterestRateSwap{swap_type, notional, fixed_rate, start, maturity, fixed_frequency, floating_frequency})
+    }
+
+    /// Returns the side of the swap.
+    pub fn get_swap_type(&self)->SwapType{
+        self.swap_type
+    }
+
+    /// Returns the notional.
+    pub fn get_notional(&self)->f64{
+        self.notional
+    }
+
+    /// Returns the fixed rate.
+    pub fn get_fixed_rate(&self)->f64{
+        self.fixed_rate
+    }
+
+    /// Returns the start of the swap.
+    pub fn get_start(&self)->f64{
+        self.start
+    }
+
+    /// Returns the maturity of the swap.
+    pub fn get_maturity(&self)->f64{
+        self.maturity
+    }
+
+    /// Returns the payment times of the fixed leg.
+    pub fn fixed_payment_times(&self)->Vec<f64>{
+        schedule(self.start, self.maturity, self.fixed_frequency)[1..].to_vec()
+    }
+
+    /// Returns the annuity of the fixed leg, i.e. the value of receiving 1 per year on the fixed schedule.
+    pub fn annuity(&self, discount_curve: impl RateCurve)->f64{
+        schedule(self.start, self.maturity, self.fixed_frequency).windows(2)
+            .map(|w| (w[1]-w[0])*discount_curve.discount_factor(0.0, w[1])).sum()
+    }
+
+    /// Returns the value of the fixed leg.
+    pub fn fixed_leg_value(&self, discount_curve: impl RateCurve)->f64{
+        self.notional*self.fixed_rate*self.annuity(discount_curve)
+    }
+
+    /// Returns the value of the floating leg, paying at the end of each floating period the forward rate of `forward_curve` for it.
+    pub fn floating_leg_value(&self, discount_curve: impl RateCurve, forward_curve: impl RateCurve)->f64{
+        self.notional*schedule(self.start, self.maturity, self.floating_frequency).windows(2).map(|w| {
+            // The simple forward rate times the accrual period.
+            let accrued_rate = forward_curve.integral(w[0], w[1]).exp_m1();
+            accrued_rate*discount_curve.discount_factor(0.0, w[1])
+        }).sum::<f64>()
+    }
+
+    /// Returns the value of the swap for its holder.
+    pub fn value(&self, discount_curve: impl RateCurve, forward_curve: impl RateCurve)->f64{
+        let payer_value = self.floating_leg_value(&discount_curve, forward_curve)-self.fixed_leg_value(&discount_curve);
+        match self.swap_type {
+            SwapType::Payer => payer_value,
+            SwapType::Receiver => -payer_value,
+        }
+    }
+
+    /// Returns the par rate, i.e. the fixed rate for which the swap has zero value.
+    pub fn par_rate(&self, discount_curve: impl RateCurve, forward_curve: impl RateCurve)->f64{
+        self.floating_leg_value(&discount_curve, forward_curve)/(self.notional*self.annuity(&discount_curve))
+    }
+
+    /// Returns the DV01, i.e. the change in value of the swap if both curves are shifted up in parallel by one basis point,
+    /// computed with a central difference.
+    pub fn dv01(&self, discount_curve: impl RateCurve, forward_curve: impl RateCurve)->f64{
+        let shifted = |shift: f64| self.value(ShiftedCurve(&discount_curve, shift), ShiftedCurve(&forward_curve, shift));
+        0.5*(shifted(BASIS_POINT)-shifted(-BASIS_POINT))
+    }
+}
+
+/// A physically settled european swaption, i.e. the right to enter an interest rate swap at expiry. The swaption is a payer
+/// (receiver) swaption if the swap is a payer (receiver) swap.
+///
+/// Swaptions are quoted with the volatility of the forward swap rate, either lognormal (Black's model) or normal (Bachelier's
+/// model), and their prices are the annuity of the swap times the price of an option on the forward swap rate.
+///
+/// # Examples
+///
+/// ```
+/// use derivative_pricer::fixed_income::{InterestRateSwap, Swaption, SwapType};
+/// let swap = InterestRateSwap::new(SwapType::Payer, 1e6, 0.03, 1.0, 6.0, 1, 4);
+/// let swaption = Swaption::new(swap, 1.0);
+/// let black = swaption.black_price(0.03, 0.03, 0.2);
+/// let bachelier = swaption.bachelier_price(0.03, 0.03, 0.006);
+/// assert!((black-bachelier).abs() < 0.01*black);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Swaption{
+    /// The swap entered at expiry.
+    swap: InterestRateSwap,
+    expiry: f64,
+}
+
+impl Swaption {
+    /// Returns the swaption expiring at `expiry` on `swap`.
+    /// # Panics
+    /// Panics if `expiry` is negative or after the start of the swap.
+    pub fn new(swap: InterestRateSwap, expiry: f64)->Swaption{
+        match Swaption::try_new(swap, expiry) {
+            Ok(swaption) => swaption,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `new`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_new(swap: InterestRateSwap, expiry: f64)->Result<Swaption, PricingError>{
+        if expiry < 0.0 || expiry > swap.get_start() || expiry.is_nan() {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(Swaption{swap, expiry})
+    }
+
+    /// Returns the underlying swap.
+    pub fn get_swap(&self)->InterestRateSwap{
+        self.swap
+    }
+
+    /// Returns the expiry.
+    pub fn get_expiry(&self)->f64{
+        self.expiry
+    }
+
+    /// Returns the value of the annuity of the swap times its notional, by which a swaption price is a price on the swap rate.
+    pub fn annuity(&self, discount_curve: impl RateCurve)->f64{
+        self.swap.get_notional()*self.swap.annuity(discount_curve)
+    }
+
+    /// Returns the forward swap rate, i.e. the par rate of the swap.
+    pub fn forward_swap_rate(&self, discount_curve: impl RateCurve, forward_curve: impl RateCurve)->f64{
+        self.swap.par_rate(discount_curve, forward_curve)
+    }
+
+    /// Returns the price of the swaption in Black's model, in which the forward swap rate is lognormal with volatility
+    /// `volatility`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `volatility` is negative, or if the forward swap rate or the fixed rate is not positive.
+    pub fn black_price(&self, discount_curve: impl RateCurve, forward_curve: impl RateCurve, volatility: f64)->f64{
+        self.try_black_price(discount_curve, forward_curve, volatility).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `black_price`, but returns `PricingError::NegativeInput` if `volatility` is negative and
+    /// `PricingError::InvalidMarketData` if the forward swap rate or the fixed rate is not positive, instead of panicking.
+    pub fn try_black_price(&self, discount_curve: impl RateCurve, forward_curve: impl RateCurve, volatility: f64)->Result<f64, PricingError>{
+        if volatility < 0.0 || volatility.is_nan() {
+            return Err(PricingError::NegativeInput);
+        }
+        let (forward, strike) = (self.forward_swap_rate(&discount_curve, forward_curve), self.swap.get_fixed_rate());
+        if !(forward > 0.0 && strike > 0.0) {
+            return Err(PricingError::InvalidMarketData);
+        }
+        let deviation = volatility*self.expiry.sqrt();
+        let (call, put) = if deviation > 0.0 {
+            let d1 = ((forward/strike).ln()+0.5*deviation*deviation)/deviation;
+            let d2 = d1-deviation;
+            (forward*cumulative_normal_function(d1)-strike*cumulative_normal_function(d2),
+                strike*cumulative_normal_function(-d2)-forward*cumulative_normal_function(-d1))
+        }
+        else {
+            (f64::max(forward-strike, 0.0), f64::max(strike-forward, 0.0))
+        };
+        Ok(self.annuity(discount_curve)*self.select(call, put))
+    }
+
+    /// Returns the price of the swaption in Bachelier's model, in which the forward swap rate is normal with volatility
+    /// `volatility` (an absolute volatility, e.g. 0.01 for 100 basis points a year).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `volatility` is negative.
+    pub fn bachelier_price(&self, discount_curve: impl RateCurve, forward_curve: impl RateCurve, volatility: f64)->f64{
+        self.try_bachelier_price(discount_curve, forward_curve, volatility).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `bachelier_price`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_bachelier_price(&self, discount_curve: impl RateCurve, forward_curve: impl RateCurve, volatility: f64)
+        ->Result<f64, PricingError>{
+        if volatility < 0.0 || volatility.is_nan() {
+            return Err(PricingError::NegativeInput);
+        }
+        let (forward, strike) = (self.forward_swap_rate(&discount_curve, forward_curve), self.swap.get_fixed_rate());
+        let deviation = volatility*self.expiry.sqrt();
+        let (call, put) = if deviation > 0.0 {
+            let d = (forward-strike)/deviation;
+            let time_value = deviation*normal_probability_density_function(d);
+            ((forward-strike)*cumulative_normal_function(d)+time_value, (strike-forward)*cumulative_normal_function(-d)+time_value)
+        }
+        else {
+            (f64::max(forward-strike, 0.0), f64::max(strike-forward, 0.0))
+        };
+        Ok(self.annuity(discount_curve)*self.select(call, put))
+    }
+
+    /// Returns `call` for a payer swaption and `put` for a receiver swaption.
+    fn select(&self, call: f64, put: f64)->f64{
+        match self.swap.get_swap_type() {
+            SwapType::Payer => call,
+            SwapType::Receiver => put,
+        }
+    }
+}
+
+/// A rate curve shifted in parallel.
+struct ShiftedCurve<C>(C, f64);
+
+impl<C: RateCurve> RateCurve for ShiftedCurve<C> {
+    fn integral(&self, from: f64, to: f64)->f64{
+        self.0.integral(from, to)+self.1*(to-from)
+    }
+}
+
+/// Returns the boundaries of the periods of length `1/frequency` from `start` to `end`, counted back from `end`.
+fn schedule(start: f64, end: f64, frequency: u32)->Vec<f64>{
+    let period = 1.0/frequency as f64;
+    // The number of periods, ignoring a first period within rounding of zero length.
+    let count = (((end-start)/period)-1e-9).ceil().max(1.0) as usize;
+    let mut times: Vec<f64> = (0..count).rev().map(|k| end-k as f64*period).collect();
+    times.insert(0, start);
+    times
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::term_structure::PiecewiseConstantCurve;
+
+    #[test]
+    fn coupon_bond_test(){
+        let bond = CouponBond::new(100.0, 0.06, 2, 2.25);
+        let flows = bond.cash_flows();
+        assert_eq!(flows.len(), 5);
+        assert!((flows[0].0-0.25).abs() < 1e-12 && flows[0].1 == 3.0 && flows[4].1 == 103.0);
+        assert!((bond.accrued_interest()-1.5).abs() < 1e-12);
+        let curve = PiecewiseConstantCurve::new(vec![1.0], vec![0.03, 0.05]);
+        let expected: f64 = flows.iter().map(|(t, c)| c*(-curve.integral(0.0, *t)).exp()).sum();
+        assert!((bond.dirty_price(&curve)-expected).abs() < 1e-12);
+        assert!((bond.clean_price(&curve)-expected+1.5).abs() < 1e-12);
+        // A flat continuous rate is the yield compounded at the coupon frequency of the equivalent growth.
+        let bond_yield = bond.yield_to_maturity(bond.dirty_price(0.04)).unwrap();
+        assert!((bond_yield-2.0*((0.02_f64).exp()-1.0)).abs() < 1e-12);
+        // A bond on a coupon date prices at par when its yield is its coupon rate.
+        let par = CouponBond::new(100.0, 0.05, 4, 3.0);
+        assert!((par.dirty_price_from_yield(0.05)-100.0).abs() < 1e-10);
+        assert!(par.yield_to_maturity(-1.0).is_err());
+        assert!(CouponBond::try_new(100.0, 0.05, 0, 3.0).is_err());
+    }
+
+    #[test]
+    fn duration_and_convexity_test(){
+        let bond = CouponBond::new(1000.0, 0.045, 2, 7.4);
+        let (bond_yield, h) = (0.052, 1e-5);
+        let price = bond.dirty_price_from_yield(bond_yield);
+        let (up, down) = (bond.dirty_price_from_yield(bond_yield+h), bond.dirty_price_from_yield(bond_yield-h));
+        assert!((bond.modified_duration(bond_yield)+(up-down)/(2.0*h*price)).abs() < 1e-6);
+        assert!((bond.convexity(bond_yield)-(up-2.0*price+down)/(h*h*price)).abs() < 1e-3);
+        let zero = CouponBond::new(100.0, 0.0, 1, 3.5);
+        assert!((zero.macaulay_duration(0.03)-3.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn swap_test(){
+        let curve = PiecewiseConstantCurve::new(vec![2.0], vec![0.02, 0.035]);
+        let swap = InterestRateSwap::new(SwapType::Payer, 100.0, 0.03, 0.5, 5.0, 1, 2);
+        assert_eq!(swap.fixed_payment_times(), vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        // With a single curve the floating leg is worth the notional at the start minus the notional at maturity.
+        let floating = 100.0*(curve.discount_factor(0.0, 0.5)-curve.discount_factor(0.0, 5.0));
+        assert!((swap.floating_leg_value(&curve, &curve)-floating).abs() < 1e-12);
+        let annuity = 0.5*curve.discount_factor(0.0, 1.0)+(2..=5).map(|t| curve.discount_factor(0.0, t as f64)).sum::<f64>();
+        assert!((swap.annuity(&curve)-annuity).abs() < 1e-12);
+        let par_rate = swap.par_rate(&curve, &curve);
+        let par_swap = InterestRateSwap::new(SwapType::Receiver, 100.0, par_rate, 0.5, 5.0, 1, 2);
+        assert!(par_swap.value(&curve, &curve).abs() < 1e-12);
+        assert!((swap.value(&curve, &curve)+InterestRateSwap::new(SwapType::Receiver, 100.0, 0.03, 0.5, 5.0, 1, 2).value(&curve, &curve)).abs() < 1e-12);
+        // A payer swap gains when rates rise, by about the annuity of the remaining floating leg per basis point.
+        let dv01 = swap.dv01(&curve, 0.04);
+        assert!(dv01 > 0.0 && (dv01-100.0*1e-4*annuity).abs() < 0.1*dv01);
+        assert!(InterestRateSwap::try_new(SwapType::Payer, 100.0, 0.03, 2.0, 1.0, 1, 2).is_err());
+    }
+
+    #[test]
+    fn degenerate_swap_test(){
+        assert_eq!(InterestRateSwap::try_new(SwapType::Payer, 100.0, 0.03, 1.0, 1.0, 1, 2), Err(PricingError::NegativeInput));
+        assert_eq!(InterestRateSwap::try_new(SwapType::Payer, 100.0, 0.03, 0.0, 1.0, 0, 2), Err(PricingError::NegativeInput));
+        assert_eq!(InterestRateSwap::try_new(SwapType::Payer, -100.0, 0.03, 0.0, 1.0, 1, 2), Err(PricingError::NegativeInput));
+        assert_eq!(InterestRateSwap::try_new(SwapType::Payer, 100.0, f64::NAN, 0.0, 1.0, 1, 2), Err(PricingError::NegativeInput));
+        // A swap without notional is worth nothing, and the par rate of an annual swap on a flat curve is the annually
+        // compounded rate.
+        let empty = InterestRateSwap::new(SwapType::Payer, 0.0, 0.03, 0.0, 2.0, 1, 4);
+        assert_eq!((empty.value(0.03, 0.05), empty.dv01(0.03, 0.05)), (0.0, 0.0));
+        let spot_starting = InterestRateSwap::new(SwapType::Receiver, 100.0, 0.0, 0.0, 2.0, 1, 1);
+        let par_rate = spot_starting.par_rate(0.03, 0.03);
+        assert!((par_rate-(0.03_f64.exp()-1.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn swaption_test(){
+        let (discount, forward) = (PiecewiseConstantCurve::new(vec![1.0], vec![0.025, 0.03]), 0.032);
+        let payer_swap = InterestRateSwap::new(SwapType::Payer, 1e4, 0.031, 2.0, 7.0, 1, 2);
+        let receiver_swap = InterestRateSwap::new(SwapType::Receiver, 1e4, 0.031, 2.0, 7.0, 1, 2);
+        let (payer, receiver) = (Swaption::new(payer_swap, 1.5), Swaption::new(receiver_swap, 1.5));
+        // A payer minus a receiver swaption is the forward starting payer swap, in both models.
+        let swap_value = payer_swap.value(&discount, forward);
+        let black_parity = payer.black_price(&discount, forward, 0.25)-receiver.black_price(&discount, forward, 0.25);
+        assert!((black_parity-swap_value).abs() < 1e-10);
+        let bachelier_parity = payer.bachelier_price(&discount, forward, 0.008)-receiver.bachelier_price(&discount, forward, 0.008);
+        assert!((bachelier_parity-swap_value).abs() < 1e-10);
+        let par_rate = payer.forward_swap_rate(&discount, forward);
+        let at_the_money = Swaption::new(InterestRateSwap::new(SwapType::Receiver, 1e4, par_rate, 2.0, 7.0, 1, 2), 1.5);
+        let expected = at_the_money.annuity(&discount)*0.008*1.5_f64.sqrt()/(2.0*std::f64::consts::PI).sqrt();
+        assert!((at_the_money.bachelier_price(&discount, forward, 0.008)-expected).abs() < 1e-10);
+        assert!(payer.try_black_price(&discount, -0.01, 0.2).is_err());
+        assert!(Swaption::try_new(payer_swap, 2.5).is_err());
+    }
+
+    #[test]
+    fn degenerate_swaption_test(){
+        let (discount, forward) = (0.03, 0.035);
+        let swap = InterestRateSwap::new(SwapType::Payer, 1e4, 0.03, 1.0, 3.0, 1, 1);
+        let swaption = Swaption::new(swap, 1.0);
+        // Without volatility, or at expiry, the swaption is worth the swap if it is in the money, and nothing otherwise.
+        let intrinsic = swap.value(discount, forward);
+        assert!(intrinsic > 0.0);
+        assert!((swaption.black_price(discount, forward, 0.0)-intrinsic).abs() < 1e-10);
+        assert!((swaption.bachelier_price(discount, forward, 0.0)-intrinsic).abs() < 1e-10);
+        let receiver = Swaption::new(InterestRateSwap::new(SwapType::Receiver, 1e4, 0.03, 1.0, 3.0, 1, 1), 0.0);
+        assert_eq!(receiver.black_price(discount, forward, 0.2), 0.0);
+        // Bachelier's model prices negative rates, Black's model does not.
+        assert!(swaption.try_bachelier_price(discount, -0.01, 0.01).unwrap() > 0.0);
+        assert_eq!(swaption.try_black_price(discount, -0.01, 0.2), Err(PricingError::InvalidMarketData));
+        assert_eq!(swaption.try_black_price(discount, forward, -0.2), Err(PricingError::NegativeInput));
+        assert_eq!(swaption.try_bachelier_price(discount, forward, f64::NAN), Err(PricingError::NegativeInput));
+        assert_eq!(Swaption::try_new(swap, -1.0), Err(PricingError::NegativeInput));
+    }
+}
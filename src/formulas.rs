@@ -1,104 +1,260 @@
 //! Provides Black-Scholes formulas for various securities and greeks.
 //! Provides Black-Scholes formulas for european call and put options, digital call and put options,
 //! forward prices and zero coupon bonds, and greeks of call and put options.
+//! The second order greeks (vanna, volga, charm, speed, zomma and color) can be negative, so they are returned as `f64`.
+//! 
+//! The volatility used for an option is the root mean square volatility of the stock until expiry, so options on stocks
+//! with a time dependent volatility are priced exactly, and their vega is the derivative with respect to that volatility.
+//! Likewise, the divident rate is the average divident rate of the stock until expiry, including its borrow cost (see
+//! `GeometricBrownianMotionStock::get_average_divident_rate`), which gives the exact forward and the exact distribution of the
+//! price at expiry for term structures and Ornstein-Uhlenbeck processes of divident rates. Prices of calls, puts and digitals,
+//! and their greeks by the spot, the volatility and the rate, are then exact as well. Thetas, charms and colors treat the
+//! average divident rate and the volatility as constant in the time to expiry, so they are approximations for such stocks.
+//! A negative average divident rate, which an Ornstein-Uhlenbeck divident rate with a large variance can have, is accepted.
 //! 
 //! Note: the functions in this module use the custome types `Stock` and `NonNegativeFloat` defined in `stock.rs` and `utils.rs`, respectively.
 //! For ease of use, the formulas are also implemented using only the `f64` type in the module `raw_formulas`.
 
+use crate::greeks::Greeks;
 use crate::raw_formulas;
-use crate::utils::NonNegativeFloat;
+use crate::utils::{Duration, NonNegativeFloat};
 use crate::stock::GeometricBrownianMotionStock;
 
-pub fn european_call_option_price(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::european_call_option_price(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+/// The parameters of the Black-Scholes formulas for an option on a stock, with the root mean square volatility and the average
+/// divident rate of the stock until expiry. The formulas of `raw_formulas` reject negative divident rates, so a negative average
+/// divident rate `q` is folded into the spot: the option is priced on the spot `S*exp(-q*T)` without divident rate, which has the
+/// same forward, and the greeks by the spot and the time are converted back with the chain rule.
+struct Parameters{
+    spot: f64,
+    time_to_expiry: f64,
+    volatility: f64,
+    divident_rate: f64,
+    /// The negative divident rate folded into the spot, or zero.
+    folded_rate: f64,
+}
+
+impl Parameters {
+    fn new(stock: &GeometricBrownianMotionStock, time_to_expiry: Duration)->Parameters{
+        let spot = f64::from(stock.get_current_state().get_value());
+        let divident_rate = stock.get_average_divident_rate(time_to_expiry);
+        let volatility = f64::from(stock.get_root_mean_square_volatility(time_to_expiry));
+        let time_to_expiry = f64::from(time_to_expiry);
+        if divident_rate >= 0.0 {
+            return Parameters{spot, time_to_expiry, volatility, divident_rate, folded_rate: 0.0};
+        }
+        Parameters{spot: spot*(-divident_rate*time_to_expiry).exp(), time_to_expiry, volatility, divident_rate: 0.0, folded_rate: divident_rate}
+    }
+
+    /// Evaluates a formula of `raw_formulas` with the parameters.
+    fn evaluate(&self, formula: fn(f64, f64, f64, f64, f64, f64)->f64, strike: NonNegativeFloat, r: f64)->f64{
+        formula(self.spot, f64::from(strike), r, self.time_to_expiry, self.volatility, self.divident_rate)
+    }
+
+    /// Returns the derivative of the folded spot by the spot of the stock, `exp(-q*T)`.
+    fn spot_factor(&self)->f64{
+        (-self.folded_rate*self.time_to_expiry).exp()
+    }
+
+    /// Converts the theta of the option on the folded spot to the theta of the option, given the delta by the folded spot. The
+    /// folded spot decreases with the time to expiry at the rate `q`.
+    fn theta(&self, theta: f64, delta: f64)->f64{
+        if self.folded_rate == 0.0 {
+            return theta;
+        }
+        theta+self.folded_rate*self.spot*delta
+    }
+
+    /// Converts the charm of the option on the folded spot to the charm of the option, given the delta and the gamma by the
+    /// folded spot.
+    fn charm(&self, charm: f64, delta: f64, gamma: f64)->f64{
+        if self.folded_rate == 0.0 {
+            return charm;
+        }
+        self.spot_factor()*(charm+self.folded_rate*(delta+self.spot*gamma))
+    }
+
+    /// Converts the color of the option on the folded spot to the color of the option, given the gamma and the speed by the
+    /// folded spot.
+    fn color(&self, color: f64, gamma: f64, speed: f64)->f64{
+        if self.folded_rate == 0.0 {
+            return color;
+        }
+        self.spot_factor().powi(2)*(color+self.folded_rate*(2.0*gamma+self.spot*speed))
+    }
+
+    /// Converts the greeks of the option on the folded spot to the greeks of the option.
+    fn greeks(&self, greeks: Greeks)->Greeks{
+        let factor = self.spot_factor();
+        Greeks{
+            delta: greeks.delta*factor,
+            gamma: greeks.gamma*factor*factor,
+            theta: self.theta(greeks.theta, greeks.delta),
+            ..greeks
+        }
+    }
+}
+
+pub fn european_call_option_price(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::european_call_option_price, strike, r);
     NonNegativeFloat::from(ret)
 }
 
-pub fn european_put_option_price(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::european_put_option_price(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn european_put_option_price(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::european_put_option_price, strike, r);
     NonNegativeFloat::from(ret)
 }
 
-pub fn forward_price(stock: &GeometricBrownianMotionStock, r: f64, time: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::forward_price(f64::from(stock.get_current_state().get_value()), r, f64::from(time), f64::from(stock.get_divident_rate()));
+pub fn forward_price(stock: &GeometricBrownianMotionStock, r: f64, time: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time);
+    let ret = raw_formulas::forward_price(p.spot, r, p.time_to_expiry, p.divident_rate);
     NonNegativeFloat::from(ret)
 }
 
-pub fn digital_call_price(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::digital_call_price(f64::from(stock.get_current_state().get_value()), 
-    f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn digital_call_price(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::digital_call_price, strike, r);
     NonNegativeFloat::from(ret)
 }
 
-pub fn digital_put_price(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::digital_put_price(f64::from(stock.get_current_state().get_value()), 
-    f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn digital_put_price(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::digital_put_price, strike, r);
     NonNegativeFloat::from(ret)
 }
 
-pub fn zero_coupon_bond(r: f64,time_to_maturity: NonNegativeFloat) -> NonNegativeFloat{
+pub fn zero_coupon_bond(r: f64,time_to_maturity: Duration) -> NonNegativeFloat{
     let ret = raw_formulas::zero_coupon_bond(r, f64::from(time_to_maturity));
     NonNegativeFloat::from(ret)
 }
 
-pub fn call_delta(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::call_delta(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn call_delta(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::call_delta, strike, r)*p.spot_factor();
     NonNegativeFloat::from(ret)
 }
 
-pub fn call_gamma(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::call_gamma(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn call_gamma(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::call_gamma, strike, r)*p.spot_factor().powi(2);
     NonNegativeFloat::from(ret)
 }
 
-pub fn call_vega(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::call_vega(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn call_vega(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::call_vega, strike, r);
     NonNegativeFloat::from(ret)
 }
 
-pub fn call_rho(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::call_rho(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn call_rho(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::call_rho, strike, r);
     NonNegativeFloat::from(ret)
 }
 
-pub fn call_theta(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::call_theta(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn call_theta(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.theta(p.evaluate(raw_formulas::call_theta, strike, r), p.evaluate(raw_formulas::call_delta, strike, r));
     NonNegativeFloat::from(ret)
 }
 
-pub fn put_delta(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::put_delta(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn put_delta(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::put_delta, strike, r)*p.spot_factor();
     NonNegativeFloat::from(ret)
 }
 
-pub fn put_gamma(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::put_gamma(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn put_gamma(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::put_gamma, strike, r)*p.spot_factor().powi(2);
     NonNegativeFloat::from(ret)
 }
 
-pub fn put_vega(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::put_vega(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn put_vega(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::put_vega, strike, r);
     NonNegativeFloat::from(ret)
 }
 
-pub fn put_rho(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::put_rho(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn put_rho(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.evaluate(raw_formulas::put_rho, strike, r);
     NonNegativeFloat::from(ret)
 }
 
-pub fn put_theta(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: NonNegativeFloat) -> NonNegativeFloat{
-    let ret = raw_formulas::put_theta(f64::from(stock.get_current_state().get_value()), 
-        f64::from(strike), r, f64::from(time_to_expiry), f64::from(stock.get_volatility()), f64::from(stock.get_divident_rate()));
+pub fn put_theta(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> NonNegativeFloat{
+    let p = Parameters::new(stock, time_to_expiry);
+    let ret = p.theta(p.evaluate(raw_formulas::put_theta, strike, r), p.evaluate(raw_formulas::put_delta, strike, r));
     NonNegativeFloat::from(ret)
+}
+
+pub fn call_greeks(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> Greeks{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.greeks(raw_formulas::call_greeks(p.spot, f64::from(strike), r, p.time_to_expiry, p.volatility, p.divident_rate))
+}
+
+pub fn put_greeks(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> Greeks{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.greeks(raw_formulas::put_greeks(p.spot, f64::from(strike), r, p.time_to_expiry, p.volatility, p.divident_rate))
+}
+
+pub fn call_vanna(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.evaluate(raw_formulas::call_vanna, strike, r)*p.spot_factor()
+}
+
+pub fn call_volga(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.evaluate(raw_formulas::call_volga, strike, r)
+}
+
+pub fn call_charm(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.charm(p.evaluate(raw_formulas::call_charm, strike, r), p.evaluate(raw_formulas::call_delta, strike, r), p.evaluate(raw_formulas::call_gamma, strike, r))
+}
+
+pub fn call_speed(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.evaluate(raw_formulas::call_speed, strike, r)*p.spot_factor().powi(3)
+}
+
+pub fn call_zomma(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.evaluate(raw_formulas::call_zomma, strike, r)*p.spot_factor().powi(2)
+}
+
+pub fn call_color(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.color(p.evaluate(raw_formulas::call_color, strike, r), p.evaluate(raw_formulas::call_gamma, strike, r), p.evaluate(raw_formulas::call_speed, strike, r))
+}
+
+pub fn put_vanna(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.evaluate(raw_formulas::put_vanna, strike, r)*p.spot_factor()
+}
+
+pub fn put_volga(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.evaluate(raw_formulas::put_volga, strike, r)
+}
+
+pub fn put_charm(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.charm(p.evaluate(raw_formulas::put_charm, strike, r), p.evaluate(raw_formulas::put_delta, strike, r), p.evaluate(raw_formulas::put_gamma, strike, r))
+}
+
+pub fn put_speed(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.evaluate(raw_formulas::put_speed, strike, r)*p.spot_factor().powi(3)
+}
+
+pub fn put_zomma(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.evaluate(raw_formulas::put_zomma, strike, r)*p.spot_factor().powi(2)
+}
+
+pub fn put_color(stock: &GeometricBrownianMotionStock, strike:NonNegativeFloat, r: f64, time_to_expiry: Duration) -> f64{
+    let p = Parameters::new(stock, time_to_expiry);
+    p.color(p.evaluate(raw_formulas::put_color, strike, r), p.evaluate(raw_formulas::put_gamma, strike, r), p.evaluate(raw_formulas::put_speed, strike, r))
 }
\ No newline at end of file
@@ -0,0 +1,545 @@
+//! Provides batch pricing of european options on the GPU, using `wgpu` compute shaders, for valuing whole portfolios at once.
+//! This module is available with the `gpu` feature.
+//!
+//! A `BatchPricer` evaluates the Black-Scholes prices and greeks, or Monte Carlo prices, of a slice of options. It runs on the
+//! first GPU adapter found, and falls back to the CPU (spreading the options over the available threads) if there is no
+//! adapter or a GPU computation fails, so the same code works on machines with and without a GPU.
+//!
+//! The GPU computes in single precision, so its results agree with the CPU ones up to a relative error of about `1e-5` for
+//! the formulas. The Monte Carlo simulations use the same random numbers on both backends.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::payoff::VanillaType;
+//! use derivative_pricer::gpu::{BatchOption, BatchPricer};
+//! let options: Vec<BatchOption> = (0..1000).map(|i| BatchOption{
+//!     vanilla_type: VanillaType::Call,
+//!     spot: 100.0,
+//!     strike: 50.0+0.1*i as f64,
+//!     short_rate_of_interest: 0.05,
+//!     time_to_expiry: 1.0,
+//!     volatility: 0.2,
+//!     divident_rate: 0.0,
+//! }).collect();
+//! let greeks = BatchPricer::new().price_and_greeks(&options);
+//! assert!((greeks[500].price-10.4506).abs() < 1e-3);
+//! ```
+
+use std::borrow::Cow;
+
+use wgpu::util::DeviceExt;
+
+use crate::payoff::VanillaType;
+use crate::error::PricingError;
+use crate::greeks::Greeks;
+use crate::monte_carlo_pricer::McResult;
+use crate::raw_formulas;
+
+/// The maximal number of options sent to the GPU in one dispatch, keeping the buffers below the default binding size limit.
+const GPU_CHUNK: usize = 1 << 20;
+
+/// The number of invocations in a workgroup of the shaders.
+const WORKGROUP_SIZE: usize = 64;
+
+/// The number of `f32` describing an option in the input buffer of the shaders.
+const INPUT_FLOATS: usize = 8;
+
+/// The number of options below which the CPU backend does not spawn threads.
+const MINIMAL_PARALLEL_BATCH: usize = 1024;
+
+/// The compute shaders, with one invocation per option.
+const SHADER: &str = r#"
+struct BatchOption {
+    spot: f32,
+    strike: f32,
+    short_rate_of_interest: f32,
+    time_to_expiry: f32,
+    volatility: f32,
+    divident_rate: f32,
+    is_call: f32,
+    padding: f32,
+}
+
+struct Params {
+    number_of_paths: u32,
+    seed_low: u32,
+    seed_high: u32,
+    offset: u32,
+}
+
+@group(0) @binding(0) var<storage, read> inputs: array<BatchOption>;
+@group(0) @binding(1) var<storage, read_write> outputs: array<f32>;
+@group(0) @binding(2) var<uniform> params: Params;
+
+// The Abramowitz-Stegun approximation 7.1.26 of the error function, accurate to single precision.
+fn cumulative_normal(x: f32) -> f32 {
+    let z = abs(x)*0.70710678;
+    let t = 1.0/(1.0+0.3275911*z);
+    let y = 1.0-(((((1.061405429*t-1.453152027)*t+1.421413741)*t-0.284496736)*t+0.254829592)*t)*exp(-z*z);
+    return select(0.5*(1.0-y), 0.5*(1.0+y), x >= 0.0);
+}
+
+fn normal_density(x: f32) -> f32 {
+    return 0.39894228*exp(-0.5*x*x);
+}
+
+fn pcg_hash(input: u32) -> u32 {
+    let state = input*747796405u+2891336453u;
+    let word = ((state >> ((state >> 28u)+4u)) ^ state)*277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn uniform(state: u32) -> f32 {
+    return (f32(state >> 8u)+0.5)/16777216.0;
+}
+
+@compute @workgroup_size(64)
+fn greeks(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= arrayLength(&inputs)) {
+        return;
+    }
+    let o = inputs[i];
+    let is_call = o.is_call > 0.5;
+    var result = array<f32, 6>(0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+    if (o.time_to_expiry <= 0.0) {
+        let in_the_money = select(0.0, 1.0, o.spot > o.strike);
+        if (is_call) {
+            result[0] = max(o.spot-o.strike, 0.0);
+            result[1] = in_the_money;
+        } else {
+            result[0] = max(o.strike-o.spot, 0.0);
+            result[1] = in_the_money-1.0;
+        }
+    } else {
+        let root_of_time = sqrt(o.time_to_expiry);
+        let deviation = max(o.volatility*root_of_time, 1e-30);
+        let d1 = (log(o.spot/o.strike)+(o.short_rate_of_interest-o.divident_rate+0.5*o.volatility*o.volatility)*o.time_to_expiry)/deviation;
+        let d2 = d1-deviation;
+        let discount_factor = exp(-o.short_rate_of_interest*o.time_to_expiry);
+        let divident_discount_factor = exp(-o.divident_rate*o.time_to_expiry);
+        let density = normal_density(d1);
+        let forward_spot = o.spot*divident_discount_factor;
+        let discounted_strike = o.strike*discount_factor;
+        let time_decay = forward_spot*density*o.volatility/(2.0*root_of_time);
+        result[2] = divident_discount_factor*density/(o.spot*deviation);
+        result[3] = forward_spot*density*root_of_time;
+        if (is_call) {
+            let n_d1 = cumulative_normal(d1);
+            let n_d2 = cumulative_normal(d2);
+            result[0] = forward_spot*n_d1-discounted_strike*n_d2;
+            result[1] = divident_discount_factor*n_d1;
+            result[4] = o.divident_rate*forward_spot*n_d1-time_decay-o.short_rate_of_interest*discounted_strike*n_d2;
+            result[5] = o.time_to_expiry*discounted_strike*n_d2;
+        } else {
+            let n_minus_d1 = cumulative_normal(-d1);
+            let n_minus_d2 = cumulative_normal(-d2);
+            result[0] = discounted_strike*n_minus_d2-forward_spot*n_minus_d1;
+            result[1] = -divident_discount_factor*n_minus_d1;
+            result[4] = -o.divident_rate*forward_spot*n_minus_d1-time_decay+o.short_rate_of_interest*discounted_strike*n_minus_d2;
+            result[5] = -o.time_to_expiry*discounted_strike*n_minus_d2;
+        }
+    }
+    for (var k = 0u; k < 6u; k++) {
+        outputs[6u*i+k] = result[k];
+    }
+}
+
+@compute @workgroup_size(64)
+fn monte_carlo(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= arrayLength(&inputs)) {
+        return;
+    }
+    let o = inputs[i];
+    var state = pcg_hash(params.seed_low ^ pcg_hash((i+params.offset) ^ pcg_hash(params.seed_high)));
+    let drift = (o.short_rate_of_interest-o.divident_rate-0.5*o.volatility*o.volatility)*o.time_to_expiry;
+    let deviation = o.volatility*sqrt(o.time_to_expiry);
+    let discount_factor = exp(-o.short_rate_of_interest*o.time_to_expiry);
+    var mean: f32 = 0.0;
+    var sum_of_squares: f32 = 0.0;
+    for (var path = 0u; path < params.number_of_paths; path++) {
+        state = pcg_hash(state);
+        let u1 = uniform(state);
+        state = pcg_hash(state);
+        let u2 = uniform(state);
+        let z = sqrt(-2.0*log(u1))*cos(6.2831853*u2);
+        let terminal = o.spot*exp(drift+deviation*z);
+        let payoff = select(max(o.strike-terminal, 0.0), max(terminal-o.strike, 0.0), o.is_call > 0.5);
+        let value = discount_factor*payoff;
+        let difference = value-mean;
+        mean += difference/f32(path+1u);
+        sum_of_squares += difference*(value-mean);
+    }
+    let n = f32(params.number_of_paths);
+    outputs[2u*i] = mean;
+    outputs[2u*i+1u] = sqrt(sum_of_squares/(n-1.0)/n);
+}
+"#;
+
+/// A european vanilla option priced by a `BatchPricer`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatchOption{
+    /// The type of the option.
+    pub vanilla_type: VanillaType,
+    /// The current spot of the underlying.
+    pub spot: f64,
+    /// The strike of the option.
+    pub strike: f64,
+    /// The short rate of interest.
+    pub short_rate_of_interest: f64,
+    /// The time to expiry of the option.
+    pub time_to_expiry: f64,
+    /// The volatility of the underlying.
+    pub volatility: f64,
+    /// The continuous divident rate of the underlying.
+    pub divident_rate: f64,
+}
+
+/// A pricer of batches of european options, running on the GPU if one is available and on the CPU otherwise.
+pub struct BatchPricer{
+    gpu: Option<GpuContext>,
+}
+
+/// The GPU device with the compiled shaders.
+struct GpuContext{
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    greeks_pipeline: wgpu::ComputePipeline,
+    monte_carlo_pipeline: wgpu::ComputePipeline,
+}
+
+impl Default for BatchPricer {
+    fn default() -> Self {
+        BatchPricer::new()
+    }
+}
+
+impl BatchPricer {
+    /// Returns a pricer running on the GPU if an adapter is available, and on the CPU otherwise.
+    pub fn new()->BatchPricer{
+        BatchPricer{gpu: GpuContext::new()}
+    }
+
+    /// Returns a pricer running on the CPU.
+    pub fn cpu()->BatchPricer{
+        BatchPricer{gpu: None}
+    }
+
+    /// Returns true if the pricer runs on the GPU.
+    pub fn is_gpu(&self)->bool{
+        self.gpu.is_some()
+    }
+
+    /// Returns the Black-Scholes price and greeks of every option, in the order of the options.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the parameters of an option (except the short rate of interest) is negative.
+    pub fn price_and_greeks(&self, options: &[BatchOption])->Vec<Greeks>{
+        match self.try_price_and_greeks(options) {
+            Ok(greeks) => greeks,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `price_and_greeks`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_price_and_greeks(&self, options: &[BatchOption])->Result<Vec<Greeks>, PricingError>{
+        check_options(options)?;
+        if let Some(gpu) = &self.gpu {
+            if let Some(greeks) = gpu.price_and_greeks(options) {
+                return Ok(greeks);
+            }
+        }
+        Ok(parallel_map(options, |o| match o.vanilla_type {
+            VanillaType::Call => raw_formulas::call_greeks(o.spot, o.strike, o.short_rate_of_interest, o.time_to_expiry, o.volatility,
+                o.divident_rate),
+            VanillaType::Put => raw_formulas::put_greeks(o.spot, o.strike, o.short_rate_of_interest, o.time_to_expiry, o.volatility,
+                o.divident_rate),
+        }))
+    }
+
+    /// Returns Monte Carlo estimates of the prices of the options, simulating the spot at expiry of every option
+    /// independently.
+    ///
+    /// # Parameters
+    ///
+    /// - `options` - The options to price.
+    /// - `number_of_paths` - The number of trials per option.
+    /// - `seed` - The seed of the random number generation. The same seed gives the same random numbers on both backends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the parameters of an option (except the short rate of interest) is negative, or if `number_of_paths`
+    /// is less than 2 or does not fit in a `u32`.
+    pub fn monte_carlo_prices(&self, options: &[BatchOption], number_of_paths: usize, seed: u64)->Vec<McResult>{
+        assert!(number_of_paths >= 2 && number_of_paths <= u32::MAX as usize, "The number of paths must be between 2 and u32::MAX");
+        match self.try_monte_carlo_prices(options, number_of_paths, seed) {
+            Ok(results) => results,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `monte_carlo_prices`, but returns `PricingError::NegativeInput` instead of panicking if a parameter of an
+    /// option is negative, `PricingError::InsufficientSamples` if `number_of_paths` is less than 2, and
+    /// `PricingError::InvalidState` if it does not fit in a `u32`.
+    pub fn try_monte_carlo_prices(&self, options: &[BatchOption], number_of_paths: usize, seed: u64)
+        ->Result<Vec<McResult>, PricingError>{
+        if number_of_paths < 2 {
+            return Err(PricingError::InsufficientSamples);
+        }
+        if number_of_paths > u32::MAX as usize {
+            return Err(PricingError::InvalidState);
+        }
+        check_options(options)?;
+        if let Some(gpu) = &self.gpu {
+            if let Some(results) = gpu.monte_carlo_prices(options, number_of_paths, seed) {
+                return Ok(results);
+            }
+        }
+        let indexed: Vec<(usize, &BatchOption)> = options.iter().enumerate().collect();
+        Ok(parallel_map(&indexed, |(i, o)| cpu_monte_carlo_price(o, *i as u32, number_of_paths, seed)))
+    }
+}
+
+impl GpuContext {
+    /// Returns the context of the first GPU adapter, or `None` if there is none.
+    fn new()->Option<GpuContext>{
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions{
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).ok()?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor{
+            label: Some("batch pricer"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER)),
+        });
+        let pipeline = |entry_point| device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor{
+            label: Some(entry_point),
+            layout: None,
+            module: &module,
+            entry_point,
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let greeks_pipeline = pipeline("greeks");
+        let monte_carlo_pipeline = pipeline("monte_carlo");
+        Some(GpuContext{device, queue, greeks_pipeline, monte_carlo_pipeline})
+    }
+
+    /// Returns the prices and greeks of the options, or `None` if the computation failed.
+    fn price_and_greeks(&self, options: &[BatchOption])->Option<Vec<Greeks>>{
+        let mut greeks = Vec::with_capacity(options.len());
+        for chunk in options.chunks(GPU_CHUNK) {
+            let output = self.run(&self.greeks_pipeline, chunk, None, 6)?;
+            greeks.extend(output.chunks_exact(6).map(|g| Greeks{
+                price: g[0] as f64,
+                delta: g[1] as f64,
+                gamma: g[2] as f64,
+                vega: g[3] as f64,
+                theta: g[4] as f64,
+                rho: g[5] as f64,
+            }));
+        }
+        Some(greeks)
+    }
+
+    /// Returns Monte Carlo prices of the options, or `None` if the computation failed.
+    fn monte_carlo_prices(&self, options: &[BatchOption], number_of_paths: usize, seed: u64)->Option<Vec<McResult>>{
+        let mut results = Vec::with_capacity(options.len());
+        for (index, chunk) in options.chunks(GPU_CHUNK).enumerate() {
+            let params = [number_of_paths as u32, seed as u32, (seed >> 32) as u32, (index*GPU_CHUNK) as u32];
+            let output = self.run(&self.monte_carlo_pipeline, chunk, Some(params), 2)?;
+            results.extend(output.chunks_exact(2).map(|r| McResult::new(r[0] as f64, r[1] as f64, number_of_paths, seed)));
+        }
+        Some(results)
+    }
+
+    /// Runs `pipeline` on the options, which write `outputs_per_option` floats each, and returns the outputs.
+    fn run(&self, pipeline: &wgpu::ComputePipeline, options: &[BatchOption], params: Option<[u32; 4]>, outputs_per_option: usize)
+        ->Option<Vec<f32>>{
+        let input: Vec<u8> = options.iter().flat_map(|o| {
+            let is_call = if o.vanilla_type == VanillaType::Call { 1.0 } else { 0.0 };
+            let floats: [f32; INPUT_FLOATS] = [o.spot as f32, o.strike as f32, o.short_rate_of_interest as f32, o.time_to_expiry as f32,
+                o.volatility as f32, o.divident_rate as f32, is_call, 0.0];
+            floats.into_iter().flat_map(f32::to_le_bytes)
+        }).collect();
+        let input_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor{
+            label: Some("batch inputs"),
+            contents: &input,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let size = (options.len()*outputs_per_option*4) as u64;
+        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("batch outputs"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("batch readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let params_buffer = params.map(|params| self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor{
+            label: Some("batch parameters"),
+            contents: &params.into_iter().flat_map(u32::to_le_bytes).collect::<Vec<u8>>(),
+            usage: wgpu::BufferUsages::UNIFORM,
+        }));
+        let mut entries = vec![
+            wgpu::BindGroupEntry{binding: 0, resource: input_buffer.as_entire_binding()},
+            wgpu::BindGroupEntry{binding: 1, resource: output_buffer.as_entire_binding()},
+        ];
+        if let Some(buffer) = &params_buffer {
+            entries.push(wgpu::BindGroupEntry{binding: 2, resource: buffer.as_entire_binding()});
+        }
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor{
+            label: Some("batch bind group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &entries,
+        });
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor{label: Some("batch encoder")});
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor{label: Some("batch pass"), timestamp_writes: None});
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(options.len().div_ceil(WORKGROUP_SIZE) as u32, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&output_buffer, 0, &staging_buffer, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+        let slice = staging_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv().ok()?.ok()?;
+        let output = slice.get_mapped_range().chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+        staging_buffer.unmap();
+        Some(output)
+    }
+}
+
+/// Returns `PricingError::NegativeInput` if one of the parameters of an option (except the short rate of interest) is
+/// negative or NaN.
+fn check_options(options: &[BatchOption])->Result<(), PricingError>{
+    let invalid = options.iter().any(|o| [o.spot, o.strike, o.time_to_expiry, o.volatility, o.divident_rate].iter()
+        .any(|x| *x < 0.0 || x.is_nan()));
+    if invalid {
+        return Err(PricingError::NegativeInput);
+    }
+    Ok(())
+}
+
+/// Applies `f` to every item, spreading the items over the available threads.
+fn parallel_map<T: Sync, U: Send>(items: &[T], f: impl Fn(&T)->U+Sync)->Vec<U>{
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    if threads == 1 || items.len() < MINIMAL_PARALLEL_BATCH {
+        return items.iter().map(f).collect();
+    }
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items.chunks(items.len().div_ceil(threads))
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<U>>())).collect();
+        handles.into_iter().flat_map(|handle| handle.join().expect("A pricing thread panicked")).collect()
+    })
+}
+
+/// The PCG hash, used by both backends as a counter based random number generator.
+fn pcg_hash(input: u32)->u32{
+    let state = input.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28)+4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+/// Returns the Monte Carlo price of the option with index `index` in its batch, as computed by the `monte_carlo` shader.
+fn cpu_monte_carlo_price(option: &BatchOption, index: u32, number_of_paths: usize, seed: u64)->McResult{
+    let mut state = pcg_hash(seed as u32 ^ pcg_hash(index ^ pcg_hash((seed >> 32) as u32)));
+    let mut uniform = || {
+        state = pcg_hash(state);
+        ((state >> 8) as f64+0.5)/16777216.0
+    };
+    let (spot, volatility, time_to_expiry) = (option.spot, option.volatility, option.time_to_expiry);
+    let drift = (option.short_rate_of_interest-option.divident_rate-0.5*volatility*volatility)*time_to_expiry;
+    let deviation = volatility*time_to_expiry.sqrt();
+    let discount_factor = (-option.short_rate_of_interest*time_to_expiry).exp();
+    let (mut mean, mut sum_of_squares) = (0.0, 0.0);
+    for path in 0..number_of_paths {
+        let (u1, u2) = (uniform(), uniform());
+        let z = (-2.0*u1.ln()).sqrt()*(2.0*std::f64::consts::PI*u2).cos();
+        let terminal = spot*(drift+deviation*z).exp();
+        let payoff = match option.vanilla_type {
+            VanillaType::Call => f64::max(terminal-option.strike, 0.0),
+            VanillaType::Put => f64::max(option.strike-terminal, 0.0),
+        };
+        let value = discount_factor*payoff;
+        let difference = value-mean;
+        mean += difference/(path+1) as f64;
+        sum_of_squares += difference*(value-mean);
+    }
+    let n = number_of_paths as f64;
+    McResult::new(mean, (sum_of_squares/(n-1.0)/n).sqrt(), number_of_paths, seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_options()->Vec<BatchOption>{
+        (0..2000).map(|i| BatchOption{
+            vanilla_type: if i%2 == 0 { VanillaType::Call } else { VanillaType::Put },
+            spot: 80.0+0.02*i as f64,
+            strike: 100.5,
+            short_rate_of_interest: 0.03,
+            time_to_expiry: if i%500 == 0 { 0.0 } else { 0.5+0.001*i as f64 },
+            volatility: 0.25,
+            divident_rate: 0.01,
+        }).collect()
+    }
+
+    #[test]
+    fn cpu_backend_test(){
+        let options = test_options();
+        let pricer = BatchPricer::cpu();
+        assert!(!pricer.is_gpu());
+        let greeks = pricer.price_and_greeks(&options);
+        let o = options[1];
+        assert_eq!(greeks[1], raw_formulas::put_greeks(o.spot, o.strike, o.short_rate_of_interest, o.time_to_expiry, o.volatility,
+            o.divident_rate));
+        assert_eq!(greeks[500].price, 0.0);
+        let mut invalid = options.clone();
+        invalid[7].volatility = -0.1;
+        assert_eq!(pricer.try_price_and_greeks(&invalid), Err(PricingError::NegativeInput));
+        let results = pricer.monte_carlo_prices(&options[1..3], 20000, 42);
+        for (result, g) in results.iter().zip(&greeks[1..3]) {
+            assert!((result.get_price()-g.price).abs() < 4.0*result.get_std_error());
+        }
+        assert_eq!(results[1].get_price(), cpu_monte_carlo_price(&options[2], 1, 20000, 42).get_price());
+        assert_eq!(pricer.try_monte_carlo_prices(&options[1..3], 1, 42), Err(PricingError::InsufficientSamples));
+    }
+
+    // Runs on the GPU if the machine has one, and checks the fallback otherwise.
+    #[test]
+    fn default_backend_test(){
+        let options = test_options();
+        let pricer = BatchPricer::new();
+        let greeks = pricer.price_and_greeks(&options);
+        let expected = BatchPricer::cpu().price_and_greeks(&options);
+        for ((g, e), o) in greeks.iter().zip(&expected).zip(&options) {
+            assert!((g.price-e.price).abs() < 1e-3*(1.0+e.price));
+            // The greeks of expired options are only compared on the price, as the formulas are singular there.
+            if o.time_to_expiry > 0.0 {
+                assert!((g.delta-e.delta).abs() < 1e-4);
+                assert!((g.vega-e.vega).abs() < 1e-3*(1.0+e.vega));
+            }
+        }
+        let results = pricer.monte_carlo_prices(&options[..4], 10000, 7);
+        let expected = BatchPricer::cpu().monte_carlo_prices(&options[..4], 10000, 7);
+        for (r, e) in results.iter().zip(&expected) {
+            assert!((r.get_price()-e.get_price()).abs() < 1e-2);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Provides tools for computing the greeks of derivative options.
+//! 
+//! The analytic greeks of european call and put options are implemented in `raw_formulas` and `formulas`.
+//! The submodule `automatic_differentiation` computes greeks exactly from the pricing formulas, and `finite_difference`
+//! computes greeks for options without closed form prices.
+//! The trait `HasGreeks` gives the options of the `option` module a uniform interface to their greeks, and the submodule
+//! `surface` evaluates them over grids of spots and volatilities or times.
+//!
+//! Thetas are derivatives with respect to the current time, per year. `ThetaConvention` converts them to thetas per calendar or
+//! trading day, and `ThetaDecomposition` splits the theta of european options into time decay and carry.
+//! `DeltaConvention` selects the delta quoted by FX and commodity desks, see `raw_formulas::call_delta_with_convention`.
+
+#[cfg(feature = "std")]
+pub mod finite_difference;
+#[cfg(feature = "std")]
+pub mod surface;
+pub mod automatic_differentiation;
+
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+#[cfg(feature = "std")]
+use crate::error::PricingError;
+#[cfg(feature = "std")]
+use crate::option::DerivativeOption;
+#[cfg(feature = "std")]
+use crate::stock::GeometricBrownianMotionStock;
+
+/// A struct holding the price of an option together with its first order greeks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Greeks{
+    /// The price of the option.
+    pub price: f64,
+    /// The derivative of the price with respect to the spot.
+    pub delta: f64,
+    /// The second derivative of the price with respect to the spot.
+    pub gamma: f64,
+    /// The derivative of the price with respect to the volatility.
+    pub vega: f64,
+    /// The derivative of the price with respect to the current time, i.e. minus the derivative with respect to the time to expiry.
+    pub theta: f64,
+    /// The derivative of the price with respect to the short rate of interest.
+    pub rho: f64,
+}
+
+impl Greeks {
+    /// Returns the theta per day of `convention`.
+    pub fn theta_per_day(&self, convention: ThetaConvention)->f64{
+        self.theta/convention.days_per_year()
+    }
+}
+
+/// The days by which a theta per year is divided to get a theta per day.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ThetaConvention{
+    /// Calendar days, 365 a year.
+    #[default]
+    CalendarDay,
+    /// Trading days, 252 a year.
+    TradingDay,
+    /// Days of a year with the given number of days.
+    DaysPerYear(f64),
+}
+
+impl ThetaConvention {
+    /// Returns the number of days in a year.
+    pub fn days_per_year(&self)->f64{
+        match self {
+            ThetaConvention::CalendarDay => 365.0,
+            ThetaConvention::TradingDay => 252.0,
+            ThetaConvention::DaysPerYear(days) => *days,
+        }
+    }
+}
+
+/// The conventions in which the delta of a european option is quoted. The spot and forward deltas are the hedge ratios in the
+/// spot and in the forward, and their premium adjusted variants subtract the premium, paid in the foreign currency (or in the
+/// underlying), from the hedge. The driftless delta `N(ln(F/K)/(sigma*sqrt(T)))` is only used to quote strikes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeltaConvention{
+    /// The derivative of the price with respect to the spot, `exp(-q*T)*N(d1)` for a call.
+    #[default]
+    Spot,
+    /// The derivative of the forward price with respect to the forward, `N(d1)` for a call.
+    Forward,
+    /// The spot delta less the premium in units of the spot, `K/S*exp(-r*T)*N(d2)` for a call.
+    SpotPremiumAdjusted,
+    /// The forward delta less the forward premium in units of the forward, `K/F*N(d2)` for a call.
+    ForwardPremiumAdjusted,
+    /// The simple delta `N(ln(F/K)/(sigma*sqrt(T)))` for a call, without the drift terms of `d1` and `d2`.
+    Driftless,
+}
+
+/// The theta of an option split into the decay of its time value at a fixed forward, and the carry, i.e. the change of its
+/// value due to the discounting and the growth of the forward with the rate and the divident rate as time passes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThetaDecomposition{
+    /// The part of the theta due to the loss of optionality, which vanishes without volatility.
+    pub time_decay: f64,
+    /// The part of the theta due to the short rate of interest and the divident rate, which vanishes when both are zero.
+    pub carry: f64,
+}
+
+impl ThetaDecomposition {
+    /// Returns the theta, i.e. the sum of the time decay and the carry.
+    pub fn total(&self)->f64{
+        self.time_decay+self.carry
+    }
+
+    /// Returns the decomposition of the theta per day of `convention`, from a decomposition of the theta per year.
+    pub fn per_day(&self, convention: ThetaConvention)->ThetaDecomposition{
+        let days = convention.days_per_year();
+        ThetaDecomposition{time_decay: self.time_decay/days, carry: self.carry/days}
+    }
+}
+
+/// The seed of the simulations used by the default implementations of `HasGreeks`.
+#[cfg(feature = "std")]
+pub const GREEKS_SEED: u64 = 42;
+
+/// The number of paths of the simulations used by the default implementations of `HasGreeks`.
+#[cfg(feature = "std")]
+pub const GREEKS_NUMBER_OF_PATHS: usize = 100000;
+
+/// A trait for options on a `GeometricBrownianMotionStock` which expose their price and first order greeks.
+///
+/// Options implement `get_underlying` and `with_underlying`. By default the greeks are then computed by `monte_carlo_greeks`,
+/// repricing the option rebuilt on bumped stocks with common random numbers (with `GREEKS_SEED`, `GREEKS_NUMBER_OF_PATHS` paths
+/// and the default bumps). Options with closed form greeks override `greeks`.
+///
+/// The single greek methods compute all the greeks by default, so `greeks` should be used when several of them are needed.
+///
+/// At or past expiry the price is the settlement value of the option (see `DerivativeOption::settlement_value`) and the greeks
+/// are zero.
+///
+/// # Panics
+///
+/// The methods panic if the option expired and its settlement value is unknown, see `try_greeks`.
+#[cfg(feature = "std")]
+pub trait HasGreeks: DerivativeOption<GeometricBrownianMotionStock>+Sized{
+    /// Returns the underlying stock of the option.
+    fn get_underlying(&self)->Arc<GeometricBrownianMotionStock>;
+
+    /// Returns the same option written on `stock` instead, keeping the states of the underlying recorded before the current time
+    /// of `stock`, so that the greeks of a seasoned option account for its past fixings.
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->Self;
+
+    /// Returns the price and the first order greeks of the option, given the short rate of interest `r`.
+    fn greeks(&self, r: f64)->Greeks{
+        if let Some(settled) = settled_greeks(self, r) {
+            return settled.unwrap_or_else(|e| panic!("{}", e));
+        }
+        finite_difference::monte_carlo_greeks(&self.get_underlying(), |stock| self.with_underlying(stock), r, GREEKS_SEED,
+            GREEKS_NUMBER_OF_PATHS, &finite_difference::BumpSizes::default())
+    }
+
+    /// Same as `greeks`, but returns `PricingError::ExpiredOption` instead of panicking if the option expired and its settlement
+    /// value is unknown.
+    fn try_greeks(&self, r: f64)->Result<Greeks, PricingError>{
+        settled_greeks(self, r).unwrap_or_else(|| Ok(self.greeks(r)))
+    }
+
+    /// Returns the delta of the option. Like the other single greeks, it is computed with `greeks`, i.e. with the full set of
+    /// bumped simulations by default, so `greeks` should be called once when several greeks are needed.
+    fn delta(&self, r: f64)->f64{
+        self.greeks(r).delta
+    }
+
+    /// Returns the gamma of the option.
+    fn gamma(&self, r: f64)->f64{
+        self.greeks(r).gamma
+    }
+
+    /// Returns the vega of the option.
+    fn vega(&self, r: f64)->f64{
+        self.greeks(r).vega
+    }
+
+    /// Returns the theta of the option.
+    fn theta(&self, r: f64)->f64{
+        self.greeks(r).theta
+    }
+
+    /// Returns the theta of the option per day of `convention`.
+    fn theta_per_day(&self, r: f64, convention: ThetaConvention)->f64{
+        self.greeks(r).theta_per_day(convention)
+    }
+
+    /// Returns the rho of the option.
+    fn rho(&self, r: f64)->f64{
+        self.greeks(r).rho
+    }
+}
+
+/// Returns the greeks of an option at or past expiry, i.e. its settlement value with zero greeks, or the error of its settlement
+/// value if it is unknown. Returns `None` if the option has not reached expiry yet.
+#[cfg(feature = "std")]
+pub(crate) fn settled_greeks<O: HasGreeks>(option: &O, r: f64)->Option<Result<Greeks, PricingError>>{
+    match option.get_time_to_expiry() {
+        Some(tau) if f64::from(tau) > 0.0 => None,
+        _ => Some(option.settlement_value(r).map(|price| Greeks{price, delta: 0.0, gamma: 0.0, vega: 0.0, theta: 0.0, rho: 0.0})),
+    }
+}
@@ -0,0 +1,250 @@
+//! Computes greeks by automatic differentiation of the pricing formulas in `raw_formulas`.
+//! 
+//! The pricing formulas are evaluated with dual number inputs, so every greek is exact up to machine precision and stays in
+//! sync with the pricing formula it is derived from.
+
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::greeks::Greeks;
+use crate::utils::Real;
+
+/// The index of the derivative by the spot in the gradient of a `GreeksDual`.
+const SPOT: usize = 0;
+/// The index of the derivative by the volatility in the gradient of a `GreeksDual`.
+const VOLATILITY: usize = 1;
+/// The index of the derivative by the time to expiry in the gradient of a `GreeksDual`.
+const TIME: usize = 2;
+/// The index of the derivative by the short rate of interest in the gradient of a `GreeksDual`.
+const RATE: usize = 3;
+
+/// A number carrying its derivatives by the spot, the volatility, the time to expiry and the short rate of interest, and its
+/// second derivative by the spot, so that a single evaluation of a pricing formula gives the price and all the greeks.
+///
+/// # Examples
+///
+/// ```
+/// use derivative_pricer::greeks::automatic_differentiation::GreeksDual;
+/// use derivative_pricer::utils::Real;
+/// let spot = GreeksDual::spot(3.0);
+/// let y = spot*spot*GreeksDual::volatility(2.0);
+/// assert_eq!(y.value(), 18.0);
+/// assert_eq!(y.get_gradient(), [12.0, 9.0, 0.0, 0.0]);
+/// assert_eq!(y.get_spot_curvature(), 4.0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GreeksDual{
+    value: f64,
+    gradient: [f64; 4],
+    spot_curvature: f64,
+}
+
+impl GreeksDual {
+    /// Returns the spot, i.e. the variable with derivative 1 by the spot.
+    pub fn spot(value: f64)->GreeksDual{
+        GreeksDual::variable(value, SPOT)
+    }
+
+    /// Returns the volatility, i.e. the variable with derivative 1 by the volatility.
+    pub fn volatility(value: f64)->GreeksDual{
+        GreeksDual::variable(value, VOLATILITY)
+    }
+
+    /// Returns the time to expiry, i.e. the variable with derivative 1 by the time to expiry.
+    pub fn time_to_expiry(value: f64)->GreeksDual{
+        GreeksDual::variable(value, TIME)
+    }
+
+    /// Returns the short rate of interest, i.e. the variable with derivative 1 by the short rate of interest.
+    pub fn short_rate_of_interest(value: f64)->GreeksDual{
+        GreeksDual::variable(value, RATE)
+    }
+
+    /// Returns the derivatives by the spot, the volatility, the time to expiry and the short rate of interest, in this order.
+    pub fn get_gradient(&self)->[f64; 4]{
+        self.gradient
+    }
+
+    /// Returns the second derivative by the spot.
+    pub fn get_spot_curvature(&self)->f64{
+        self.spot_curvature
+    }
+
+    /// Returns the variable whose derivative by the given index of the gradient is 1.
+    fn variable(value: f64, index: usize)->GreeksDual{
+        let mut gradient = [0.0; 4];
+        gradient[index] = 1.0;
+        GreeksDual{value, gradient, spot_curvature: 0.0}
+    }
+
+    /// Returns `f(self)`, given `f`, `f'` and `f''` at the value of `self`.
+    fn compose(self, value: f64, derivative: f64, second_derivative: f64)->GreeksDual{
+        GreeksDual{
+            value,
+            gradient: self.gradient.map(|x| derivative*x),
+            spot_curvature: derivative*self.spot_curvature+second_derivative*self.gradient[SPOT]*self.gradient[SPOT],
+        }
+    }
+
+    /// Returns `1/self`.
+    fn reciprocal(self)->GreeksDual{
+        let reciprocal = 1.0/self.value;
+        self.compose(reciprocal, -reciprocal*reciprocal, 2.0*reciprocal*reciprocal*reciprocal)
+    }
+}
+
+impl Add for GreeksDual {
+    type Output = GreeksDual;
+    fn add(self, rhs: Self) -> Self::Output {
+        GreeksDual{
+            value: self.value+rhs.value,
+            gradient: core::array::from_fn(|i| self.gradient[i]+rhs.gradient[i]),
+            spot_curvature: self.spot_curvature+rhs.spot_curvature,
+        }
+    }
+}
+
+impl Sub for GreeksDual {
+    type Output = GreeksDual;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self+(-rhs)
+    }
+}
+
+impl Mul for GreeksDual {
+    type Output = GreeksDual;
+    fn mul(self, rhs: Self) -> Self::Output {
+        GreeksDual{
+            value: self.value*rhs.value,
+            gradient: core::array::from_fn(|i| self.gradient[i]*rhs.value+self.value*rhs.gradient[i]),
+            spot_curvature: self.spot_curvature*rhs.value+2.0*self.gradient[SPOT]*rhs.gradient[SPOT]+self.value*rhs.spot_curvature,
+        }
+    }
+}
+
+impl Div for GreeksDual {
+    type Output = GreeksDual;
+    fn div(self, rhs: Self) -> Self::Output {
+        Mul::mul(self, rhs.reciprocal())
+    }
+}
+
+impl Neg for GreeksDual {
+    type Output = GreeksDual;
+    fn neg(self) -> Self::Output {
+        GreeksDual{value: -self.value, gradient: self.gradient.map(|x| -x), spot_curvature: -self.spot_curvature}
+    }
+}
+
+impl Real for GreeksDual {
+    fn constant(x: f64)->Self {
+        GreeksDual{value: x, gradient: [0.0; 4], spot_curvature: 0.0}
+    }
+
+    fn value(self)->f64 {
+        self.value
+    }
+
+    fn exp(self)->Self {
+        let e = Real::exp(self.value);
+        self.compose(e, e, e)
+    }
+
+    fn ln(self)->Self {
+        let reciprocal = 1.0/self.value;
+        self.compose(Real::ln(self.value), reciprocal, -reciprocal*reciprocal)
+    }
+
+    fn sqrt(self)->Self {
+        let root = Real::sqrt(self.value);
+        self.compose(root, 0.5/root, -0.25/(root*self.value))
+    }
+
+    fn cumulative_normal(self)->Self {
+        let density = self.value.normal_density();
+        self.compose(self.value.cumulative_normal(), density, -self.value*density)
+    }
+
+    fn normal_density(self)->Self {
+        let density = self.value.normal_density();
+        self.compose(density, -self.value*density, (self.value*self.value-1.0)*density)
+    }
+}
+
+/// Computes the price and greeks of an option given a pricing formula generic over `Real`, such as
+/// `raw_formulas::european_call_option_price`. The formula is evaluated once, with `GreeksDual` inputs carrying all the
+/// derivatives.
+/// 
+/// # Parameters
+/// 
+/// - `pricer` - A pricing formula with parameters spot, strike, short rate of interest, time to expiry, volatility and dividend rate.
+/// - The remaining parameters are the parameters passed to `pricer`.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use derivative_pricer::greeks::automatic_differentiation::dual_greeks;
+/// use derivative_pricer::raw_formulas;
+/// let greeks = dual_greeks(raw_formulas::european_call_option_price, 101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+/// assert!((greeks.delta-raw_formulas::call_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+/// ```
+pub fn dual_greeks<F>(pricer: F, spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)->Greeks
+where F: Fn(GreeksDual, GreeksDual, GreeksDual, GreeksDual, GreeksDual, GreeksDual)->GreeksDual{
+    let price = pricer(GreeksDual::spot(spot), GreeksDual::constant(strike), GreeksDual::short_rate_of_interest(short_rate_of_interest),
+        GreeksDual::time_to_expiry(time_to_expiry), GreeksDual::volatility(volatility), GreeksDual::constant(divident_rate));
+    Greeks{
+        price: price.value,
+        delta: price.gradient[SPOT],
+        gamma: price.spot_curvature,
+        vega: price.gradient[VOLATILITY],
+        theta: -price.gradient[TIME],
+        rho: price.gradient[RATE],
+    }
+}
+
+/// Returns the price and greeks of a european call option, computed by automatic differentiation.
+pub fn european_call_greeks(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)->Greeks{
+    dual_greeks(crate::raw_formulas::european_call_option_price, spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
+}
+
+/// Returns the price and greeks of a european put option, computed by automatic differentiation.
+pub fn european_put_greeks(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)->Greeks{
+    dual_greeks(crate::raw_formulas::european_put_option_price, spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw_formulas;
+
+    #[test]
+    fn call_greeks_test(){
+        let greeks = european_call_greeks(101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((greeks.price-raw_formulas::european_call_option_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+        assert!((greeks.delta-raw_formulas::call_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+        assert!((greeks.gamma-raw_formulas::call_gamma(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+        assert!((greeks.vega-raw_formulas::call_vega(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-10);
+        assert!((greeks.theta-raw_formulas::call_theta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-10);
+        assert!((greeks.rho-raw_formulas::call_rho(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-10);
+    }
+
+    #[test]
+    fn put_greeks_test(){
+        let greeks = european_put_greeks(101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((greeks.price-raw_formulas::european_put_option_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+        assert!((greeks.delta-raw_formulas::put_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+        assert!((greeks.gamma-raw_formulas::put_gamma(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+        assert!((greeks.vega-raw_formulas::put_vega(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-10);
+        assert!((greeks.theta-raw_formulas::put_theta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-10);
+        assert!((greeks.rho-raw_formulas::put_rho(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-10);
+    }
+
+    #[test]
+    fn digital_delta_test(){
+        let greeks = dual_greeks(raw_formulas::digital_call_price, 45.34, 43.234, 0.03, 0.7, 0.25, 0.01);
+        let h = 1e-5;
+        let fd = (raw_formulas::digital_call_price(45.34+h, 43.234, 0.03, 0.7, 0.25, 0.01)-
+            raw_formulas::digital_call_price(45.34-h, 43.234, 0.03, 0.7, 0.25, 0.01))/(2.0*h);
+        assert!((greeks.delta-fd).abs()<1e-7);
+    }
+}
@@ -0,0 +1,151 @@
+//! Implements bump-and-reprice greeks for any `DerivativeOption` on a `GeometricBrownianMotionStock`.
+//! 
+//! Every bumped price is computed by a Monte Carlo simulation with the same seed, i.e. with common random numbers,
+//! so most of the simulation noise cancels out in the central differences.
+
+use std::sync::Arc;
+
+use crate::greeks::Greeks;
+use crate::monte_carlo_pricer::monte_carlo_pricer;
+use crate::option::DerivativeOption;
+use crate::stock::GeometricBrownianMotionStock;
+use crate::utils::{NonNegativeFloat, TimeStamp};
+
+/// The smallest bump of the spot, used when the relative bump of a small spot is smaller.
+const MINIMAL_SPOT_BUMP: f64 = 1e-4;
+
+/// The sizes of the bumps used when computing greeks by finite differences.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BumpSizes{
+    /// The relative bump of the spot, e.g. `0.01` bumps the spot by 1%.
+    pub spot: f64,
+    /// The absolute bump of the volatility.
+    pub volatility: f64,
+    /// The absolute bump of the short rate of interest.
+    pub rate: f64,
+    /// The absolute bump of the current time.
+    pub time: f64,
+}
+
+impl Default for BumpSizes {
+    /// Returns a 1% relative spot bump, a one vol point volatility bump, a one basis point rate bump and a one day time bump.
+    fn default() -> Self {
+        BumpSizes{
+            spot: 0.01,
+            volatility: 0.01,
+            rate: 0.0001,
+            time: 1.0/365.0,
+        }
+    }
+}
+
+/// Computes the price and greeks of an option by repricing it under bumped spot, volatility, rate and time.
+/// 
+/// Central differences are used, except when bumping down would make the spot, the volatility or the current time negative,
+/// in which case a one sided difference is used. The spot is bumped by at least `1e-4`, so that a zero spot has greeks too.
+/// 
+/// # Parameters
+/// 
+/// - `stock` - The underlying stock.
+/// - `build_option` - A function that gets a shared reference to a (possibly bumped) stock and returns the option written on it.
+/// - `r` - The short rate of interest.
+/// - `seed` - The seed used for every simulation.
+/// - `number_of_paths` - The number of trials in every simulation.
+/// - `bumps` - The sizes of the bumps.
+/// 
+/// # Panics
+/// 
+/// Panics if the option is expired, or if a bump makes it expire.
+pub fn monte_carlo_greeks<O, F>(stock: &GeometricBrownianMotionStock, build_option: F, r: f64, seed: u64, number_of_paths: usize,
+    bumps: &BumpSizes)->Greeks
+where O: DerivativeOption<GeometricBrownianMotionStock>, F: Fn(&Arc<GeometricBrownianMotionStock>)->O{
+    let spot = f64::from(stock.get_current_state().get_value());
+    let time = f64::from(stock.get_current_state().get_time());
+    let lowest_volatility = stock.get_volatility_curve().get_values().iter().fold(f64::INFINITY, |a, b| a.min(*b));
+    // The volatility is bumped by a parallel shift of the volatility curve, so that term structures are kept.
+    let price_with = |spot: f64, time: f64, volatility_shift: f64, r: f64|{
+        let bumped = GeometricBrownianMotionStock::with_term_structures(NonNegativeFloat::from(spot), TimeStamp::from(time),
+            stock.get_drift_curve().clone(), stock.get_volatility_curve().shifted(volatility_shift), stock.get_divident_rate())
+            .with_dividents_of(stock);
+        monte_carlo_pricer(&build_option(&Arc::new(bumped)), r, Some(seed), number_of_paths)
+    };
+
+    let price = price_with(spot, time, 0.0, r);
+
+    let h = (spot*bumps.spot).max(MINIMAL_SPOT_BUMP);
+    let up = price_with(spot+h, time, 0.0, r);
+    let (delta, gamma) = if spot >= h {
+        let down = price_with(spot-h, time, 0.0, r);
+        ((up-down)/(2.0*h), (up-2.0*price+down)/(h*h))
+    }
+    else {
+        let double_up = price_with(spot+2.0*h, time, 0.0, r);
+        ((up-price)/h, (double_up-2.0*up+price)/(h*h))
+    };
+
+    let vega = if lowest_volatility >= bumps.volatility {
+        (price_with(spot, time, bumps.volatility, r)-price_with(spot, time, -bumps.volatility, r))/(2.0*bumps.volatility)
+    }
+    else {
+        (price_with(spot, time, bumps.volatility, r)-price)/bumps.volatility
+    };
+
+    let theta = if time >= bumps.time {
+        (price_with(spot, time+bumps.time, 0.0, r)-price_with(spot, time-bumps.time, 0.0, r))/(2.0*bumps.time)
+    }
+    else {
+        (price_with(spot, time+bumps.time, 0.0, r)-price)/bumps.time
+    };
+
+    let rho = (price_with(spot, time, 0.0, r+bumps.rate)-price_with(spot, time, 0.0, r-bumps.rate))/(2.0*bumps.rate);
+
+    Greeks{
+        price,
+        delta,
+        gamma,
+        vega,
+        theta,
+        rho,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::option::VanillaStockOption;
+    use crate::raw_formulas;
+
+    fn call_payoff(spot: NonNegativeFloat, params: &[f64])->f64{
+        f64::max(f64::from(spot)-params[0], 0.0)
+    }
+
+    #[test]
+    fn call_greeks_test(){
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.5), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.01));
+        let build = |s: &Arc<GeometricBrownianMotionStock>| VanillaStockOption::new(s, TimeStamp::from(1.5), Box::new(call_payoff), vec![105.0]);
+        let bumps = BumpSizes{spot: 0.05, ..BumpSizes::default()};
+        let greeks = monte_carlo_greeks(&stock, build, 0.03, 7, 200000, &bumps);
+        assert!((greeks.price-raw_formulas::european_call_option_price(100.0, 105.0, 0.03, 1.0, 0.2, 0.01)).abs()<0.15);
+        assert!((greeks.delta-raw_formulas::call_delta(100.0, 105.0, 0.03, 1.0, 0.2, 0.01)).abs()<0.01);
+        assert!((greeks.gamma-raw_formulas::call_gamma(100.0, 105.0, 0.03, 1.0, 0.2, 0.01)).abs()<0.002);
+        assert!((greeks.vega-raw_formulas::call_vega(100.0, 105.0, 0.03, 1.0, 0.2, 0.01)).abs()<1.0);
+        assert!((greeks.theta-raw_formulas::call_theta(100.0, 105.0, 0.03, 1.0, 0.2, 0.01)).abs()<0.3);
+        assert!((greeks.rho-raw_formulas::call_rho(100.0, 105.0, 0.03, 1.0, 0.2, 0.01)).abs()<1.0);
+    }
+
+    #[test]
+    fn one_sided_difference_test(){
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.005), NonNegativeFloat::from(0.0));
+        let build = |s: &Arc<GeometricBrownianMotionStock>| VanillaStockOption::new(s, TimeStamp::from(1.0), Box::new(call_payoff), vec![90.0]);
+        let greeks = monte_carlo_greeks(&stock, build, 0.03, 7, 10000, &BumpSizes::default());
+        assert!(greeks.vega.is_finite());
+        assert!(greeks.theta.is_finite());
+        let worthless = GeometricBrownianMotionStock::new(NonNegativeFloat::from(0.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
+        let greeks = monte_carlo_greeks(&worthless, build, 0.03, 7, 1000, &BumpSizes::default());
+        assert_eq!((greeks.price, greeks.delta, greeks.gamma), (0.0, 0.0, 0.0));
+    }
+}
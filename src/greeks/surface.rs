@@ -0,0 +1,206 @@
+//! Evaluates prices and greeks of an option over grids of market states, e.g. for plotting greek surfaces or for risk matrices.
+//!
+//! The option is rebuilt with `HasGreeks::with_underlying` on a copy of its underlying stock for every point of the grid, so
+//! the greeks are analytic where the option has closed form greeks and bump-and-reprice otherwise.
+
+use std::sync::Arc;
+
+use crate::greeks::{Greeks, HasGreeks};
+use crate::stock::GeometricBrownianMotionStock;
+use crate::term_structure::PiecewiseConstantCurve;
+use crate::utils::{NonNegativeFloat, TimeStamp};
+
+/// Values on a rectangular grid, where `get_values()[i][j]` is the value at `(get_x()[i], get_y()[j])`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid2D{
+    /// The points of the first axis.
+    x: Vec<f64>,
+    /// The points of the second axis.
+    y: Vec<f64>,
+    /// The values, one row for every point of the first axis.
+    values: Vec<Vec<f64>>,
+}
+
+impl Grid2D {
+    /// Returns a grid with the given axes and values.
+    /// # Panics
+    /// Panics if there is not one row of values for every point of `x`, with one value for every point of `y`.
+    pub fn new(x: Vec<f64>, y: Vec<f64>, values: Vec<Vec<f64>>)->Grid2D{
+        if values.len() != x.len() || values.iter().any(|row| row.len() != y.len()) {
+            panic!("The values must have one row for every x, with one value for every y.");
+        }
+        Grid2D{x, y, values}
+    }
+
+    /// Returns the points of the first axis.
+    pub fn get_x(&self)->&[f64]{
+        &self.x
+    }
+
+    /// Returns the points of the second axis.
+    pub fn get_y(&self)->&[f64]{
+        &self.y
+    }
+
+    /// Returns the values, one row for every point of the first axis.
+    pub fn get_values(&self)->&[Vec<f64>]{
+        &self.values
+    }
+
+    /// Returns the value at `(get_x()[i], get_y()[j])`.
+    /// # Panics
+    /// Panics if `i` or `j` is out of range.
+    pub fn value(&self, i: usize, j: usize)->f64{
+        self.values[i][j]
+    }
+
+    /// Returns the grid as `(x, y, value)` triples, row by row, e.g. for writing it to a file for plotting.
+    pub fn points(&self)->Vec<(f64, f64, f64)>{
+        self.x.iter().zip(&self.values)
+            .flat_map(|(x, row)| self.y.iter().zip(row).map(move |(y, value)| (*x, *y, *value)))
+            .collect()
+    }
+}
+
+/// The second axis of a surface, the first being the spot.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SurfaceAxis{
+    /// Flat volatilities, replacing the volatility curve of the underlying.
+    Volatility(Vec<f64>),
+    /// Current times of the underlying, i.e. the surface shows how the option ages.
+    Time(Vec<f64>),
+}
+
+impl SurfaceAxis {
+    /// Returns the points of the axis.
+    pub fn get_points(&self)->&[f64]{
+        match self {
+            SurfaceAxis::Volatility(points) | SurfaceAxis::Time(points) => points,
+        }
+    }
+}
+
+/// A quantity evaluated on a surface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SurfaceQuantity{
+    /// The price of the option.
+    Price,
+    /// The delta of the option.
+    Delta,
+    /// The gamma of the option.
+    Gamma,
+    /// The vega of the option.
+    Vega,
+    /// The theta of the option.
+    Theta,
+    /// The rho of the option.
+    Rho,
+}
+
+impl SurfaceQuantity {
+    /// Returns the quantity from the given greeks.
+    pub fn value(&self, greeks: &Greeks)->f64{
+        match self {
+            SurfaceQuantity::Price => greeks.price,
+            SurfaceQuantity::Delta => greeks.delta,
+            SurfaceQuantity::Gamma => greeks.gamma,
+            SurfaceQuantity::Vega => greeks.vega,
+            SurfaceQuantity::Theta => greeks.theta,
+            SurfaceQuantity::Rho => greeks.rho,
+        }
+    }
+}
+
+/// Returns `quantity` of `option` on the grid of `spots` and the points of `axis`, given the short rate of interest `r`.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use derivative_pricer::greeks::surface::{surface, SurfaceAxis, SurfaceQuantity};
+/// use derivative_pricer::option::VanillaStockOption;
+/// use derivative_pricer::payoff::CallPayoff;
+/// use derivative_pricer::stock::GeometricBrownianMotionStock;
+/// use derivative_pricer::utils::{NonNegativeFloat, TimeStamp};
+/// let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 0.05,
+///     NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+/// let call = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(100.0));
+/// let gammas = surface(&call, &[90.0, 100.0, 110.0], &SurfaceAxis::Volatility(vec![0.1, 0.2, 0.3]), SurfaceQuantity::Gamma, 0.05);
+/// assert!(gammas.value(1, 0) > gammas.value(1, 2));
+/// ```
+///
+/// # Panics
+///
+/// Panics if a spot or a volatility is negative, or if the option is expired at one of the times.
+pub fn surface<O: HasGreeks>(option: &O, spots: &[f64], axis: &SurfaceAxis, quantity: SurfaceQuantity, r: f64)->Grid2D{
+    surfaces(option, spots, axis, &[quantity], r).remove(0)
+}
+
+/// Same as `surface`, but returns one grid for every quantity of `quantities`, computing the greeks only once per point.
+///
+/// # Panics
+///
+/// Panics if a spot or a volatility is negative, or if the option is expired at one of the times.
+pub fn surfaces<O: HasGreeks>(option: &O, spots: &[f64], axis: &SurfaceAxis, quantities: &[SurfaceQuantity], r: f64)->Vec<Grid2D>{
+    let greeks = greeks_grid(option, spots, axis, r);
+    quantities.iter().map(|quantity| {
+        let values = greeks.iter().map(|row| row.iter().map(|g| quantity.value(g)).collect()).collect();
+        Grid2D::new(spots.to_vec(), axis.get_points().to_vec(), values)
+    }).collect()
+}
+
+/// Returns the greeks of `option` for every spot of `spots` (the rows) and every point of `axis`.
+fn greeks_grid<O: HasGreeks>(option: &O, spots: &[f64], axis: &SurfaceAxis, r: f64)->Vec<Vec<Greeks>>{
+    let stock = option.get_underlying();
+    let time = f64::from(stock.get_current_state().get_time());
+    spots.iter().map(|spot| axis.get_points().iter().map(|point| {
+        let (time, volatility) = match axis {
+            SurfaceAxis::Volatility(_) => (time, PiecewiseConstantCurve::constant(f64::from(NonNegativeFloat::from(*point)))),
+            SurfaceAxis::Time(_) => (*point, stock.get_volatility_curve().clone()),
+        };
+        let moved = GeometricBrownianMotionStock::with_term_structures(NonNegativeFloat::from(*spot), TimeStamp::from(time),
+            stock.get_drift_curve().clone(), volatility, stock.get_divident_rate())
+            .with_dividents_of(&stock);
+        option.with_underlying(&Arc::new(moved)).greeks(r)
+    }).collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::option::VanillaStockOption;
+    use crate::payoff::CallPayoff;
+    use crate::raw_formulas;
+
+    #[test]
+    fn grid_test(){
+        let grid = Grid2D::new(vec![1.0, 2.0], vec![3.0, 4.0, 5.0], vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+        assert_eq!(grid.value(1, 2), 6.0);
+        assert_eq!(grid.points()[4], (2.0, 4.0, 5.0));
+        assert_eq!(grid.points().len(), 6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn ragged_grid_test(){
+        Grid2D::new(vec![1.0, 2.0], vec![3.0], vec![vec![1.0], vec![]]);
+    }
+
+    #[test]
+    fn call_surface_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.05, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.01)));
+        let call = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(105.0));
+        let spots = [80.0, 100.0, 120.0];
+        let volatilities = SurfaceAxis::Volatility(vec![0.1, 0.3]);
+        let grids = surfaces(&call, &spots, &volatilities, &[SurfaceQuantity::Price, SurfaceQuantity::Vega], 0.05);
+        assert_eq!(grids[0].get_x(), &spots);
+        assert_eq!(grids[0].get_y(), &[0.1, 0.3]);
+        assert_eq!(grids[0].value(2, 1), raw_formulas::european_call_option_price(120.0, 105.0, 0.05, 1.0, 0.3, 0.01));
+        assert_eq!(grids[1].value(0, 0), raw_formulas::call_vega(80.0, 105.0, 0.05, 1.0, 0.1, 0.01));
+        let times = SurfaceAxis::Time(vec![0.0, 0.75]);
+        let deltas = surface(&call, &spots, &times, SurfaceQuantity::Delta, 0.05);
+        assert_eq!(deltas.value(1, 1), raw_formulas::call_delta(100.0, 105.0, 0.05, 0.25, 0.2, 0.01));
+        assert!(deltas.value(0, 1) < deltas.value(0, 0));
+    }
+}
@@ -0,0 +1,236 @@
+//! Implements a Halton low-discrepancy sequence generator, a light-weight quasi Monte Carlo alternative to pseudo random numbers.
+//!
+//! Coordinate `k` of the `n`-th point of the Halton sequence is the radical inverse of `n` in the `k`-th prime base.
+//! The plain sequence is deterministic, so a Monte Carlo price computed with it carries no statistical error estimate.
+//! Randomizing the sequence (by a random shift or by Owen-style scrambling) keeps its low discrepancy while making every
+//! point uniformly distributed, so the standard error can be estimated from independent randomizations, which are obtained
+//! with `substream`.
+
+use rand::Rng;
+
+use crate::random_number_generator::{splitmix64, Pcg64, RandomNumberGeneratorTrait, SubstreamGenerator};
+use crate::utils::inverse_cumulative_normal_function;
+
+/// The randomization applied to the Halton sequence.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HaltonRandomization{
+    /// The plain, deterministic Halton sequence.
+    #[default]
+    None,
+    /// A random (Cranley-Patterson) shift of every coordinate modulo 1.
+    RandomShift,
+    /// Owen-style nested scrambling: every digit is permuted by a random affine permutation which depends on the
+    /// preceding digits. Scrambling also breaks the correlations between the coordinates in high prime bases.
+    OwenScrambling,
+}
+
+/// A Halton sequence generator. Consecutive samples fill the coordinates of consecutive points, so that each call to
+/// `get_uniforms(dimension)` returns the next point of the sequence.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HaltonGenerator{
+    /// The prime bases of the coordinates.
+    bases: Vec<u64>,
+    /// The randomization of the sequence.
+    randomization: HaltonRandomization,
+    /// The seed of the randomization.
+    seed: u64,
+    /// The random shifts of the coordinates (zero if the sequence is not shifted).
+    shifts: Vec<f64>,
+    /// The key of the scrambling permutations.
+    scrambling_key: u64,
+    /// The index of the current point. The sequence starts at point 1, skipping the origin.
+    index: u64,
+    /// The next coordinate of the current point.
+    coordinate: usize,
+}
+
+impl HaltonGenerator {
+    /// Returns a new Halton generator of dimension `dimension`, with the given randomization seeded by `seed`
+    /// (or a random seed if `seed`=`None`). The seed is ignored by the plain sequence.
+    /// # Panics
+    /// Panics if `dimension` is zero.
+    pub fn new(dimension: usize, randomization: HaltonRandomization, seed: Option<u64>) -> HaltonGenerator{
+        if dimension == 0 {
+            panic!("The dimension must be positive.");
+        }
+        let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+        HaltonGenerator::with_stream(first_primes(dimension), randomization, seed, 0)
+    }
+
+    /// Returns a generator whose randomization is drawn from stream `stream` of the seed.
+    fn with_stream(bases: Vec<u64>, randomization: HaltonRandomization, seed: u64, stream: u64) -> HaltonGenerator{
+        let mut rng = Pcg64::from_seed(seed, stream);
+        let shifts = match randomization {
+            HaltonRandomization::RandomShift => (0..bases.len()).map(|_| rng.next_f64()).collect(),
+            _ => vec![0.0; bases.len()],
+        };
+        let scrambling_key = rng.next_u64();
+        HaltonGenerator{
+            bases,
+            randomization,
+            seed,
+            shifts,
+            scrambling_key,
+            index: 1,
+            coordinate: 0,
+        }
+    }
+
+    /// Returns the dimension of the points of the sequence.
+    pub fn get_dimension(&self) -> usize{
+        self.bases.len()
+    }
+
+    /// Returns the randomization of the sequence.
+    pub fn get_randomization(&self) -> HaltonRandomization{
+        self.randomization
+    }
+
+    /// Returns coordinate `coordinate` of point `index` of the sequence.
+    fn value(&self, index: u64, coordinate: usize) -> f64{
+        let base = self.bases[coordinate];
+        let x = match self.randomization {
+            HaltonRandomization::OwenScrambling => scrambled_radical_inverse(index, base,
+                splitmix64(self.scrambling_key^(coordinate as u64))),
+            _ => radical_inverse(index, base),
+        };
+        let x = x+self.shifts[coordinate];
+        if x >= 1.0 {
+            x-1.0
+        }
+        else {
+            x
+        }
+    }
+}
+
+impl RandomNumberGeneratorTrait for HaltonGenerator {
+    /// Returns the next `n` coordinates of the sequence, which are in [0,1).
+    fn get_uniforms(&mut self, n: usize) -> Vec<f64>{
+        let mut v = Vec::with_capacity(n);
+        for _ in 0..n{
+            v.push(self.value(self.index, self.coordinate));
+            self.coordinate += 1;
+            if self.coordinate == self.bases.len() {
+                self.coordinate = 0;
+                self.index += 1;
+            }
+        }
+        v
+    }
+
+    /// Returns the next `n` coordinates of the sequence, transformed to standard Gaussians with the inverse cumulative normal function.
+    fn get_gaussians(&mut self, n: usize) -> Vec<f64>{
+        // The randomized sequences can hit 0 (with negligible probability), which has no Gaussian inverse.
+        self.get_uniforms(n).into_iter().map(|x| inverse_cumulative_normal_function(x.max(f64::EPSILON))).collect()
+    }
+
+    /// Advances the generator by `n_draws` coordinates in constant time.
+    fn skip(&mut self, n_draws: usize){
+        let position = self.coordinate+n_draws;
+        self.index += (position/self.bases.len()) as u64;
+        self.coordinate = position%self.bases.len();
+    }
+}
+
+impl SubstreamGenerator for HaltonGenerator {
+    /// Returns an independent randomization of the sequence, started at its first point. The randomizations of different
+    /// substreams are independent, so the spread of the prices computed with them gives a standard error.
+    /// All substreams of the plain sequence are the same.
+    fn substream(&self, index: u64) -> Self{
+        HaltonGenerator::with_stream(self.bases.clone(), self.randomization, self.seed, index)
+    }
+}
+
+/// Returns the first `n` primes.
+fn first_primes(n: usize) -> Vec<u64>{
+    let mut primes: Vec<u64> = Vec::with_capacity(n);
+    let mut candidate = 2;
+    while primes.len() < n {
+        if primes.iter().take_while(|&&p| p*p <= candidate).all(|&p| candidate%p != 0) {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+/// Returns the radical inverse of `index` in base `base`, i.e. its digits mirrored around the radix point.
+fn radical_inverse(mut index: u64, base: u64) -> f64{
+    let inverse_base = 1.0/base as f64;
+    let mut factor = inverse_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index%base) as f64*factor;
+        index /= base;
+        factor *= inverse_base;
+    }
+    result
+}
+
+/// Returns the radical inverse of `index` in base `base`, with each digit scrambled by an affine permutation
+/// `d -> (a*d+b) mod base` whose coefficients are hashed from `key` and the preceding (unscrambled) digits.
+/// All the digits down to double precision are scrambled, including the trailing zeros of `index`.
+fn scrambled_radical_inverse(mut index: u64, base: u64, key: u64) -> f64{
+    let inverse_base = 1.0/base as f64;
+    let number_of_digits = (53.0*std::f64::consts::LN_2/(base as f64).ln()).ceil() as usize;
+    let mut factor = inverse_base;
+    let mut result = 0.0;
+    let mut prefix_hash = key;
+    for _ in 0..number_of_digits{
+        let digit = index%base;
+        index /= base;
+        let hash = splitmix64(prefix_hash);
+        let multiplier = 1+(hash>>32)%(base-1).max(1);
+        let shift = (hash&0xffff_ffff)%base;
+        result += ((multiplier*digit+shift)%base) as f64*factor;
+        factor *= inverse_base;
+        prefix_hash = splitmix64(prefix_hash^(digit+1));
+    }
+    result.min(1.0-f64::EPSILON/2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn halton_sequence_test(){
+        let mut generator = HaltonGenerator::new(2, HaltonRandomization::None, None);
+        assert_eq!(generator.get_uniforms(2), vec![0.5, 1.0/3.0]);
+        assert_eq!(generator.get_uniforms(2), vec![0.25, 2.0/3.0]);
+        generator.skip(3);
+        assert_eq!(generator.get_uniforms(1)[0], radical_inverse(4, 3));
+        assert_eq!(first_primes(6), vec![2, 3, 5, 7, 11, 13]);
+    }
+
+    #[test]
+    fn randomization_test(){
+        // Scrambling keeps the first 3^4 points of the base 3 coordinate in distinct intervals of length 3^-4.
+        let scrambled = HaltonGenerator::new(2, HaltonRandomization::OwenScrambling, Some(11));
+        let mut cells: Vec<usize> = (0..81).map(|i| (scrambled.value(i, 1)*81.0) as usize).collect();
+        cells.sort();
+        assert_eq!(cells, (0..81).collect::<Vec<usize>>());
+        // A shifted point differs from the plain point by the same shift modulo 1.
+        let shifted = HaltonGenerator::new(2, HaltonRandomization::RandomShift, Some(11));
+        for i in 1..20 {
+            let difference = (shifted.value(i, 0)-radical_inverse(i, 2)).rem_euclid(1.0);
+            assert!((difference-shifted.shifts[0]).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn randomized_integration_test(){
+        // Estimate E[Z1^2+Z2^2+Z3^2] = 3 with independent scramblings; the estimates are unbiased and close to each other.
+        let generator = HaltonGenerator::new(3, HaltonRandomization::OwenScrambling, Some(2));
+        let estimates: Vec<f64> = (0..10).map(|s| {
+            let mut g = generator.substream(s);
+            (0..4096).map(|_| g.get_gaussians(3).iter().map(|z| z*z).sum::<f64>()).sum::<f64>()/4096.0
+        }).collect();
+        let mean = estimates.iter().sum::<f64>()/10.0;
+        let standard_error = (estimates.iter().map(|e| (e-mean).powi(2)).sum::<f64>()/90.0).sqrt();
+        assert!(estimates.windows(2).all(|w| w[0] != w[1]));
+        assert!((mean-3.0).abs() < 4.0*standard_error+1e-3);
+        assert!(standard_error < 0.01);
+    }
+}
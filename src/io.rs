@@ -0,0 +1,268 @@
+//! Provides batch pricing of european options read from CSV or JSON, with the results written back as CSV or JSON.
+//!
+//! Every row (or JSON object) is a `PricingRequest`, e.g. the CSV
+//!
+//! ```text
+//! id,instrument,spot,strike,short_rate_of_interest,time_to_expiry,volatility,divident_rate
+//! a,european_call,100,100,0.05,1,0.2,0
+//! ```
+//!
+//! Every request yields a `PricingResult` with the price and greeks, or with an error message if the request is invalid, so
+//! that one bad row does not stop the batch. Rows of the CSV input are read one by one as well, so a row which cannot be
+//! parsed only fails itself. This module is available with the `io` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::io::{price_rows, read_requests_csv};
+//! let csv = "id,instrument,spot,strike,short_rate_of_interest,time_to_expiry,volatility,divident_rate\n\
+//!            a,european_call,100,100,0.05,1,0.2,0\n\
+//!            b,european_swap,100,100,0.05,1,0.2,0\n";
+//! let rows = read_requests_csv(csv.as_bytes());
+//! assert!(rows[1].is_err());
+//! let results = price_rows(&rows);
+//! assert!((results[0].price.unwrap()-10.4506).abs() < 1e-4);
+//! assert!(results[1].error.is_some());
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::greeks::automatic_differentiation::{dual_greeks, GreeksDual};
+use crate::raw_formulas;
+
+/// The instruments that can be priced from a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstrumentType{
+    /// A european call option.
+    EuropeanCall,
+    /// A european put option.
+    EuropeanPut,
+    /// A digital call option, paying 1 if the spot at expiry is above the strike.
+    DigitalCall,
+    /// A digital put option, paying 1 if the spot at expiry is below the strike.
+    DigitalPut,
+    /// An asset-or-nothing call option, paying the spot at expiry if it is above the strike.
+    AssetOrNothingCall,
+    /// An asset-or-nothing put option, paying the spot at expiry if it is below the strike.
+    AssetOrNothingPut,
+}
+
+/// A request to price an option in the Black-Scholes model.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PricingRequest{
+    /// An identifier of the request, copied to its result.
+    pub id: String,
+    /// The instrument to price.
+    pub instrument: InstrumentType,
+    /// The current spot of the underlying.
+    pub spot: f64,
+    /// The strike of the option.
+    pub strike: f64,
+    /// The short rate of interest.
+    pub short_rate_of_interest: f64,
+    /// The time to expiry of the option.
+    pub time_to_expiry: f64,
+    /// The volatility of the underlying.
+    pub volatility: f64,
+    /// The continuous divident rate of the underlying, zero if omitted.
+    #[serde(default)]
+    pub divident_rate: f64,
+}
+
+/// The result of a `PricingRequest`. The price and greeks are `None` exactly when `error` is not.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PricingResult{
+    /// The identifier of the request.
+    pub id: String,
+    /// The price of the option.
+    pub price: Option<f64>,
+    /// The derivative of the price with respect to the spot.
+    pub delta: Option<f64>,
+    /// The second derivative of the price with respect to the spot.
+    pub gamma: Option<f64>,
+    /// The derivative of the price with respect to the volatility.
+    pub vega: Option<f64>,
+    /// The derivative of the price with respect to the current time.
+    pub theta: Option<f64>,
+    /// The derivative of the price with respect to the short rate of interest.
+    pub rho: Option<f64>,
+    /// The reason the request could not be priced.
+    pub error: Option<String>,
+}
+
+/// The ways reading or writing a batch can fail.
+#[derive(Debug)]
+pub enum IoError{
+    /// The CSV input or output failed.
+    Csv(csv::Error),
+    /// The JSON input or output failed.
+    Json(serde_json::Error),
+}
+
+impl std::fmt::Display for IoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IoError::Csv(error) => write!(f, "CSV error: {}", error),
+            IoError::Json(error) => write!(f, "JSON error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for IoError { }
+
+impl From<csv::Error> for IoError {
+    fn from(value: csv::Error) -> Self {
+        IoError::Csv(value)
+    }
+}
+
+impl From<serde_json::Error> for IoError {
+    fn from(value: serde_json::Error) -> Self {
+        IoError::Json(value)
+    }
+}
+
+/// A pricing formula of `raw_formulas`, evaluated with dual numbers to compute the greeks.
+type DualFormula = fn(GreeksDual, GreeksDual, GreeksDual, GreeksDual, GreeksDual, GreeksDual)->GreeksDual;
+
+/// Reads pricing requests from CSV with a header row naming the fields of `PricingRequest`, returning the request or the error of
+/// every row, in the order of the rows. A header which cannot be read is returned as the error of the first row.
+pub fn read_requests_csv<R: std::io::Read>(reader: R)->Vec<Result<PricingRequest, IoError>>{
+    let mut csv_reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    csv_reader.deserialize().map(|row| row.map_err(IoError::from)).collect()
+}
+
+/// Reads pricing requests from a JSON array of objects with the fields of `PricingRequest`.
+pub fn read_requests_json<R: std::io::Read>(reader: R)->Result<Vec<PricingRequest>, IoError>{
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Writes pricing results as CSV with a header row. Missing values are written as empty fields.
+pub fn write_results_csv<W: std::io::Write>(writer: W, results: &[PricingResult])->Result<(), IoError>{
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    for result in results {
+        csv_writer.serialize(result)?;
+    }
+    csv_writer.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
+
+/// Writes pricing results as a JSON array of objects. Missing values are written as `null`.
+pub fn write_results_json<W: std::io::Write>(writer: W, results: &[PricingResult])->Result<(), IoError>{
+    Ok(serde_json::to_writer_pretty(writer, results)?)
+}
+
+/// Prices a single request.
+pub fn price_request(request: &PricingRequest)->PricingResult{
+    let inputs = [request.spot, request.strike, request.time_to_expiry, request.volatility, request.divident_rate];
+    if inputs.iter().any(|x| *x < 0.0 || x.is_nan()) || request.short_rate_of_interest.is_nan() {
+        return PricingResult::failure(&request.id, crate::error::PricingError::NegativeInput.to_string());
+    }
+    let pricer: DualFormula = match request.instrument {
+        InstrumentType::EuropeanCall => raw_formulas::european_call_option_price,
+        InstrumentType::EuropeanPut => raw_formulas::european_put_option_price,
+        InstrumentType::DigitalCall => raw_formulas::digital_call_price,
+        InstrumentType::DigitalPut => raw_formulas::digital_put_price,
+        InstrumentType::AssetOrNothingCall => raw_formulas::asset_or_nothing_call_price,
+        InstrumentType::AssetOrNothingPut => raw_formulas::asset_or_nothing_put_price,
+    };
+    let greeks = dual_greeks(pricer, request.spot, request.strike, request.short_rate_of_interest, request.time_to_expiry,
+        request.volatility, request.divident_rate);
+    PricingResult{
+        id: request.id.clone(),
+        price: Some(greeks.price),
+        delta: Some(greeks.delta),
+        gamma: Some(greeks.gamma),
+        vega: Some(greeks.vega),
+        theta: Some(greeks.theta),
+        rho: Some(greeks.rho),
+        error: None,
+    }
+}
+
+/// Prices the requests one after the other, returning the results in the order of the requests.
+pub fn price_requests(requests: &[PricingRequest])->Vec<PricingResult>{
+    requests.iter().map(price_request).collect()
+}
+
+/// Prices the rows read by `read_requests_csv` one after the other, returning the results in the order of the rows. A row
+/// which could not be read yields a failed result with an empty id, whose error names the line of the row.
+pub fn price_rows(rows: &[Result<PricingRequest, IoError>])->Vec<PricingResult>{
+    rows.iter().map(|row| match row {
+        Ok(request) => price_request(request),
+        Err(error) => PricingResult::failure("", error.to_string()),
+    }).collect()
+}
+
+/// Prices the requests on `number_of_threads` threads, returning the results in the order of the requests.
+///
+/// # Panics
+///
+/// Panics if `number_of_threads` is 0.
+pub fn price_requests_parallel(requests: &[PricingRequest], number_of_threads: usize)->Vec<PricingResult>{
+    assert!(number_of_threads > 0, "At least one thread is needed");
+    if requests.is_empty() {
+        return Vec::new();
+    }
+    let chunk_size = requests.len().div_ceil(number_of_threads);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = requests.chunks(chunk_size).map(|chunk| scope.spawn(move || price_requests(chunk))).collect();
+        handles.into_iter().flat_map(|handle| handle.join().expect("A pricing thread panicked")).collect()
+    })
+}
+
+impl PricingResult {
+    /// Returns the result of a request which could not be priced.
+    fn failure(id: &str, error: String)->PricingResult{
+        PricingResult{id: id.to_string(), price: None, delta: None, gamma: None, vega: None, theta: None, rho: None, error: Some(error)}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_round_trip_test(){
+        let csv = "id,instrument,spot,strike,short_rate_of_interest,time_to_expiry,volatility,divident_rate\n\
+                   a,european_call,100,100,0.05,1,0.2,0.01\n\
+                   b,digital_put,100,90,0.05,1,0.2,0.01\n\
+                   c,european_put,-100,90,0.05,1,0.2,0.01\n\
+                   d,european_put,100,ninety,0.05,1,0.2,0.01\n";
+        let rows = read_requests_csv(csv.as_bytes());
+        assert_eq!(rows.len(), 4);
+        assert!(matches!(rows[3], Err(IoError::Csv(_))));
+        let failure = &price_rows(&rows)[3];
+        assert_eq!(failure.price, None);
+        assert!(failure.error.as_ref().unwrap().contains("line: 5"));
+        let requests: Vec<PricingRequest> = rows.into_iter().take(3).collect::<Result<_, _>>().unwrap();
+        assert_eq!(requests[1].instrument, InstrumentType::DigitalPut);
+        let results = price_requests_parallel(&requests, 2);
+        assert_eq!(results, price_requests(&requests));
+        let price = raw_formulas::european_call_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01);
+        assert!((results[0].price.unwrap()-price).abs() < 1e-12);
+        assert!((results[0].delta.unwrap()-raw_formulas::call_delta(100.0, 100.0, 0.05, 1.0, 0.2, 0.01)).abs() < 1e-12);
+        assert_eq!(results[2].price, None);
+        assert!(results[2].error.is_some());
+        let mut output = Vec::new();
+        write_results_csv(&mut output, &results).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("id,price,delta,gamma,vega,theta,rho,error\n"));
+        assert_eq!(output.lines().count(), 4);
+    }
+
+    #[test]
+    fn json_round_trip_test(){
+        let json = r#"[{"id": "x", "instrument": "european_put", "spot": 100, "strike": 110, "short_rate_of_interest": 0.03,
+            "time_to_expiry": 0.5, "volatility": 0.3}]"#;
+        let requests = read_requests_json(json.as_bytes()).unwrap();
+        assert_eq!(requests[0].divident_rate, 0.0);
+        let results = price_requests(&requests);
+        let mut output = Vec::new();
+        write_results_json(&mut output, &results).unwrap();
+        let parsed: Vec<PricingResult> = serde_json::from_slice(&output).unwrap();
+        assert_eq!(parsed, results);
+        assert!(read_requests_json("[{\"id\": \"x\"}]".as_bytes()).is_err());
+    }
+}
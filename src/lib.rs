@@ -5,19 +5,171 @@
 //! # Features
 //! 
 //! - [x] Black Scholes pricing formulas for european call and put options, digital call and put options, forward price of a stock,
-//!     and zero coupon bonds.
+//!   and zero coupon bonds.
 //! - [x] Monte-Carlo pricer for vanilla options.
 //! - [x] Monte-Carlo pricer for exotic options.
 //! - [x] Formulas for the greeks.
+//! - [x] Monte-Carlo greeks for exotic options, by bump-and-reprice with common random numbers.
+//! - [x] Crank-Nicolson finite difference pricer for options with arbitrary terminal payoffs.
+//! - [x] Carr-Madan FFT pricer for models with a known characteristic function.
+//! - [x] Black-Scholes, Heston, Merton jump diffusion, Variance Gamma and Normal Inverse Gaussian models.
+//! - [x] Gauss-Hermite quadrature pricer for european options with arbitrary payoffs.
+//! - [x] Numerical integration routines (adaptive Simpson, Gauss-Legendre, Gauss-Hermite).
+//! - [x] Root finders (Brent and safeguarded Newton).
+//! - [x] Halton quasi random sequences with random shifts and Owen-style scrambling.
+//! - [x] Option strategies (spreads, straddles, butterflies, iron condors) with aggregate prices, greeks and payoff diagrams.
+//! - [x] Monte Carlo Value at Risk and Expected Shortfall of option portfolios.
+//! - [x] Arbitrage checks (put-call parity, monotonicity, convexity and bounds) for chains of option prices.
+//! - [x] Calibration of the Heston model to vanilla option prices.
+//! - [x] Day count conventions (ACT/365F, ACT/360, 30/360) and holiday calendars.
+//! - [x] Dates from `chrono` for stocks, expiries and monitoring schedules, behind the `chrono` feature.
+//! - [x] Batch pricing with greeks from CSV or JSON, behind the `io` feature.
+//! - [x] WebAssembly support with JavaScript bindings of the formulas and the Monte Carlo pricer, behind the `wasm` feature.
+//! - [x] `no_std` support for `raw_formulas`, `utils` and `numerics`, with `libm` for the elementary functions.
+//! - [x] Pricing formulas generic over the scalar type (`f64`, `f32`, dual numbers, or any type implementing `Real`).
+//! - [x] Batch evaluation of prices, deltas, gammas and vegas of european options over slices.
+//! - [x] Prices and greeks of option chains over grids of strikes and expiries.
+//! - [x] A Black-Scholes calculator sharing `d1`, `d2` and the discount factors between the price and the greeks.
+//! - [x] Single-pass `call_greeks` and `put_greeks` returning the price and all first order greeks.
+//! - [x] Batch pricing of prices, greeks and Monte Carlo prices on the GPU with a CPU fallback, behind the `gpu` feature.
+//! - [x] Piecewise constant term structures of the volatility and the drift of the geometric Brownian motion.
+//! - [x] Risk neutral paths under deterministic, time dependent short rates of interest.
+//! - [x] A mean reverting Ornstein-Uhlenbeck underlying with exact simulation, for spreads, commodities and volatilities.
+//! - [x] The Schwartz one-factor commodity model with seasonality, futures curve fitting, and futures and option prices.
+//! - [x] A seasonal mean reverting temperature underlying, and heating and cooling degree day options.
+//! - [x] The Cox-Ingersoll-Ross short rate model with zero coupon bond prices and exact noncentral chi-squared sampling.
+//! - [x] The Vasicek short rate model with zero coupon bond prices and Jamshidian's coupon bond options.
+//! - [x] Coupon bonds with clean and dirty prices from a discount curve, yields to maturity, durations and convexity.
+//! - [x] Plain vanilla interest rate swaps on discount and forward curves, with par rates and DV01.
+//! - [x] European payer and receiver swaptions with Black and Bachelier volatilities.
+//! - [x] The Barone-Adesi and Whaley approximation of american call and put prices.
+//! - [x] The Bjerksund and Stensland (1993 and 2002) approximations of american call and put prices.
+//! - [x] The Turnbull-Wakeman moment matched approximation of arithmetic Asian call and put prices, with continuous or discrete fixings.
+//! - [x] Floating strike and partial window Asian options, with built-in arithmetic and geometric averaging.
+//! - [x] Ladder options, priced with Monte Carlo and in closed form for continuous monitoring.
+//! - [x] Accumulators and decumulators with knock-out barriers and gearing, priced with Monte Carlo over a fixing calendar.
+//! - [x] Basket Asian and Himalayan options on correlated stocks.
+//! - [x] Deterministic limits of the closed form prices and greeks without volatility or time to expiry.
+//! - [x] Thread-safe options (`Send + Sync`), sharing their underlying through an `Arc`.
+//! - [x] A `HasGreeks` trait giving every single stock option its greeks, analytic for calls and puts and bump-and-reprice otherwise.
+//! - [x] A `MonteCarloEngine` configured by a single `McConfig` (paths, seed, antithetic and quasi random samples, control variate, threads, tolerance, block size), reproducible whatever the number of threads.
+//! - [x] Batch-means error estimation in `MonteCarloEngine`, with the batch standard error and a Student t confidence interval in `McResult`, valid for quasi random runs too.
+//! - [x] Greek surfaces: prices and greeks of any `HasGreeks` option over a grid of spots and volatilities or times, as a `Grid2D`.
+//! - [x] Market data ingestion: `OptionQuote` and `OptionChain` snapshots, read from CSV or JSON, converted into a `VolSurface` with implied forwards, divident rates and volatilities.
+//! - [x] Historical volatility and drift estimators (close-to-close, Parkinson, Garman-Klass, Yang-Zhang) from price series or OHLC bars, ready for `GeometricBrownianMotionStock::new`.
+//! - [x] The early exercise boundary of american options priced with the finite difference pricer.
+//! - [x] The full time-spot solution grid of the finite difference pricer, interpolated in spot and time.
+//! - [x] Sinh-stretched spot grids and Rannacher start-up in the finite difference pricer, for accurate greeks of digital and barrier payoffs.
+//! - [x] Greeks from the Carr-Madan pricer for any characteristic function model: delta and gamma from the transform, vega, theta and rho by bumping.
+//! - [x] Heston path simulation with the quadratic-exponential scheme of Andersen, full truncation Euler or exact sampling of the variance.
+//! - [x] Exact, Euler and Milstein discretization schemes for stock path generation.
+//! - [x] A PathGenerator trait with geometric Brownian motion, Heston, jump diffusion and Ornstein-Uhlenbeck paths, on which options with path payoffs are priced by Monte Carlo.
+//! - [x] A general SDE with closure-defined drift, diffusion and jumps, simulated with the Euler or Milstein scheme, for Monte Carlo pricing under custom dynamics.
+//! - [x] Multi-factor SDE systems driven by correlated Brownian motions, with joint paths for the payoffs of hybrid products.
+//! - [x] Divident rates as constants, term structures or Ornstein-Uhlenbeck processes, and a separate borrow cost, in the forward, the formulas and the simulated paths of a stock.
+//! - [x] Discrete cash dividents subtracted from the simulated paths of a stock at their ex-times, floored at zero.
+//! - [x] Roll-Geske-Whaley american call price with a discrete divident, the benchmark for divident driven early exercise.
+//! - [x] Risk neutral and real world probabilities of finishing in the money, expected payoffs given exercise and breakeven spots of calls, puts and digitals.
+//! - [x] Lognormal distribution of the price of a stock at a horizon, with its density, cdf, moments, quantiles, samples and expectations.
+//! - [x] Risk neutral and real world probabilities of touching a barrier, the density of the first passage time and the expected life until it.
+//! - [x] Thetas per calendar or trading day, and the theta of european options split into time decay and carry.
+//! - [x] Spot, forward, premium adjusted and driftless deltas of european options, selected with a `DeltaConvention`.
+//! - [x] FX smiles built from at-the-money, risk reversal and butterfly quotes at standard deltas, and the strikes of options with given deltas.
+//! - [x] Calendar spread and butterfly arbitrage checks of volatility surfaces, with a repair of small violations.
+//! - [x] Static replication of twice differentiable european payoffs with strips of calls and puts priced on a volatility surface.
+//! - [x] Dupire local volatility surfaces built from implied volatility surfaces, with regularization, simulated as local volatility stocks.
 //! 
+//! # `no_std`
 //! 
+//! With `default-features = false, features = ["libm"]`, the crate is `no_std` (it still needs `alloc`), and only the modules
+//! `error`, `utils`, `raw_formulas` and `numerics` are available. The elementary functions are then computed by `libm`, so
+//! the results are the same on every platform.
+//! 
+
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("Either the `std` or the `libm` feature must be enabled.");
 
+#[cfg(feature = "std")]
 pub mod random_number_generator;
 pub mod utils;
+#[cfg(feature = "std")]
 pub mod option;
+#[cfg(feature = "std")]
+pub mod payoff;
+#[cfg(feature = "std")]
+pub mod strategy;
+#[cfg(feature = "std")]
+pub mod risk;
+#[cfg(feature = "std")]
+pub mod verify;
+#[cfg(feature = "std")]
+pub mod calibration;
+#[cfg(feature = "std")]
+pub mod chain;
+#[cfg(feature = "std")]
+pub mod time;
+#[cfg(feature = "std")]
+pub mod term_structure;
+#[cfg(feature = "io")]
+pub mod io;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "std")]
 pub mod statistics_gatherer;
+#[cfg(feature = "std")]
 pub mod monte_carlo_pricer;
+#[cfg(feature = "std")]
 pub mod formulas;
+#[cfg(feature = "std")]
 pub mod stock;
+#[cfg(feature = "std")]
+pub mod ornstein_uhlenbeck;
+#[cfg(feature = "std")]
+pub mod commodity;
+#[cfg(feature = "std")]
+pub mod weather;
+#[cfg(feature = "std")]
+pub mod short_rate;
+#[cfg(feature = "std")]
+pub mod fixed_income;
 pub mod raw_formulas;
+pub mod greeks;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod volatility_derivatives;
+#[cfg(feature = "std")]
+pub mod pde_pricer;
+#[cfg(feature = "std")]
+pub mod fft_pricer;
+#[cfg(feature = "std")]
+pub mod models;
+#[cfg(feature = "std")]
+pub mod path_generator;
+#[cfg(feature = "std")]
+pub mod sde;
+#[cfg(feature = "std")]
+pub mod quadrature_pricer;
+#[cfg(feature = "std")]
+pub mod replication_pricer;
+#[cfg(feature = "std")]
+pub mod local_vol;
+pub mod numerics;
+#[cfg(feature = "std")]
+pub mod halton_generator;
+#[cfg(feature = "std")]
+pub mod vol_surface;
+#[cfg(feature = "std")]
+pub mod market_data;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod distributions;
+mod math;
 
@@ -0,0 +1,228 @@
+//! Provides the Dupire local volatility surface implied by a `VolSurface`, on a grid of strikes and times, and the local
+//! volatility stock it defines as an `Sde`, to price exotic options consistently with the calls and puts of the surface.
+//!
+//! With the total implied variance `w(y,T) = sigma^2*T` as a function of the log forward moneyness `y = ln(K/F(T))`, the local
+//! variance is
+//!
+//! `sigma_loc^2 = dw/dT / (1-y/w*dw/dy+1/4*(-1/4-1/w+y^2/w^2)*(dw/dy)^2+1/2*d^2w/dy^2)`,
+//!
+//! whose derivatives are computed by central finite differences. The denominator is proportional to the density implied by the
+//! surface, and the numerator to the price of a calendar spread, so they are only positive on a surface free of arbitrage.
+//! Where either of them is too small, the local variance is regularized by the implied variance, and the local volatility is
+//! then bounded by `MINIMAL_LOCAL_VOLATILITY` and `MAXIMAL_LOCAL_VOLATILITY`, so that the paths stay well behaved in the wings.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use derivative_pricer::local_vol::LocalVolSurface;
+//! use derivative_pricer::monte_carlo_pricer::monte_carlo_pricer;
+//! use derivative_pricer::sde::SdeOption;
+//! use derivative_pricer::utils::TimeStamp;
+//! use derivative_pricer::vol_surface::{Smile, VolSurface};
+//! let surface = VolSurface::new(100.0, 0.05, vec![1.0], vec![100.0*0.05_f64.exp()], vec![Smile::new(vec![100.0], vec![0.2])]);
+//! let local_vol = LocalVolSurface::from_vol_surface(&surface, vec![0.5, 1.0], vec![50.0, 100.0, 200.0]);
+//! // A flat smile gives a flat local volatility.
+//! assert!((local_vol.volatility(120.0, 0.7)-0.2).abs() < 1e-6);
+//! let stock = Arc::new(local_vol.to_sde());
+//! let call = SdeOption::new(&stock, &[TimeStamp::from(1.0)], Box::new(|path| (path.get_values()[0]-100.0).max(0.0)));
+//! assert!((monte_carlo_pricer(&call, 0.05, Some(1), 10000)-surface.call_price(100.0, 1.0)).abs() < 0.5);
+//! ```
+
+use crate::error::PricingError;
+use crate::sde::Sde;
+use crate::utils::TimeStamp;
+use crate::vol_surface::VolSurface;
+
+/// The lower bound of the local volatility.
+pub const MINIMAL_LOCAL_VOLATILITY: f64 = 0.01;
+
+/// The upper bound of the local volatility.
+pub const MAXIMAL_LOCAL_VOLATILITY: f64 = 5.0;
+
+/// The smallest denominator of the Dupire formula, relative to its value of 1 on a flat surface, below which the local variance
+/// is replaced by the implied variance.
+const DENOMINATOR_FLOOR: f64 = 1e-2;
+
+/// The step of the finite differences in the log forward moneyness.
+const LOG_MONEYNESS_STEP: f64 = 1e-2;
+
+/// The step of the finite differences in the time, relative to the time.
+const RELATIVE_TIME_STEP: f64 = 1e-2;
+
+/// A local volatility surface, given on a grid of strikes and times to expiry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalVolSurface{
+    /// The implied volatility surface, which gives the spot, the short rate of interest and the forwards of the underlying.
+    surface: VolSurface,
+    /// The times of the grid, positive and strictly increasing.
+    times: Vec<f64>,
+    /// The strikes of the grid, positive and strictly increasing.
+    strikes: Vec<f64>,
+    /// The local volatilities, with one row of values at the strikes for every time.
+    volatilities: Vec<Vec<f64>>,
+}
+
+impl LocalVolSurface {
+    /// Returns the local volatility surface implied by `surface` at the grid of `times` and `strikes`. The grid should cover the
+    /// strikes reached by the paths with a significant probability, as the local volatility is extrapolated flat beyond it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the grid is invalid, see `try_from_vol_surface`.
+    pub fn from_vol_surface(surface: &VolSurface, times: Vec<f64>, strikes: Vec<f64>)->LocalVolSurface{
+        LocalVolSurface::try_from_vol_surface(surface, times, strikes).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `from_vol_surface`, but returns `PricingError::InvalidTimeGrid` if `times` is empty, not positive or not strictly
+    /// increasing, and `PricingError::InvalidMarketData` if the same holds for `strikes`.
+    pub fn try_from_vol_surface(surface: &VolSurface, times: Vec<f64>, strikes: Vec<f64>)->Result<LocalVolSurface, PricingError>{
+        let is_invalid = |grid: &[f64]| grid.is_empty() || grid.iter().any(|x| x.is_nan() || *x <= 0.0) || grid.windows(2).any(|w| w[0] >= w[1]);
+        if is_invalid(&times) {
+            return Err(PricingError::InvalidTimeGrid);
+        }
+        if is_invalid(&strikes) {
+            return Err(PricingError::InvalidMarketData);
+        }
+        let volatilities = times.iter().map(|t| strikes.iter().map(|k| local_volatility(surface, *k, *t)).collect()).collect();
+        Ok(LocalVolSurface{surface: surface.clone(), times, strikes, volatilities})
+    }
+
+    /// Returns the spot of the underlying.
+    pub fn get_spot(&self)->f64{
+        self.surface.get_spot()
+    }
+
+    /// Returns the short rate of interest.
+    pub fn get_short_rate_of_interest(&self)->f64{
+        self.surface.get_short_rate_of_interest()
+    }
+
+    /// Returns the times of the grid.
+    pub fn get_times(&self)->&[f64]{
+        &self.times
+    }
+
+    /// Returns the strikes of the grid.
+    pub fn get_strikes(&self)->&[f64]{
+        &self.strikes
+    }
+
+    /// Returns the local volatilities at the grid, with one row of values at the strikes for every time.
+    pub fn get_volatilities(&self)->&[Vec<f64>]{
+        &self.volatilities
+    }
+
+    /// Returns the local volatility when the underlying is at `spot` at the time `time`, interpolated bilinearly in the grid
+    /// and extrapolated flat beyond it.
+    pub fn volatility(&self, spot: f64, time: f64)->f64{
+        let (i, time_weight) = interpolation_weight(&self.times, time);
+        let (j, strike_weight) = interpolation_weight(&self.strikes, spot);
+        let row = |i: usize| (1.0-strike_weight)*self.volatilities[i][j]+strike_weight*self.volatilities[i][(j+1).min(self.strikes.len()-1)];
+        (1.0-time_weight)*row(i)+time_weight*row((i+1).min(self.times.len()-1))
+    }
+
+    /// Returns the forward of the underlying at the time `time`, i.e. the forward of the implied volatility surface, see
+    /// `VolSurface::forward`.
+    pub fn forward(&self, time: f64)->f64{
+        self.surface.forward(time)
+    }
+
+    /// Returns the local volatility stock, i.e. the process `dS = mu(t)*S*dt + sigma_loc(S,t)*S*dW` started at the spot at time
+    /// 0, where the instantaneous growth rate `mu(t)` reprices the forwards of the surface. Its drift includes the short rate of
+    /// interest and is net of the divident rate implied by the forwards.
+    pub fn to_sde(&self)->Sde{
+        let (drift_surface, diffusion_surface) = (self.surface.clone(), self.clone());
+        Sde::new(self.get_spot(), TimeStamp::from(0.0), move |t, x| drift_surface.instantaneous_forward_growth_rate(t)*x,
+            move |t, x| diffusion_surface.volatility(x, t)*x)
+    }
+}
+
+/// Returns the index of the node of `grid` at or below `x` and the weight of the next node in the linear interpolation at `x`,
+/// which is zero beyond the ends of the grid.
+fn interpolation_weight(grid: &[f64], x: f64)->(usize, f64){
+    let last = grid.len()-1;
+    if x <= grid[0] {
+        return (0, 0.0);
+    }
+    if x >= grid[last] {
+        return (last, 0.0);
+    }
+    let i = grid.partition_point(|g| *g <= x)-1;
+    (i, (x-grid[i])/(grid[i+1]-grid[i]))
+}
+
+/// Returns the regularized Dupire local volatility of `surface` at `strike` and `time`.
+fn local_volatility(surface: &VolSurface, strike: f64, time: f64)->f64{
+    let y = (strike/surface.forward(time)).ln();
+    // The total variance at the log forward moneyness `y` and the time `t`.
+    let w = |y: f64, t: f64| surface.total_variance(surface.forward(t)*y.exp(), t);
+    let (h, dt) = (LOG_MONEYNESS_STEP, RELATIVE_TIME_STEP*time);
+    let variance = w(y, time);
+    let (lower, upper) = (w(y-h, time), w(y+h, time));
+    let time_derivative = (w(y, time+dt)-w(y, time-dt))/(2.0*dt);
+    let first_derivative = (upper-lower)/(2.0*h);
+    let second_derivative = (upper-2.0*variance+lower)/(h*h);
+    let denominator = 1.0-y/variance*first_derivative
+        +0.25*(-0.25-1.0/variance+y*y/(variance*variance))*first_derivative*first_derivative+0.5*second_derivative;
+    let local_variance = if time_derivative > 0.0 && denominator > DENOMINATOR_FLOOR {
+        time_derivative/denominator
+    } else {
+        variance/time
+    };
+    local_variance.sqrt().clamp(MINIMAL_LOCAL_VOLATILITY, MAXIMAL_LOCAL_VOLATILITY)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::monte_carlo_pricer::monte_carlo_pricer;
+    use crate::sde::SdeOption;
+    use crate::vol_surface::Smile;
+
+    #[test]
+    fn local_vol_test(){
+        let (spot, r, q): (f64, f64, f64) = (100.0, 0.03, 0.01);
+        let forward = |t: f64| spot*((r-q)*t).exp();
+        let strikes: Vec<f64> = (0..=40).map(|i| 40.0+5.0*i as f64).collect();
+        let times: Vec<f64> = (1..=20).map(|i| 0.05*i as f64).collect();
+        // A term structure of flat smiles gives the forward volatilities between the expiries.
+        let term_structure = VolSurface::new(spot, r, vec![0.5, 1.0], vec![forward(0.5), forward(1.0)],
+            vec![Smile::new(vec![100.0], vec![0.2]), Smile::new(vec![100.0], vec![0.3])]);
+        let local_vol = LocalVolSurface::from_vol_surface(&term_structure, times.clone(), strikes.clone());
+        assert!((local_vol.volatility(90.0, 0.25)-0.2).abs() < 1e-8);
+        assert!((local_vol.volatility(130.0, 0.8)-((0.09-0.02)/0.5_f64).sqrt()).abs() < 1e-8);
+        assert!((local_vol.forward(0.7)-forward(0.7)).abs() < 1e-10);
+        // A skew gives a steeper local volatility skew, and the local volatility stock reprices the calls of the surface.
+        let skewed = VolSurface::new(spot, r, vec![0.5, 1.0], vec![forward(0.5), forward(1.0)],
+            vec![Smile::new(vec![50.0, 150.0], vec![0.3, 0.2]), Smile::new(vec![50.0, 150.0], vec![0.28, 0.2])]);
+        let local_vol = LocalVolSurface::from_vol_surface(&skewed, times, strikes);
+        let (low, high) = (local_vol.volatility(90.0, 0.75), local_vol.volatility(110.0, 0.75));
+        assert!(low-high > skewed.volatility(90.0, 0.75)-skewed.volatility(110.0, 0.75));
+        let stock = Arc::new(local_vol.to_sde());
+        for strike in [80.0, 100.0, 120.0] {
+            let call = SdeOption::new(&stock, &[TimeStamp::from(1.0)], Box::new(move |path| (path.get_values()[0]-strike).max(0.0)));
+            assert!((monte_carlo_pricer(&call, r, Some(3), 40000)-skewed.call_price(strike, 1.0)).abs() < 0.15);
+        }
+        assert_eq!(LocalVolSurface::try_from_vol_surface(&skewed, vec![], vec![100.0]), Err(PricingError::InvalidTimeGrid));
+        assert_eq!(LocalVolSurface::try_from_vol_surface(&skewed, vec![1.0], vec![100.0, 90.0]), Err(PricingError::InvalidMarketData));
+    }
+
+    #[test]
+    fn degenerate_local_vol_test(){
+        let surface = |volatilities: [f64; 2]| VolSurface::new(100.0, 0.0, vec![0.5, 1.0], vec![100.0, 100.0],
+            vec![Smile::new(vec![100.0], vec![volatilities[0]]), Smile::new(vec![100.0], vec![volatilities[1]])]);
+        // A total variance decreasing in time is a calendar arbitrage, where the local variance is replaced by the implied one.
+        let arbitrage = surface([0.4, 0.2]);
+        let local_vol = LocalVolSurface::from_vol_surface(&arbitrage, vec![0.75], vec![100.0]);
+        assert!((local_vol.get_volatilities()[0][0]-arbitrage.volatility(100.0, 0.75)).abs() < 1e-12);
+        // A single node is extrapolated flat in both directions.
+        assert_eq!(local_vol.volatility(10.0, 0.01), local_vol.volatility(1000.0, 5.0));
+        let flat = surface([0.2, 0.2]);
+        assert_eq!(LocalVolSurface::try_from_vol_surface(&flat, vec![0.0, 1.0], vec![100.0]), Err(PricingError::InvalidTimeGrid));
+        assert_eq!(LocalVolSurface::try_from_vol_surface(&flat, vec![1.0], vec![f64::NAN]), Err(PricingError::InvalidMarketData));
+        assert_eq!(LocalVolSurface::try_from_vol_surface(&flat, vec![1.0], vec![]), Err(PricingError::InvalidMarketData));
+    }
+}
@@ -0,0 +1,291 @@
+//! Provides snapshots of quoted option chains, as delivered by brokers or exchanges, and their conversion into an implied
+//! volatility surface.
+//!
+//! An `OptionChain` holds the quotes of the calls and puts on one underlying at several strikes and expiries. For every expiry
+//! the forward is implied from put-call parity and the implied divident rate from the forward, and the out of the money quotes
+//! are converted into a smile of implied volatilities, which together form a `VolSurface`.
+//!
+//! With the `io` feature, chains can be read from CSV with a header row naming the fields of `OptionQuote`, e.g.
+//!
+//! ```text
+//! vanilla_type,strike,time_to_expiry,bid,ask,last
+//! call,100,0.5,6.1,6.3,
+//! put,100,0.5,4.9,5.1,5.0
+//! ```
+//!
+//! or from JSON objects with the spot, the short rate of interest and an array of quotes.
+
+use crate::payoff::VanillaType;
+use crate::error::PricingError;
+#[cfg(feature = "io")]
+use crate::io::IoError;
+use crate::raw_formulas;
+use crate::vol_surface::{Smile, VolSurface};
+
+/// A quote of a european vanilla option. Missing prices are `None`, e.g. an empty field in CSV.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "io", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptionQuote{
+    /// The type of the option.
+    pub vanilla_type: VanillaType,
+    /// The strike of the option.
+    pub strike: f64,
+    /// The time to expiry of the option.
+    pub time_to_expiry: f64,
+    /// The best bid.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub bid: Option<f64>,
+    /// The best ask.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub ask: Option<f64>,
+    /// The price of the last trade.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub last: Option<f64>,
+}
+
+impl OptionQuote {
+    /// Returns the price of the option: the mid of the bid and the ask if both are quoted and the ask is not below the bid,
+    /// and the price of the last trade otherwise.
+    pub fn mid(&self)->Option<f64>{
+        match (self.bid, self.ask) {
+            (Some(bid), Some(ask)) if bid >= 0.0 && ask >= bid => Some(0.5*(bid+ask)),
+            _ => self.last,
+        }
+    }
+}
+
+/// The quotes of the options on one underlying, with the spot of the underlying and the short rate of interest at the time of
+/// the quotes.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "io", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptionChain{
+    /// The spot of the underlying.
+    spot: f64,
+    /// The short rate of interest.
+    short_rate_of_interest: f64,
+    /// The quotes of the options.
+    quotes: Vec<OptionQuote>,
+}
+
+impl OptionChain {
+    /// Returns the chain with the given quotes.
+    pub fn new(spot: f64, short_rate_of_interest: f64, quotes: Vec<OptionQuote>)->OptionChain{
+        OptionChain{spot, short_rate_of_interest, quotes}
+    }
+
+    /// Returns the spot of the underlying.
+    pub fn get_spot(&self)->f64{
+        self.spot
+    }
+
+    /// Returns the short rate of interest.
+    pub fn get_short_rate_of_interest(&self)->f64{
+        self.short_rate_of_interest
+    }
+
+    /// Returns the quotes of the chain.
+    pub fn get_quotes(&self)->&[OptionQuote]{
+        &self.quotes
+    }
+
+    /// Returns the distinct positive times to expiry of the quotes, in increasing order.
+    pub fn get_expiries(&self)->Vec<f64>{
+        let mut expiries: Vec<f64> = self.quotes.iter().map(|quote| quote.time_to_expiry).filter(|t| *t > 0.0).collect();
+        expiries.sort_by(f64::total_cmp);
+        expiries.dedup();
+        expiries
+    }
+
+    /// Returns the forward of the underlying at the expiry `time_to_expiry` implied by put-call parity `C-P = exp(-r*T)*(F-K)`,
+    /// as the median of the forwards implied by the strikes at which both a call and a put are priced.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PricingError::InvalidMarketData` if no strike of the expiry has both a call and a put price, or if the implied
+    /// forward is not positive.
+    pub fn implied_forward(&self, time_to_expiry: f64)->Result<f64, PricingError>{
+        let discount_factor = (-self.short_rate_of_interest*time_to_expiry).exp();
+        let mut forwards: Vec<f64> = self.prices_at(time_to_expiry, VanillaType::Call).into_iter().filter_map(|(strike, call)| {
+            self.prices_at(time_to_expiry, VanillaType::Put).into_iter()
+                .find(|(put_strike, _)| *put_strike == strike)
+                .map(|(_, put)| strike+(call-put)/discount_factor)
+        }).collect();
+        if forwards.is_empty() {
+            return Err(PricingError::InvalidMarketData);
+        }
+        forwards.sort_by(f64::total_cmp);
+        let middle = forwards.len()/2;
+        let forward = if forwards.len()%2 == 0 { 0.5*(forwards[middle-1]+forwards[middle]) } else { forwards[middle] };
+        if forward.is_nan() || forward <= 0.0 {
+            return Err(PricingError::InvalidMarketData);
+        }
+        Ok(forward)
+    }
+
+    /// Returns the continuous divident rate implied by the forward at the expiry `time_to_expiry`, i.e. `r-ln(F/S)/T`. It is
+    /// negative if the forward grows faster than the short rate of interest, e.g. for a hard to borrow stock.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PricingError::InvalidMarketData` if the forward can not be implied, see `implied_forward`.
+    pub fn implied_divident_rate(&self, time_to_expiry: f64)->Result<f64, PricingError>{
+        Ok(self.short_rate_of_interest-(self.implied_forward(time_to_expiry)?/self.spot).ln()/time_to_expiry)
+    }
+
+    /// Returns the smile of the expiry `time_to_expiry`, given the forward at that expiry. At every strike the implied volatility
+    /// of the out of the money option is used (the put below the forward and the call above it), or of the other option if only
+    /// it is priced. Prices violating the no-arbitrage bounds are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PricingError::InvalidMarketData` if no price of the expiry gives an implied volatility.
+    pub fn implied_smile(&self, time_to_expiry: f64, forward: f64)->Result<Smile, PricingError>{
+        let discount_factor = (-self.short_rate_of_interest*time_to_expiry).exp();
+        let calls = self.prices_at(time_to_expiry, VanillaType::Call);
+        let puts = self.prices_at(time_to_expiry, VanillaType::Put);
+        let mut strikes: Vec<f64> = calls.iter().chain(&puts).map(|(strike, _)| *strike).collect();
+        strikes.sort_by(f64::total_cmp);
+        strikes.dedup();
+        let price = |prices: &[(f64, f64)], strike: f64| prices.iter().find(|(k, _)| *k == strike).map(|(_, price)| *price);
+        // The volatilities are implied with Black's formula on the forward, which needs no divident rate.
+        let call_volatility = |strike: f64| price(&calls, strike)
+            .and_then(|call| raw_formulas::try_call_implied_volatility(forward, strike, 0.0, time_to_expiry, 0.0, call/discount_factor).ok());
+        let put_volatility = |strike: f64| price(&puts, strike)
+            .and_then(|put| raw_formulas::try_put_implied_volatility(forward, strike, 0.0, time_to_expiry, 0.0, put/discount_factor).ok());
+        let (smile_strikes, volatilities): (Vec<f64>, Vec<f64>) = strikes.into_iter().filter(|strike| *strike > 0.0).filter_map(|strike| {
+            let volatility = if strike < forward {
+                put_volatility(strike).or_else(|| call_volatility(strike))
+            }
+            else {
+                call_volatility(strike).or_else(|| put_volatility(strike))
+            };
+            volatility.map(|volatility| (strike, volatility))
+        }).unzip();
+        Smile::try_new(smile_strikes, volatilities)
+    }
+
+    /// Returns the implied volatility surface of the chain, with the implied forward and smile at every expiry. Expiries whose
+    /// forward or smile can not be implied are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use derivative_pricer::payoff::VanillaType;
+    /// use derivative_pricer::market_data::{OptionChain, OptionQuote};
+    /// use derivative_pricer::raw_formulas;
+    /// let quote = |vanilla_type, strike: f64, price: f64| OptionQuote{vanilla_type, strike, time_to_expiry: 1.0, bid: Some(price-0.05),
+    ///     ask: Some(price+0.05), last: None};
+    /// let call = raw_formulas::european_call_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01);
+    /// let put = raw_formulas::european_put_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01);
+    /// let chain = OptionChain::new(100.0, 0.05, vec![quote(VanillaType::Call, 100.0, call), quote(VanillaType::Put, 100.0, put)]);
+    /// let surface = chain.to_vol_surface().unwrap();
+    /// assert!((surface.implied_divident_rate(1.0)-0.01).abs() < 1e-10);
+    /// assert!((surface.volatility(100.0, 1.0)-0.2).abs() < 1e-10);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `PricingError::InvalidMarketData` if the spot is not positive, or if no expiry has both a forward and a smile.
+    pub fn to_vol_surface(&self)->Result<VolSurface, PricingError>{
+        let (mut expiries, mut forwards, mut smiles) = (Vec::new(), Vec::new(), Vec::new());
+        for time_to_expiry in self.get_expiries() {
+            let Ok(forward) = self.implied_forward(time_to_expiry) else { continue };
+            let Ok(smile) = self.implied_smile(time_to_expiry, forward) else { continue };
+            expiries.push(time_to_expiry);
+            forwards.push(forward);
+            smiles.push(smile);
+        }
+        VolSurface::try_new(self.spot, self.short_rate_of_interest, expiries, forwards, smiles)
+    }
+
+    /// Returns the strikes and prices of the priced options of type `vanilla_type` with expiry `time_to_expiry`.
+    fn prices_at(&self, time_to_expiry: f64, vanilla_type: VanillaType)->Vec<(f64, f64)>{
+        self.quotes.iter()
+            .filter(|quote| quote.time_to_expiry == time_to_expiry && quote.vanilla_type == vanilla_type)
+            .filter_map(|quote| quote.mid().map(|price| (quote.strike, price)))
+            .collect()
+    }
+}
+
+/// Reads the quotes of a chain from CSV with a header row naming the fields of `OptionQuote`, where the types are `call` and
+/// `put` and missing prices are empty fields.
+#[cfg(feature = "io")]
+pub fn read_option_chain_csv<R: std::io::Read>(reader: R, spot: f64, short_rate_of_interest: f64)->Result<OptionChain, IoError>{
+    let mut csv_reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    Ok(OptionChain::new(spot, short_rate_of_interest, csv_reader.deserialize().collect::<Result<_, _>>()?))
+}
+
+/// Reads a chain from a JSON object with the fields `spot`, `short_rate_of_interest` and `quotes`, an array of objects with the
+/// fields of `OptionQuote`.
+#[cfg(feature = "io")]
+pub fn read_option_chain_json<R: std::io::Read>(reader: R)->Result<OptionChain, IoError>{
+    Ok(serde_json::from_reader(reader)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the quotes of a chain priced with a skewed volatility, with a spread of 0.02 around the prices.
+    fn skewed_quotes(spot: f64, r: f64, q: f64)->Vec<OptionQuote>{
+        let mut quotes = Vec::new();
+        for t in [0.25, 1.0] {
+            for strike in [80.0, 90.0, 100.0, 110.0, 120.0] {
+                let volatility = 0.2-0.1*(strike/spot-1.0);
+                let call = raw_formulas::european_call_option_price(spot, strike, r, t, volatility, q);
+                let put = raw_formulas::european_put_option_price(spot, strike, r, t, volatility, q);
+                quotes.push(OptionQuote{vanilla_type: VanillaType::Call, strike, time_to_expiry: t, bid: Some(call-0.01), ask: Some(call+0.01), last: None});
+                quotes.push(OptionQuote{vanilla_type: VanillaType::Put, strike, time_to_expiry: t, bid: Some(put-0.01), ask: Some(put+0.01), last: None});
+            }
+        }
+        quotes
+    }
+
+    #[test]
+    fn mid_test(){
+        let quote = OptionQuote{vanilla_type: VanillaType::Call, strike: 100.0, time_to_expiry: 1.0, bid: Some(1.0), ask: Some(1.5), last: Some(2.0)};
+        assert_eq!(quote.mid(), Some(1.25));
+        assert_eq!(OptionQuote{ask: None, ..quote}.mid(), Some(2.0));
+        assert_eq!(OptionQuote{ask: Some(0.5), last: None, ..quote}.mid(), None);
+    }
+
+    #[test]
+    fn vol_surface_test(){
+        let (spot, r, q) = (100.0, 0.03, 0.02);
+        let mut quotes = skewed_quotes(spot, r, q);
+        // A quote below the intrinsic value and an expiry without puts are left out.
+        quotes.push(OptionQuote{vanilla_type: VanillaType::Call, strike: 50.0, time_to_expiry: 1.0, bid: None, ask: None, last: Some(1.0)});
+        quotes.push(OptionQuote{vanilla_type: VanillaType::Call, strike: 100.0, time_to_expiry: 2.0, bid: None, ask: None, last: Some(12.0)});
+        let chain = OptionChain::new(spot, r, quotes);
+        assert_eq!(chain.get_expiries(), vec![0.25, 1.0, 2.0]);
+        assert!((chain.implied_forward(1.0).unwrap()-spot*(r-q).exp()).abs() < 1e-10);
+        assert!((chain.implied_divident_rate(0.25).unwrap()-q).abs() < 1e-10);
+        assert_eq!(chain.implied_forward(2.0), Err(PricingError::InvalidMarketData));
+        let surface = chain.to_vol_surface().unwrap();
+        assert_eq!(surface.get_expiries(), &[0.25, 1.0]);
+        assert_eq!(surface.get_smiles()[1].get_strikes(), &[80.0, 90.0, 100.0, 110.0, 120.0]);
+        for (strike, volatility) in surface.get_smiles()[1].get_strikes().iter().zip(surface.get_smiles()[1].get_volatilities()) {
+            assert!((volatility-(0.2-0.1*(strike/spot-1.0))).abs() < 1e-8);
+        }
+        let call = raw_formulas::european_call_option_price(spot, 110.0, r, 1.0, 0.19, q);
+        assert!((surface.call_price(110.0, 1.0)-call).abs() < 1e-8);
+        assert_eq!(OptionChain::new(spot, r, Vec::new()).to_vol_surface(), Err(PricingError::InvalidMarketData));
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn read_option_chain_test(){
+        let csv = "vanilla_type,strike,time_to_expiry,bid,ask,last\n\
+                   call,100,0.5,6.1,6.3,\n\
+                   put,100,0.5,4.9,5.1,5.0\n";
+        let chain = read_option_chain_csv(csv.as_bytes(), 100.0, 0.05).unwrap();
+        assert_eq!(chain.get_quotes().len(), 2);
+        assert_eq!(chain.get_quotes()[0].last, None);
+        assert_eq!(chain.get_quotes()[1].vanilla_type, VanillaType::Put);
+        assert_eq!(chain.get_quotes()[1].mid(), Some(5.0));
+        let json = serde_json::to_string(&chain).unwrap();
+        assert_eq!(read_option_chain_json(json.as_bytes()).unwrap(), chain);
+        let json = r#"{"spot": 100, "short_rate_of_interest": 0.05, "quotes": [{"vanilla_type": "call", "strike": 100, "time_to_expiry": 0.5, "last": 6.0}]}"#;
+        assert_eq!(read_option_chain_json(json.as_bytes()).unwrap().get_quotes()[0].mid(), Some(6.0));
+    }
+}
@@ -0,0 +1,91 @@
+//! Provides the elementary functions of `f64` which are not in `core`. With `std` they are the inherent methods of `f64`,
+//! and otherwise they are computed by `libm`.
+//!
+//! `exp`, `ln` and `sqrt` are methods of `utils::Real`, which calls the functions below, and the other functions are methods
+//! of `LibmFloat` in `no_std` builds (test builds link `std`, and use the inherent methods), so the code using them is the same with and without `std`.
+
+/// Returns `e^x`.
+pub(crate) fn exp(x: f64)->f64{
+    #[cfg(feature = "std")]
+    { x.exp() }
+    #[cfg(not(feature = "std"))]
+    { libm::exp(x) }
+}
+
+/// Returns the natural logarithm of `x`.
+pub(crate) fn ln(x: f64)->f64{
+    #[cfg(feature = "std")]
+    { x.ln() }
+    #[cfg(not(feature = "std"))]
+    { libm::log(x) }
+}
+
+/// Returns the square root of `x`.
+pub(crate) fn sqrt(x: f64)->f64{
+    #[cfg(feature = "std")]
+    { x.sqrt() }
+    #[cfg(not(feature = "std"))]
+    { libm::sqrt(x) }
+}
+
+/// Returns `e^x` in single precision.
+pub(crate) fn exp_f32(x: f32)->f32{
+    #[cfg(feature = "std")]
+    { x.exp() }
+    #[cfg(not(feature = "std"))]
+    { libm::expf(x) }
+}
+
+/// Returns the natural logarithm of `x` in single precision.
+pub(crate) fn ln_f32(x: f32)->f32{
+    #[cfg(feature = "std")]
+    { x.ln() }
+    #[cfg(not(feature = "std"))]
+    { libm::logf(x) }
+}
+
+/// Returns the square root of `x` in single precision.
+pub(crate) fn sqrt_f32(x: f32)->f32{
+    #[cfg(feature = "std")]
+    { x.sqrt() }
+    #[cfg(not(feature = "std"))]
+    { libm::sqrtf(x) }
+}
+
+/// The elementary functions of `f64`, other than those of `utils::Real`, which are only inherent methods with `std`.
+#[cfg(all(not(feature = "std"), not(test)))]
+pub(crate) trait LibmFloat {
+    fn powf(self, n: f64)->f64;
+    fn sin(self)->f64;
+    fn cos(self)->f64;
+    fn asin(self)->f64;
+    fn trunc(self)->f64;
+    fn exp_m1(self)->f64;
+}
+
+#[cfg(all(not(feature = "std"), not(test)))]
+impl LibmFloat for f64 {
+    fn powf(self, n: f64)->f64{
+        libm::pow(self, n)
+    }
+
+    fn sin(self)->f64{
+        libm::sin(self)
+    }
+
+    fn cos(self)->f64{
+        libm::cos(self)
+    }
+
+    fn asin(self)->f64{
+        libm::asin(self)
+    }
+
+    fn trunc(self)->f64{
+        libm::trunc(self)
+    }
+
+    fn exp_m1(self)->f64{
+        libm::expm1(self)
+    }
+}
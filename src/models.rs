@@ -0,0 +1,712 @@
+//! Provides models of the dynamics of a stock, described by the characteristic function of the log of the stock price at expiry.
+//!
+//! Transform based pricers, such as the Carr-Madan pricer of the `fft_pricer` module, only depend on the `CharacteristicFunction`
+//! trait, so any model implementing it can be priced by them.
+
+use crate::random_number_generator::RandomNumberGeneratorTrait;
+use crate::short_rate::CoxIngersollRossModel;
+use num_complex::Complex64;
+
+/// A trait for models of a stock in which the characteristic function of the log of the stock price at expiry is known
+/// in closed form under the risk neutral measure.
+pub trait CharacteristicFunction {
+    /// Returns `E[exp(i*u*ln(S_T))]` under the risk neutral measure.
+    /// # Parameters
+    /// - `u` - the (complex) argument of the characteristic function.
+    /// - `spot` - the current spot of the stock.
+    /// - `short_rate_of_interest` - the short rate of interest.
+    /// - `time_to_expiry` - the time to expiry.
+    fn characteristic_function(&self, u: Complex64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64)->Complex64;
+}
+
+/// A trait for models with a volatility, which transform based pricers bump to compute the vega of an option.
+pub trait VolatilityBump: CharacteristicFunction+Sized {
+    /// Returns the model with its volatility shifted by `bump`.
+    fn with_volatility_bump(&self, bump: f64)->Self;
+}
+
+/// The Black-Scholes model, i.e. a geometric Brownian motion with a constant volatility and divident rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlackScholesModel{
+    /// The volatility of the stock.
+    volatility: f64,
+    /// The continuous divident rate of the stock.
+    divident_rate: f64,
+}
+
+impl BlackScholesModel {
+    /// Returns a new Black-Scholes model.
+    /// # Panics
+    /// Panics if `volatility` or `divident_rate` are negative.
+    pub fn new(volatility: f64, divident_rate: f64)->BlackScholesModel{
+        if volatility < 0.0 || divident_rate < 0.0 {
+            panic!("One of the parameters is negative")
+        }
+        BlackScholesModel{
+            volatility,
+            divident_rate,
+        }
+    }
+
+    /// Returns the volatility of the stock.
+    pub fn get_volatility(&self)->f64{
+        self.volatility
+    }
+
+    /// Returns the divident rate of the stock.
+    pub fn get_divident_rate(&self)->f64{
+        self.divident_rate
+    }
+}
+
+impl CharacteristicFunction for BlackScholesModel {
+    fn characteristic_function(&self, u: Complex64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64)->Complex64{
+        let variance = self.volatility*self.volatility*time_to_expiry;
+        let mean = spot.ln()+(short_rate_of_interest-self.divident_rate)*time_to_expiry-0.5*variance;
+        (Complex64::i()*u*mean-0.5*variance*u*u).exp()
+    }
+}
+
+impl VolatilityBump for BlackScholesModel {
+    fn with_volatility_bump(&self, bump: f64)->BlackScholesModel{
+        BlackScholesModel{volatility: self.volatility+bump, ..*self}
+    }
+}
+
+/// The discretization of the Heston model used to simulate paths.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HestonScheme{
+    /// The Euler scheme in which the variance is floored at 0 in the drift and the diffusion ("full truncation", Lord et al.).
+    /// It needs many small steps, and is badly biased with a high volatility of the variance and a strongly negative correlation.
+    FullTruncationEuler,
+    /// The quadratic-exponential scheme of Andersen, which matches the first two moments of the variance with a quadratic
+    /// Gaussian or an exponential-like distribution, and integrates the log spot with the central rule and the martingale
+    /// correction. It is accurate with a few steps per year.
+    #[default]
+    QuadraticExponential,
+    /// The variance is sampled exactly from its scaled noncentral chi-squared transition, as the rate of the
+    /// Cox-Ingersoll-Ross model, and the log spot is integrated with the central rule of the quadratic-exponential scheme,
+    /// without the martingale correction. Each step is slower than with the other schemes, but the variance is unbiased for
+    /// any step.
+    ExactVariance,
+}
+
+/// The Heston stochastic volatility model, in which the variance of the stock follows the CIR process
+/// `dv = kappa*(theta-v)dt + sigma*sqrt(v)dW`, and the Brownian motions of the stock and of the variance have correlation `rho`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HestonModel{
+    /// The initial variance.
+    initial_variance: f64,
+    /// The speed of mean reversion of the variance.
+    mean_reversion_speed: f64,
+    /// The long term variance.
+    long_term_variance: f64,
+    /// The volatility of the variance.
+    volatility_of_variance: f64,
+    /// The correlation between the stock and its variance.
+    correlation: f64,
+    /// The continuous divident rate of the stock.
+    divident_rate: f64,
+    /// The discretization used to simulate paths.
+    scheme: HestonScheme,
+}
+
+impl HestonModel {
+    /// Returns a new Heston model.
+    /// # Parameters
+    /// - `initial_variance` - The initial variance `v_0`.
+    /// - `mean_reversion_speed` - The speed of mean reversion `kappa` of the variance.
+    /// - `long_term_variance` - The long term variance `theta`.
+    /// - `volatility_of_variance` - The volatility of the variance `sigma`.
+    /// - `correlation` - The correlation `rho` between the stock and its variance.
+    /// - `divident_rate` - The continuous divident rate of the stock.
+    /// # Panics
+    /// Panics if one of the parameters other than `correlation` is negative, if `volatility_of_variance` is zero
+    /// or if `correlation` is not in [-1,1].
+    pub fn new(initial_variance: f64, mean_reversion_speed: f64, long_term_variance: f64, volatility_of_variance: f64, correlation: f64,
+        divident_rate: f64)->HestonModel{
+        if initial_variance < 0.0 || mean_reversion_speed < 0.0 || long_term_variance < 0.0 || volatility_of_variance < 0.0 || divident_rate < 0.0 {
+            panic!("One of the parameters is negative")
+        }
+        if volatility_of_variance == 0.0 {
+            panic!("The volatility of the variance must be positive.");
+        }
+        if !(-1.0..=1.0).contains(&correlation){
+            panic!("Correlation must be between -1 and 1.");
+        }
+        HestonModel{
+            initial_variance,
+            mean_reversion_speed,
+            long_term_variance,
+            volatility_of_variance,
+            correlation,
+            divident_rate,
+            scheme: HestonScheme::default(),
+        }
+    }
+
+    /// Returns the model which simulates paths with `scheme`. The default is the quadratic-exponential scheme.
+    pub fn with_scheme(self, scheme: HestonScheme)->HestonModel{
+        HestonModel{scheme, ..self}
+    }
+
+    /// Returns the discretization used to simulate paths.
+    pub fn get_scheme(&self)->HestonScheme{
+        self.scheme
+    }
+
+    /// Returns the initial variance.
+    pub fn get_initial_variance(&self)->f64{
+        self.initial_variance
+    }
+
+    /// Returns the speed of mean reversion of the variance.
+    pub fn get_mean_reversion_speed(&self)->f64{
+        self.mean_reversion_speed
+    }
+
+    /// Returns the long term variance.
+    pub fn get_long_term_variance(&self)->f64{
+        self.long_term_variance
+    }
+
+    /// Returns the volatility of the variance.
+    pub fn get_volatility_of_variance(&self)->f64{
+        self.volatility_of_variance
+    }
+
+    /// Returns the correlation between the stock and its variance.
+    pub fn get_correlation(&self)->f64{
+        self.correlation
+    }
+
+    /// Returns the divident rate of the stock.
+    pub fn get_divident_rate(&self)->f64{
+        self.divident_rate
+    }
+
+    /// Returns the CIR process followed by the variance.
+    fn variance_process(&self)->CoxIngersollRossModel{
+        CoxIngersollRossModel::new(self.initial_variance, self.mean_reversion_speed, self.long_term_variance, self.volatility_of_variance)
+    }
+
+    /// Advances the log spot and the variance by `time_step` under the risk neutral measure with the scheme of the model, and
+    /// returns them.
+    /// # Parameters
+    /// - `log_spot` - the log of the spot at the beginning of the step.
+    /// - `variance` - the variance at the beginning of the step.
+    /// - `short_rate_of_interest` - the short rate of interest.
+    /// - `time_step` - the length of the step.
+    /// - `rng` - the random number generator from which the samples of the step are drawn.
+    pub fn sample_step(&self, log_spot: f64, variance: f64, short_rate_of_interest: f64, time_step: f64,
+        rng: &mut impl RandomNumberGeneratorTrait)->(f64, f64){
+        let (kappa, theta, sigma, rho) = (self.mean_reversion_speed, self.long_term_variance, self.volatility_of_variance, self.correlation);
+        let drift = (short_rate_of_interest-self.divident_rate)*time_step;
+        // The log spot with the central rule, K_0+K_1*v+K_2*v'+sqrt(K_3*v+K_4*v')*Z.
+        let k1 = 0.5*time_step*(kappa*rho/sigma-0.5)-rho/sigma;
+        let k2 = 0.5*time_step*(kappa*rho/sigma-0.5)+rho/sigma;
+        let k3 = 0.5*time_step*(1.0-rho*rho);
+        let central_rule = |k0: f64, next_variance: f64, gaussian: f64| {
+            log_spot+drift+k0+k1*variance+k2*next_variance+(k3*(variance+next_variance)).sqrt()*gaussian
+        };
+        // Without the martingale correction, K_0 = -rho*kappa*theta*dt/sigma.
+        let uncorrected_k0 = -rho*kappa*theta*time_step/sigma;
+        match self.scheme {
+            HestonScheme::FullTruncationEuler => {
+                let gaussians = rng.get_gaussians(2);
+                let positive = variance.max(0.0);
+                let stock_gaussian = rho*gaussians[0]+(1.0-rho*rho).sqrt()*gaussians[1];
+                let next_variance = variance+kappa*(theta-positive)*time_step+sigma*(positive*time_step).sqrt()*gaussians[0];
+                (log_spot+drift-0.5*positive*time_step+(positive*time_step).sqrt()*stock_gaussian, next_variance)
+            },
+            HestonScheme::QuadraticExponential => {
+                const CRITICAL_PSI: f64 = 1.5;
+                let gaussians = rng.get_gaussians(2);
+                let uniform = rng.get_uniforms(1)[0];
+                let decay = (-kappa*time_step).exp();
+                // The fraction (1-exp(-kappa*dt))/kappa, which tends to dt as kappa tends to 0.
+                let decay_fraction = if kappa*time_step < 1e-8 { time_step } else { (1.0-decay)/kappa };
+                let mean = theta+(variance-theta)*decay;
+                if mean == 0.0 {
+                    // The variance is 0 and stays there (theta or kappa*dt is 0), so psi is undefined and the step is riskless.
+                    return (log_spot+drift, 0.0);
+                }
+                let second_moment = variance*sigma*sigma*decay*decay_fraction+0.5*theta*sigma*sigma*kappa*decay_fraction*decay_fraction;
+                let psi = second_moment/(mean*mean);
+                let a = k2+0.5*k3;
+                let (next_variance, martingale_k0) = if psi <= CRITICAL_PSI {
+                    let b_squared = 2.0/psi-1.0+(2.0/psi).sqrt()*(2.0/psi-1.0).sqrt();
+                    let scale = mean/(1.0+b_squared);
+                    let b = b_squared.sqrt();
+                    let k0 = (a < 0.5/scale).then(|| -a*b_squared*scale/(1.0-2.0*a*scale)+0.5*(1.0-2.0*a*scale).ln());
+                    (scale*(b+gaussians[0]).powi(2), k0)
+                }
+                else {
+                    let p = (psi-1.0)/(psi+1.0);
+                    let beta = (1.0-p)/mean;
+                    let k0 = (a < beta).then(|| -(p+beta*(1.0-p)/(beta-a)).ln());
+                    let next_variance = if uniform <= p { 0.0 } else { ((1.0-p)/(1.0-uniform)).ln()/beta };
+                    (next_variance, k0)
+                };
+                let k0 = martingale_k0.map_or(uncorrected_k0, |k0| k0-(k1+0.5*k3)*variance);
+                (central_rule(k0, next_variance, gaussians[1]), next_variance)
+            },
+            HestonScheme::ExactVariance => {
+                let next_variance = self.variance_process().sample_transition(variance, time_step, rng);
+                let gaussian = rng.get_gaussians(1)[0];
+                (central_rule(uncorrected_k0, next_variance, gaussian), next_variance)
+            },
+        }
+    }
+
+    /// Returns a path of the stock at `times` under the risk neutral measure, simulated with the scheme of the model.
+    /// # Parameters
+    /// - `spot` - the current spot of the stock.
+    /// - `short_rate_of_interest` - the short rate of interest.
+    /// - `times` - an increasing slice of positive times, measured from now.
+    /// - `maximal_time_step` - the longest step of the discretization. The intervals between `times` are split into equal steps
+    ///   no longer than it.
+    /// - `rng` - the random number generator used to sample the steps.
+    /// # Examples
+    /// ```
+    /// use derivative_pricer::models::{HestonModel, HestonScheme};
+    /// use derivative_pricer::random_number_generator::RandomNumberGenerator;
+    /// let model = HestonModel::new(0.04, 1.5, 0.04, 0.5, -0.7, 0.0).with_scheme(HestonScheme::QuadraticExponential);
+    /// let path = model.generate_risk_neutral_path(100.0, 0.05, &[0.5, 1.0], 0.25, &mut RandomNumberGenerator::new(Some(1)));
+    /// assert_eq!(path.len(), 2);
+    /// ```
+    /// # Panics
+    /// Panics if `maximal_time_step` is not positive.
+    pub fn generate_risk_neutral_path(&self, spot: f64, short_rate_of_interest: f64, times: &[f64], maximal_time_step: f64,
+        rng: &mut impl RandomNumberGeneratorTrait)->Vec<f64>{
+        if maximal_time_step.is_nan() || maximal_time_step <= 0.0 {
+            panic!("The maximal time step must be positive.");
+        }
+        let mut log_spot = spot.ln();
+        let mut variance = self.initial_variance;
+        let mut previous_time = 0.0;
+        times.iter().map(|t| {
+            let number_of_steps = ((t-previous_time)/maximal_time_step).ceil().max(1.0) as usize;
+            let time_step = (t-previous_time)/number_of_steps as f64;
+            for _ in 0..number_of_steps {
+                (log_spot, variance) = self.sample_step(log_spot, variance, short_rate_of_interest, time_step, rng);
+            }
+            previous_time = *t;
+            log_spot.exp()
+        }).collect()
+    }
+}
+
+impl CharacteristicFunction for HestonModel {
+    /// Uses the formulation of Albrecher et al. ("The little Heston trap"), which avoids the branch cut of the complex logarithm.
+    fn characteristic_function(&self, u: Complex64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64)->Complex64{
+        let i = Complex64::i();
+        let kappa = self.mean_reversion_speed;
+        let sigma = self.volatility_of_variance;
+        let beta = kappa-self.correlation*sigma*i*u;
+        let d = (beta*beta+sigma*sigma*(i*u+u*u)).sqrt();
+        let g = (beta-d)/(beta+d);
+        let decay = (-d*time_to_expiry).exp();
+        let drift = i*u*(spot.ln()+(short_rate_of_interest-self.divident_rate)*time_to_expiry);
+        let c = kappa*self.long_term_variance/(sigma*sigma)*((beta-d)*time_to_expiry-2.0*((1.0-g*decay)/(1.0-g)).ln());
+        let d_term = (beta-d)/(sigma*sigma)*(1.0-decay)/(1.0-g*decay);
+        (drift+c+d_term*self.initial_variance).exp()
+    }
+}
+
+impl VolatilityBump for HestonModel {
+    /// Shifts both the initial volatility `sqrt(v_0)` and the long term volatility `sqrt(theta)` by `bump`, i.e. shifts the whole
+    /// term structure of the volatility.
+    fn with_volatility_bump(&self, bump: f64)->HestonModel{
+        HestonModel{
+            initial_variance: (self.initial_variance.sqrt()+bump).powi(2),
+            long_term_variance: (self.long_term_variance.sqrt()+bump).powi(2),
+            ..*self
+        }
+    }
+}
+
+/// The Merton jump diffusion model, i.e. a geometric Brownian motion with Poisson jumps whose logarithms are normally distributed.
+/// The drift is compensated so that the discounted stock is a martingale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MertonJumpDiffusionModel{
+    /// The volatility of the diffusion.
+    volatility: f64,
+    /// The expected number of jumps per unit of time.
+    jump_intensity: f64,
+    /// The mean of the logarithm of a jump.
+    jump_mean: f64,
+    /// The standard deviation of the logarithm of a jump.
+    jump_volatility: f64,
+    /// The continuous divident rate of the stock.
+    divident_rate: f64,
+}
+
+impl MertonJumpDiffusionModel {
+    /// Returns a new Merton jump diffusion model.
+    /// # Parameters
+    /// - `volatility` - The volatility of the diffusion.
+    /// - `jump_intensity` - The expected number of jumps per unit of time.
+    /// - `jump_mean` - The mean of the logarithm of a jump.
+    /// - `jump_volatility` - The standard deviation of the logarithm of a jump.
+    /// - `divident_rate` - The continuous divident rate of the stock.
+    /// # Panics
+    /// Panics if one of the parameters other than `jump_mean` is negative.
+    pub fn new(volatility: f64, jump_intensity: f64, jump_mean: f64, jump_volatility: f64, divident_rate: f64)->MertonJumpDiffusionModel{
+        if volatility < 0.0 || jump_intensity < 0.0 || jump_volatility < 0.0 || divident_rate < 0.0 {
+            panic!("One of the parameters is negative")
+        }
+        MertonJumpDiffusionModel{
+            volatility,
+            jump_intensity,
+            jump_mean,
+            jump_volatility,
+            divident_rate,
+        }
+    }
+
+    /// Returns the volatility of the diffusion.
+    pub fn get_volatility(&self)->f64{
+        self.volatility
+    }
+
+    /// Returns the expected number of jumps per unit of time.
+    pub fn get_jump_intensity(&self)->f64{
+        self.jump_intensity
+    }
+
+    /// Returns the mean of the logarithm of a jump.
+    pub fn get_jump_mean(&self)->f64{
+        self.jump_mean
+    }
+
+    /// Returns the standard deviation of the logarithm of a jump.
+    pub fn get_jump_volatility(&self)->f64{
+        self.jump_volatility
+    }
+
+    /// Returns the divident rate of the stock.
+    pub fn get_divident_rate(&self)->f64{
+        self.divident_rate
+    }
+}
+
+impl CharacteristicFunction for MertonJumpDiffusionModel {
+    fn characteristic_function(&self, u: Complex64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64)->Complex64{
+        let i = Complex64::i();
+        let jump_variance = self.jump_volatility*self.jump_volatility;
+        let mean_jump_size = (self.jump_mean+0.5*jump_variance).exp()-1.0;
+        let drift = short_rate_of_interest-self.divident_rate-0.5*self.volatility*self.volatility-self.jump_intensity*mean_jump_size;
+        let jumps = self.jump_intensity*time_to_expiry*((i*u*self.jump_mean-0.5*jump_variance*u*u).exp()-1.0);
+        (i*u*(spot.ln()+drift*time_to_expiry)-0.5*self.volatility*self.volatility*time_to_expiry*u*u+jumps).exp()
+    }
+}
+
+impl VolatilityBump for MertonJumpDiffusionModel {
+    /// Shifts the volatility of the diffusion by `bump`, keeping the jumps.
+    fn with_volatility_bump(&self, bump: f64)->MertonJumpDiffusionModel{
+        MertonJumpDiffusionModel{volatility: self.volatility+bump, ..*self}
+    }
+}
+
+/// The Variance Gamma model of Madan, Carr and Chang, in which the log of the stock is a Brownian motion with drift `theta`
+/// and volatility `sigma` evaluated at a gamma time change with unit mean rate and variance rate `nu`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VarianceGammaModel{
+    /// The volatility `sigma` of the time changed Brownian motion.
+    volatility: f64,
+    /// The variance rate `nu` of the gamma time change.
+    variance_rate: f64,
+    /// The drift `theta` of the time changed Brownian motion.
+    drift: f64,
+    /// The continuous divident rate of the stock.
+    divident_rate: f64,
+}
+
+impl VarianceGammaModel {
+    /// Returns a new Variance Gamma model.
+    /// # Parameters
+    /// - `volatility` - The volatility `sigma` of the time changed Brownian motion.
+    /// - `variance_rate` - The variance rate `nu` of the gamma time change.
+    /// - `drift` - The drift `theta` of the time changed Brownian motion, which controls the skew.
+    /// - `divident_rate` - The continuous divident rate of the stock.
+    /// # Panics
+    /// Panics if `volatility`, `variance_rate` or `divident_rate` are negative, if `variance_rate` is zero, or if
+    /// `1-drift*variance_rate-volatility^2*variance_rate/2` is not positive, in which case the stock has no finite expectation.
+    pub fn new(volatility: f64, variance_rate: f64, drift: f64, divident_rate: f64)->VarianceGammaModel{
+        if volatility < 0.0 || variance_rate < 0.0 || divident_rate < 0.0 {
+            panic!("One of the parameters is negative")
+        }
+        if variance_rate == 0.0 {
+            panic!("The variance rate must be positive.");
+        }
+        if 1.0-drift*variance_rate-0.5*volatility*volatility*variance_rate <= 0.0 {
+            panic!("The stock has no finite expectation with these parameters.");
+        }
+        VarianceGammaModel{
+            volatility,
+            variance_rate,
+            drift,
+            divident_rate,
+        }
+    }
+
+    /// Returns the volatility of the time changed Brownian motion.
+    pub fn get_volatility(&self)->f64{
+        self.volatility
+    }
+
+    /// Returns the variance rate of the gamma time change.
+    pub fn get_variance_rate(&self)->f64{
+        self.variance_rate
+    }
+
+    /// Returns the drift of the time changed Brownian motion.
+    pub fn get_drift(&self)->f64{
+        self.drift
+    }
+
+    /// Returns the divident rate of the stock.
+    pub fn get_divident_rate(&self)->f64{
+        self.divident_rate
+    }
+}
+
+impl CharacteristicFunction for VarianceGammaModel {
+    fn characteristic_function(&self, u: Complex64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64)->Complex64{
+        let i = Complex64::i();
+        let nu = self.variance_rate;
+        let sigma_squared = self.volatility*self.volatility;
+        let compensator = (1.0-self.drift*nu-0.5*sigma_squared*nu).ln()/nu;
+        let drift = i*u*(spot.ln()+(short_rate_of_interest-self.divident_rate+compensator)*time_to_expiry);
+        drift.exp()*(1.0-i*u*self.drift*nu+0.5*sigma_squared*nu*u*u).powf(-time_to_expiry/nu)
+    }
+}
+
+impl VolatilityBump for VarianceGammaModel {
+    /// Shifts the volatility `sigma` of the time changed Brownian motion by `bump`.
+    fn with_volatility_bump(&self, bump: f64)->VarianceGammaModel{
+        VarianceGammaModel{volatility: self.volatility+bump, ..*self}
+    }
+}
+
+/// The Normal Inverse Gaussian model of Barndorff-Nielsen, in which the log of the stock is a Brownian motion with drift `beta`
+/// evaluated at an inverse Gaussian time change. The tails are controlled by `alpha`, the skew by `beta` and the scale by `delta`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NormalInverseGaussianModel{
+    /// The tail heaviness `alpha`.
+    alpha: f64,
+    /// The skew `beta`.
+    beta: f64,
+    /// The scale `delta`.
+    delta: f64,
+    /// The continuous divident rate of the stock.
+    divident_rate: f64,
+}
+
+impl NormalInverseGaussianModel {
+    /// Returns a new Normal Inverse Gaussian model.
+    /// # Parameters
+    /// - `alpha` - The tail heaviness.
+    /// - `beta` - The skew.
+    /// - `delta` - The scale.
+    /// - `divident_rate` - The continuous divident rate of the stock.
+    /// # Panics
+    /// Panics if `delta` or `divident_rate` are negative, or if `alpha` is not greater than both `|beta|` and `|beta+1|`,
+    /// in which case the stock has no finite expectation.
+    pub fn new(alpha: f64, beta: f64, delta: f64, divident_rate: f64)->NormalInverseGaussianModel{
+        if delta < 0.0 || divident_rate < 0.0 {
+            panic!("One of the parameters is negative")
+        }
+        if alpha <= beta.abs() || alpha <= (beta+1.0).abs() {
+            panic!("The stock has no finite expectation with these parameters.");
+        }
+        NormalInverseGaussianModel{
+            alpha,
+            beta,
+            delta,
+            divident_rate,
+        }
+    }
+
+    /// Returns the tail heaviness.
+    pub fn get_alpha(&self)->f64{
+        self.alpha
+    }
+
+    /// Returns the skew.
+    pub fn get_beta(&self)->f64{
+        self.beta
+    }
+
+    /// Returns the scale.
+    pub fn get_delta(&self)->f64{
+        self.delta
+    }
+
+    /// Returns the divident rate of the stock.
+    pub fn get_divident_rate(&self)->f64{
+        self.divident_rate
+    }
+
+    /// Returns the drift correction which makes the discounted stock a martingale.
+    fn compensator(&self)->f64{
+        -self.delta*((self.alpha*self.alpha-self.beta*self.beta).sqrt()-(self.alpha*self.alpha-(self.beta+1.0)*(self.beta+1.0)).sqrt())
+    }
+
+    /// Returns an increment of the NIG process (without the drift of the stock) over `time_step`.
+    /// The inverse Gaussian time change is sampled with the algorithm of Michael, Schucany and Haas.
+    /// # Parameters
+    /// - `time_step` - the length of the increment.
+    /// - `gaussians` - two independent standard Gaussian samples.
+    /// - `uniform` - a uniform sample on [0,1], independent of `gaussians`.
+    pub fn sample_increment(&self, time_step: f64, gaussians: [f64; 2], uniform: f64)->f64{
+        let gamma = (self.alpha*self.alpha-self.beta*self.beta).sqrt();
+        let mean = self.delta*time_step/gamma;
+        let shape = (self.delta*time_step).powi(2);
+        let y = gaussians[0]*gaussians[0];
+        let x = mean+mean*mean*y/(2.0*shape)-mean/(2.0*shape)*(4.0*mean*shape*y+mean*mean*y*y).sqrt();
+        let time_change = if uniform <= mean/(mean+x) { x } else { mean*mean/x };
+        self.beta*time_change+time_change.sqrt()*gaussians[1]
+    }
+
+    /// Returns a path of the stock at `times` under the risk neutral measure.
+    /// # Parameters
+    /// - `spot` - the current spot of the stock.
+    /// - `short_rate_of_interest` - the short rate of interest.
+    /// - `times` - an increasing slice of positive times, measured from now.
+    /// - `rng` - the random number generator used to sample the increments.
+    pub fn generate_risk_neutral_path(&self, spot: f64, short_rate_of_interest: f64, times: &[f64], rng: &mut impl RandomNumberGeneratorTrait)
+        ->Vec<f64>{
+        let drift = short_rate_of_interest-self.divident_rate+self.compensator();
+        let mut log_spot = spot.ln();
+        let mut previous_time = 0.0;
+        times.iter().map(|t| {
+            let gaussians = rng.get_gaussians(2);
+            let uniform = rng.get_uniforms(1)[0];
+            let time_step = t-previous_time;
+            log_spot += drift*time_step+self.sample_increment(time_step, [gaussians[0], gaussians[1]], uniform);
+            previous_time = *t;
+            log_spot.exp()
+        }).collect()
+    }
+}
+
+impl CharacteristicFunction for NormalInverseGaussianModel {
+    fn characteristic_function(&self, u: Complex64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64)->Complex64{
+        let i = Complex64::i();
+        let alpha_squared = self.alpha*self.alpha;
+        let gamma = (alpha_squared-self.beta*self.beta).sqrt();
+        let drift = i*u*(spot.ln()+(short_rate_of_interest-self.divident_rate+self.compensator())*time_to_expiry);
+        let shifted = self.beta+i*u;
+        (drift+self.delta*time_to_expiry*(gamma-(alpha_squared-shifted*shifted).sqrt())).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_pricer::CarrMadanPricer;
+    use crate::raw_formulas;
+
+    fn forward(model: &impl CharacteristicFunction)->f64{
+        model.characteristic_function(-Complex64::i(), 100.0, 0.05, 0.5).re
+    }
+
+    #[test]
+    fn martingale_test(){
+        let expected = 100.0*(0.03_f64*0.5).exp();
+        assert!((forward(&BlackScholesModel::new(0.2, 0.02))-expected).abs()<1e-10);
+        assert!((forward(&HestonModel::new(0.04, 1.5, 0.05, 0.6, -0.7, 0.02))-expected).abs()<1e-10);
+        assert!((forward(&MertonJumpDiffusionModel::new(0.2, 0.5, -0.1, 0.15, 0.02))-expected).abs()<1e-10);
+        assert!((forward(&VarianceGammaModel::new(0.2, 0.3, -0.15, 0.02))-expected).abs()<1e-10);
+        assert!((forward(&NormalInverseGaussianModel::new(15.0, -5.0, 0.5, 0.02))-expected).abs()<1e-10);
+    }
+
+    #[test]
+    fn heston_small_volatility_of_variance_test(){
+        // With a tiny volatility of variance and v_0 = theta the variance stays constant, so Heston reduces to Black-Scholes.
+        let model = HestonModel::new(0.04, 2.0, 0.04, 1e-4, -0.5, 0.01);
+        let price = CarrMadanPricer::default().call_prices(&model, 100.0, 0.03, 1.0, &[110.0])[0];
+        assert!((price-raw_formulas::european_call_option_price(100.0, 110.0, 0.03, 1.0, 0.2, 0.01)).abs()<0.01);
+    }
+
+    #[test]
+    fn merton_series_test(){
+        // Merton's formula: a Poisson weighted sum of Black-Scholes prices.
+        let (volatility, intensity, jump_mean, jump_volatility) = (0.2, 0.8, -0.1, 0.2);
+        let model = MertonJumpDiffusionModel::new(volatility, intensity, jump_mean, jump_volatility, 0.0);
+        let price = CarrMadanPricer::default().call_prices(&model, 100.0, 0.05, 1.0, &[100.0])[0];
+        let mean_jump_size = f64::exp(jump_mean+0.5*jump_volatility*jump_volatility)-1.0;
+        let adjusted_intensity = intensity*(1.0+mean_jump_size);
+        let mut series = 0.0;
+        let mut weight = (-adjusted_intensity).exp();
+        for n in 0..50{
+            if n > 0 {
+                weight *= adjusted_intensity/n as f64;
+            }
+            let n = n as f64;
+            let vol = (volatility*volatility+n*jump_volatility*jump_volatility).sqrt();
+            let rate = 0.05-intensity*mean_jump_size+n*(jump_mean+0.5*jump_volatility*jump_volatility);
+            series += weight*raw_formulas::european_call_option_price(100.0, 100.0, rate, 1.0, vol, 0.0);
+        }
+        assert!((price-series).abs()<0.01);
+    }
+
+    #[test]
+    fn heston_zero_variance_test(){
+        // With v_0 = theta = 0 the variance stays 0, and the stock grows at the rate of interest.
+        let model = HestonModel::new(0.0, 1.5, 0.0, 0.5, -0.7, 0.0);
+        let mut rng = crate::random_number_generator::RandomNumberGenerator::new(Some(5));
+        for scheme in [HestonScheme::QuadraticExponential, HestonScheme::FullTruncationEuler, HestonScheme::ExactVariance]{
+            let path = model.with_scheme(scheme).generate_risk_neutral_path(100.0, 0.05, &[0.5, 1.0], 0.25, &mut rng);
+            assert!((path[1]-100.0*0.05_f64.exp()).abs()<1e-10);
+        }
+    }
+
+    #[test]
+    fn heston_schemes_test(){
+        // A high volatility of the variance and a strongly negative correlation, with 8 steps per year.
+        let model = HestonModel::new(0.04, 0.5, 0.04, 1.0, -0.9, 0.0);
+        assert_eq!(model.get_scheme(), HestonScheme::QuadraticExponential);
+        let reference = CarrMadanPricer::default().call_prices(&model, 100.0, 0.0, 1.0, &[100.0])[0];
+        let monte_carlo = |scheme| {
+            let model = model.with_scheme(scheme);
+            let mut rng = crate::random_number_generator::RandomNumberGenerator::new(Some(3));
+            let number_of_paths = 50000;
+            let (mut mean, mut call) = (0.0, 0.0);
+            for _ in 0..number_of_paths{
+                let spot = model.generate_risk_neutral_path(100.0, 0.0, &[0.5, 1.0], 0.125, &mut rng)[1];
+                mean += spot/number_of_paths as f64;
+                call += f64::max(spot-100.0, 0.0)/number_of_paths as f64;
+            }
+            (mean, call)
+        };
+        let (mean, call) = monte_carlo(HestonScheme::QuadraticExponential);
+        assert!((mean-100.0).abs() < 0.3);
+        // The standard error of the call is about 0.02.
+        assert!((call-reference).abs() < 0.06);
+        let (_, call) = monte_carlo(HestonScheme::FullTruncationEuler);
+        assert!(call-reference > 1.0);
+        let (mean, call) = monte_carlo(HestonScheme::ExactVariance);
+        assert!((mean-100.0).abs() < 0.3);
+        assert!((call-reference).abs() < 0.06);
+    }
+
+    #[test]
+    fn normal_inverse_gaussian_monte_carlo_test(){
+        let model = NormalInverseGaussianModel::new(15.0, -5.0, 0.5, 0.02);
+        let mut rng = crate::random_number_generator::RandomNumberGenerator::new(Some(7));
+        let number_of_paths = 100000;
+        let mut mean = 0.0;
+        let mut call = 0.0;
+        for _ in 0..number_of_paths{
+            let path = model.generate_risk_neutral_path(100.0, 0.05, &[0.25, 0.5, 1.0], &mut rng);
+            mean += path[2]/number_of_paths as f64;
+            call += f64::max(path[2]-100.0, 0.0)/number_of_paths as f64;
+        }
+        assert!((mean-100.0*0.03_f64.exp()).abs()<0.2);
+        let fft = CarrMadanPricer::default().call_prices(&model, 100.0, 0.05, 1.0, &[100.0])[0];
+        assert!((call*(-0.05_f64).exp()-fft).abs()<0.1);
+    }
+}
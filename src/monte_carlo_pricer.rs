@@ -1,5 +1,8 @@
 //! Provides Monte Carlo pricers for various types of derivative options.
 //! Currently implements a Monte Carlo pricer only for vanilla options
+//!
+//! `MonteCarloEngine` bundles the number of paths, the seed, the variance reduction and the parallelism of a run in one
+//! `McConfig`, so that a run can be reproduced from its configuration.
 
 /* 
 use crate::random_number_generator::{RandomNumberGenerator, RandomNumberGeneratorTrait};
@@ -9,9 +12,44 @@ use crate::utils::{NonNegativeFloat, TimeStamp};
 use crate::stock::Stock;
 */
 
-use crate::option::{DerivativeOption, Underlying};
-use crate::random_number_generator::RandomNumberGeneratorTrait;
-use crate::statistics_gatherer::StatisticsGathererTrait;
+use crate::error::PricingError;
+use crate::halton_generator::{HaltonGenerator, HaltonRandomization};
+use crate::option::{DerivativeOption, PathPayoff, Underlying};
+use crate::path_generator::{Path, PathGenerator};
+use crate::random_number_generator::{RandomNumberGenerator, RandomNumberGeneratorState, RandomNumberGeneratorTrait, SubstreamGenerator};
+use crate::statistics_gatherer::{MeanVarianceStatisticsGatherer, PathStoringGatherer, StatisticsGathererTrait};
+
+/// The callback of a `MonteCarloProgress` hook.
+type ProgressCallback<'a> = Box<dyn FnMut(usize, &dyn StatisticsGathererTrait)->bool+'a>;
+
+/// A progress hook for long Monte Carlo simulations, called every `interval` paths with the number of completed paths and
+/// the statistics gatherer, e.g. to display the current estimate. The simulation is cancelled if the callback returns `false`.
+pub struct MonteCarloProgress<'a>{
+    /// The number of paths between calls of the callback.
+    interval: usize,
+    /// The callback.
+    callback: ProgressCallback<'a>,
+}
+
+impl<'a> MonteCarloProgress<'a> {
+    /// Returns a new progress hook which calls `callback` every `interval` paths.
+    /// # Panics
+    /// Panics if `interval` is zero.
+    pub fn new(interval: usize, callback: impl FnMut(usize, &dyn StatisticsGathererTrait)->bool+'a)->MonteCarloProgress<'a>{
+        if interval == 0 {
+            panic!("The progress interval must be positive.");
+        }
+        MonteCarloProgress{
+            interval,
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Returns the number of paths between calls of the callback.
+    pub fn get_interval(&self)->usize{
+        self.interval
+    }
+}
 
 /// A Monte Carlo Simulator.
 /// 
@@ -19,25 +57,349 @@ use crate::statistics_gatherer::StatisticsGathererTrait;
 /// 
 /// - `option` - A `DerivativeOption`, as defined in the `option` module.
 /// - `gatherer` - A mutable object implementing the `StatisticsGathererTrait` trait described in the `statistics_gatherer` module.
-///     This will be used to output the results of the Monte Carlo simulation.
+///   This will be used to output the results of the Monte Carlo simulation.
 /// - `r` - the short rate of interest.
 /// - `rng` - an object implementing the `RandomNumberGeneratorTrait`, such as `RandomNumberGenerator`. Both are descrived in the `random-number_generator` module.
 /// - `number_of_paths` - The number of trials in the simulation.
 /// 
+/// An option at or past expiry whose settlement value is known (see `DerivativeOption::settlement_value`) contributes its
+/// settlement value to every path, without drawing random samples.
+/// 
 /// # Panics
 /// 
-/// The function panics if `option.expiry - evaluation_time` is negative.
+/// The function panics if the option expired and its settlement value is unknown.
 pub fn monte_carlo_simulation<T>(option: &impl DerivativeOption<T>, gatherer: &mut impl StatisticsGathererTrait, r: f64, rng: &mut impl RandomNumberGeneratorTrait, 
     number_of_paths: usize)
 where T: Underlying{
-    let tau= option.get_time_to_expiry().expect("The option expiered!");
-    let discount_factor = f64::exp(-r*f64::from(tau));
+    try_monte_carlo_simulation(option, gatherer, r, rng, number_of_paths).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Same as `monte_carlo_simulation`, but returns `PricingError::ExpiredOption` instead of panicking if the option expired
+/// and its settlement value is unknown, e.g. so that batch jobs can skip such options.
+pub fn try_monte_carlo_simulation<T>(option: &impl DerivativeOption<T>, gatherer: &mut impl StatisticsGathererTrait, r: f64,
+    rng: &mut impl RandomNumberGeneratorTrait, number_of_paths: usize)->Result<(), PricingError>
+where T: Underlying{
+    simulate_paths(option, gatherer, r, rng, number_of_paths, None).map(|_| ())
+}
+
+/// Same as `monte_carlo_simulation`, but calls the `progress` hook every `progress.get_interval()` paths, which reports the
+/// progress of the simulation and can cancel it.
+/// 
+/// Returns the number of completed paths, which is less than `number_of_paths` only if the simulation was cancelled.
+/// 
+/// # Panics
+/// 
+/// The function panics if the option expired and its settlement value is unknown.
+pub fn monte_carlo_simulation_with_progress<T>(option: &impl DerivativeOption<T>, gatherer: &mut impl StatisticsGathererTrait, r: f64,
+    rng: &mut impl RandomNumberGeneratorTrait, number_of_paths: usize, progress: &mut MonteCarloProgress)->usize
+where T: Underlying{
+    try_monte_carlo_simulation_with_progress(option, gatherer, r, rng, number_of_paths, progress).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Same as `monte_carlo_simulation_with_progress`, but returns `PricingError::ExpiredOption` instead of panicking if the option
+/// expired and its settlement value is unknown.
+pub fn try_monte_carlo_simulation_with_progress<T>(option: &impl DerivativeOption<T>, gatherer: &mut impl StatisticsGathererTrait, r: f64,
+    rng: &mut impl RandomNumberGeneratorTrait, number_of_paths: usize, progress: &mut MonteCarloProgress)->Result<usize, PricingError>
+where T: Underlying{
+    simulate_paths(option, gatherer, r, rng, number_of_paths, Some(progress))
+}
+
+/// Runs the simulation of `try_monte_carlo_simulation_with_progress`, with an optional progress hook, and returns the number of
+/// completed paths.
+fn simulate_paths<T>(option: &impl DerivativeOption<T>, gatherer: &mut impl StatisticsGathererTrait, r: f64,
+    rng: &mut impl RandomNumberGeneratorTrait, number_of_paths: usize, mut progress: Option<&mut MonteCarloProgress>)->Result<usize, PricingError>
+where T: Underlying{
+    let (settlement_value, discount_factor) = settlement_and_discount_factor(option, r)?;
+    for path in 1..=number_of_paths{
+        let result = match settlement_value {
+            Some(value) => value,
+            None => discount_factor*option.price_path(&rng.get_gaussians(option.get_dimensionality()), r),
+        };
+        gatherer.dump_one_result(result);
+        if let Some(progress) = progress.as_mut() {
+            if path%progress.interval == 0 && !(progress.callback)(path, &*gatherer) {
+                return Ok(path);
+            }
+        }
+    }
+    Ok(number_of_paths)
+}
+
+/// Returns the settlement value of an option at or past expiry, if it is known, and the discount factor of the payoff of the option.
+/// Returns `PricingError::ExpiredOption` if the option expired and its settlement value is unknown.
+fn settlement_and_discount_factor<T, O>(option: &O, r: f64)->Result<(Option<f64>, f64), PricingError>
+where T: Underlying, O: DerivativeOption<T>+?Sized{
+    match option.get_time_to_expiry() {
+        None => Ok((Some(option.settlement_value(r)?), 1.0)),
+        Some(tau) if f64::from(tau) == 0.0 => Ok((option.settlement_value(r).ok(), 1.0)),
+        Some(tau) => Ok((None, f64::exp(-r*f64::from(tau)))),
+    }
+}
+
+/// A Monte Carlo simulator which also records the simulated paths of the underlying in a `PathStoringGatherer`, until it is full.
+/// The paths are generated with `DerivativeOption::simulate_path` from the same random samples as the payoffs.
+/// 
+/// # Parameters
+/// 
+/// - `option` - A `DerivativeOption`, as defined in the `option` module.
+/// - `gatherer` - The `PathStoringGatherer` which collects the results and the paths.
+/// - `r` - the short rate of interest.
+/// - `rng` - an object implementing the `RandomNumberGeneratorTrait`.
+/// - `number_of_paths` - The number of trials in the simulation.
+/// 
+/// # Panics
+/// 
+/// The function panics if the option expired and its settlement value is unknown.
+pub fn monte_carlo_simulation_storing_paths<T>(option: &impl DerivativeOption<T>, gatherer: &mut PathStoringGatherer, r: f64,
+    rng: &mut impl RandomNumberGeneratorTrait, number_of_paths: usize)
+where T: Underlying{
+    let (settlement_value, discount_factor) = settlement_and_discount_factor(option, r).unwrap_or_else(|e| panic!("{}", e));
+    if let Some(value) = settlement_value {
+        (0..number_of_paths).for_each(|_| gatherer.dump_one_result(value));
+        return;
+    }
     for _ in 0..number_of_paths{
-        gatherer.dump_one_result(discount_factor*option.price_path(&rng.get_gaussians(option.get_dimensionality()), r));
+        let random_samples = rng.get_gaussians(option.get_dimensionality());
+        let result = discount_factor*option.price_path(&random_samples, r);
+        if gatherer.is_full() {
+            gatherer.dump_one_result(result);
+        }
+        else {
+            gatherer.dump_one_path(option.simulate_path(&random_samples, r), result);
+        }
     }
 }
 
-/// A function that returnes the value of the given option.
+/// A Monte Carlo simulator pricing an option with a path payoff on the paths of any `PathGenerator`, e.g. a vanilla or an asian
+/// option on a `HestonStock`. The underlying of the option only provides the recorded history and the current time; the
+/// future values come from the generator, which should start from the same state.
+///
+/// # Parameters
+///
+/// - `option` - An option implementing `PathPayoff`, as defined in the `option` module.
+/// - `generator` - The `PathGenerator` of the paths of the underlying.
+/// - `gatherer` - A mutable object implementing the `StatisticsGathererTrait` trait, which collects the discounted payoffs.
+/// - `r` - the short rate of interest.
+/// - `rng` - an object implementing the `RandomNumberGeneratorTrait`.
+/// - `number_of_paths` - The number of trials in the simulation.
+///
+/// An option exactly at its payment time contributes its settlement value, the payoff of the current value of the generator at
+/// the remaining times of its schedule, to every path, without drawing random samples.
+///
+/// # Panics
+///
+/// Panics if the payment time of the option is before the current time of the generator, or if the schedule of the option
+/// starts before it.
+pub fn monte_carlo_simulation_with_generator(option: &impl PathPayoff, generator: &impl PathGenerator,
+    gatherer: &mut impl StatisticsGathererTrait, r: f64, rng: &mut impl RandomNumberGeneratorTrait, number_of_paths: usize){
+    try_monte_carlo_simulation_with_generator(option, generator, gatherer, r, rng, number_of_paths).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Same as `monte_carlo_simulation_with_generator`, but returns `PricingError::ExpiredOption` instead of panicking if the
+/// payment time of the option is before the current time of the generator.
+pub fn try_monte_carlo_simulation_with_generator(option: &impl PathPayoff, generator: &impl PathGenerator,
+    gatherer: &mut impl StatisticsGathererTrait, r: f64, rng: &mut impl RandomNumberGeneratorTrait, number_of_paths: usize)
+    ->Result<(), PricingError>{
+    let time_to_payment = f64::from(option.get_payment_time().checked_duration_since(generator.get_current_time())
+        .ok_or(PricingError::ExpiredOption)?);
+    let schedule = option.get_schedule();
+    if time_to_payment == 0.0 {
+        let path = Path::new(schedule.clone(), vec![generator.get_current_value(); schedule.len()]);
+        let settlement_value = option.path_payoff(&path, r);
+        (0..number_of_paths).for_each(|_| gatherer.dump_one_result(settlement_value));
+        return Ok(());
+    }
+    let discount_factor = f64::exp(-r*time_to_payment);
+    for _ in 0..number_of_paths{
+        let path = generator.generate(rng, &schedule, r);
+        gatherer.dump_one_result(discount_factor*option.path_payoff(&path, r));
+    }
+    Ok(())
+}
+
+/// The prices of several options estimated on shared paths by `monte_carlo_simulation_multi`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiOptionResult{
+    /// The estimated prices of the options.
+    prices: Vec<f64>,
+    /// The covariance matrix of the estimated prices.
+    covariance: Vec<Vec<f64>>,
+}
+
+impl MultiOptionResult {
+    /// Returns the estimated prices of the options, in the order they were given.
+    pub fn get_prices(&self)->&[f64]{
+        &self.prices
+    }
+
+    /// Returns the covariance matrix of the estimated prices (i.e. the covariance matrix of the discounted payoffs divided by
+    /// the number of paths). Since the paths are shared, the errors of the prices are strongly correlated, which makes
+    /// differences of prices (e.g. of call spreads) much more accurate than the individual prices.
+    pub fn get_covariance(&self)->&[Vec<f64>]{
+        &self.covariance
+    }
+
+    /// Returns the standard errors of the estimated prices.
+    pub fn get_standard_errors(&self)->Vec<f64>{
+        (0..self.prices.len()).map(|i| self.covariance[i][i].sqrt()).collect()
+    }
+}
+
+/// A Monte Carlo simulator for several options at once, which draws the random samples of each path once and evaluates the
+/// payoffs of all the options on them. Options on the same underlying which use the random samples in the same way (e.g. a
+/// chain of vanilla options with the same expiry) are thus priced on exactly the same simulated paths. Every option uses the
+/// first `get_dimensionality()` samples of each path.
+/// 
+/// # Parameters
+/// 
+/// - `options` - The options, as defined in the `option` module.
+/// - `r` - the short rate of interest.
+/// - `rng` - an object implementing the `RandomNumberGeneratorTrait`.
+/// - `number_of_paths` - The number of trials in the simulation.
+/// 
+/// # Panics
+/// 
+/// The function panics if one of the options expired and its settlement value is unknown.
+pub fn monte_carlo_simulation_multi<T>(options: &[&dyn DerivativeOption<T>], r: f64, rng: &mut impl RandomNumberGeneratorTrait,
+    number_of_paths: usize)->MultiOptionResult
+where T: Underlying{
+    let settlements: Vec<(Option<f64>, f64)> = options.iter()
+        .map(|option| settlement_and_discount_factor(*option, r).unwrap_or_else(|e| panic!("{}", e))).collect();
+    let dimensionality = options.iter().map(|option| option.get_dimensionality()).max().unwrap_or(0);
+    let n = options.len();
+    let mut means = vec![0.0; n];
+    // Welford's streaming update of the means and of the sums of products of the deviations from them.
+    let mut comoments = vec![vec![0.0; n]; n];
+    for path in 1..=number_of_paths{
+        let random_samples = rng.get_gaussians(dimensionality);
+        let results: Vec<f64> = options.iter().zip(&settlements)
+            .map(|(option, (settlement_value, discount_factor))| match settlement_value {
+                Some(value) => *value,
+                None => discount_factor*option.price_path(&random_samples, r),
+            }).collect();
+        let old_deviations: Vec<f64> = results.iter().zip(&means).map(|(x, m)| x-m).collect();
+        for (mean, deviation) in means.iter_mut().zip(&old_deviations) {
+            *mean += deviation/path as f64;
+        }
+        for (row, old_deviation) in comoments.iter_mut().zip(&old_deviations) {
+            for ((comoment, x), mean) in row.iter_mut().zip(&results).zip(&means) {
+                *comoment += old_deviation*(x-mean);
+            }
+        }
+    }
+    let denominator = if number_of_paths > 1 { ((number_of_paths-1)*number_of_paths) as f64 } else { f64::INFINITY };
+    MultiOptionResult{
+        prices: means,
+        covariance: comoments.into_iter().map(|row| row.into_iter().map(|c| c/denominator).collect()).collect(),
+    }
+}
+
+/// The result of a Monte Carlo valuation, with the information needed to judge its accuracy and to reproduce it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct McResult{
+    /// The estimated price.
+    price: f64,
+    /// The standard error of the estimated price.
+    std_error: f64,
+    /// The 95% confidence interval of the price.
+    confidence_interval: (f64, f64),
+    /// The number of simulated paths.
+    paths: usize,
+    /// The seed of the random number generator.
+    seed: u64,
+    /// The number of independent batches, or 1 if the paths were not batched.
+    batches: usize,
+    /// The standard error of the price estimated from the spread of the batch means, if there are at least two batches.
+    batch_std_error: Option<f64>,
+}
+
+impl McResult {
+    /// Returns a result with the given price and standard error, and the corresponding 95% confidence interval.
+    pub(crate) fn new(price: f64, std_error: f64, paths: usize, seed: u64)->McResult{
+        McResult{
+            price,
+            std_error,
+            confidence_interval: (price-1.96*std_error, price+1.96*std_error),
+            paths,
+            seed,
+            batches: 1,
+            batch_std_error: None,
+        }
+    }
+
+    /// Returns the result with the standard error `batch_std_error` estimated from `batches` independent batches.
+    pub(crate) fn with_batch_std_error(self, batches: usize, batch_std_error: f64)->McResult{
+        McResult{batches, batch_std_error: Some(batch_std_error), ..self}
+    }
+
+    /// Returns the estimated price.
+    pub fn get_price(&self)->f64{
+        self.price
+    }
+
+    /// Returns the standard error of the estimated price.
+    pub fn get_std_error(&self)->f64{
+        self.std_error
+    }
+
+    /// Returns the 95% confidence interval of the price, i.e. the price plus or minus 1.96 standard errors.
+    pub fn get_confidence_interval(&self)->(f64, f64){
+        self.confidence_interval
+    }
+
+    /// Returns the number of simulated paths.
+    pub fn get_paths(&self)->usize{
+        self.paths
+    }
+
+    /// Returns the seed of the random number generator, with which the valuation can be reproduced (even if it was run
+    /// with a random seed).
+    pub fn get_seed(&self)->u64{
+        self.seed
+    }
+
+    /// Returns the number of independent batches of the valuation, or 1 if the paths were not batched.
+    pub fn get_batches(&self)->usize{
+        self.batches
+    }
+
+    /// Returns the standard error estimated from the spread of the means of the independent batches, or `None` if the paths
+    /// were not batched. Unlike `get_std_error`, it does not assume the paths are independent, so it is also valid for quasi
+    /// random samples.
+    pub fn get_batch_std_error(&self)->Option<f64>{
+        self.batch_std_error
+    }
+
+    /// Returns the 95% confidence interval of the price from the batch means, i.e. the price plus or minus the batch standard
+    /// error times the 97.5% quantile of the Student t distribution with one degree of freedom less than the number of batches,
+    /// or `None` if the paths were not batched.
+    pub fn get_batch_confidence_interval(&self)->Option<(f64, f64)>{
+        self.batch_std_error.map(|error| {
+            let half_width = student_t_quantile_975(self.batches-1)*error;
+            (self.price-half_width, self.price+half_width)
+        })
+    }
+}
+
+/// Returns the 97.5% quantile of the Student t distribution with `degrees_of_freedom` degrees of freedom, from a table up to
+/// 30 degrees of freedom and from the Cornish-Fisher expansion around the normal quantile above.
+fn student_t_quantile_975(degrees_of_freedom: usize)->f64{
+    const TABLE: [f64; 30] = [12.7062, 4.3027, 3.1824, 2.7764, 2.5706, 2.4469, 2.3646, 2.3060, 2.2622, 2.2281, 2.2010, 2.1788,
+        2.1604, 2.1448, 2.1314, 2.1199, 2.1098, 2.1009, 2.0930, 2.0860, 2.0796, 2.0739, 2.0687, 2.0639, 2.0595, 2.0555, 2.0518,
+        2.0484, 2.0452, 2.0423];
+    if degrees_of_freedom == 0 {
+        return f64::INFINITY;
+    }
+    if degrees_of_freedom <= TABLE.len() {
+        return TABLE[degrees_of_freedom-1];
+    }
+    let z: f64 = 1.959963984540054;
+    let nu = degrees_of_freedom as f64;
+    z+(z.powi(3)+z)/(4.0*nu)+(5.0*z.powi(5)+16.0*z.powi(3)+3.0*z)/(96.0*nu*nu)
+        +(3.0*z.powi(7)+19.0*z.powi(5)+17.0*z.powi(3)-15.0*z)/(384.0*nu.powi(3))
+}
+
+/// A function that returnes the value of the given option, together with its standard error and the seed used.
 /// A wraper function for `monte_carlo_simulation` that does not require creating a statistics gatherer and random number generator.
 /// 
 /// # Parameters
@@ -46,19 +408,619 @@ where T: Underlying{
 /// - `r` - the short rate of interest.
 /// - `seed` - An optional seed for the random number generation. If `None`, a random seed will be used.
 /// - `number_of_paths` - The number of trials in the simulation.
-pub fn monte_carlo_pricer<T>(option: &impl DerivativeOption<T>, r: f64, seed: Option<u64>, number_of_paths: usize)->f64
+/// 
+/// # Panics
+/// 
+/// The function panics if the option expired and its settlement value is unknown.
+pub fn monte_carlo_pricer_with_error<T>(option: &impl DerivativeOption<T>, r: f64, seed: Option<u64>, number_of_paths: usize)->McResult
 where T: Underlying{
-    let mut sg = crate::statistics_gatherer::MeanStatisticsGatherer::new();
-    let mut rng = crate::random_number_generator::RandomNumberGenerator::new(seed);
+    let mut sg = MeanVarianceStatisticsGatherer::new();
+    let mut rng = RandomNumberGenerator::new(seed);
     monte_carlo_simulation(option, &mut sg, r, &mut rng, number_of_paths);
-    sg.get_results_so_far()[0][0]
+    McResult::new(sg.get_mean(), sg.get_standard_error(), number_of_paths, rng.get_seed())
+}
+
+/// Same as `monte_carlo_pricer_with_error`, but returns `PricingError::ExpiredOption` instead of panicking if the option expired
+/// and its settlement value is unknown.
+pub fn try_monte_carlo_pricer_with_error<T>(option: &impl DerivativeOption<T>, r: f64, seed: Option<u64>, number_of_paths: usize)
+    ->Result<McResult, PricingError>
+where T: Underlying{
+    let mut sg = MeanVarianceStatisticsGatherer::new();
+    let mut rng = RandomNumberGenerator::new(seed);
+    try_monte_carlo_simulation(option, &mut sg, r, &mut rng, number_of_paths)?;
+    Ok(McResult::new(sg.get_mean(), sg.get_standard_error(), number_of_paths, rng.get_seed()))
+}
+
+/// A function that returnes the value of the given option.
+/// A thin wraper for `monte_carlo_pricer_with_error` which only returns the price.
+/// 
+/// # Parameters
+/// 
+/// - `option` - A `DerivativeOption`, as defined in the `option` module.
+/// - `r` - the short rate of interest.
+/// - `seed` - An optional seed for the random number generation. If `None`, a random seed will be used.
+/// - `number_of_paths` - The number of trials in the simulation.
+pub fn monte_carlo_pricer<T>(option: &impl DerivativeOption<T>, r: f64, seed: Option<u64>, number_of_paths: usize)->f64
+where T: Underlying{
+    monte_carlo_pricer_with_error(option, r, seed, number_of_paths).get_price()
+}
+
+/// Same as `monte_carlo_pricer`, but returns `PricingError::ExpiredOption` instead of panicking if the option expired
+/// and its settlement value is unknown.
+pub fn try_monte_carlo_pricer<T>(option: &impl DerivativeOption<T>, r: f64, seed: Option<u64>, number_of_paths: usize)->Result<f64, PricingError>
+where T: Underlying{
+    Ok(try_monte_carlo_pricer_with_error(option, r, seed, number_of_paths)?.get_price())
+}
+
+/// The state of a Monte Carlo simulation after some of its paths, from which the simulation can be resumed with
+/// bit-identical results.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonteCarloCheckpoint<G>{
+    /// The statistics gatherer, holding the results of the completed paths.
+    gatherer: G,
+    /// The state of the random number generator after the completed paths.
+    rng_state: RandomNumberGeneratorState,
+    /// The number of completed paths.
+    completed_paths: usize,
+}
+
+impl<G: StatisticsGathererTrait> MonteCarloCheckpoint<G> {
+    /// Returns the checkpoint of a simulation which has not started yet, with the given (usually empty) gatherer and generator.
+    pub fn new(gatherer: G, rng: &RandomNumberGenerator)->MonteCarloCheckpoint<G>{
+        MonteCarloCheckpoint{
+            gatherer,
+            rng_state: rng.state(),
+            completed_paths: 0,
+        }
+    }
+
+    /// Returns the statistics gatherer.
+    pub fn get_gatherer(&self)->&G{
+        &self.gatherer
+    }
+
+    /// Returns the state of the random number generator.
+    pub fn get_rng_state(&self)->RandomNumberGeneratorState{
+        self.rng_state
+    }
+
+    /// Returns the number of completed paths.
+    pub fn get_completed_paths(&self)->usize{
+        self.completed_paths
+    }
+}
+
+/// A Monte Carlo simulator which saves a checkpoint every `checkpoint_interval` paths, so that a long simulation can be
+/// resumed after an interruption. Running the simulation from a checkpoint gives bit-identical results to an uninterrupted run.
+///
+/// # Parameters
+///
+/// - `option` - A `DerivativeOption`, as defined in the `option` module.
+/// - `checkpoint` - The checkpoint to start from, created with `MonteCarloCheckpoint::new` for a new simulation. It is updated
+///   after every `checkpoint_interval` paths.
+/// - `r` - the short rate of interest.
+/// - `number_of_paths` - The total number of trials in the simulation, including those already completed.
+/// - `checkpoint_interval` - The number of paths between checkpoints.
+/// - `on_checkpoint` - Called with every new checkpoint, e.g. to save it. The simulation stops if it returns `false`.
+///
+/// Returns `true` if all the paths were completed, and `false` if the simulation was stopped by `on_checkpoint`.
+///
+/// # Panics
+///
+/// The function panics if the option expired and its settlement value is unknown, or if `checkpoint_interval` is zero.
+pub fn monte_carlo_simulation_with_checkpoints<T, G>(option: &impl DerivativeOption<T>, checkpoint: &mut MonteCarloCheckpoint<G>, r: f64,
+    number_of_paths: usize, checkpoint_interval: usize, mut on_checkpoint: impl FnMut(&MonteCarloCheckpoint<G>)->bool)->bool
+where T: Underlying, G: StatisticsGathererTrait{
+    if checkpoint_interval == 0 {
+        panic!("The checkpoint interval must be positive.");
+    }
+    let mut rng = RandomNumberGenerator::new(Some(0));
+    rng.restore(&checkpoint.rng_state);
+    while checkpoint.completed_paths < number_of_paths{
+        let paths = checkpoint_interval.min(number_of_paths-checkpoint.completed_paths);
+        monte_carlo_simulation(option, &mut checkpoint.gatherer, r, &mut rng, paths);
+        checkpoint.completed_paths += paths;
+        checkpoint.rng_state = rng.state();
+        if !on_checkpoint(checkpoint) {
+            return false;
+        }
+    }
+    true
+}
+
+/// The configuration of a `MonteCarloEngine`, created with `McConfig::new` and adjusted with the `with_*` methods. A run with a
+/// given seed is reproducible from its configuration alone, whatever the number of threads. With the `io` feature the configuration
+/// can be serialized with serde, e.g. to save it next to the results. A deserialized configuration is checked as by the `with_*`
+/// methods, and fails to deserialize if it has no threads, empty blocks or less than two batches.
+///
+/// The paths are simulated in blocks of `block_size` paths. Block `k` uses substream `k` of the seed (or the next stretch of the
+/// Halton sequence), the blocks are spread over the threads and their statistics are combined in order, so that the threads do
+/// not change the result.
+///
+/// With `number_of_batches` set, the paths are split into that many independent batches of blocks, each quasi random batch with
+/// its own scrambling of the Halton sequence, and the spread of the batch means gives a standard error which does not rely on
+/// the paths being independent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "io", derive(serde::Serialize, serde::Deserialize), serde(try_from = "UncheckedMcConfig"))]
+pub struct McConfig{
+    /// The number of paths, or the maximal number of paths if `tolerance` is set. With antithetic variates every path is a pair
+    /// of antithetic paths.
+    number_of_paths: usize,
+    /// The seed of the random numbers, or `None` for a random seed.
+    seed: Option<u64>,
+    /// Whether every path is averaged with its antithetic path, simulated from the negated random samples.
+    antithetic: bool,
+    /// Whether the random samples are taken from an Owen-scrambled Halton sequence instead of a pseudo random generator.
+    quasi_random: bool,
+    /// Whether the control passed to `MonteCarloEngine::price_with_control_variate` is used.
+    control_variate: bool,
+    /// The number of threads simulating blocks in parallel.
+    number_of_threads: usize,
+    /// The standard error at which the simulation stops, checked after every block, or `None` to simulate all the paths.
+    tolerance: Option<f64>,
+    /// The number of paths in a block.
+    block_size: usize,
+    /// The number of independent batches, or `None` if the paths are not batched.
+    number_of_batches: Option<usize>,
+}
+
+/// A deserialized `McConfig`, before it is checked.
+#[cfg(feature = "io")]
+#[derive(serde::Deserialize)]
+struct UncheckedMcConfig{
+    number_of_paths: usize,
+    seed: Option<u64>,
+    antithetic: bool,
+    quasi_random: bool,
+    control_variate: bool,
+    number_of_threads: usize,
+    tolerance: Option<f64>,
+    block_size: usize,
+    number_of_batches: Option<usize>,
+}
+
+#[cfg(feature = "io")]
+impl TryFrom<UncheckedMcConfig> for McConfig {
+    type Error = PricingError;
+
+    /// Returns the configuration, or `PricingError::InvalidState` if it has no threads, empty blocks or less than two batches.
+    fn try_from(config: UncheckedMcConfig)->Result<McConfig, PricingError>{
+        if config.number_of_threads == 0 || config.block_size == 0 || config.number_of_batches.is_some_and(|batches| batches < 2) {
+            return Err(PricingError::InvalidState);
+        }
+        Ok(McConfig{
+            number_of_paths: config.number_of_paths,
+            seed: config.seed,
+            antithetic: config.antithetic,
+            quasi_random: config.quasi_random,
+            control_variate: config.control_variate,
+            number_of_threads: config.number_of_threads,
+            tolerance: config.tolerance,
+            block_size: config.block_size,
+            number_of_batches: config.number_of_batches,
+        })
+    }
+}
+
+impl Default for McConfig {
+    /// Returns the configuration of 100000 paths with a random seed, without variance reduction, in blocks of 10000 paths on one thread.
+    fn default() -> Self {
+        McConfig{
+            number_of_paths: 100000,
+            seed: None,
+            antithetic: false,
+            quasi_random: false,
+            control_variate: false,
+            number_of_threads: 1,
+            tolerance: None,
+            block_size: 10000,
+            number_of_batches: None,
+        }
+    }
 }
- 
+
+impl McConfig {
+    /// Returns the default configuration with `number_of_paths` paths.
+    pub fn new(number_of_paths: usize)->McConfig{
+        McConfig{number_of_paths, ..McConfig::default()}
+    }
+
+    /// Returns the configuration with the seed `seed`.
+    pub fn with_seed(self, seed: u64)->McConfig{
+        McConfig{seed: Some(seed), ..self}
+    }
+
+    /// Returns the configuration with antithetic variates turned on or off.
+    pub fn with_antithetic(self, antithetic: bool)->McConfig{
+        McConfig{antithetic, ..self}
+    }
+
+    /// Returns the configuration with quasi random (scrambled Halton) samples turned on or off. The standard error of a quasi random
+    /// run is estimated as for pseudo random samples, which overestimates it.
+    pub fn with_quasi_random(self, quasi_random: bool)->McConfig{
+        McConfig{quasi_random, ..self}
+    }
+
+    /// Returns the configuration with the control variate turned on or off.
+    pub fn with_control_variate(self, control_variate: bool)->McConfig{
+        McConfig{control_variate, ..self}
+    }
+
+    /// Returns the configuration with `number_of_threads` threads.
+    /// # Panics
+    /// Panics if `number_of_threads` is zero.
+    pub fn with_number_of_threads(self, number_of_threads: usize)->McConfig{
+        if number_of_threads == 0 {
+            panic!("The number of threads must be positive.");
+        }
+        McConfig{number_of_threads, ..self}
+    }
+
+    /// Returns the configuration which stops the simulation once the standard error is at most `tolerance`.
+    pub fn with_tolerance(self, tolerance: f64)->McConfig{
+        McConfig{tolerance: Some(tolerance), ..self}
+    }
+
+    /// Returns the configuration with blocks of `block_size` paths.
+    /// # Panics
+    /// Panics if `block_size` is zero.
+    pub fn with_block_size(self, block_size: usize)->McConfig{
+        if block_size == 0 {
+            panic!("The block size must be positive.");
+        }
+        McConfig{block_size, ..self}
+    }
+
+    /// Returns the configuration with the paths split into `number_of_batches` independent batches. With batches, the tolerance
+    /// is checked against the batch standard error after every batch.
+    /// # Panics
+    /// Panics if `number_of_batches` is less than two.
+    pub fn with_number_of_batches(self, number_of_batches: usize)->McConfig{
+        if number_of_batches < 2 {
+            panic!("There must be at least two batches.");
+        }
+        McConfig{number_of_batches: Some(number_of_batches), ..self}
+    }
+
+    /// Returns the (maximal) number of paths.
+    pub fn get_number_of_paths(&self)->usize{
+        self.number_of_paths
+    }
+
+    /// Returns the seed, or `None` if a random seed is used.
+    pub fn get_seed(&self)->Option<u64>{
+        self.seed
+    }
+
+    /// Returns whether antithetic variates are used.
+    pub fn get_antithetic(&self)->bool{
+        self.antithetic
+    }
+
+    /// Returns whether quasi random samples are used.
+    pub fn get_quasi_random(&self)->bool{
+        self.quasi_random
+    }
+
+    /// Returns whether the control variate is used.
+    pub fn get_control_variate(&self)->bool{
+        self.control_variate
+    }
+
+    /// Returns the number of threads.
+    pub fn get_number_of_threads(&self)->usize{
+        self.number_of_threads
+    }
+
+    /// Returns the standard error at which the simulation stops, if any.
+    pub fn get_tolerance(&self)->Option<f64>{
+        self.tolerance
+    }
+
+    /// Returns the number of paths in a block.
+    pub fn get_block_size(&self)->usize{
+        self.block_size
+    }
+
+    /// Returns the number of independent batches, if the paths are batched.
+    pub fn get_number_of_batches(&self)->Option<usize>{
+        self.number_of_batches
+    }
+}
+
+/// The streaming means and comoments of the discounted payoffs of an option and of its control.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct PathStatistics{
+    /// The number of paths.
+    paths: usize,
+    /// The mean of the payoffs of the option.
+    mean: f64,
+    /// The mean of the payoffs of the control.
+    control_mean: f64,
+    /// The sum of the squared deviations of the payoffs of the option from their mean.
+    variance_sum: f64,
+    /// The sum of the squared deviations of the payoffs of the control from their mean.
+    control_variance_sum: f64,
+    /// The sum of the products of the deviations of the payoffs of the option and of the control.
+    covariance_sum: f64,
+}
+
+impl PathStatistics {
+    /// Adds the payoffs of one path, with Welford's update.
+    fn add(&mut self, value: f64, control_value: f64){
+        self.paths += 1;
+        let deviation = value-self.mean;
+        let control_deviation = control_value-self.control_mean;
+        self.mean += deviation/self.paths as f64;
+        self.control_mean += control_deviation/self.paths as f64;
+        self.variance_sum += deviation*(value-self.mean);
+        self.control_variance_sum += control_deviation*(control_value-self.control_mean);
+        self.covariance_sum += deviation*(control_value-self.control_mean);
+    }
+
+    /// Adds the payoffs of the paths of `other`, with Chan's update.
+    fn merge(&mut self, other: &PathStatistics){
+        if other.paths == 0 {
+            return;
+        }
+        let paths = self.paths+other.paths;
+        let weight = (self.paths*other.paths) as f64/paths as f64;
+        let deviation = other.mean-self.mean;
+        let control_deviation = other.control_mean-self.control_mean;
+        self.variance_sum += other.variance_sum+deviation*deviation*weight;
+        self.control_variance_sum += other.control_variance_sum+control_deviation*control_deviation*weight;
+        self.covariance_sum += other.covariance_sum+deviation*control_deviation*weight;
+        self.mean += deviation*other.paths as f64/paths as f64;
+        self.control_mean += control_deviation*other.paths as f64/paths as f64;
+        self.paths = paths;
+    }
+
+    /// Returns the estimated price and its standard error. With a control of known price `control_price`, the payoffs are corrected
+    /// by the deviation of the control from its price times the regression coefficient of the option on the control.
+    fn estimate(&self, control_price: Option<f64>)->(f64, f64){
+        let (price, residual_sum) = match control_price {
+            Some(control_price) => {
+                let beta = if self.control_variance_sum > 0.0 { self.covariance_sum/self.control_variance_sum } else { 0.0 };
+                (self.mean-beta*(self.control_mean-control_price), self.variance_sum-beta*self.covariance_sum)
+            },
+            None => (self.mean, self.variance_sum),
+        };
+        if self.paths < 2 {
+            return (price, 0.0);
+        }
+        (price, (residual_sum.max(0.0)/((self.paths-1)*self.paths) as f64).sqrt())
+    }
+}
+
+/// Returns the standard error of the mean of the prices estimated from the statistics of the independent `batches`.
+fn batch_std_error(batches: &[PathStatistics], control_price: Option<f64>)->f64{
+    let prices: Vec<f64> = batches.iter().map(|batch| batch.estimate(control_price).0).collect();
+    let mean = prices.iter().sum::<f64>()/prices.len() as f64;
+    let variance = prices.iter().map(|price| (price-mean)*(price-mean)).sum::<f64>()/(prices.len()-1) as f64;
+    (variance/prices.len() as f64).sqrt()
+}
+
+/// A block of paths simulated by a `MonteCarloEngine`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Block{
+    /// The index of the block among all blocks, which selects its substream of pseudo random numbers.
+    index: usize,
+    /// The batch of the block.
+    batch: usize,
+    /// The index of the first path of the block in its batch.
+    first_path: usize,
+    /// The number of paths of the block.
+    paths: usize,
+}
+
+/// An option simulated by a `MonteCarloEngine`, with its settlement value (if it is already known) and its discount factor.
+type SimulatedOption<'a, T> = (&'a (dyn DerivativeOption<T>+Sync), Option<f64>, f64);
+
+/// A Monte Carlo engine, which prices options with the paths, seed, variance reduction and parallelism of its `McConfig`.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use derivative_pricer::monte_carlo_pricer::{McConfig, MonteCarloEngine};
+/// use derivative_pricer::option::VanillaStockOption;
+/// use derivative_pricer::payoff::CallPayoff;
+/// use derivative_pricer::stock::GeometricBrownianMotionStock;
+/// use derivative_pricer::utils::{NonNegativeFloat, TimeStamp};
+/// let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 0.05,
+///     NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+/// let call = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(100.0));
+/// let engine = MonteCarloEngine::new(McConfig::new(100000).with_seed(7).with_antithetic(true).with_number_of_threads(4));
+/// let result = engine.price(&call, 0.05);
+/// assert!((result.get_price()-10.4506).abs() < 4.0*result.get_std_error());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MonteCarloEngine{
+    /// The configuration of the engine.
+    config: McConfig,
+}
+
+impl MonteCarloEngine {
+    /// Returns an engine with the configuration `config`.
+    pub fn new(config: McConfig)->MonteCarloEngine{
+        MonteCarloEngine{config}
+    }
+
+    /// Returns the configuration of the engine.
+    pub fn get_config(&self)->&McConfig{
+        &self.config
+    }
+
+    /// Returns the price of `option`, given the short rate of interest `r`, with its standard error and the seed used.
+    /// An option at or past expiry whose settlement value is known is valued at its settlement value, without simulation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the option expired and its settlement value is unknown.
+    pub fn price<T>(&self, option: &(impl DerivativeOption<T>+Sync), r: f64)->McResult
+    where T: Underlying{
+        self.try_price(option, r).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `price`, but returns `PricingError::ExpiredOption` instead of panicking if the option expired and its settlement
+    /// value is unknown.
+    pub fn try_price<T>(&self, option: &(impl DerivativeOption<T>+Sync), r: f64)->Result<McResult, PricingError>
+    where T: Underlying{
+        self.run(option, None, r)
+    }
+
+    /// Returns the price of `option` as `price`, using `control` as a control variate if it is turned on in the configuration.
+    /// The control is simulated on the same random samples as the option, and its known price `control_price` corrects the
+    /// estimate, e.g. a geometric Asian option priced in closed form for an arithmetic Asian option.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the option or the control expired and its settlement value is unknown.
+    pub fn price_with_control_variate<T>(&self, option: &(impl DerivativeOption<T>+Sync), control: &(impl DerivativeOption<T>+Sync),
+        control_price: f64, r: f64)->McResult
+    where T: Underlying{
+        self.try_price_with_control_variate(option, control, control_price, r).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `price_with_control_variate`, but returns `PricingError::ExpiredOption` instead of panicking if the option or the
+    /// control expired and its settlement value is unknown.
+    pub fn try_price_with_control_variate<T>(&self, option: &(impl DerivativeOption<T>+Sync), control: &(impl DerivativeOption<T>+Sync),
+        control_price: f64, r: f64)->Result<McResult, PricingError>
+    where T: Underlying{
+        if self.config.control_variate {
+            self.run(option, Some((control, control_price)), r)
+        }
+        else {
+            self.run(option, None, r)
+        }
+    }
+
+    /// Simulates the option (and the control) block by block, as described in `McConfig`.
+    fn run<T>(&self, option: &(dyn DerivativeOption<T>+Sync), control: Option<(&(dyn DerivativeOption<T>+Sync), f64)>, r: f64)
+        ->Result<McResult, PricingError>
+    where T: Underlying{
+        let (settlement_value, discount_factor) = settlement_and_discount_factor(option, r)?;
+        let seed = self.config.seed.unwrap_or_else(|| RandomNumberGenerator::new(None).get_seed());
+        if let Some(value) = settlement_value {
+            return Ok(McResult::new(value, 0.0, self.config.number_of_paths, seed));
+        }
+        let simulated = (option, settlement_value, discount_factor);
+        let simulated_control = match control {
+            Some((control, _)) => {
+                let (control_settlement, control_discount) = settlement_and_discount_factor(control, r)?;
+                Some((control, control_settlement, control_discount))
+            },
+            None => None,
+        };
+        let dimensionality = option.get_dimensionality().max(control.map_or(0, |(control, _)| control.get_dimensionality())).max(1);
+        let control_price = control.map(|(_, price)| price);
+        let number_of_batches = self.config.number_of_batches.unwrap_or(1).min(self.config.number_of_paths.max(1));
+        let blocks = self.blocks(number_of_batches);
+        let mut statistics = PathStatistics::default();
+        let mut batch_statistics = vec![PathStatistics::default(); number_of_batches];
+        let mut completed_batches = 0;
+        let mut next_block = 0;
+        'simulation: while next_block < blocks.len() {
+            let round = &blocks[next_block..blocks.len().min(next_block+self.config.number_of_threads)];
+            let round_statistics: Vec<PathStatistics> = if round.len() == 1 {
+                vec![self.simulate_block(simulated, simulated_control, r, seed, dimensionality, &round[0])]
+            }
+            else {
+                std::thread::scope(|scope| {
+                    let handles: Vec<_> = round.iter().map(|block| scope.spawn(move ||
+                        self.simulate_block(simulated, simulated_control, r, seed, dimensionality, block))).collect();
+                    handles.into_iter().map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e))).collect()
+                })
+            };
+            for (block, block_statistics) in round.iter().zip(&round_statistics) {
+                statistics.merge(block_statistics);
+                batch_statistics[block.batch].merge(block_statistics);
+                next_block += 1;
+                let batch_completed = blocks.get(next_block).is_none_or(|next| next.batch != block.batch);
+                if batch_completed {
+                    completed_batches = block.batch+1;
+                }
+                if let Some(tolerance) = self.config.tolerance {
+                    let error = if number_of_batches > 1 {
+                        (batch_completed && completed_batches > 1)
+                            .then(|| batch_std_error(&batch_statistics[..completed_batches], control_price))
+                    }
+                    else {
+                        (statistics.paths > 1).then(|| statistics.estimate(control_price).1)
+                    };
+                    if error.is_some_and(|error| error <= tolerance) {
+                        break 'simulation;
+                    }
+                }
+            }
+        }
+        let (price, std_error) = statistics.estimate(control_price);
+        let result = McResult::new(price, std_error, statistics.paths, seed);
+        if completed_batches > 1 {
+            return Ok(result.with_batch_std_error(completed_batches, batch_std_error(&batch_statistics[..completed_batches], control_price)));
+        }
+        Ok(result)
+    }
+
+    /// Returns the blocks of the paths split into `number_of_batches` batches of as equal sizes as possible.
+    fn blocks(&self, number_of_batches: usize)->Vec<Block>{
+        let mut blocks = Vec::new();
+        for batch in 0..number_of_batches {
+            let batch_paths = self.config.number_of_paths/number_of_batches+usize::from(batch < self.config.number_of_paths%number_of_batches);
+            for first_path in (0..batch_paths).step_by(self.config.block_size) {
+                blocks.push(Block{
+                    index: blocks.len(),
+                    batch,
+                    first_path,
+                    paths: self.config.block_size.min(batch_paths-first_path),
+                });
+            }
+        }
+        blocks
+    }
+
+    /// Simulates the paths of `block`.
+    fn simulate_block<T>(&self, option: SimulatedOption<T>, control: Option<SimulatedOption<T>>, r: f64, seed: u64, dimensionality: usize,
+        block: &Block)->PathStatistics
+    where T: Underlying{
+        let mut rng: Box<dyn RandomNumberGeneratorTrait> = if self.config.quasi_random {
+            let mut halton = HaltonGenerator::new(dimensionality, HaltonRandomization::OwenScrambling, Some(seed)).substream(block.batch as u64);
+            halton.skip(block.first_path*dimensionality);
+            Box::new(halton)
+        }
+        else {
+            Box::new(RandomNumberGenerator::new(Some(seed)).substream(block.index as u64))
+        };
+        let value = |(option, settlement_value, discount_factor): SimulatedOption<T>, samples: &[f64]| match settlement_value {
+            Some(value) => value,
+            None => discount_factor*option.price_path(samples, r),
+        };
+        let mut statistics = PathStatistics::default();
+        for _ in 0..block.paths {
+            let samples = rng.get_gaussians(dimensionality);
+            let mut result = value(option, &samples);
+            let mut control_result = control.map_or(0.0, |control| value(control, &samples));
+            if self.config.antithetic {
+                let antithetic_samples: Vec<f64> = samples.iter().map(|x| -x).collect();
+                result = 0.5*(result+value(option, &antithetic_samples));
+                control_result = 0.5*(control_result+control.map_or(0.0, |control| value(control, &antithetic_samples)));
+            }
+            statistics.add(result, control_result);
+        }
+        statistics
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::rc::Rc;
+    use std::sync::Arc;
 
+    use crate::fft_pricer::CarrMadanPricer;
+    use crate::models::HestonModel;
     use crate::option::{AsianOption, VanillaStockOption};
+    use crate::path_generator::HestonStock;
+    use crate::payoff::{CallPayoff, ClosurePayoff};
+    use crate::statistics_gatherer::MeanStatisticsGatherer;
     use crate::stock::{GeometricBrownianMotionStock, StockState};
     use crate::utils::{NonNegativeFloat, TimeStamp};
 
@@ -68,12 +1030,12 @@ mod tests {
     fn vanilla_call_test1() {
         let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(3.2), TimeStamp::from(0.0), 
             1.0, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
-        let params = Box::new(vec![5.0]);
-        fn payoff(spot: NonNegativeFloat, params: &Box<Vec<f64>>)->f64{
+        let params = vec![5.0];
+        fn payoff(spot: NonNegativeFloat, params: &[f64])->f64{
             f64::max(f64::from(spot)-params[0], 0.0)
         }
 
-        let opt = VanillaStockOption::new(&Rc::new(stock), TimeStamp::from(3.7), Box::new(payoff), params);
+        let opt = VanillaStockOption::new(&Arc::new(stock), TimeStamp::from(3.7), Box::new(payoff), params);
         assert!(f64::abs(monte_carlo_pricer(&opt, 0.05, None, 100000)-0.2)<0.01)
         
     }
@@ -82,12 +1044,12 @@ mod tests {
     fn vanilla_call_test2() {
         let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(3.2), TimeStamp::from(0.0), 
             1.0, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
-        let params = Box::new(vec![10.0]);
-        fn payoff(spot: NonNegativeFloat, params: &Box<Vec<f64>>)->f64{
+        let params = vec![10.0];
+        fn payoff(spot: NonNegativeFloat, params: &[f64])->f64{
             f64::max(f64::from(spot)-params[0], 0.0)
         }
 
-        let opt = VanillaStockOption::new(&Rc::new(stock), TimeStamp::from(3.7), Box::new(payoff), params);
+        let opt = VanillaStockOption::new(&Arc::new(stock), TimeStamp::from(3.7), Box::new(payoff), params);
         assert!(f64::abs(monte_carlo_pricer(&opt, 0.05, None, 100000)-0.0)<0.01)
         
     }
@@ -96,12 +1058,12 @@ mod tests {
     fn vanilla_put_test1() {
         let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(3.2), TimeStamp::from(0.0), 
             1.0, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
-        let params = Box::new(vec![5.0]);
-        fn payoff(spot: NonNegativeFloat, params: &Box<Vec<f64>>)->f64{
+        let params = vec![5.0];
+        fn payoff(spot: NonNegativeFloat, params: &[f64])->f64{
             f64::max(params[0]-f64::from(spot), 0.0)
         }
 
-        let opt = VanillaStockOption::new(&Rc::new(stock), TimeStamp::from(3.7), Box::new(payoff), params);
+        let opt = VanillaStockOption::new(&Arc::new(stock), TimeStamp::from(3.7), Box::new(payoff), params);
         assert!(f64::abs(monte_carlo_pricer(&opt, 0.05, None, 100000)-1.16)<0.01)
         
     }
@@ -110,12 +1072,12 @@ mod tests {
     fn vanilla_put_test2() {
         let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(3.2), TimeStamp::from(0.0), 
             1.0, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
-        let params = Box::new(vec![10.0]);
-        fn payoff(spot: NonNegativeFloat, params: &Box<Vec<f64>>)->f64{
+        let params = vec![10.0];
+        fn payoff(spot: NonNegativeFloat, params: &[f64])->f64{
             f64::max(params[0]-f64::from(spot), 0.0)
         }
 
-        let opt = VanillaStockOption::new(&Rc::new(stock), TimeStamp::from(3.7), Box::new(payoff), params);
+        let opt = VanillaStockOption::new(&Arc::new(stock), TimeStamp::from(3.7), Box::new(payoff), params);
         assert!(f64::abs(monte_carlo_pricer(&opt, 0.05, None, 100000)-5.12)<0.01)
         
     }
@@ -124,12 +1086,12 @@ mod tests {
     fn vanilla_put_test3() {
         let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(3.2), TimeStamp::from(0.0), 
             1.0, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.04));
-        let params = Box::new(vec![10.0]);
-        fn payoff(spot: NonNegativeFloat, params: &Box<Vec<f64>>)->f64{
+        let params = vec![10.0];
+        fn payoff(spot: NonNegativeFloat, params: &[f64])->f64{
             f64::max(params[0]-f64::from(spot), 0.0)
         }
 
-        let opt = VanillaStockOption::new(&Rc::new(stock), TimeStamp::from(3.7), Box::new(payoff), params);
+        let opt = VanillaStockOption::new(&Arc::new(stock), TimeStamp::from(3.7), Box::new(payoff), params);
         assert!(f64::abs(monte_carlo_pricer(&opt, 0.05, None, 100000)-5.55)<0.01)
         
     }
@@ -138,7 +1100,7 @@ mod tests {
     fn asian_call_test1(){
         let stock=GeometricBrownianMotionStock::new(NonNegativeFloat::from(10.2), TimeStamp::from(0.0), 
         1.0, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
-        fn average(states: &Vec<StockState>,monitoring_times: &Vec<TimeStamp>)->NonNegativeFloat{
+        fn average(states: &[StockState],monitoring_times: &[TimeStamp])->NonNegativeFloat{
             let mut sum=0.0;
             let mut j=0;
             for t in monitoring_times.iter(){
@@ -157,13 +1119,13 @@ mod tests {
         }
 
 
-        fn payoff(average: NonNegativeFloat, params: &Box<Vec<f64>>)->f64{
+        fn payoff(average: NonNegativeFloat, params: &[f64])->f64{
             f64::max(f64::from(average)-params[0], 0.0)
         }
         let monitoring_times = vec![TimeStamp::from(0.0), TimeStamp::from(1.0), 
             TimeStamp::from(2.0), TimeStamp::from(3.0), TimeStamp::from(4.0), TimeStamp::from(5.0)];
-        let op = AsianOption::new(&Rc::new(stock), TimeStamp::from(5.0), &monitoring_times, Box::new(average), 
-            Box::new(payoff), Box::new(vec![5.4 as f64]));
+        let op = AsianOption::new(&Arc::new(stock), TimeStamp::from(5.0), &monitoring_times, Box::new(average), 
+            Box::new(payoff), vec![5.4]);
         assert!(f64::abs(monte_carlo_pricer(&op, 0.03, None, 300000)-4.83)<0.01)
     }
 
@@ -171,7 +1133,7 @@ mod tests {
     fn asian_put_test1(){
         let stock=GeometricBrownianMotionStock::new(NonNegativeFloat::from(10.2), TimeStamp::from(0.0), 
         1.0, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
-        fn average(states: &Vec<StockState>,monitoring_times: &Vec<TimeStamp>)->NonNegativeFloat{
+        fn average(states: &[StockState],monitoring_times: &[TimeStamp])->NonNegativeFloat{
             let mut sum=0.0;
             let mut j=0;
             for t in monitoring_times.iter(){
@@ -190,13 +1152,323 @@ mod tests {
         }
 
 
-        fn payoff(average: NonNegativeFloat, params: &Box<Vec<f64>>)->f64{
+        fn payoff(average: NonNegativeFloat, params: &[f64])->f64{
             f64::max(params[0]-f64::from(average), 0.0)
         }
         let monitoring_times = vec![TimeStamp::from(0.0), TimeStamp::from(1.0), 
             TimeStamp::from(2.0), TimeStamp::from(3.0), TimeStamp::from(4.0), TimeStamp::from(5.0)];
-        let op = AsianOption::new(&Rc::new(stock), TimeStamp::from(5.0), &monitoring_times, Box::new(average), 
-            Box::new(payoff), Box::new(vec![12.6 as f64]));
+        let op = AsianOption::new(&Arc::new(stock), TimeStamp::from(5.0), &monitoring_times, Box::new(average), 
+            Box::new(payoff), vec![12.6]);
         assert!(f64::abs(monte_carlo_pricer(&op, 0.03, None, 300000)-1.86)<0.01)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn checkpoint_test(){
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
+        fn payoff(spot: NonNegativeFloat, params: &[f64])->f64{
+            f64::max(f64::from(spot)-params[0], 0.0)
+        }
+        let opt = VanillaStockOption::new(&Arc::new(stock), TimeStamp::from(1.0), Box::new(payoff), vec![100.0]);
+        let rng = RandomNumberGenerator::new(Some(42));
+        let mut uninterrupted = MeanStatisticsGatherer::new();
+        monte_carlo_simulation(&opt, &mut uninterrupted, 0.05, &mut RandomNumberGenerator::new(Some(42)), 10000);
+        let mut checkpoint = MonteCarloCheckpoint::new(MeanStatisticsGatherer::new(), &rng);
+        // Interrupt the simulation after the second checkpoint, and resume it from a saved copy.
+        let mut saved = None;
+        let finished = monte_carlo_simulation_with_checkpoints(&opt, &mut checkpoint, 0.05, 10000, 3000, |c| {
+            saved = Some(c.clone());
+            c.get_completed_paths() < 6000
+        });
+        assert!(!finished);
+        let mut resumed = saved.unwrap();
+        assert_eq!(resumed.get_completed_paths(), 6000);
+        assert!(monte_carlo_simulation_with_checkpoints(&opt, &mut resumed, 0.05, 10000, 3000, |_| true));
+        assert_eq!(resumed.get_gatherer(), &uninterrupted);
+    }
+
+    #[test]
+    fn progress_test(){
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
+        fn payoff(spot: NonNegativeFloat, params: &[f64])->f64{
+            f64::max(f64::from(spot)-params[0], 0.0)
+        }
+        let opt = VanillaStockOption::new(&Arc::new(stock), TimeStamp::from(1.0), Box::new(payoff), vec![100.0]);
+        let mut reports = Vec::new();
+        let mut progress = MonteCarloProgress::new(1000, |paths, gatherer| {
+            reports.push((paths, gatherer.get_results_so_far()[0][0]));
+            paths < 3000
+        });
+        let mut sg = MeanStatisticsGatherer::new();
+        let mut rng = RandomNumberGenerator::new(Some(3));
+        let completed = monte_carlo_simulation_with_progress(&opt, &mut sg, 0.05, &mut rng, 10000, &mut progress);
+        drop(progress);
+        assert_eq!(completed, 3000);
+        assert_eq!(reports.iter().map(|r| r.0).collect::<Vec<usize>>(), vec![1000, 2000, 3000]);
+        assert_eq!(reports[2].1, sg.get_results_so_far()[0][0]);
+    }
+
+    #[test]
+    fn path_storing_test(){
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
+        fn average(states: &[StockState], _monitoring_times: &[TimeStamp])->NonNegativeFloat{
+            NonNegativeFloat::from(states.iter().map(|s| f64::from(s.get_value())).sum::<f64>()/states.len() as f64)
+        }
+        fn payoff(average: NonNegativeFloat, params: &[f64])->f64{
+            f64::max(f64::from(average)-params[0], 0.0)
+        }
+        let monitoring_times = [TimeStamp::from(0.5), TimeStamp::from(1.0)];
+        let opt = AsianOption::new(&Arc::new(stock), TimeStamp::from(1.0), &monitoring_times, Box::new(average),
+            Box::new(payoff), vec![100.0]);
+        let mut gatherer = PathStoringGatherer::new(5);
+        let mut rng = RandomNumberGenerator::new(Some(8));
+        monte_carlo_simulation_storing_paths(&opt, &mut gatherer, 0.05, &mut rng, 1000);
+        assert_eq!(gatherer.get_paths().len(), 5);
+        for (path, result) in gatherer.get_paths().iter().zip(gatherer.get_path_results()) {
+            assert_eq!(path.iter().map(|s| s.get_time()).collect::<Vec<TimeStamp>>(),
+                vec![TimeStamp::from(0.0), TimeStamp::from(0.5), TimeStamp::from(1.0)]);
+            assert_eq!(*result, (-0.05_f64).exp()*payoff(average(path, &monitoring_times), &[100.0]));
+        }
+        assert!((gatherer.get_results_so_far()[0][0]-monte_carlo_pricer(&opt, 0.05, Some(8), 1000)).abs() < 1e-10);
+    }
+
+    #[test]
+    fn multi_option_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let strikes = [90.0, 100.0, 110.0];
+        let calls: Vec<VanillaStockOption<CallPayoff>> = strikes.iter()
+            .map(|k| VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(*k))).collect();
+        let options: Vec<&dyn DerivativeOption<GeometricBrownianMotionStock>> = calls.iter()
+            .map(|c| c as &dyn DerivativeOption<GeometricBrownianMotionStock>).collect();
+        let mut rng = RandomNumberGenerator::new(Some(4));
+        let result = monte_carlo_simulation_multi(&options, 0.05, &mut rng, 20000);
+        for (call, price) in calls.iter().zip(result.get_prices()) {
+            assert!((price-monte_carlo_pricer(call, 0.05, Some(4), 20000)).abs() < 1e-10);
+        }
+        let errors = result.get_standard_errors();
+        assert!(errors[0] > errors[1] && errors[1] > errors[2]);
+        // The estimates are positively correlated, and the covariance matrix is symmetric.
+        assert!(result.get_covariance()[0][2] > 0.0);
+        assert!((result.get_covariance()[0][2]-result.get_covariance()[2][0]).abs() < 1e-12);
+        assert!((errors[1]-0.1).abs() < 0.02);
+    }
+
+    #[test]
+    fn degenerate_multi_option_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(1.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let mut rng = RandomNumberGenerator::new(Some(4));
+        let empty = monte_carlo_simulation_multi::<GeometricBrownianMotionStock>(&[], 0.05, &mut rng, 10);
+        assert_eq!((empty.get_prices(), empty.get_standard_errors()), (&[][..], vec![]));
+        // An option at expiry contributes its settlement value to every path, without variance, next to a live option.
+        let at_expiry = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(90.0));
+        let live = VanillaStockOption::with_payoff(&stock, TimeStamp::from(2.0), CallPayoff::new(90.0));
+        let result = monte_carlo_simulation_multi(&[&at_expiry, &live], 0.05, &mut rng, 1000);
+        assert_eq!(result.get_prices()[0], 10.0);
+        assert_eq!((&result.get_covariance()[0], result.get_covariance()[1][0]), (&vec![0.0, 0.0], 0.0));
+        assert!(result.get_standard_errors()[1] > 0.0);
+        // A single path gives no estimate of the covariance.
+        let single = monte_carlo_simulation_multi(&[&live], 0.05, &mut rng, 1);
+        assert_eq!(single.get_covariance(), &[vec![0.0]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The option expiered!")]
+    fn expired_multi_option_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(1.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let expired = VanillaStockOption::with_payoff(&stock, TimeStamp::from(0.5), CallPayoff::new(90.0));
+        monte_carlo_simulation_multi(&[&expired], 0.05, &mut RandomNumberGenerator::new(Some(4)), 10);
+    }
+
+    #[test]
+    fn mc_result_test(){
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
+        fn payoff(spot: NonNegativeFloat, params: &[f64])->f64{
+            f64::max(f64::from(spot)-params[0], 0.0)
+        }
+        let opt = VanillaStockOption::new(&Arc::new(stock), TimeStamp::from(1.0), Box::new(payoff), vec![100.0]);
+        let result = monte_carlo_pricer_with_error(&opt, 0.05, None, 40000);
+        let (low, high) = result.get_confidence_interval();
+        assert!(low < result.get_price() && result.get_price() < high);
+        assert!((result.get_std_error()-0.073).abs() < 0.005);
+        assert_eq!(result.get_paths(), 40000);
+        // A run with a random seed can be reproduced from the reported seed.
+        assert_eq!(monte_carlo_pricer(&opt, 0.05, Some(result.get_seed()), 40000), result.get_price());
+        assert!((result.get_price()-10.4506).abs() < 4.0*result.get_std_error());
+    }
+
+    #[test]
+    fn single_path_mc_result_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let call = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(100.0));
+        // One path gives no estimate of the variance, so the confidence interval collapses to the price.
+        let result = monte_carlo_pricer_with_error(&call, 0.05, Some(7), 1);
+        assert_eq!(result.get_std_error(), 0.0);
+        assert_eq!(result.get_confidence_interval(), (result.get_price(), result.get_price()));
+        assert_eq!((result.get_paths(), result.get_seed(), result.get_batches(), result.get_batch_std_error()), (1, 7, 1, None));
+        let expired = VanillaStockOption::with_payoff(&Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0),
+            TimeStamp::from(2.0), 0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0))), TimeStamp::from(1.0), CallPayoff::new(100.0));
+        assert_eq!(try_monte_carlo_pricer_with_error(&expired, 0.05, Some(7), 10), Err(PricingError::ExpiredOption));
+    }
+
+    #[test]
+    fn expired_option_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(110.0), TimeStamp::from(1.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let at_expiry = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(100.0));
+        let result = try_monte_carlo_pricer_with_error(&at_expiry, 0.05, Some(1), 100).unwrap();
+        assert_eq!(result.get_price(), 10.0);
+        assert_eq!(result.get_std_error(), 0.0);
+        let expired = VanillaStockOption::with_payoff(&stock, TimeStamp::from(0.5), CallPayoff::new(100.0));
+        assert_eq!(try_monte_carlo_pricer(&expired, 0.05, Some(1), 100), Err(PricingError::ExpiredOption));
+        let multi = monte_carlo_simulation_multi(&[&at_expiry], 0.05, &mut RandomNumberGenerator::new(Some(1)), 10);
+        assert_eq!(multi.get_prices(), &[10.0]);
+    }
+
+    fn engine_call()->VanillaStockOption<CallPayoff>{
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 0.05,
+            NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(100.0))
+    }
+
+    #[test]
+    fn engine_reproducibility_test(){
+        let call = engine_call();
+        let config = McConfig::new(20000).with_seed(3).with_block_size(3000);
+        let first = MonteCarloEngine::new(config).price(&call, 0.05);
+        assert_eq!(first, MonteCarloEngine::new(config).price(&call, 0.05));
+        let threaded = MonteCarloEngine::new(config.with_number_of_threads(4)).price(&call, 0.05);
+        assert_eq!(first, threaded);
+        assert_eq!(first.get_paths(), 20000);
+        assert_eq!(first.get_seed(), 3);
+        assert!((first.get_price()-10.4506).abs() < 4.0*first.get_std_error());
+        let random = MonteCarloEngine::new(McConfig::new(10)).price(&call, 0.05);
+        let replayed = MonteCarloEngine::new(McConfig::new(10).with_seed(random.get_seed())).price(&call, 0.05);
+        assert_eq!(random, replayed);
+    }
+
+    #[test]
+    fn engine_variance_reduction_test(){
+        let call = engine_call();
+        let config = McConfig::new(20000).with_seed(5);
+        let plain = MonteCarloEngine::new(config).price(&call, 0.05);
+        let antithetic = MonteCarloEngine::new(config.with_antithetic(true)).price(&call, 0.05);
+        assert!(antithetic.get_std_error() < plain.get_std_error());
+        assert!((antithetic.get_price()-10.4506).abs() < 4.0*antithetic.get_std_error());
+        let quasi_random = MonteCarloEngine::new(config.with_quasi_random(true)).price(&call, 0.05);
+        assert!((quasi_random.get_price()-10.4506).abs() < 0.05);
+        let stock = call.get_underlying();
+        let forward = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0),
+            ClosurePayoff::new(Box::new(|spot, _| f64::from(spot)), vec![]));
+        let controlled_engine = MonteCarloEngine::new(config.with_control_variate(true));
+        let controlled = controlled_engine.price_with_control_variate(&call, &forward, 100.0, 0.05);
+        assert!(controlled.get_std_error() < 0.5*plain.get_std_error());
+        assert!((controlled.get_price()-10.4506).abs() < 4.0*controlled.get_std_error());
+        assert_eq!(MonteCarloEngine::new(config).price_with_control_variate(&call, &forward, 100.0, 0.05), plain);
+    }
+
+    #[test]
+    fn engine_tolerance_test(){
+        let call = engine_call();
+        let config = McConfig::new(1000000).with_seed(11).with_block_size(1000).with_tolerance(0.1).with_number_of_threads(3);
+        let result = MonteCarloEngine::new(config).price(&call, 0.05);
+        assert!(result.get_std_error() <= 0.1);
+        assert!(result.get_paths() < 1000000);
+        assert_eq!(result.get_paths()%1000, 0);
+        assert_eq!(result, MonteCarloEngine::new(config.with_number_of_threads(1)).price(&call, 0.05));
+    }
+
+    #[test]
+    fn engine_batch_means_test(){
+        let call = engine_call();
+        let config = McConfig::new(40000).with_seed(13).with_block_size(3000).with_number_of_batches(16);
+        let pseudo_random = MonteCarloEngine::new(config).price(&call, 0.05);
+        assert_eq!(pseudo_random.get_batches(), 16);
+        let batch_error = pseudo_random.get_batch_std_error().unwrap();
+        assert!((batch_error/pseudo_random.get_std_error()-1.0).abs() < 0.5);
+        let (low, high) = pseudo_random.get_batch_confidence_interval().unwrap();
+        assert!((high-low-2.0*2.1314*batch_error).abs() < 1e-12);
+        assert!(low < 10.4506 && 10.4506 < high);
+        assert_eq!(pseudo_random, MonteCarloEngine::new(config.with_number_of_threads(5)).price(&call, 0.05));
+        let quasi_random = MonteCarloEngine::new(config.with_quasi_random(true)).price(&call, 0.05);
+        let quasi_random_error = quasi_random.get_batch_std_error().unwrap();
+        assert!(quasi_random_error < 0.25*quasi_random.get_std_error());
+        assert!((quasi_random.get_price()-10.4506).abs() < 4.0*quasi_random_error);
+        let unbatched = MonteCarloEngine::new(McConfig::new(40000).with_seed(13)).price(&call, 0.05);
+        assert_eq!((unbatched.get_batches(), unbatched.get_batch_std_error()), (1, None));
+        assert_eq!(unbatched.get_batch_confidence_interval(), None);
+        let early = MonteCarloEngine::new(config.with_tolerance(0.15).with_number_of_threads(3)).price(&call, 0.05);
+        assert!(early.get_batches() < 16);
+        assert!(early.get_batch_std_error().unwrap() <= 0.15);
+        assert_eq!(early.get_paths(), 2500*early.get_batches());
+    }
+
+    #[test]
+    fn generator_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.05, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        fn average(states: &[StockState], _monitoring_times: &[TimeStamp])->NonNegativeFloat{
+            NonNegativeFloat::from(states.iter().map(|s| f64::from(s.get_value())).sum::<f64>()/states.len() as f64)
+        }
+        let monitoring_times = [TimeStamp::from(0.25), TimeStamp::from(0.5), TimeStamp::from(1.0)];
+        let asian = AsianOption::with_payoff(&stock, TimeStamp::from(1.0), &monitoring_times, Box::new(average), CallPayoff::new(100.0));
+        let mut gatherer = MeanStatisticsGatherer::new();
+        monte_carlo_simulation_with_generator(&asian, &*stock, &mut gatherer, 0.05, &mut RandomNumberGenerator::new(Some(3)), 2000);
+        assert!((gatherer.get_results_so_far()[0][0]-monte_carlo_pricer(&asian, 0.05, Some(3), 2000)).abs() < 1e-10);
+
+        let model = HestonModel::new(0.04, 1.5, 0.04, 0.4, -0.6, 0.0);
+        let heston = HestonStock::new(model, stock.get_current_state(), 0.01);
+        let call = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(100.0));
+        let mut gatherer = MeanVarianceStatisticsGatherer::new();
+        monte_carlo_simulation_with_generator(&call, &heston, &mut gatherer, 0.05, &mut RandomNumberGenerator::new(Some(5)), 20000);
+        let reference = CarrMadanPricer::default().call_prices(&model, 100.0, 0.05, 1.0, &[100.0])[0];
+        assert!((gatherer.get_mean()-reference).abs() < 3.0*gatherer.get_standard_error());
+    }
+
+    #[test]
+    fn generator_at_and_past_expiry_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(1.0),
+            0.05, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let model = HestonModel::new(0.04, 1.5, 0.04, 0.4, -0.6, 0.0);
+        let heston = HestonStock::new(model, stock.get_current_state(), 0.01);
+        let at_expiry = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(90.0));
+        let mut gatherer = MeanVarianceStatisticsGatherer::new();
+        let mut rng = RandomNumberGenerator::new(Some(5));
+        let state = rng.state();
+        assert_eq!(try_monte_carlo_simulation_with_generator(&at_expiry, &heston, &mut gatherer, 0.05, &mut rng, 10), Ok(()));
+        assert_eq!((gatherer.get_mean(), gatherer.get_standard_error()), (10.0, 0.0));
+        assert_eq!(rng.state(), state);
+        let expired = VanillaStockOption::with_payoff(&stock, TimeStamp::from(0.5), CallPayoff::new(90.0));
+        assert_eq!(try_monte_carlo_simulation_with_generator(&expired, &heston, &mut gatherer, 0.05, &mut rng, 10),
+            Err(PricingError::ExpiredOption));
+    }
+
+    #[test]
+    fn student_t_quantile_test(){
+        assert_eq!(student_t_quantile_975(0), f64::INFINITY);
+        assert_eq!(student_t_quantile_975(1), 12.7062);
+        assert!((student_t_quantile_975(31)-2.0395).abs() < 1e-4);
+        assert!((student_t_quantile_975(120)-1.9799).abs() < 1e-4);
+        assert!((student_t_quantile_975(1000000)-1.96).abs() < 1e-4);
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn engine_config_serialization_test(){
+        let config = McConfig::new(5000).with_seed(9).with_antithetic(true).with_tolerance(0.01);
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<McConfig>(&json).unwrap(), config);
+        // Configurations which the builders reject do not deserialize either.
+        for invalid in [json.replace("\"number_of_threads\":1", "\"number_of_threads\":0"), json.replace("\"block_size\":10000", "\"block_size\":0"),
+            json.replace("\"number_of_batches\":null", "\"number_of_batches\":1")] {
+            assert_ne!(invalid, json);
+            assert!(serde_json::from_str::<McConfig>(&invalid).is_err());
+        }
+    }
+}
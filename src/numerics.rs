@@ -0,0 +1,6 @@
+//! Provides numerical routines shared by the pricers.
+//!
+//! The submodule `integration` provides quadrature rules, and `roots` provides root finders.
+
+pub mod integration;
+pub mod roots;
@@ -0,0 +1,219 @@
+//! Provides routines for numerical integration: adaptive Simpson for general integrands on a finite interval, Simpson weights
+//! for integrands sampled on an evenly spaced grid, Gauss-Legendre for smooth integrands on a finite interval, Gauss-Hermite
+//! for integrands against a Gaussian density,
+//! and a Riemann sum for integrands only known at sampled points, such as option prices on a strip of strikes.
+
+use core::f64::consts::PI;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::math::LibmFloat;
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::utils::Real;
+
+/// Returns the integral of `f` over `[a, b]` with adaptive Simpson's rule, refining each subinterval until the
+/// Richardson error estimate is below its share of `tolerance`.
+/// # Panics
+/// Panics if `tolerance` is not positive.
+pub fn adaptive_simpson(f: &dyn Fn(f64)->f64, a: f64, b: f64, tolerance: f64)->f64{
+    const MAXIMAL_DEPTH: usize = 50;
+    if tolerance <= 0.0 {
+        panic!("The tolerance must be positive.");
+    }
+    let (fa, fm, fb) = (f(a), f(0.5*(a+b)), f(b));
+    let whole = (b-a)/6.0*(fa+4.0*fm+fb);
+    simpson_step(f, a, b, fa, fm, fb, whole, tolerance, MAXIMAL_DEPTH)
+}
+
+/// One recursive step of `adaptive_simpson` on `[a, b]`, given the values at the end points and the middle, and the
+/// Simpson estimate `whole` on `[a, b]`.
+#[allow(clippy::too_many_arguments)]
+fn simpson_step(f: &dyn Fn(f64)->f64, a: f64, b: f64, fa: f64, fm: f64, fb: f64, whole: f64, tolerance: f64, depth: usize)->f64{
+    let m = 0.5*(a+b);
+    let (left_middle, right_middle) = (f(0.5*(a+m)), f(0.5*(m+b)));
+    let left = (m-a)/6.0*(fa+4.0*left_middle+fm);
+    let right = (b-m)/6.0*(fm+4.0*right_middle+fb);
+    let error = left+right-whole;
+    if depth == 0 || error.abs() <= 15.0*tolerance {
+        return left+right+error/15.0;
+    }
+    simpson_step(f, a, m, fa, left_middle, fm, left, 0.5*tolerance, depth-1)+
+        simpson_step(f, m, b, fm, right_middle, fb, right, 0.5*tolerance, depth-1)
+}
+
+/// Returns the weights `1/3, 4/3, 2/3, 4/3, ..., 2/3, 4/3` of Simpson's rule on `n` equally spaced points with unit spacing,
+/// without an end correction at the last point. This is the rule of the Carr-Madan FFT, for integrals over `[0, inf)`
+/// truncated where the integrand is negligible.
+pub fn truncated_simpson_weights(n: usize)->Vec<f64>{
+    (0..n).map(|j| if j == 0 { 1.0/3.0 } else if j%2 == 1 { 4.0/3.0 } else { 2.0/3.0 }).collect()
+}
+
+/// Returns the nodes and weights of the `n` point Gauss-Legendre rule on `[-1, 1]`, found by Newton's method on the Legendre
+/// polynomials. The nodes are sorted in increasing order.
+/// # Panics
+/// Panics if `n` is zero.
+pub fn gauss_legendre_nodes(n: usize)->(Vec<f64>, Vec<f64>){
+    const MAXIMAL_ITERATIONS: usize = 100;
+    if n == 0 {
+        panic!("The number of nodes must be positive.");
+    }
+    let nf = n as f64;
+    let mut nodes = vec![0.0; n];
+    let mut weights = vec![0.0; n];
+    for i in 0..n.div_ceil(2){
+        let mut z = (PI*(i as f64+0.75)/(nf+0.5)).cos();
+        let mut derivative = 1.0;
+        for _ in 0..MAXIMAL_ITERATIONS{
+            let mut p1 = 1.0;
+            let mut p2 = 0.0;
+            for j in 0..n{
+                let p3 = p2;
+                p2 = p1;
+                let j = j as f64;
+                p1 = ((2.0*j+1.0)*z*p2-j*p3)/(j+1.0);
+            }
+            derivative = nf*(z*p1-p2)/(z*z-1.0);
+            let previous = z;
+            z = previous-p1/derivative;
+            if (z-previous).abs() <= 1e-15 {
+                break;
+            }
+        }
+        nodes[i] = -z;
+        nodes[n-1-i] = z;
+        weights[i] = 2.0/((1.0-z*z)*derivative*derivative);
+        weights[n-1-i] = weights[i];
+    }
+    (nodes, weights)
+}
+
+/// Returns the integral of `f` over `[a, b]` with the `number_of_nodes` point Gauss-Legendre rule, which is exact for
+/// polynomials of degree up to `2*number_of_nodes-1`.
+/// # Panics
+/// Panics if `number_of_nodes` is zero.
+pub fn gauss_legendre(f: &dyn Fn(f64)->f64, a: f64, b: f64, number_of_nodes: usize)->f64{
+    let (nodes, weights) = gauss_legendre_nodes(number_of_nodes);
+    let half_length = 0.5*(b-a);
+    let middle = 0.5*(a+b);
+    half_length*nodes.iter().zip(&weights).map(|(x, w)| w*f(middle+half_length*x)).sum::<f64>()
+}
+
+/// Returns the nodes and weights of the `n` point Gauss-Hermite rule for the weight `exp(-x^2)`, found by Newton's method on
+/// the orthonormal Hermite polynomials as in Numerical Recipes. The nodes are sorted in decreasing order.
+/// # Panics
+/// Panics if `n` is zero.
+pub fn gauss_hermite_nodes(n: usize)->(Vec<f64>, Vec<f64>){
+    const MAXIMAL_ITERATIONS: usize = 100;
+    if n == 0 {
+        panic!("The number of nodes must be positive.");
+    }
+    let pi_to_minus_quarter = PI.powf(-0.25);
+    let mut nodes = vec![0.0; n];
+    let mut weights = vec![0.0; n];
+    let nf = n as f64;
+    let mut z: f64 = 0.0;
+    for i in 0..n.div_ceil(2){
+        z = match i {
+            0 => (2.0*nf+1.0).sqrt()-1.85575*(2.0*nf+1.0).powf(-0.16667),
+            1 => z-1.14*nf.powf(0.426)/z,
+            2 => 1.86*z-0.86*nodes[0],
+            3 => 1.91*z-0.91*nodes[1],
+            _ => 2.0*z-nodes[i-2],
+        };
+        let mut derivative = 0.0;
+        for _ in 0..MAXIMAL_ITERATIONS{
+            let mut p1 = pi_to_minus_quarter;
+            let mut p2 = 0.0;
+            for j in 0..n{
+                let p3 = p2;
+                p2 = p1;
+                let j = j as f64;
+                p1 = z*(2.0/(j+1.0)).sqrt()*p2-(j/(j+1.0)).sqrt()*p3;
+            }
+            derivative = (2.0*nf).sqrt()*p2;
+            let previous = z;
+            z = previous-p1/derivative;
+            if (z-previous).abs() <= 1e-14*z.abs().max(1.0) {
+                break;
+            }
+        }
+        nodes[i] = z;
+        nodes[n-1-i] = -z;
+        weights[i] = 2.0/(derivative*derivative);
+        weights[n-1-i] = weights[i];
+    }
+    (nodes, weights)
+}
+
+/// Returns the integral of `f(x)*exp(-x^2)` over the real line with the `number_of_nodes` point Gauss-Hermite rule.
+/// # Panics
+/// Panics if `number_of_nodes` is zero.
+pub fn gauss_hermite(f: &dyn Fn(f64)->f64, number_of_nodes: usize)->f64{
+    let (nodes, weights) = gauss_hermite_nodes(number_of_nodes);
+    nodes.iter().zip(&weights).map(|(x, w)| w*f(*x)).sum()
+}
+
+/// Returns the integral of a function known only at the sorted `points`, as the sum of `values` weighted by half the distance
+/// between the neighbouring points, and by the distance to the only neighbour at the ends.
+/// This is the discretization used by the CBOE to replicate the variance swap from a strip of options.
+/// # Panics
+/// Panics if there are fewer than two points, or if `points` and `values` have different lengths.
+pub fn sampled_riemann_sum(points: &[f64], values: &[f64])->f64{
+    if points.len() < 2 || points.len() != values.len() {
+        panic!("Invalid sampled function")
+    }
+    let n = points.len();
+    (0..n).map(|i| {
+        let width = if i == 0 {
+            points[1]-points[0]
+        }
+        else if i == n-1 {
+            points[n-1]-points[n-2]
+        }
+        else {
+            0.5*(points[i+1]-points[i-1])
+        };
+        width*values[i]
+    }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adaptive_simpson_test(){
+        assert!((adaptive_simpson(&|x| x.sin(), 0.0, PI, 1e-10)-2.0).abs()<1e-9);
+        assert!((adaptive_simpson(&|x| x.sqrt(), 0.0, 1.0, 1e-10)-2.0/3.0).abs()<1e-8);
+    }
+
+    #[test]
+    fn truncated_simpson_weights_test(){
+        let h = 0.01;
+        let weights = truncated_simpson_weights(4001);
+        let integral: f64 = weights.iter().enumerate().map(|(j, w)| h*w*(-(j as f64)*h).exp()).sum();
+        assert!((integral-1.0).abs()<1e-9);
+    }
+
+    #[test]
+    fn gauss_legendre_test(){
+        assert!((gauss_legendre(&|x| x.powi(9)+x*x, -1.0, 2.0, 5)-(1023.0/10.0+3.0)).abs()<1e-10);
+        assert!((gauss_legendre(&|x| x.exp(), 0.0, 1.0, 10)-(1.0_f64.exp()-1.0)).abs()<1e-14);
+        let (nodes, weights) = gauss_legendre_nodes(7);
+        assert!(nodes.windows(2).all(|w| w[0] < w[1]));
+        assert!((weights.iter().sum::<f64>()-2.0).abs()<1e-14);
+    }
+
+    #[test]
+    fn gauss_hermite_test(){
+        assert!((gauss_hermite(&|x| x*x, 4)-0.5*PI.sqrt()).abs()<1e-13);
+        assert!((gauss_hermite(&|x| (2.0*x).cos(), 30)-PI.sqrt()*(-1.0_f64).exp()).abs()<1e-13);
+    }
+
+    #[test]
+    fn sampled_riemann_sum_test(){
+        let points = [0.0, 1.0, 3.0, 4.0];
+        assert_eq!(sampled_riemann_sum(&points, &[1.0, 1.0, 1.0, 1.0]), 5.0);
+    }
+}
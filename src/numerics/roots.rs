@@ -0,0 +1,165 @@
+//! Provides root finders for functions of one variable: Brent's method, which only needs the function and a bracket,
+//! and Newton's method safeguarded by bisection, which also uses the derivative and converges faster near the root.
+
+use crate::error::PricingError;
+
+/// The maximal number of iterations of the root finders.
+const MAXIMAL_ITERATIONS: usize = 200;
+
+/// Returns a root of `f` in `[a, b]` with Brent's method, which combines inverse quadratic interpolation, the secant method
+/// and bisection. The root is found to within `tolerance`.
+/// # Errors
+/// Returns `PricingError::RootNotBracketed` if `f(a)` and `f(b)` have the same sign, and `PricingError::NoConvergence` if
+/// the root was not found within the maximal number of iterations.
+pub fn brent(f: &dyn Fn(f64)->f64, a: f64, b: f64, tolerance: f64)->Result<f64, PricingError>{
+    let (mut a, mut b) = (a, b);
+    let (mut fa, mut fb) = (f(a), f(b));
+    if fa == 0.0 {
+        return Ok(a);
+    }
+    if fb == 0.0 {
+        return Ok(b);
+    }
+    if fa.signum() == fb.signum() {
+        return Err(PricingError::RootNotBracketed);
+    }
+    let (mut c, mut fc) = (a, fa);
+    let mut d = b-a;
+    let mut e = d;
+    for _ in 0..MAXIMAL_ITERATIONS{
+        if fb.signum() == fc.signum() {
+            c = a;
+            fc = fa;
+            d = b-a;
+            e = d;
+        }
+        if fc.abs() < fb.abs() {
+            a = b;
+            b = c;
+            c = a;
+            fa = fb;
+            fb = fc;
+            fc = fa;
+        }
+        let accuracy = 2.0*f64::EPSILON*b.abs()+0.5*tolerance;
+        let middle = 0.5*(c-b);
+        if middle.abs() <= accuracy || fb == 0.0 {
+            return Ok(b);
+        }
+        if e.abs() >= accuracy && fa.abs() > fb.abs() {
+            let s = fb/fa;
+            let (mut p, mut q) = if a == c {
+                (2.0*middle*s, 1.0-s)
+            }
+            else {
+                let q = fa/fc;
+                let r = fb/fc;
+                (s*(2.0*middle*q*(q-r)-(b-a)*(r-1.0)), (q-1.0)*(r-1.0)*(s-1.0))
+            };
+            if p > 0.0 {
+                q = -q;
+            }
+            p = p.abs();
+            if 2.0*p < f64::min(3.0*middle*q-(accuracy*q).abs(), (e*q).abs()) {
+                e = d;
+                d = p/q;
+            }
+            else {
+                d = middle;
+                e = d;
+            }
+        }
+        else {
+            d = middle;
+            e = d;
+        }
+        a = b;
+        fa = fb;
+        b += if d.abs() > accuracy { d } else { accuracy.copysign(middle) };
+        fb = f(b);
+    }
+    Err(PricingError::NoConvergence)
+}
+
+/// Returns a root of `f` in `[a, b]` with Newton's method, falling back to bisection whenever a Newton step leaves the current
+/// bracket or does not halve the step size. The root is found to within `tolerance`.
+/// # Parameters
+/// - `f` - the function.
+/// - `derivative` - the derivative of `f`.
+/// - `a`, `b` - the end points of an interval on which `f` changes sign.
+/// - `tolerance` - the required accuracy of the root.
+/// # Errors
+/// Returns `PricingError::RootNotBracketed` if `f(a)` and `f(b)` have the same sign, and `PricingError::NoConvergence` if
+/// the root was not found within the maximal number of iterations.
+pub fn safeguarded_newton(f: &dyn Fn(f64)->f64, derivative: &dyn Fn(f64)->f64, a: f64, b: f64, tolerance: f64)->Result<f64, PricingError>{
+    let (fa, fb) = (f(a), f(b));
+    if fa == 0.0 {
+        return Ok(a);
+    }
+    if fb == 0.0 {
+        return Ok(b);
+    }
+    if fa.signum() == fb.signum() {
+        return Err(PricingError::RootNotBracketed);
+    }
+    // The bracket is kept oriented so that f(low) < 0 < f(high).
+    let (mut low, mut high) = if fa < 0.0 { (a, b) } else { (b, a) };
+    let mut x = 0.5*(a+b);
+    let mut previous_step = (b-a).abs();
+    let mut step = previous_step;
+    let mut fx = f(x);
+    let mut dfx = derivative(x);
+    for _ in 0..MAXIMAL_ITERATIONS{
+        let newton_leaves_bracket = ((x-high)*dfx-fx)*((x-low)*dfx-fx) > 0.0;
+        if newton_leaves_bracket || (2.0*fx).abs() > (previous_step*dfx).abs() {
+            previous_step = step;
+            step = 0.5*(high-low);
+            x = low+step;
+        }
+        else {
+            previous_step = step;
+            step = fx/dfx;
+            x -= step;
+        }
+        if step.abs() < tolerance {
+            return Ok(x);
+        }
+        fx = f(x);
+        dfx = derivative(x);
+        if fx == 0.0 {
+            return Ok(x);
+        }
+        if fx < 0.0 {
+            low = x;
+        }
+        else {
+            high = x;
+        }
+    }
+    Err(PricingError::NoConvergence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw_formulas;
+
+    #[test]
+    fn brent_test(){
+        let root = brent(&|x| x*x*x-2.0*x-5.0, 2.0, 3.0, 1e-12).unwrap();
+        assert!((root-2.0945514815423265).abs()<1e-11);
+        assert_eq!(brent(&|x| x*x+1.0, -1.0, 1.0, 1e-12), Err(PricingError::RootNotBracketed));
+    }
+
+    #[test]
+    fn safeguarded_newton_test(){
+        // Implied volatility of a call priced at 20% volatility, starting from a wide bracket.
+        let price = raw_formulas::european_call_option_price(100.0, 110.0, 0.05, 1.0, 0.2, 0.0);
+        let volatility = safeguarded_newton(&|v| raw_formulas::european_call_option_price(100.0, 110.0, 0.05, 1.0, v, 0.0)-price,
+            &|v| raw_formulas::call_vega(100.0, 110.0, 0.05, 1.0, v, 0.0), 0.01, 3.0, 1e-12).unwrap();
+        assert!((volatility-0.2).abs()<1e-9);
+        // atan has a Newton iteration which diverges far from the root, so bisection has to take over.
+        let root = safeguarded_newton(&|x| x.atan(), &|x| 1.0/(1.0+x*x), -20.0, 10.0, 1e-12).unwrap();
+        assert!(root.abs()<1e-12);
+    }
+}
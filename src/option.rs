@@ -1,8 +1,17 @@
 //! Provides struct representing derivative options.
+//!
+//! The options share their underlying stock through an `Arc`, and their payoff and averaging functions are `Send + Sync`, so
+//! the options are `Send + Sync` themselves and can be priced concurrently from several threads.
 
-use crate::stock::{GeometricBrownianMotionStock, StockState};
-use crate::utils::{NonNegativeFloat, TimeStamp};
-use std::rc::Rc;
+use crate::payoff::VanillaType;
+use crate::error::PricingError;
+use crate::greeks::{finite_difference, settled_greeks, Greeks, HasGreeks, GREEKS_NUMBER_OF_PATHS, GREEKS_SEED};
+use crate::path_generator::Path;
+use crate::payoff::{ClosurePayoff, Payoff};
+use crate::raw_formulas::BsCalculator;
+use crate::stock::{DividentYield, GeometricBrownianMotionStock, StockState};
+use crate::utils::{CorrelationMatrix, Duration, NonNegativeFloat, TimeStamp};
+use std::sync::Arc;
 
 
 /// A trait indicating that the implementing struct is a state of the underlying of some option.
@@ -19,45 +28,125 @@ impl Underlying for GeometricBrownianMotionStock {
 /// A trait indicating that the class implementing it is an option that can be priced
 pub trait DerivativeOption<T: Underlying> {
     /// Returns the time to expiry of the option, or None if the option expired.
-    fn get_time_to_expiry(&self)->Option<TimeStamp>;
+    fn get_time_to_expiry(&self)->Option<Duration>;
     /// Returns the number of random samples needed to price one path of the option.
     fn get_dimensionality(&self)->usize;
     /// Prices the option (not discounted) given one path of the underlying.
     /// #Parameters
     /// - `random_samples` - a vector of iid random samples of length `self.get_dimensionality()` from whatever distribution the option needs.
     /// - `r` - the short rate of interest.
-    fn price_path(&self, random_samples: &Vec<f64>,r: f64)->f64;
+    fn price_path(&self, random_samples: &[f64],r: f64)->f64;
+    /// Same as `price_path`, but returns a `PricingError` instead of panicking if `random_samples` is too short. An option at or
+    /// past expiry returns its settlement value when it is known, and `PricingError::ExpiredOption` if it expired otherwise.
+    fn try_price_path(&self, random_samples: &[f64], r: f64)->Result<f64, PricingError>{
+        match self.get_time_to_expiry() {
+            None => return self.settlement_value(r),
+            Some(tau) if f64::from(tau) == 0.0 => if let Ok(value) = self.settlement_value(r) {
+                return Ok(value);
+            },
+            Some(_) => {},
+        }
+        if random_samples.len()<self.get_dimensionality(){
+            return Err(PricingError::InsufficientSamples);
+        }
+        Ok(self.price_path(random_samples, r))
+    }
+    /// Returns the path of the underlying simulated by `price_path` from the same random samples, starting with the states
+    /// already observed, for debugging payoffs and plotting. Options that do not expose their path return an empty vector.
+    fn simulate_path(&self, _random_samples: &[f64], _r: f64)->Vec<StockState>{
+        Vec::new()
+    }
+    /// Returns the (undiscounted) value paid by the option when it is already determined, i.e. at or after expiry, so that options
+    /// at or past expiry can be valued without simulation. Options whose settlement value cannot be determined from the recorded
+    /// states of their underlying return `PricingError::ExpiredOption`, which is also the default.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        Err(PricingError::ExpiredOption)
+    }
+}
+
+/// A trait for options on a single underlying whose payoff only depends on the values of the underlying at a schedule of times,
+/// so that they can be priced on the paths of any model implementing `path_generator::PathGenerator`.
+///
+/// The options keep their recorded history and their current time, so the schedule only holds the future times. The paths
+/// are usually generated from the current state of the underlying of the option, e.g. by a `HestonStock` with the same spot.
+///
+/// A `Path` holds the values of a single underlying, so the options on several stocks (`SpreadOption`, `BasketAsianOption` and
+/// `HimalayanOption`) do not implement this trait, and are only priced on correlated geometric Brownian motions.
+pub trait PathPayoff {
+    /// Returns the time at which the payoff is paid, to which it is discounted.
+    fn get_payment_time(&self)->TimeStamp;
+
+    /// Returns the future times at which the payoff observes the underlying, i.e. the times of the paths it is priced on.
+    fn get_schedule(&self)->Vec<TimeStamp>;
+
+    /// Returns the (undiscounted) payoff given a path of the underlying at the times of `get_schedule`.
+    /// # Parameters
+    /// - `path` - the values of the underlying at the schedule.
+    /// - `r` - the short rate of interest, for payoffs accruing cash flows before expiry.
+    /// # Panics
+    /// Panics if `path` misses a time of the schedule, or if the option needs a past value of the underlying which was not
+    /// recorded.
+    fn path_payoff(&self, path: &Path, r: f64)->f64;
+}
+
+/// Returns the risk neutral path of `stock` at `schedule`, using one sample of `random_samples` per time.
+fn stock_path(stock: &GeometricBrownianMotionStock, schedule: &[TimeStamp], random_samples: &[f64], r: f64)->Path{
+    if schedule.is_empty() {
+        return Path::new(Vec::new(), Vec::new());
+    }
+    Path::from_states(&stock.generate_risk_neutral_path_from_time_stamps(random_samples, schedule, r))
 }
 
+/// A payoff function. Gets the value (or average value) of the underlying asset and a slice of parameters such as strike price.
+pub type PayoffFunction = Box<dyn Fn(NonNegativeFloat, &[f64])->f64+Send+Sync>;
+
+/// An averaging function. Gets a slice of states of the underlying stock and a slice of monitoring times, and computes an average.
+pub type AverageFunction = Box<dyn Fn(&[StockState], &[TimeStamp])->NonNegativeFloat+Send+Sync>;
+
+/// An averaging function shared between an option and the copies of it built by `HasGreeks::with_underlying`.
+type SharedAverageFunction = Arc<dyn Fn(&[StockState], &[TimeStamp])->NonNegativeFloat+Send+Sync>;
+
 /// A struct implementing a vanilla option, i.e. an option whose payoff only depends on the value of the underlying
-/// asset at exercise time.
-pub struct VanillaStockOption{
+/// asset at exercise time. Options with one of the payoffs of the `payoff` module can be cloned and compared.
+#[derive(Clone, PartialEq)]
+pub struct VanillaStockOption<P: Payoff = ClosurePayoff>{
     ///A shared reference to the underlying stock.
-    underlying_stock: Rc<GeometricBrownianMotionStock>,
+    underlying_stock: Arc<GeometricBrownianMotionStock>,
     /// The time of expiry.
     expiry: TimeStamp,
-    /// The payoff function of the option. Gets the value of the underlying asset at exercise time and a boxed vector of
-    /// parameters such as strike price.
-    payoff_function: Box<dyn Fn(NonNegativeFloat, &Box<Vec<f64>>)->f64>,
-    /// A boxed vector of whatever parameters are needed to compute the payoff function, e.g. strike price.
-    params: Box<Vec<f64>>,
+    /// The payoff of the option, as a function of the value of the underlying asset at exercise time.
+    payoff: P,
 }
 
 impl VanillaStockOption {
+    /// Returns a new vanilla option with a payoff given by a boxed function.
+    /// # Parameters
+    /// - `underlying_stock`: A shared reference to the underlying stock.
+    /// - `expiry`: The expiry time.
+    /// - `payoff_function`: A boxed payoff function. The function gets the value of the underlying asset at exercise time and a slice of parameters such as strike price.
+    /// - `params`: A vector of parameters, for the payoff function.
+    pub fn new(underlying_stock: &Arc<GeometricBrownianMotionStock>, expiry:TimeStamp, payoff_function: PayoffFunction, params: Vec<f64>)->VanillaStockOption{
+        VanillaStockOption::with_payoff(underlying_stock, expiry, ClosurePayoff::new(payoff_function, params))
+    }
+}
+
+impl<P: Payoff> VanillaStockOption<P> {
     /// Returns a new vanilla option.
     /// # Parameters
     /// - `underlying_stock`: A shared reference to the underlying stock.
     /// - `expiry`: The expiry time.
-    /// - `payoff_function`: A boxed payoff function. The function gets the value of the underlying asset at exercise time and a boxed vector of parameters such as strike price.
-    /// - `params`: A boxed vector of parameters, for the payoff function.
-    pub fn new(underlying_stock: &Rc<GeometricBrownianMotionStock>, expiry:TimeStamp, payoff_function: Box<dyn Fn(NonNegativeFloat, &Box<Vec<f64>>)->f64>, params: Box<Vec<f64>>)->VanillaStockOption{
+    /// - `payoff`: The payoff of the option, e.g. a `CallPayoff`.
+    pub fn with_payoff(underlying_stock: &Arc<GeometricBrownianMotionStock>, expiry: TimeStamp, payoff: P)->VanillaStockOption<P>{
         VanillaStockOption{
-            underlying_stock: Rc::clone(&underlying_stock),
+            underlying_stock: Arc::clone(underlying_stock),
             expiry,
-            payoff_function,
-            params,
+            payoff,
         }
+    }
 
+    /// Returns the payoff of the option.
+    pub fn get_payoff(&self) -> &P{
+        &self.payoff
     }
 
     /// Returns the expiry of the option.
@@ -66,19 +155,15 @@ impl VanillaStockOption {
     }
 
     /// Returns the underlying stock of the option.
-    pub fn get_underlying(&self) -> Rc<GeometricBrownianMotionStock>{
+    pub fn get_underlying(&self) -> Arc<GeometricBrownianMotionStock>{
         self.underlying_stock.clone()
     }
 }
 
-impl DerivativeOption<GeometricBrownianMotionStock> for VanillaStockOption {
+impl<P: Payoff> DerivativeOption<GeometricBrownianMotionStock> for VanillaStockOption<P> {
     ///Returns the time to expiry of the option, where the current time is considered to be the current time of the underlying stock.
-    fn get_time_to_expiry(&self)->Option<TimeStamp> {
-        let x=f64::from(self.expiry)-f64::from(self.underlying_stock.get_current_state().get_time());
-        if x<0.0{
-            return None;
-        }
-        Some(NonNegativeFloat::from(x))
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.expiry.checked_duration_since(self.underlying_stock.get_current_state().get_time())
     }
     
     /// Returns the number of random samples needed to price one path of the option.
@@ -90,36 +175,97 @@ impl DerivativeOption<GeometricBrownianMotionStock> for VanillaStockOption {
     /// #Parameters
     /// - `random_samples` - a vector with (at least...) one Gaussian sample.
     /// - `r` - the short rate of interest.
-    fn price_path(&self, random_samples: &Vec<f64>, r: f64)->f64 {
-        if random_samples.len()< 1{
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        if random_samples.is_empty(){
             panic!("Incorrect length of random_samples");
         }
         if self.expiry < self.underlying_stock.get_current_state().get_time(){
-            panic!("The option expiered!")
+            return self.settlement_value(r).unwrap_or_else(|e| panic!("{}", e));
+        }
+        self.path_payoff(&stock_path(&self.underlying_stock, &self.get_schedule(), random_samples, r), r)
+    }
+
+    /// Returns the current state of the stock and its simulated state at expiry.
+    fn simulate_path(&self, random_samples: &[f64], r: f64)->Vec<StockState>{
+        let mut path = vec![self.underlying_stock.get_current_state()];
+        path.append(&mut self.underlying_stock.generate_risk_neutral_path_from_time_stamps(random_samples, &[self.expiry], r));
+        path
+    }
+
+    /// Returns the payoff of the current value of the stock if the option is exactly at expiry. Past expiry the value of the
+    /// stock at expiry is unknown, and `PricingError::ExpiredOption` is returned.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        let current_state = self.underlying_stock.get_current_state();
+        if current_state.get_time() == self.expiry {
+            Ok(self.payoff.value(current_state.get_value()))
+        }
+        else {
+            Err(PricingError::ExpiredOption)
+        }
+    }
+}
+
+impl<P: Payoff> PathPayoff for VanillaStockOption<P> {
+    fn get_payment_time(&self)->TimeStamp{
+        self.expiry
+    }
+
+    fn get_schedule(&self)->Vec<TimeStamp>{
+        vec![self.expiry]
+    }
+
+    fn path_payoff(&self, path: &Path, _r: f64)->f64{
+        let value = path.value_at(self.expiry).expect("The path has no value at the expiry.");
+        self.payoff.value(NonNegativeFloat::from(value))
+    }
+}
+
+impl<P: Payoff+Clone> HasGreeks for VanillaStockOption<P> {
+    fn get_underlying(&self)->Arc<GeometricBrownianMotionStock>{
+        Arc::clone(&self.underlying_stock)
+    }
+
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->VanillaStockOption<P>{
+        VanillaStockOption::with_payoff(stock, self.expiry, self.payoff.clone())
+    }
+
+    /// Returns the Black-Scholes greeks of calls and puts on a stock with a constant volatility and divident rate, and the Monte Carlo
+    /// greeks otherwise. At or past expiry, see `HasGreeks::try_greeks`.
+    fn greeks(&self, r: f64)->Greeks{
+        if let Some(settled) = settled_greeks(self, r) {
+            return settled.unwrap_or_else(|e| panic!("{}", e));
+        }
+        let stock = &self.underlying_stock;
+        let time_to_expiry = f64::from(self.get_time_to_expiry().unwrap_or(Duration::from(0.0)));
+        match self.payoff.as_vanilla() {
+            Some((vanilla_type, strike)) if stock.get_volatility_curve().is_constant()
+                && matches!(stock.get_divident_yield(), DividentYield::Constant(_)) => {
+                let calculator = BsCalculator::new(f64::from(stock.get_current_state().get_value()), strike, r, time_to_expiry,
+                    f64::from(stock.get_volatility()), stock.get_average_divident_rate(Duration::from(time_to_expiry)));
+                match vanilla_type {
+                    VanillaType::Call => calculator.call_greeks(),
+                    VanillaType::Put => calculator.put_greeks(),
+                }
+            },
+            _ => finite_difference::monte_carlo_greeks(stock, |stock| self.with_underlying(stock), r, GREEKS_SEED,
+                GREEKS_NUMBER_OF_PATHS, &finite_difference::BumpSizes::default()),
         }
-        let time_stamps=vec![self.expiry];
-        let state=self.underlying_stock.generate_risk_neutral_path_from_time_stamps(random_samples, &time_stamps, r);
-        (self.payoff_function)(state[0].get_value(), &self.params)
     }
-    
 }
 
-pub struct AsianOption{
+pub struct AsianOption<P: Payoff = ClosurePayoff>{
     ///A shared reference to the underlying stock.
-    underlying_stock: Rc<GeometricBrownianMotionStock>,
+    underlying_stock: Arc<GeometricBrownianMotionStock>,
     /// The time of expiry.
     expiry: TimeStamp,
     /// A vector of the times at which the value of the underlying stock will be used for the average.
     monitoring_times: Vec<TimeStamp>,
     /// A vector of states of the underlying stock.
     history: Vec<StockState>,
-    /// A boxed function that gets a vector of states of the underlying stock and a vector of monitoring times, and computes an average.
-    average_function: Box<dyn Fn(&Vec<StockState>, &Vec<TimeStamp>)->NonNegativeFloat>,
-    /// A boxed function that gets the average of the underlying stock, as computed by `self.average_function` and a boxed vector of parameters, and evaluates the payoff of the option.
-    payoff_function: Box<dyn Fn(NonNegativeFloat, &Box<Vec<f64>>)->f64>,
-    /// A boxed vector of whatever parameters are needed to compute the payoff function, e.g. strike price.
-    params: Box<Vec<f64>>,
-    
+    /// A function that gets a slice of states of the underlying stock and a slice of monitoring times, and computes an average.
+    average_function: SharedAverageFunction,
+    /// The payoff of the option, as a function of the average of the underlying stock computed by `self.average_function`.
+    payoff: P,
 }
 
 
@@ -129,21 +275,52 @@ impl AsianOption{
     /// - `underlying_stock`: A shared reference to the underlying stock.
     /// - `expiry`: The expiry time.
     /// - `monitoring_times`: A vector of the times at which the value of the underlying stock will be used for the average. Needs to be sorted with unique values. 
-    /// - `average_function`: A boxed function that gets a vector of states of the underlying stock and a vector of monitoring times, and computes an average.
-    /// - `payoff_function`: A boxed payoff function. The function gets the value of the underlying asset at exercise time and a boxed vector of parameters such as strike price.
-    /// - `params`: A boxed vector of parameters, for the payoff function.
-    pub fn new(underlying_stock: &Rc<GeometricBrownianMotionStock>, expiry: TimeStamp, monitoring_times: &Vec<TimeStamp>, average_function: Box<dyn Fn(&Vec<StockState>, &Vec<TimeStamp>)->NonNegativeFloat>,
-        payoff_function: Box<dyn Fn(NonNegativeFloat, &Box<Vec<f64>>)->f64>, params: Box<Vec<f64>>,)->AsianOption{
-            AsianOption{
-                underlying_stock: underlying_stock.clone(),
-                expiry,
-                monitoring_times: monitoring_times.clone(),
-                history: vec![underlying_stock.get_current_state()],
-                average_function,
-                payoff_function,
-                params,
-            }
+    /// - `average_function`: A boxed function that gets a slice of states of the underlying stock and a slice of monitoring times, and computes an average.
+    /// - `payoff_function`: A boxed payoff function. The function gets the value of the underlying asset at exercise time and a slice of parameters such as strike price.
+    /// - `params`: A vector of parameters, for the payoff function.
+    pub fn new(underlying_stock: &Arc<GeometricBrownianMotionStock>, expiry: TimeStamp, monitoring_times: &[TimeStamp], average_function: AverageFunction,
+        payoff_function: PayoffFunction, params: Vec<f64>,)->AsianOption{
+            AsianOption::with_payoff(underlying_stock, expiry, monitoring_times, average_function, ClosurePayoff::new(payoff_function, params))
+        }
+}
+
+impl<P: Payoff> AsianOption<P>{
+    /// Returnes a new Asian option.
+    /// # Parameters:
+    /// - `underlying_stock`: A shared reference to the underlying stock.
+    /// - `expiry`: The expiry time.
+    /// - `monitoring_times`: A vector of the times at which the value of the underlying stock will be used for the average. Needs to be sorted with unique values. 
+    /// - `average_function`: A boxed function that gets a slice of states of the underlying stock and a slice of monitoring times, and computes an average.
+    /// - `payoff`: The payoff of the option as a function of the average, e.g. a `CallPayoff`.
+    pub fn with_payoff(underlying_stock: &Arc<GeometricBrownianMotionStock>, expiry: TimeStamp, monitoring_times: &[TimeStamp],
+        average_function: AverageFunction, payoff: P)->AsianOption<P>{
+        AsianOption{
+            underlying_stock: underlying_stock.clone(),
+            expiry,
+            monitoring_times: monitoring_times.to_vec(),
+            history: vec![underlying_stock.get_current_state()],
+            average_function: Arc::from(average_function),
+            payoff,
         }
+    }
+
+    /// Returnes a new Asian option with one of the built-in averages, so no averaging function needs to be written.
+    /// Averaging over part of the life of the option is done by passing monitoring times in a window, e.g. from `averaging_window`.
+    /// # Parameters:
+    /// - `underlying_stock`: A shared reference to the underlying stock.
+    /// - `expiry`: The expiry time.
+    /// - `monitoring_times`: A vector of the times at which the value of the underlying stock will be used for the average. Needs to be sorted with unique values.
+    /// - `averaging`: The type of the average.
+    /// - `payoff`: The payoff of the option as a function of the average, e.g. a `CallPayoff`.
+    pub fn with_averaging(underlying_stock: &Arc<GeometricBrownianMotionStock>, expiry: TimeStamp, monitoring_times: &[TimeStamp],
+        averaging: AveragingType, payoff: P)->AsianOption<P>{
+        AsianOption::with_payoff(underlying_stock, expiry, monitoring_times, averaging.average_function(), payoff)
+    }
+
+    /// Returns the payoff of the option.
+    pub fn get_payoff(&self) -> &P{
+        &self.payoff
+    }
     
     /// Updates the option with the current state of the underlying stock.
     pub  fn update(&mut self){
@@ -154,14 +331,10 @@ impl AsianOption{
     }
 }
 
-impl DerivativeOption<GeometricBrownianMotionStock> for AsianOption {
+impl<P: Payoff> DerivativeOption<GeometricBrownianMotionStock> for AsianOption<P> {
     /// Returns the time to expiry of the option, or None if the option expiered.
-    fn get_time_to_expiry(&self)->Option<TimeStamp> {
-        let x=f64::from(self.expiry)-f64::from(self.underlying_stock.get_current_state().get_time());
-        if x<0.0{
-            return None;
-        }
-        Some(NonNegativeFloat::from(x))
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.expiry.checked_duration_since(self.underlying_stock.get_current_state().get_time())
     }
     
     /// Returns the number of random samples needed to price one path of the option.
@@ -178,24 +351,1808 @@ impl DerivativeOption<GeometricBrownianMotionStock> for AsianOption {
     /// #Parameters
     /// - `random_samples` - a vector of iid random samples of length `self.get_dimensionality()` from whatever distribution the option needs.
     /// - `r` - the short rate of interest.
-    fn price_path(&self, random_samples: &Vec<f64>, r: f64) ->f64{
+    fn price_path(&self, random_samples: &[f64], r: f64) ->f64{
+        self.path_payoff(&stock_path(&self.underlying_stock, &self.get_schedule(), random_samples, r), r)
+    }
+
+    /// Returns the recorded history of the stock, followed by its simulated states at the future monitoring times.
+    fn simulate_path(&self, random_samples: &[f64], r: f64)->Vec<StockState>{
+        self.history_with_path(&stock_path(&self.underlying_stock, &self.get_schedule(), random_samples, r))
+    }
+
+    /// Returns the payoff of the average of the recorded history of the stock, if the option is at or past expiry.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        let current_state = self.underlying_stock.get_current_state();
+        if current_state.get_time() < self.expiry {
+            return Err(PricingError::ExpiredOption);
+        }
+        let mut history = self.history.clone();
+        if current_state.get_time() != history[history.len()-1].get_time(){
+            history.push(current_state);
+        }
+        Ok(self.payoff.value((*self.average_function)(&history, &self.monitoring_times)))
+    }
+    
+
+}
+
+impl<P: Payoff> AsianOption<P> {
+    /// Returns the recorded history of the stock, including its current state, followed by the states of `path`.
+    fn history_with_path(&self, path: &Path)->Vec<StockState>{
         let mut history = self.history.clone();
         if self.underlying_stock.get_current_state().get_time()!=history[history.len()-1].get_time(){
             history.push(self.underlying_stock.get_current_state());
         }
-        let t0=history[history.len()-1].get_time();
-        let mut time_stamps=Vec::new();
-        for t in self.monitoring_times.iter(){
-            if *t>t0{
-                time_stamps.push(*t);
-            }
+        history.extend(path.get_times().iter().zip(path.get_values()).map(|(t, v)| StockState::new(NonNegativeFloat::from(*v), *t)));
+        history
+    }
+}
+
+impl<P: Payoff> PathPayoff for AsianOption<P> {
+    fn get_payment_time(&self)->TimeStamp{
+        self.expiry
+    }
+
+    /// Returns the monitoring times after the last recorded state of the stock.
+    fn get_schedule(&self)->Vec<TimeStamp>{
+        let current_time = self.underlying_stock.get_current_state().get_time().max(self.history[self.history.len()-1].get_time());
+        self.monitoring_times.iter().filter(|t| **t > current_time).copied().collect()
+    }
+
+    fn path_payoff(&self, path: &Path, _r: f64)->f64{
+        self.payoff.value((*self.average_function)(&self.history_with_path(path), &self.monitoring_times))
+    }
+}
+
+impl<P: Payoff+Clone> HasGreeks for AsianOption<P> {
+    fn get_underlying(&self)->Arc<GeometricBrownianMotionStock>{
+        Arc::clone(&self.underlying_stock)
+    }
+
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->AsianOption<P>{
+        AsianOption{
+            underlying_stock: Arc::clone(stock),
+            expiry: self.expiry,
+            monitoring_times: self.monitoring_times.clone(),
+            history: rebased_history(&self.history, stock),
+            average_function: Arc::clone(&self.average_function),
+            payoff: self.payoff.clone(),
         }
-        let mut v=self.underlying_stock.generate_risk_neutral_path_from_time_stamps(random_samples, &time_stamps, r);
-        history.append(&mut v);
-        (*self.payoff_function)((*self.average_function)(&history, &self.monitoring_times), &self.params)
     }
-    
+}
+
+
+/// The type of the average of an Asian option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AveragingType{
+    /// The arithmetic mean of the fixings.
+    Arithmetic,
+    /// The geometric mean of the fixings.
+    Geometric,
+}
+
+impl AveragingType {
+    /// Returns the average of `values`.
+    /// # Panics
+    /// Panics if `values` is empty.
+    pub fn average(&self, values: &[f64])->f64{
+        if values.is_empty(){
+            panic!("Cannot average an empty slice.");
+        }
+        let n = values.len() as f64;
+        match self {
+            AveragingType::Arithmetic => values.iter().sum::<f64>()/n,
+            AveragingType::Geometric => (values.iter().map(|v| v.ln()).sum::<f64>()/n).exp(),
+        }
+    }
+
+    /// Returns an `AverageFunction` computing this average of the values of the stock at the monitoring times.
+    /// Values at monitoring times that fall between two recorded states are interpolated linearly, and values after
+    /// the last recorded state are taken to be the last recorded value.
+    pub fn average_function(self)->AverageFunction{
+        Box::new(move |states: &[StockState], monitoring_times: &[TimeStamp]|{
+            NonNegativeFloat::from(self.average(&values_at_times(states, monitoring_times)))
+        })
+    }
+}
+
+/// Returns the values of the states at `times`, interpolated linearly between states. Both need to be sorted.
+fn values_at_times(states: &[StockState], times: &[TimeStamp])->Vec<f64>{
+    let mut values = Vec::with_capacity(times.len());
+    let mut j = 0;
+    for t in times.iter(){
+        while j < states.len() && states[j].get_time() < *t{
+            j += 1;
+        }
+        if j == states.len(){
+            values.push(f64::from(states[states.len()-1].get_value()));
+        }
+        else if states[j].get_time() == *t || j == 0{
+            values.push(f64::from(states[j].get_value()));
+        }
+        else {
+            let a = (f64::from(states[j].get_time())-f64::from(*t))/(f64::from(states[j].get_time())-f64::from(states[j-1].get_time()));
+            values.push(a*f64::from(states[j-1].get_value())+(1.0-a)*f64::from(states[j].get_value()));
+        }
+    }
+    values
+}
+
+/// Returns `number_of_fixings` evenly spaced monitoring times from `start` to `end` (both included), for averaging over part of the
+/// life of an option. A single fixing is taken at `end`.
+/// # Panics
+/// Panics if `number_of_fixings` is 0 or `end` is before `start`.
+pub fn averaging_window(start: TimeStamp, end: TimeStamp, number_of_fixings: usize)->Vec<TimeStamp>{
+    if number_of_fixings == 0 || end < start{
+        panic!("An averaging window needs at least one fixing and must not end before it starts.");
+    }
+    if number_of_fixings == 1{
+        return vec![end];
+    }
+    let step = (f64::from(end)-f64::from(start))/(number_of_fixings-1) as f64;
+    (0..number_of_fixings).map(|i| TimeStamp::from(f64::from(start)+i as f64*step)).collect()
+}
+
+/// A floating strike Asian option, whose strike is a multiple of the average of the underlying stock over the monitoring times.
+/// The payoff of a call is `max(S_T-k*A, 0)` and of a put is `max(k*A-S_T, 0)`, where `S_T` is the value of the stock at expiry,
+/// `A` the average and `k` the strike multiplier.
+pub struct FloatingStrikeAsianOption{
+    ///A shared reference to the underlying stock.
+    underlying_stock: Arc<GeometricBrownianMotionStock>,
+    /// The time of expiry.
+    expiry: TimeStamp,
+    /// The times at which the value of the underlying stock is used for the average.
+    monitoring_times: Vec<TimeStamp>,
+    /// The monitoring times followed by the expiry, if it is not a monitoring time.
+    fixing_times: Vec<TimeStamp>,
+    /// The type of the average.
+    averaging: AveragingType,
+    /// Whether the option is a call or a put.
+    vanilla_type: VanillaType,
+    /// The multiple of the average used as the strike.
+    strike_multiplier: f64,
+    /// A vector of states of the underlying stock.
+    history: Vec<StockState>,
+}
+
+impl FloatingStrikeAsianOption {
+    /// Returns a new floating strike Asian option.
+    /// # Parameters
+    /// - `underlying_stock`: A shared reference to the underlying stock.
+    /// - `expiry`: The expiry time.
+    /// - `monitoring_times`: The times at which the value of the underlying stock is used for the average. Needs to be sorted with unique
+    ///   values, none of them after the expiry.
+    /// - `averaging`: The type of the average.
+    /// - `vanilla_type`: Whether the option is a call or a put.
+    /// - `strike_multiplier`: The multiple of the average used as the strike, usually 1.
+    /// # Panics
+    /// Panics if there are no monitoring times, or one of them is after the expiry.
+    pub fn new(underlying_stock: &Arc<GeometricBrownianMotionStock>, expiry: TimeStamp, monitoring_times: &[TimeStamp], averaging: AveragingType,
+        vanilla_type: VanillaType, strike_multiplier: f64)->FloatingStrikeAsianOption{
+        if monitoring_times.is_empty() || monitoring_times[monitoring_times.len()-1] > expiry{
+            panic!("A floating strike Asian option needs monitoring times, none of them after the expiry.");
+        }
+        let mut fixing_times = monitoring_times.to_vec();
+        if monitoring_times[monitoring_times.len()-1] < expiry{
+            fixing_times.push(expiry);
+        }
+        FloatingStrikeAsianOption{
+            underlying_stock: Arc::clone(underlying_stock),
+            expiry,
+            monitoring_times: monitoring_times.to_vec(),
+            fixing_times,
+            averaging,
+            vanilla_type,
+            strike_multiplier,
+            history: vec![underlying_stock.get_current_state()],
+        }
+    }
+
+    /// Returns the expiry of the option.
+    pub fn get_expiry(&self) -> TimeStamp{
+        self.expiry
+    }
+
+    /// Returns the type of the average.
+    pub fn get_averaging(&self) -> AveragingType{
+        self.averaging
+    }
+
+    /// Returns whether the option is a call or a put.
+    pub fn get_vanilla_type(&self) -> VanillaType{
+        self.vanilla_type
+    }
+
+    /// Updates the option with the current state of the underlying stock. Should be called at every past monitoring time.
+    pub fn update(&mut self){
+        if self.history[self.history.len()-1].get_time() == self.underlying_stock.get_current_state().get_time() {
+            return;
+        }
+        self.history.push(self.underlying_stock.get_current_state());
+    }
+
+    /// Returns the payoff given the values of the underlying at the fixing times.
+    fn payoff(&self, fixings: &[f64])->f64{
+        let strike = self.strike_multiplier*self.averaging.average(&fixings[..self.monitoring_times.len()]);
+        let terminal = fixings[fixings.len()-1];
+        match self.vanilla_type {
+            VanillaType::Call => f64::max(terminal-strike, 0.0),
+            VanillaType::Put => f64::max(strike-terminal, 0.0),
+        }
+    }
+}
+
+impl DerivativeOption<GeometricBrownianMotionStock> for FloatingStrikeAsianOption {
+    /// Returns the time to expiry of the option, or None if the option expiered.
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.expiry.checked_duration_since(self.underlying_stock.get_current_state().get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option, i.e. the number of future fixing times.
+    fn get_dimensionality(&self)->usize {
+        let current_time = self.underlying_stock.get_current_state().get_time();
+        self.fixing_times.iter().filter(|t| **t > current_time).count()
+    }
+
+    /// Prices the option (not discounted) given one path of the underlying.
+    /// #Parameters
+    /// - `random_samples` - a vector of iid Gaussian samples of length `self.get_dimensionality()`.
+    /// - `r` - the short rate of interest.
+    /// # Panics
+    /// Panics if the state of the underlying at a past monitoring time was not recorded with `update`.
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        self.path_payoff(&stock_path(&self.underlying_stock, &self.get_schedule(), random_samples, r), r)
+    }
+
+    /// Returns the payoff of the recorded fixings, if the option is at or past expiry and all of them were recorded with `update`.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        let fixings = recorded_fixings(&self.underlying_stock, &self.history, &self.fixing_times).ok_or(PricingError::ExpiredOption)?;
+        Ok(self.payoff(&fixings))
+    }
+
+    /// Returns the current state of the stock and its simulated states at the future fixing times.
+    fn simulate_path(&self, random_samples: &[f64], r: f64)->Vec<StockState>{
+        let current_state = self.underlying_stock.get_current_state();
+        let future_times: Vec<TimeStamp> = self.fixing_times.iter().filter(|t| **t > current_state.get_time()).copied().collect();
+        let mut path = vec![current_state];
+        path.append(&mut self.underlying_stock.generate_risk_neutral_path_from_time_stamps(random_samples, &future_times, r));
+        path
+    }
+}
+
+impl PathPayoff for FloatingStrikeAsianOption {
+    fn get_payment_time(&self)->TimeStamp{
+        self.expiry
+    }
+
+    fn get_schedule(&self)->Vec<TimeStamp>{
+        future_times(&self.underlying_stock, &self.fixing_times)
+    }
+
+    fn path_payoff(&self, path: &Path, _r: f64)->f64{
+        self.payoff(&fixings_on_path(&self.underlying_stock, &self.history, &self.fixing_times, path))
+    }
+}
+
+impl HasGreeks for FloatingStrikeAsianOption {
+    fn get_underlying(&self)->Arc<GeometricBrownianMotionStock>{
+        Arc::clone(&self.underlying_stock)
+    }
+
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->FloatingStrikeAsianOption{
+        FloatingStrikeAsianOption{
+            history: rebased_history(&self.history, stock),
+            ..FloatingStrikeAsianOption::new(stock, self.expiry, &self.monitoring_times, self.averaging, self.vanilla_type, self.strike_multiplier)
+        }
+    }
+}
+
+/// A european spread option on two stocks, with payoff `max(S1-S2-K, 0)` at expiry.
+/// The terminal values of the stocks are sampled exactly, so Monte Carlo pricing has no discretization error.
+/// Both stocks are assumed to be observed at the same current time.
+pub struct SpreadOption{
+    /// A shared reference to the first (long) stock.
+    first_stock: Arc<GeometricBrownianMotionStock>,
+    /// A shared reference to the second (short) stock.
+    second_stock: Arc<GeometricBrownianMotionStock>,
+    /// The correlation between the Brownian motions driving the two stocks.
+    correlation: f64,
+    /// The strike of the spread. Can be negative.
+    strike: f64,
+    /// The time of expiry.
+    expiry: TimeStamp,
+}
+
+impl SpreadOption {
+    /// Returns a new spread option.
+    /// # Parameters
+    /// - `first_stock`: A shared reference to the first (long) stock.
+    /// - `second_stock`: A shared reference to the second (short) stock.
+    /// - `correlation`: The correlation between the Brownian motions driving the two stocks.
+    /// - `strike`: The strike of the spread.
+    /// - `expiry`: The expiry time.
+    /// # Panics
+    /// Panics if `correlation` is not in [-1,1].
+    pub fn new(first_stock: &Arc<GeometricBrownianMotionStock>, second_stock: &Arc<GeometricBrownianMotionStock>, correlation: f64, strike: f64,
+        expiry: TimeStamp)->SpreadOption{
+        if !(-1.0..=1.0).contains(&correlation){
+            panic!("Correlation must be between -1 and 1.");
+        }
+        SpreadOption{
+            first_stock: Arc::clone(first_stock),
+            second_stock: Arc::clone(second_stock),
+            correlation,
+            strike,
+            expiry,
+        }
+    }
+
+    /// Returns the expiry of the option.
+    pub fn get_expiry(&self) -> TimeStamp{
+        self.expiry
+    }
+
+    /// Returns the strike of the option.
+    pub fn get_strike(&self) -> f64{
+        self.strike
+    }
+
+    /// Returns the correlation between the two stocks.
+    pub fn get_correlation(&self) -> f64{
+        self.correlation
+    }
+}
+
+impl DerivativeOption<GeometricBrownianMotionStock> for SpreadOption {
+    /// Returns the time to expiry of the option, where the current time is considered to be the current time of the first stock.
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.expiry.checked_duration_since(self.first_stock.get_current_state().get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option.
+    fn get_dimensionality(&self)->usize {
+        2
+    }
+
+    /// Prices the option (not discounted) given one path of the underlyings.
+    /// #Parameters
+    /// - `random_samples` - a vector with (at least...) two independent Gaussian samples, which are correlated internally.
+    /// - `r` - the short rate of interest.
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        if random_samples.len()<2{
+            panic!("Incorrect length of random_samples");
+        }
+        let time_stamps = [self.expiry];
+        let second_sample = self.correlation*random_samples[0]+(1.0-self.correlation*self.correlation).sqrt()*random_samples[1];
+        let first = self.first_stock.generate_risk_neutral_path_from_time_stamps(&random_samples[..1], &time_stamps, r);
+        let second = self.second_stock.generate_risk_neutral_path_from_time_stamps(&[second_sample], &time_stamps, r);
+        self.payoff(f64::from(first[0].get_value()), f64::from(second[0].get_value()))
+    }
+
+    /// Returns the payoff of the current values of the stocks if both are exactly at expiry. Past expiry the values of the
+    /// stocks at expiry are unknown, and `PricingError::ExpiredOption` is returned.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        let (first, second) = (self.first_stock.get_current_state(), self.second_stock.get_current_state());
+        if first.get_time() != self.expiry || second.get_time() != self.expiry {
+            return Err(PricingError::ExpiredOption);
+        }
+        Ok(self.payoff(f64::from(first.get_value()), f64::from(second.get_value())))
+    }
+}
+
+impl SpreadOption {
+    /// Returns the payoff given the values of the stocks at expiry.
+    fn payoff(&self, first: f64, second: f64)->f64{
+        f64::max(first-second-self.strike, 0.0)
+    }
+}
 
+/// An Asian option on a basket of stocks, whose payoff is a call or put on the arithmetic average over the monitoring times of the
+/// weighted sum of the stocks, i.e. `max(A-K, 0)` or `max(K-A, 0)` with `A = sum_t sum_i w_i*S_i(t)/n`.
+/// All the monitoring times need to be after the current time of the stocks, which are assumed to be observed at the same time.
+pub struct BasketAsianOption{
+    /// Shared references to the stocks in the basket.
+    stocks: Vec<Arc<GeometricBrownianMotionStock>>,
+    /// The weights of the stocks in the basket.
+    weights: Vec<f64>,
+    /// The correlation matrix of the Brownian motions driving the stocks.
+    correlation: CorrelationMatrix,
+    /// The monitoring times, sorted with unique values. The last one is the expiry.
+    monitoring_times: Vec<TimeStamp>,
+    /// Whether the option is a call or a put.
+    vanilla_type: VanillaType,
+    /// The strike of the option.
+    strike: f64,
+}
+
+impl BasketAsianOption {
+    /// Returns a new basket Asian option.
+    /// # Parameters
+    /// - `stocks`: Shared references to the stocks in the basket.
+    /// - `weights`: The weights of the stocks in the basket.
+    /// - `correlation`: The correlation matrix of the Brownian motions driving the stocks.
+    /// - `monitoring_times`: The monitoring times. Need to be sorted with unique values, after the current time of the stocks.
+    ///   The last monitoring time is the expiry.
+    /// - `vanilla_type`: Whether the option is a call or a put.
+    /// - `strike`: The strike of the option.
+    /// # Panics
+    /// Panics if there are no monitoring times, or the numbers of stocks, weights and rows of the correlation matrix differ.
+    pub fn new(stocks: &[Arc<GeometricBrownianMotionStock>], weights: &[f64], correlation: CorrelationMatrix, monitoring_times: &[TimeStamp],
+        vanilla_type: VanillaType, strike: f64)->BasketAsianOption{
+        if monitoring_times.is_empty() || stocks.len() != weights.len() || stocks.len() != correlation.get_dimension(){
+            panic!("A basket Asian option needs monitoring times, and a weight and a row of the correlation matrix for every stock.");
+        }
+        BasketAsianOption{
+            stocks: stocks.to_vec(),
+            weights: weights.to_vec(),
+            correlation,
+            monitoring_times: monitoring_times.to_vec(),
+            vanilla_type,
+            strike,
+        }
+    }
+
+    /// Returns the expiry of the option, i.e. the last monitoring time.
+    pub fn get_expiry(&self) -> TimeStamp{
+        self.monitoring_times[self.monitoring_times.len()-1]
+    }
+
+    /// Returns the weights of the stocks in the basket.
+    pub fn get_weights(&self) -> &[f64]{
+        &self.weights
+    }
+}
+
+impl DerivativeOption<GeometricBrownianMotionStock> for BasketAsianOption {
+    /// Returns the time to expiry of the option, where the current time is considered to be the current time of the first stock.
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.get_expiry().checked_duration_since(self.stocks[0].get_current_state().get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option, i.e. one per stock per monitoring time.
+    fn get_dimensionality(&self)->usize {
+        self.stocks.len()*self.monitoring_times.len()
+    }
+
+    /// Prices the option (not discounted) given one path of the underlyings.
+    /// #Parameters
+    /// - `random_samples` - a vector of iid Gaussian samples of length `self.get_dimensionality()`, ordered by monitoring time and then
+    ///   by stock, which are correlated internally.
+    /// - `r` - the short rate of interest.
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        self.payoff(&correlated_paths(&self.stocks, &self.correlation, &self.monitoring_times, random_samples, r))
+    }
+
+    /// Returns the payoff of the current values of the stocks if the expiry is the only monitoring time and all the stocks are
+    /// exactly at expiry. Otherwise the values of the stocks at some monitoring time are unknown, as the option does not record
+    /// them, and `PricingError::ExpiredOption` is returned.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        Ok(self.payoff(&settlement_paths(&self.stocks, &self.monitoring_times)?))
+    }
 }
 
+impl BasketAsianOption {
+    /// Returns the payoff given the values of the stocks at the monitoring times, one vector per stock.
+    fn payoff(&self, paths: &[Vec<f64>])->f64{
+        let average = paths.iter().zip(&self.weights).map(|(path, weight)| weight*path.iter().sum::<f64>()).sum::<f64>()
+            /self.monitoring_times.len() as f64;
+        match self.vanilla_type {
+            VanillaType::Call => f64::max(average-self.strike, 0.0),
+            VanillaType::Put => f64::max(self.strike-average, 0.0),
+        }
+    }
+}
+
+/// A Himalayan option on a basket of stocks. At every monitoring time, the performance `S_i(t)/S_i(0)` of the best performing stock
+/// still in the basket is recorded and the stock is removed from the basket. The payoff at the last monitoring time is
+/// `notional*max(P-K, 0)`, where `P` is the average of the recorded performances.
+/// All the monitoring times need to be after the current time of the stocks, which are assumed to be observed at the same time.
+pub struct HimalayanOption{
+    /// Shared references to the stocks in the basket.
+    stocks: Vec<Arc<GeometricBrownianMotionStock>>,
+    /// The correlation matrix of the Brownian motions driving the stocks.
+    correlation: CorrelationMatrix,
+    /// The monitoring times, sorted with unique values, one for every stock. The last one is the expiry.
+    monitoring_times: Vec<TimeStamp>,
+    /// The strike, in units of performance.
+    strike: f64,
+    /// The notional of the option.
+    notional: f64,
+}
 
+impl HimalayanOption {
+    /// Returns a new Himalayan option.
+    /// # Parameters
+    /// - `stocks`: Shared references to the stocks in the basket.
+    /// - `correlation`: The correlation matrix of the Brownian motions driving the stocks.
+    /// - `monitoring_times`: The monitoring times, one for every stock. Need to be sorted with unique values, after the current time
+    ///   of the stocks. The last monitoring time is the expiry.
+    /// - `strike`: The strike, in units of performance, e.g. 1 for an at the money option.
+    /// - `notional`: The notional of the option.
+    /// # Panics
+    /// Panics if the numbers of stocks, monitoring times and rows of the correlation matrix differ, or there are no stocks.
+    pub fn new(stocks: &[Arc<GeometricBrownianMotionStock>], correlation: CorrelationMatrix, monitoring_times: &[TimeStamp], strike: f64,
+        notional: f64)->HimalayanOption{
+        if stocks.is_empty() || stocks.len() != monitoring_times.len() || stocks.len() != correlation.get_dimension(){
+            panic!("A Himalayan option needs a monitoring time and a row of the correlation matrix for every stock.");
+        }
+        HimalayanOption{
+            stocks: stocks.to_vec(),
+            correlation,
+            monitoring_times: monitoring_times.to_vec(),
+            strike,
+            notional,
+        }
+    }
+
+    /// Returns the expiry of the option, i.e. the last monitoring time.
+    pub fn get_expiry(&self) -> TimeStamp{
+        self.monitoring_times[self.monitoring_times.len()-1]
+    }
+}
+
+impl DerivativeOption<GeometricBrownianMotionStock> for HimalayanOption {
+    /// Returns the time to expiry of the option, where the current time is considered to be the current time of the first stock.
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.get_expiry().checked_duration_since(self.stocks[0].get_current_state().get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option, i.e. one per stock per monitoring time.
+    fn get_dimensionality(&self)->usize {
+        self.stocks.len()*self.monitoring_times.len()
+    }
+
+    /// Prices the option (not discounted) given one path of the underlyings.
+    /// #Parameters
+    /// - `random_samples` - a vector of iid Gaussian samples of length `self.get_dimensionality()`, ordered by monitoring time and then
+    ///   by stock, which are correlated internally.
+    /// - `r` - the short rate of interest.
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        self.payoff(&correlated_paths(&self.stocks, &self.correlation, &self.monitoring_times, random_samples, r))
+    }
+
+    /// Returns the payoff of the current values of the stocks if the option is on a single stock, whose only monitoring time
+    /// is the expiry, and the stock is exactly at expiry. Otherwise the values of the stocks at some monitoring time are unknown,
+    /// as the option does not record them, and `PricingError::ExpiredOption` is returned.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        Ok(self.payoff(&settlement_paths(&self.stocks, &self.monitoring_times)?))
+    }
+}
+
+impl HimalayanOption {
+    /// Returns the payoff given the values of the stocks at the monitoring times, one vector per stock.
+    fn payoff(&self, paths: &[Vec<f64>])->f64{
+        let initial_values: Vec<f64> = self.stocks.iter().map(|stock| f64::from(stock.get_current_state().get_value())).collect();
+        let mut remaining = vec![true; self.stocks.len()];
+        let mut total_performance = 0.0;
+        for (j, _) in self.monitoring_times.iter().enumerate(){
+            let (best, performance) = (0..self.stocks.len()).filter(|i| remaining[*i])
+                .map(|i| (i, paths[i][j]/initial_values[i]))
+                .fold((0, f64::NEG_INFINITY), |best, candidate| if candidate.1 > best.1 { candidate } else { best });
+            remaining[best] = false;
+            total_performance += performance;
+        }
+        self.notional*f64::max(total_performance/self.monitoring_times.len() as f64-self.strike, 0.0)
+    }
+}
+
+/// Returns the current values of the stocks as their paths at `times`, one vector per stock, if the only time is the current
+/// time of all the stocks, and `PricingError::ExpiredOption` otherwise.
+fn settlement_paths(stocks: &[Arc<GeometricBrownianMotionStock>], times: &[TimeStamp])->Result<Vec<Vec<f64>>, PricingError>{
+    if times.len() != 1 || stocks.iter().any(|stock| stock.get_current_state().get_time() != times[0]) {
+        return Err(PricingError::ExpiredOption);
+    }
+    Ok(stocks.iter().map(|stock| vec![f64::from(stock.get_current_state().get_value())]).collect())
+}
+
+/// Returns the values of the stocks at `times`, one vector per stock, simulated under the risk neutral measure with the correlation
+/// matrix. `random_samples` are used in chunks of one sample per stock, one chunk per time.
+/// # Panics
+/// Panics if there are not enough random samples.
+fn correlated_paths(stocks: &[Arc<GeometricBrownianMotionStock>], correlation: &CorrelationMatrix, times: &[TimeStamp], random_samples: &[f64],
+    r: f64)->Vec<Vec<f64>>{
+    if random_samples.len() < stocks.len()*times.len(){
+        panic!("Incorrect length of random_samples");
+    }
+    let correlated: Vec<Vec<f64>> = random_samples.chunks(stocks.len()).take(times.len()).map(|z| correlation.correlate(z)).collect();
+    stocks.iter().enumerate().map(|(i, stock)|{
+        let gaussians: Vec<f64> = correlated.iter().map(|z| z[i]).collect();
+        stock.generate_risk_neutral_path_from_time_stamps(&gaussians, times, r).iter().map(|state| f64::from(state.get_value())).collect()
+    }).collect()
+}
+
+/// A cliquet (ratchet) option, i.e. a series of forward start options whose returns are locally capped and floored, summed, and then
+/// globally capped and floored. The payoff at the last reset time is
+/// `notional*max(global_floor, min(global_cap, sum_i max(local_floor, min(local_cap, S_i/S_{i-1}-1))))`.
+pub struct CliquetOption{
+    ///A shared reference to the underlying stock.
+    underlying_stock: Arc<GeometricBrownianMotionStock>,
+    /// The reset times, sorted with unique values. The first reset time sets the initial strike, and the last one is the expiry.
+    reset_times: Vec<TimeStamp>,
+    /// The floor of every periodic return.
+    local_floor: f64,
+    /// The cap of every periodic return.
+    local_cap: f64,
+    /// The floor of the sum of the periodic returns.
+    global_floor: f64,
+    /// The cap of the sum of the periodic returns.
+    global_cap: f64,
+    /// The notional of the option.
+    notional: f64,
+    /// A vector of states of the underlying stock.
+    history: Vec<StockState>,
+}
+
+impl CliquetOption {
+    /// Returns a new cliquet option.
+    /// # Parameters
+    /// - `underlying_stock`: A shared reference to the underlying stock.
+    /// - `reset_times`: The reset times. Needs to be sorted with unique values, with at least two values. The last reset time is the expiry.
+    /// - `local_floor`, `local_cap`: The floor and cap of every periodic return. Use `f64::NEG_INFINITY` and `f64::INFINITY` for none.
+    /// - `global_floor`, `global_cap`: The floor and cap of the sum of the periodic returns. Use `f64::NEG_INFINITY` and `f64::INFINITY` for none.
+    /// - `notional`: The notional of the option.
+    /// # Panics
+    /// Panics if there are less than two reset times, or if they are not strictly increasing.
+    pub fn new(underlying_stock: &Arc<GeometricBrownianMotionStock>, reset_times: &[TimeStamp], local_floor: f64, local_cap: f64, global_floor: f64,
+        global_cap: f64, notional: f64)->CliquetOption{
+        if reset_times.len()<2{
+            panic!("A cliquet option needs at least two reset times.");
+        }
+        if reset_times.windows(2).any(|w| w[0] >= w[1]){
+            panic!("The reset times need to be sorted with unique values.");
+        }
+        CliquetOption{
+            underlying_stock: Arc::clone(underlying_stock),
+            reset_times: reset_times.to_vec(),
+            local_floor,
+            local_cap,
+            global_floor,
+            global_cap,
+            notional,
+            history: vec![underlying_stock.get_current_state()],
+        }
+    }
+
+    /// Returns the expiry of the option, i.e. the last reset time.
+    pub fn get_expiry(&self) -> TimeStamp{
+        self.reset_times[self.reset_times.len()-1]
+    }
+
+    /// Updates the option with the current state of the underlying stock. Should be called at every past reset time.
+    pub fn update(&mut self){
+        if self.history[self.history.len()-1].get_time() == self.underlying_stock.get_current_state().get_time() {
+            return;
+        }
+        self.history.push(self.underlying_stock.get_current_state());
+    }
+
+    /// Returns the payoff given the values of the underlying at the reset times.
+    fn payoff(&self, fixings: &[f64])->f64{
+        let sum: f64 = fixings.windows(2)
+            .map(|w| f64::max(self.local_floor, f64::min(self.local_cap, w[1]/w[0]-1.0)))
+            .sum();
+        self.notional*f64::max(self.global_floor, f64::min(self.global_cap, sum))
+    }
+}
+
+impl DerivativeOption<GeometricBrownianMotionStock> for CliquetOption {
+    /// Returns the time to expiry of the option, or None if the option expiered.
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.get_expiry().checked_duration_since(self.underlying_stock.get_current_state().get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option, i.e. the number of future reset times.
+    fn get_dimensionality(&self)->usize {
+        let current_time = self.underlying_stock.get_current_state().get_time();
+        self.reset_times.iter().filter(|t| **t > current_time).count()
+    }
+
+    /// Prices the option (not discounted) given one path of the underlying.
+    /// #Parameters
+    /// - `random_samples` - a vector of iid Gaussian samples of length `self.get_dimensionality()`.
+    /// - `r` - the short rate of interest.
+    /// # Panics
+    /// Panics if the state of the underlying at a past reset time was not recorded with `update`.
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        self.path_payoff(&stock_path(&self.underlying_stock, &self.get_schedule(), random_samples, r), r)
+    }
+
+    /// Returns the payoff of the recorded fixings, if the option is at or past expiry and all of them were recorded with `update`.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        let fixings = recorded_fixings(&self.underlying_stock, &self.history, &self.reset_times).ok_or(PricingError::ExpiredOption)?;
+        Ok(self.payoff(&fixings))
+    }
+
+    /// Returns the current state of the stock and its simulated states at the future reset times.
+    fn simulate_path(&self, random_samples: &[f64], r: f64)->Vec<StockState>{
+        let current_state = self.underlying_stock.get_current_state();
+        let future_times: Vec<TimeStamp> = self.reset_times.iter().filter(|t| **t > current_state.get_time()).copied().collect();
+        let mut path = vec![current_state];
+        path.append(&mut self.underlying_stock.generate_risk_neutral_path_from_time_stamps(random_samples, &future_times, r));
+        path
+    }
+}
+
+impl PathPayoff for CliquetOption {
+    fn get_payment_time(&self)->TimeStamp{
+        self.get_expiry()
+    }
+
+    fn get_schedule(&self)->Vec<TimeStamp>{
+        future_times(&self.underlying_stock, &self.reset_times)
+    }
+
+    fn path_payoff(&self, path: &Path, _r: f64)->f64{
+        self.payoff(&fixings_on_path(&self.underlying_stock, &self.history, &self.reset_times, path))
+    }
+}
+
+impl HasGreeks for CliquetOption {
+    fn get_underlying(&self)->Arc<GeometricBrownianMotionStock>{
+        Arc::clone(&self.underlying_stock)
+    }
+
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->CliquetOption{
+        CliquetOption{
+            history: rebased_history(&self.history, stock),
+            ..CliquetOption::new(stock, &self.reset_times, self.local_floor, self.local_cap, self.global_floor, self.global_cap, self.notional)
+        }
+    }
+}
+
+/// The type of a touch option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TouchType{
+    /// Pays 1 if the barrier is touched before expiry.
+    OneTouch,
+    /// Pays 1 at expiry if the barrier is not touched before expiry.
+    NoTouch,
+}
+
+/// A one-touch or no-touch option on a stock with a continuously monitored barrier. The barrier is a down barrier if it is
+/// below the current value of the stock and an up barrier otherwise.
+/// Paths are simulated on an evenly spaced grid, and crossings of the barrier between grid points are detected with the
+/// Brownian bridge crossing probability, so the continuous barrier is priced without the bias of discrete monitoring.
+pub struct TouchOption{
+    ///A shared reference to the underlying stock.
+    underlying_stock: Arc<GeometricBrownianMotionStock>,
+    /// The barrier level.
+    barrier: NonNegativeFloat,
+    /// The time of expiry.
+    expiry: TimeStamp,
+    /// Whether the option is a one-touch or a no-touch.
+    touch_type: TouchType,
+    /// Whether a one-touch pays when the barrier is hit rather than at expiry. Ignored for no-touch options.
+    pay_at_hit: bool,
+    /// The number of simulation steps between the current time of the stock and expiry.
+    number_of_steps: usize,
+}
+
+impl TouchOption {
+    /// Returns a new touch option.
+    /// # Parameters
+    /// - `underlying_stock`: A shared reference to the underlying stock.
+    /// - `barrier`: The barrier level.
+    /// - `expiry`: The expiry time.
+    /// - `touch_type`: Whether the option is a one-touch or a no-touch.
+    /// - `pay_at_hit`: Whether a one-touch pays when the barrier is hit rather than at expiry.
+    /// - `number_of_steps`: The number of simulation steps per path.
+    /// # Panics
+    /// Panics if `number_of_steps` is zero.
+    pub fn new(underlying_stock: &Arc<GeometricBrownianMotionStock>, barrier: NonNegativeFloat, expiry: TimeStamp, touch_type: TouchType,
+        pay_at_hit: bool, number_of_steps: usize)->TouchOption{
+        if number_of_steps == 0{
+            panic!("The number of steps must be positive.");
+        }
+        TouchOption{
+            underlying_stock: Arc::clone(underlying_stock),
+            barrier,
+            expiry,
+            touch_type,
+            pay_at_hit,
+            number_of_steps,
+        }
+    }
+
+    /// Returns the expiry of the option.
+    pub fn get_expiry(&self) -> TimeStamp{
+        self.expiry
+    }
+
+    /// Returns the barrier of the option.
+    pub fn get_barrier(&self) -> NonNegativeFloat{
+        self.barrier
+    }
+
+    /// Returns the type of the option.
+    pub fn get_touch_type(&self) -> TouchType{
+        self.touch_type
+    }
+
+    /// Returns the payoff given the time (measured from the current time of the stock) at which the barrier is touched, if
+    /// it is touched before expiry. A one-touch paying at hit is accrued from the hitting time to expiry.
+    fn payoff(&self, hitting_time: Option<f64>, time_to_expiry: f64, r: f64)->f64{
+        match (self.touch_type, hitting_time) {
+            (TouchType::NoTouch, None) => 1.0,
+            (TouchType::NoTouch, Some(_)) | (TouchType::OneTouch, None) => 0.0,
+            (TouchType::OneTouch, Some(t)) => if self.pay_at_hit { (r*(time_to_expiry-t)).exp() } else { 1.0 },
+        }
+    }
+
+    /// Returns the time (measured from the current time of the stock) at which the simulated path touches the barrier,
+    /// or `None` if it does not touch it before expiry.
+    fn hitting_time(&self, random_samples: &[f64], r: f64, time_to_expiry: f64)->Option<f64>{
+        let current_state = self.underlying_stock.get_current_state();
+        let log_barrier = f64::from(self.barrier).ln();
+        let mut previous = f64::from(current_state.get_value()).ln();
+        let is_down = log_barrier < previous;
+        if log_barrier == previous{
+            return Some(0.0);
+        }
+        let step = time_to_expiry/self.number_of_steps as f64;
+        let time_stamps = grid_times(current_state.get_time(), self.expiry, self.number_of_steps);
+        let path = self.underlying_stock.generate_risk_neutral_path_from_time_stamps(&random_samples[..self.number_of_steps], &time_stamps, r);
+        let mut previous_time = current_state.get_time();
+        for (i, state) in path.iter().enumerate(){
+            let variance = self.underlying_stock.get_integrated_variance(previous_time, state.get_time());
+            previous_time = state.get_time();
+            let next = f64::from(state.get_value()).ln();
+            if (is_down && next <= log_barrier) || (!is_down && next >= log_barrier){
+                return Some(step*(i+1) as f64);
+            }
+            let crossing_probability = if variance > 0.0 { (-2.0*(previous-log_barrier)*(next-log_barrier)/variance).exp() } else { 0.0 };
+            if crate::utils::cumulative_normal_function(random_samples[self.number_of_steps+i]) < crossing_probability{
+                return Some(step*(i+1) as f64);
+            }
+            previous = next;
+        }
+        None
+    }
+}
+
+impl DerivativeOption<GeometricBrownianMotionStock> for TouchOption {
+    /// Returns the time to expiry of the option, where the current time is considered to be the current time of the stock.
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.expiry.checked_duration_since(self.underlying_stock.get_current_state().get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option: one per step for the path,
+    /// and one per step for the barrier crossing test.
+    fn get_dimensionality(&self)->usize {
+        2*self.number_of_steps
+    }
+
+    /// Prices the option (not discounted) given one path of the underlying. A one-touch paying at hit is accrued
+    /// from the hitting time to expiry, so that discounting from expiry gives its value. At or past expiry the settlement value
+    /// is returned.
+    /// #Parameters
+    /// - `random_samples` - a vector of iid Gaussian samples of length `self.get_dimensionality()`.
+    /// - `r` - the short rate of interest.
+    /// # Panics
+    /// Panics if `random_samples` is too short, or if the option is at or past expiry and its settlement value is unknown.
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        if random_samples.len()<self.get_dimensionality(){
+            panic!("Incorrect length of random_samples");
+        }
+        let time_to_expiry = match self.get_time_to_expiry() {
+            Some(tau) if f64::from(tau) > 0.0 => f64::from(tau),
+            _ => return self.settlement_value(r).unwrap_or_else(|e| panic!("{}", e)),
+        };
+        self.payoff(self.hitting_time(random_samples, r, time_to_expiry), time_to_expiry, r)
+    }
+
+    /// Returns the payoff of the option if it is exactly at expiry, where the barrier is touched if the stock is at it. As in
+    /// `price_path`, the barrier is taken not to have been touched before the current time. Past expiry `PricingError::ExpiredOption`
+    /// is returned.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        let current_state = self.underlying_stock.get_current_state();
+        if current_state.get_time() != self.expiry {
+            return Err(PricingError::ExpiredOption);
+        }
+        let touched = current_state.get_value() == self.barrier;
+        Ok(match (self.touch_type, touched) {
+            (TouchType::OneTouch, true) | (TouchType::NoTouch, false) => 1.0,
+            (TouchType::OneTouch, false) | (TouchType::NoTouch, true) => 0.0,
+        })
+    }
+}
+
+impl PathPayoff for TouchOption {
+    fn get_payment_time(&self)->TimeStamp{
+        self.expiry
+    }
+
+    /// Returns the evenly spaced grid of `number_of_steps` times up to expiry, or no times at or past expiry.
+    fn get_schedule(&self)->Vec<TimeStamp>{
+        grid_times(self.underlying_stock.get_current_state().get_time(), self.expiry, self.number_of_steps)
+    }
+
+    /// Returns the payoff given a path of the underlying, as in `price_path`. The barrier is only monitored at the times of the
+    /// schedule, since a generic path has no Brownian bridge between them, so the touch probability is biased low for a small
+    /// number of steps. At or past expiry the settlement value is returned.
+    /// # Panics
+    /// Panics if `path` misses a time of the schedule, or if the option is at or past expiry and its settlement value is unknown.
+    fn path_payoff(&self, path: &Path, r: f64)->f64{
+        let time_to_expiry = match self.get_time_to_expiry() {
+            Some(tau) if f64::from(tau) > 0.0 => f64::from(tau),
+            _ => return self.settlement_value(r).unwrap_or_else(|e| panic!("{}", e)),
+        };
+        let current_state = self.underlying_stock.get_current_state();
+        let (current, barrier) = (f64::from(current_state.get_value()), f64::from(self.barrier));
+        let hitting_time = if current == barrier {
+            Some(0.0)
+        }
+        else {
+            self.get_schedule().into_iter().find(|t| {
+                let value = path.value_at(*t).expect("The path has no value at a time of the schedule.");
+                if barrier < current { value <= barrier } else { value >= barrier }
+            }).map(|t| f64::from(t.checked_duration_since(current_state.get_time()).unwrap_or(Duration::from(0.0))))
+        };
+        self.payoff(hitting_time, time_to_expiry, r)
+    }
+}
+
+impl HasGreeks for TouchOption {
+    fn get_underlying(&self)->Arc<GeometricBrownianMotionStock>{
+        Arc::clone(&self.underlying_stock)
+    }
+
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->TouchOption{
+        TouchOption::new(stock, self.barrier, self.expiry, self.touch_type, self.pay_at_hit, self.number_of_steps)
+    }
+}
+
+/// A ladder option on a stock, which locks in gains as the stock crosses predefined rungs. The payoff of a call is
+/// `max(S_T-K, L-K, 0)`, where `L` is the highest rung reached before expiry, and the payoff of a put is `max(K-S_T, K-L, 0)`,
+/// where `L` is the lowest rung reached before expiry.
+/// Paths are simulated on an evenly spaced grid, and the extremum of the stock between grid points is sampled exactly from its
+/// Brownian bridge distribution, so rung crossings between grid points are not missed.
+pub struct LadderOption{
+    ///A shared reference to the underlying stock.
+    underlying_stock: Arc<GeometricBrownianMotionStock>,
+    /// The strike of the option.
+    strike: f64,
+    /// The rungs, sorted away from the strike, i.e. increasingly for a call and decreasingly for a put.
+    rungs: Vec<f64>,
+    /// Whether the option is a call or a put.
+    vanilla_type: VanillaType,
+    /// The time of expiry.
+    expiry: TimeStamp,
+    /// The number of simulation steps between the current time of the stock and expiry.
+    number_of_steps: usize,
+    /// The maximum (for a call) or minimum (for a put) of the stock observed so far.
+    observed_extremum: f64,
+}
+
+impl LadderOption {
+    /// Returns a new ladder option.
+    /// # Parameters
+    /// - `underlying_stock`: A shared reference to the underlying stock.
+    /// - `strike`: The strike of the option.
+    /// - `rungs`: The rungs. For a call they need to be sorted in increasing order and above the strike, and for a put sorted in
+    ///   decreasing order and below the strike.
+    /// - `vanilla_type`: Whether the option is a call or a put.
+    /// - `expiry`: The expiry time.
+    /// - `number_of_steps`: The number of simulation steps per path.
+    /// # Panics
+    /// Panics if `number_of_steps` is zero, or the rungs are not sorted away from the strike.
+    pub fn new(underlying_stock: &Arc<GeometricBrownianMotionStock>, strike: f64, rungs: &[f64], vanilla_type: VanillaType, expiry: TimeStamp,
+        number_of_steps: usize)->LadderOption{
+        if number_of_steps == 0{
+            panic!("The number of steps must be positive.");
+        }
+        let sign = match vanilla_type {
+            VanillaType::Call => 1.0,
+            VanillaType::Put => -1.0,
+        };
+        if rungs.windows(2).any(|w| sign*w[0] >= sign*w[1]) || rungs.iter().any(|rung| sign*rung <= sign*strike){
+            panic!("The rungs need to be sorted away from the strike.");
+        }
+        LadderOption{
+            underlying_stock: Arc::clone(underlying_stock),
+            strike,
+            rungs: rungs.to_vec(),
+            vanilla_type,
+            expiry,
+            number_of_steps,
+            observed_extremum: f64::from(underlying_stock.get_current_state().get_value()),
+        }
+    }
+
+    /// Returns the expiry of the option.
+    pub fn get_expiry(&self) -> TimeStamp{
+        self.expiry
+    }
+
+    /// Returns the rungs of the option.
+    pub fn get_rungs(&self) -> &[f64]{
+        &self.rungs
+    }
+
+    /// Returns the maximum (for a call) or minimum (for a put) of the stock observed so far.
+    pub fn get_observed_extremum(&self) -> f64{
+        self.observed_extremum
+    }
+
+    /// Updates the observed extremum with the current value of the underlying stock. Should be called whenever the stock moves,
+    /// since crossings between updates are not seen.
+    pub fn update(&mut self){
+        let value = f64::from(self.underlying_stock.get_current_state().get_value());
+        self.observed_extremum = match self.vanilla_type {
+            VanillaType::Call => f64::max(self.observed_extremum, value),
+            VanillaType::Put => f64::min(self.observed_extremum, value),
+        };
+    }
+
+    /// Returns the payoff given the value of the stock at expiry and its extremum over the life of the option.
+    fn payoff(&self, terminal: f64, extremum: f64)->f64{
+        match self.vanilla_type {
+            VanillaType::Call => {
+                let locked = self.rungs.iter().rev().find(|rung| **rung <= extremum).copied().unwrap_or(self.strike);
+                f64::max(f64::max(terminal, locked)-self.strike, 0.0)
+            },
+            VanillaType::Put => {
+                let locked = self.rungs.iter().rev().find(|rung| **rung >= extremum).copied().unwrap_or(self.strike);
+                f64::max(self.strike-f64::min(terminal, locked), 0.0)
+            },
+        }
+    }
+}
+
+impl DerivativeOption<GeometricBrownianMotionStock> for LadderOption {
+    /// Returns the time to expiry of the option, where the current time is considered to be the current time of the stock.
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.expiry.checked_duration_since(self.underlying_stock.get_current_state().get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option: one per step for the path,
+    /// and one per step for the extremum between grid points.
+    fn get_dimensionality(&self)->usize {
+        2*self.number_of_steps
+    }
+
+    /// Prices the option (not discounted) given one path of the underlying. At expiry the settlement value is returned.
+    /// #Parameters
+    /// - `random_samples` - a vector of iid Gaussian samples of length `self.get_dimensionality()`.
+    /// - `r` - the short rate of interest.
+    /// # Panics
+    /// Panics if the option expired or if `random_samples` is too short.
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        if random_samples.len()<self.get_dimensionality(){
+            panic!("Incorrect length of random_samples");
+        }
+        let current_state = self.underlying_stock.get_current_state();
+        let time_stamps = grid_times(current_state.get_time(), self.expiry, self.number_of_steps);
+        if time_stamps.is_empty() {
+            return self.settlement_value(r).unwrap_or_else(|e| panic!("{}", e));
+        }
+        let path = self.underlying_stock.generate_risk_neutral_path_from_time_stamps(&random_samples[..self.number_of_steps], &time_stamps, r);
+        let sign = match self.vanilla_type {
+            VanillaType::Call => 1.0,
+            VanillaType::Put => -1.0,
+        };
+        let mut extremum = self.observed_extremum;
+        let mut previous = f64::from(current_state.get_value()).ln();
+        let mut previous_time = current_state.get_time();
+        for (i, state) in path.iter().enumerate(){
+            let variance = self.underlying_stock.get_integrated_variance(previous_time, state.get_time());
+            previous_time = state.get_time();
+            let next = f64::from(state.get_value()).ln();
+            // The maximum (or minimum) of a Brownian bridge from `previous` to `next`, sampled by inverting its distribution.
+            let uniform = f64::max(crate::utils::cumulative_normal_function(random_samples[self.number_of_steps+i]), f64::MIN_POSITIVE);
+            let bridge_extremum = 0.5*(previous+next+sign*((next-previous)*(next-previous)-2.0*variance*uniform.ln()).sqrt());
+            extremum = if sign > 0.0 { f64::max(extremum, bridge_extremum.exp()) } else { f64::min(extremum, bridge_extremum.exp()) };
+            previous = next;
+        }
+        self.payoff(f64::from(path[path.len()-1].get_value()), extremum)
+    }
+
+    /// Returns the payoff of the current value of the stock and the observed extremum (including the current value) if the
+    /// option is exactly at expiry. Past expiry the value of the stock at expiry is unknown, and `PricingError::ExpiredOption`
+    /// is returned.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        let current_state = self.underlying_stock.get_current_state();
+        if current_state.get_time() != self.expiry {
+            return Err(PricingError::ExpiredOption);
+        }
+        let value = f64::from(current_state.get_value());
+        let extremum = match self.vanilla_type {
+            VanillaType::Call => f64::max(self.observed_extremum, value),
+            VanillaType::Put => f64::min(self.observed_extremum, value),
+        };
+        Ok(self.payoff(value, extremum))
+    }
+}
+
+impl PathPayoff for LadderOption {
+    fn get_payment_time(&self)->TimeStamp{
+        self.expiry
+    }
+
+    /// Returns the evenly spaced grid of `number_of_steps` times up to expiry, or no times at or past expiry.
+    fn get_schedule(&self)->Vec<TimeStamp>{
+        grid_times(self.underlying_stock.get_current_state().get_time(), self.expiry, self.number_of_steps)
+    }
+
+    /// Returns the payoff given a path of the underlying, as in `price_path`. The extremum is only taken over the times of the
+    /// schedule, since a generic path has no Brownian bridge between them, so the rungs locked in are biased for a small
+    /// number of steps. At expiry the settlement value is returned.
+    /// # Panics
+    /// Panics if `path` misses a time of the schedule, or if the option expired.
+    fn path_payoff(&self, path: &Path, r: f64)->f64{
+        let schedule = self.get_schedule();
+        if schedule.is_empty() {
+            return self.settlement_value(r).unwrap_or_else(|e| panic!("{}", e));
+        }
+        let values: Vec<f64> = schedule.iter()
+            .map(|t| path.value_at(*t).expect("The path has no value at a time of the schedule.")).collect();
+        let extremum = values.iter().fold(self.observed_extremum, |extremum, value| match self.vanilla_type {
+            VanillaType::Call => f64::max(extremum, *value),
+            VanillaType::Put => f64::min(extremum, *value),
+        });
+        self.payoff(values[values.len()-1], extremum)
+    }
+}
+
+impl HasGreeks for LadderOption {
+    fn get_underlying(&self)->Arc<GeometricBrownianMotionStock>{
+        Arc::clone(&self.underlying_stock)
+    }
+
+    /// Returns the option on `stock`, whose observed extremum is the one observed so far, unless it is the current value of the
+    /// underlying, which is replaced by the current value of `stock`.
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->LadderOption{
+        let mut option = LadderOption::new(stock, self.strike, &self.rungs, self.vanilla_type, self.expiry, self.number_of_steps);
+        if self.observed_extremum != f64::from(self.underlying_stock.get_current_state().get_value()) {
+            option.observed_extremum = self.observed_extremum;
+            option.update();
+        }
+        option
+    }
+}
+
+/// The direction of an `Accumulator`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccumulatorType{
+    /// Buys shares at the strike at every fixing, and is knocked out when the stock rises to the barrier.
+    Accumulator,
+    /// Sells shares at the strike at every fixing, and is knocked out when the stock falls to the barrier.
+    Decumulator,
+}
+
+/// An accumulator (or decumulator) on a stock. At every fixing until the knock-out, the holder buys (or sells) a number of shares
+/// at the strike. When the stock is on the wrong side of the strike, i.e. below it for an accumulator and above it for a
+/// decumulator, the number of shares is multiplied by the gearing. The structure terminates at the first fixing at which the stock
+/// is at or beyond the knock-out barrier, and no shares are traded at that fixing.
+/// Every fixing is settled at the fixing time, so its cash flow is accrued to expiry at the short rate of interest before the
+/// Monte Carlo pricer discounts it. Past fixings are assumed to have been settled, and only determine whether the structure
+/// was knocked out.
+pub struct Accumulator{
+    ///A shared reference to the underlying stock.
+    underlying_stock: Arc<GeometricBrownianMotionStock>,
+    /// The fixing times, sorted with unique values. The last one is the expiry.
+    fixing_times: Vec<TimeStamp>,
+    /// The price at which shares are traded.
+    strike: f64,
+    /// The knock-out barrier.
+    knock_out_barrier: f64,
+    /// The number of shares traded at every fixing.
+    shares_per_fixing: f64,
+    /// The multiplier of the number of shares when the stock is on the wrong side of the strike, usually 2.
+    gearing: f64,
+    /// Whether the structure buys or sells shares.
+    accumulator_type: AccumulatorType,
+    /// A vector of states of the underlying stock.
+    history: Vec<StockState>,
+}
+
+impl Accumulator {
+    /// Returns a new accumulator or decumulator.
+    /// # Parameters
+    /// - `underlying_stock`: A shared reference to the underlying stock.
+    /// - `fixing_times`: The fixing calendar. Needs to be sorted with unique values. The last fixing time is the expiry.
+    /// - `strike`: The price at which shares are traded.
+    /// - `knock_out_barrier`: The knock-out barrier, above the strike for an accumulator and below it for a decumulator.
+    /// - `shares_per_fixing`: The number of shares traded at every fixing.
+    /// - `gearing`: The multiplier of the number of shares when the stock is on the wrong side of the strike.
+    /// - `accumulator_type`: Whether the structure buys or sells shares.
+    /// # Panics
+    /// Panics if there are no fixing times.
+    pub fn new(underlying_stock: &Arc<GeometricBrownianMotionStock>, fixing_times: &[TimeStamp], strike: f64, knock_out_barrier: f64,
+        shares_per_fixing: f64, gearing: f64, accumulator_type: AccumulatorType)->Accumulator{
+        if fixing_times.is_empty(){
+            panic!("An accumulator needs at least one fixing time.");
+        }
+        Accumulator{
+            underlying_stock: Arc::clone(underlying_stock),
+            fixing_times: fixing_times.to_vec(),
+            strike,
+            knock_out_barrier,
+            shares_per_fixing,
+            gearing,
+            accumulator_type,
+            history: vec![underlying_stock.get_current_state()],
+        }
+    }
+
+    /// Returns the expiry of the structure, i.e. the last fixing time.
+    pub fn get_expiry(&self) -> TimeStamp{
+        self.fixing_times[self.fixing_times.len()-1]
+    }
+
+    /// Returns the type of the structure.
+    pub fn get_accumulator_type(&self) -> AccumulatorType{
+        self.accumulator_type
+    }
+
+    /// Updates the structure with the current state of the underlying stock. Should be called at every past fixing time.
+    pub fn update(&mut self){
+        if self.history[self.history.len()-1].get_time() == self.underlying_stock.get_current_state().get_time() {
+            return;
+        }
+        self.history.push(self.underlying_stock.get_current_state());
+    }
+
+    /// Returns the value at expiry of the cash flows of the fixings after the current time, given the values of the stock at all
+    /// the fixing times.
+    fn accrued_cash_flows(&self, fixings: &[f64], r: f64)->f64{
+        let sign = match self.accumulator_type {
+            AccumulatorType::Accumulator => 1.0,
+            AccumulatorType::Decumulator => -1.0,
+        };
+        let current_time = self.underlying_stock.get_current_state().get_time();
+        let expiry = f64::from(self.get_expiry());
+        let mut value = 0.0;
+        for (t, fixing) in self.fixing_times.iter().zip(fixings){
+            if sign*fixing >= sign*self.knock_out_barrier{
+                break;
+            }
+            if *t <= current_time{
+                continue;
+            }
+            let gain = sign*(fixing-self.strike);
+            let shares = if gain < 0.0 { self.gearing*self.shares_per_fixing } else { self.shares_per_fixing };
+            value += shares*gain*(r*(expiry-f64::from(*t))).exp();
+        }
+        value
+    }
+}
+
+impl DerivativeOption<GeometricBrownianMotionStock> for Accumulator {
+    /// Returns the time to expiry of the structure, or None if it expiered.
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.get_expiry().checked_duration_since(self.underlying_stock.get_current_state().get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the structure, i.e. the number of future fixing times.
+    fn get_dimensionality(&self)->usize {
+        let current_time = self.underlying_stock.get_current_state().get_time();
+        self.fixing_times.iter().filter(|t| **t > current_time).count()
+    }
+
+    /// Returns the value at expiry of the future cash flows of the structure given one path of the underlying. The value is
+    /// negative when the shares are traded at a loss.
+    /// #Parameters
+    /// - `random_samples` - a vector of iid Gaussian samples of length `self.get_dimensionality()`.
+    /// - `r` - the short rate of interest.
+    /// # Panics
+    /// Panics if the state of the underlying at a past fixing time was not recorded with `update`.
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        self.path_payoff(&stock_path(&self.underlying_stock, &self.get_schedule(), random_samples, r), r)
+    }
+
+    /// Returns 0 at or past expiry, since the cash flows of past fixings are assumed to have been settled.
+    fn settlement_value(&self, _r: f64)->Result<f64, PricingError>{
+        if self.get_time_to_expiry().is_some_and(|tau| f64::from(tau) > 0.0) {
+            return Err(PricingError::ExpiredOption);
+        }
+        Ok(0.0)
+    }
+
+    /// Returns the current state of the stock and its simulated states at the future fixing times.
+    fn simulate_path(&self, random_samples: &[f64], r: f64)->Vec<StockState>{
+        let current_state = self.underlying_stock.get_current_state();
+        let future_times: Vec<TimeStamp> = self.fixing_times.iter().filter(|t| **t > current_state.get_time()).copied().collect();
+        let mut path = vec![current_state];
+        path.append(&mut self.underlying_stock.generate_risk_neutral_path_from_time_stamps(random_samples, &future_times, r));
+        path
+    }
+}
+
+impl PathPayoff for Accumulator {
+    fn get_payment_time(&self)->TimeStamp{
+        self.get_expiry()
+    }
+
+    fn get_schedule(&self)->Vec<TimeStamp>{
+        future_times(&self.underlying_stock, &self.fixing_times)
+    }
+
+    /// Returns the value at expiry of the future cash flows, accrued at the rate `r`.
+    fn path_payoff(&self, path: &Path, r: f64)->f64{
+        self.accrued_cash_flows(&fixings_on_path(&self.underlying_stock, &self.history, &self.fixing_times, path), r)
+    }
+}
+
+impl HasGreeks for Accumulator {
+    fn get_underlying(&self)->Arc<GeometricBrownianMotionStock>{
+        Arc::clone(&self.underlying_stock)
+    }
+
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->Accumulator{
+        Accumulator{
+            history: rebased_history(&self.history, stock),
+            ..Accumulator::new(stock, &self.fixing_times, self.strike, self.knock_out_barrier, self.shares_per_fixing, self.gearing,
+                self.accumulator_type)
+        }
+    }
+}
+
+/// Returns the states of `history` recorded before the current time of `stock`, followed by the current state of `stock`, i.e.
+/// the history of an option rebuilt on a bumped stock, whose past fixings are kept.
+pub(crate) fn rebased_history(history: &[StockState], stock: &GeometricBrownianMotionStock)->Vec<StockState>{
+    let current_state = stock.get_current_state();
+    history.iter().filter(|s| s.get_time() < current_state.get_time()).copied().chain(std::iter::once(current_state)).collect()
+}
+
+/// Returns the recorded values of the underlying stock at `times`, looked up in `history` (or the current value of the stock),
+/// or `None` if one of the times is in the future or no state was recorded at it.
+fn recorded_fixings(underlying_stock: &GeometricBrownianMotionStock, history: &[StockState], times: &[TimeStamp])->Option<Vec<f64>>{
+    let current_state = underlying_stock.get_current_state();
+    times.iter().map(|t| history.iter().chain(std::iter::once(&current_state)).find(|s| s.get_time() == *t)
+        .map(|s| f64::from(s.get_value()))).collect()
+}
+
+/// Returns the values of the underlying stock at `times`, which need to be sorted with unique values.
+/// Values at times up to the current time of the stock are looked up in `history` (or are the current value of the stock),
+/// and the rest are simulated under the risk neutral measure, using one sample of `random_samples` per time.
+/// # Panics
+/// Panics if the state of the underlying at a past time is missing from `history`, or if there are not enough random samples.
+pub(crate) fn fixings_at(underlying_stock: &GeometricBrownianMotionStock, history: &[StockState], times: &[TimeStamp], random_samples: &[f64], r: f64)->Vec<f64>{
+    let path = stock_path(underlying_stock, &future_times(underlying_stock, times), random_samples, r);
+    fixings_on_path(underlying_stock, history, times, &path)
+}
+
+/// Returns `number_of_steps` evenly spaced times after `current_time`, the last of them at `expiry`, or no times if `expiry`
+/// is not after `current_time`.
+fn grid_times(current_time: TimeStamp, expiry: TimeStamp, number_of_steps: usize)->Vec<TimeStamp>{
+    let time_to_expiry = f64::from(expiry.checked_duration_since(current_time).unwrap_or(Duration::from(0.0)));
+    if time_to_expiry == 0.0 {
+        return Vec::new();
+    }
+    let step = time_to_expiry/number_of_steps as f64;
+    (1..=number_of_steps).map(|i| current_time+Duration::from(step*i as f64)).collect()
+}
+
+/// Returns the times of `times` after the current time of the underlying stock.
+pub(crate) fn future_times(underlying_stock: &GeometricBrownianMotionStock, times: &[TimeStamp])->Vec<TimeStamp>{
+    let current_time = underlying_stock.get_current_state().get_time();
+    times.iter().filter(|t| **t > current_time).copied().collect()
+}
+
+/// Same as `fixings_at`, but takes the values at the future times from `path`.
+/// # Panics
+/// Panics if the state of the underlying at a past time is missing from `history`, or if `path` misses a future time.
+pub(crate) fn fixings_on_path(underlying_stock: &GeometricBrownianMotionStock, history: &[StockState], times: &[TimeStamp], path: &Path)->Vec<f64>{
+    let current_time = underlying_stock.get_current_state().get_time();
+    let past_times: Vec<TimeStamp> = times.iter().filter(|t| **t <= current_time).copied().collect();
+    let mut fixings = recorded_fixings(underlying_stock, history, &past_times)
+        .expect("No state of the underlying was recorded at a past fixing time.");
+    fixings.extend(future_times(underlying_stock, times).iter()
+        .map(|t| path.value_at(*t).expect("The path has no value at a future fixing time.")));
+    fixings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call_payoff(spot: NonNegativeFloat, params: &[f64])->f64{
+        f64::max(f64::from(spot)-params[0], 0.0)
+    }
+
+    #[test]
+    fn try_price_path_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(2.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let expired = VanillaStockOption::new(&stock, TimeStamp::from(1.0), Box::new(call_payoff), vec![100.0]);
+        assert_eq!(expired.try_price_path(&[0.5], 0.05), Err(PricingError::ExpiredOption));
+        let opt = VanillaStockOption::new(&stock, TimeStamp::from(3.0), Box::new(call_payoff), vec![100.0]);
+        assert_eq!(opt.try_price_path(&[], 0.05), Err(PricingError::InsufficientSamples));
+        assert_eq!(opt.try_price_path(&[0.5], 0.05), Ok(opt.price_path(&[0.5], 0.05)));
+    }
+
+    #[test]
+    fn spread_option_test(){
+        let first = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(122.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let second = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(120.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let opt = SpreadOption::new(&first, &second, -0.5, 3.0, TimeStamp::from(0.1));
+        let mc = crate::monte_carlo_pricer::monte_carlo_pricer(&opt, 0.1, Some(11), 200000);
+        let approximation = crate::raw_formulas::spread_call_price_bjerksund_stensland(122.0, 120.0, 3.0, 0.1, 0.1, 0.2, 0.2, -0.5, 0.0, 0.0);
+        assert!((mc-approximation).abs()<0.05);
+    }
+
+    #[test]
+    fn cliquet_option_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let reset_times = [TimeStamp::from(0.0), TimeStamp::from(0.5), TimeStamp::from(1.0), TimeStamp::from(1.5)];
+        let opt = CliquetOption::new(&stock, &reset_times, 0.0, f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, 1.0);
+        let mc = crate::monte_carlo_pricer::monte_carlo_pricer(&opt, 0.05, Some(3), 200000);
+        let forward_start_call = (0.05_f64*0.5).exp()*crate::raw_formulas::european_call_option_price(1.0, 1.0, 0.05, 0.5, 0.2, 0.0);
+        assert!((mc-3.0*forward_start_call*(-0.05_f64*1.5).exp()).abs()<0.002);
+    }
+
+    #[test]
+    #[should_panic(expected = "The reset times need to be sorted with unique values.")]
+    fn cliquet_unsorted_reset_times_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        CliquetOption::new(&stock, &[TimeStamp::from(0.5), TimeStamp::from(0.5), TimeStamp::from(1.0)], 0.0, 0.1, 0.0, 1.0, 1.0);
+    }
+
+    #[test]
+    fn cliquet_global_cap_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let reset_times = [TimeStamp::from(0.0), TimeStamp::from(0.5), TimeStamp::from(1.0)];
+        let opt = CliquetOption::new(&stock, &reset_times, -0.05, 0.05, 0.0, 0.08, 100.0);
+        assert_eq!(opt.price_path(&[5.0, 5.0], 0.05), 8.0);
+        assert_eq!(opt.price_path(&[-5.0, -5.0], 0.05), 0.0);
+    }
+
+    #[test]
+    fn touch_option_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.01)));
+        for (barrier, pay_at_hit) in [(90.0, false), (115.0, true)]{
+            let one_touch = TouchOption::new(&stock, NonNegativeFloat::from(barrier), TimeStamp::from(0.5), TouchType::OneTouch, pay_at_hit, 50);
+            let mc = crate::monte_carlo_pricer::monte_carlo_pricer(&one_touch, 0.05, Some(5), 20000);
+            let analytic = crate::raw_formulas::one_touch_price(100.0, barrier, 0.05, 0.5, 0.2, 0.01, pay_at_hit);
+            assert!((mc-analytic).abs()<0.01);
+        }
+        let no_touch = TouchOption::new(&stock, NonNegativeFloat::from(90.0), TimeStamp::from(0.5), TouchType::NoTouch, false, 50);
+        let mc = crate::monte_carlo_pricer::monte_carlo_pricer(&no_touch, 0.05, Some(5), 20000);
+        assert!((mc-crate::raw_formulas::no_touch_price(100.0, 90.0, 0.05, 0.5, 0.2, 0.01)).abs()<0.01);
+    }
+
+    #[test]
+    fn path_payoff_test(){
+        use crate::monte_carlo_pricer::monte_carlo_simulation_with_generator;
+        use crate::random_number_generator::RandomNumberGenerator;
+        use crate::statistics_gatherer::{MeanStatisticsGatherer, StatisticsGathererTrait};
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.01)));
+        let price = |option: &dyn Fn(&mut MeanStatisticsGatherer)| {
+            let mut gatherer = MeanStatisticsGatherer::new();
+            option(&mut gatherer);
+            gatherer.get_results_so_far()[0][0]
+        };
+        let one_touch = TouchOption::new(&stock, NonNegativeFloat::from(90.0), TimeStamp::from(0.5), TouchType::OneTouch, false, 250);
+        let mc = price(&|gatherer| monte_carlo_simulation_with_generator(&one_touch, &*stock, gatherer, 0.05,
+            &mut RandomNumberGenerator::new(Some(5)), 20000));
+        // The barrier is only monitored at the schedule, which is priced by shifting the continuous barrier away from the spot
+        // by `exp(0.5826*sigma*sqrt(dt))` (Broadie, Glasserman and Kou).
+        let shifted_barrier = 90.0*(-0.5826*0.2*(0.5_f64/250.0).sqrt()).exp();
+        assert!((mc-crate::raw_formulas::one_touch_price(100.0, shifted_barrier, 0.05, 0.5, 0.2, 0.01, false)).abs()<0.01);
+        let call = LadderOption::new(&stock, 100.0, &[110.0, 120.0], VanillaType::Call, TimeStamp::from(1.0), 250);
+        let mc = price(&|gatherer| monte_carlo_simulation_with_generator(&call, &*stock, gatherer, 0.05,
+            &mut RandomNumberGenerator::new(Some(5)), 20000));
+        // The maximum over the schedule is below the continuous maximum, so fewer rungs are locked in.
+        let analytic = crate::raw_formulas::ladder_call_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01, &[110.0, 120.0]);
+        assert!(mc < analytic && mc > analytic-0.5);
+    }
+
+    #[test]
+    fn payoff_option_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let call = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), crate::payoff::CallPayoff::new(100.0));
+        let copy = call.clone();
+        assert!(copy == call);
+        assert_eq!(copy.get_payoff().get_strike(), 100.0);
+        let closure_call = VanillaStockOption::new(&stock, TimeStamp::from(1.0), Box::new(call_payoff), vec![100.0]);
+        assert_eq!(call.price_path(&[0.3], 0.05), closure_call.price_path(&[0.3], 0.05));
+    }
+
+    #[test]
+    fn averaging_test(){
+        assert_eq!(AveragingType::Arithmetic.average(&[1.0, 4.0]), 2.5);
+        assert!((AveragingType::Geometric.average(&[1.0, 4.0])-2.0).abs()<1e-12);
+        assert_eq!(averaging_window(TimeStamp::from(0.5), TimeStamp::from(1.0), 3),
+            vec![TimeStamp::from(0.5), TimeStamp::from(0.75), TimeStamp::from(1.0)]);
+        let states = [StockState::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0)),
+            StockState::new(NonNegativeFloat::from(110.0), TimeStamp::from(1.0))];
+        let average = AveragingType::Arithmetic.average_function();
+        assert!((f64::from(average(&states, &[TimeStamp::from(0.5), TimeStamp::from(1.0)]))-107.5).abs()<1e-12);
+    }
+
+    #[test]
+    fn partial_window_asian_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.25), NonNegativeFloat::from(0.0)));
+        let window = averaging_window(TimeStamp::from(0.5), TimeStamp::from(1.0), 6);
+        let opt = AsianOption::with_averaging(&stock, TimeStamp::from(1.0), &window, AveragingType::Arithmetic,
+            crate::payoff::CallPayoff::new(100.0));
+        let mc = crate::monte_carlo_pricer::monte_carlo_pricer(&opt, 0.05, Some(42), 200000);
+        let fixing_times: Vec<f64> = window.iter().map(|t| f64::from(*t)).collect();
+        let approximation = crate::raw_formulas::discrete_asian_call_price_turnbull_wakeman(100.0, 100.0, 0.05, 1.0, 0.25, 0.0, &fixing_times);
+        assert!((mc-approximation).abs()<0.06);
+        let geometric = AsianOption::with_averaging(&stock, TimeStamp::from(1.0), &window, AveragingType::Geometric,
+            crate::payoff::CallPayoff::new(100.0));
+        assert!(crate::monte_carlo_pricer::monte_carlo_pricer(&geometric, 0.05, Some(42), 200000) < mc);
+    }
+
+    #[test]
+    fn floating_strike_asian_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let monitoring_times = averaging_window(TimeStamp::from(0.25), TimeStamp::from(0.75), 3);
+        let call = FloatingStrikeAsianOption::new(&stock, TimeStamp::from(1.0), &monitoring_times, AveragingType::Arithmetic, VanillaType::Call, 1.0);
+        let put = FloatingStrikeAsianOption::new(&stock, TimeStamp::from(1.0), &monitoring_times, AveragingType::Arithmetic, VanillaType::Put, 1.0);
+        assert_eq!(call.get_dimensionality(), 4);
+        // Put-call parity: the call minus the put is worth the stock minus the discounted expected average.
+        let call_price = crate::monte_carlo_pricer::monte_carlo_pricer(&call, 0.05, Some(7), 200000);
+        let put_price = crate::monte_carlo_pricer::monte_carlo_pricer(&put, 0.05, Some(7), 200000);
+        let expected_average = [0.25, 0.5, 0.75].iter().map(|t: &f64| 100.0*(0.05*t).exp()).sum::<f64>()/3.0;
+        assert!((call_price-put_price-(100.0-(-0.05_f64).exp()*expected_average)).abs()<0.05);
+        let path = [0.3, -0.2, 0.5, 0.1];
+        let fixings: Vec<f64> = call.simulate_path(&path, 0.05).iter().skip(1).map(|s| f64::from(s.get_value())).collect();
+        assert!((call.price_path(&path, 0.05)-put.price_path(&path, 0.05)-(fixings[3]-(fixings[0]+fixings[1]+fixings[2])/3.0)).abs()<1e-10);
+    }
+
+    #[test]
+    fn ladder_option_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.01)));
+        let call = LadderOption::new(&stock, 100.0, &[110.0, 120.0], VanillaType::Call, TimeStamp::from(1.0), 20);
+        let mc = crate::monte_carlo_pricer::monte_carlo_pricer(&call, 0.05, Some(5), 100000);
+        assert!((mc-crate::raw_formulas::ladder_call_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01, &[110.0, 120.0])).abs()<0.08);
+        let put = LadderOption::new(&stock, 100.0, &[95.0, 85.0], VanillaType::Put, TimeStamp::from(1.0), 20);
+        let mc = crate::monte_carlo_pricer::monte_carlo_pricer(&put, 0.05, Some(5), 100000);
+        assert!((mc-crate::raw_formulas::ladder_put_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01, &[95.0, 85.0])).abs()<0.08);
+    }
+
+    #[test]
+    fn accumulator_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.01)));
+        let fixing_times = averaging_window(TimeStamp::from(0.25), TimeStamp::from(1.0), 4);
+        // Without a barrier or gearing, an accumulator is a strip of forwards.
+        let strip = Accumulator::new(&stock, &fixing_times, 95.0, f64::INFINITY, 10.0, 1.0, AccumulatorType::Accumulator);
+        let forwards: f64 = fixing_times.iter().map(|t| 10.0*(100.0*(-0.01*f64::from(*t)).exp()-95.0*(-0.05*f64::from(*t)).exp())).sum();
+        let mc = crate::monte_carlo_pricer::monte_carlo_pricer(&strip, 0.05, Some(9), 100000);
+        assert!((mc-forwards).abs()<0.5);
+        // Gearing on the wrong side and the knock-out both make the accumulator cheaper.
+        let geared = Accumulator::new(&stock, &fixing_times, 95.0, 105.0, 10.0, 2.0, AccumulatorType::Accumulator);
+        assert!(crate::monte_carlo_pricer::monte_carlo_pricer(&geared, 0.05, Some(9), 100000) < mc);
+        assert_eq!(geared.price_path(&[3.0, 0.0, 0.0, 0.0], 0.05), 0.0);
+        let decumulator = Accumulator::new(&stock, &fixing_times, 105.0, 95.0, 10.0, 2.0, AccumulatorType::Decumulator);
+        assert_eq!(decumulator.price_path(&[-3.0, 0.0, 0.0, 0.0], 0.05), 0.0);
+        let path = [0.0, 0.0, 0.0, 0.0];
+        let fixings: Vec<f64> = decumulator.simulate_path(&path, 0.05).iter().skip(1).map(|s| f64::from(s.get_value())).collect();
+        let expected: f64 = fixings.iter().zip(&fixing_times).map(|(s, t)| 10.0*(105.0-s)*(0.05*(1.0-f64::from(*t))).exp()).sum();
+        assert!((decumulator.price_path(&path, 0.05)-expected).abs()<1e-10);
+    }
+
+    #[test]
+    fn knocked_out_accumulator_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let fixing_times = averaging_window(TimeStamp::from(0.25), TimeStamp::from(1.0), 4);
+        let accumulator = Accumulator::new(&stock, &fixing_times, 95.0, 105.0, 10.0, 2.0, AccumulatorType::Accumulator);
+        // The stock fixed at the barrier at the first fixing, so the structure is knocked out whatever the later fixings are.
+        let fixed = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(105.0), TimeStamp::from(0.25),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let knocked_out = accumulator.with_underlying(&fixed);
+        assert_eq!(knocked_out.get_dimensionality(), 3);
+        assert_eq!(knocked_out.price_path(&[-3.0, -3.0, -3.0], 0.05), 0.0);
+        // The cash flows of past fixings are settled, so the structure is worth nothing at and past expiry.
+        let expired = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(90.0), TimeStamp::from(1.5),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        assert_eq!(accumulator.with_underlying(&expired).settlement_value(0.05), Ok(0.0));
+        assert_eq!(accumulator.settlement_value(0.05), Err(PricingError::ExpiredOption));
+    }
+
+    #[test]
+    #[should_panic(expected = "An accumulator needs at least one fixing time.")]
+    fn accumulator_without_fixings_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        Accumulator::new(&stock, &[], 95.0, 105.0, 10.0, 2.0, AccumulatorType::Accumulator);
+    }
+
+    #[test]
+    fn basket_asian_option_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let monitoring_times = averaging_window(TimeStamp::from(0.5), TimeStamp::from(1.0), 3);
+        let single = BasketAsianOption::new(&[Arc::clone(&stock)], &[1.0], CorrelationMatrix::new(vec![vec![1.0]]), &monitoring_times,
+            VanillaType::Call, 100.0);
+        let asian = AsianOption::with_averaging(&stock, TimeStamp::from(1.0), &monitoring_times, AveragingType::Arithmetic,
+            crate::payoff::CallPayoff::new(100.0));
+        let path = [0.4, -1.2, 0.8];
+        assert!((single.price_path(&path, 0.05)-asian.price_path(&path, 0.05)).abs()<1e-10);
+        // Two perfectly correlated copies of the stock with half weights are the stock itself.
+        let pair = BasketAsianOption::new(&[Arc::clone(&stock), Arc::clone(&stock)], &[0.5, 0.5], CorrelationMatrix::new(vec![vec![1.0, 1.0], vec![1.0, 1.0]]),
+            &monitoring_times, VanillaType::Call, 100.0);
+        assert!((pair.price_path(&[0.4, 0.0, -1.2, 0.0, 0.8, 0.0], 0.05)-asian.price_path(&path, 0.05)).abs()<1e-10);
+        let uncorrelated = BasketAsianOption::new(&[Arc::clone(&stock), Arc::clone(&stock)], &[0.5, 0.5], CorrelationMatrix::new(vec![vec![1.0, 0.0], vec![0.0, 1.0]]),
+            &monitoring_times, VanillaType::Call, 100.0);
+        assert!(crate::monte_carlo_pricer::monte_carlo_pricer(&uncorrelated, 0.05, Some(2), 50000)
+            < crate::monte_carlo_pricer::monte_carlo_pricer(&pair, 0.05, Some(2), 50000));
+    }
+
+    #[test]
+    fn himalayan_option_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        // With a single stock, a Himalayan option is a call on its performance.
+        let single = HimalayanOption::new(&[Arc::clone(&stock)], CorrelationMatrix::new(vec![vec![1.0]]), &[TimeStamp::from(1.0)], 1.0, 100.0);
+        let mc = crate::monte_carlo_pricer::monte_carlo_pricer(&single, 0.05, Some(4), 100000);
+        assert!((mc-crate::raw_formulas::european_call_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.0)).abs()<0.1);
+        // The best performer is removed first, and the worst one is recorded last.
+        let other = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(50.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let pair = HimalayanOption::new(&[Arc::clone(&stock), other], CorrelationMatrix::new(vec![vec![1.0, 0.0], vec![0.0, 1.0]]),
+            &[TimeStamp::from(0.5), TimeStamp::from(1.0)], 0.0, 1.0);
+        let paths = correlated_paths(&pair.stocks, &pair.correlation, &pair.monitoring_times, &[1.0, -1.0, 0.0, 0.0], 0.05);
+        let expected = 0.5*(paths[0][0]/100.0+paths[1][1]/50.0);
+        assert!((pair.price_path(&[1.0, -1.0, 0.0, 0.0], 0.05)-expected).abs()<1e-12);
+    }
+
+    #[test]
+    fn settlement_value_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(110.0), TimeStamp::from(1.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let at_expiry = VanillaStockOption::new(&stock, TimeStamp::from(1.0), Box::new(call_payoff), vec![100.0]);
+        assert_eq!(at_expiry.settlement_value(0.05), Ok(10.0));
+        let expired = VanillaStockOption::new(&stock, TimeStamp::from(0.5), Box::new(call_payoff), vec![100.0]);
+        assert_eq!(expired.settlement_value(0.05), Err(PricingError::ExpiredOption));
+        let asian = AsianOption::with_averaging(&stock, TimeStamp::from(1.0), &[TimeStamp::from(1.0)], AveragingType::Arithmetic,
+            crate::payoff::CallPayoff::new(105.0));
+        assert_eq!(asian.settlement_value(0.05), Ok(5.0));
+        let cliquet = CliquetOption::new(&stock, &[TimeStamp::from(0.5), TimeStamp::from(1.0)], 0.0, 1.0, 0.0, 1.0, 1.0);
+        assert_eq!(cliquet.settlement_value(0.05), Err(PricingError::ExpiredOption));
+        // Options at expiry settle in price_path and greeks, and expired options return a typed error.
+        let touch = TouchOption::new(&stock, NonNegativeFloat::from(120.0), TimeStamp::from(1.0), TouchType::NoTouch, false, 10);
+        assert_eq!(touch.price_path(&[0.0; 20], 0.05), 1.0);
+        let ladder = LadderOption::new(&stock, 100.0, &[105.0, 115.0], VanillaType::Call, TimeStamp::from(1.0), 10);
+        assert_eq!(ladder.try_price_path(&[0.0; 20], 0.05), Ok(10.0));
+        let expired_ladder = LadderOption::new(&stock, 100.0, &[105.0, 115.0], VanillaType::Call, TimeStamp::from(0.5), 10);
+        assert_eq!(expired_ladder.try_price_path(&[0.0; 20], 0.05), Err(PricingError::ExpiredOption));
+        let greeks = at_expiry.greeks(0.05);
+        assert_eq!((greeks.price, greeks.delta, greeks.vega), (10.0, 0.0, 0.0));
+        assert_eq!(expired.try_greeks(0.05), Err(PricingError::ExpiredOption));
+    }
+
+    #[test]
+    fn multi_asset_settlement_value_test(){
+        use crate::monte_carlo_pricer::try_monte_carlo_pricer;
+        let stock = |spot: f64| Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(spot), TimeStamp::from(1.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let (first, second) = (stock(110.0), stock(95.0));
+        let spread = SpreadOption::new(&first, &second, 0.5, 10.0, TimeStamp::from(1.0));
+        assert_eq!(spread.settlement_value(0.05), Ok(5.0));
+        assert_eq!(try_monte_carlo_pricer(&spread, 0.05, Some(1), 10), Ok(5.0));
+        let expired_spread = SpreadOption::new(&first, &second, 0.5, 10.0, TimeStamp::from(0.5));
+        assert_eq!(expired_spread.settlement_value(0.05), Err(PricingError::ExpiredOption));
+        let correlation = CorrelationMatrix::new(vec![vec![1.0, 0.5], vec![0.5, 1.0]]);
+        let basket = BasketAsianOption::new(&[Arc::clone(&first), Arc::clone(&second)], &[0.5, 0.5], correlation.clone(),
+            &[TimeStamp::from(1.0)], VanillaType::Put, 105.0);
+        assert_eq!(basket.settlement_value(0.05), Ok(2.5));
+        assert_eq!(try_monte_carlo_pricer(&basket, 0.05, Some(1), 10), Ok(2.5));
+        // The value of the stocks at the earlier monitoring time is not recorded.
+        let seasoned_basket = BasketAsianOption::new(&[Arc::clone(&first), Arc::clone(&second)], &[0.5, 0.5], correlation.clone(),
+            &[TimeStamp::from(0.5), TimeStamp::from(1.0)], VanillaType::Put, 105.0);
+        assert_eq!(seasoned_basket.settlement_value(0.05), Err(PricingError::ExpiredOption));
+        let himalayan = HimalayanOption::new(&[Arc::clone(&first)], CorrelationMatrix::new(vec![vec![1.0]]), &[TimeStamp::from(1.0)],
+            0.9, 100.0);
+        assert!((himalayan.settlement_value(0.05).unwrap()-10.0).abs() < 1e-12);
+        assert!((try_monte_carlo_pricer(&himalayan, 0.05, Some(1), 10).unwrap()-10.0).abs() < 1e-12);
+        let seasoned_himalayan = HimalayanOption::new(&[first, second], correlation, &[TimeStamp::from(0.5), TimeStamp::from(1.0)],
+            0.9, 100.0);
+        assert_eq!(seasoned_himalayan.settlement_value(0.05), Err(PricingError::ExpiredOption));
+    }
+
+    #[test]
+    fn options_are_thread_safe_test(){
+        fn assert_send_sync<T: Send+Sync>(){}
+        assert_send_sync::<VanillaStockOption>();
+        assert_send_sync::<AsianOption>();
+        assert_send_sync::<FloatingStrikeAsianOption>();
+        assert_send_sync::<SpreadOption>();
+        assert_send_sync::<BasketAsianOption>();
+        assert_send_sync::<HimalayanOption>();
+        assert_send_sync::<CliquetOption>();
+        assert_send_sync::<TouchOption>();
+        assert_send_sync::<LadderOption>();
+        assert_send_sync::<Accumulator>();
+        use crate::monte_carlo_pricer::monte_carlo_pricer;
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.05, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let option = VanillaStockOption::new(&stock, TimeStamp::from(1.0), Box::new(call_payoff), vec![100.0]);
+        let shared = &option;
+        let prices: Vec<f64> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4).map(|seed| scope.spawn(move || monte_carlo_pricer(shared, 0.05, Some(seed), 1000))).collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        for (seed, price) in prices.iter().enumerate() {
+            assert_eq!(*price, monte_carlo_pricer(&option, 0.05, Some(seed as u64), 1000));
+        }
+    }
+
+    #[test]
+    fn shared_underlying_test(){
+        use crate::monte_carlo_pricer::try_monte_carlo_pricer;
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(1.0),
+            0.05, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        // Options share their underlying instead of copying it, and release it when they are dropped.
+        let expired = VanillaStockOption::new(&stock, TimeStamp::from(0.5), Box::new(call_payoff), vec![100.0]);
+        let asian = AsianOption::with_averaging(&stock, TimeStamp::from(2.0), &[TimeStamp::from(2.0)], AveragingType::Arithmetic,
+            crate::payoff::CallPayoff::new(100.0));
+        assert_eq!(Arc::strong_count(&stock), 3);
+        assert!(Arc::ptr_eq(&expired.get_underlying(), &stock));
+        drop(asian);
+        assert_eq!(Arc::strong_count(&stock), 2);
+        // An expired option returns an error on every thread instead of panicking in one of them.
+        let shared = &expired;
+        let results: Vec<Result<f64, PricingError>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4).map(|seed| scope.spawn(move || try_monte_carlo_pricer(shared, 0.05, Some(seed), 100))).collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        assert_eq!(results, vec![Err(PricingError::ExpiredOption); 4]);
+    }
+
+    #[test]
+    fn has_greeks_test(){
+        use crate::payoff::CallPayoff;
+        use crate::raw_formulas;
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.05, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.01)));
+        let analytic = VanillaStockOption::with_payoff(&stock, TimeStamp::from(1.0), CallPayoff::new(105.0));
+        assert_eq!(analytic.greeks(0.05), raw_formulas::call_greeks(100.0, 105.0, 0.05, 1.0, 0.2, 0.01));
+        assert_eq!(analytic.vega(0.05), raw_formulas::call_vega(100.0, 105.0, 0.05, 1.0, 0.2, 0.01));
+        // A call given by a closure has no closed form greeks, and falls back to bump-and-reprice.
+        let closure = VanillaStockOption::new(&stock, TimeStamp::from(1.0), Box::new(call_payoff), vec![105.0]);
+        let greeks = closure.greeks(0.05);
+        assert!((greeks.delta-analytic.delta(0.05)).abs()<0.01);
+        assert!((greeks.vega-analytic.vega(0.05)).abs()<1.0);
+        assert!((greeks.rho-analytic.rho(0.05)).abs()<1.0);
+        let asian = AsianOption::with_averaging(&stock, TimeStamp::from(1.0), &averaging_window(TimeStamp::from(0.0), TimeStamp::from(1.0), 4),
+            AveragingType::Arithmetic, CallPayoff::new(105.0));
+        let asian_greeks = asian.greeks(0.05);
+        assert!(asian_greeks.delta > 0.0 && asian_greeks.delta < greeks.delta);
+        assert!(asian_greeks.vega > 0.0 && asian_greeks.vega < greeks.vega);
+        // A seasoned cliquet keeps its past fixing when it is rebuilt on the bumped stocks.
+        let seasoned_stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.5),
+            0.05, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.01)));
+        let reset_times = [TimeStamp::from(0.0), TimeStamp::from(0.5), TimeStamp::from(1.0)];
+        let mut cliquet = CliquetOption::new(&seasoned_stock, &reset_times, f64::NEG_INFINITY, f64::INFINITY, 0.0, f64::INFINITY, 1.0);
+        cliquet.history = vec![StockState::new(NonNegativeFloat::from(80.0), TimeStamp::from(0.0)), seasoned_stock.get_current_state()];
+        let price = crate::monte_carlo_pricer::monte_carlo_pricer(&cliquet, 0.05, Some(GREEKS_SEED), GREEKS_NUMBER_OF_PATHS);
+        assert!(price > 0.2);
+        assert!((cliquet.greeks(0.05).price-price).abs() < 1e-12);
+    }
+
+    #[test]
+    fn expired_greeks_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(110.0), TimeStamp::from(1.0),
+            0.05, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        // Options without closed form greeks settle at expiry instead of bumping the stock.
+        let at_expiry = VanillaStockOption::new(&stock, TimeStamp::from(1.0), Box::new(call_payoff), vec![105.0]);
+        assert_eq!(at_expiry.try_greeks(0.05), Ok(Greeks{price: 5.0, delta: 0.0, gamma: 0.0, vega: 0.0, theta: 0.0, rho: 0.0}));
+        let expired = CliquetOption::new(&stock, &[TimeStamp::from(0.0), TimeStamp::from(0.5)], 0.0, 1.0, 0.0, 1.0, 1.0);
+        assert_eq!(expired.try_greeks(0.05), Err(PricingError::ExpiredOption));
+    }
+
+    #[test]
+    #[should_panic(expected = "The option expiered!")]
+    fn expired_greeks_panic_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(110.0), TimeStamp::from(1.0),
+            0.05, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        VanillaStockOption::new(&stock, TimeStamp::from(0.5), Box::new(call_payoff), vec![105.0]).delta(0.05);
+    }
+}
@@ -0,0 +1,283 @@
+//! Provides a mean reverting underlying following the Ornstein-Uhlenbeck SDE
+//!
+//! `dX = kappa*(theta-X)*dt + sigma*dW`,
+//!
+//! for spreads, commodities and volatilities, which can be negative or are pulled back to a long term level, unlike a stock
+//! following geometric Brownian motion.
+//!
+//! The value at a later time is normally distributed, so paths are simulated exactly, whatever the length of the steps.
+//! The parameters are taken to be those of the pricing measure, i.e. paths are used as is by the Monte Carlo pricer.
+
+use std::sync::Arc;
+
+use crate::error::PricingError;
+use crate::option::{DerivativeOption, Underlying};
+use crate::utils::{Duration, NonNegativeFloat, TimeStamp};
+
+/// A payoff of an option on an Ornstein-Uhlenbeck process, as a function of the value at expiry (which may be negative).
+pub type OrnsteinUhlenbeckPayoff = Box<dyn Fn(f64)->f64+Send+Sync>;
+
+/// A process satisfying the Ornstein-Uhlenbeck SDE.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrnsteinUhlenbeckProcess{
+    /// The current value of the process.
+    value: f64,
+    /// The current time, i.e. the time at which the value was observed.
+    current_time: TimeStamp,
+    /// The speed `kappa` at which the process reverts to its long term mean.
+    mean_reversion_speed: NonNegativeFloat,
+    /// The long term mean `theta`.
+    long_term_mean: f64,
+    /// The volatility `sigma`.
+    volatility: NonNegativeFloat,
+}
+
+/// The state of an Ornstein-Uhlenbeck process at some time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrnsteinUhlenbeckState{
+    value: f64,
+    time: TimeStamp,
+}
+
+impl OrnsteinUhlenbeckState {
+    pub fn new(value: f64, time: TimeStamp)->OrnsteinUhlenbeckState{
+        OrnsteinUhlenbeckState{value, time}
+    }
+
+    pub fn get_value(&self)->f64{
+        self.value
+    }
+
+    pub fn get_time(&self)->TimeStamp{
+        self.time
+    }
+}
+
+impl Underlying for OrnsteinUhlenbeckProcess {
+
+}
+
+impl OrnsteinUhlenbeckProcess {
+    ///Returns a new process with the given parameters.
+    pub fn new(value: f64, current_time: TimeStamp, mean_reversion_speed: NonNegativeFloat, long_term_mean: f64,
+                volatility: NonNegativeFloat)->OrnsteinUhlenbeckProcess{
+        OrnsteinUhlenbeckProcess{
+            value,
+            current_time,
+            mean_reversion_speed,
+            long_term_mean,
+            volatility,
+        }
+    }
+
+    ///Returns the speed of mean reversion.
+    pub fn get_mean_reversion_speed(&self)->NonNegativeFloat{
+        self.mean_reversion_speed
+    }
+
+    ///Returns the long term mean.
+    pub fn get_long_term_mean(&self)->f64{
+        self.long_term_mean
+    }
+
+    ///Returns the volatility.
+    pub fn get_volatility(&self)->NonNegativeFloat{
+        self.volatility
+    }
+
+    ///Returns the current value and time of the process.
+    pub fn get_current_state(&self)->OrnsteinUhlenbeckState{
+        OrnsteinUhlenbeckState{value: self.value, time: self.current_time}
+    }
+
+    ///Returns the expected value of the process `time_step` after its current time.
+    pub fn get_mean(&self, time_step: Duration)->f64{
+        self.transition(self.value, f64::from(time_step)).0
+    }
+
+    ///Returns the variance of the process `time_step` after its current time.
+    pub fn get_variance(&self, time_step: Duration)->f64{
+        self.transition(self.value, f64::from(time_step)).1
+    }
+
+    ///Returns the expected value of the integral of the process over the `time_step` after its current time.
+    pub fn get_integral_mean(&self, time_step: Duration)->f64{
+        let (weight, _, _, _, _) = self.integral_transition(f64::from(time_step));
+        self.long_term_mean*f64::from(time_step)+(self.value-self.long_term_mean)*weight
+    }
+
+    ///Returns the variance of the integral of the process over the `time_step` after its current time.
+    pub fn get_integral_variance(&self, time_step: Duration)->f64{
+        self.integral_transition(f64::from(time_step)).2
+    }
+
+    /// Evolves the process exactly by `time_step`.
+    /// # Parameters
+    /// `gaussian_sample` - A sample of N(0,1), driving the move.
+    /// `time_step` - The length of time by which the process is evolved.
+    pub fn evolve(&mut self, gaussian_sample: f64, time_step: Duration){
+        let (mean, variance) = self.transition(self.value, f64::from(time_step));
+        self.value = mean+variance.sqrt()*gaussian_sample;
+        self.current_time += time_step;
+    }
+
+    /// Generates a path of the process at the provided time stamps, sampling every step exactly.
+    /// # Parameters
+    /// - `gaussians` - iid samples of N(0,1). Must be the same size or larger than `time_stamps`.
+    /// - `time_stamps` - Strictly increasing time stamps, the first one not before the current time.
+    /// # Panics
+    /// - If `time_stamps` is empty, not increasing, or there are time stamps before the current time.
+    /// - If `gaussians.len()<time_stamps.len()`.
+    pub fn generate_path_from_time_stamps(&self, gaussians: &[f64], time_stamps: &[TimeStamp])->Vec<OrnsteinUhlenbeckState>{
+        self.try_generate_path_from_time_stamps(gaussians, time_stamps).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `generate_path_from_time_stamps`, but returns a `PricingError` instead of panicking.
+    pub fn try_generate_path_from_time_stamps(&self, gaussians: &[f64], time_stamps: &[TimeStamp])
+        ->Result<Vec<OrnsteinUhlenbeckState>, PricingError>{
+        if gaussians.len() < time_stamps.len() {
+            return Err(PricingError::InsufficientSamples);
+        }
+        if time_stamps.is_empty() || time_stamps[0] < self.current_time {
+            return Err(PricingError::InvalidTimeGrid);
+        }
+        let mut path = Vec::with_capacity(time_stamps.len());
+        let (mut time, mut value) = (f64::from(self.current_time), self.value);
+        for (time_stamp, gaussian) in time_stamps.iter().zip(gaussians) {
+            let next_time = f64::from(*time_stamp);
+            if next_time < time {
+                return Err(PricingError::InvalidTimeGrid);
+            }
+            let (mean, variance) = self.transition(value, next_time-time);
+            value = mean+variance.sqrt()*gaussian;
+            time = next_time;
+            path.push(OrnsteinUhlenbeckState{value, time: *time_stamp});
+        }
+        Ok(path)
+    }
+
+    /// Returns the joint transition of the process `X` and of its integral `I` over `time_step`, as the tuple of
+    /// - the weight `a` of the initial deviation from the long term mean in the mean of `I`,
+    /// - the decay `d` of the initial deviation in the mean of `X`,
+    /// - the variance of `I`, the variance of `X`, and their covariance.
+    pub(crate) fn integral_transition(&self, time_step: f64)->(f64, f64, f64, f64, f64){
+        let kappa = f64::from(self.mean_reversion_speed);
+        let sigma_squared = f64::from(self.volatility)*f64::from(self.volatility);
+        let x = kappa*time_step;
+        let (weight, value_variance) = if kappa == 0.0 {
+            (time_step, sigma_squared*time_step)
+        }
+        else {
+            (-(-x).exp_m1()/kappa, -sigma_squared*(-2.0*x).exp_m1()/(2.0*kappa))
+        };
+        let integral_variance = if x < 1e-3 {
+            // The series of sigma^2/kappa^3*(x-2*(1-exp(-x))+(1-exp(-2x))/2), avoiding its cancellation.
+            sigma_squared*time_step.powi(3)*(1.0/3.0-x/4.0+7.0*x*x/60.0)
+        }
+        else {
+            sigma_squared*(x+2.0*(-x).exp_m1()-0.5*(-2.0*x).exp_m1())/kappa.powi(3)
+        };
+        (weight, (-x).exp(), integral_variance, value_variance, 0.5*sigma_squared*weight*weight)
+    }
+
+    /// Returns the mean and the variance of the process `time_step` after it had the value `value`.
+    fn transition(&self, value: f64, time_step: f64)->(f64, f64){
+        let kappa = f64::from(self.mean_reversion_speed);
+        let sigma_squared = f64::from(self.volatility)*f64::from(self.volatility);
+        if kappa*time_step < 1e-8 {
+            // The limit of a vanishing mean reversion, avoiding the cancellation in 1-exp(-2*kappa*t).
+            return (value+kappa*(self.long_term_mean-value)*time_step, sigma_squared*time_step);
+        }
+        let decay = (-kappa*time_step).exp();
+        (self.long_term_mean+(value-self.long_term_mean)*decay, sigma_squared*(1.0-decay*decay)/(2.0*kappa))
+    }
+}
+
+/// A european option on an Ornstein-Uhlenbeck process, whose payoff depends on the value of the process at expiry.
+pub struct OrnsteinUhlenbeckOption{
+    /// A shared reference to the underlying process.
+    underlying: Arc<OrnsteinUhlenbeckProcess>,
+    /// The time of expiry.
+    expiry: TimeStamp,
+    /// The payoff, as a function of the value of the process at expiry.
+    payoff: OrnsteinUhlenbeckPayoff,
+}
+
+impl OrnsteinUhlenbeckOption {
+    /// Returns a new option on `underlying` with the given expiry and payoff.
+    pub fn new(underlying: &Arc<OrnsteinUhlenbeckProcess>, expiry: TimeStamp, payoff: OrnsteinUhlenbeckPayoff)->OrnsteinUhlenbeckOption{
+        OrnsteinUhlenbeckOption{underlying: Arc::clone(underlying), expiry, payoff}
+    }
+
+    /// Returns the expiry of the option.
+    pub fn get_expiry(&self)->TimeStamp{
+        self.expiry
+    }
+}
+
+impl DerivativeOption<OrnsteinUhlenbeckProcess> for OrnsteinUhlenbeckOption {
+    /// Returns the time to expiry of the option, where the current time is considered to be the current time of the process.
+    fn get_time_to_expiry(&self)->Option<Duration>{
+        self.expiry.checked_duration_since(self.underlying.get_current_state().get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option.
+    fn get_dimensionality(&self)->usize{
+        1
+    }
+
+    /// Prices the option (not discounted) given one Gaussian sample. The short rate of interest is not used, as the
+    /// parameters of the process are those of the pricing measure.
+    fn price_path(&self, random_samples: &[f64], _r: f64)->f64{
+        let state = self.underlying.generate_path_from_time_stamps(random_samples, &[self.expiry]);
+        (self.payoff)(state[0].get_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monte_carlo_pricer::monte_carlo_pricer_with_error;
+    use crate::utils::{cumulative_normal_function, normal_probability_density_function};
+
+    #[test]
+    fn ornstein_uhlenbeck_test(){
+        let process = OrnsteinUhlenbeckProcess::new(-0.5, TimeStamp::from(1.0), NonNegativeFloat::from(2.0), 1.0,
+            NonNegativeFloat::from(0.8));
+        let decay = (-2.0_f64).exp();
+        assert!((process.get_mean(Duration::from(1.0))-(1.0-1.5*decay)).abs() < 1e-12);
+        assert!((process.get_variance(Duration::from(1.0))-0.64*(1.0-decay*decay)/4.0).abs() < 1e-12);
+        let path = process.generate_path_from_time_stamps(&[0.0, 0.0], &[TimeStamp::from(2.0), TimeStamp::from(3.0)]);
+        assert!((path[1].get_value()-process.get_mean(Duration::from(2.0))).abs() < 1e-12);
+        let mut evolved = process;
+        evolved.evolve(0.7, Duration::from(1.0));
+        let sample = process.generate_path_from_time_stamps(&[0.7], &[TimeStamp::from(2.0)]);
+        assert_eq!(evolved.get_current_state(), sample[0]);
+        assert_eq!(process.try_generate_path_from_time_stamps(&[0.0], &[TimeStamp::from(0.5)]), Err(PricingError::InvalidTimeGrid));
+        let brownian = OrnsteinUhlenbeckProcess::new(0.0, TimeStamp::from(0.0), NonNegativeFloat::from(0.0), 1.0,
+            NonNegativeFloat::from(0.3));
+        assert!((brownian.get_variance(Duration::from(2.0))-0.18).abs() < 1e-15);
+        assert!((brownian.get_integral_variance(Duration::from(2.0))-0.09*8.0/3.0).abs() < 1e-15);
+        // The integral over [1,2] of the mean, and the variance of the integral against a fine Riemann sum of the covariance.
+        assert!((process.get_integral_mean(Duration::from(1.0))-(1.0-1.5*(1.0-decay)/2.0)).abs() < 1e-12);
+        let n = 400;
+        let h = 1.0/n as f64;
+        let covariance = |s: f64, u: f64| 0.64/4.0*((-2.0*(s-u).abs()).exp()-(-2.0*(s+u)).exp());
+        let variance: f64 = (0..n).flat_map(|i| (0..n).map(move |j| (i, j)))
+            .map(|(i, j)| covariance((i as f64+0.5)*h, (j as f64+0.5)*h)*h*h).sum();
+        assert!((process.get_integral_variance(Duration::from(1.0))-variance).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ornstein_uhlenbeck_option_test(){
+        let process = Arc::new(OrnsteinUhlenbeckProcess::new(0.2, TimeStamp::from(0.0), NonNegativeFloat::from(1.5), 0.5,
+            NonNegativeFloat::from(0.4)));
+        let (strike, r) = (0.4, 0.03);
+        let option = OrnsteinUhlenbeckOption::new(&process, TimeStamp::from(1.0), Box::new(move |x| f64::max(x-strike, 0.0)));
+        let result = monte_carlo_pricer_with_error(&option, r, Some(5), 100000);
+        let (mean, deviation) = (process.get_mean(Duration::from(1.0)), process.get_variance(Duration::from(1.0)).sqrt());
+        let d = (mean-strike)/deviation;
+        let expected = (-r).exp()*((mean-strike)*cumulative_normal_function(d)+deviation*normal_probability_density_function(d));
+        assert!((result.get_price()-expected).abs() < 4.0*result.get_std_error());
+    }
+}
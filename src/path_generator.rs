@@ -0,0 +1,302 @@
+//! Provides the `PathGenerator` trait, which decouples the simulation of an underlying from the options written on it.
+//!
+//! A generator simulates the values of its underlying at a schedule of future times, and returns them as a `Path`. Options
+//! implementing `option::PathPayoff` compute their payoff from a `Path` alone, so they can be priced on any model implementing
+//! `PathGenerator` with `monte_carlo_pricer::monte_carlo_simulation_with_generator`, without a model specific implementation.
+//!
+//! The generators of this module are the stock following geometric Brownian motion, the Ornstein-Uhlenbeck process, a stock
+//! following the Heston model and a stock following the Merton jump diffusion model.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::models::MertonJumpDiffusionModel;
+//! use derivative_pricer::path_generator::{JumpDiffusionStock, PathGenerator};
+//! use derivative_pricer::random_number_generator::RandomNumberGenerator;
+//! use derivative_pricer::stock::StockState;
+//! use derivative_pricer::utils::{NonNegativeFloat, TimeStamp};
+//! let stock = JumpDiffusionStock::new(MertonJumpDiffusionModel::new(0.2, 0.5, -0.1, 0.15, 0.0),
+//!     StockState::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0)));
+//! let schedule = [TimeStamp::from(0.5), TimeStamp::from(1.0)];
+//! let path = stock.generate(&mut RandomNumberGenerator::new(Some(1)), &schedule, 0.05);
+//! assert_eq!(path.get_times(), &schedule);
+//! assert!(path.get_values().iter().all(|v| *v > 0.0));
+//! ```
+
+use crate::models::{HestonModel, MertonJumpDiffusionModel};
+use crate::ornstein_uhlenbeck::OrnsteinUhlenbeckProcess;
+use crate::random_number_generator::RandomNumberGeneratorTrait;
+use crate::stock::{GeometricBrownianMotionStock, StockState};
+use crate::utils::TimeStamp;
+
+/// The simulated values of an underlying at a schedule of times.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path{
+    /// The times of the schedule, increasing.
+    times: Vec<TimeStamp>,
+    /// The values of the underlying at the times.
+    values: Vec<f64>,
+}
+
+impl Path {
+    /// Returns a path with `values` at `times`.
+    /// # Panics
+    /// Panics if there is not one value for every time.
+    pub fn new(times: Vec<TimeStamp>, values: Vec<f64>)->Path{
+        if times.len() != values.len() {
+            panic!("A path needs one value for every time.");
+        }
+        Path{times, values}
+    }
+
+    /// Returns the path of the values and times of `states`.
+    pub fn from_states(states: &[StockState])->Path{
+        Path{
+            times: states.iter().map(|s| s.get_time()).collect(),
+            values: states.iter().map(|s| f64::from(s.get_value())).collect(),
+        }
+    }
+
+    /// Returns the times of the path.
+    pub fn get_times(&self)->&[TimeStamp]{
+        &self.times
+    }
+
+    /// Returns the values of the path.
+    pub fn get_values(&self)->&[f64]{
+        &self.values
+    }
+
+    /// Returns the number of times of the path.
+    pub fn len(&self)->usize{
+        self.times.len()
+    }
+
+    /// Returns `true` if the path has no times.
+    pub fn is_empty(&self)->bool{
+        self.times.is_empty()
+    }
+
+    /// Returns the value of the path at `time`, or `None` if `time` is not one of its times.
+    pub fn value_at(&self, time: TimeStamp)->Option<f64>{
+        self.times.iter().position(|t| *t == time).map(|i| self.values[i])
+    }
+}
+
+/// A trait for simulated underlyings, which generate paths at a schedule of future times under the pricing measure.
+pub trait PathGenerator {
+    /// Returns the current time of the underlying, from which the paths start.
+    fn get_current_time(&self)->TimeStamp;
+
+    /// Returns the current value of the underlying.
+    fn get_current_value(&self)->f64;
+
+    /// Returns a path of the underlying at the times of `schedule`.
+    /// # Parameters
+    /// - `rng` - the random number generator used to sample the path.
+    /// - `schedule` - increasing times, none of them before the current time.
+    /// - `r` - the short rate of interest, which sets the risk neutral drift of a stock. Generators of quantities which are not
+    ///   traded, such as the Ornstein-Uhlenbeck process, ignore it.
+    /// # Panics
+    /// Panics if `schedule` is not increasing or starts before the current time.
+    fn generate<R: RandomNumberGeneratorTrait>(&self, rng: &mut R, schedule: &[TimeStamp], r: f64)->Path;
+}
+
+/// Panics unless `schedule` is increasing and does not start before `current_time`.
+fn check_schedule(schedule: &[TimeStamp], current_time: TimeStamp){
+    if schedule.first().is_some_and(|t| *t < current_time) || schedule.windows(2).any(|w| w[1] < w[0]) {
+        panic!("The schedule must be increasing, and must not start before the current time.");
+    }
+}
+
+impl PathGenerator for GeometricBrownianMotionStock {
+    fn get_current_time(&self)->TimeStamp{
+        self.get_current_state().get_time()
+    }
+
+    fn get_current_value(&self)->f64{
+        f64::from(self.get_current_state().get_value())
+    }
+
+    /// Generates the path under the risk neutral measure with the discretization scheme of the stock, using one Gaussian sample
+    /// per time.
+    fn generate<R: RandomNumberGeneratorTrait>(&self, rng: &mut R, schedule: &[TimeStamp], r: f64)->Path{
+        check_schedule(schedule, self.get_current_time());
+        if schedule.is_empty() {
+            return Path::new(Vec::new(), Vec::new());
+        }
+        Path::from_states(&self.generate_risk_neutral_path_from_time_stamps(&rng.get_gaussians(schedule.len()), schedule, r))
+    }
+}
+
+impl PathGenerator for OrnsteinUhlenbeckProcess {
+    fn get_current_time(&self)->TimeStamp{
+        self.get_current_state().get_time()
+    }
+
+    fn get_current_value(&self)->f64{
+        self.get_current_state().get_value()
+    }
+
+    /// Generates the path exactly, using one Gaussian sample per time. The parameters of the process are those of the pricing
+    /// measure, so `r` is ignored.
+    fn generate<R: RandomNumberGeneratorTrait>(&self, rng: &mut R, schedule: &[TimeStamp], _r: f64)->Path{
+        check_schedule(schedule, self.get_current_time());
+        if schedule.is_empty() {
+            return Path::new(Vec::new(), Vec::new());
+        }
+        let states = self.generate_path_from_time_stamps(&rng.get_gaussians(schedule.len()), schedule);
+        Path::new(schedule.to_vec(), states.iter().map(|s| s.get_value()).collect())
+    }
+}
+
+/// A stock following the Heston model from a current state, simulated with the scheme of the model.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HestonStock{
+    /// The model of the stock.
+    model: HestonModel,
+    /// The current state of the stock.
+    current_state: StockState,
+    /// The longest step of the discretization.
+    maximal_time_step: f64,
+}
+
+impl HestonStock {
+    /// Returns a new Heston stock. The intervals between the times of a schedule are split into equal steps no longer than
+    /// `maximal_time_step`, see `HestonModel::generate_risk_neutral_path`.
+    /// # Panics
+    /// Panics if `maximal_time_step` is not positive.
+    pub fn new(model: HestonModel, current_state: StockState, maximal_time_step: f64)->HestonStock{
+        if maximal_time_step.is_nan() || maximal_time_step <= 0.0 {
+            panic!("The maximal time step must be positive.");
+        }
+        HestonStock{model, current_state, maximal_time_step}
+    }
+
+    /// Returns the model of the stock.
+    pub fn get_model(&self)->&HestonModel{
+        &self.model
+    }
+}
+
+impl PathGenerator for HestonStock {
+    fn get_current_time(&self)->TimeStamp{
+        self.current_state.get_time()
+    }
+
+    fn get_current_value(&self)->f64{
+        f64::from(self.current_state.get_value())
+    }
+
+    fn generate<R: RandomNumberGeneratorTrait>(&self, rng: &mut R, schedule: &[TimeStamp], r: f64)->Path{
+        check_schedule(schedule, self.get_current_time());
+        let now = f64::from(self.get_current_time());
+        let times: Vec<f64> = schedule.iter().map(|t| f64::from(*t)-now).collect();
+        Path::new(schedule.to_vec(), self.model.generate_risk_neutral_path(self.get_current_value(), r, &times, self.maximal_time_step, rng))
+    }
+}
+
+/// A stock following the Merton jump diffusion model from a current state. The log return over every interval of a schedule is
+/// sampled exactly, as a Gaussian plus a Poisson number of Gaussian jumps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JumpDiffusionStock{
+    /// The model of the stock.
+    model: MertonJumpDiffusionModel,
+    /// The current state of the stock.
+    current_state: StockState,
+}
+
+impl JumpDiffusionStock {
+    /// Returns a new jump diffusion stock.
+    pub fn new(model: MertonJumpDiffusionModel, current_state: StockState)->JumpDiffusionStock{
+        JumpDiffusionStock{model, current_state}
+    }
+
+    /// Returns the model of the stock.
+    pub fn get_model(&self)->&MertonJumpDiffusionModel{
+        &self.model
+    }
+}
+
+impl PathGenerator for JumpDiffusionStock {
+    fn get_current_time(&self)->TimeStamp{
+        self.current_state.get_time()
+    }
+
+    fn get_current_value(&self)->f64{
+        f64::from(self.current_state.get_value())
+    }
+
+    fn generate<R: RandomNumberGeneratorTrait>(&self, rng: &mut R, schedule: &[TimeStamp], r: f64)->Path{
+        check_schedule(schedule, self.get_current_time());
+        let model = &self.model;
+        let (volatility, jump_mean, jump_volatility) = (model.get_volatility(), model.get_jump_mean(), model.get_jump_volatility());
+        let mean_jump_size = (jump_mean+0.5*jump_volatility*jump_volatility).exp()-1.0;
+        let drift = r-model.get_divident_rate()-0.5*volatility*volatility-model.get_jump_intensity()*mean_jump_size;
+        let mut log_spot = self.get_current_value().ln();
+        let mut previous_time = f64::from(self.get_current_time());
+        let values = schedule.iter().map(|t| {
+            let time_step = f64::from(*t)-previous_time;
+            let gaussians = rng.get_gaussians(2);
+            let jumps = rng.get_poissons(model.get_jump_intensity()*time_step, 1)[0] as f64;
+            log_spot += drift*time_step+volatility*time_step.sqrt()*gaussians[0]+jumps*jump_mean+jumps.sqrt()*jump_volatility*gaussians[1];
+            previous_time = f64::from(*t);
+            log_spot.exp()
+        }).collect();
+        Path::new(schedule.to_vec(), values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_pricer::CarrMadanPricer;
+    use crate::random_number_generator::RandomNumberGenerator;
+    use crate::utils::NonNegativeFloat;
+
+    #[test]
+    fn path_test(){
+        let path = Path::new(vec![TimeStamp::from(1.0), TimeStamp::from(2.0)], vec![3.0, 4.0]);
+        assert_eq!(path.value_at(TimeStamp::from(2.0)), Some(4.0));
+        assert_eq!(path.value_at(TimeStamp::from(1.5)), None);
+        assert_eq!(path.len(), 2);
+        let states = [StockState::new(NonNegativeFloat::from(3.0), TimeStamp::from(1.0)), StockState::new(NonNegativeFloat::from(4.0),
+            TimeStamp::from(2.0))];
+        assert_eq!(Path::from_states(&states), path);
+    }
+
+    #[test]
+    fn generators_test(){
+        let schedule = [TimeStamp::from(1.5), TimeStamp::from(2.0)];
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(1.0), 0.1, NonNegativeFloat::from(0.2),
+            NonNegativeFloat::from(0.0));
+        let mut rng = RandomNumberGenerator::new(Some(1));
+        let gaussians = RandomNumberGenerator::new(Some(1)).get_gaussians(2);
+        let path = stock.generate(&mut rng, &schedule, 0.05);
+        assert_eq!(path, Path::from_states(&stock.generate_risk_neutral_path_from_time_stamps(&gaussians, &schedule, 0.05)));
+        let process = OrnsteinUhlenbeckProcess::new(-1.0, TimeStamp::from(1.0), NonNegativeFloat::from(2.0), 0.5, NonNegativeFloat::from(0.3));
+        let path = process.generate(&mut RandomNumberGenerator::new(Some(1)), &schedule, 0.05);
+        assert_eq!(path.get_values()[1], process.generate_path_from_time_stamps(&gaussians, &schedule)[1].get_value());
+        // The jump diffusion stock prices vanilla options like the transform of its characteristic function.
+        let model = MertonJumpDiffusionModel::new(0.2, 0.8, -0.1, 0.2, 0.01);
+        let stock = JumpDiffusionStock::new(model, StockState::new(NonNegativeFloat::from(100.0), TimeStamp::from(1.0)));
+        let number_of_paths = 100000;
+        let (mut mean, mut call) = (0.0, 0.0);
+        for _ in 0..number_of_paths {
+            let spot = stock.generate(&mut rng, &schedule, 0.05).get_values()[1];
+            mean += spot/number_of_paths as f64;
+            call += f64::max(spot-100.0, 0.0)/number_of_paths as f64;
+        }
+        assert!((mean-100.0*0.04_f64.exp()).abs() < 0.2);
+        let reference = CarrMadanPricer::default().call_prices(&model, 100.0, 0.05, 1.0, &[100.0])[0];
+        assert!((call*(-0.05_f64).exp()-reference).abs() < 0.1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn schedule_before_current_time_test(){
+        let stock = HestonStock::new(HestonModel::new(0.04, 1.0, 0.04, 0.3, -0.5, 0.0),
+            StockState::new(NonNegativeFloat::from(100.0), TimeStamp::from(1.0)), 0.1);
+        stock.generate(&mut RandomNumberGenerator::new(Some(1)), &[TimeStamp::from(0.5)], 0.05);
+    }
+}
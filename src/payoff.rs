@@ -0,0 +1,199 @@
+//! Provides the `Payoff` trait for the payoffs of options whose value at exercise depends on a single value of the underlying
+//! (e.g. its value at expiry, or its average), together with the standard payoffs.
+//!
+//! Unlike boxed closures with a slice of parameters, the concrete payoffs can be cloned, compared and printed, and so can the
+//! options built from them.
+//!
+//! The trait is used by `VanillaStockOption` and `AsianOption`, the options of the `option` module whose payoff is chosen by
+//! the user. The other options there (floating strike Asian, spread, basket, Himalayan, cliquet, touch, ladder options and
+//! accumulators) have a payoff fixed by their type, with its terms stored as fields, so they take no payoff. The grid pricers
+//! (`pde_pricer`, `quadrature_pricer` and `replication_pricer`) take a plain `&dyn Fn(f64)->f64`, which a payoff gives as
+//! `|s| payoff.value(NonNegativeFloat::from(s))`. The payoffs of options on an Ornstein-Uhlenbeck process and on the solutions
+//! of an SDE stay closures, since they take values which may be negative, or whole paths.
+
+use std::sync::Arc;
+
+use crate::option::PayoffFunction;
+use crate::utils::NonNegativeFloat;
+
+/// The type of a vanilla option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "io", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum VanillaType{
+    /// A european call option.
+    Call,
+    /// A european put option.
+    Put,
+}
+
+/// A payoff of an option as a function of a value of the underlying.
+pub trait Payoff {
+    /// Returns the payoff given the value (or average value) of the underlying.
+    fn value(&self, spot: NonNegativeFloat)->f64;
+    /// Returns the type and the strike of the payoff if it is the payoff of a call or a put, so that options with it can use the
+    /// closed form formulas. Returns `None` by default.
+    fn as_vanilla(&self)->Option<(VanillaType, f64)>{
+        None
+    }
+}
+
+/// The payoff `max(S-K, 0)` of a call option.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CallPayoff{
+    /// The strike of the option.
+    strike: f64,
+}
+
+impl CallPayoff {
+    /// Returns the payoff of a call option with strike `strike`.
+    pub fn new(strike: f64)->CallPayoff{
+        CallPayoff{strike}
+    }
+
+    /// Returns the strike of the option.
+    pub fn get_strike(&self)->f64{
+        self.strike
+    }
+}
+
+impl Payoff for CallPayoff {
+    fn value(&self, spot: NonNegativeFloat)->f64{
+        f64::max(f64::from(spot)-self.strike, 0.0)
+    }
+
+    fn as_vanilla(&self)->Option<(VanillaType, f64)>{
+        Some((VanillaType::Call, self.strike))
+    }
+}
+
+/// The payoff `max(K-S, 0)` of a put option.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PutPayoff{
+    /// The strike of the option.
+    strike: f64,
+}
+
+impl PutPayoff {
+    /// Returns the payoff of a put option with strike `strike`.
+    pub fn new(strike: f64)->PutPayoff{
+        PutPayoff{strike}
+    }
+
+    /// Returns the strike of the option.
+    pub fn get_strike(&self)->f64{
+        self.strike
+    }
+}
+
+impl Payoff for PutPayoff {
+    fn value(&self, spot: NonNegativeFloat)->f64{
+        f64::max(self.strike-f64::from(spot), 0.0)
+    }
+
+    fn as_vanilla(&self)->Option<(VanillaType, f64)>{
+        Some((VanillaType::Put, self.strike))
+    }
+}
+
+/// The payoff of a digital call option, which pays 1 if `S>K` and 0 otherwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DigitalCallPayoff{
+    /// The strike of the option.
+    strike: f64,
+}
+
+impl DigitalCallPayoff {
+    /// Returns the payoff of a digital call option with strike `strike`.
+    pub fn new(strike: f64)->DigitalCallPayoff{
+        DigitalCallPayoff{strike}
+    }
+
+    /// Returns the strike of the option.
+    pub fn get_strike(&self)->f64{
+        self.strike
+    }
+}
+
+impl Payoff for DigitalCallPayoff {
+    fn value(&self, spot: NonNegativeFloat)->f64{
+        if f64::from(spot) > self.strike { 1.0 } else { 0.0 }
+    }
+}
+
+/// The payoff of a digital put option, which pays 1 if `S<K` and 0 otherwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DigitalPutPayoff{
+    /// The strike of the option.
+    strike: f64,
+}
+
+impl DigitalPutPayoff {
+    /// Returns the payoff of a digital put option with strike `strike`.
+    pub fn new(strike: f64)->DigitalPutPayoff{
+        DigitalPutPayoff{strike}
+    }
+
+    /// Returns the strike of the option.
+    pub fn get_strike(&self)->f64{
+        self.strike
+    }
+}
+
+impl Payoff for DigitalPutPayoff {
+    fn value(&self, spot: NonNegativeFloat)->f64{
+        if f64::from(spot) < self.strike { 1.0 } else { 0.0 }
+    }
+}
+
+/// A payoff function shared between the clones of a `ClosurePayoff`.
+type SharedPayoffFunction = Arc<dyn Fn(NonNegativeFloat, &[f64])->f64+Send+Sync>;
+
+/// A payoff given by a boxed function and a vector of parameters, for payoffs without a dedicated struct. Clones share the function.
+#[derive(Clone)]
+pub struct ClosurePayoff{
+    /// The payoff function. Gets the value of the underlying and a slice of parameters.
+    payoff_function: SharedPayoffFunction,
+    /// A vector of whatever parameters are needed to compute the payoff function, e.g. strike price.
+    params: Vec<f64>,
+}
+
+impl ClosurePayoff {
+    /// Returns a new payoff which evaluates `payoff_function` with the parameters `params`.
+    pub fn new(payoff_function: PayoffFunction, params: Vec<f64>)->ClosurePayoff{
+        ClosurePayoff{
+            payoff_function: Arc::from(payoff_function),
+            params,
+        }
+    }
+
+    /// Returns the parameters of the payoff function.
+    pub fn get_params(&self)->&[f64]{
+        &self.params
+    }
+}
+
+impl Payoff for ClosurePayoff {
+    fn value(&self, spot: NonNegativeFloat)->f64{
+        (self.payoff_function)(spot, &self.params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payoffs_test(){
+        let spot = NonNegativeFloat::from(105.0);
+        assert_eq!(CallPayoff::new(100.0).value(spot), 5.0);
+        assert_eq!(PutPayoff::new(100.0).value(spot), 0.0);
+        assert_eq!(DigitalCallPayoff::new(100.0).value(spot), 1.0);
+        assert_eq!(DigitalPutPayoff::new(100.0).value(spot), 0.0);
+        fn call(spot: NonNegativeFloat, params: &[f64])->f64{
+            f64::max(f64::from(spot)-params[0], 0.0)
+        }
+        assert_eq!(ClosurePayoff::new(Box::new(call), vec![100.0]).value(spot), 5.0);
+        assert_eq!(CallPayoff::new(100.0).as_vanilla(), Some((VanillaType::Call, 100.0)));
+        assert_eq!(ClosurePayoff::new(Box::new(call), vec![100.0]).as_vanilla(), None);
+    }
+}
@@ -0,0 +1,749 @@
+//! Provides a finite difference pricer solving the Black-Scholes PDE with a Crank-Nicolson scheme.
+//!
+//! The pricer works backwards from a terminal payoff on a grid of spot values, and returns the values of the option
+//! on the whole grid at the current time, as well as the price at the given spot. The grid is uniform, or sinh-stretched to
+//! concentrate the spot values near a strike or a barrier, and the first time steps can be replaced by implicit Euler
+//! half steps (Rannacher start-up), which damps the oscillations Crank-Nicolson produces from discontinuous payoffs. On request it also returns the values at
+//! every time step, as a `PdeSurface` which can be interpolated in spot and time.
+//! American options are priced by projecting each time step on the early exercise constraint with projected SOR, and their
+//! solutions also hold the early exercise boundary.
+
+use crate::error::PricingError;
+use crate::greeks::surface::Grid2D;
+
+/// A boundary function. Gets the time to expiry and returns the value of the option on the boundary.
+pub type BoundaryFunction = Box<dyn Fn(f64)->f64>;
+
+/// A boundary condition of the finite difference grid.
+pub enum BoundaryCondition{
+    /// The value on the boundary is given as a function of the time to expiry.
+    Dirichlet(BoundaryFunction),
+    /// The second derivative of the value with respect to the spot vanishes on the boundary.
+    Linear,
+}
+
+/// The grid of the finite difference scheme: spot values in `[minimal_spot, maximal_spot]`, evenly spaced unless the grid is
+/// concentrated with `with_concentration`, and evenly spaced time steps between now and expiry.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PdeGrid{
+    /// The lowest spot value of the grid.
+    minimal_spot: f64,
+    /// The highest spot value of the grid.
+    maximal_spot: f64,
+    /// The number of steps between `minimal_spot` and `maximal_spot`.
+    number_of_spot_steps: usize,
+    /// The number of steps between now and expiry.
+    number_of_time_steps: usize,
+    /// The spot value around which the spot values are concentrated and the width of the concentration, if any.
+    concentration: Option<(f64, f64)>,
+}
+
+impl PdeGrid {
+    /// Returns a new grid.
+    /// # Parameters
+    /// - `minimal_spot`: The lowest spot value of the grid, usually 0.
+    /// - `maximal_spot`: The highest spot value of the grid, usually a few standard deviations above the spot.
+    /// - `number_of_spot_steps`: The number of steps between `minimal_spot` and `maximal_spot`.
+    /// - `number_of_time_steps`: The number of steps between now and expiry.
+    /// # Panics
+    /// Panics if `minimal_spot` is negative, if `maximal_spot` is not greater than `minimal_spot`, if there are fewer than two
+    /// spot steps or if there are no time steps.
+    pub fn new(minimal_spot: f64, maximal_spot: f64, number_of_spot_steps: usize, number_of_time_steps: usize)->PdeGrid{
+        if minimal_spot < 0.0 || maximal_spot <= minimal_spot{
+            panic!("The spot range of the grid is invalid.");
+        }
+        if number_of_spot_steps < 2 || number_of_time_steps == 0{
+            panic!("The grid needs at least two spot steps and one time step.");
+        }
+        PdeGrid{
+            minimal_spot,
+            maximal_spot,
+            number_of_spot_steps,
+            number_of_time_steps,
+            concentration: None,
+        }
+    }
+
+    /// Returns the grid with its spot values concentrated around `center`, e.g. the strike or the barrier of the option, with the
+    /// sinh transformation `S(u) = center+width*sinh(u)` of evenly spaced `u`. Within about `width` of `center` the spot steps
+    /// are roughly `width/(maximal_spot-minimal_spot)` times the mean step times a logarithmic factor, and they grow
+    /// exponentially away from it, so a smaller `width` gives a stronger concentration.
+    /// # Examples
+    /// ```
+    /// use derivative_pricer::pde_pricer::PdeGrid;
+    /// let spots = PdeGrid::new(0.0, 400.0, 200, 100).with_concentration(100.0, 10.0).get_spots();
+    /// assert_eq!((spots[0], spots[200]), (0.0, 400.0));
+    /// let step_near = |x: f64| spots.windows(2).find(|w| w[1] >= x).map(|w| w[1]-w[0]).unwrap();
+    /// assert!(step_near(100.0) < 0.5 && step_near(300.0) > 5.0);
+    /// ```
+    /// # Panics
+    /// Panics if `width` is not positive or `center` is not finite.
+    pub fn with_concentration(self, center: f64, width: f64)->PdeGrid{
+        if width.is_nan() || width <= 0.0 || !center.is_finite(){
+            panic!("The width of the concentration must be positive and its center finite.");
+        }
+        PdeGrid{concentration: Some((center, width)), ..self}
+    }
+
+    /// Returns the spot values of the grid.
+    pub fn get_spots(&self)->Vec<f64>{
+        let n = self.number_of_spot_steps;
+        match self.concentration {
+            None => {
+                let step = (self.maximal_spot-self.minimal_spot)/n as f64;
+                (0..=n).map(|i| self.minimal_spot+step*i as f64).collect()
+            },
+            Some((center, width)) => {
+                let lowest = ((self.minimal_spot-center)/width).asinh();
+                let highest = ((self.maximal_spot-center)/width).asinh();
+                let mut spots: Vec<f64> = (0..=n).map(|i| center+width*(lowest+(highest-lowest)*i as f64/n as f64).sinh()).collect();
+                spots[0] = self.minimal_spot;
+                spots[n] = self.maximal_spot;
+                spots
+            },
+        }
+    }
+
+    /// Returns the number of steps between now and expiry.
+    pub fn get_number_of_time_steps(&self)->usize{
+        self.number_of_time_steps
+    }
+}
+
+/// The result of a finite difference pricing: the values of the option on the spot grid at the current time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PdeSolution{
+    /// The price at the requested spot, interpolated from the grid.
+    price: f64,
+    /// The spot values of the grid.
+    spots: Vec<f64>,
+    /// The values of the option at the spot values of the grid.
+    values: Vec<f64>,
+    /// The early exercise boundary of an american option as `(time, critical spot)` points, empty for european options.
+    exercise_boundary: Vec<(f64, f64)>,
+    /// The values at every time step, if the pricer stores them.
+    surface: Option<PdeSurface>,
+}
+
+impl PdeSolution {
+    /// Returns the price at the requested spot.
+    pub fn get_price(&self)->f64{
+        self.price
+    }
+
+    /// Returns the spot values of the grid.
+    pub fn get_spots(&self)->&[f64]{
+        &self.spots
+    }
+
+    /// Returns the values of the option at the spot values of the grid.
+    pub fn get_values(&self)->&[f64]{
+        &self.values
+    }
+
+    /// Returns the early exercise boundary of an american option, as `(time, critical spot)` points in increasing order of
+    /// time, where the time is measured from now. At every time step before expiry, the critical spots are the exercised grid
+    /// spots next to a spot where the option is held, e.g. the highest spot at which an american put is exercised. The
+    /// boundary is thus accurate to one spot step, and a time at which the option is nowhere exercised has no point.
+    /// The boundary is empty for european options.
+    pub fn get_exercise_boundary(&self)->&[(f64, f64)]{
+        &self.exercise_boundary
+    }
+
+    /// Returns the values of the option at every time step, or `None` unless the pricer was created `with_surface_output`.
+    pub fn get_surface(&self)->Option<&PdeSurface>{
+        self.surface.as_ref()
+    }
+
+    /// Returns the delta of the option at `spot`, from central differences on the grid, linearly interpolated.
+    /// # Panics
+    /// Panics if `spot` is outside the grid.
+    pub fn delta_at(&self, spot: f64)->f64{
+        spot_derivative(&self.spots, &self.values, spot, false)
+    }
+
+    /// Returns the gamma of the option at `spot`, from central differences on the grid, linearly interpolated.
+    /// # Panics
+    /// Panics if `spot` is outside the grid.
+    pub fn gamma_at(&self, spot: f64)->f64{
+        spot_derivative(&self.spots, &self.values, spot, true)
+    }
+
+    /// Returns the value of the option at `spot`, linearly interpolated from the grid.
+    /// # Panics
+    /// Panics if `spot` is outside the grid.
+    pub fn value_at(&self, spot: f64)->f64{
+        interpolate(&self.spots, &self.values, spot)
+    }
+}
+
+/// The values of an option on the whole `(time, spot)` grid of a finite difference scheme, where the time is measured from now.
+///
+/// # Examples
+///
+/// ```
+/// use derivative_pricer::pde_pricer::{BoundaryCondition, CrankNicolsonPricer, PdeGrid};
+/// let pricer = CrankNicolsonPricer::new(PdeGrid::new(0.0, 400.0, 400, 100), BoundaryCondition::Linear, BoundaryCondition::Linear)
+///     .with_surface_output(true);
+/// let solution = pricer.price(&|s| f64::max(s-100.0, 0.0), 100.0, 0.05, 1.0, 0.2, 0.0);
+/// let surface = solution.get_surface().unwrap();
+/// assert_eq!(surface.value_at(100.0, 0.0), solution.get_price());
+/// assert!(surface.value_at(100.0, 0.5) < solution.get_price());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PdeSurface{
+    /// The times of the time steps, increasing from now to expiry.
+    times: Vec<f64>,
+    /// The spot values of the grid.
+    spots: Vec<f64>,
+    /// The values of the option, one row for every time.
+    values: Vec<Vec<f64>>,
+}
+
+impl PdeSurface {
+    /// Returns the times of the time steps, increasing from now (0) to expiry.
+    pub fn get_times(&self)->&[f64]{
+        &self.times
+    }
+
+    /// Returns the spot values of the grid.
+    pub fn get_spots(&self)->&[f64]{
+        &self.spots
+    }
+
+    /// Returns the values of the option, where `get_values()[i][j]` is the value at `get_times()[i]` and `get_spots()[j]`.
+    pub fn get_values(&self)->&[Vec<f64>]{
+        &self.values
+    }
+
+    /// Returns the value of the option at `spot` and `time`, interpolated bilinearly from the grid.
+    /// # Panics
+    /// Panics if `spot` or `time` is outside the grid.
+    pub fn value_at(&self, spot: f64, time: f64)->f64{
+        self.interpolate_in_time(time, |row| interpolate(&self.spots, row, spot))
+    }
+
+    /// Returns the delta of the option at `spot` and `time`, from central differences on the grid, interpolated bilinearly.
+    /// # Panics
+    /// Panics if `spot` or `time` is outside the grid.
+    pub fn delta_at(&self, spot: f64, time: f64)->f64{
+        self.interpolate_in_time(time, |row| spot_derivative(&self.spots, row, spot, false))
+    }
+
+    /// Returns the gamma of the option at `spot` and `time`, from central differences on the grid, interpolated bilinearly.
+    /// # Panics
+    /// Panics if `spot` or `time` is outside the grid.
+    pub fn gamma_at(&self, spot: f64, time: f64)->f64{
+        self.interpolate_in_time(time, |row| spot_derivative(&self.spots, row, spot, true))
+    }
+
+    /// Returns the theta of the option at `spot` and `time`, i.e. the derivative of the value with respect to the time, from
+    /// the difference between the time steps around `time`.
+    /// # Panics
+    /// Panics if `spot` or `time` is outside the grid.
+    pub fn theta_at(&self, spot: f64, time: f64)->f64{
+        let i = self.time_index(time);
+        (interpolate(&self.spots, &self.values[i], spot)-interpolate(&self.spots, &self.values[i-1], spot))/(self.times[i]-self.times[i-1])
+    }
+
+    /// Returns the surface as a `Grid2D` with the times as the first axis and the spots as the second, e.g. for plotting.
+    pub fn to_grid(&self)->Grid2D{
+        Grid2D::new(self.times.clone(), self.spots.clone(), self.values.clone())
+    }
+
+    /// Returns the index `i` such that `time` is in `[times[i-1], times[i]]`.
+    fn time_index(&self, time: f64)->usize{
+        if time < self.times[0] || time > self.times[self.times.len()-1]{
+            panic!("The time is outside the grid.");
+        }
+        self.times.partition_point(|t| *t <= time).clamp(1, self.times.len()-1)
+    }
+
+    /// Interpolates `f` of the rows of the time steps around `time` linearly in time.
+    fn interpolate_in_time(&self, time: f64, f: impl Fn(&[f64])->f64)->f64{
+        let i = self.time_index(time);
+        let weight = (time-self.times[i-1])/(self.times[i]-self.times[i-1]);
+        (1.0-weight)*f(&self.values[i-1])+weight*f(&self.values[i])
+    }
+}
+
+/// A Crank-Nicolson finite difference pricer for options on a stock following a geometric Brownian motion.
+pub struct CrankNicolsonPricer{
+    /// The grid of the scheme.
+    grid: PdeGrid,
+    /// The boundary condition at the lowest spot of the grid.
+    lower_boundary: BoundaryCondition,
+    /// The boundary condition at the highest spot of the grid.
+    upper_boundary: BoundaryCondition,
+    /// Whether the solutions hold the values at every time step.
+    surface_output: bool,
+    /// The number of first time steps replaced by two implicit Euler half steps each.
+    rannacher_steps: usize,
+}
+
+impl CrankNicolsonPricer {
+    /// Returns a new pricer.
+    /// # Parameters
+    /// - `grid`: The grid of the scheme.
+    /// - `lower_boundary`: The boundary condition at the lowest spot of the grid.
+    /// - `upper_boundary`: The boundary condition at the highest spot of the grid.
+    pub fn new(grid: PdeGrid, lower_boundary: BoundaryCondition, upper_boundary: BoundaryCondition)->CrankNicolsonPricer{
+        CrankNicolsonPricer{
+            grid,
+            lower_boundary,
+            upper_boundary,
+            surface_output: false,
+            rannacher_steps: 0,
+        }
+    }
+
+    /// Returns the pricer which replaces each of the first `rannacher_steps` time steps after expiry by two implicit Euler half
+    /// steps (Rannacher start-up). Two steps usually suffice to remove the spurious oscillations in the greeks of options with
+    /// discontinuous payoffs, such as digitals, without losing the second order accuracy of Crank-Nicolson.
+    pub fn with_rannacher_steps(self, rannacher_steps: usize)->CrankNicolsonPricer{
+        CrankNicolsonPricer{rannacher_steps, ..self}
+    }
+
+    /// Returns the pricer whose solutions hold the values at every time step, as a `PdeSurface`, if `surface_output` is true.
+    /// The surface takes memory proportional to the number of spot steps times the number of time steps.
+    pub fn with_surface_output(self, surface_output: bool)->CrankNicolsonPricer{
+        CrankNicolsonPricer{surface_output, ..self}
+    }
+
+    /// Returns the grid of the pricer.
+    pub fn get_grid(&self)->PdeGrid{
+        self.grid
+    }
+
+    /// Prices an option with the given terminal payoff.
+    /// # Parameters
+    /// - `payoff` - the payoff of the option as a function of the spot at expiry.
+    /// - `spot` - the current spot of the stock.
+    /// - `short_rate_of_interest` - the short rate of interest.
+    /// - `time_to_expiry` - the time to expiry of the option.
+    /// - `volatility` - the volatility of the stock.
+    /// - `divident_rate` - the continuous divident rate of the stock.
+    /// # Panics
+    /// Panics if one of the parameters is negative, or if `spot` is outside the grid.
+    pub fn price(&self, payoff: &dyn Fn(f64)->f64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+        divident_rate: f64)->PdeSolution{
+        match self.solve(payoff, spot, short_rate_of_interest, time_to_expiry, volatility, divident_rate, false) {
+            Ok(solution) => solution,
+            Err(_) => unreachable!("Only the early exercise iteration can fail to converge."),
+        }
+    }
+
+    /// Prices an american option, i.e. an option that can be exercised at any time up to expiry for `payoff`.
+    /// # Parameters
+    /// - `payoff` - the exercise value of the option as a function of the spot.
+    /// - `spot` - the current spot of the stock.
+    /// - `short_rate_of_interest` - the short rate of interest.
+    /// - `time_to_expiry` - the time to expiry of the option.
+    /// - `volatility` - the volatility of the stock.
+    /// - `divident_rate` - the continuous divident rate of the stock.
+    /// # Panics
+    /// Panics if one of the parameters is negative, if `spot` is outside the grid, or if the projected SOR iteration which
+    /// enforces early exercise does not converge at some time step.
+    pub fn price_american(&self, payoff: &dyn Fn(f64)->f64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+        divident_rate: f64)->PdeSolution{
+        match self.solve(payoff, spot, short_rate_of_interest, time_to_expiry, volatility, divident_rate, true) {
+            Ok(solution) => solution,
+            Err(_) => panic!("The projected SOR iteration did not converge."),
+        }
+    }
+
+    /// Same as `price_american`, but returns `PricingError::NegativeInput` if one of the parameters is negative,
+    /// `PricingError::InvalidMarketData` if `spot` is outside the grid, and `PricingError::NoConvergence` if the projected SOR
+    /// iteration does not converge, instead of panicking.
+    pub fn try_price_american(&self, payoff: &dyn Fn(f64)->f64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+        divident_rate: f64)->Result<PdeSolution, PricingError>{
+        self.check_inputs(spot, time_to_expiry, volatility, divident_rate)?;
+        self.solve(payoff, spot, short_rate_of_interest, time_to_expiry, volatility, divident_rate, true)
+    }
+
+    /// Runs the scheme backwards from expiry. If `american` is `true`, the values are kept above `payoff` at every time step, and
+    /// `PricingError::NoConvergence` is returned if the projected SOR iteration does not converge.
+    #[allow(clippy::too_many_arguments)]
+    fn solve(&self, payoff: &dyn Fn(f64)->f64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+        divident_rate: f64, american: bool)->Result<PdeSolution, PricingError>{
+        if spot < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+            panic!("One of the parameters is negative")
+        }
+        let spots = self.grid.get_spots();
+        let exercise_values: Vec<f64> = spots.iter().map(|s| payoff(*s)).collect();
+        let mut values = exercise_values.clone();
+        let time_step = time_to_expiry/self.grid.number_of_time_steps as f64;
+        let n = spots.len();
+        let mut exercise_boundary = Vec::new();
+        let mut surface_values = Vec::new();
+        if self.surface_output {
+            surface_values.push(values.clone());
+        }
+        // The coefficients of the spatial operator at the interior nodes, acting on the values at the node below, at the node and
+        // above, from the second order differences on the possibly non-uniform grid.
+        let operator: Vec<(f64, f64, f64)> = (1..n-1).map(|i| {
+            let (s, below, above) = (spots[i], spots[i]-spots[i-1], spots[i+1]-spots[i]);
+            let diffusion = volatility*volatility*s*s/(below+above);
+            let convection = (short_rate_of_interest-divident_rate)*s/(below+above);
+            (diffusion/below-convection*above/below, -(diffusion+convection*(below-above))*(below+above)/(below*above)
+                -short_rate_of_interest, diffusion/above+convection*below/above)
+        }).collect();
+        for step in 1..=self.grid.number_of_time_steps{
+            let tau = time_step*step as f64;
+            if step <= self.rannacher_steps {
+                self.theta_step(&operator, &spots, &mut values, &exercise_values, 0.5*time_step, 1.0, tau-0.5*time_step, american)?;
+                self.theta_step(&operator, &spots, &mut values, &exercise_values, 0.5*time_step, 1.0, tau, american)?;
+            }
+            else {
+                self.theta_step(&operator, &spots, &mut values, &exercise_values, time_step, 0.5, tau, american)?;
+            }
+            if american {
+                // Projected SOR sets the exercised interior values to the exercise values exactly.
+                let exercised: Vec<bool> = (1..n-1).map(|i| exercise_values[i] > 0.0 && values[i] == exercise_values[i]).collect();
+                let time = time_to_expiry-tau;
+                for i in 0..n-2 {
+                    let next_to_held = (i > 0 && !exercised[i-1]) || (i < n-3 && !exercised[i+1]);
+                    if exercised[i] && next_to_held {
+                        exercise_boundary.push((time, spots[i+1]));
+                    }
+                }
+            }
+            if self.surface_output {
+                surface_values.push(values.clone());
+            }
+        }
+        exercise_boundary.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let surface = self.surface_output.then(|| {
+            surface_values.reverse();
+            PdeSurface{
+                times: (0..=self.grid.number_of_time_steps).map(|step| time_step*step as f64).collect(),
+                spots: spots.clone(),
+                values: surface_values,
+            }
+        });
+        Ok(PdeSolution{
+            price: interpolate(&spots, &values, spot),
+            spots,
+            values,
+            exercise_boundary,
+            surface,
+        })
+    }
+
+    /// Same as `price`, but returns `PricingError::NegativeInput` if one of the parameters is negative, and
+    /// `PricingError::InvalidMarketData` if `spot` is outside the grid, instead of panicking.
+    pub fn try_price(&self, payoff: &dyn Fn(f64)->f64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+        divident_rate: f64)->Result<PdeSolution, PricingError>{
+        self.check_inputs(spot, time_to_expiry, volatility, divident_rate)?;
+        self.solve(payoff, spot, short_rate_of_interest, time_to_expiry, volatility, divident_rate, false)
+    }
+
+    /// Advances `values` by the time step `time_step` to the time to expiry `tau` with the theta scheme, i.e. Crank-Nicolson if
+    /// `theta` is 0.5 and implicit Euler if it is 1.
+    #[allow(clippy::too_many_arguments)]
+    fn theta_step(&self, operator: &[(f64, f64, f64)], spots: &[f64], values: &mut [f64], exercise_values: &[f64], time_step: f64,
+        theta: f64, tau: f64, american: bool)->Result<(), PricingError>{
+        let n = spots.len();
+        let explicit = (1.0-theta)*time_step;
+        let implicit = theta*time_step;
+        let mut lower = Vec::with_capacity(n-2);
+        let mut diagonal = Vec::with_capacity(n-2);
+        let mut upper = Vec::with_capacity(n-2);
+        let mut rhs = Vec::with_capacity(n-2);
+        for (i, (a, b, c)) in operator.iter().enumerate(){
+            lower.push(-implicit*a);
+            diagonal.push(1.0-implicit*b);
+            upper.push(-implicit*c);
+            rhs.push(values[i+1]+explicit*(a*values[i]+b*values[i+1]+c*values[i+2]));
+        }
+        let m = n-3;
+        // The weights of the linear extrapolation to the boundaries.
+        let lower_weight = (spots[1]-spots[0])/(spots[2]-spots[1]);
+        let upper_weight = (spots[n-1]-spots[n-2])/(spots[n-2]-spots[n-3]);
+        match &self.lower_boundary {
+            BoundaryCondition::Dirichlet(f) => rhs[0] -= lower[0]*f(tau),
+            BoundaryCondition::Linear => {
+                // V_0 = (1+w) V_1 - w V_2
+                diagonal[0] += (1.0+lower_weight)*lower[0];
+                upper[0] -= lower_weight*lower[0];
+            },
+        }
+        match &self.upper_boundary {
+            BoundaryCondition::Dirichlet(f) => rhs[m] -= upper[m]*f(tau),
+            BoundaryCondition::Linear => {
+                // V_n = (1+w) V_{n-1} - w V_{n-2}
+                diagonal[m] += (1.0+upper_weight)*upper[m];
+                lower[m] -= upper_weight*upper[m];
+            },
+        }
+        let interior = if american {
+            projected_sor(&lower, &diagonal, &upper, &rhs, &exercise_values[1..n-1], &values[1..n-1])?
+        } else {
+            solve_tridiagonal(&lower, &diagonal, &upper, &rhs)
+        };
+        values[1..n-1].copy_from_slice(&interior);
+        values[0] = match &self.lower_boundary {
+            BoundaryCondition::Dirichlet(f) => f(tau),
+            BoundaryCondition::Linear => (1.0+lower_weight)*values[1]-lower_weight*values[2],
+        };
+        values[n-1] = match &self.upper_boundary {
+            BoundaryCondition::Dirichlet(f) => f(tau),
+            BoundaryCondition::Linear => (1.0+upper_weight)*values[n-2]-upper_weight*values[n-3],
+        };
+        if american {
+            values[0] = values[0].max(exercise_values[0]);
+            values[n-1] = values[n-1].max(exercise_values[n-1]);
+        }
+        Ok(())
+    }
+
+    /// Checks the inputs of the `try_` pricing methods.
+    fn check_inputs(&self, spot: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)->Result<(), PricingError>{
+        if [spot, time_to_expiry, volatility, divident_rate].iter().any(|x| *x < 0.0 || x.is_nan()){
+            return Err(PricingError::NegativeInput);
+        }
+        if spot < self.grid.minimal_spot || spot > self.grid.maximal_spot{
+            return Err(PricingError::InvalidMarketData);
+        }
+        Ok(())
+    }
+}
+
+/// Solves a tridiagonal linear system with the Thomas algorithm.
+/// `lower[0]` and `upper[upper.len()-1]` are ignored.
+pub(crate) fn solve_tridiagonal(lower: &[f64], diagonal: &[f64], upper: &[f64], rhs: &[f64])->Vec<f64>{
+    let n = diagonal.len();
+    let mut modified_upper = vec![0.0; n];
+    let mut modified_rhs = vec![0.0; n];
+    modified_upper[0] = upper[0]/diagonal[0];
+    modified_rhs[0] = rhs[0]/diagonal[0];
+    for i in 1..n{
+        let denominator = diagonal[i]-lower[i]*modified_upper[i-1];
+        modified_upper[i] = upper[i]/denominator;
+        modified_rhs[i] = (rhs[i]-lower[i]*modified_rhs[i-1])/denominator;
+    }
+    let mut solution = modified_rhs;
+    for i in (0..n-1).rev(){
+        solution[i] -= modified_upper[i]*solution[i+1];
+    }
+    solution
+}
+
+/// Solves the linear complementarity problem `A x >= rhs`, `x >= obstacle`, with equality in one of them for each row,
+/// where `A` is tridiagonal, with projected successive over-relaxation starting from `initial_guess`.
+/// `lower[0]` and `upper[upper.len()-1]` are ignored. Returns `PricingError::NoConvergence` if the updates are still above the
+/// tolerance after the maximal number of iterations.
+fn projected_sor(lower: &[f64], diagonal: &[f64], upper: &[f64], rhs: &[f64], obstacle: &[f64], initial_guess: &[f64])
+    ->Result<Vec<f64>, PricingError>{
+    const RELAXATION: f64 = 1.2;
+    const TOLERANCE: f64 = 1e-10;
+    const MAXIMAL_ITERATIONS: usize = 10000;
+    let n = diagonal.len();
+    let mut x: Vec<f64> = initial_guess.iter().zip(obstacle).map(|(v, g)| v.max(*g)).collect();
+    for _ in 0..MAXIMAL_ITERATIONS{
+        let mut error = 0.0;
+        for i in 0..n{
+            let mut residual = rhs[i];
+            if i > 0 {
+                residual -= lower[i]*x[i-1];
+            }
+            if i < n-1 {
+                residual -= upper[i]*x[i+1];
+            }
+            let updated = (x[i]+RELAXATION*(residual/diagonal[i]-x[i])).max(obstacle[i]);
+            error += (updated-x[i])*(updated-x[i]);
+            x[i] = updated;
+        }
+        if error < TOLERANCE*TOLERANCE{
+            return Ok(x);
+        }
+    }
+    Err(PricingError::NoConvergence)
+}
+
+/// Returns the first (or, if `second` is true, the second) derivative of `values` given at the sorted `points` at `x`, from
+/// the three point differences on the possibly non-uniform grid at the interior points, as in the spatial operator, linearly
+/// interpolated and constant beyond the outermost interior points.
+fn spot_derivative(points: &[f64], values: &[f64], x: f64, second: bool)->f64{
+    let n = points.len();
+    let derivatives: Vec<f64> = (1..n-1).map(|i| {
+        let (below, above) = (points[i]-points[i-1], points[i+1]-points[i]);
+        if second {
+            2.0*(below*values[i+1]-(below+above)*values[i]+above*values[i-1])/(below*above*(below+above))
+        }
+        else {
+            (below*below*values[i+1]+(above*above-below*below)*values[i]-above*above*values[i-1])/(below*above*(below+above))
+        }
+    }).collect();
+    if x < points[0] || x > points[n-1]{
+        panic!("The spot is outside the grid.");
+    }
+    interpolate(&points[1..n-1], &derivatives, x.clamp(points[1], points[n-2]))
+}
+
+/// Linearly interpolates `values` given at the sorted `points` at `x`.
+/// # Panics
+/// Panics if `x` is outside `points`.
+fn interpolate(points: &[f64], values: &[f64], x: f64)->f64{
+    if x < points[0] || x > points[points.len()-1]{
+        panic!("The spot is outside the grid.");
+    }
+    let i = points.partition_point(|p| *p <= x).clamp(1, points.len()-1);
+    let weight = (x-points[i-1])/(points[i]-points[i-1]);
+    (1.0-weight)*values[i-1]+weight*values[i]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw_formulas;
+
+    #[test]
+    fn european_call_test(){
+        let pricer = CrankNicolsonPricer::new(PdeGrid::new(0.0, 400.0, 400, 200), BoundaryCondition::Linear, BoundaryCondition::Linear);
+        let solution = pricer.price(&|s| f64::max(s-100.0, 0.0), 100.0, 0.05, 1.0, 0.2, 0.02);
+        let exact = raw_formulas::european_call_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.02);
+        assert!((solution.get_price()-exact).abs()<0.01);
+        let exact = raw_formulas::european_call_option_price(120.0, 100.0, 0.05, 1.0, 0.2, 0.02);
+        assert!((solution.value_at(120.0)-exact).abs()<0.01);
+    }
+
+    #[test]
+    fn european_put_dirichlet_test(){
+        let lower = BoundaryCondition::Dirichlet(Box::new(|tau| 100.0*(-0.05*tau).exp()));
+        let upper = BoundaryCondition::Dirichlet(Box::new(|_| 0.0));
+        let pricer = CrankNicolsonPricer::new(PdeGrid::new(0.0, 400.0, 400, 200), lower, upper);
+        let solution = pricer.price(&|s| f64::max(100.0-s, 0.0), 90.0, 0.05, 0.5, 0.3, 0.0);
+        let exact = raw_formulas::european_put_option_price(90.0, 100.0, 0.05, 0.5, 0.3, 0.0);
+        assert!((solution.get_price()-exact).abs()<0.01);
+        assert_eq!(solution.get_spots().len(), solution.get_values().len());
+        assert_eq!(pricer.try_price(&|s| s, 500.0, 0.05, 0.5, 0.3, 0.0), Err(PricingError::InvalidMarketData));
+    }
+
+    #[test]
+    fn american_put_test(){
+        let pricer = CrankNicolsonPricer::new(PdeGrid::new(0.0, 200.0, 1000, 500), BoundaryCondition::Linear, BoundaryCondition::Linear);
+        // Reference values from Longstaff and Schwartz (2001), computed with a fine finite difference grid.
+        let solution = pricer.price_american(&|s| f64::max(40.0-s, 0.0), 36.0, 0.06, 1.0, 0.2, 0.0);
+        assert!((solution.get_price()-4.478).abs()<0.01);
+        assert!((solution.value_at(40.0)-2.314).abs()<0.01);
+        assert!(solution.get_values().iter().zip(solution.get_spots()).all(|(v, s)| *v >= f64::max(40.0-s, 0.0)));
+        assert!((solution.delta_at(20.0)+1.0).abs()<1e-6);
+        assert!(solution.gamma_at(40.0) > 0.0);
+    }
+
+    #[test]
+    fn surface_test(){
+        let pricer = CrankNicolsonPricer::new(PdeGrid::new(0.0, 400.0, 800, 400), BoundaryCondition::Linear, BoundaryCondition::Linear);
+        assert_eq!(pricer.price(&|s| f64::max(s-100.0, 0.0), 100.0, 0.05, 1.0, 0.2, 0.01).get_surface(), None);
+        let pricer = pricer.with_surface_output(true);
+        let solution = pricer.price(&|s| f64::max(s-100.0, 0.0), 100.0, 0.05, 1.0, 0.2, 0.01);
+        let surface = solution.get_surface().unwrap();
+        assert_eq!(surface.get_times().len(), 401);
+        assert_eq!(surface.get_values()[0], solution.get_values());
+        assert_eq!(surface.get_values()[400][300], 50.0);
+        for (spot, time) in [(90.0, 0.0), (110.0, 0.5), (100.0, 0.7512)] {
+            let tau = 1.0-time;
+            assert!((surface.value_at(spot, time)-raw_formulas::european_call_option_price(spot, 100.0, 0.05, tau, 0.2, 0.01)).abs() < 0.01);
+            assert!((surface.delta_at(spot, time)-raw_formulas::call_delta(spot, 100.0, 0.05, tau, 0.2, 0.01)).abs() < 1e-3);
+            assert!((surface.gamma_at(spot, time)-raw_formulas::call_gamma(spot, 100.0, 0.05, tau, 0.2, 0.01)).abs() < 1e-3);
+            assert!((surface.theta_at(spot, time)-raw_formulas::call_theta(spot, 100.0, 0.05, tau, 0.2, 0.01)).abs() < 0.02);
+        }
+        assert_eq!(surface.delta_at(100.0, 0.0), solution.delta_at(100.0));
+        let grid = surface.to_grid();
+        assert_eq!(grid.value(400, 300), 50.0);
+        assert_eq!(grid.get_x(), surface.get_times());
+    }
+
+    #[test]
+    fn digital_call_gamma_test(){
+        let digital = |s: f64| if s > 100.0 {1.0} else {0.0};
+        let exact_gamma = |s: f64| {
+            let (price, h) = (|x| raw_formulas::digital_call_price(x, 100.0, 0.05, 0.25, 0.2, 0.0), 1e-3);
+            (price(s+h)-2.0*price(s)+price(s-h))/(h*h)
+        };
+        let gamma_error = |pricer: &CrankNicolsonPricer| {
+            let solution = pricer.price(&digital, 100.0, 0.05, 0.25, 0.2, 0.0);
+            [96.0, 98.0, 99.5, 100.0, 100.5, 102.0, 104.0].iter().map(|s| (solution.gamma_at(*s)-exact_gamma(*s)).abs()).fold(0.0, f64::max)
+        };
+        let uniform = PdeGrid::new(0.0, 300.0, 300, 50);
+        let concentrated = uniform.with_concentration(100.0, 10.0);
+        let spots = concentrated.get_spots();
+        assert!(spots.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!((spots[0], spots[300]), (0.0, 300.0));
+        let pricer = |grid, rannacher_steps| CrankNicolsonPricer::new(grid, BoundaryCondition::Linear, BoundaryCondition::Linear)
+            .with_rannacher_steps(rannacher_steps);
+        assert!(gamma_error(&pricer(uniform, 0)) > 1e-4);
+        // Crank-Nicolson alone rings on the fine steps around the discontinuity, the implicit start-up damps it.
+        assert!(gamma_error(&pricer(concentrated, 0)) > 5e-3);
+        assert!(gamma_error(&pricer(concentrated, 2)) < 1e-5);
+        let price = pricer(concentrated, 2).price(&digital, 100.0, 0.05, 0.25, 0.2, 0.0).get_price();
+        assert!((price-raw_formulas::digital_call_price(100.0, 100.0, 0.05, 0.25, 0.2, 0.0)).abs() < 1e-4);
+        // The concentrated grid also prices vanilla and american options.
+        let call = pricer(PdeGrid::new(0.0, 400.0, 200, 100).with_concentration(100.0, 20.0), 2)
+            .price(&|s| f64::max(s-100.0, 0.0), 100.0, 0.05, 1.0, 0.2, 0.02);
+        assert!((call.get_price()-raw_formulas::european_call_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.02)).abs() < 1e-3);
+        let put = pricer(PdeGrid::new(0.0, 200.0, 300, 200).with_concentration(40.0, 5.0), 2)
+            .price_american(&|s| f64::max(40.0-s, 0.0), 36.0, 0.06, 1.0, 0.2, 0.0);
+        assert!((put.get_price()-4.478).abs() < 0.01);
+    }
+
+    #[test]
+    fn projected_sor_test(){
+        let solution = projected_sor(&[0.0, -1.0], &[4.0, 4.0], &[-1.0, 0.0], &[3.0, 3.0], &[0.0, 2.0], &[0.0, 0.0]).unwrap();
+        assert!((solution[0]-1.25).abs()<1e-10 && solution[1] == 2.0);
+        // The iteration diverges on a system which is far from diagonally dominant.
+        let obstacle = [f64::NEG_INFINITY; 3];
+        assert_eq!(projected_sor(&[0.0, -3.0, -3.0], &[1.0; 3], &[-3.0, -3.0, 0.0], &[1.0; 3], &obstacle, &[0.0; 3]),
+            Err(PricingError::NoConvergence));
+    }
+
+    #[test]
+    fn non_uniform_spot_derivative_test(){
+        // The three point differences are exact for quadratics on any grid.
+        let points = [1.0, 1.5, 3.0, 3.2, 5.0];
+        let values: Vec<f64> = points.iter().map(|x| x*x).collect();
+        for x in [1.5, 3.0, 3.2]{
+            assert!((spot_derivative(&points, &values, x, false)-2.0*x).abs()<1e-12);
+            assert!((spot_derivative(&points, &values, x, true)-2.0).abs()<1e-12);
+        }
+    }
+
+    #[test]
+    fn exercise_boundary_test(){
+        let pricer = CrankNicolsonPricer::new(PdeGrid::new(0.0, 200.0, 1000, 500), BoundaryCondition::Linear, BoundaryCondition::Linear);
+        let put = pricer.price_american(&|s| f64::max(40.0-s, 0.0), 36.0, 0.06, 1.0, 0.2, 0.0);
+        let boundary = put.get_exercise_boundary();
+        assert_eq!(boundary.len(), 500);
+        assert_eq!(boundary[0].0, 0.0);
+        // The critical spot of the put rises towards the strike as expiry approaches.
+        assert!(boundary.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 <= w[1].1));
+        // Now it is above the critical spot 2rK/(2r+sigma^2) = 30 of the perpetual put.
+        assert!(boundary[0].1 > 30.0 && boundary[0].1 < 34.0);
+        assert!(boundary[499].1 < 40.0 && boundary[499].1 > 38.5);
+        // Above the critical spot the put is held, at and below it it is exercised.
+        let (critical, step) = (boundary[0].1, 0.2);
+        assert!((put.value_at(critical)-(40.0-critical)).abs() < 1e-12);
+        assert!(put.value_at(critical+step) > 40.0-critical-step+1e-6);
+        let european = pricer.price(&|s| f64::max(40.0-s, 0.0), 36.0, 0.06, 1.0, 0.2, 0.0);
+        assert!(european.get_exercise_boundary().is_empty());
+        // The critical spot of a call on a stock paying dividends falls towards max(K, rK/q) as expiry approaches.
+        let call = pricer.price_american(&|s| f64::max(s-100.0, 0.0), 100.0, 0.03, 1.0, 0.2, 0.06);
+        let boundary = call.get_exercise_boundary();
+        assert!(boundary.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert!(boundary[boundary.len()-1].1 > 100.0 && boundary[boundary.len()-1].1 < 103.0);
+        assert!(boundary[0].1 > 120.0);
+    }
+
+    #[test]
+    fn american_call_without_dividends_test(){
+        let pricer = CrankNicolsonPricer::new(PdeGrid::new(0.0, 400.0, 400, 200), BoundaryCondition::Linear, BoundaryCondition::Linear);
+        let american = pricer.price_american(&|s| f64::max(s-100.0, 0.0), 100.0, 0.05, 1.0, 0.2, 0.0);
+        let european = pricer.price(&|s| f64::max(s-100.0, 0.0), 100.0, 0.05, 1.0, 0.2, 0.0);
+        assert!((american.get_price()-european.get_price()).abs()<1e-6);
+        let with_dividends = pricer.price_american(&|s| f64::max(s-100.0, 0.0), 100.0, 0.05, 1.0, 0.2, 0.08);
+        assert!(with_dividends.get_price() > raw_formulas::european_call_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.08));
+        let delta = with_dividends.delta_at(100.0);
+        assert!(delta > 0.0 && delta < 1.0);
+    }
+}
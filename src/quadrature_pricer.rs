@@ -0,0 +1,92 @@
+//! Provides a pricer for european options with arbitrary terminal payoffs, which integrates the payoff against the lognormal
+//! density of the stock at expiry with Gauss-Hermite quadrature.
+//!
+//! The prices are deterministic, so unlike Monte Carlo prices they can be differentiated by bumping without noise.
+//! Smooth payoffs converge very fast in the number of nodes, while payoffs with kinks (such as calls and puts) need more nodes.
+
+use crate::numerics::integration::gauss_hermite_nodes;
+use std::f64::consts::PI;
+
+/// A Gauss-Hermite quadrature pricer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GaussHermitePricer{
+    /// The nodes of the quadrature rule for the weight `exp(-x^2)`.
+    nodes: Vec<f64>,
+    /// The weights of the quadrature rule for the weight `exp(-x^2)`.
+    weights: Vec<f64>,
+}
+
+impl GaussHermitePricer {
+    /// Returns a new pricer with `number_of_nodes` nodes.
+    /// # Panics
+    /// Panics if `number_of_nodes` is zero.
+    pub fn new(number_of_nodes: usize)->GaussHermitePricer{
+        if number_of_nodes == 0 {
+            panic!("The number of nodes must be positive.");
+        }
+        let (nodes, weights) = gauss_hermite_nodes(number_of_nodes);
+        GaussHermitePricer{
+            nodes,
+            weights,
+        }
+    }
+
+    /// Returns the number of nodes of the quadrature rule.
+    pub fn get_number_of_nodes(&self)->usize{
+        self.nodes.len()
+    }
+
+    /// Returns `E[f(Z)]` for a standard Gaussian `Z`. Any model in which the quantity of interest is a function of a single
+    /// Gaussian can be priced with this.
+    pub fn expectation(&self, f: &dyn Fn(f64)->f64)->f64{
+        self.nodes.iter().zip(&self.weights).map(|(x, w)| w*f(std::f64::consts::SQRT_2*x)).sum::<f64>()/PI.sqrt()
+    }
+
+    /// Returns the price of a european option with the given payoff.
+    /// # Parameters
+    /// - `payoff` - the payoff of the option as a function of the spot at expiry.
+    /// - `spot` - the current spot of the stock.
+    /// - `short_rate_of_interest` - the short rate of interest.
+    /// - `time_to_expiry` - the time to expiry of the option.
+    /// - `volatility` - the volatility of the stock.
+    /// - `divident_rate` - the continuous divident rate of the stock.
+    /// # Panics
+    /// Panics if one of the parameters is negative.
+    pub fn price(&self, payoff: &dyn Fn(f64)->f64, spot: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+        divident_rate: f64)->f64{
+        if spot < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+            panic!("One of the parameters is negative")
+        }
+        let drift = (short_rate_of_interest-divident_rate-0.5*volatility*volatility)*time_to_expiry;
+        let standard_deviation = volatility*time_to_expiry.sqrt();
+        (-short_rate_of_interest*time_to_expiry).exp()*self.expectation(&|z| payoff(spot*(drift+standard_deviation*z).exp()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw_formulas;
+
+    #[test]
+    fn nodes_test(){
+        let pricer = GaussHermitePricer::new(5);
+        // The rule with n nodes integrates polynomials of degree up to 2n-1 exactly: E[Z^4] = 3, E[Z^6] = 15.
+        assert!((pricer.expectation(&|_| 1.0)-1.0).abs()<1e-13);
+        assert!((pricer.expectation(&|z| z.powi(4))-3.0).abs()<1e-12);
+        assert!((pricer.expectation(&|z| z.powi(3))).abs()<1e-12);
+        assert!(pricer.nodes.windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    fn european_prices_test(){
+        let pricer = GaussHermitePricer::new(100);
+        let call = pricer.price(&|s| f64::max(s-100.0, 0.0), 100.0, 0.05, 1.0, 0.2, 0.02);
+        assert!((call-raw_formulas::european_call_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.02)).abs()<0.05);
+        let forward = pricer.price(&|s| s, 100.0, 0.05, 1.0, 0.2, 0.02);
+        assert!((forward-100.0*(-0.02_f64).exp()).abs()<1e-10);
+        // A smooth payoff is integrated to machine precision.
+        let power = pricer.price(&|s| s*s, 100.0, 0.05, 1.0, 0.2, 0.02);
+        assert!((power/1e4-(0.06_f64+0.04-0.05).exp()).abs()<1e-12);
+    }
+}
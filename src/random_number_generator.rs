@@ -1,10 +1,12 @@
 //! Implements a random number generator for use in the Monte Carlo simulations.
 //! A different random number generator can be implemented using the `RandomNumberGeneratorTrait` if requiered.
 
-use rand::{Rng,SeedableRng};
-use rand::rngs::StdRng;
+use rand::{Rng, RngCore};
+use rand_distr::StandardNormal;
 
-use crate::utils::inverse_cumulative_normal_function;
+use crate::error::PricingError;
+use crate::utils::{cumulative_normal_function, inverse_cumulative_normal_function_with_algorithm, CorrelationMatrix,
+    InverseCumulativeNormalAlgorithm};
 
 ///Provides an interface for random number generators.
 pub trait RandomNumberGeneratorTrait {
@@ -14,24 +16,371 @@ pub trait RandomNumberGeneratorTrait {
     ///Returns a vector of samples from the standard Gaussian distribution N(0,1) of size `n`.
     /// Generating several random samples sequentially should yield the same result as generating them all at once.
     fn get_gaussians(&mut self, n: usize) -> Vec<f64>;
+    /// Advances the generator by `n_draws` uniform samples, as if `get_uniforms(n_draws)` was called.
+    /// The default implementation generates and discards the samples.
+    fn skip(&mut self, n_draws: usize){
+        self.get_uniforms(n_draws);
+    }
+    /// Returns `n_steps` vectors of standard Gaussian samples, each with the correlations of `correlation`, by applying its
+    /// Cholesky factor to independent samples from `get_gaussians`.
+    fn get_correlated_gaussians(&mut self, correlation: &CorrelationMatrix, n_steps: usize) -> Vec<Vec<f64>>{
+        let dimension = correlation.get_dimension();
+        self.get_gaussians(dimension*n_steps).chunks(dimension).map(|z| correlation.correlate(z)).collect()
+    }
+    /// Returns a vector of samples from the exponential distribution with the given rate (i.e. mean `1/rate`) of size `n`,
+    /// by inverting the cumulative distribution function of uniform samples.
+    /// # Panics
+    /// Panics if `rate` is not positive.
+    fn get_exponentials(&mut self, rate: f64, n: usize) -> Vec<f64>{
+        if rate <= 0.0 || rate.is_nan() {
+            panic!("The rate must be positive.");
+        }
+        self.get_uniforms(n).into_iter().map(|u| -(1.0-u).ln()/rate).collect()
+    }
+    /// Returns a vector of samples from the Poisson distribution with mean `lambda` of size `n`, by inverting the cumulative
+    /// distribution function of uniform samples. Means above 500 are split into several Poisson samples, each using its own uniform.
+    /// # Panics
+    /// Panics if `lambda` is negative.
+    fn get_poissons(&mut self, lambda: f64, n: usize) -> Vec<u64>{
+        const MAXIMAL_MEAN_PER_UNIFORM: f64 = 500.0;
+        if lambda < 0.0 || lambda.is_nan() {
+            panic!("One of the parameters is negative")
+        }
+        let pieces = (lambda/MAXIMAL_MEAN_PER_UNIFORM).ceil().max(1.0) as usize;
+        let piece_mean = lambda/pieces as f64;
+        let uniforms = self.get_uniforms(n*pieces);
+        uniforms.chunks(pieces).map(|chunk| chunk.iter().map(|u| {
+            let mut k = 0;
+            let mut probability = (-piece_mean).exp();
+            let mut cumulative = probability;
+            while *u >= cumulative && probability > 0.0 {
+                k += 1;
+                probability *= piece_mean/k as f64;
+                cumulative += probability;
+            }
+            k
+        }).sum()).collect()
+    }
+    /// Returns a vector of samples from the gamma distribution with the given shape and scale 1 of size `n`, with the method of
+    /// Marsaglia and Tsang, which uses a Gaussian and a uniform sample per attempt (and one more uniform if `shape<1`).
+    /// # Panics
+    /// Panics if `shape` is negative.
+    fn get_gammas(&mut self, shape: f64, n: usize) -> Vec<f64>{
+        if shape < 0.0 || shape.is_nan() {
+            panic!("One of the parameters is negative")
+        }
+        if shape == 0.0 {
+            return vec![0.0; n];
+        }
+        // Samples with shape below 1 are those with shape+1, scaled by U^(1/shape).
+        let boosted_shape = if shape < 1.0 { shape+1.0 } else { shape };
+        let d = boosted_shape-1.0/3.0;
+        let c = 1.0/(9.0*d).sqrt();
+        (0..n).map(|_| {
+            let sample = loop {
+                let z = self.get_gaussians(1)[0];
+                let v = (1.0+c*z).powi(3);
+                if v <= 0.0 {
+                    continue;
+                }
+                let u = self.get_uniforms(1)[0];
+                if u.ln() < 0.5*z*z+d-d*v+d*v.ln() {
+                    break d*v;
+                }
+            };
+            if shape < 1.0 {
+                sample*self.get_uniforms(1)[0].powf(1.0/shape)
+            }
+            else {
+                sample
+            }
+        }).collect()
+    }
+    /// Returns a vector of samples from the noncentral chi-squared distribution with the given (not necessarily integer)
+    /// degrees of freedom and noncentrality of size `n`, as central chi-squared samples with a Poisson number of extra pairs
+    /// of degrees of freedom. This is exact, and is e.g. the transition of a Cox-Ingersoll-Ross process.
+    /// # Panics
+    /// Panics if `degrees_of_freedom` or `noncentrality` is negative or NaN.
+    fn get_noncentral_chi_squareds(&mut self, degrees_of_freedom: f64, noncentrality: f64, n: usize) -> Vec<f64>{
+        if degrees_of_freedom < 0.0 || degrees_of_freedom.is_nan() || noncentrality < 0.0 || noncentrality.is_nan() {
+            panic!("One of the parameters is negative")
+        }
+        (0..n).map(|_| {
+            let extra_pairs = self.get_poissons(0.5*noncentrality, 1)[0];
+            2.0*self.get_gammas(0.5*degrees_of_freedom+extra_pairs as f64, 1)[0]
+        }).collect()
+    }
+}
+
+///Provides independent substreams of a random number generator, for simulations split into blocks or threads.
+pub trait SubstreamGenerator: RandomNumberGeneratorTrait+Sized {
+    /// Returns a generator with the same seed whose samples are independent of those of `self` and of the other substreams.
+    /// Parallel simulations can use substream `k` in thread `k`, so that results do not depend on scheduling.
+    fn substream(&self, index: u64) -> Self;
+}
+
+
+/// The method used to turn uniform samples into Gaussian samples.
+///
+/// In the `gaussian_transforms` benchmark (`cargo bench --bench gaussian_transforms`), the Ziggurat method is the fastest, about
+/// twice as fast as the inverse cumulative normal function, and Box-Muller, which takes a logarithm, a square root and a sine and
+/// a cosine per pair, is the slowest. The inverse cumulative normal function is the default, since it maps each uniform to one
+/// Gaussian, as quasi Monte Carlo requires, and keeps seeded prices unchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GaussianTransform{
+    /// Applies the inverse cumulative normal function with the given algorithm to each uniform sample.
+    InverseCumulativeNormal(InverseCumulativeNormalAlgorithm),
+    /// The Box-Muller transform, which turns a pair of uniform samples into a pair of Gaussian samples.
+    BoxMuller,
+    /// Marsaglia and Tsang's Ziggurat method, which uses a variable number of uniform samples per Gaussian sample.
+    /// It is implemented by `rand_distr`, so unlike the other transforms its samples may change between versions of that crate.
+    Ziggurat,
+}
+
+impl Default for GaussianTransform {
+    fn default() -> Self {
+        GaussianTransform::InverseCumulativeNormal(InverseCumulativeNormalAlgorithm::Moro)
+    }
+}
+
+/// The 64 bit permuted congruential generator PCG64 (XSL RR 128/64) of O'Neill.
+///
+/// The generator is implemented in this crate, so that seeded samples (and therefore seeded Monte Carlo prices) are the
+/// same on all platforms and do not change with the versions of the dependencies. Its output matches the reference
+/// implementation `pcg64_srandom_r`/`pcg64_random_r`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pcg64{
+    /// The state of the underlying linear congruential generator.
+    state: u128,
+    /// The (odd) increment of the linear congruential generator, which selects the stream.
+    increment: u128,
+}
+
+impl Pcg64 {
+    /// The multiplier of the linear congruential generator.
+    const MULTIPLIER: u128 = 0x2360_ed05_1fc6_5da4_4385_df64_9fcc_f645;
+
+    /// Returns a new generator with the given initial state on stream `stream`, as `pcg64_srandom_r(initial_state, stream)`.
+    pub fn new(initial_state: u128, stream: u128) -> Pcg64{
+        let mut rng = Pcg64{state: 0, increment: (stream<<1)|1};
+        rng.step();
+        rng.state = rng.state.wrapping_add(initial_state);
+        rng.step();
+        rng
+    }
 
+    /// Returns a new generator on stream `stream`, whose 128 bit initial state is expanded from `seed` with SplitMix64.
+    pub fn from_seed(seed: u64, stream: u64) -> Pcg64{
+        let high = splitmix64(seed) as u128;
+        let low = splitmix64(seed.wrapping_add(0x9e37_79b9_7f4a_7c15)) as u128;
+        Pcg64::new((high<<64)|low, stream as u128)
+    }
 
+    /// Returns the stream of the generator.
+    pub fn get_stream(&self) -> u128{
+        self.increment>>1
+    }
+
+    /// Advances the underlying linear congruential generator by one step.
+    fn step(&mut self){
+        self.state = self.state.wrapping_mul(Pcg64::MULTIPLIER).wrapping_add(self.increment);
+    }
+
+    /// Returns the next 64 bit output of the generator.
+    pub fn next_u64(&mut self) -> u64{
+        self.step();
+        let rotation = (self.state>>122) as u32;
+        (((self.state>>64) as u64)^(self.state as u64)).rotate_right(rotation)
+    }
+
+    /// Returns the next uniform sample from [0,1), built from the top 53 bits of the next output.
+    pub fn next_f64(&mut self) -> f64{
+        (self.next_u64()>>11) as f64*(1.0/(1_u64<<53) as f64)
+    }
+
+    /// Advances the generator by `delta` outputs in `O(log(delta))` steps, with Brown's algorithm for jumping ahead
+    /// in a linear congruential generator.
+    pub fn advance(&mut self, mut delta: u128){
+        let mut accumulated_multiplier: u128 = 1;
+        let mut accumulated_increment: u128 = 0;
+        let mut multiplier = Pcg64::MULTIPLIER;
+        let mut increment = self.increment;
+        while delta > 0 {
+            if delta&1 == 1 {
+                accumulated_multiplier = accumulated_multiplier.wrapping_mul(multiplier);
+                accumulated_increment = accumulated_increment.wrapping_mul(multiplier).wrapping_add(increment);
+            }
+            increment = multiplier.wrapping_add(1).wrapping_mul(increment);
+            multiplier = multiplier.wrapping_mul(multiplier);
+            delta >>= 1;
+        }
+        self.state = accumulated_multiplier.wrapping_mul(self.state).wrapping_add(accumulated_increment);
+    }
 }
 
+impl RngCore for Pcg64 {
+    fn next_u32(&mut self) -> u32{
+        (Pcg64::next_u64(self)>>32) as u32
+    }
 
-///Implements a random number generator for use in the Monte Carlo simulations. A wrapper class for `StdRng`.
+    fn next_u64(&mut self) -> u64{
+        Pcg64::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]){
+        for chunk in dest.chunks_mut(8) {
+            let bytes = Pcg64::next_u64(self).to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error>{
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Returns the output of the SplitMix64 generator in state `x`, which is also a good 64 bit hash function.
+pub(crate) fn splitmix64(x: u64) -> u64{
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z^(z>>30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z^(z>>27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z^(z>>31)
+}
+
+///Implements a random number generator for use in the Monte Carlo simulations. A wrapper class for the in-crate `Pcg64`
+///generator, which supports skipping ahead and independent substreams, and whose seeded samples are reproducible across
+///platforms and versions of this crate.
 pub struct RandomNumberGenerator{
-    rng: StdRng,
+    rng: Pcg64,
+    /// The seed of the generator, shared by its substreams.
+    seed: u64,
+    /// The method used to turn uniform samples into Gaussian samples.
+    gaussian_transform: GaussianTransform,
+    /// The second Gaussian sample of the last Box-Muller pair, if it was not used yet.
+    spare_gaussian: Option<f64>,
+}
+
+/// The full state of a `RandomNumberGenerator`, from which it can be restored to produce exactly the same samples.
+/// The state can be saved as text with `to_string` and parsed back with `parse`. The text starts with the name of the
+/// generator algorithm, so that states saved by a different algorithm are rejected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RandomNumberGeneratorState{
+    /// The seed of the generator.
+    seed: u64,
+    /// The stream of the generator.
+    stream: u64,
+    /// The state of the `Pcg64` generator.
+    generator_state: u128,
+    /// The method used to turn uniform samples into Gaussian samples.
+    gaussian_transform: GaussianTransform,
+    /// The unused second Gaussian sample of the last Box-Muller pair.
+    spare_gaussian: Option<f64>,
+}
+
+impl std::fmt::Display for RandomNumberGeneratorState {
+    /// Writes the state as space separated fields. The spare Gaussian sample is written as its bits, so that it is restored exactly.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let transform = match self.gaussian_transform {
+            GaussianTransform::InverseCumulativeNormal(InverseCumulativeNormalAlgorithm::Moro) => "moro",
+            GaussianTransform::InverseCumulativeNormal(InverseCumulativeNormalAlgorithm::Wichura) => "wichura",
+            GaussianTransform::BoxMuller => "box-muller",
+            GaussianTransform::Ziggurat => "ziggurat",
+        };
+        let spare = match self.spare_gaussian {
+            Some(x) => x.to_bits().to_string(),
+            None => "-".to_string(),
+        };
+        write!(f, "pcg64 {} {} {} {} {}", self.seed, self.stream, self.generator_state, transform, spare)
+    }
+}
+
+impl std::str::FromStr for RandomNumberGeneratorState {
+    type Err = PricingError;
+
+    /// Parses a state written by `to_string`, returning `PricingError::InvalidState` if it is malformed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 6 || fields[0] != "pcg64" {
+            return Err(PricingError::InvalidState);
+        }
+        let fields = &fields[1..];
+        let gaussian_transform = match fields[3] {
+            "moro" => GaussianTransform::InverseCumulativeNormal(InverseCumulativeNormalAlgorithm::Moro),
+            "wichura" => GaussianTransform::InverseCumulativeNormal(InverseCumulativeNormalAlgorithm::Wichura),
+            "box-muller" => GaussianTransform::BoxMuller,
+            "ziggurat" => GaussianTransform::Ziggurat,
+            _ => return Err(PricingError::InvalidState),
+        };
+        let spare_gaussian = match fields[4] {
+            "-" => None,
+            bits => Some(f64::from_bits(bits.parse().map_err(|_| PricingError::InvalidState)?)),
+        };
+        Ok(RandomNumberGeneratorState{
+            seed: fields[0].parse().map_err(|_| PricingError::InvalidState)?,
+            stream: fields[1].parse().map_err(|_| PricingError::InvalidState)?,
+            generator_state: fields[2].parse().map_err(|_| PricingError::InvalidState)?,
+            gaussian_transform,
+            spare_gaussian,
+        })
+    }
 }
 
 impl RandomNumberGenerator{
+    /// Returns the current state of the generator.
+    pub fn state(&self) -> RandomNumberGeneratorState{
+        RandomNumberGeneratorState{
+            seed: self.seed,
+            stream: self.rng.get_stream() as u64,
+            generator_state: self.rng.state,
+            gaussian_transform: self.gaussian_transform,
+            spare_gaussian: self.spare_gaussian,
+        }
+    }
+
+    /// Restores the generator to `state`, after which it produces the same samples as the generator `state` was taken from.
+    pub fn restore(&mut self, state: &RandomNumberGeneratorState){
+        self.rng = Pcg64{state: state.generator_state, increment: ((state.stream as u128)<<1)|1};
+        self.seed = state.seed;
+        self.gaussian_transform = state.gaussian_transform;
+        self.spare_gaussian = state.spare_gaussian;
+    }
+
     /// Returns a new random number generator with given seed (or a random seed if `seed`=`None`).
+    /// Gaussian samples are generated with Moro's inverse cumulative normal function.
     pub fn new(seed: Option<u64>) -> RandomNumberGenerator{
-        let rng = match seed {
-            Some(x) => StdRng::seed_from_u64(x),
-            None =>  StdRng::seed_from_u64(rand::thread_rng().gen())
-        };
-        RandomNumberGenerator{rng}
+        RandomNumberGenerator::with_gaussian_transform(seed, GaussianTransform::default())
+    }
+
+    /// Returns a new random number generator with given seed (or a random seed if `seed`=`None`), which generates Gaussian
+    /// samples with the given inverse cumulative normal algorithm.
+    pub fn with_inverse_cumulative_normal(seed: Option<u64>, algorithm: InverseCumulativeNormalAlgorithm) -> RandomNumberGenerator{
+        RandomNumberGenerator::with_gaussian_transform(seed, GaussianTransform::InverseCumulativeNormal(algorithm))
+    }
+
+    /// Returns a new random number generator with given seed (or a random seed if `seed`=`None`), which generates Gaussian
+    /// samples with the given transform.
+    pub fn with_gaussian_transform(seed: Option<u64>, gaussian_transform: GaussianTransform) -> RandomNumberGenerator{
+        let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+        RandomNumberGenerator{rng: Pcg64::from_seed(seed, 0), seed, gaussian_transform, spare_gaussian: None}
+    }
+
+    /// Returns the seed of the generator, which is the randomly drawn seed if the generator was created without one.
+    pub fn get_seed(&self) -> u64{
+        self.seed
+    }
+
+    /// Returns one Gaussian sample with the Box-Muller transform, generating a new pair when no spare sample is left.
+    fn box_muller_gaussian(&mut self) -> f64{
+        if let Some(x) = self.spare_gaussian.take(){
+            return x;
+        }
+        let u1 = 1.0-self.rng.next_f64();
+        let u2 = self.rng.next_f64();
+        let radius = (-2.0*u1.ln()).sqrt();
+        let angle = 2.0*std::f64::consts::PI*u2;
+        self.spare_gaussian = Some(radius*angle.sin());
+        radius*angle.cos()
     }
 }
 
@@ -41,18 +390,133 @@ impl RandomNumberGeneratorTrait for RandomNumberGenerator {
         let mut v = Vec::with_capacity(n);
 
         for _ in 0..n{
-            v.push(self.rng.gen());
+            v.push(self.rng.next_f64());
         }
         v
     }
 
+    /// Advances the generator by `n_draws` uniform samples in constant time. Any spare Box-Muller sample is discarded.
+    fn skip(&mut self, n_draws: usize){
+        // Each uniform sample consumes one output of the generator.
+        self.rng.advance(n_draws as u128);
+        self.spare_gaussian = None;
+    }
+
     /// Returns a vector of standard Gaussian samples of size `n`.
     fn get_gaussians(&mut self, n: usize) -> Vec<f64>{  
-        let v = self.get_uniforms(n);
-        v.into_iter().map(inverse_cumulative_normal_function).collect()
+        match self.gaussian_transform {
+            GaussianTransform::InverseCumulativeNormal(algorithm) => {
+                let v = self.get_uniforms(n);
+                v.into_iter().map(|x| inverse_cumulative_normal_function_with_algorithm(x, algorithm)).collect()
+            },
+            GaussianTransform::BoxMuller => (0..n).map(|_| self.box_muller_gaussian()).collect(),
+            GaussianTransform::Ziggurat => (0..n).map(|_| self.rng.sample(StandardNormal)).collect(),
+        }
+    }
+}
+
+impl SubstreamGenerator for RandomNumberGenerator {
+    /// Returns a generator with the same seed and Gaussian transform, on PCG stream `index`.
+    /// Substream 0 is the stream of the generator returned by `new`.
+    fn substream(&self, index: u64) -> Self{
+        RandomNumberGenerator{rng: Pcg64::from_seed(self.seed, index), seed: self.seed, gaussian_transform: self.gaussian_transform, spare_gaussian: None}
+    }
+}
+
+
+/// A decorator which matches the first two moments of the Gaussian samples of another generator.
+///
+/// The generator draws the Gaussians of a whole batch of paths at once, rescales every coordinate of the paths to have
+/// exactly zero sample mean and unit sample variance across the batch, and then serves the rescaled samples in order.
+/// This removes the small-sample bias of the first two moments (e.g. the forward of a stock is priced exactly), at the
+/// cost of making the paths of a batch slightly dependent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MomentMatchingGenerator<R: RandomNumberGeneratorTrait>{
+    /// The generator of the raw Gaussian samples.
+    generator: R,
+    /// The number of Gaussian samples of a path.
+    dimension: usize,
+    /// The number of paths in a batch.
+    batch_size: usize,
+    /// The moment matched samples of the current batch.
+    batch: Vec<f64>,
+    /// The position of the next sample in `batch`.
+    position: usize,
+}
+
+impl<R: RandomNumberGeneratorTrait> MomentMatchingGenerator<R> {
+    /// Returns a new moment matching generator which draws its samples from `generator`, in batches of `batch_size` paths
+    /// with `dimension` Gaussian samples each. For Monte Carlo pricing, `dimension` is the dimensionality of the option
+    /// and `batch_size` is the number of paths (or a divisor of it).
+    /// # Panics
+    /// Panics if `dimension` is zero or if `batch_size` is smaller than 2.
+    pub fn new(generator: R, dimension: usize, batch_size: usize) -> MomentMatchingGenerator<R>{
+        if dimension == 0 || batch_size < 2 {
+            panic!("The dimension must be positive and the batch size at least 2.");
+        }
+        MomentMatchingGenerator{
+            generator,
+            dimension,
+            batch_size,
+            batch: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Returns the number of Gaussian samples of a path.
+    pub fn get_dimension(&self) -> usize{
+        self.dimension
+    }
+
+    /// Returns the number of paths in a batch.
+    pub fn get_batch_size(&self) -> usize{
+        self.batch_size
+    }
+
+    /// Draws a new batch of samples and matches the mean and variance of each of its coordinates.
+    fn generate_batch(&mut self){
+        let mut batch = self.generator.get_gaussians(self.dimension*self.batch_size);
+        let n = self.batch_size as f64;
+        for coordinate in 0..self.dimension{
+            let mean = batch.iter().skip(coordinate).step_by(self.dimension).sum::<f64>()/n;
+            let variance = batch.iter().skip(coordinate).step_by(self.dimension).map(|z| (z-mean)*(z-mean)).sum::<f64>()/n;
+            let standard_deviation = variance.sqrt();
+            batch.iter_mut().skip(coordinate).step_by(self.dimension).for_each(|z| *z = (*z-mean)/standard_deviation);
+        }
+        self.batch = batch;
+        self.position = 0;
     }
 }
 
+impl<R: RandomNumberGeneratorTrait> RandomNumberGeneratorTrait for MomentMatchingGenerator<R> {
+    /// Returns a vector of uniform samples of size `n`, obtained by applying the cumulative normal function to moment matched
+    /// Gaussian samples, so that uniform and Gaussian samples are drawn from the same batches.
+    fn get_uniforms(&mut self, n: usize) -> Vec<f64>{
+        self.get_gaussians(n).into_iter().map(cumulative_normal_function).collect()
+    }
+
+    /// Returns a vector of moment matched standard Gaussian samples of size `n`, drawing new batches when needed.
+    fn get_gaussians(&mut self, n: usize) -> Vec<f64>{
+        let mut v = Vec::with_capacity(n);
+        while v.len() < n {
+            if self.position == self.batch.len() {
+                self.generate_batch();
+            }
+            let take = (n-v.len()).min(self.batch.len()-self.position);
+            v.extend_from_slice(&self.batch[self.position..self.position+take]);
+            self.position += take;
+        }
+        v
+    }
+}
+
+impl<R: SubstreamGenerator> SubstreamGenerator for MomentMatchingGenerator<R> {
+    /// Returns a moment matching generator with the same dimension and batch size, which draws its samples from substream
+    /// `index` of the underlying generator.
+    fn substream(&self, index: u64) -> Self{
+        MomentMatchingGenerator::new(self.generator.substream(index), self.dimension, self.batch_size)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -91,4 +555,163 @@ mod tests {
         let v2 = rg2.get_uniforms(12);
         assert_eq!(v1, v2);
     }
+
+    #[test]
+    fn inverse_cumulative_normal_algorithm_test(){
+        let mut moro = RandomNumberGenerator::new(Some(3));
+        let mut wichura = RandomNumberGenerator::with_inverse_cumulative_normal(Some(3), InverseCumulativeNormalAlgorithm::Wichura);
+        for (x, y) in moro.get_gaussians(100).iter().zip(wichura.get_gaussians(100)){
+            assert!((x-y).abs()<1e-8);
+        }
+    }
+
+    #[test]
+    fn gaussian_transform_test(){
+        for transform in [GaussianTransform::BoxMuller, GaussianTransform::Ziggurat]{
+            let mut rg = RandomNumberGenerator::with_gaussian_transform(Some(11), transform);
+            let mut rg2 = RandomNumberGenerator::with_gaussian_transform(Some(11), transform);
+            let mut v1 = rg.get_gaussians(5);
+            v1.append(&mut rg.get_gaussians(4));
+            assert_eq!(v1, rg2.get_gaussians(9));
+            let samples = rg.get_gaussians(200000);
+            let mean = samples.iter().sum::<f64>()/samples.len() as f64;
+            let variance = samples.iter().map(|x| x*x).sum::<f64>()/samples.len() as f64-mean*mean;
+            assert!(mean.abs()<0.01);
+            assert!((variance-1.0).abs()<0.01);
+        }
+    }
+
+    #[test]
+    fn get_exponentials_test(){
+        let mut rg = RandomNumberGenerator::new(Some(13));
+        let samples = rg.get_exponentials(4.0, 200000);
+        let mean = samples.iter().sum::<f64>()/samples.len() as f64;
+        assert!(samples.iter().all(|x| *x >= 0.0));
+        assert!((mean-0.25).abs()<0.002);
+    }
+
+    #[test]
+    fn get_poissons_test(){
+        let mut rg = RandomNumberGenerator::new(Some(17));
+        for lambda in [0.0, 0.3, 7.0, 1200.0]{
+            let samples = rg.get_poissons(lambda, 50000);
+            let mean = samples.iter().sum::<u64>() as f64/samples.len() as f64;
+            let variance = samples.iter().map(|k| (*k as f64-mean).powi(2)).sum::<f64>()/samples.len() as f64;
+            assert!((mean-lambda).abs()<=0.02*lambda.max(1.0));
+            assert!((variance-lambda).abs()<=0.05*lambda.max(1.0));
+        }
+    }
+
+    #[test]
+    fn get_gammas_test(){
+        let mut rg = RandomNumberGenerator::new(Some(29));
+        for shape in [0.4, 1.0, 6.5]{
+            let samples = rg.get_gammas(shape, 100000);
+            let mean = samples.iter().sum::<f64>()/samples.len() as f64;
+            let variance = samples.iter().map(|x| (x-mean).powi(2)).sum::<f64>()/samples.len() as f64;
+            assert!(samples.iter().all(|x| *x >= 0.0));
+            assert!((mean-shape).abs()<0.02*shape.max(1.0));
+            assert!((variance-shape).abs()<0.05*shape.max(1.0));
+        }
+        let samples = rg.get_noncentral_chi_squareds(0.7, 3.0, 100000);
+        let mean = samples.iter().sum::<f64>()/samples.len() as f64;
+        let variance = samples.iter().map(|x| (x-mean).powi(2)).sum::<f64>()/samples.len() as f64;
+        assert!((mean-3.7).abs()<0.05);
+        assert!((variance-2.0*(0.7+6.0)).abs()<0.3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_noncentral_chi_squareds_negative_noncentrality_test(){
+        RandomNumberGenerator::new(Some(29)).get_noncentral_chi_squareds(0.7, -3.0, 1);
+    }
+
+    #[test]
+    fn get_correlated_gaussians_test(){
+        let mut rg = RandomNumberGenerator::new(Some(19));
+        let correlation = CorrelationMatrix::new(vec![vec![1.0, -0.6], vec![-0.6, 1.0]]);
+        let samples = rg.get_correlated_gaussians(&correlation, 200000);
+        let covariance = samples.iter().map(|z| z[0]*z[1]).sum::<f64>()/samples.len() as f64;
+        let variance = samples.iter().map(|z| z[1]*z[1]).sum::<f64>()/samples.len() as f64;
+        assert!((covariance+0.6).abs()<0.01);
+        assert!((variance-1.0).abs()<0.01);
+    }
+
+    #[test]
+    fn skip_test(){
+        let mut rg = RandomNumberGenerator::new(Some(23));
+        let mut rg2 = RandomNumberGenerator::new(Some(23));
+        let v = rg.get_uniforms(10);
+        rg2.get_uniforms(3);
+        rg2.skip(4);
+        assert_eq!(rg2.get_uniforms(3), v[7..]);
+    }
+
+    #[test]
+    fn substream_test(){
+        let rg = RandomNumberGenerator::new(Some(29));
+        assert_eq!(rg.substream(0).get_uniforms(5), RandomNumberGenerator::new(Some(29)).get_uniforms(5));
+        let first = rg.substream(1).get_uniforms(5);
+        assert_eq!(first, rg.substream(1).get_uniforms(5));
+        assert_ne!(first, rg.substream(2).get_uniforms(5));
+    }
+
+    #[test]
+    fn state_test(){
+        let mut rg = RandomNumberGenerator::with_gaussian_transform(Some(31), GaussianTransform::BoxMuller).substream(3);
+        rg.get_gaussians(7);
+        let saved = rg.state().to_string();
+        let expected = rg.get_gaussians(5);
+        let mut restored = RandomNumberGenerator::new(None);
+        restored.restore(&saved.parse().unwrap());
+        assert_eq!(restored.get_gaussians(5), expected);
+        assert_eq!("1 2".parse::<RandomNumberGeneratorState>(), Err(PricingError::InvalidState));
+    }
+
+    #[test]
+    fn moment_matching_test(){
+        let mut mm = MomentMatchingGenerator::new(RandomNumberGenerator::new(Some(37)), 3, 50);
+        let mut samples = mm.get_gaussians(100);
+        samples.append(&mut mm.get_gaussians(50));
+        // The coordinates of each batch of 50 paths have exactly zero mean and unit variance.
+        for batch in samples.chunks(150) {
+            for coordinate in 0..3 {
+                let mean = batch.iter().skip(coordinate).step_by(3).sum::<f64>()/50.0;
+                let variance = batch.iter().skip(coordinate).step_by(3).map(|z| z*z).sum::<f64>()/50.0;
+                assert!(mean.abs()<1e-14);
+                assert!((variance-1.0).abs()<1e-13);
+            }
+        }
+        let mut restarted = MomentMatchingGenerator::new(RandomNumberGenerator::new(Some(37)), 3, 50);
+        restarted.skip(20);
+        assert_eq!(restarted.get_gaussians(10), samples[20..30]);
+    }
+
+    #[test]
+    fn pcg64_test(){
+        // The outputs of the reference implementation seeded with pcg64_srandom_r(42, 54).
+        let mut rng = Pcg64::new(42, 54);
+        assert_eq!(rng.next_u64(), 0x86b1da1d72062b68);
+        assert_eq!(rng.next_u64(), 0x1304aa46c9853d39);
+        assert_eq!(rng.next_u64(), 0xa3670e9e0dd50358);
+        let mut jumped = Pcg64::new(42, 54);
+        jumped.advance(1000);
+        for _ in 0..997 {
+            rng.next_u64();
+        }
+        assert_eq!(rng, jumped);
+        // Seeded samples are pinned, so that seeded prices are reproducible across platforms and crate versions.
+        let mut seeded = Pcg64::from_seed(1, 0);
+        assert_eq!(seeded.next_u64(), 0x3b5a9effd6b2d506);
+        assert_eq!(seeded.next_u64(), 0x6cef0f13397196d0);
+        assert_eq!(seeded.next_u64(), 0x3e0de6fb4727a421);
+        let mut rg = RandomNumberGenerator::new(Some(1));
+        assert_eq!(rg.get_uniforms(3), vec![0.23185151811658078, 0.425522749131636, 0.2423996318313173]);
+        let mut rg = RandomNumberGenerator::new(Some(1));
+        let gaussians = rg.get_gaussians(3);
+        for (x, expected) in gaussians.into_iter().zip([-0.7327629277933473, -0.18778461224577916, -0.6986044472269823]) {
+            // The inverse cumulative normal function goes through the platform's logarithm, so the last bits may differ.
+            assert!((x-expected).abs() < 1e-14);
+        }
+    }
 }
\ No newline at end of file
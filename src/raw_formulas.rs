@@ -1,250 +1,3374 @@
 //! Provides Black-Scholes formulas for various securities and greeks, with inputs being f64.
-//! Provides Black-Scholes formulas for european call and put options, digital call and put options,
-//! forward prices, zero coupon bonds, and the greeks of put and call options.
+//! Provides Black-Scholes formulas for european call and put options, digital (cash-or-nothing), asset-or-nothing and gap call and put options,
+//! forward prices, zero coupon bonds, and the greeks of put and call options, including the second order greeks
+//! vanna, volga, charm, speed, zomma and color, and of the binary options.
 //! 
 //! The formulas in this module do not use the custom types `NonNegativeFloat` and `Stock`, so they can be used more
 //! easily outside the library.
 //! All functions panic if provided with negative parameters (except for short rate of interest).
+//! When the volatility or the time to expiry is zero, prices and greeks are their deterministic limits. At the money forward,
+//! where the payoff has a kink or a jump, greeks which blow up in the limit are infinite, with the sign of the limit.
+//! Every function has a `try_` variant which returns a `PricingError` instead of panicking.
+//! The implied divident rate, short rate of interest and volatility are recovered from option prices by the `implied_` functions.
+//! The `_with_borrow_cost` functions take the cost of borrowing the stock separately from its divident rate; both reduce the
+//! forward in the same way, so the other formulas can be used with their sum as the divident rate.
+//! 
+//! The `_batch` functions evaluate prices and greeks of many options in one call, writing the results to a slice.
+//! 
+//! The prices of european, digital, asset-or-nothing and gap options, forward prices, zero coupon bonds and the first and second
+//! order greeks of european options (except the theta decompositions) are generic over the `Real` trait, so they can be evaluated
+//! in `f32`, and evaluating them with `Dual` inputs returns the value together with its derivative, see
+//! `greeks::automatic_differentiation`. The other formulas, `BsCalculator` and the `_batch` functions take `f64`.
 
+use crate::error::PricingError;
+use crate::greeks::{DeltaConvention, Greeks, ThetaConvention, ThetaDecomposition};
+use crate::numerics::integration;
+use crate::numerics::roots;
 use crate::utils;
+use crate::utils::Real;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::math::LibmFloat;
 
-pub fn european_call_option_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+pub fn european_call_option_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
         panic!("One of the parameters is negative")
     }
-    if time_to_expiry==0.0{
-        if spot > strike{
-            return spot-strike;
+    if (volatility*time_to_expiry.sqrt()).value()==0.0{
+        let intrinsic = spot*(-divident_rate*time_to_expiry).exp()-strike*(-short_rate_of_interest*time_to_expiry).exp();
+        if intrinsic.value() > 0.0{
+            return intrinsic;
         }
-        return 0.0;
+        return T::constant(0.0);
     }
-    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
-    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
-    spot*utils::cumulative_normal_function(d1)*(-time_to_expiry*divident_rate).exp()-
-        strike*utils::cumulative_normal_function(d2)*(-short_rate_of_interest*time_to_expiry).exp()
+    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+volatility*volatility*T::constant(0.5))*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-volatility*volatility*T::constant(0.5))*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    spot*d1.cumulative_normal()*(-time_to_expiry*divident_rate).exp()-
+        strike*d2.cumulative_normal()*(-short_rate_of_interest*time_to_expiry).exp()
 }
 
-pub fn european_put_option_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_maturity: f64, volatility: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || strike < 0.0 || time_to_maturity < 0.0 || volatility < 0.0 || divident_rate < 0.0{
+pub fn european_put_option_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_maturity: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_maturity.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0{
         panic!("One of the parameters is negative")
     }
-    if time_to_maturity==0.0{
-        if spot < strike{
-            return strike-spot;
+    if (volatility*time_to_maturity.sqrt()).value()==0.0{
+        let intrinsic = strike*(-short_rate_of_interest*time_to_maturity).exp()-spot*(-divident_rate*time_to_maturity).exp();
+        if intrinsic.value() > 0.0{
+            return intrinsic;
         }
-        return 0.0;
+        return T::constant(0.0);
     }
 
-    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+0.5*volatility*volatility)*time_to_maturity)/(time_to_maturity.sqrt()*volatility);
-    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-0.5*volatility*volatility)*time_to_maturity)/(time_to_maturity.sqrt()*volatility);
-    strike*utils::cumulative_normal_function(-d2)*(-short_rate_of_interest*time_to_maturity).exp()-
-        spot*utils::cumulative_normal_function(-d1)*(-time_to_maturity*divident_rate).exp()
+    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+volatility*volatility*T::constant(0.5))*time_to_maturity)/(time_to_maturity.sqrt()*volatility);
+    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-volatility*volatility*T::constant(0.5))*time_to_maturity)/(time_to_maturity.sqrt()*volatility);
+    strike*(-d2).cumulative_normal()*(-short_rate_of_interest*time_to_maturity).exp()-
+        spot*(-d1).cumulative_normal()*(-time_to_maturity*divident_rate).exp()
 }
 
-pub fn forward_price(spot: f64, short_rate_of_interest: f64, time: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || time < 0.0 || divident_rate < 0.0{
+pub fn forward_price<T: Real>(spot: T, short_rate_of_interest: T, time: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || time.value() < 0.0 || divident_rate.value() < 0.0{
         panic!("One of the parameters is negative")
     }
     spot*((short_rate_of_interest-divident_rate)*time).exp()
 }
 
-pub fn digital_call_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+/// Returns the forward price of a stock with the divident rate `divident_rate` and the borrow (or repo) cost `borrow_cost`.
+pub fn forward_price_with_borrow_cost<T: Real>(spot: T, short_rate_of_interest: T, time: T, divident_rate: T, borrow_cost: T) ->T{
+    if borrow_cost.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    forward_price(spot, short_rate_of_interest, time, divident_rate+borrow_cost)
+}
+
+/// Returns the price of a european call option on a stock with the divident rate `divident_rate` and the borrow (or repo) cost
+/// `borrow_cost`.
+pub fn european_call_option_price_with_borrow_cost<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T,
+    divident_rate: T, borrow_cost: T) ->T{
+    if borrow_cost.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    european_call_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate+borrow_cost)
+}
+
+/// Returns the price of a european put option on a stock with the divident rate `divident_rate` and the borrow (or repo) cost
+/// `borrow_cost`.
+pub fn european_put_option_price_with_borrow_cost<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T,
+    divident_rate: T, borrow_cost: T) ->T{
+    if borrow_cost.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    european_put_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate+borrow_cost)
+}
+
+pub fn digital_call_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
         panic!("One of the parameters is negative")
     }
-    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
-    (-short_rate_of_interest*time_to_expiry).exp()*utils::cumulative_normal_function(d2)
+    if (volatility*time_to_expiry.sqrt()).value()==0.0{
+        let probability = degenerate_probability(spot.value(), strike.value(), short_rate_of_interest.value(), time_to_expiry.value(), divident_rate.value());
+        return (-short_rate_of_interest*time_to_expiry).exp()*T::constant(probability);
+    }
+    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-volatility*volatility*T::constant(0.5))*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    (-short_rate_of_interest*time_to_expiry).exp()*d2.cumulative_normal()
 }
 
-pub fn digital_put_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0{
+pub fn digital_put_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0{
         panic!("One of the parameters is negative")
     }
-    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
-    (-short_rate_of_interest*time_to_expiry).exp()*utils::cumulative_normal_function(-d2)
+    if (volatility*time_to_expiry.sqrt()).value()==0.0{
+        let probability = degenerate_probability(spot.value(), strike.value(), short_rate_of_interest.value(), time_to_expiry.value(), divident_rate.value());
+        return (-short_rate_of_interest*time_to_expiry).exp()*T::constant(1.0-probability);
+    }
+    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-volatility*volatility*T::constant(0.5))*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    (-short_rate_of_interest*time_to_expiry).exp()*(-d2).cumulative_normal()
 }
 
-pub fn zero_coupon_bond(short_rate_of_interest: f64, time_to_maturity: f64) -> f64{
-    if time_to_maturity < 0.0{
+pub fn zero_coupon_bond<T: Real>(short_rate_of_interest: T, time_to_maturity: T) -> T{
+    if time_to_maturity.value() < 0.0{
         panic!("One of the parameters is negative")
     }
     (-short_rate_of_interest*time_to_maturity).exp()
 }
 
+/// Returns the limit of `N(d1)` and `N(d2)` when `volatility*sqrt(time_to_expiry)` tends to zero: 1 if the forward is above the strike,
+/// 0 if it is below it, and 1/2 at the money forward.
+fn degenerate_probability(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, divident_rate: f64)->f64{
+    let log_moneyness = (spot/strike).ln()+(short_rate_of_interest-divident_rate)*time_to_expiry;
+    if log_moneyness > 0.0 {
+        1.0
+    }
+    else if log_moneyness < 0.0 {
+        0.0
+    }
+    else {
+        0.5
+    }
+}
+
+/// Returns `(d1, d2)` of the Black-Scholes formulas. When `volatility*sqrt(time_to_expiry)` is zero they are replaced by their limits,
+/// which are infinite with the sign of the log moneyness of the forward, or zero at the money forward.
+fn d1_d2<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T)->(T, T){
+    let deviation = volatility*time_to_expiry.sqrt();
+    if deviation.value() == 0.0 {
+        let limit = ((spot/strike).ln()+(short_rate_of_interest-divident_rate)*time_to_expiry).value();
+        let limit = if limit == 0.0 || limit.is_nan() { 0.0 } else { limit.signum()*f64::INFINITY };
+        return (T::constant(limit), T::constant(limit));
+    }
+    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+T::constant(0.5)*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    (d1, d1-deviation)
+}
+
+/// Returns `n(d)/deviation`, with its limits when `deviation` is zero: infinite at `d=0`, and zero otherwise (`d` is then infinite).
+fn density_over_deviation<T: Real>(d: T, deviation: T)->T{
+    if deviation.value() == 0.0 {
+        return T::constant(if d.value() == 0.0 { f64::INFINITY } else { 0.0 });
+    }
+    utils::normal_probability_density_function(d)/deviation
+}
+
 ///returns the derivatie of a european call option with respect to the spot, i.e. the delta.
-pub fn call_delta(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+pub fn call_delta<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
         panic!("One of the parameters is negative")
     }
-    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    let (d1, _) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
     utils::cumulative_normal_function(d1)*(-divident_rate*time_to_expiry).exp()
 }
 
+///returns the delta of a european call option in the convention `convention`.
+pub fn call_delta_with_convention<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T,
+    convention: DeltaConvention) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let forward = spot*((short_rate_of_interest-divident_rate)*time_to_expiry).exp();
+    match convention {
+        DeltaConvention::Spot => utils::cumulative_normal_function(d1)*(-divident_rate*time_to_expiry).exp(),
+        DeltaConvention::Forward => utils::cumulative_normal_function(d1),
+        DeltaConvention::SpotPremiumAdjusted => strike/spot*(-short_rate_of_interest*time_to_expiry).exp()*utils::cumulative_normal_function(d2),
+        DeltaConvention::ForwardPremiumAdjusted => strike/forward*utils::cumulative_normal_function(d2),
+        DeltaConvention::Driftless => utils::cumulative_normal_function(T::constant(0.5)*(d1+d2)),
+    }
+}
+
+///returns the delta of a european put option in the convention `convention`.
+pub fn put_delta_with_convention<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T,
+    convention: DeltaConvention) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let forward = spot*((short_rate_of_interest-divident_rate)*time_to_expiry).exp();
+    match convention {
+        DeltaConvention::Spot => -utils::cumulative_normal_function(-d1)*(-divident_rate*time_to_expiry).exp(),
+        DeltaConvention::Forward => -utils::cumulative_normal_function(-d1),
+        DeltaConvention::SpotPremiumAdjusted => -strike/spot*(-short_rate_of_interest*time_to_expiry).exp()*utils::cumulative_normal_function(-d2),
+        DeltaConvention::ForwardPremiumAdjusted => -strike/forward*utils::cumulative_normal_function(-d2),
+        DeltaConvention::Driftless => -utils::cumulative_normal_function(T::constant(-0.5)*(d1+d2)),
+    }
+}
+
 ///returns the second derivatie of a european call option with respect to the spot, i.e. the gamma.
-pub fn call_gamma(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+pub fn call_gamma<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
         panic!("One of the parameters is negative")
     }
-    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
-    utils::normal_probability_density_function(d1)*((-divident_rate*time_to_expiry).exp())/(volatility*spot*(time_to_expiry.sqrt()))
+    let (d1, _) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    density_over_deviation(d1, volatility*time_to_expiry.sqrt())*((-divident_rate*time_to_expiry).exp())/spot
 }
 
 ///returns the derivatie of a european call option with respect to the volatility, i.e. the vega.
-pub fn call_vega(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+pub fn call_vega<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
         panic!("One of the parameters is negative")
     }
-    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    let (d1, _) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
     utils::normal_probability_density_function(d1)*spot*(time_to_expiry.sqrt())*((-divident_rate*time_to_expiry).exp())
 }
 
 ///returns the derivatie of a european call option with respect to the time to expiry, i.e. the theta.
-pub fn call_theta(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+pub fn call_theta<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
         panic!("One of the parameters is negative")
     }
-    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
-    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
-    let t1 = spot*utils::normal_probability_density_function(d1)*volatility*(0.5/time_to_expiry.sqrt());
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let t1 = time_decay(spot, d1, time_to_expiry, volatility);
     let t2 = divident_rate*spot*utils::cumulative_normal_function(d1);
     let t3 = short_rate_of_interest*strike*utils::cumulative_normal_function(d2)*((-short_rate_of_interest*time_to_expiry).exp());
     (t2-t1)*(-divident_rate*time_to_expiry).exp()-t3
 }
 
+/// Returns the part of the theta due to the passage of time, `S*n(d1)*sigma/(2*sqrt(T))`, without the divident discount. It is zero
+/// without volatility, and infinite at the money forward at expiry.
+fn time_decay<T: Real>(spot: T, d1: T, time_to_expiry: T, volatility: T)->T{
+    if volatility.value() == 0.0 {
+        return T::constant(0.0);
+    }
+    T::constant(0.5)*spot*volatility*density_over_deviation(d1, time_to_expiry.sqrt())
+}
+
+///returns the theta of a european call option split into time decay and carry, see `ThetaDecomposition`. The time decay is
+///`-S*exp(-q*T)*n(d1)*sigma/(2*sqrt(T))` and the carry `q*S*exp(-q*T)*N(d1)-r*K*exp(-r*T)*N(d2)`.
+pub fn call_theta_decomposition(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->ThetaDecomposition{
+    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let divident_discount = (-divident_rate*time_to_expiry).exp();
+    ThetaDecomposition{
+        time_decay: -time_decay(spot, d1, time_to_expiry, volatility)*divident_discount,
+        carry: divident_rate*spot*utils::cumulative_normal_function(d1)*divident_discount-
+            short_rate_of_interest*strike*utils::cumulative_normal_function(d2)*(-short_rate_of_interest*time_to_expiry).exp(),
+    }
+}
+
+///returns the theta of a european call option per day of `convention`.
+pub fn call_theta_per_day<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T,
+    convention: ThetaConvention) ->T{
+    call_theta(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)/T::constant(convention.days_per_year())
+}
+
 ///returns the derivatie of a european call option with respect to the short rate of interest, i.e. the rho.
-pub fn call_rho(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+pub fn call_rho<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    let (_, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
     strike*time_to_expiry*utils::cumulative_normal_function(d2)*(-short_rate_of_interest*time_to_expiry).exp()
 }
 
 ///returns the derivatie of a european put option with respect to the spot, i.e. the delta.
-pub fn put_delta(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+pub fn put_delta<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
         panic!("One of the parameters is negative")
     }
-    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
-    (utils::cumulative_normal_function(d1)-1.0)*(-divident_rate*time_to_expiry).exp()
+    let (d1, _) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    (utils::cumulative_normal_function(d1)-T::constant(1.0))*(-divident_rate*time_to_expiry).exp()
 }
 
 ///returns the second derivatie of a european put option with respect to the spot, i.e. the gamma. Is equal to the gamma of the call option.
-pub fn put_gamma(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+pub fn put_gamma<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
     call_gamma(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
 }
 
 ///returns the derivatie of a european call option with respect to the volatility, i.e. the vega. Is equal to the vega of a call option.
-pub fn put_vega(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+pub fn put_vega<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
     call_vega(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
 }
 
 ///returns the derivatie of a european put option with respect to the time to expiry, i.e. the theta.
-pub fn put_theta(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+pub fn put_theta<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
         panic!("One of the parameters is negative")
     }
-    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
-    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
-    let t1 = spot*utils::normal_probability_density_function(d1)*volatility*(0.5/time_to_expiry.sqrt());
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let t1 = time_decay(spot, d1, time_to_expiry, volatility);
     let t2 = divident_rate*spot*utils::cumulative_normal_function(-d1);
     let t3 = short_rate_of_interest*strike*utils::cumulative_normal_function(-d2)*((-short_rate_of_interest*time_to_expiry).exp());
     (-t2-t1)*(-divident_rate*time_to_expiry).exp()+t3
 }
 
+///returns the theta of a european put option split into time decay and carry, see `ThetaDecomposition`. The time decay is
+///the one of the call, and the carry is `r*K*exp(-r*T)*N(-d2)-q*S*exp(-q*T)*N(-d1)`.
+pub fn put_theta_decomposition(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->ThetaDecomposition{
+    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let divident_discount = (-divident_rate*time_to_expiry).exp();
+    ThetaDecomposition{
+        time_decay: -time_decay(spot, d1, time_to_expiry, volatility)*divident_discount,
+        carry: short_rate_of_interest*strike*utils::cumulative_normal_function(-d2)*(-short_rate_of_interest*time_to_expiry).exp()-
+            divident_rate*spot*utils::cumulative_normal_function(-d1)*divident_discount,
+    }
+}
+
+///returns the theta of a european put option per day of `convention`.
+pub fn put_theta_per_day<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T,
+    convention: ThetaConvention) ->T{
+    put_theta(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)/T::constant(convention.days_per_year())
+}
+
 ///returns the derivatie of a european put option with respect to the short rate of interest, i.e. the rho.
-pub fn put_rho(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
-    let d2 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate-0.5*volatility*volatility)*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+pub fn put_rho<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    let (_, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
     -strike*time_to_expiry*utils::cumulative_normal_function(-d2)*(-short_rate_of_interest*time_to_expiry).exp()
 }
 
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+///returns the derivative of the delta of a european call option with respect to the volatility, i.e. the vanna.
+pub fn call_vanna<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    if (volatility*time_to_expiry.sqrt()).value() == 0.0 {
+        // Off the money forward the density vanishes faster than the other terms, and at the money forward d2=-sigma*sqrt(T)/2.
+        return if d1.value() == 0.0 {
+            T::constant(0.5)*(-divident_rate*time_to_expiry).exp()*utils::normal_probability_density_function(T::constant(0.0))*time_to_expiry.sqrt()
+        } else { T::constant(0.0) };
+    }
+    -(-divident_rate*time_to_expiry).exp()*utils::normal_probability_density_function(d1)*d2/volatility
+}
 
-    #[test]
-    fn forward_times_zero_cupon_is_spot(){
-        assert!((32.333-forward_price(32.333, 4.657, 3.2345, 0.0)*zero_coupon_bond(4.657, 3.2345))
-            .abs()<1e-14);
+///returns the second derivative of a european call option with respect to the volatility, i.e. the volga (or vomma).
+pub fn call_volga<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
     }
+    if (volatility*time_to_expiry.sqrt()).value() == 0.0 {
+        return T::constant(0.0);
+    }
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    call_vega(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)*d1*d2/volatility
+}
 
-    #[test]
-    fn call_put_parity_test_1(){
-        assert!((zero_coupon_bond(3.33, 0.15)*10.111-european_put_option_price(15.45, 10.111, 3.33, 0.15, 6.75, 0.0)-
-            (15.45-european_call_option_price(15.45, 10.111, 3.33, 0.15, 6.75, 0.0))).abs()<1e-14);
+///returns the derivative of the delta of a european call option with respect to time, i.e. the charm.
+///Has the same sign convention as `call_theta`.
+pub fn call_charm<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
     }
+    let root_of_time = time_to_expiry.sqrt();
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let dividend_discount = (-divident_rate*time_to_expiry).exp();
+    if (volatility*root_of_time).value() == 0.0 {
+        let carry = divident_rate*dividend_discount*utils::cumulative_normal_function(d1);
+        if d1.value() != 0.0 {
+            return carry;
+        }
+        // At the money forward the density term is `((r-q)/sigma+sigma/2)/sqrt(T)` times a positive factor as T tends to zero,
+        // and `(r-q)/sigma` times a positive factor as sigma tends to zero.
+        let (rate, divident_rate, volatility) = (short_rate_of_interest.value(), divident_rate.value(), volatility.value());
+        let coefficient = if volatility == 0.0 { rate-divident_rate } else { (rate-divident_rate)/volatility+0.5*volatility };
+        return if coefficient == 0.0 { carry } else { T::constant(-coefficient.signum()*f64::INFINITY) };
+    }
+    divident_rate*dividend_discount*utils::cumulative_normal_function(d1)-
+        dividend_discount*utils::normal_probability_density_function(d1)*
+        (T::constant(2.0)*(short_rate_of_interest-divident_rate)*time_to_expiry-d2*volatility*root_of_time)/
+        (T::constant(2.0)*time_to_expiry*volatility*root_of_time)
+}
 
-    #[test]
-    fn call_put_parity_test_2(){
-        assert!((5.0-4.0*zero_coupon_bond(2.0, 2.0)-
-            (european_call_option_price(5.0, 4.0, 2.0, 2.0, 1.0, 0.0)-european_put_option_price(5.0, 4.0, 2.0, 2.0, 1.0, 0.0))).abs()<1e-14);
+///returns the third derivative of a european call option with respect to the spot, i.e. the speed.
+pub fn call_speed<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, _) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    if (volatility*time_to_expiry.sqrt()).value() == 0.0 {
+        return T::constant(if d1.value() == 0.0 { f64::NEG_INFINITY } else { 0.0 });
     }
+    let gamma = call_gamma(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    -gamma/spot*(d1/(volatility*time_to_expiry.sqrt())+T::constant(1.0))
+}
 
-    #[test]
-    fn digital_put_plus_digital_call_equals_zero_cupon(){
+///returns the derivative of the gamma of a european call option with respect to the volatility, i.e. the zomma.
+pub fn call_zomma<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    if (volatility*time_to_expiry.sqrt()).value() == 0.0 {
+        return T::constant(if d1.value() == 0.0 { f64::NEG_INFINITY } else { 0.0 });
+    }
+    call_gamma(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)*(d1*d2-T::constant(1.0))/volatility
+}
 
-        assert!((zero_coupon_bond(3.784, 5.7)-digital_call_price(45.34, 43.234, 3.784, 5.7, 11.8563, 1.0)-digital_put_price(45.34, 43.234, 3.784, 5.7, 11.8563, 1.0))
-            .abs()<1e-14);
+///returns the derivative of the gamma of a european call option with respect to time, i.e. the color.
+///Has the same sign convention as `call_theta`.
+pub fn call_color<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let root_of_time = time_to_expiry.sqrt();
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    if (volatility*root_of_time).value() == 0.0 {
+        // At the money forward the color tends to infinity times `1+(r+q)T`.
+        let growth = 1.0+((short_rate_of_interest+divident_rate)*time_to_expiry).value();
+        return T::constant(if d1.value() == 0.0 { growth.signum()*f64::INFINITY } else { 0.0 });
     }
+    let t1 = (-divident_rate*time_to_expiry).exp()*utils::normal_probability_density_function(d1)/
+        (T::constant(2.0)*spot*time_to_expiry*volatility*root_of_time);
+    let t2 = T::constant(2.0)*divident_rate*time_to_expiry+T::constant(1.0)+
+        (T::constant(2.0)*(short_rate_of_interest-divident_rate)*time_to_expiry-d2*volatility*root_of_time)/(volatility*root_of_time)*d1;
+    t1*t2
+}
 
-    #[test]
-    fn call_price_test(){
-        assert!((european_call_option_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-2.36031028).abs()<1e-6)
+///returns the vanna of a european put option. Is equal to the vanna of a call option.
+pub fn put_vanna<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    call_vanna(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
+}
+
+///returns the volga of a european put option. Is equal to the volga of a call option.
+pub fn put_volga<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    call_volga(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
+}
+
+///returns the derivative of the delta of a european put option with respect to time, i.e. the charm.
+///Has the same sign convention as `put_theta`.
+pub fn put_charm<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    call_charm(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)-divident_rate*(-divident_rate*time_to_expiry).exp()
+}
+
+///returns the speed of a european put option. Is equal to the speed of a call option.
+pub fn put_speed<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    call_speed(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
+}
+
+///returns the zomma of a european put option. Is equal to the zomma of a call option.
+pub fn put_zomma<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    call_zomma(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
+}
+
+///returns the color of a european put option. Is equal to the color of a call option.
+pub fn put_color<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    call_color(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
+}
+
+///returns the risk neutral probability that a european call option finishes in the money, i.e. `N(d2)`. It is also the probability
+///that a digital call pays, and the undiscounted price of a digital call.
+pub fn call_in_the_money_probability(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    let (_, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    utils::cumulative_normal_function(d2)
+}
+
+///returns the risk neutral probability that a european put option (or a digital put) finishes in the money, i.e. `N(-d2)`.
+pub fn put_in_the_money_probability(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    let (_, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    utils::cumulative_normal_function(-d2)
+}
+
+///returns the real world probability that a european call option (or a digital call) finishes in the money, when the stock has
+///the total expected return `expected_return` (including the divident rate) instead of the short rate of interest.
+pub fn call_real_world_in_the_money_probability(spot: f64, strike: f64, expected_return: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    call_in_the_money_probability(spot, strike, expected_return, time_to_expiry, volatility, divident_rate)
+}
+
+///returns the real world probability that a european put option (or a digital put) finishes in the money, see
+///`call_real_world_in_the_money_probability`.
+pub fn put_real_world_in_the_money_probability(spot: f64, strike: f64, expected_return: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    put_in_the_money_probability(spot, strike, expected_return, time_to_expiry, volatility, divident_rate)
+}
+
+///returns the expected payoff of a european call option conditional on it finishing in the money, `E[S-K|S>K]`, under the risk
+///neutral measure. Passing the total expected return of the stock as `short_rate_of_interest` gives the real world expectation.
+///Returns NaN if the option finishes in the money with probability zero.
+pub fn call_expected_payoff_given_exercise(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let probability = utils::cumulative_normal_function(d2);
+    if probability == 0.0 {
+        return f64::NAN;
     }
+    let forward = spot*((short_rate_of_interest-divident_rate)*time_to_expiry).exp();
+    forward*utils::cumulative_normal_function(d1)/probability-strike
+}
 
-    #[test]
-    fn call_delta_test(){
-        assert!((call_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-0.23812531).abs()<1e-6)
+///returns the expected payoff of a european put option conditional on it finishing in the money, `E[K-S|S<K]`, see
+///`call_expected_payoff_given_exercise`.
+pub fn put_expected_payoff_given_exercise(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let probability = utils::cumulative_normal_function(-d2);
+    if probability == 0.0 {
+        return f64::NAN;
     }
+    let forward = spot*((short_rate_of_interest-divident_rate)*time_to_expiry).exp();
+    strike-forward*utils::cumulative_normal_function(-d1)/probability
+}
 
-    #[test]
-    fn call_gamma_test(){
-        assert!((call_gamma(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-0.01671937).abs()<1e-6)
+///returns the spot at expiry at which a european call option bought for `premium` breaks even, i.e. the strike plus the premium
+///carried to expiry at the short rate of interest.
+pub fn call_breakeven_spot(strike: f64, premium: f64, short_rate_of_interest: f64, time_to_expiry: f64) ->f64{
+    strike+premium*(short_rate_of_interest*time_to_expiry).exp()
+}
+
+///returns the spot at expiry at which a european put option bought for `premium` breaks even, i.e. the strike less the premium
+///carried to expiry at the short rate of interest. It is negative if the put cannot break even.
+pub fn put_breakeven_spot(strike: f64, premium: f64, short_rate_of_interest: f64, time_to_expiry: f64) ->f64{
+    strike-premium*(short_rate_of_interest*time_to_expiry).exp()
+}
+
+
+///returns the price of a european spread call option, with payoff `max(S1-S2-K, 0)`, using Kirk's approximation.
+///The spread is priced as a call on the first asset with the sum of the second asset and the strike lognormally distributed.
+///Exact when `strike` is zero (Margrabe's formula), and accurate for strikes small compared to the second spot.
+///Panics if one of the parameters is negative, or if the forward of the second asset plus the strike is not positive, see
+///`try_spread_call_price_kirk`.
+#[allow(clippy::too_many_arguments)]
+pub fn spread_call_price_kirk(spot_1: f64, spot_2: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility_1: f64,
+    volatility_2: f64, correlation: f64, divident_rate_1: f64, divident_rate_2: f64) ->f64{
+    try_spread_call_price_kirk(spot_1, spot_2, strike, short_rate_of_interest, time_to_expiry, volatility_1, volatility_2, correlation,
+        divident_rate_1, divident_rate_2).unwrap_or_else(|e| panic!("{}", e))
+}
+
+///returns the price of a european spread call option, with payoff `max(S1-S2-K, 0)`, using the Bjerksund-Stensland (2014) approximation.
+///It is a lower bound for the true price, and is generally more accurate than Kirk's approximation.
+///Panics if one of the parameters is negative, or if the forward of the second asset plus the strike is not positive, see
+///`try_spread_call_price_bjerksund_stensland`.
+#[allow(clippy::too_many_arguments)]
+pub fn spread_call_price_bjerksund_stensland(spot_1: f64, spot_2: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility_1: f64,
+    volatility_2: f64, correlation: f64, divident_rate_1: f64, divident_rate_2: f64) ->f64{
+    try_spread_call_price_bjerksund_stensland(spot_1, spot_2, strike, short_rate_of_interest, time_to_expiry, volatility_1, volatility_2,
+        correlation, divident_rate_1, divident_rate_2).unwrap_or_else(|e| panic!("{}", e))
+}
+
+///returns the price of an american call option with the quadratic approximation of Barone-Adesi and Whaley (1987).
+///The early exercise premium is approximated by the solution of the Black-Scholes equation without its time derivative term, which
+///gives a fast analytic price. The error is typically a few cents for expiries up to a year, and grows for longer expiries, where
+///the PDE pricer should be preferred. Without dividends the call is never exercised early and its price is the european price.
+pub fn american_call_price_barone_adesi_whaley(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->f64{
+    let european = european_call_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    if time_to_expiry==0.0 || divident_rate==0.0 || volatility==0.0 {
+        return f64::max(european, spot-strike);
     }
+    let ((_, q2), divident_discount_factor) = barone_adesi_whaley_exponents(short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let d1 = |s: f64| ((s/strike).ln()+(short_rate_of_interest-divident_rate+0.5*volatility*volatility)*time_to_expiry)
+        /(volatility*time_to_expiry.sqrt());
+    // The critical spot above which the call is exercised solves `excess(s)=0`.
+    let excess = |s: f64| european_call_option_price(s, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
+        +(1.0-divident_discount_factor*utils::cumulative_normal_function(d1(s)))*s/q2-(s-strike);
+    let mut high = 2.0*strike;
+    while excess(high) > 0.0 && high < 1e6*strike {
+        high *= 2.0;
+    }
+    let critical_spot = match roots::brent(&excess, strike, high, 1e-10*strike).ok() {
+        Some(critical_spot) => critical_spot,
+        None => return f64::max(european, spot-strike),
+    };
+    if spot >= critical_spot {
+        return spot-strike;
+    }
+    let a2 = critical_spot/q2*(1.0-divident_discount_factor*utils::cumulative_normal_function(d1(critical_spot)));
+    european+a2*(spot/critical_spot).powf(q2)
+}
 
-    #[test]
-    fn call_vega_test(){
-        assert!((call_vega(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-36.72893205).abs()<1e-6)
+///returns the price of an american call option on a stock paying a single known cash divident `divident` at `divident_time`,
+///with the closed form of Roll, Geske and Whaley (1977-1981). The stock less the present value of the divident is assumed to
+///follow a geometric brownian motion with volatility `volatility`, and the call is only exercised just before the ex-divident
+///time, if the stock is then above a critical price. If the divident is too small to ever justify early exercise, or is paid
+///after expiry, the price is the european price.
+pub fn american_call_price_roll_geske_whaley(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident: f64, divident_time: f64) ->f64{
+    if divident_time >= time_to_expiry || divident == 0.0 {
+        return european_call_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, 0.0);
+    }
+    let r = short_rate_of_interest;
+    let immediate_exercise = spot-strike*(-r*divident_time).exp();
+    let ex_divident_spot = spot-divident*(-r*divident_time).exp();
+    if ex_divident_spot <= 0.0 {
+        return f64::max(immediate_exercise, 0.0);
+    }
+    let european = european_call_option_price(ex_divident_spot, strike, r, time_to_expiry, volatility, 0.0);
+    let remaining_time = time_to_expiry-divident_time;
+    if divident <= -strike*(-r*remaining_time).exp_m1() {
+        return european;
+    }
+    if divident_time == 0.0 || volatility == 0.0 {
+        return f64::max(european, f64::max(immediate_exercise, 0.0));
     }
+    // The critical price above which the call is exercised before the divident solves `excess(s)=0`.
+    let excess = |s: f64| european_call_option_price(s, strike, r, remaining_time, volatility, 0.0)-(s+divident-strike);
+    let low = 1e-8*strike;
+    if excess(low) <= 0.0 {
+        return f64::max(immediate_exercise, 0.0);
+    }
+    let mut high = 2.0*strike;
+    while excess(high) > 0.0 && high < 1e6*strike {
+        high *= 2.0;
+    }
+    let critical_price = match roots::brent(&excess, low, high, 1e-10*strike).ok() {
+        Some(critical_price) => critical_price,
+        None => return f64::max(european, immediate_exercise),
+    };
+    let (deviation, deviation_1) = (volatility*time_to_expiry.sqrt(), volatility*divident_time.sqrt());
+    let a1 = ((ex_divident_spot/strike).ln()+(r+0.5*volatility*volatility)*time_to_expiry)/deviation;
+    let a2 = a1-deviation;
+    let b1 = ((ex_divident_spot/critical_price).ln()+(r+0.5*volatility*volatility)*divident_time)/deviation_1;
+    let b2 = b1-deviation_1;
+    let rho = -(divident_time/time_to_expiry).sqrt();
+    ex_divident_spot*utils::cumulative_normal_function(b1)+ex_divident_spot*utils::bivariate_cumulative_normal(a1, -b1, rho)
+        -strike*(-r*time_to_expiry).exp()*utils::bivariate_cumulative_normal(a2, -b2, rho)
+        -(strike-divident)*(-r*divident_time).exp()*utils::cumulative_normal_function(b2)
+}
 
-    #[test]
-    fn call_rho_test(){
-        assert!((call_rho(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-31.08530034).abs()<1e-6)
+///returns the price of an american put option with the quadratic approximation of Barone-Adesi and Whaley (1987), see
+///`american_call_price_barone_adesi_whaley`. With a non positive short rate of interest the put is never exercised early and its
+///price is the european price.
+pub fn american_put_price_barone_adesi_whaley(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->f64{
+    let european = european_put_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    if time_to_expiry==0.0 || short_rate_of_interest <= 0.0 || volatility==0.0 {
+        return f64::max(european, strike-spot);
+    }
+    let ((q1, _), divident_discount_factor) = barone_adesi_whaley_exponents(short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let d1 = |s: f64| ((s/strike).ln()+(short_rate_of_interest-divident_rate+0.5*volatility*volatility)*time_to_expiry)
+        /(volatility*time_to_expiry.sqrt());
+    // The critical spot below which the put is exercised solves `excess(s)=0`.
+    let excess = |s: f64| european_put_option_price(s, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)
+        -(1.0-divident_discount_factor*utils::cumulative_normal_function(-d1(s)))*s/q1-(strike-s);
+    let critical_spot = match roots::brent(&excess, 1e-8*strike, strike, 1e-10*strike).ok() {
+        Some(critical_spot) => critical_spot,
+        None => return f64::max(european, strike-spot),
+    };
+    if spot <= critical_spot {
+        return strike-spot;
     }
+    let a1 = -critical_spot/q1*(1.0-divident_discount_factor*utils::cumulative_normal_function(-d1(critical_spot)));
+    european+a1*(spot/critical_spot).powf(q1)
+}
 
-    #[test]
-    fn call_theta_test(){
-        assert!((call_theta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)+2.72505217).abs()<1e-6)
+///returns the price of an american call option with the flat boundary approximation of Bjerksund and Stensland (2002).
+///The exercise boundary is approximated by a flat boundary up to `0.5*(sqrt(5)-1)` of the time to expiry and another one up to
+///expiry, which gives a lower bound of the price, typically within a few cents of it and more accurate than the 1993 variant.
+///Without dividends the call is never exercised early and its price is the european price.
+pub fn american_call_price_bjerksund_stensland(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->f64{
+    let european = european_call_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    if time_to_expiry==0.0 || divident_rate==0.0 || volatility==0.0 {
+        return f64::max(european, spot-strike);
+    }
+    let (r, b, t) = (short_rate_of_interest, short_rate_of_interest-divident_rate, time_to_expiry);
+    let (beta, infinite_boundary, initial_boundary) = bjerksund_stensland_boundaries(strike, r, b, volatility);
+    let t1 = 0.5*(5.0_f64.sqrt()-1.0)*t;
+    let boundary = |time: f64| {
+        let h = -(b*time+2.0*volatility*time.sqrt())*strike*strike/((infinite_boundary-initial_boundary)*initial_boundary);
+        initial_boundary+(infinite_boundary-initial_boundary)*(1.0-h.exp())
+    };
+    let (i1, i2) = (boundary(t1), boundary(t));
+    if spot >= i2 {
+        return spot-strike;
     }
+    let (alpha1, alpha2) = ((i1-strike)*i1.powf(-beta), (i2-strike)*i2.powf(-beta));
+    let phi = |gamma: f64, h: f64, i: f64| bjerksund_stensland_phi(spot, t1, gamma, h, i, r, b, volatility);
+    let psi = |gamma: f64, h: f64| bjerksund_stensland_psi(spot, t, gamma, h, i2, i1, t1, r, b, volatility);
+    alpha2*spot.powf(beta)-alpha2*phi(beta, i2, i2)+phi(1.0, i2, i2)-phi(1.0, i1, i2)-strike*phi(0.0, i2, i2)+strike*phi(0.0, i1, i2)
+        +alpha1*phi(beta, i1, i2)-alpha1*psi(beta, i1)+psi(1.0, i1)-psi(1.0, strike)-strike*psi(0.0, i1)+strike*psi(0.0, strike)
+}
 
-    #[test]
-    fn put_price_test(){
-        assert!((european_put_option_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-16.69385653).abs()<1e-6)
+///returns the price of an american put option with the Bjerksund and Stensland (2002) approximation, through the put-call
+///transformation `P(S, K, r, q) = C(K, S, q, r)`. With a non positive short rate of interest the put is never exercised early
+///and its price is the european price.
+pub fn american_put_price_bjerksund_stensland(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->f64{
+    if short_rate_of_interest <= 0.0 {
+        let european = european_put_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+        return f64::max(european, strike-spot);
     }
+    american_call_price_bjerksund_stensland(strike, spot, divident_rate, time_to_expiry, volatility, short_rate_of_interest)
+}
 
-    #[test]
-    fn put_delta_test(){
-        println!("{}",put_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03));
-        assert!((put_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)+0.71988186).abs()<1e-6)
+///returns the price of an american call option with the flat boundary approximation of Bjerksund and Stensland (1993), which
+///uses a single flat exercise boundary up to expiry. It is faster but less accurate than `american_call_price_bjerksund_stensland`,
+///and is given for consistency with systems still using it.
+pub fn american_call_price_bjerksund_stensland_1993(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64,
+    volatility: f64, divident_rate: f64) ->f64{
+    let european = european_call_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    if time_to_expiry==0.0 || divident_rate==0.0 || volatility==0.0 {
+        return f64::max(european, spot-strike);
+    }
+    let (r, b, t) = (short_rate_of_interest, short_rate_of_interest-divident_rate, time_to_expiry);
+    let (beta, infinite_boundary, initial_boundary) = bjerksund_stensland_boundaries(strike, r, b, volatility);
+    let h = -(b*t+2.0*volatility*t.sqrt())*initial_boundary/(infinite_boundary-initial_boundary);
+    let i = initial_boundary+(infinite_boundary-initial_boundary)*(1.0-h.exp());
+    if spot >= i {
+        return spot-strike;
     }
+    let alpha = (i-strike)*i.powf(-beta);
+    let phi = |gamma: f64, h: f64| bjerksund_stensland_phi(spot, t, gamma, h, i, r, b, volatility);
+    alpha*spot.powf(beta)-alpha*phi(beta, i)+phi(1.0, i)-phi(1.0, strike)-strike*phi(0.0, i)+strike*phi(0.0, strike)
+}
 
-    #[test]
-    fn put_gamma_test(){
-        assert!((put_gamma(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-0.01671937).abs()<1e-6)
+///returns the price of an american put option with the Bjerksund and Stensland (1993) approximation, through the put-call
+///transformation, see `american_put_price_bjerksund_stensland`.
+pub fn american_put_price_bjerksund_stensland_1993(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64,
+    volatility: f64, divident_rate: f64) ->f64{
+    if short_rate_of_interest <= 0.0 {
+        let european = european_put_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+        return f64::max(european, strike-spot);
     }
+    american_call_price_bjerksund_stensland_1993(strike, spot, divident_rate, time_to_expiry, volatility, short_rate_of_interest)
+}
 
-    #[test]
-    fn put_vega_test(){
-        println!("put vega {}",put_vega(101.2, 123.0, 0.07, 1.43, 0.15, 0.03));
-        assert!((put_vega(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-36.72893205).abs()<1e-6)
+/// Returns `beta` and the exercise boundaries of a perpetual option and of an option at expiry, of the Bjerksund-Stensland
+/// approximations with cost of carry `b`.
+fn bjerksund_stensland_boundaries(strike: f64, r: f64, b: f64, volatility: f64)->(f64, f64, f64){
+    let variance = volatility*volatility;
+    let beta = (0.5-b/variance)+((b/variance-0.5)*(b/variance-0.5)+2.0*r/variance).sqrt();
+    (beta, beta/(beta-1.0)*strike, f64::max(strike, r/(r-b)*strike))
+}
+
+/// The function `phi` of Bjerksund and Stensland, the value of receiving `S^gamma` at `t` if the spot stays below the
+/// boundary `i` and ends below `h`.
+#[allow(clippy::too_many_arguments)]
+fn bjerksund_stensland_phi(spot: f64, t: f64, gamma: f64, h: f64, i: f64, r: f64, b: f64, volatility: f64)->f64{
+    let variance = volatility*volatility;
+    let deviation = volatility*t.sqrt();
+    let lambda = (-r+gamma*b+0.5*gamma*(gamma-1.0)*variance)*t;
+    let d = -((spot/h).ln()+(b+(gamma-0.5)*variance)*t)/deviation;
+    let kappa = 2.0*b/variance+(2.0*gamma-1.0);
+    lambda.exp()*spot.powf(gamma)*(utils::cumulative_normal_function(d)
+        -(i/spot).powf(kappa)*utils::cumulative_normal_function(d-2.0*(i/spot).ln()/deviation))
+}
+
+/// The function `psi` of Bjerksund and Stensland (2002), the analogue of `phi` with the two boundaries `i1` up to `t1` and `i2`
+/// from `t1` to `t`.
+#[allow(clippy::too_many_arguments)]
+fn bjerksund_stensland_psi(spot: f64, t: f64, gamma: f64, h: f64, i2: f64, i1: f64, t1: f64, r: f64, b: f64, volatility: f64)->f64{
+    let variance = volatility*volatility;
+    let drift = b+(gamma-0.5)*variance;
+    let (deviation_1, deviation) = (volatility*t1.sqrt(), volatility*t.sqrt());
+    let e1 = ((spot/i1).ln()+drift*t1)/deviation_1;
+    let e2 = ((i2*i2/(spot*i1)).ln()+drift*t1)/deviation_1;
+    let e3 = ((spot/i1).ln()-drift*t1)/deviation_1;
+    let e4 = ((i2*i2/(spot*i1)).ln()-drift*t1)/deviation_1;
+    let f1 = ((spot/h).ln()+drift*t)/deviation;
+    let f2 = ((i2*i2/(spot*h)).ln()+drift*t)/deviation;
+    let f3 = ((i1*i1/(spot*h)).ln()+drift*t)/deviation;
+    let f4 = ((spot*i1*i1/(h*i2*i2)).ln()+drift*t)/deviation;
+    let rho = (t1/t).sqrt();
+    let lambda = -r+gamma*b+0.5*gamma*(gamma-1.0)*variance;
+    let kappa = 2.0*b/variance+(2.0*gamma-1.0);
+    (lambda*t).exp()*spot.powf(gamma)*(utils::bivariate_cumulative_normal(-e1, -f1, rho)
+        -(i2/spot).powf(kappa)*utils::bivariate_cumulative_normal(-e2, -f2, rho)
+        -(i1/spot).powf(kappa)*utils::bivariate_cumulative_normal(-e3, -f3, -rho)
+        +(i1/i2).powf(kappa)*utils::bivariate_cumulative_normal(-e4, -f4, -rho))
+}
+
+/// Returns the negative and positive roots `(q1, q2)` of the quadratic equation of Barone-Adesi and Whaley, and `exp(-q*t)`.
+fn barone_adesi_whaley_exponents(short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)->((f64, f64), f64){
+    let variance = volatility*volatility;
+    let n = 2.0*(short_rate_of_interest-divident_rate)/variance;
+    // M/K = 2r/(variance*(1-exp(-r*t))), which tends to 2/(variance*t) as r tends to 0.
+    let m_over_k = if (short_rate_of_interest*time_to_expiry).abs() < 1e-12 {
+        2.0/(variance*time_to_expiry)
     }
+    else {
+        2.0*short_rate_of_interest/(variance*-(-short_rate_of_interest*time_to_expiry).exp_m1())
+    };
+    let root = ((n-1.0)*(n-1.0)+4.0*m_over_k).sqrt();
+    ((0.5*(1.0-n-root), 0.5*(1.0-n+root)), (-divident_rate*time_to_expiry).exp())
+}
 
-    #[test]
-    fn put_rho_test(){
-        assert!((put_rho(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)+128.05063872).abs()<1e-6)
+///returns the price of an arithmetic average Asian call option, with the average taken continuously from now to expiry, using the
+///moment matched lognormal approximation of Turnbull and Wakeman (1991). The first two moments of the average are computed exactly
+///and the average is priced as a lognormal variable with the same moments. The approximation is accurate for moderate volatilities,
+///and is a natural control variate for Monte Carlo pricing of `AsianOption`.
+pub fn asian_call_price_turnbull_wakeman(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->f64{
+    let (first_moment, second_moment) = continuous_average_moments(spot, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    moment_matched_lognormal_call_price(first_moment, second_moment, strike, short_rate_of_interest, time_to_expiry)
+}
+
+///returns the price of an arithmetic average Asian put option, with the average taken continuously from now to expiry, see
+///`asian_call_price_turnbull_wakeman`.
+pub fn asian_put_price_turnbull_wakeman(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->f64{
+    let (first_moment, second_moment) = continuous_average_moments(spot, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let call = moment_matched_lognormal_call_price(first_moment, second_moment, strike, short_rate_of_interest, time_to_expiry);
+    call-(-short_rate_of_interest*time_to_expiry).exp()*(first_moment-strike)
+}
+
+///returns the price of an arithmetic average Asian call option with discrete fixings, using the moment matched lognormal approximation
+///of Levy (1992), the discrete counterpart of `asian_call_price_turnbull_wakeman`.
+/// # Parameters
+/// - `fixing_times`: the times (from now) at which the underlying is observed for the average, each between 0 and `time_to_expiry`.
+///   A fixing at time 0 is the current spot.
+/// # Panics
+/// Panics if `fixing_times` is empty or one of the fixing times is outside `[0, time_to_expiry]`.
+pub fn discrete_asian_call_price_turnbull_wakeman(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64, fixing_times: &[f64]) ->f64{
+    let (first_moment, second_moment) = discrete_average_moments(spot, short_rate_of_interest, time_to_expiry, volatility, divident_rate, fixing_times);
+    moment_matched_lognormal_call_price(first_moment, second_moment, strike, short_rate_of_interest, time_to_expiry)
+}
+
+///returns the price of an arithmetic average Asian put option with discrete fixings, see `discrete_asian_call_price_turnbull_wakeman`.
+/// # Panics
+/// Panics if `fixing_times` is empty or one of the fixing times is outside `[0, time_to_expiry]`.
+pub fn discrete_asian_put_price_turnbull_wakeman(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64, fixing_times: &[f64]) ->f64{
+    let (first_moment, second_moment) = discrete_average_moments(spot, short_rate_of_interest, time_to_expiry, volatility, divident_rate, fixing_times);
+    let call = moment_matched_lognormal_call_price(first_moment, second_moment, strike, short_rate_of_interest, time_to_expiry);
+    call-(-short_rate_of_interest*time_to_expiry).exp()*(first_moment-strike)
+}
+
+/// Returns `(exp(x)-1)/x`, which tends to 1 as x tends to 0.
+fn relative_exp_m1(x: f64)->f64{
+    if x.abs() < 1e-12 {
+        1.0
+    }
+    else {
+        x.exp_m1()/x
+    }
+}
+
+/// Returns the first two moments of the continuous arithmetic average of the underlying over `[0, time_to_expiry]`.
+fn continuous_average_moments(spot: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)->(f64, f64){
+    let carry = short_rate_of_interest-divident_rate;
+    if time_to_expiry==0.0 {
+        return (spot, spot*spot);
     }
+    let first_moment = spot*relative_exp_m1(carry*time_to_expiry);
+    // E[A^2] = 2S^2/T^2 * int_0^T int_0^u exp(b(u+s)+variance*s) ds du, and the inner integral is u*exp(b*u)*relative_exp_m1((b+variance)*u).
+    let shifted_carry = carry+volatility*volatility;
+    let integrand = |u: f64| u*(carry*u).exp()*relative_exp_m1(shifted_carry*u);
+    let second_moment = 2.0*spot*spot/(time_to_expiry*time_to_expiry)*integration::gauss_legendre(&integrand, 0.0, time_to_expiry, 32);
+    (first_moment, second_moment)
+}
 
-    #[test]
-    fn put_theta_test(){
-        assert!((put_theta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-2.15630915).abs()<1e-6)
+/// Returns the first two moments of the arithmetic average of the underlying over the given fixing times.
+fn discrete_average_moments(spot: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64,
+    fixing_times: &[f64])->(f64, f64){
+    if fixing_times.is_empty() {
+        panic!("fixing_times must not be empty");
+    }
+    if fixing_times.iter().any(|t| *t < 0.0 || *t > time_to_expiry) {
+        panic!("Fixing times must be between 0 and the time to expiry");
+    }
+    let carry = short_rate_of_interest-divident_rate;
+    let variance = volatility*volatility;
+    let n = fixing_times.len() as f64;
+    let first_moment = fixing_times.iter().map(|t| spot*(carry*t).exp()).sum::<f64>()/n;
+    let mut second_moment = 0.0;
+    for ti in fixing_times {
+        for tj in fixing_times {
+            second_moment += (carry*(ti+tj)+variance*f64::min(*ti, *tj)).exp();
+        }
+    }
+    (first_moment, spot*spot*second_moment/(n*n))
+}
+
+/// Returns the discounted call price on a lognormal variable with the given first two moments.
+fn moment_matched_lognormal_call_price(first_moment: f64, second_moment: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64)->f64{
+    let discount_factor = (-short_rate_of_interest*time_to_expiry).exp();
+    let total_variance = f64::max((second_moment/(first_moment*first_moment)).ln(), 0.0);
+    if total_variance==0.0 || strike <= 0.0 {
+        return discount_factor*f64::max(first_moment-strike, 0.0);
+    }
+    let deviation = total_variance.sqrt();
+    let d1 = ((first_moment/strike).ln()+0.5*total_variance)/deviation;
+    discount_factor*(first_moment*utils::cumulative_normal_function(d1)-strike*utils::cumulative_normal_function(d1-deviation))
+}
+
+///returns the price of a continuously monitored ladder call option. The payoff at expiry is `max(S_T-K, L-K, 0)`, where `L` is the highest
+///rung reached by the underlying before expiry, so gains are locked in as the rungs are crossed. Rungs at or below the spot are
+///considered already reached.
+///The price is the european call plus, for every rung, a difference of up-and-in puts with the rung as their barrier.
+/// # Parameters
+/// - `rungs`: the rungs, sorted in increasing order, all above the strike.
+/// # Panics
+/// Panics if the rungs are not sorted, or one of them is not above the strike.
+pub fn ladder_call_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64, rungs: &[f64]) ->f64{
+    if rungs.windows(2).any(|w| w[0] >= w[1]) || rungs.iter().any(|rung| *rung <= strike) {
+        panic!("The rungs of a ladder call need to be sorted and above the strike");
+    }
+    if time_to_expiry==0.0 || volatility==0.0 {
+        let forward = spot*((short_rate_of_interest-divident_rate)*time_to_expiry).exp();
+        let maximum = f64::max(spot, forward);
+        let locked = rungs.iter().rev().find(|rung| **rung <= maximum).copied().unwrap_or(strike);
+        return (-short_rate_of_interest*time_to_expiry).exp()*f64::max(forward-strike, locked-strike);
+    }
+    let put = |x: f64| european_put_option_price(spot, x, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let up_and_in_put = |x: f64, barrier: f64| reflected_barrier_price(-1.0, spot, x, barrier, short_rate_of_interest, time_to_expiry,
+        volatility, divident_rate);
+    let mut price = european_call_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let mut previous = strike;
+    for rung in rungs {
+        price += if spot >= *rung { put(*rung)-put(previous) } else { up_and_in_put(*rung, *rung)-up_and_in_put(previous, *rung) };
+        previous = *rung;
+    }
+    price
+}
+
+///returns the price of a continuously monitored ladder put option. The payoff at expiry is `max(K-S_T, K-L, 0)`, where `L` is the lowest
+///rung reached by the underlying before expiry. Rungs at or above the spot are considered already reached. See `ladder_call_price`.
+/// # Parameters
+/// - `rungs`: the rungs, sorted in decreasing order, all below the strike.
+/// # Panics
+/// Panics if the rungs are not sorted, or one of them is not below the strike.
+pub fn ladder_put_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64, rungs: &[f64]) ->f64{
+    if rungs.windows(2).any(|w| w[0] <= w[1]) || rungs.iter().any(|rung| *rung >= strike) {
+        panic!("The rungs of a ladder put need to be sorted and below the strike");
+    }
+    if time_to_expiry==0.0 || volatility==0.0 {
+        let forward = spot*((short_rate_of_interest-divident_rate)*time_to_expiry).exp();
+        let minimum = f64::min(spot, forward);
+        let locked = rungs.iter().rev().find(|rung| **rung >= minimum).copied().unwrap_or(strike);
+        return (-short_rate_of_interest*time_to_expiry).exp()*f64::max(strike-forward, strike-locked);
+    }
+    let call = |x: f64| european_call_option_price(spot, x, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let down_and_in_call = |x: f64, barrier: f64| reflected_barrier_price(1.0, spot, x, barrier, short_rate_of_interest, time_to_expiry,
+        volatility, divident_rate);
+    let mut price = european_put_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    let mut previous = strike;
+    for rung in rungs {
+        price += if spot <= *rung { call(*rung)-call(previous) } else { down_and_in_call(*rung, *rung)-down_and_in_call(previous, *rung) };
+        previous = *rung;
+    }
+    price
+}
+
+/// Returns the reflected term of the barrier option formulas of Reiner and Rubinstein, which is the price of a down-and-in call
+/// (`phi=1`) or an up-and-in put (`phi=-1`) whose strike is beyond the barrier, for a spot on the other side of the barrier.
+#[allow(clippy::too_many_arguments)]
+fn reflected_barrier_price(phi: f64, spot: f64, strike: f64, barrier: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64)->f64{
+    let deviation = volatility*time_to_expiry.sqrt();
+    let mu = (short_rate_of_interest-divident_rate)/(volatility*volatility)-0.5;
+    let y = (barrier*barrier/(spot*strike)).ln()/deviation+(1.0+mu)*deviation;
+    let ratio = barrier/spot;
+    phi*spot*(-divident_rate*time_to_expiry).exp()*ratio.powf(2.0*(mu+1.0))*utils::cumulative_normal_function(phi*y)
+        -phi*strike*(-short_rate_of_interest*time_to_expiry).exp()*ratio.powf(2.0*mu)*utils::cumulative_normal_function(phi*(y-deviation))
+}
+
+///returns the fair strike of a variance swap (in variance units, i.e. the square of a volatility), replicated statically from a strip
+///of european call and put prices expiring at the same time as the swap.
+///Uses the discretization of the log contract of the CBOE VIX methodology: out of the money options are weighted by `dK/K^2`,
+///with the strike just below the forward used as the boundary between puts and calls.
+///# Parameters
+///- `forward` - The forward price of the underlying at expiry.
+///- `strikes` - The strikes of the strip, sorted in increasing order. The strip must contain a strike below the forward.
+///- `call_prices`, `put_prices` - The prices of the calls and puts with strikes `strikes`.
+pub fn variance_swap_strike(forward: f64, short_rate_of_interest: f64, time_to_expiry: f64, strikes: &[f64], call_prices: &[f64], put_prices: &[f64]) ->f64{
+    if forward < 0.0 || time_to_expiry < 0.0 || strikes.iter().any(|k| *k < 0.0) {
+        panic!("One of the parameters is negative")
+    }
+    if strikes.len() < 2 || strikes.len() != call_prices.len() || strikes.len() != put_prices.len() {
+        panic!("Invalid strip of options")
+    }
+    let boundary = strikes.iter().rposition(|k| *k <= forward).expect("The strip must contain a strike below the forward");
+    let growth = (short_rate_of_interest*time_to_expiry).exp();
+    let integrand: Vec<f64> = strikes.iter().enumerate().map(|(i, k)| {
+        let out_of_the_money_price = match i.cmp(&boundary) {
+            core::cmp::Ordering::Less => put_prices[i],
+            core::cmp::Ordering::Equal => 0.5*(put_prices[i]+call_prices[i]),
+            core::cmp::Ordering::Greater => call_prices[i],
+        };
+        growth*out_of_the_money_price/(k*k)
+    }).collect();
+    let sum = integration::sampled_riemann_sum(strikes, &integrand);
+    let correction = forward/strikes[boundary]-1.0;
+    (2.0*sum-correction*correction)/time_to_expiry
+}
+
+///returns the fair strike of a variance swap (in variance units) when the implied volatility is flat, i.e. `volatility^2`.
+pub fn flat_volatility_variance_swap_strike(volatility: f64) ->f64{
+    if volatility < 0.0 {
+        panic!("One of the parameters is negative")
     }
+    volatility*volatility
+}
+
+///returns the convexity adjustment between the square root of the variance swap strike and the volatility swap strike,
+///using the second order approximation `Var(V)/(8*E[V]^(3/2))` of the expectation of the square root of the realized variance `V`.
+///# Parameters
+///- `variance_swap_strike` - The fair strike of a variance swap, i.e. the expectation of the realized variance.
+///- `variance_of_realized_variance` - The variance of the realized variance, e.g. `2*volatility^4/n` for `n` observations under Black-Scholes.
+///# Panics
+///Panics if one of the parameters is negative, or if the variance swap strike is zero.
+pub fn volatility_swap_convexity_adjustment(variance_swap_strike: f64, variance_of_realized_variance: f64) ->f64{
+    if variance_swap_strike < 0.0 || variance_of_realized_variance < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    if variance_swap_strike == 0.0 {
+        panic!("The variance swap strike must be positive")
+    }
+    variance_of_realized_variance/(8.0*variance_swap_strike.powf(1.5))
+}
+
+///returns the approximate fair strike of a volatility swap, i.e. the square root of the variance swap strike minus the convexity
+///adjustment computed by `volatility_swap_convexity_adjustment`.
+pub fn volatility_swap_strike(variance_swap_strike: f64, variance_of_realized_variance: f64) ->f64{
+    variance_swap_strike.sqrt()-volatility_swap_convexity_adjustment(variance_swap_strike, variance_of_realized_variance)
+}
+
+///returns the price of an asset-or-nothing call option, which pays the value of the stock at expiry if it is above the strike.
+pub fn asset_or_nothing_call_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    if (volatility*time_to_expiry.sqrt()).value()==0.0{
+        let probability = degenerate_probability(spot.value(), strike.value(), short_rate_of_interest.value(), time_to_expiry.value(), divident_rate.value());
+        return spot*(-divident_rate*time_to_expiry).exp()*T::constant(probability);
+    }
+    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+volatility*volatility*T::constant(0.5))*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    spot*(-divident_rate*time_to_expiry).exp()*d1.cumulative_normal()
+}
+
+///returns the price of an asset-or-nothing put option, which pays the value of the stock at expiry if it is below the strike.
+pub fn asset_or_nothing_put_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    if (volatility*time_to_expiry.sqrt()).value()==0.0{
+        let probability = degenerate_probability(spot.value(), strike.value(), short_rate_of_interest.value(), time_to_expiry.value(), divident_rate.value());
+        return spot*(-divident_rate*time_to_expiry).exp()*T::constant(1.0-probability);
+    }
+    let d1 = ((spot/strike).ln() + (short_rate_of_interest-divident_rate+volatility*volatility*T::constant(0.5))*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    spot*(-divident_rate*time_to_expiry).exp()*(-d1).cumulative_normal()
+}
+
+///returns the price of a gap call option, which pays `S-payment_strike` at expiry if `S` is above `trigger_strike`.
+pub fn gap_call_price<T: Real>(spot: T, payment_strike: T, trigger_strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || payment_strike.value() < 0.0 || trigger_strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    if (volatility*time_to_expiry.sqrt()).value()==0.0{
+        let probability = degenerate_probability(spot.value(), trigger_strike.value(), short_rate_of_interest.value(), time_to_expiry.value(), divident_rate.value());
+        return (spot*(-divident_rate*time_to_expiry).exp()-payment_strike*(-short_rate_of_interest*time_to_expiry).exp())*T::constant(probability);
+    }
+    let d1 = ((spot/trigger_strike).ln() + (short_rate_of_interest-divident_rate+volatility*volatility*T::constant(0.5))*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    let d2 = d1-volatility*time_to_expiry.sqrt();
+    spot*(-divident_rate*time_to_expiry).exp()*d1.cumulative_normal()-payment_strike*(-short_rate_of_interest*time_to_expiry).exp()*d2.cumulative_normal()
+}
+
+///returns the price of a gap put option, which pays `payment_strike-S` at expiry if `S` is below `trigger_strike`.
+pub fn gap_put_price<T: Real>(spot: T, payment_strike: T, trigger_strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->T{
+    if spot.value() < 0.0 || payment_strike.value() < 0.0 || trigger_strike.value() < 0.0 || time_to_expiry.value() < 0.0 || volatility.value() < 0.0 || divident_rate.value() < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    if (volatility*time_to_expiry.sqrt()).value()==0.0{
+        let probability = degenerate_probability(spot.value(), trigger_strike.value(), short_rate_of_interest.value(), time_to_expiry.value(), divident_rate.value());
+        return (payment_strike*(-short_rate_of_interest*time_to_expiry).exp()-spot*(-divident_rate*time_to_expiry).exp())*T::constant(1.0-probability);
+    }
+    let d1 = ((spot/trigger_strike).ln() + (short_rate_of_interest-divident_rate+volatility*volatility*T::constant(0.5))*time_to_expiry)/(time_to_expiry.sqrt()*volatility);
+    let d2 = d1-volatility*time_to_expiry.sqrt();
+    payment_strike*(-short_rate_of_interest*time_to_expiry).exp()*(-d2).cumulative_normal()-spot*(-divident_rate*time_to_expiry).exp()*(-d1).cumulative_normal()
+}
 
-}
\ No newline at end of file
+///returns the derivative of an asset-or-nothing call option with respect to the spot, i.e. the delta.
+pub fn asset_or_nothing_call_delta(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, _) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    (-divident_rate*time_to_expiry).exp()*(utils::cumulative_normal_function(d1)+density_over_deviation(d1, volatility*time_to_expiry.sqrt()))
+}
+
+///returns the derivative of an asset-or-nothing put option with respect to the spot, i.e. the delta.
+pub fn asset_or_nothing_put_delta(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, _) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    (-divident_rate*time_to_expiry).exp()*(utils::cumulative_normal_function(-d1)-density_over_deviation(d1, volatility*time_to_expiry.sqrt()))
+}
+
+///returns the derivative of a gap call option with respect to the spot, i.e. the delta.
+pub fn gap_call_delta(spot: f64, payment_strike: f64, trigger_strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    if spot < 0.0 || payment_strike < 0.0 || trigger_strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, d2) = d1_d2(spot, trigger_strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    (-divident_rate*time_to_expiry).exp()*utils::cumulative_normal_function(d1)+
+        gap_jump_delta(spot, payment_strike, trigger_strike, short_rate_of_interest, time_to_expiry, d2, volatility*time_to_expiry.sqrt())
+}
+
+///returns the derivative of a gap put option with respect to the spot, i.e. the delta.
+pub fn gap_put_delta(spot: f64, payment_strike: f64, trigger_strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    if spot < 0.0 || payment_strike < 0.0 || trigger_strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let (d1, d2) = d1_d2(spot, trigger_strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    -(-divident_rate*time_to_expiry).exp()*utils::cumulative_normal_function(-d1)+
+        gap_jump_delta(spot, payment_strike, trigger_strike, short_rate_of_interest, time_to_expiry, d2, volatility*time_to_expiry.sqrt())
+}
+
+/// Returns the part of the delta of a gap option due to the jump of its payoff at the trigger strike. It is zero when the strikes
+/// are equal, even at the money forward without volatility, where the payoff has no jump.
+fn gap_jump_delta(spot: f64, payment_strike: f64, trigger_strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, d2: f64, deviation: f64)->f64{
+    if trigger_strike == payment_strike {
+        return 0.0;
+    }
+    (-short_rate_of_interest*time_to_expiry).exp()*density_over_deviation(d2, deviation)*(trigger_strike-payment_strike)/spot
+}
+
+/// Returns `vanilla_weight*vanilla+digital_weight*digital`, leaving out a term with a zero weight, whose greeks may be infinite.
+fn combine_greeks(vanilla: Greeks, vanilla_weight: f64, digital: Greeks, digital_weight: f64)->Greeks{
+    let combine = |vanilla: f64, digital: f64| {
+        if digital_weight == 0.0 { vanilla_weight*vanilla } else { vanilla_weight*vanilla+digital_weight*digital }
+    };
+    Greeks{
+        price: combine(vanilla.price, digital.price),
+        delta: combine(vanilla.delta, digital.delta),
+        gamma: combine(vanilla.gamma, digital.gamma),
+        vega: combine(vanilla.vega, digital.vega),
+        theta: combine(vanilla.theta, digital.theta),
+        rho: combine(vanilla.rho, digital.rho),
+    }
+}
+
+///returns the price, delta, gamma, vega, theta and rho of an asset-or-nothing call option, as a call option and `strike`
+///cash-or-nothing calls.
+pub fn asset_or_nothing_call_greeks(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Greeks{
+    let calculator = BsCalculator::new(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    combine_greeks(calculator.call_greeks(), 1.0, calculator.digital_call_greeks(), strike)
+}
+
+///returns the price, delta, gamma, vega, theta and rho of an asset-or-nothing put option, as `strike` cash-or-nothing puts less
+///a put option.
+pub fn asset_or_nothing_put_greeks(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Greeks{
+    let calculator = BsCalculator::new(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    combine_greeks(calculator.put_greeks(), -1.0, calculator.digital_put_greeks(), strike)
+}
+
+///returns the price, delta, gamma, vega, theta and rho of a gap call option, as a call option with strike `trigger_strike` and
+///`trigger_strike-payment_strike` cash-or-nothing calls.
+pub fn gap_call_greeks(spot: f64, payment_strike: f64, trigger_strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Greeks{
+    if payment_strike < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let calculator = BsCalculator::new(spot, trigger_strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    combine_greeks(calculator.call_greeks(), 1.0, calculator.digital_call_greeks(), trigger_strike-payment_strike)
+}
+
+///returns the price, delta, gamma, vega, theta and rho of a gap put option, as a put option with strike `trigger_strike` and
+///`payment_strike-trigger_strike` cash-or-nothing puts.
+pub fn gap_put_greeks(spot: f64, payment_strike: f64, trigger_strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Greeks{
+    if payment_strike < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    let calculator = BsCalculator::new(spot, trigger_strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    combine_greeks(calculator.put_greeks(), 1.0, calculator.digital_put_greeks(), payment_strike-trigger_strike)
+}
+
+///returns the price of a one-touch option, which pays 1 if the stock touches `barrier` before expiry.
+///The barrier is monitored continuously, and is a down barrier if it is below the spot and an up barrier otherwise.
+///If `pay_at_hit` is `true` the payment is made when the barrier is touched, otherwise it is made at expiry.
+pub fn one_touch_price(spot: f64, barrier: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64, pay_at_hit: bool) ->f64{
+    if spot < 0.0 || barrier < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    if spot == barrier {
+        return if pay_at_hit { 1.0 } else { (-short_rate_of_interest*time_to_expiry).exp() };
+    }
+    if time_to_expiry == 0.0 {
+        return 0.0;
+    }
+    if volatility == 0.0 {
+        return match deterministic_hitting_time(spot, barrier, short_rate_of_interest, time_to_expiry, divident_rate) {
+            Some(time) => (-short_rate_of_interest*if pay_at_hit { time } else { time_to_expiry }).exp(),
+            None => 0.0,
+        };
+    }
+    if !pay_at_hit {
+        return (-short_rate_of_interest*time_to_expiry).exp()-no_touch_price(spot, barrier, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+    }
+    let eta = if barrier < spot { 1.0 } else { -1.0 };
+    let variance = volatility*volatility;
+    let mu = (short_rate_of_interest-divident_rate-0.5*variance)/variance;
+    let lambda = (mu*mu+2.0*short_rate_of_interest/variance).sqrt();
+    let root_of_time = time_to_expiry.sqrt();
+    let z = (barrier/spot).ln()/(volatility*root_of_time)+lambda*volatility*root_of_time;
+    (barrier/spot).powf(mu+lambda)*utils::cumulative_normal_function(eta*z)+
+        (barrier/spot).powf(mu-lambda)*utils::cumulative_normal_function(eta*z-2.0*eta*lambda*volatility*root_of_time)
+}
+
+///returns the price of a no-touch option, which pays 1 at expiry if the stock does not touch `barrier` before expiry.
+///The barrier is monitored continuously, and is a down barrier if it is below the spot and an up barrier otherwise.
+pub fn no_touch_price(spot: f64, barrier: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    if spot < 0.0 || barrier < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    (-short_rate_of_interest*time_to_expiry).exp()*survival_probability(spot, barrier, short_rate_of_interest-divident_rate, time_to_expiry, volatility)
+}
+
+///returns the risk neutral probability that the stock touches `barrier` before expiry, i.e. the undiscounted price of a one-touch
+///option paying at expiry. The barrier is monitored continuously, and is a down barrier if it is below the spot and an up barrier
+///otherwise.
+pub fn touch_probability(spot: f64, barrier: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    if spot < 0.0 || barrier < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    1.0-survival_probability(spot, barrier, short_rate_of_interest-divident_rate, time_to_expiry, volatility)
+}
+
+///returns the real world probability that the stock touches `barrier` before expiry, when it has the total expected return
+///`expected_return` (including the divident rate) instead of the short rate of interest, see `touch_probability`.
+pub fn real_world_touch_probability(spot: f64, barrier: f64, expected_return: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    touch_probability(spot, barrier, expected_return, time_to_expiry, volatility, divident_rate)
+}
+
+///returns the risk neutral density of the first time `time` at which the stock touches `barrier` (the inverse Gaussian density
+///of the first passage time of the log price). Pass the total expected return of the stock as `short_rate_of_interest` for the
+///real world density. It is zero at time zero, and for all times without volatility.
+pub fn first_passage_time_density(spot: f64, barrier: f64, short_rate_of_interest: f64, time: f64, volatility: f64, divident_rate: f64) ->f64{
+    if spot < 0.0 || barrier < 0.0 || time < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    if time == 0.0 || volatility == 0.0 || spot == barrier {
+        return 0.0;
+    }
+    let drift = short_rate_of_interest-divident_rate-0.5*volatility*volatility;
+    let log_barrier = (barrier/spot).ln();
+    let deviation = volatility*time.sqrt();
+    log_barrier.abs()/(time*deviation)*utils::normal_probability_density_function((log_barrier-drift*time)/deviation)
+}
+
+///returns the risk neutral expectation of the time until the stock touches `barrier` or expiry, whichever comes first, i.e. the
+///expected life of a product knocked out at the barrier. Pass the total expected return of the stock as `short_rate_of_interest`
+///for the real world expectation.
+pub fn expected_first_passage_time(spot: f64, barrier: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->f64{
+    if spot < 0.0 || barrier < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+        panic!("One of the parameters is negative")
+    }
+    if spot == barrier || time_to_expiry == 0.0 {
+        return 0.0;
+    }
+    let growth_rate = short_rate_of_interest-divident_rate;
+    if volatility == 0.0 {
+        return deterministic_hitting_time(spot, barrier, growth_rate, time_to_expiry, 0.0).unwrap_or(time_to_expiry);
+    }
+    // E[min(tau, T)] is the integral of the survival probability up to expiry.
+    integration::adaptive_simpson(&|t| survival_probability(spot, barrier, growth_rate, t, volatility), 0.0, time_to_expiry,
+        1e-10*time_to_expiry)
+}
+
+/// Returns the probability that a stock growing at `growth_rate` does not touch `barrier` before `time_to_expiry`, with the
+/// reflection principle for the drifted Brownian motion of its log price.
+fn survival_probability(spot: f64, barrier: f64, growth_rate: f64, time_to_expiry: f64, volatility: f64)->f64{
+    if spot == barrier {
+        return 0.0;
+    }
+    if time_to_expiry == 0.0 {
+        return 1.0;
+    }
+    if volatility == 0.0 {
+        return match deterministic_hitting_time(spot, barrier, growth_rate, time_to_expiry, 0.0) {
+            Some(_) => 0.0,
+            None => 1.0,
+        };
+    }
+    let eta = if barrier < spot { 1.0 } else { -1.0 };
+    let drift = growth_rate-0.5*volatility*volatility;
+    let log_barrier = (barrier/spot).ln();
+    let denominator = volatility*time_to_expiry.sqrt();
+    utils::cumulative_normal_function(eta*(-log_barrier+drift*time_to_expiry)/denominator)-
+        (2.0*drift*log_barrier/(volatility*volatility)).exp()*utils::cumulative_normal_function(eta*(log_barrier+drift*time_to_expiry)/denominator)
+}
+
+/// Returns the time at which the deterministic path `S*exp((r-q)t)` of a stock without volatility reaches `barrier`, if it reaches
+/// it before expiry.
+fn deterministic_hitting_time(spot: f64, barrier: f64, short_rate_of_interest: f64, time_to_expiry: f64, divident_rate: f64)->Option<f64>{
+    let drift = short_rate_of_interest-divident_rate;
+    let time = (barrier/spot).ln()/drift;
+    if time >= 0.0 && time <= time_to_expiry {
+        Some(time)
+    }
+    else {
+        None
+    }
+}
+
+///returns the continuous divident rate implied by put-call parity `C-P = S*exp(-q*T)-K*exp(-r*T)` from the prices of a
+///call and a put with the same strike and expiry.
+///# Panics
+///Panics if one of the parameters is negative, if `time_to_expiry` is zero, or if the prices violate the bounds of put-call parity.
+pub fn implied_divident_rate(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, call_price: f64, put_price: f64) ->f64{
+    try_implied_divident_rate(spot, strike, short_rate_of_interest, time_to_expiry, call_price, put_price)
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+///returns the short rate of interest implied by put-call parity `C-P = S*exp(-q*T)-K*exp(-r*T)` from the prices of a
+///call and a put with the same strike and expiry.
+///# Panics
+///Panics if one of the parameters is negative, if `time_to_expiry` is zero, or if the prices violate the bounds of put-call parity.
+pub fn implied_short_rate_of_interest(spot: f64, strike: f64, time_to_expiry: f64, divident_rate: f64, call_price: f64, put_price: f64) ->f64{
+    try_implied_short_rate_of_interest(spot, strike, time_to_expiry, divident_rate, call_price, put_price)
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+///returns the short rate of interest and the continuous divident rate implied by the prices of calls and puts with several
+///strikes and the same expiry, as `(r, q)`. Put-call parity `C-P = S*exp(-q*T)-exp(-r*T)*K` is linear in the strike, so the
+///discount factor and the dividend discount factor are the least squares slope and intercept of `C-P` against `K`.
+///# Panics
+///Panics if one of the parameters is negative, if there are less than two distinct strikes, or if the fitted discount factors are not positive.
+pub fn implied_rate_and_divident_rate(spot: f64, time_to_expiry: f64, strikes: &[f64], call_prices: &[f64], put_prices: &[f64]) ->(f64, f64){
+    try_implied_rate_and_divident_rate(spot, time_to_expiry, strikes, call_prices, put_prices).unwrap_or_else(|e| panic!("{}", e))
+}
+
+///returns the Black-Scholes implied volatility of a european call option with price `call_price`, i.e. the volatility at which
+///`european_call_option_price` equals `call_price`. A price on the lower no-arbitrage bound has zero implied volatility.
+///# Panics
+///Panics if one of the parameters is negative, if `time_to_expiry` is zero, or if the price violates the no-arbitrage bounds
+///`max(S*exp(-q*T)-K*exp(-r*T), 0) <= C < S*exp(-q*T)`.
+pub fn call_implied_volatility(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, divident_rate: f64, call_price: f64) ->f64{
+    try_call_implied_volatility(spot, strike, short_rate_of_interest, time_to_expiry, divident_rate, call_price).unwrap_or_else(|e| panic!("{}", e))
+}
+
+///returns the Black-Scholes implied volatility of a european put option with price `put_price`, i.e. the volatility at which
+///`european_put_option_price` equals `put_price`. A price on the lower no-arbitrage bound has zero implied volatility.
+///# Panics
+///Panics if one of the parameters is negative, if `time_to_expiry` is zero, or if the price violates the no-arbitrage bounds
+///`max(K*exp(-r*T)-S*exp(-q*T), 0) <= P < K*exp(-r*T)`.
+pub fn put_implied_volatility(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, divident_rate: f64, put_price: f64) ->f64{
+    try_put_implied_volatility(spot, strike, short_rate_of_interest, time_to_expiry, divident_rate, put_price).unwrap_or_else(|e| panic!("{}", e))
+}
+
+///returns the strike of the european call option with delta `delta` in the convention `convention`, i.e. the inverse of
+///`call_delta_with_convention` in the strike. A premium adjusted call delta is not monotonic in the strike, and the strike above
+///the one of the largest premium adjusted delta is returned, as is customary for FX options.
+///# Panics
+///Panics if one of the parameters is negative, if `time_to_expiry` or `volatility` is zero, or if no call has the delta `delta`.
+pub fn call_strike_from_delta(spot: f64, delta: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64,
+    convention: DeltaConvention) ->f64{
+    try_call_strike_from_delta(spot, delta, short_rate_of_interest, time_to_expiry, volatility, divident_rate, convention).unwrap_or_else(|e| panic!("{}", e))
+}
+
+///returns the strike of the european put option with delta `delta` (which is negative) in the convention `convention`, i.e.
+///the inverse of `put_delta_with_convention` in the strike.
+///# Panics
+///Panics if one of the parameters is negative, if `time_to_expiry` or `volatility` is zero, or if no put has the delta `delta`.
+pub fn put_strike_from_delta(spot: f64, delta: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64,
+    convention: DeltaConvention) ->f64{
+    try_put_strike_from_delta(spot, delta, short_rate_of_interest, time_to_expiry, volatility, divident_rate, convention).unwrap_or_else(|e| panic!("{}", e))
+}
+
+///Returns the log forward moneyness `ln(K/F)` of the option with delta `delta`, given the standard deviation `deviation` of the
+///log price and `exp(-q*T)`, with `sign` 1 for a call and -1 for a put.
+fn log_moneyness_from_delta(delta: f64, deviation: f64, divident_discount: f64, convention: DeltaConvention, sign: f64)->Result<f64, PricingError>{
+    // The deltas in the spot conventions are the ones in the forward conventions discounted by the divident rate.
+    let forward_delta = match convention {
+        DeltaConvention::Spot | DeltaConvention::SpotPremiumAdjusted => delta/divident_discount,
+        _ => delta,
+    };
+    let probability = sign*forward_delta;
+    let premium_adjusted = matches!(convention, DeltaConvention::SpotPremiumAdjusted | DeltaConvention::ForwardPremiumAdjusted);
+    if probability.is_nan() || probability <= 0.0 || (probability >= 1.0 && !(premium_adjusted && sign < 0.0)) {
+        return Err(PricingError::InvalidMarketData);
+    }
+    if convention == DeltaConvention::Driftless {
+        return Ok(-sign*deviation*utils::inverse_cumulative_normal_function(probability));
+    }
+    // The log moneyness at which the forward delta `N(sign*d1)` has the given value.
+    let from_d1 = |probability: f64| -sign*deviation*utils::inverse_cumulative_normal_function(probability)+0.5*deviation*deviation;
+    if !premium_adjusted {
+        return Ok(from_d1(probability));
+    }
+    let adjusted_delta = |x: f64| sign*x.exp()*utils::cumulative_normal_function(sign*(-x/deviation-0.5*deviation));
+    let excess = |x: f64| adjusted_delta(x)-forward_delta;
+    let tolerance = 1e-12;
+    if sign > 0.0 {
+        // The premium adjusted call delta is largest where `deviation*N(d2) = n(d2)`, and decreases above that strike.
+        let d2 = roots::brent(&|d: f64| deviation*utils::cumulative_normal_function(d)-utils::normal_probability_density_function(d),
+            -deviation-5.0, 10.0, tolerance)?;
+        let lowest = -deviation*(d2+0.5*deviation);
+        if excess(lowest) < 0.0 {
+            return Err(PricingError::InvalidMarketData);
+        }
+        // The premium adjusted delta is below the forward delta, so the strike is below the one of the unadjusted delta.
+        roots::brent(&excess, lowest, f64::max(from_d1(probability), lowest), tolerance)
+    }
+    else {
+        let highest = if probability < 1.0 { from_d1(probability) } else { deviation };
+        let mut lowest = highest-1.0;
+        while excess(lowest) < 0.0 {
+            lowest -= 2.0*(highest-lowest);
+        }
+        roots::brent(&excess, lowest, highest, tolerance)
+    }
+}
+
+///A price or a greek of a european option, as a function of the spot, the strike, the short rate of interest, the time to
+///expiry, the volatility and the divident rate.
+type VanillaFormula = fn(f64, f64, f64, f64, f64, f64)->f64;
+
+///Returns the volatility at which `price` equals `option_price`, with Newton's method safeguarded by bisection. The price must
+///lie in `[lower_bound, upper_bound)`, the range of `price` over all volatilities.
+#[allow(clippy::too_many_arguments)]
+fn implied_volatility(price: VanillaFormula, vega: VanillaFormula, spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64,
+    divident_rate: f64, option_price: f64, lower_bound: f64, upper_bound: f64)->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, divident_rate, option_price])?;
+    if time_to_expiry == 0.0 {
+        return Err(PricingError::NegativeInput);
+    }
+    if option_price < lower_bound || option_price >= upper_bound {
+        return Err(PricingError::InvalidMarketData);
+    }
+    if option_price == lower_bound {
+        return Ok(0.0);
+    }
+    let excess = |volatility: f64| price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)-option_price;
+    let mut high = 1.0;
+    while excess(high) < 0.0 {
+        high *= 2.0;
+        if high > 1e4 {
+            return Err(PricingError::NoConvergence);
+        }
+    }
+    roots::safeguarded_newton(&excess, &|volatility: f64| vega(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate),
+        0.0, high, 1e-12)
+}
+
+///A Black-Scholes calculator for the european call and put options with given parameters. The terms shared by the prices and
+///greeks (`d1`, `d2`, the discount factors and the normal distribution values) are computed once, in `new`, so computing the
+///price and several greeks costs little more than computing the price alone. To compute one price or greek for many options,
+///use the `_batch` functions instead.
+///
+/// # Examples
+///
+/// ```
+/// use derivative_pricer::raw_formulas::{call_delta, european_call_option_price, BsCalculator};
+/// let calculator = BsCalculator::new(101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+/// assert!((calculator.call_price()-european_call_option_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs() < 1e-12);
+/// assert!((calculator.call_delta()-call_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs() < 1e-12);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BsCalculator{
+    spot: f64,
+    strike: f64,
+    short_rate_of_interest: f64,
+    time_to_expiry: f64,
+    volatility: f64,
+    divident_rate: f64,
+    d1: f64,
+    d2: f64,
+    root_of_time: f64,
+    discount_factor: f64,
+    divident_discount_factor: f64,
+    cumulative_normal_d1: f64,
+    cumulative_normal_d2: f64,
+    cumulative_normal_minus_d1: f64,
+    cumulative_normal_minus_d2: f64,
+    density_d1: f64,
+}
+
+impl BsCalculator {
+    ///Returns a calculator for the options with the given parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the parameters (except the short rate of interest) is negative.
+    pub fn new(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)->BsCalculator{
+        if spot < 0.0 || strike < 0.0 || time_to_expiry < 0.0 || volatility < 0.0 || divident_rate < 0.0 {
+            panic!("One of the parameters is negative")
+        }
+        let root_of_time = time_to_expiry.sqrt();
+        let (d1, d2) = d1_d2(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate);
+        BsCalculator{
+            spot,
+            strike,
+            short_rate_of_interest,
+            time_to_expiry,
+            volatility,
+            divident_rate,
+            d1,
+            d2,
+            root_of_time,
+            discount_factor: (-short_rate_of_interest*time_to_expiry).exp(),
+            divident_discount_factor: (-divident_rate*time_to_expiry).exp(),
+            cumulative_normal_d1: utils::cumulative_normal_function(d1),
+            cumulative_normal_d2: utils::cumulative_normal_function(d2),
+            cumulative_normal_minus_d1: utils::cumulative_normal_function(-d1),
+            cumulative_normal_minus_d2: utils::cumulative_normal_function(-d2),
+            density_d1: utils::normal_probability_density_function(d1),
+        }
+    }
+
+    ///Same as `new`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_new(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)
+        ->Result<BsCalculator, PricingError>{
+        check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+        Ok(BsCalculator::new(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+    }
+
+    ///Returns `d1`. Without volatility or time to expiry it is infinite, with the sign of the log moneyness of the forward, or
+    ///zero at the money forward.
+    pub fn d1(&self)->f64{
+        self.d1
+    }
+
+    ///Returns `d2`, with the same limits as `d1`.
+    pub fn d2(&self)->f64{
+        self.d2
+    }
+
+    ///Returns the price of the call option.
+    pub fn call_price(&self)->f64{
+        if self.volatility*self.root_of_time == 0.0 {
+            return f64::max(self.spot*self.divident_discount_factor-self.strike*self.discount_factor, 0.0);
+        }
+        self.spot*self.divident_discount_factor*self.cumulative_normal_d1-self.strike*self.discount_factor*self.cumulative_normal_d2
+    }
+
+    ///Returns the price of the put option.
+    pub fn put_price(&self)->f64{
+        if self.volatility*self.root_of_time == 0.0 {
+            return f64::max(self.strike*self.discount_factor-self.spot*self.divident_discount_factor, 0.0);
+        }
+        self.strike*self.discount_factor*self.cumulative_normal_minus_d2-self.spot*self.divident_discount_factor*self.cumulative_normal_minus_d1
+    }
+
+    ///Returns the delta of the call option.
+    pub fn call_delta(&self)->f64{
+        self.cumulative_normal_d1*self.divident_discount_factor
+    }
+
+    ///Returns the delta of the put option.
+    pub fn put_delta(&self)->f64{
+        -self.cumulative_normal_minus_d1*self.divident_discount_factor
+    }
+
+    ///Returns the gamma of the call and of the put option.
+    pub fn gamma(&self)->f64{
+        density_over_deviation(self.d1, self.volatility*self.root_of_time)*self.divident_discount_factor/self.spot
+    }
+
+    ///Returns the vega of the call and of the put option.
+    pub fn vega(&self)->f64{
+        self.density_d1*self.spot*self.root_of_time*self.divident_discount_factor
+    }
+
+    ///Returns the theta of the call option, as in `call_theta`.
+    pub fn call_theta(&self)->f64{
+        (self.divident_rate*self.spot*self.cumulative_normal_d1-self.time_decay())*self.divident_discount_factor-
+            self.short_rate_of_interest*self.strike*self.cumulative_normal_d2*self.discount_factor
+    }
+
+    ///Returns the theta of the put option, as in `put_theta`.
+    pub fn put_theta(&self)->f64{
+        (-self.divident_rate*self.spot*self.cumulative_normal_minus_d1-self.time_decay())*self.divident_discount_factor+
+            self.short_rate_of_interest*self.strike*self.cumulative_normal_minus_d2*self.discount_factor
+    }
+
+    ///Returns the rho of the call option.
+    pub fn call_rho(&self)->f64{
+        self.strike*self.time_to_expiry*self.cumulative_normal_d2*self.discount_factor
+    }
+
+    ///Returns the rho of the put option.
+    pub fn put_rho(&self)->f64{
+        -self.strike*self.time_to_expiry*self.cumulative_normal_minus_d2*self.discount_factor
+    }
+
+    ///Returns the price and first order greeks of the call option.
+    pub fn call_greeks(&self)->Greeks{
+        Greeks{
+            price: self.call_price(),
+            delta: self.call_delta(),
+            gamma: self.gamma(),
+            vega: self.vega(),
+            theta: self.call_theta(),
+            rho: self.call_rho(),
+        }
+    }
+
+    ///Returns the price and first order greeks of the put option.
+    pub fn put_greeks(&self)->Greeks{
+        Greeks{
+            price: self.put_price(),
+            delta: self.put_delta(),
+            gamma: self.gamma(),
+            vega: self.vega(),
+            theta: self.put_theta(),
+            rho: self.put_rho(),
+        }
+    }
+
+    ///Returns the price and first order greeks of the cash-or-nothing call option, which pays 1 if the stock is above the strike
+    ///at expiry.
+    pub fn digital_call_greeks(&self)->Greeks{
+        let deviation = self.volatility*self.root_of_time;
+        let price = self.discount_factor*self.cumulative_normal_d2;
+        let delta = self.discount_factor*density_over_deviation(self.d2, deviation)/self.spot;
+        let density_d2 = utils::normal_probability_density_function(self.d2);
+        let (gamma, vega, theta) = if deviation == 0.0 {
+            // At the money forward, d1 tends to deviation/2 and d2 to -deviation/2.
+            let coefficient = 0.25*self.volatility*self.volatility-(self.short_rate_of_interest-self.divident_rate);
+            if self.d2 != 0.0 {
+                (0.0, 0.0, self.short_rate_of_interest*price)
+            }
+            else {
+                let theta = if coefficient == 0.0 { self.short_rate_of_interest*price } else { coefficient.signum()*f64::INFINITY };
+                (f64::NEG_INFINITY, -0.5*self.discount_factor*density_d2*self.root_of_time, theta)
+            }
+        }
+        else {
+            let drift_of_d2 = (self.short_rate_of_interest-self.divident_rate)/deviation-0.5*self.d1/self.time_to_expiry;
+            (-self.discount_factor*density_d2*self.d1/(self.spot*self.spot*deviation*deviation),
+                -self.discount_factor*density_d2*self.d1/self.volatility,
+                self.short_rate_of_interest*price-self.discount_factor*density_d2*drift_of_d2)
+        };
+        Greeks{
+            price,
+            delta,
+            gamma,
+            vega,
+            theta,
+            rho: if self.time_to_expiry == 0.0 { 0.0 } else { self.time_to_expiry*(self.spot*delta-price) },
+        }
+    }
+
+    ///Returns the price and first order greeks of the cash-or-nothing put option, which pays 1 if the stock is below the strike
+    ///at expiry.
+    pub fn digital_put_greeks(&self)->Greeks{
+        let call = self.digital_call_greeks();
+        Greeks{
+            price: self.discount_factor-call.price,
+            delta: -call.delta,
+            gamma: -call.gamma,
+            vega: -call.vega,
+            theta: self.short_rate_of_interest*self.discount_factor-call.theta,
+            rho: -self.time_to_expiry*self.discount_factor-call.rho,
+        }
+    }
+
+    ///Returns the part of the theta due to the passage of time, `S*n(d1)*sigma/(2*sqrt(T))`, without the divident discount.
+    fn time_decay(&self)->f64{
+        time_decay(self.spot, self.d1, self.time_to_expiry, self.volatility)
+    }
+}
+
+///Returns the price, delta, gamma, vega, theta and rho of a european call option, sharing `d1`, `d2`, the normal distribution terms
+///and the discount factors between them. The greeks are the same as those of `call_delta`, `call_gamma`, `call_vega`, `call_theta`
+///and `call_rho`.
+///
+/// # Panics
+///
+/// Panics if one of the parameters (except the short rate of interest) is negative.
+pub fn call_greeks(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Greeks{
+    BsCalculator::new(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate).call_greeks()
+}
+
+///Returns the price, delta, gamma, vega, theta and rho of a european put option in one pass, as in `call_greeks`.
+///
+/// # Panics
+///
+/// Panics if one of the parameters (except the short rate of interest) is negative.
+pub fn put_greeks(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Greeks{
+    BsCalculator::new(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate).put_greeks()
+}
+
+/// The number of options evaluated together by the batch functions. The arithmetic on a chunk of options is done on fixed size
+/// arrays, so that the compiler can vectorize it.
+const BATCH_LANES: usize = 8;
+
+/// The terms of the Black-Scholes formulas for a chunk of options which every batch function needs. The other terms (the discount
+/// factor, the normal distribution values) are computed by the formula of each batch function, so that only the terms its output
+/// depends on are computed.
+struct BatchTerms{
+    d1: [f64; BATCH_LANES],
+    d2: [f64; BATCH_LANES],
+    root_of_time: [f64; BATCH_LANES],
+    divident_discount_factor: [f64; BATCH_LANES],
+}
+
+/// Evaluates `formula` on every option of a batch, writing the results to `output`. `formula` gets the terms of the chunk, the
+/// position of the option in the chunk, and its index in the batch.
+#[allow(clippy::needless_range_loop)]
+#[allow(clippy::too_many_arguments)]
+fn evaluate_batch<F>(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64], volatility: &[f64],
+    divident_rate: &[f64], output: &mut [f64], formula: F)
+where F: Fn(&BatchTerms, usize, usize)->f64{
+    let mut terms = BatchTerms{
+        d1: [0.0; BATCH_LANES],
+        d2: [0.0; BATCH_LANES],
+        root_of_time: [0.0; BATCH_LANES],
+        divident_discount_factor: [0.0; BATCH_LANES],
+    };
+    let mut log_moneyness = [0.0; BATCH_LANES];
+    for (chunk_index, chunk) in output.chunks_mut(BATCH_LANES).enumerate() {
+        let start = chunk_index*BATCH_LANES;
+        let lanes = chunk.len();
+        for lane in 0..lanes {
+            let i = start+lane;
+            log_moneyness[lane] = (spot[i]/strike[i]).ln();
+            terms.root_of_time[lane] = time_to_expiry[i].sqrt();
+            terms.divident_discount_factor[lane] = (-divident_rate[i]*time_to_expiry[i]).exp();
+        }
+        for lane in 0..lanes {
+            let i = start+lane;
+            let deviation = volatility[i]*terms.root_of_time[lane];
+            if deviation == 0.0 {
+                (terms.d1[lane], terms.d2[lane]) = d1_d2(spot[i], strike[i], short_rate_of_interest[i], time_to_expiry[i], volatility[i], divident_rate[i]);
+                continue;
+            }
+            terms.d1[lane] = (log_moneyness[lane]+(short_rate_of_interest[i]-divident_rate[i]+0.5*volatility[i]*volatility[i])*time_to_expiry[i])
+                /deviation;
+            terms.d2[lane] = terms.d1[lane]-deviation;
+        }
+        for (lane, value) in chunk.iter_mut().enumerate() {
+            *value = formula(&terms, lane, start+lane);
+        }
+    }
+}
+
+/// Returns `PricingError::InvalidMarketData` if the slices of a batch have different lengths, and `PricingError::NegativeInput`
+/// if one of the parameters (except the short rates of interest) is negative or NaN.
+fn check_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64], volatility: &[f64],
+    divident_rate: &[f64], output: &[f64])->Result<(), PricingError>{
+    let n = output.len();
+    if [spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate].iter().any(|x| x.len() != n) {
+        return Err(PricingError::InvalidMarketData);
+    }
+    for values in [spot, strike, time_to_expiry, volatility, divident_rate] {
+        check_non_negative(values)?;
+    }
+    Ok(())
+}
+
+///Computes the prices of a batch of european call options, the i-th option having the parameters at index i of the slices,
+///and writes them to `prices`. Gives the same results as `european_call_option_price`, up to rounding, with less overhead per option.
+///Each batch function computes only the terms its output depends on, so e.g. `call_delta_batch` evaluates one cumulative normal
+///function and one exponential per option. Measured by `cargo bench --bench batch_formulas`, the batch functions take 10 to 30
+///percent less time per option than a loop over the scalar ones.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths or if one of the parameters (except the short rates of interest) is negative.
+pub fn european_call_option_price_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], prices: &mut [f64]){
+    if let Err(error) = check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, prices) {
+        panic!("{}", error)
+    }
+    evaluate_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, prices, |terms, lane, i| {
+        let discount_factor = (-short_rate_of_interest[i]*time_to_expiry[i]).exp();
+        if volatility[i]*terms.root_of_time[lane] == 0.0 {
+            return f64::max(spot[i]*terms.divident_discount_factor[lane]-strike[i]*discount_factor, 0.0);
+        }
+        spot[i]*utils::cumulative_normal_function(terms.d1[lane])*terms.divident_discount_factor[lane]-
+            strike[i]*utils::cumulative_normal_function(terms.d2[lane])*discount_factor
+    });
+}
+
+///Computes the prices of a batch of european put options and writes them to `prices`, see `european_call_option_price_batch`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths or if one of the parameters (except the short rates of interest) is negative.
+pub fn european_put_option_price_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], prices: &mut [f64]){
+    if let Err(error) = check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, prices) {
+        panic!("{}", error)
+    }
+    evaluate_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, prices, |terms, lane, i| {
+        let discount_factor = (-short_rate_of_interest[i]*time_to_expiry[i]).exp();
+        if volatility[i]*terms.root_of_time[lane] == 0.0 {
+            return f64::max(strike[i]*discount_factor-spot[i]*terms.divident_discount_factor[lane], 0.0);
+        }
+        strike[i]*utils::cumulative_normal_function(-terms.d2[lane])*discount_factor-
+            spot[i]*utils::cumulative_normal_function(-terms.d1[lane])*terms.divident_discount_factor[lane]
+    });
+}
+
+///Computes the deltas of a batch of european call options and writes them to `deltas`, see `european_call_option_price_batch`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths or if one of the parameters (except the short rates of interest) is negative.
+pub fn call_delta_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], deltas: &mut [f64]){
+    if let Err(error) = check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, deltas) {
+        panic!("{}", error)
+    }
+    evaluate_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, deltas, |terms, lane, _| {
+        utils::cumulative_normal_function(terms.d1[lane])*terms.divident_discount_factor[lane]
+    });
+}
+
+///Computes the deltas of a batch of european put options and writes them to `deltas`, see `european_call_option_price_batch`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths or if one of the parameters (except the short rates of interest) is negative.
+pub fn put_delta_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], deltas: &mut [f64]){
+    if let Err(error) = check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, deltas) {
+        panic!("{}", error)
+    }
+    evaluate_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, deltas, |terms, lane, _| {
+        (utils::cumulative_normal_function(terms.d1[lane])-1.0)*terms.divident_discount_factor[lane]
+    });
+}
+
+///Computes the gammas of a batch of european call (or put) options and writes them to `gammas`, see
+///`european_call_option_price_batch`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths or if one of the parameters (except the short rates of interest) is negative.
+pub fn gamma_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], gammas: &mut [f64]){
+    if let Err(error) = check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, gammas) {
+        panic!("{}", error)
+    }
+    evaluate_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, gammas, |terms, lane, i| {
+        density_over_deviation(terms.d1[lane], volatility[i]*terms.root_of_time[lane])*terms.divident_discount_factor[lane]/spot[i]
+    });
+}
+
+///Computes the vegas of a batch of european call (or put) options and writes them to `vegas`, see
+///`european_call_option_price_batch`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths or if one of the parameters (except the short rates of interest) is negative.
+pub fn vega_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], vegas: &mut [f64]){
+    if let Err(error) = check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, vegas) {
+        panic!("{}", error)
+    }
+    evaluate_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, vegas, |terms, lane, i| {
+        utils::normal_probability_density_function(terms.d1[lane])*spot[i]*terms.root_of_time[lane]*terms.divident_discount_factor[lane]
+    });
+}
+
+/// Returns `PricingError::NegativeInput` if one of `values` is negative or NaN.
+fn check_non_negative(values: &[f64])->Result<(), PricingError>{
+    if values.iter().any(|x| x.is_nan() || *x < 0.0){
+        return Err(PricingError::NegativeInput);
+    }
+    Ok(())
+}
+
+///Same as `european_call_option_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_european_call_option_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(european_call_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `european_put_option_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_european_put_option_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_maturity: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_maturity.value(), volatility.value(), divident_rate.value()])?;
+    Ok(european_put_option_price(spot, strike, short_rate_of_interest, time_to_maturity, volatility, divident_rate))
+}
+
+///Same as `forward_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_forward_price<T: Real>(spot: T, short_rate_of_interest: T, time: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), time.value(), divident_rate.value()])?;
+    Ok(forward_price(spot, short_rate_of_interest, time, divident_rate))
+}
+
+///Same as `forward_price_with_borrow_cost`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_forward_price_with_borrow_cost<T: Real>(spot: T, short_rate_of_interest: T, time: T, divident_rate: T, borrow_cost: T)
+    ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), time.value(), divident_rate.value(), borrow_cost.value()])?;
+    Ok(forward_price_with_borrow_cost(spot, short_rate_of_interest, time, divident_rate, borrow_cost))
+}
+
+///Same as `european_call_option_price_with_borrow_cost`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_european_call_option_price_with_borrow_cost<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T,
+    volatility: T, divident_rate: T, borrow_cost: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value(), borrow_cost.value()])?;
+    Ok(european_call_option_price_with_borrow_cost(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, borrow_cost))
+}
+
+///Same as `european_put_option_price_with_borrow_cost`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_european_put_option_price_with_borrow_cost<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T,
+    volatility: T, divident_rate: T, borrow_cost: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value(), borrow_cost.value()])?;
+    Ok(european_put_option_price_with_borrow_cost(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, borrow_cost))
+}
+
+///Same as `digital_call_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_digital_call_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(digital_call_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `digital_put_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_digital_put_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(digital_put_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `zero_coupon_bond`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_zero_coupon_bond<T: Real>(short_rate_of_interest: T, time_to_maturity: T) ->Result<T, PricingError>{
+    check_non_negative(&[time_to_maturity.value()])?;
+    Ok(zero_coupon_bond(short_rate_of_interest, time_to_maturity))
+}
+
+///Same as `call_delta`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_delta<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_delta(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_gamma`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_gamma<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_gamma(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_vega`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_vega<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_vega(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_theta`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_theta<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_theta(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_rho`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_rho<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_rho(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_delta`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_delta<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_delta(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_gamma`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_gamma<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_gamma(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_vega`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_vega<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_vega(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_theta`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_theta<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_theta(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_rho`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_rho<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_rho(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_vanna`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_vanna<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_vanna(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_volga`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_volga<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_volga(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_charm`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_charm<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_charm(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_speed`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_speed<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_speed(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_zomma`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_zomma<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_zomma(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_color`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_color<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_color(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_vanna`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_vanna<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_vanna(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_volga`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_volga<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_volga(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_charm`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_charm<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_charm(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_speed`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_speed<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_speed(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_zomma`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_zomma<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_zomma(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_delta_with_convention`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_delta_with_convention<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T,
+    convention: DeltaConvention) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(call_delta_with_convention(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, convention))
+}
+
+///Same as `put_delta_with_convention`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_delta_with_convention<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T,
+    convention: DeltaConvention) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_delta_with_convention(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, convention))
+}
+
+///Same as `call_theta_decomposition`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_theta_decomposition(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)
+    ->Result<ThetaDecomposition, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(call_theta_decomposition(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_theta_decomposition`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_theta_decomposition(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)
+    ->Result<ThetaDecomposition, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(put_theta_decomposition(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_color`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_color<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(put_color(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_in_the_money_probability`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_in_the_money_probability(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(call_in_the_money_probability(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_in_the_money_probability`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_in_the_money_probability(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(put_in_the_money_probability(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_real_world_in_the_money_probability`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_real_world_in_the_money_probability(spot: f64, strike: f64, expected_return: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(call_real_world_in_the_money_probability(spot, strike, expected_return, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_real_world_in_the_money_probability`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_real_world_in_the_money_probability(spot: f64, strike: f64, expected_return: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(put_real_world_in_the_money_probability(spot, strike, expected_return, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_expected_payoff_given_exercise`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_expected_payoff_given_exercise(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(call_expected_payoff_given_exercise(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `put_expected_payoff_given_exercise`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_expected_payoff_given_exercise(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(put_expected_payoff_given_exercise(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `call_breakeven_spot`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_breakeven_spot(strike: f64, premium: f64, short_rate_of_interest: f64, time_to_expiry: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[strike, premium, time_to_expiry])?;
+    Ok(call_breakeven_spot(strike, premium, short_rate_of_interest, time_to_expiry))
+}
+
+///Same as `put_breakeven_spot`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_breakeven_spot(strike: f64, premium: f64, short_rate_of_interest: f64, time_to_expiry: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[strike, premium, time_to_expiry])?;
+    Ok(put_breakeven_spot(strike, premium, short_rate_of_interest, time_to_expiry))
+}
+
+///Same as `spread_call_price_kirk`, but returns `PricingError::NegativeInput` instead of panicking, and
+///`PricingError::InvalidMarketData` if the forward of the second asset plus the strike is not positive, as the approximation
+///then has no lognormal asset to exchange for.
+#[allow(clippy::too_many_arguments)]
+pub fn try_spread_call_price_kirk(spot_1: f64, spot_2: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility_1: f64,
+    volatility_2: f64, correlation: f64, divident_rate_1: f64, divident_rate_2: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot_1, spot_2, time_to_expiry, volatility_1, volatility_2, divident_rate_1, divident_rate_2])?;
+    let forward_1 = spot_1*((short_rate_of_interest-divident_rate_1)*time_to_expiry).exp();
+    let forward_2 = spot_2*((short_rate_of_interest-divident_rate_2)*time_to_expiry).exp();
+    let discount = (-short_rate_of_interest*time_to_expiry).exp();
+    if time_to_expiry==0.0{
+        return Ok(f64::max(spot_1-spot_2-strike, 0.0));
+    }
+    if forward_2+strike <= 0.0 {
+        return Err(PricingError::InvalidMarketData);
+    }
+    let weight = forward_2/(forward_2+strike);
+    let volatility = (volatility_1*volatility_1-2.0*correlation*volatility_1*volatility_2*weight+volatility_2*volatility_2*weight*weight).sqrt();
+    if volatility*time_to_expiry.sqrt() == 0.0 {
+        return Ok(discount*f64::max(forward_1-forward_2-strike, 0.0));
+    }
+    let d1 = ((forward_1/(forward_2+strike)).ln()+0.5*volatility*volatility*time_to_expiry)/(volatility*time_to_expiry.sqrt());
+    let d2 = d1-volatility*time_to_expiry.sqrt();
+    Ok(discount*(forward_1*utils::cumulative_normal_function(d1)-(forward_2+strike)*utils::cumulative_normal_function(d2)))
+}
+
+///Same as `spread_call_price_bjerksund_stensland`, but returns `PricingError::NegativeInput` instead of panicking, and
+///`PricingError::InvalidMarketData` if the forward of the second asset plus the strike is not positive, as for
+///`try_spread_call_price_kirk`.
+#[allow(clippy::too_many_arguments)]
+pub fn try_spread_call_price_bjerksund_stensland(spot_1: f64, spot_2: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility_1: f64,
+    volatility_2: f64, correlation: f64, divident_rate_1: f64, divident_rate_2: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot_1, spot_2, time_to_expiry, volatility_1, volatility_2, divident_rate_1, divident_rate_2])?;
+    let forward_1 = spot_1*((short_rate_of_interest-divident_rate_1)*time_to_expiry).exp();
+    let forward_2 = spot_2*((short_rate_of_interest-divident_rate_2)*time_to_expiry).exp();
+    let discount = (-short_rate_of_interest*time_to_expiry).exp();
+    if time_to_expiry==0.0{
+        return Ok(f64::max(spot_1-spot_2-strike, 0.0));
+    }
+    let a = forward_2+strike;
+    if a <= 0.0 {
+        return Err(PricingError::InvalidMarketData);
+    }
+    let b = forward_2/a;
+    let cross = correlation*volatility_1*volatility_2;
+    let volatility = (volatility_1*volatility_1-2.0*b*cross+b*b*volatility_2*volatility_2).sqrt();
+    let denominator = volatility*time_to_expiry.sqrt();
+    if denominator == 0.0 {
+        return Ok(discount*f64::max(forward_1-forward_2-strike, 0.0));
+    }
+    let log_moneyness = (forward_1/a).ln();
+    let d1 = (log_moneyness+(0.5*volatility_1*volatility_1-b*cross+0.5*b*b*volatility_2*volatility_2)*time_to_expiry)/denominator;
+    let d2 = (log_moneyness+(-0.5*volatility_1*volatility_1+cross+(0.5*b*b-b)*volatility_2*volatility_2)*time_to_expiry)/denominator;
+    let d3 = (log_moneyness+(-0.5*volatility_1*volatility_1+0.5*b*b*volatility_2*volatility_2)*time_to_expiry)/denominator;
+    Ok(discount*(forward_1*utils::cumulative_normal_function(d1)-forward_2*utils::cumulative_normal_function(d2)-
+        strike*utils::cumulative_normal_function(d3)))
+}
+
+///Same as `american_call_price_barone_adesi_whaley`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_american_call_price_barone_adesi_whaley(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(american_call_price_barone_adesi_whaley(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `american_call_price_roll_geske_whaley`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_american_call_price_roll_geske_whaley(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident: f64, divident_time: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident, divident_time])?;
+    Ok(american_call_price_roll_geske_whaley(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident, divident_time))
+}
+
+///Same as `american_put_price_barone_adesi_whaley`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_american_put_price_barone_adesi_whaley(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(american_put_price_barone_adesi_whaley(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `american_call_price_bjerksund_stensland`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_american_call_price_bjerksund_stensland(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(american_call_price_bjerksund_stensland(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `american_put_price_bjerksund_stensland`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_american_put_price_bjerksund_stensland(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(american_put_price_bjerksund_stensland(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `american_call_price_bjerksund_stensland_1993`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_american_call_price_bjerksund_stensland_1993(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64,
+    volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(american_call_price_bjerksund_stensland_1993(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `american_put_price_bjerksund_stensland_1993`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_american_put_price_bjerksund_stensland_1993(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64,
+    volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(american_put_price_bjerksund_stensland_1993(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `asian_call_price_turnbull_wakeman`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_asian_call_price_turnbull_wakeman(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(asian_call_price_turnbull_wakeman(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `asian_put_price_turnbull_wakeman`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_asian_put_price_turnbull_wakeman(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(asian_put_price_turnbull_wakeman(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `ladder_call_price`, but returns `PricingError::NegativeInput` instead of panicking on negative inputs.
+pub fn try_ladder_call_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64, rungs: &[f64]) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(ladder_call_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, rungs))
+}
+
+///Same as `ladder_put_price`, but returns `PricingError::NegativeInput` instead of panicking on negative inputs.
+pub fn try_ladder_put_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64, rungs: &[f64]) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(ladder_put_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, rungs))
+}
+
+///Same as `variance_swap_strike`, but returns a `PricingError` instead of panicking.
+pub fn try_variance_swap_strike(forward: f64, short_rate_of_interest: f64, time_to_expiry: f64, strikes: &[f64], call_prices: &[f64], put_prices: &[f64]) ->Result<f64, PricingError>{
+    check_non_negative(&[forward, time_to_expiry])?;
+    check_non_negative(strikes)?;
+    if strikes.len() < 2 || strikes.len() != call_prices.len() || strikes.len() != put_prices.len() || !strikes.iter().any(|k| *k <= forward) {
+        return Err(PricingError::InvalidMarketData);
+    }
+    Ok(variance_swap_strike(forward, short_rate_of_interest, time_to_expiry, strikes, call_prices, put_prices))
+}
+
+///Same as `flat_volatility_variance_swap_strike`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_flat_volatility_variance_swap_strike(volatility: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[volatility])?;
+    Ok(flat_volatility_variance_swap_strike(volatility))
+}
+
+///Same as `volatility_swap_convexity_adjustment`, but returns `PricingError::NegativeInput` instead of panicking on negative
+///parameters, and `PricingError::InvalidMarketData` on a zero variance swap strike.
+pub fn try_volatility_swap_convexity_adjustment(variance_swap_strike: f64, variance_of_realized_variance: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[variance_swap_strike, variance_of_realized_variance])?;
+    if variance_swap_strike == 0.0 {
+        return Err(PricingError::InvalidMarketData);
+    }
+    Ok(volatility_swap_convexity_adjustment(variance_swap_strike, variance_of_realized_variance))
+}
+
+///Same as `volatility_swap_strike`, but returns errors instead of panicking, as `try_volatility_swap_convexity_adjustment`.
+pub fn try_volatility_swap_strike(variance_swap_strike: f64, variance_of_realized_variance: f64) ->Result<f64, PricingError>{
+    Ok(variance_swap_strike.sqrt()-try_volatility_swap_convexity_adjustment(variance_swap_strike, variance_of_realized_variance)?)
+}
+
+///Same as `asset_or_nothing_call_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_asset_or_nothing_call_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(asset_or_nothing_call_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `asset_or_nothing_put_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_asset_or_nothing_put_price<T: Real>(spot: T, strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(asset_or_nothing_put_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `gap_call_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_gap_call_price<T: Real>(spot: T, payment_strike: T, trigger_strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), payment_strike.value(), trigger_strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(gap_call_price(spot, payment_strike, trigger_strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `gap_put_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_gap_put_price<T: Real>(spot: T, payment_strike: T, trigger_strike: T, short_rate_of_interest: T, time_to_expiry: T, volatility: T, divident_rate: T) ->Result<T, PricingError>{
+    check_non_negative(&[spot.value(), payment_strike.value(), trigger_strike.value(), time_to_expiry.value(), volatility.value(), divident_rate.value()])?;
+    Ok(gap_put_price(spot, payment_strike, trigger_strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `asset_or_nothing_call_delta`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_asset_or_nothing_call_delta(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(asset_or_nothing_call_delta(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `asset_or_nothing_put_delta`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_asset_or_nothing_put_delta(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(asset_or_nothing_put_delta(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `gap_call_delta`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_gap_call_delta(spot: f64, payment_strike: f64, trigger_strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, payment_strike, trigger_strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(gap_call_delta(spot, payment_strike, trigger_strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `gap_put_delta`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_gap_put_delta(spot: f64, payment_strike: f64, trigger_strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, payment_strike, trigger_strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(gap_put_delta(spot, payment_strike, trigger_strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `asset_or_nothing_call_greeks`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_asset_or_nothing_call_greeks(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<Greeks, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(asset_or_nothing_call_greeks(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `asset_or_nothing_put_greeks`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_asset_or_nothing_put_greeks(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<Greeks, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(asset_or_nothing_put_greeks(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `gap_call_greeks`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_gap_call_greeks(spot: f64, payment_strike: f64, trigger_strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<Greeks, PricingError>{
+    check_non_negative(&[spot, payment_strike, trigger_strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(gap_call_greeks(spot, payment_strike, trigger_strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `gap_put_greeks`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_gap_put_greeks(spot: f64, payment_strike: f64, trigger_strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<Greeks, PricingError>{
+    check_non_negative(&[spot, payment_strike, trigger_strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(gap_put_greeks(spot, payment_strike, trigger_strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `one_touch_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_one_touch_price(spot: f64, barrier: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64, pay_at_hit: bool) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, barrier, time_to_expiry, volatility, divident_rate])?;
+    Ok(one_touch_price(spot, barrier, short_rate_of_interest, time_to_expiry, volatility, divident_rate, pay_at_hit))
+}
+
+///Same as `no_touch_price`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_no_touch_price(spot: f64, barrier: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, barrier, time_to_expiry, volatility, divident_rate])?;
+    Ok(no_touch_price(spot, barrier, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `touch_probability`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_touch_probability(spot: f64, barrier: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, barrier, time_to_expiry, volatility, divident_rate])?;
+    Ok(touch_probability(spot, barrier, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `real_world_touch_probability`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_real_world_touch_probability(spot: f64, barrier: f64, expected_return: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, barrier, time_to_expiry, volatility, divident_rate])?;
+    Ok(real_world_touch_probability(spot, barrier, expected_return, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `first_passage_time_density`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_first_passage_time_density(spot: f64, barrier: f64, short_rate_of_interest: f64, time: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, barrier, time, volatility, divident_rate])?;
+    Ok(first_passage_time_density(spot, barrier, short_rate_of_interest, time, volatility, divident_rate))
+}
+
+///Same as `expected_first_passage_time`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_expected_first_passage_time(spot: f64, barrier: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, barrier, time_to_expiry, volatility, divident_rate])?;
+    Ok(expected_first_passage_time(spot, barrier, short_rate_of_interest, time_to_expiry, volatility, divident_rate))
+}
+
+///Same as `implied_divident_rate`, but returns `PricingError::NegativeInput` if one of the parameters is negative (or the time
+///to expiry is zero), and `PricingError::InvalidMarketData` if the prices violate the bounds of put-call parity.
+pub fn try_implied_divident_rate(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, call_price: f64, put_price: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, call_price, put_price])?;
+    if time_to_expiry == 0.0 {
+        return Err(PricingError::NegativeInput);
+    }
+    let dividend_discount_factor = (call_price-put_price+strike*(-short_rate_of_interest*time_to_expiry).exp())/spot;
+    if dividend_discount_factor <= 0.0 || !dividend_discount_factor.is_finite() {
+        return Err(PricingError::InvalidMarketData);
+    }
+    Ok(-dividend_discount_factor.ln()/time_to_expiry)
+}
+
+///Same as `implied_short_rate_of_interest`, but returns `PricingError::NegativeInput` if one of the parameters is negative (or the
+///time to expiry is zero), and `PricingError::InvalidMarketData` if the prices violate the bounds of put-call parity.
+pub fn try_implied_short_rate_of_interest(spot: f64, strike: f64, time_to_expiry: f64, divident_rate: f64, call_price: f64, put_price: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, strike, time_to_expiry, divident_rate, call_price, put_price])?;
+    if time_to_expiry == 0.0 {
+        return Err(PricingError::NegativeInput);
+    }
+    let discount_factor = (spot*(-divident_rate*time_to_expiry).exp()-call_price+put_price)/strike;
+    if discount_factor <= 0.0 || !discount_factor.is_finite() {
+        return Err(PricingError::InvalidMarketData);
+    }
+    Ok(-discount_factor.ln()/time_to_expiry)
+}
+
+///Same as `implied_rate_and_divident_rate`, but returns `PricingError::NegativeInput` if one of the parameters is negative (or the
+///time to expiry is zero), and `PricingError::InvalidMarketData` if the strip is too short or inconsistent.
+pub fn try_implied_rate_and_divident_rate(spot: f64, time_to_expiry: f64, strikes: &[f64], call_prices: &[f64], put_prices: &[f64]) ->Result<(f64, f64), PricingError>{
+    check_non_negative(&[spot, time_to_expiry])?;
+    check_non_negative(strikes)?;
+    if time_to_expiry == 0.0 {
+        return Err(PricingError::NegativeInput);
+    }
+    if strikes.len() < 2 || strikes.len() != call_prices.len() || strikes.len() != put_prices.len() {
+        return Err(PricingError::InvalidMarketData);
+    }
+    let n = strikes.len() as f64;
+    let differences: Vec<f64> = call_prices.iter().zip(put_prices).map(|(c, p)| c-p).collect();
+    let mean_strike = strikes.iter().sum::<f64>()/n;
+    let mean_difference = differences.iter().sum::<f64>()/n;
+    let covariance: f64 = strikes.iter().zip(&differences).map(|(k, d)| (k-mean_strike)*(d-mean_difference)).sum();
+    let variance: f64 = strikes.iter().map(|k| (k-mean_strike)*(k-mean_strike)).sum();
+    if variance == 0.0 {
+        return Err(PricingError::InvalidMarketData);
+    }
+    let discount_factor = -covariance/variance;
+    let dividend_discount_factor = (mean_difference+discount_factor*mean_strike)/spot;
+    if discount_factor <= 0.0 || dividend_discount_factor <= 0.0 || !dividend_discount_factor.is_finite() {
+        return Err(PricingError::InvalidMarketData);
+    }
+    Ok((-discount_factor.ln()/time_to_expiry, -dividend_discount_factor.ln()/time_to_expiry))
+}
+
+///Same as `call_implied_volatility`, but returns `PricingError::NegativeInput` if one of the parameters is negative (or the time
+///to expiry is zero), and `PricingError::InvalidMarketData` if the price violates the no-arbitrage bounds.
+pub fn try_call_implied_volatility(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, divident_rate: f64, call_price: f64) ->Result<f64, PricingError>{
+    let forward_spot = spot*(-divident_rate*time_to_expiry).exp();
+    let lower_bound = f64::max(forward_spot-strike*(-short_rate_of_interest*time_to_expiry).exp(), 0.0);
+    implied_volatility(european_call_option_price, call_vega, spot, strike, short_rate_of_interest, time_to_expiry, divident_rate, call_price,
+        lower_bound, forward_spot)
+}
+
+///Same as `put_implied_volatility`, but returns `PricingError::NegativeInput` if one of the parameters is negative (or the time
+///to expiry is zero), and `PricingError::InvalidMarketData` if the price violates the no-arbitrage bounds.
+pub fn try_put_implied_volatility(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, divident_rate: f64, put_price: f64) ->Result<f64, PricingError>{
+    let discounted_strike = strike*(-short_rate_of_interest*time_to_expiry).exp();
+    let lower_bound = f64::max(discounted_strike-spot*(-divident_rate*time_to_expiry).exp(), 0.0);
+    implied_volatility(european_put_option_price, put_vega, spot, strike, short_rate_of_interest, time_to_expiry, divident_rate, put_price,
+        lower_bound, discounted_strike)
+}
+
+///Same as `call_strike_from_delta`, but returns `PricingError::NegativeInput` if one of the parameters is negative (or the time
+///to expiry or the volatility is zero), and `PricingError::InvalidMarketData` if no call has the delta `delta`.
+pub fn try_call_strike_from_delta(spot: f64, delta: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64,
+    convention: DeltaConvention) ->Result<f64, PricingError>{
+    strike_from_delta(spot, delta, short_rate_of_interest, time_to_expiry, volatility, divident_rate, convention, 1.0)
+}
+
+///Same as `put_strike_from_delta`, but returns `PricingError::NegativeInput` if one of the parameters is negative (or the time
+///to expiry or the volatility is zero), and `PricingError::InvalidMarketData` if no put has the delta `delta`.
+pub fn try_put_strike_from_delta(spot: f64, delta: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64,
+    convention: DeltaConvention) ->Result<f64, PricingError>{
+    strike_from_delta(spot, delta, short_rate_of_interest, time_to_expiry, volatility, divident_rate, convention, -1.0)
+}
+
+///Returns the strike of the call (`sign` 1) or the put (`sign` -1) with delta `delta`, see `call_strike_from_delta`.
+#[allow(clippy::too_many_arguments)]
+fn strike_from_delta(spot: f64, delta: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64,
+    convention: DeltaConvention, sign: f64) ->Result<f64, PricingError>{
+    check_non_negative(&[spot, time_to_expiry, volatility, divident_rate])?;
+    if time_to_expiry == 0.0 || volatility == 0.0 {
+        return Err(PricingError::NegativeInput);
+    }
+    let log_moneyness = log_moneyness_from_delta(delta, volatility*time_to_expiry.sqrt(), (-divident_rate*time_to_expiry).exp(), convention, sign)?;
+    Ok(forward_price(spot, short_rate_of_interest, time_to_expiry, divident_rate)*log_moneyness.exp())
+}
+
+///Same as `european_call_option_price_batch`, but returns `PricingError::InvalidMarketData` if the slices have different lengths and
+///`PricingError::NegativeInput` if one of the parameters is negative, instead of panicking.
+pub fn try_european_call_option_price_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], prices: &mut [f64])->Result<(), PricingError>{
+    check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, prices)?;
+    european_call_option_price_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, prices);
+    Ok(())
+}
+
+///Same as `european_put_option_price_batch`, but returns `PricingError::InvalidMarketData` if the slices have different lengths and
+///`PricingError::NegativeInput` if one of the parameters is negative, instead of panicking.
+pub fn try_european_put_option_price_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], prices: &mut [f64])->Result<(), PricingError>{
+    check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, prices)?;
+    european_put_option_price_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, prices);
+    Ok(())
+}
+
+///Same as `call_delta_batch`, but returns `PricingError::InvalidMarketData` if the slices have different lengths and
+///`PricingError::NegativeInput` if one of the parameters is negative, instead of panicking.
+pub fn try_call_delta_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], deltas: &mut [f64])->Result<(), PricingError>{
+    check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, deltas)?;
+    call_delta_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, deltas);
+    Ok(())
+}
+
+///Same as `put_delta_batch`, but returns `PricingError::InvalidMarketData` if the slices have different lengths and
+///`PricingError::NegativeInput` if one of the parameters is negative, instead of panicking.
+pub fn try_put_delta_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], deltas: &mut [f64])->Result<(), PricingError>{
+    check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, deltas)?;
+    put_delta_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, deltas);
+    Ok(())
+}
+
+///Same as `gamma_batch`, but returns `PricingError::InvalidMarketData` if the slices have different lengths and
+///`PricingError::NegativeInput` if one of the parameters is negative, instead of panicking.
+pub fn try_gamma_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], gammas: &mut [f64])->Result<(), PricingError>{
+    check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, gammas)?;
+    gamma_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, gammas);
+    Ok(())
+}
+
+///Same as `vega_batch`, but returns `PricingError::InvalidMarketData` if the slices have different lengths and
+///`PricingError::NegativeInput` if one of the parameters is negative, instead of panicking.
+pub fn try_vega_batch(spot: &[f64], strike: &[f64], short_rate_of_interest: &[f64], time_to_expiry: &[f64],
+    volatility: &[f64], divident_rate: &[f64], vegas: &mut [f64])->Result<(), PricingError>{
+    check_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, vegas)?;
+    vega_batch(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate, vegas);
+    Ok(())
+}
+
+///Same as `call_greeks`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_call_greeks(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<Greeks, PricingError>{
+    Ok(BsCalculator::try_new(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)?.call_greeks())
+}
+
+///Same as `put_greeks`, but returns `PricingError::NegativeInput` instead of panicking.
+pub fn try_put_greeks(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64) ->Result<Greeks, PricingError>{
+    Ok(BsCalculator::try_new(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)?.put_greeks())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward_times_zero_cupon_is_spot(){
+        assert!((32.333_f64-forward_price(32.333, 4.657, 3.2345, 0.0)*zero_coupon_bond(4.657, 3.2345))
+            .abs()<1e-14);
+    }
+
+    #[test]
+    fn call_put_parity_test_1(){
+        assert!((zero_coupon_bond(3.33_f64, 0.15)*10.111-european_put_option_price(15.45, 10.111, 3.33, 0.15, 6.75, 0.0)-
+            (15.45-european_call_option_price(15.45, 10.111, 3.33, 0.15, 6.75, 0.0))).abs()<1e-14);
+    }
+
+    #[test]
+    fn call_put_parity_test_2(){
+        assert!((5.0_f64-4.0*zero_coupon_bond(2.0, 2.0)-
+            (european_call_option_price(5.0, 4.0, 2.0, 2.0, 1.0, 0.0)-european_put_option_price(5.0, 4.0, 2.0, 2.0, 1.0, 0.0))).abs()<1e-14);
+    }
+
+    #[test]
+    fn digital_put_plus_digital_call_equals_zero_cupon(){
+
+        assert!((zero_coupon_bond(3.784_f64, 5.7)-digital_call_price(45.34, 43.234, 3.784, 5.7, 11.8563, 1.0)-digital_put_price(45.34, 43.234, 3.784, 5.7, 11.8563, 1.0))
+            .abs()<1e-14);
+    }
+
+    #[test]
+    fn call_price_test(){
+        assert!((european_call_option_price(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-2.36031190).abs()<1e-6)
+    }
+
+    #[test]
+    fn call_delta_test(){
+        assert!((call_delta(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-0.23812531).abs()<1e-6)
+    }
+
+    #[test]
+    fn call_gamma_test(){
+        assert!((call_gamma(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-0.01671937).abs()<1e-6)
+    }
+
+    #[test]
+    fn call_vega_test(){
+        assert!((call_vega(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-36.72893205).abs()<1e-6)
+    }
+
+    #[test]
+    fn call_rho_test(){
+        assert!((call_rho(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-31.08530732).abs()<1e-6)
+    }
+
+    #[test]
+    fn call_theta_test(){
+        assert!((call_theta(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)+2.72505217).abs()<1e-6)
+    }
+
+    #[test]
+    fn put_price_test(){
+        assert!((european_put_option_price(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-16.69385816).abs()<1e-6)
+    }
+
+    #[test]
+    fn put_delta_test(){
+        println!("{}",put_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03));
+        assert!((put_delta(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)+0.71988186).abs()<1e-6)
+    }
+
+    #[test]
+    fn put_gamma_test(){
+        assert!((put_gamma(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-0.01671937).abs()<1e-6)
+    }
+
+    #[test]
+    fn put_vega_test(){
+        println!("put vega {}",put_vega(101.2, 123.0, 0.07, 1.43, 0.15, 0.03));
+        assert!((put_vega(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-36.72893205).abs()<1e-6)
+    }
+
+    #[test]
+    fn put_rho_test(){
+        assert!((put_rho(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)+128.05063175).abs()<1e-6)
+    }
+
+    #[test]
+    fn put_theta_test(){
+        assert!((put_theta(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-2.15630915).abs()<1e-6)
+    }
+
+    #[test]
+    fn delta_convention_test(){
+        let (spot, strike, r, t, vol, q) = (1.3_f64, 1.35, 0.03, 0.5, 0.12, 0.01);
+        let call = |convention| call_delta_with_convention(spot, strike, r, t, vol, q, convention);
+        let put = |convention| put_delta_with_convention(spot, strike, r, t, vol, q, convention);
+        assert_eq!(call(DeltaConvention::Spot), call_delta(spot, strike, r, t, vol, q));
+        assert_eq!(put(DeltaConvention::Spot), put_delta(spot, strike, r, t, vol, q));
+        assert!((call(DeltaConvention::Forward)-put(DeltaConvention::Forward)-1.0).abs()<1e-14);
+        assert!((call(DeltaConvention::Spot)-call(DeltaConvention::Forward)*(-q*t).exp()).abs()<1e-14);
+        // The premium adjusted deltas are the deltas less the premium in units of the spot or of the forward.
+        let premium = european_call_option_price(spot, strike, r, t, vol, q);
+        assert!((call(DeltaConvention::SpotPremiumAdjusted)-call(DeltaConvention::Spot)+premium/spot).abs()<1e-14);
+        let forward = forward_price(spot, r, t, q);
+        let put_premium = european_put_option_price(spot, strike, r, t, vol, q);
+        assert!((put(DeltaConvention::ForwardPremiumAdjusted)-put(DeltaConvention::Forward)+put_premium*(r*t).exp()/forward).abs()<1e-14);
+        assert!((call(DeltaConvention::Driftless)-utils::cumulative_normal_function((forward/strike).ln()/(vol*t.sqrt()))).abs()<1e-14);
+        assert_eq!(call_delta_with_convention(spot, strike, r, t, 0.0, q, DeltaConvention::Driftless), 0.0);
+        assert!(try_put_delta_with_convention(spot, strike, r, t, -vol, q, DeltaConvention::Forward).is_err());
+    }
+
+    #[test]
+    fn expired_delta_convention_test(){
+        // At expiry the deltas are digital: a call in the money hedges one unit less the strike in units of the spot when premium
+        // adjusted, a put out of the money nothing, and an option at the money half a unit.
+        let (r, q) = (0.03, 0.01);
+        for convention in [DeltaConvention::Spot, DeltaConvention::Forward, DeltaConvention::Driftless]{
+            assert_eq!(call_delta_with_convention(1.4, 1.35, r, 0.0, 0.12, q, convention), 1.0);
+            assert_eq!(put_delta_with_convention(1.4, 1.35, r, 0.0, 0.12, q, convention), 0.0);
+            assert_eq!(call_delta_with_convention(1.35, 1.35, r, 0.0, 0.12, q, convention), 0.5);
+        }
+        for convention in [DeltaConvention::SpotPremiumAdjusted, DeltaConvention::ForwardPremiumAdjusted]{
+            assert!((call_delta_with_convention(1.4, 1.35, r, 0.0, 0.12, q, convention)-1.35_f64/1.4).abs()<1e-14);
+            assert_eq!(put_delta_with_convention(1.4, 1.35, r, 0.0, 0.12, q, convention), 0.0);
+        }
+        assert_eq!(try_call_delta_with_convention(1.4, 1.35, r, -0.5, 0.12, q, DeltaConvention::Spot), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    #[should_panic(expected = "One of the parameters is negative")]
+    fn negative_delta_convention_test(){
+        call_delta_with_convention(1.3, -1.35, 0.03, 0.5, 0.12, 0.01, DeltaConvention::ForwardPremiumAdjusted);
+    }
+
+    #[test]
+    fn strike_from_delta_test(){
+        let (spot, r, t, vol, q) = (1.3, 0.03, 0.5, 0.12, 0.01);
+        for convention in [DeltaConvention::Spot, DeltaConvention::Forward, DeltaConvention::SpotPremiumAdjusted,
+            DeltaConvention::ForwardPremiumAdjusted, DeltaConvention::Driftless] {
+            for delta in [0.1, 0.25, 0.5] {
+                let call_strike = call_strike_from_delta(spot, delta, r, t, vol, q, convention);
+                assert!((call_delta_with_convention(spot, call_strike, r, t, vol, q, convention)-delta).abs()<1e-8);
+                let put_strike = put_strike_from_delta(spot, -delta, r, t, vol, q, convention);
+                assert!((put_delta_with_convention(spot, put_strike, r, t, vol, q, convention)+delta).abs()<1e-8);
+            }
+        }
+        // The premium adjusted call delta is bounded, so a large one has no strike.
+        assert_eq!(try_call_strike_from_delta(spot, 0.99, r, t, vol, q, DeltaConvention::ForwardPremiumAdjusted), Err(PricingError::InvalidMarketData));
+        assert_eq!(try_put_strike_from_delta(spot, 0.25, r, t, vol, q, DeltaConvention::Spot), Err(PricingError::InvalidMarketData));
+        assert_eq!(try_call_strike_from_delta(spot, 0.25, r, 0.0, vol, q, DeltaConvention::Spot), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn theta_decomposition_test(){
+        let call = call_theta_decomposition(101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((call.total()-call_theta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+        let put = put_theta_decomposition(101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((put.total()-put_theta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+        assert_eq!(call.time_decay, put.time_decay);
+        // Without rates and dividents all the theta is time decay, and without volatility all of it is carry.
+        assert_eq!(call_theta_decomposition(101.2, 123.0, 0.0, 1.43, 0.15, 0.0).carry, 0.0);
+        assert_eq!(put_theta_decomposition(101.2, 123.0, 0.07, 1.43, 0.0, 0.03).time_decay, 0.0);
+        let per_day = call.per_day(ThetaConvention::TradingDay);
+        assert!((per_day.total()-call_theta_per_day(101.2, 123.0, 0.07, 1.43, 0.15, 0.03, ThetaConvention::TradingDay)).abs()<1e-14);
+        assert_eq!(put_theta_per_day(101.2, 123.0, 0.07, 1.43, 0.15, 0.03, ThetaConvention::DaysPerYear(365.0)),
+            put_theta_per_day(101.2, 123.0, 0.07, 1.43, 0.15, 0.03, ThetaConvention::CalendarDay));
+        assert!(try_put_theta_decomposition(101.2, 123.0, 0.07, -1.43, 0.15, 0.03).is_err());
+    }
+
+    #[test]
+    fn expired_theta_decomposition_test(){
+        // At expiry an option out of the money has no theta, one in the money only carries, and one at the money decays infinitely fast.
+        assert_eq!(call_theta_decomposition(101.2, 123.0, 0.07, 0.0, 0.15, 0.03), ThetaDecomposition{time_decay: 0.0, carry: 0.0});
+        let put = put_theta_decomposition(101.2, 123.0, 0.07, 0.0, 0.15, 0.03);
+        assert_eq!(put.time_decay, 0.0);
+        assert!((put.carry-(0.07*123.0-0.03*101.2)).abs()<1e-12);
+        assert!((put.per_day(ThetaConvention::TradingDay).carry-put.carry/252.0).abs()<1e-15);
+        assert_eq!(call_theta_decomposition(123.0, 123.0, 0.07, 0.0, 0.15, 0.03).time_decay, f64::NEG_INFINITY);
+        assert_eq!(try_call_theta_decomposition(-101.2, 123.0, 0.07, 1.43, 0.15, 0.03), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    #[should_panic(expected = "One of the parameters is negative")]
+    fn negative_theta_decomposition_test(){
+        put_theta_decomposition(101.2, 123.0, 0.07, 1.43, -0.15, 0.03);
+    }
+
+    #[test]
+    fn call_vanna_test(){
+        let h = 1e-6;
+        let fd = (call_delta(101.2, 123.0, 0.07, 1.43, 0.15+h, 0.03)-call_delta(101.2, 123.0, 0.07, 1.43, 0.15-h, 0.03))/(2.0*h);
+        assert!((call_vanna(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-fd).abs()<1e-6)
+    }
+
+    #[test]
+    fn call_volga_test(){
+        let h = 1e-6;
+        let fd = (call_vega(101.2, 123.0, 0.07, 1.43, 0.15+h, 0.03)-call_vega(101.2, 123.0, 0.07, 1.43, 0.15-h, 0.03))/(2.0*h);
+        assert!((call_volga(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-fd).abs()<1e-5)
+    }
+
+    #[test]
+    fn charm_test(){
+        let h = 1e-6;
+        let fd = -(call_delta(101.2, 123.0, 0.07, 1.43+h, 0.15, 0.03)-call_delta(101.2, 123.0, 0.07, 1.43-h, 0.15, 0.03))/(2.0*h);
+        assert!((call_charm(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-fd).abs()<1e-6);
+        let fd = -(put_delta(101.2, 123.0, 0.07, 1.43+h, 0.15, 0.03)-put_delta(101.2, 123.0, 0.07, 1.43-h, 0.15, 0.03))/(2.0*h);
+        assert!((put_charm(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-fd).abs()<1e-6)
+    }
+
+    #[test]
+    fn call_speed_test(){
+        let h = 1e-4;
+        let fd = (call_gamma(101.2+h, 123.0, 0.07, 1.43, 0.15, 0.03)-call_gamma(101.2-h, 123.0, 0.07, 1.43, 0.15, 0.03))/(2.0*h);
+        assert!((call_speed(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-fd).abs()<1e-8)
+    }
+
+    #[test]
+    fn call_zomma_test(){
+        let h = 1e-6;
+        let fd = (call_gamma(101.2, 123.0, 0.07, 1.43, 0.15+h, 0.03)-call_gamma(101.2, 123.0, 0.07, 1.43, 0.15-h, 0.03))/(2.0*h);
+        assert!((call_zomma(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-fd).abs()<1e-7)
+    }
+
+    #[test]
+    fn call_color_test(){
+        let h = 1e-6;
+        let fd = -(call_gamma(101.2, 123.0, 0.07, 1.43+h, 0.15, 0.03)-call_gamma(101.2, 123.0, 0.07, 1.43-h, 0.15, 0.03))/(2.0*h);
+        assert!((call_color(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)-fd).abs()<1e-8)
+    }
+
+    #[test]
+    fn exercise_metrics_test(){
+        let (spot, strike, r, t, vol, q) = (101.2, 110.0, 0.05, 0.75, 0.25, 0.02);
+        let growth = (r*t).exp();
+        let call_probability = call_in_the_money_probability(spot, strike, r, t, vol, q);
+        assert!((call_probability-growth*digital_call_price(spot, strike, r, t, vol, q)).abs()<1e-14);
+        assert!((call_probability+put_in_the_money_probability(spot, strike, r, t, vol, q)-1.0).abs()<1e-14);
+        // The discounted expected payoff given exercise times the probability of exercise is the price.
+        let call = call_expected_payoff_given_exercise(spot, strike, r, t, vol, q)*call_probability/growth;
+        assert!((call-european_call_option_price(spot, strike, r, t, vol, q)).abs()<1e-10);
+        let put = put_expected_payoff_given_exercise(spot, strike, r, t, vol, q)*put_in_the_money_probability(spot, strike, r, t, vol, q)/growth;
+        assert!((put-european_put_option_price(spot, strike, r, t, vol, q)).abs()<1e-10);
+        // A higher expected return makes the call more likely to finish in the money.
+        assert!(call_real_world_in_the_money_probability(spot, strike, 0.1, t, vol, q) > call_probability);
+        assert!(put_real_world_in_the_money_probability(spot, strike, 0.1, t, vol, q) < 1.0-call_probability);
+        assert!(call_expected_payoff_given_exercise(spot, strike, r, t, 0.0, q).is_nan());
+        let premium = european_call_option_price(spot, strike, r, t, vol, q);
+        assert!((call_breakeven_spot(strike, premium, r, t)-strike-premium*growth).abs()<1e-12);
+        assert_eq!(put_breakeven_spot(strike, 2.0, 0.0, t), strike-2.0);
+        assert!(try_put_breakeven_spot(strike, -2.0, r, t).is_err());
+        assert!(try_call_in_the_money_probability(spot, strike, r, -t, vol, q).is_err());
+    }
+
+    #[test]
+    fn degenerate_exercise_metrics_test(){
+        // At expiry the option is in the money with certainty or not at all.
+        assert_eq!(call_in_the_money_probability(120.0, 110.0, 0.05, 0.0, 0.25, 0.02), 1.0);
+        assert_eq!(put_in_the_money_probability(120.0, 110.0, 0.05, 0.0, 0.25, 0.02), 0.0);
+        assert!(put_expected_payoff_given_exercise(120.0, 110.0, 0.05, 0.0, 0.25, 0.02).is_nan());
+        // Without volatility the stock grows to its forward, which is in the money here.
+        let forward = 100.0*(0.03_f64*2.0).exp();
+        assert_eq!(call_in_the_money_probability(100.0, 105.0, 0.05, 2.0, 0.0, 0.02), 1.0);
+        assert!((call_expected_payoff_given_exercise(100.0, 105.0, 0.05, 2.0, 0.0, 0.02)-(forward-105.0)).abs() < 1e-10);
+        // A put whose premium carried to expiry exceeds its strike cannot break even.
+        assert!(put_breakeven_spot(10.0, 12.0, 0.05, 1.0) < 0.0);
+        assert_eq!(try_call_expected_payoff_given_exercise(f64::NAN, 105.0, 0.05, 2.0, 0.2, 0.02), Err(PricingError::NegativeInput));
+        assert_eq!(try_put_real_world_in_the_money_probability(100.0, 105.0, 0.1, 1.0, -0.2, 0.02), Err(PricingError::NegativeInput));
+        assert_eq!(try_call_breakeven_spot(105.0, 3.0, 0.05, -1.0), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn try_call_price_test(){
+        assert_eq!(try_european_call_option_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03), Ok(european_call_option_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)));
+        assert_eq!(try_european_call_option_price(101.2, 123.0, 0.07, 1.43, -0.15, 0.03), Err(PricingError::NegativeInput));
+        assert_eq!(try_call_rho(101.2, f64::NAN, 0.07, 1.43, 0.15, 0.03), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn kirk_zero_strike_is_margrabe_test(){
+        let volatility = (0.3_f64*0.3-2.0*0.4*0.3*0.2+0.2*0.2).sqrt();
+        let exchange = {
+            let d1 = ((110.0_f64/100.0).ln()+(0.01-0.02+0.5*volatility*volatility)*0.75)/(volatility*0.75_f64.sqrt());
+            let d2 = d1-volatility*0.75_f64.sqrt();
+            110.0*(-0.02_f64*0.75).exp()*utils::cumulative_normal_function(d1)-100.0*(-0.01_f64*0.75).exp()*utils::cumulative_normal_function(d2)
+        };
+        assert!((spread_call_price_kirk(110.0, 100.0, 0.0, 0.03, 0.75, 0.3, 0.2, 0.4, 0.02, 0.01)-exchange).abs()<1e-10);
+        assert!((spread_call_price_bjerksund_stensland(110.0, 100.0, 0.0, 0.03, 0.75, 0.3, 0.2, 0.4, 0.02, 0.01)-exchange).abs()<1e-10);
+    }
+
+    #[test]
+    fn spread_approximations_agree_test(){
+        let kirk = spread_call_price_kirk(122.0, 120.0, 3.0, 0.1, 0.1, 0.2, 0.2, -0.5, 0.0, 0.0);
+        assert_eq!(try_spread_call_price_kirk(10.0, 10.0, -20.0, 0.05, 1.0, 0.2, 0.2, 0.5, 0.0, 0.0), Err(PricingError::InvalidMarketData));
+        assert_eq!(try_spread_call_price_bjerksund_stensland(10.0, 10.0, -20.0, 0.05, 1.0, 0.2, 0.2, 0.5, 0.0, 0.0),
+            Err(PricingError::InvalidMarketData));
+        assert_eq!(try_spread_call_price_bjerksund_stensland(10.0, 0.0, 0.0, 0.05, 1.0, 0.2, 0.2, 0.5, 0.0, 0.0), Err(PricingError::InvalidMarketData));
+        let bs = spread_call_price_bjerksund_stensland(122.0, 120.0, 3.0, 0.1, 0.1, 0.2, 0.2, -0.5, 0.0, 0.0);
+        assert!((kirk-bs).abs()<0.05);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid market data.")]
+    fn spread_without_lognormal_leg_test(){
+        spread_call_price_bjerksund_stensland(10.0, 0.0, 0.0, 0.05, 1.0, 0.2, 0.2, 0.5, 0.0, 0.0);
+    }
+
+    #[test]
+    fn variance_swap_strike_test(){
+        let strikes: Vec<f64> = (0..400).map(|i| 20.0+0.5*i as f64).collect();
+        let calls: Vec<f64> = strikes.iter().map(|k| european_call_option_price(100.0, *k, 0.03, 0.5, 0.2, 0.01)).collect();
+        let puts: Vec<f64> = strikes.iter().map(|k| european_put_option_price(100.0, *k, 0.03, 0.5, 0.2, 0.01)).collect();
+        let strike = variance_swap_strike(forward_price(100.0, 0.03, 0.5, 0.01), 0.03, 0.5, &strikes, &calls, &puts);
+        assert!((strike-flat_volatility_variance_swap_strike(0.2)).abs()<1e-4);
+    }
+
+    #[test]
+    fn asset_or_nothing_test(){
+        let call = asset_or_nothing_call_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        let put = asset_or_nothing_put_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((call+put-101.2*(-0.03_f64*1.43).exp()).abs()<1e-12);
+        assert!((call-123.0*digital_call_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-european_call_option_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+    }
+
+    #[test]
+    fn gap_test(){
+        assert!((gap_call_price(101.2_f64, 123.0, 123.0, 0.07, 1.43, 0.15, 0.03)-european_call_option_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+        assert!((gap_put_price(101.2_f64, 123.0, 123.0, 0.07, 1.43, 0.15, 0.03)-european_put_option_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+        let gap = gap_call_price(101.2_f64, 110.0, 123.0, 0.07, 1.43, 0.15, 0.03);
+        let replication = asset_or_nothing_call_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-110.0*digital_call_price(101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((gap-replication).abs()<1e-12);
+    }
+
+    #[test]
+    fn generic_greeks_test(){
+        use crate::utils::Dual;
+        let single = call_vanna(101.2_f32, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((single as f64-call_vanna(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-4);
+        let delta = call_delta(Dual::variable(101.2_f64), Dual::constant(123.0), Dual::constant(0.07), Dual::constant(1.43),
+            Dual::constant(0.15), Dual::constant(0.03));
+        assert!((delta.get_value()-call_delta(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-15);
+        assert!((delta.get_derivative()-call_gamma(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03)).abs()<1e-12);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn binary_delta_test(){
+        use crate::greeks::automatic_differentiation::{dual_greeks, GreeksDual};
+        let greeks = dual_greeks(asset_or_nothing_call_price, 101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((asset_or_nothing_call_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-greeks.delta).abs()<1e-12);
+        let greeks = dual_greeks(asset_or_nothing_put_price, 101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((asset_or_nothing_put_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)-greeks.delta).abs()<1e-12);
+        let greeks = dual_greeks(|s, k, r, t, v, q| gap_call_price(s, GreeksDual::constant(110.0), k, r, t, v, q), 101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((gap_call_delta(101.2, 110.0, 123.0, 0.07, 1.43, 0.15, 0.03)-greeks.delta).abs()<1e-12);
+        let greeks = dual_greeks(|s, k, r, t, v, q| gap_put_price(s, GreeksDual::constant(110.0), k, r, t, v, q), 101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((gap_put_delta(101.2, 110.0, 123.0, 0.07, 1.43, 0.15, 0.03)-greeks.delta).abs()<1e-12);
+    }
+
+    #[test]
+    fn binary_greeks_test(){
+        use crate::greeks::automatic_differentiation::{dual_greeks, GreeksDual};
+        let assert_close = |greeks: Greeks, expected: Greeks| {
+            for (value, expected) in [(greeks.price, expected.price), (greeks.delta, expected.delta), (greeks.gamma, expected.gamma),
+                (greeks.vega, expected.vega), (greeks.theta, expected.theta), (greeks.rho, expected.rho)] {
+                assert!((value-expected).abs()<1e-10);
+            }
+        };
+        assert_close(asset_or_nothing_call_greeks(101.2, 123.0, 0.07, 1.43, 0.15, 0.03),
+            dual_greeks(asset_or_nothing_call_price, 101.2, 123.0, 0.07, 1.43, 0.15, 0.03));
+        assert_close(asset_or_nothing_put_greeks(101.2, 123.0, 0.07, 1.43, 0.15, 0.03),
+            dual_greeks(asset_or_nothing_put_price, 101.2, 123.0, 0.07, 1.43, 0.15, 0.03));
+        assert_close(gap_call_greeks(101.2, 110.0, 123.0, 0.07, 1.43, 0.15, 0.03),
+            dual_greeks(|s, k, r, t, v, q| gap_call_price(s, GreeksDual::constant(110.0), k, r, t, v, q), 101.2, 123.0, 0.07, 1.43, 0.15, 0.03));
+        assert_close(gap_put_greeks(101.2, 110.0, 123.0, 0.07, 1.43, 0.15, 0.03),
+            dual_greeks(|s, k, r, t, v, q| gap_put_price(s, GreeksDual::constant(110.0), k, r, t, v, q), 101.2, 123.0, 0.07, 1.43, 0.15, 0.03));
+        assert_close(BsCalculator::new(101.2, 123.0, 0.07, 1.43, 0.15, 0.03).digital_put_greeks(),
+            dual_greeks(digital_put_price, 101.2, 123.0, 0.07, 1.43, 0.15, 0.03));
+        // At expiry, away from the trigger strike, the gap call is its payoff S-110 with the carry q*S-r*110 as theta.
+        let expired = gap_call_greeks(130.0, 110.0, 123.0, 0.07, 0.0, 0.15, 0.03);
+        assert_close(expired, Greeks{price: 20.0, delta: 1.0, gamma: 0.0, vega: 0.0, theta: 0.03*130.0-0.07*110.0, rho: 0.0});
+        assert_close(gap_put_greeks(130.0, 110.0, 123.0, 0.07, 0.0, 0.15, 0.03), Greeks{price: 0.0, delta: 0.0, gamma: 0.0, vega: 0.0, theta: 0.0, rho: 0.0});
+        // At the trigger strike the jump of the payoff makes the delta infinite, and with equal strikes there is no jump.
+        assert_eq!(gap_call_greeks(123.0, 110.0, 123.0, 0.07, 0.0, 0.15, 0.03).delta, f64::INFINITY);
+        assert_eq!(gap_call_greeks(123.0, 123.0, 123.0, 0.07, 0.0, 0.15, 0.03).delta, 0.5);
+        assert_eq!(asset_or_nothing_put_greeks(90.0, 100.0, 0.05, 0.0, 0.2, 0.0).delta, 1.0);
+        assert!(try_gap_call_greeks(101.2, -110.0, 123.0, 0.07, 1.43, 0.15, 0.03).is_err());
+    }
+
+    #[test]
+    fn touch_parity_test(){
+        for barrier in [80.0, 120.0]{
+            let one_touch = one_touch_price(100.0, barrier, 0.05, 0.5, 0.25, 0.02, false);
+            let no_touch = no_touch_price(100.0, barrier, 0.05, 0.5, 0.25, 0.02);
+            assert!((one_touch+no_touch-zero_coupon_bond(0.05, 0.5)).abs()<1e-14);
+            assert!(one_touch < one_touch_price(100.0, barrier, 0.05, 0.5, 0.25, 0.02, true));
+        }
+    }
+
+    #[test]
+    fn first_passage_test(){
+        let (spot, r, t, vol, q) = (100.0, 0.05, 0.5, 0.25, 0.02);
+        for barrier in [85.0, 110.0]{
+            let probability = touch_probability(spot, barrier, r, t, vol, q);
+            assert!((probability-(r*t).exp()*one_touch_price(spot, barrier, r, t, vol, q, false)).abs()<1e-13);
+            // The density integrates to the probability of touching, and to the price of a one-touch paying at hit when discounted.
+            let integral = integration::adaptive_simpson(&|s| first_passage_time_density(spot, barrier, r, s, vol, q), 0.0, t, 1e-12);
+            assert!((integral-probability).abs()<1e-8);
+            let discounted = integration::adaptive_simpson(&|s| (-r*s).exp()*first_passage_time_density(spot, barrier, r, s, vol, q), 0.0, t, 1e-12);
+            assert!((discounted-one_touch_price(spot, barrier, r, t, vol, q, true)).abs()<1e-8);
+            // E[min(tau, T)] = E[tau; tau<T]+T*P(tau>=T).
+            let hit_time = integration::adaptive_simpson(&|s| s*first_passage_time_density(spot, barrier, r, s, vol, q), 0.0, t, 1e-12);
+            assert!((expected_first_passage_time(spot, barrier, r, t, vol, q)-hit_time-t*(1.0-probability)).abs()<1e-8);
+        }
+        assert!(real_world_touch_probability(spot, 110.0, 0.12, t, vol, q) > touch_probability(spot, 110.0, r, t, vol, q));
+        assert_eq!(expected_first_passage_time(spot, 110.0, 0.1, 2.0, 0.0, 0.0), 10.0*1.1_f64.ln());
+        assert!(try_touch_probability(spot, -110.0, r, t, vol, q).is_err());
+    }
+
+    #[test]
+    fn degenerate_first_passage_test(){
+        let (spot, r, vol, q) = (100.0, 0.05, 0.25, 0.02);
+        // A stock at the barrier has already touched it, and at expiry nothing can touch it any more.
+        assert_eq!(touch_probability(spot, spot, r, 1.0, vol, q), 1.0);
+        assert_eq!(expected_first_passage_time(spot, spot, r, 1.0, vol, q), 0.0);
+        assert_eq!(touch_probability(spot, 110.0, r, 0.0, vol, q), 0.0);
+        assert_eq!(expected_first_passage_time(spot, 110.0, r, 0.0, vol, q), 0.0);
+        assert_eq!(first_passage_time_density(spot, 110.0, r, 0.0, vol, q), 0.0);
+        // Without volatility the stock never reaches a barrier against its drift and survives until expiry.
+        assert_eq!(touch_probability(spot, 90.0, r, 1.0, 0.0, q), 0.0);
+        assert_eq!(first_passage_time_density(spot, 90.0, r, 0.5, 0.0, q), 0.0);
+        assert_eq!(expected_first_passage_time(spot, 90.0, r, 1.0, 0.0, q), 1.0);
+        assert_eq!(try_real_world_touch_probability(spot, 110.0, 0.1, -1.0, vol, q), Err(PricingError::NegativeInput));
+        assert_eq!(try_first_passage_time_density(spot, 110.0, r, 0.5, -vol, q), Err(PricingError::NegativeInput));
+        assert_eq!(try_expected_first_passage_time(spot, 110.0, r, 1.0, vol, -q), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    #[should_panic(expected = "One of the parameters is negative")]
+    fn negative_first_passage_test(){
+        expected_first_passage_time(100.0, 110.0, 0.05, -1.0, 0.25, 0.02);
+    }
+
+    #[test]
+    fn one_touch_zero_rate_test(){
+        // With no rates and no drift correction the reflection principle gives P(touch)=2*P(S_T beyond barrier) for a driftless log price.
+        let volatility = 0.3;
+        let price = one_touch_price(100.0, 110.0, 0.0, 1.0, volatility, 0.0, true);
+        let drift = -0.5*volatility*volatility;
+        let b = (110.0_f64/100.0).ln();
+        let expected = utils::cumulative_normal_function((-b+drift)/volatility)+(2.0*drift*b/(volatility*volatility)).exp()*utils::cumulative_normal_function((-b-drift)/volatility);
+        assert!((price-expected).abs()<1e-12);
+    }
+
+    #[test]
+    fn implied_rates_test(){
+        let (spot, r, t, vol, q) = (100.0, 0.04, 0.75, 0.25, 0.015);
+        let strikes = [80.0, 95.0, 100.0, 110.0];
+        let calls: Vec<f64> = strikes.iter().map(|k| european_call_option_price(spot, *k, r, t, vol, q)).collect();
+        let puts: Vec<f64> = strikes.iter().map(|k| european_put_option_price(spot, *k, r, t, vol, q)).collect();
+        assert!((implied_divident_rate(spot, 95.0, r, t, calls[1], puts[1])-q).abs()<1e-12);
+        assert!((implied_short_rate_of_interest(spot, 110.0, t, q, calls[3], puts[3])-r).abs()<1e-12);
+        let (implied_r, implied_q) = implied_rate_and_divident_rate(spot, t, &strikes, &calls, &puts);
+        assert!((implied_r-r).abs()<1e-10 && (implied_q-q).abs()<1e-10);
+        assert_eq!(try_implied_divident_rate(spot, 95.0, r, t, 0.0, 200.0), Err(PricingError::InvalidMarketData));
+        assert_eq!(try_implied_rate_and_divident_rate(spot, t, &strikes[..1], &calls[..1], &puts[..1]), Err(PricingError::InvalidMarketData));
+    }
+
+    #[test]
+    fn implied_volatility_test(){
+        let (spot, r, t, q) = (100.0, 0.04, 0.75, 0.015);
+        for (strike, vol) in [(60.0, 0.45), (95.0, 0.25), (100.0, 0.01), (140.0, 0.3), (100.0, 2.5)] {
+            let call = european_call_option_price(spot, strike, r, t, vol, q);
+            let put = european_put_option_price(spot, strike, r, t, vol, q);
+            assert!((call_implied_volatility(spot, strike, r, t, q, call)-vol).abs() < 1e-9);
+            assert!((put_implied_volatility(spot, strike, r, t, q, put)-vol).abs() < 1e-9);
+        }
+        let intrinsic = spot*(-q*t).exp()-80.0*(-r*t).exp();
+        assert_eq!(call_implied_volatility(spot, 80.0, r, t, q, intrinsic), 0.0);
+        assert_eq!(try_call_implied_volatility(spot, 80.0, r, t, q, intrinsic-0.01), Err(PricingError::InvalidMarketData));
+        assert_eq!(try_call_implied_volatility(spot, 80.0, r, t, q, spot), Err(PricingError::InvalidMarketData));
+        assert_eq!(try_put_implied_volatility(spot, 80.0, r, t, q, 80.0), Err(PricingError::InvalidMarketData));
+        assert_eq!(try_put_implied_volatility(spot, 80.0, r, 0.0, q, 1.0), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn batch_test(){
+        let n = 21;
+        let spot: Vec<f64> = (0..n).map(|i| 80.0+2.0*i as f64).collect();
+        let strike = vec![100.0; n];
+        let rate: Vec<f64> = (0..n).map(|i| 0.01*(i%5) as f64).collect();
+        let time: Vec<f64> = (0..n).map(|i| if i == 3 { 0.0 } else { 0.1+0.1*i as f64 }).collect();
+        let vol: Vec<f64> = (0..n).map(|i| 0.1+0.01*i as f64).collect();
+        let q = vec![0.02; n];
+        let mut calls = vec![0.0; n];
+        let mut puts = vec![0.0; n];
+        let mut deltas = vec![0.0; n];
+        let mut vegas = vec![0.0; n];
+        european_call_option_price_batch(&spot, &strike, &rate, &time, &vol, &q, &mut calls);
+        european_put_option_price_batch(&spot, &strike, &rate, &time, &vol, &q, &mut puts);
+        call_delta_batch(&spot, &strike, &rate, &time, &vol, &q, &mut deltas);
+        vega_batch(&spot, &strike, &rate, &time, &vol, &q, &mut vegas);
+        for i in 0..n {
+            assert!((calls[i]-european_call_option_price(spot[i], strike[i], rate[i], time[i], vol[i], q[i])).abs() < 1e-10);
+            assert!((puts[i]-european_put_option_price(spot[i], strike[i], rate[i], time[i], vol[i], q[i])).abs() < 1e-10);
+            if time[i] > 0.0 {
+                assert!((deltas[i]-call_delta(spot[i], strike[i], rate[i], time[i], vol[i], q[i])).abs() < 1e-12);
+                assert!((vegas[i]-call_vega(spot[i], strike[i], rate[i], time[i], vol[i], q[i])).abs() < 1e-10);
+            }
+        }
+        assert_eq!(try_gamma_batch(&spot, &strike, &rate, &time, &vol, &q, &mut calls[..3]), Err(PricingError::InvalidMarketData));
+        let negative = vec![-1.0; n];
+        assert_eq!(try_put_delta_batch(&spot, &strike, &rate, &time, &negative, &q, &mut deltas), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn bs_calculator_test(){
+        let (spot, strike, r, t, vol, q) = (101.2, 123.0, 0.07, 1.43, 0.15, 0.03);
+        let calculator = BsCalculator::new(spot, strike, r, t, vol, q);
+        assert!((calculator.put_price()-european_put_option_price(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((calculator.put_delta()-put_delta(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((calculator.gamma()-call_gamma(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((calculator.vega()-call_vega(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((calculator.call_theta()-call_theta(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((calculator.put_theta()-put_theta(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((calculator.call_rho()-call_rho(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((calculator.put_rho()-put_rho(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert_eq!(BsCalculator::new(spot, strike, r, 0.0, vol, q).put_price(), strike-spot);
+        assert_eq!(BsCalculator::try_new(spot, -1.0, r, t, vol, q), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn single_pass_greeks_test(){
+        let (spot, strike, r, t, vol, q) = (97.0, 105.0, 0.04, 0.75, 0.25, 0.02);
+        let call = call_greeks(spot, strike, r, t, vol, q);
+        assert!((call.price-european_call_option_price(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((call.delta-call_delta(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((call.theta-call_theta(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((call.rho-call_rho(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        let put = put_greeks(spot, strike, r, t, vol, q);
+        assert!((put.price-european_put_option_price(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((put.delta-put_delta(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((put.vega-put_vega(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert!((put.rho-put_rho(spot, strike, r, t, vol, q)).abs() < 1e-12);
+        assert_eq!(put.gamma, call.gamma);
+        assert_eq!(try_call_greeks(spot, strike, r, t, -vol, q), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn barone_adesi_whaley_test(){
+        let european = european_put_option_price(100.0, 100.0, 0.08, 0.25, 0.2, 0.04);
+        let american = american_put_price_barone_adesi_whaley(100.0, 100.0, 0.08, 0.25, 0.2, 0.04);
+        assert!(american > european && american < european+0.2);
+        // Deep in the money options are exercised at once.
+        assert_eq!(american_put_price_barone_adesi_whaley(70.0, 100.0, 0.08, 0.25, 0.2, 0.04), 30.0);
+        assert_eq!(american_call_price_barone_adesi_whaley(140.0, 100.0, 0.08, 0.25, 0.2, 0.12), 40.0);
+        assert_eq!(american_call_price_barone_adesi_whaley(100.0, 90.0, 0.05, 1.0, 0.3, 0.0),
+            european_call_option_price(100.0, 90.0, 0.05, 1.0, 0.3, 0.0));
+        assert_eq!(american_put_price_barone_adesi_whaley(100.0, 90.0, 0.0, 1.0, 0.3, 0.02),
+            european_put_option_price(100.0, 90.0, 0.0, 1.0, 0.3, 0.02));
+        assert!(try_american_put_price_barone_adesi_whaley(100.0, 90.0, 0.05, -1.0, 0.3, 0.0).is_err());
+    }
+
+    #[test]
+    fn roll_geske_whaley_test(){
+        // Reference value from Haug, The Complete Guide to Option Pricing Formulas (2007).
+        assert!((american_call_price_roll_geske_whaley(80.0, 82.0, 0.06, 1.0/3.0, 0.3, 4.0, 0.25)-4.3860).abs()<1e-3);
+        // The price is the expectation of the better of exercising just before the divident and holding the european call.
+        let (spot, strike, r, t, vol, divident, divident_time): (f64, f64, f64, f64, f64, f64, f64) = (100.0, 95.0, 0.05, 1.0, 0.25, 6.0, 0.5);
+        let ex_divident_spot = spot-divident*(-r*divident_time).exp();
+        let n = 4000;
+        let mut expectation = 0.0;
+        for i in 0..n {
+            let z = -8.0+16.0*(i as f64+0.5)/n as f64;
+            let s = ex_divident_spot*((r-0.5*vol*vol)*divident_time+vol*divident_time.sqrt()*z).exp();
+            let hold = european_call_option_price(s, strike, r, t-divident_time, vol, 0.0);
+            expectation += f64::max(s+divident-strike, hold)*utils::normal_probability_density_function(z)*16.0/n as f64;
+        }
+        let american = american_call_price_roll_geske_whaley(spot, strike, r, t, vol, divident, divident_time);
+        assert!((american-(-r*divident_time).exp()*expectation).abs()<1e-6);
+        assert!(american > european_call_option_price(ex_divident_spot, strike, r, t, vol, 0.0));
+        // A small divident never justifies early exercise.
+        assert_eq!(american_call_price_roll_geske_whaley(spot, strike, r, t, vol, 0.5, divident_time),
+            european_call_option_price(spot-0.5*(-r*divident_time).exp(), strike, r, t, vol, 0.0));
+        assert!(try_american_call_price_roll_geske_whaley(spot, strike, r, t, vol, -1.0, divident_time).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn barone_adesi_whaley_pde_test(){
+        use crate::pde_pricer::{BoundaryCondition, CrankNicolsonPricer, PdeGrid};
+        // The approximation is within a few percent of a fine Crank-Nicolson grid with early exercise.
+        let pricer = CrankNicolsonPricer::new(PdeGrid::new(0.0, 300.0, 600, 200), BoundaryCondition::Linear, BoundaryCondition::Linear);
+        for (spot, t, vol) in [(90.0, 0.5, 0.25), (110.0, 1.0, 0.2)] {
+            let put = pricer.price_american(&|s| f64::max(100.0-s, 0.0), spot, 0.06, t, vol, 0.01).get_price();
+            assert!((american_put_price_barone_adesi_whaley(spot, 100.0, 0.06, t, vol, 0.01)-put).abs()<0.02*put);
+            let call = pricer.price_american(&|s| f64::max(s-100.0, 0.0), spot, 0.03, t, vol, 0.07).get_price();
+            assert!((american_call_price_barone_adesi_whaley(spot, 100.0, 0.03, t, vol, 0.07)-call).abs()<0.02*call);
+        }
+    }
+
+    #[test]
+    fn barone_adesi_whaley_table_test(){
+        // Reference values from Haug, The Complete Guide to Option Pricing Formulas (2007), for calls on a stock with a cost
+        // of carry of 0 (r = q = 0.1) and a strike of 100, at spots 90 and 100.
+        for (t, vol, expected) in [(0.1, 0.15, [0.0206, 1.8771]), (0.1, 0.25, [0.3159, 3.1280]), (0.1, 0.35, [0.9495, 4.3777]),
+            (0.5, 0.15, [0.8208, 4.0842]), (0.5, 0.25, [2.7437, 6.8015]), (0.5, 0.35, [5.0063, 9.5106])] {
+            for (spot, expected) in [90.0, 100.0].iter().zip(expected) {
+                assert!((american_call_price_barone_adesi_whaley(*spot, 100.0, 0.1, t, vol, 0.1)-expected).abs()<5e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn bjerksund_stensland_test(){
+        // Reference values from Haug, The Complete Guide to Option Pricing Formulas (2007).
+        assert!((american_call_price_bjerksund_stensland_1993(42.0, 40.0, 0.04, 0.75, 0.35, 0.08)-5.2704).abs()<1e-4);
+        assert!((american_call_price_bjerksund_stensland(42.0, 40.0, 0.04, 0.75, 0.35, 0.08)-5.2869).abs()<1e-4);
+        // Both variants are lower bounds, and the 2002 one is the better one.
+        let (call_2002, call_1993) = (american_call_price_bjerksund_stensland(100.0, 100.0, 0.03, 1.0, 0.25, 0.07),
+            american_call_price_bjerksund_stensland_1993(100.0, 100.0, 0.03, 1.0, 0.25, 0.07));
+        assert!(call_2002 >= call_1993 && call_1993 > european_call_option_price(100.0, 100.0, 0.03, 1.0, 0.25, 0.07));
+        let put = american_put_price_bjerksund_stensland(95.0, 100.0, 0.06, 0.5, 0.3, 0.02);
+        assert!((put-american_call_price_bjerksund_stensland(100.0, 95.0, 0.02, 0.5, 0.3, 0.06)).abs()<1e-14);
+        assert_eq!(american_put_price_bjerksund_stensland_1993(60.0, 100.0, 0.06, 0.5, 0.3, 0.02), 40.0);
+        assert!(try_american_call_price_bjerksund_stensland(100.0, 90.0, 0.05, 1.0, -0.3, 0.0).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn bjerksund_stensland_lattice_test(){
+        use crate::pde_pricer::{BoundaryCondition, CrankNicolsonPricer, PdeGrid};
+        let pricer = CrankNicolsonPricer::new(PdeGrid::new(0.0, 300.0, 600, 200), BoundaryCondition::Linear, BoundaryCondition::Linear);
+        for (spot, t, vol) in [(90.0, 0.5, 0.25), (110.0, 1.0, 0.2)] {
+            let put = pricer.price_american(&|s| f64::max(100.0-s, 0.0), spot, 0.06, t, vol, 0.01).get_price();
+            let (put_2002, put_1993) = (american_put_price_bjerksund_stensland(spot, 100.0, 0.06, t, vol, 0.01),
+                american_put_price_bjerksund_stensland_1993(spot, 100.0, 0.06, t, vol, 0.01));
+            assert!(put_1993 <= put_2002 && put_2002 <= put && put-put_1993 < 0.03*put);
+        }
+    }
+
+    #[test]
+    fn turnbull_wakeman_test(){
+        // A single fixing at expiry is a european option on a lognormal variable, so the approximation is exact.
+        assert!((discrete_asian_call_price_turnbull_wakeman(100.0, 95.0, 0.05, 1.0, 0.3, 0.02, &[1.0])
+            -european_call_option_price(100.0, 95.0, 0.05, 1.0, 0.3, 0.02)).abs()<1e-10);
+        assert!((discrete_asian_put_price_turnbull_wakeman(100.0, 95.0, 0.05, 1.0, 0.3, 0.02, &[1.0])
+            -european_put_option_price(100.0, 95.0, 0.05, 1.0, 0.3, 0.02)).abs()<1e-10);
+        // Many equally spaced fixings converge to the continuous average.
+        let fixing_times: Vec<f64> = (1..=2000).map(|i| i as f64/2000.0).collect();
+        let discrete = discrete_asian_call_price_turnbull_wakeman(100.0, 100.0, 0.05, 1.0, 0.25, 0.01, &fixing_times);
+        assert!((discrete-asian_call_price_turnbull_wakeman(100.0, 100.0, 0.05, 1.0, 0.25, 0.01)).abs()<0.01);
+        // Put-call parity on the average, and the zero carry limit.
+        let call = asian_call_price_turnbull_wakeman(100.0, 90.0, 0.03, 2.0, 0.2, 0.03);
+        let put = asian_put_price_turnbull_wakeman(100.0, 90.0, 0.03, 2.0, 0.2, 0.03);
+        assert!((call-put-(-0.06_f64).exp()*10.0).abs()<1e-10);
+        assert!(try_asian_call_price_turnbull_wakeman(100.0, 90.0, 0.03, 2.0, -0.2, 0.03).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn turnbull_wakeman_monte_carlo_test(){
+        use crate::monte_carlo_pricer::monte_carlo_pricer;
+        use crate::option::AsianOption;
+        use crate::stock::{GeometricBrownianMotionStock, StockState};
+        use crate::utils::{NonNegativeFloat, TimeStamp};
+        use std::sync::Arc;
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.05, NonNegativeFloat::from(0.25), NonNegativeFloat::from(0.0)));
+        fn average(states: &[StockState], _monitoring_times: &[TimeStamp])->NonNegativeFloat{
+            NonNegativeFloat::from(states[1..].iter().map(|s| f64::from(s.get_value())).sum::<f64>()/(states.len()-1) as f64)
+        }
+        fn payoff(average: NonNegativeFloat, params: &[f64])->f64{
+            f64::max(f64::from(average)-params[0], 0.0)
+        }
+        let fixing_times: Vec<f64> = (1..=12).map(|i| i as f64/12.0).collect();
+        let monitoring_times: Vec<TimeStamp> = fixing_times.iter().map(|t| TimeStamp::from(*t)).collect();
+        let opt = AsianOption::new(&stock, TimeStamp::from(1.0), &monitoring_times, Box::new(average), Box::new(payoff), vec![100.0]);
+        let monte_carlo = monte_carlo_pricer(&opt, 0.05, Some(42), 200000);
+        assert!((discrete_asian_call_price_turnbull_wakeman(100.0, 100.0, 0.05, 1.0, 0.25, 0.0, &fixing_times)-monte_carlo).abs()<0.05);
+    }
+
+    #[test]
+    fn ladder_test(){
+        // Without rungs a ladder is a vanilla option, and a rung already reached locks in its gain.
+        assert_eq!(ladder_call_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01, &[]), european_call_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01));
+        let locked = ladder_call_price(120.0, 100.0, 0.05, 1.0, 0.2, 0.01, &[110.0]);
+        assert!(locked >= (-0.05_f64).exp()*10.0);
+        let call = ladder_call_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01, &[110.0, 120.0]);
+        assert!(call > european_call_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01));
+        assert!(call < ladder_call_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01, &[105.0, 110.0, 115.0, 120.0]));
+        // A rung at the strike adds nothing, so the price is continuous as the first rung approaches the strike.
+        assert!((ladder_put_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01, &[100.0-1e-9])-european_put_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01)).abs()<1e-6);
+        assert!(try_ladder_put_price(100.0, 100.0, 0.05, -1.0, 0.2, 0.01, &[90.0]).is_err());
+    }
+
+    type Formula = fn(f64, f64, f64, f64, f64, f64)->f64;
+
+    const GREEKS: [Formula; 20] = [call_delta, call_gamma, call_vega, call_theta, call_rho, put_delta, put_gamma, put_vega, put_theta,
+        put_rho, call_vanna, call_volga, call_charm, call_speed, call_zomma, call_color, put_charm, asset_or_nothing_call_delta,
+        asset_or_nothing_put_delta, digital_call_price];
+
+    /// Spot, strike, rate, time to expiry, volatility and divident rate of options without volatility or time to expiry: in, out of
+    /// and at the money forward.
+    const DEGENERATE_CASES: [(f64, f64, f64, f64, f64, f64); 9] = [
+        (120.0, 100.0, 0.05, 1.0, 0.0, 0.02), (80.0, 100.0, 0.05, 1.0, 0.0, 0.02), (100.0, 100.0, 0.03, 1.0, 0.0, 0.03),
+        (120.0, 100.0, 0.05, 0.0, 0.2, 0.02), (80.0, 100.0, 0.05, 0.0, 0.2, 0.02), (100.0, 100.0, 0.05, 0.0, 0.2, 0.02),
+        (120.0, 100.0, 0.05, 0.0, 0.0, 0.02), (80.0, 100.0, 0.05, 0.0, 0.0, 0.02), (100.0, 100.0, 0.05, 0.0, 0.0, 0.02)];
+
+    #[test]
+    fn degenerate_prices_test(){
+        for (s, k, r, t, v, q) in DEGENERATE_CASES {
+            let forward = s*(-q*t).exp();
+            let discounted_strike = k*(-r*t).exp();
+            let probability = if s > k { 1.0 } else if s < k { 0.0 } else { 0.5 };
+            assert_eq!(european_call_option_price(s, k, r, t, v, q), f64::max(forward-discounted_strike, 0.0));
+            assert_eq!(european_put_option_price(s, k, r, t, v, q), f64::max(discounted_strike-forward, 0.0));
+            assert_eq!(digital_call_price(s, k, r, t, v, q), (-r*t).exp()*probability);
+            assert_eq!(digital_put_price(s, k, r, t, v, q), (-r*t).exp()*(1.0-probability));
+            assert_eq!(asset_or_nothing_call_price(s, k, r, t, v, q), forward*probability);
+            assert_eq!(asset_or_nothing_put_price(s, k, r, t, v, q), forward*(1.0-probability));
+            assert_eq!(gap_call_price(s, 90.0, k, r, t, v, q), (forward-90.0*(-r*t).exp())*probability);
+            assert_eq!(gap_put_price(s, 90.0, k, r, t, v, q), (90.0*(-r*t).exp()-forward)*(1.0-probability));
+            let calculator = BsCalculator::new(s, k, r, t, v, q);
+            assert_eq!(calculator.call_price(), european_call_option_price(s, k, r, t, v, q));
+            assert_eq!(calculator.put_price(), european_put_option_price(s, k, r, t, v, q));
+            for price in [american_call_price_barone_adesi_whaley(s, k, r, t, v, q), american_put_price_barone_adesi_whaley(s, k, r, t, v, q),
+                american_call_price_bjerksund_stensland(s, k, r, t, v, q), american_put_price_bjerksund_stensland(s, k, r, t, v, q),
+                asian_call_price_turnbull_wakeman(s, k, r, t, v, q), asian_put_price_turnbull_wakeman(s, k, r, t, v, q),
+                ladder_call_price(s, k, r, t, v, q, &[130.0]), ladder_put_price(s, k, r, t, v, q, &[70.0]),
+                spread_call_price_kirk(s, 10.0, k-10.0, r, t, v, v, 0.5, q, q), spread_call_price_bjerksund_stensland(s, 10.0, k-10.0, r, t, v, v, 0.5, q, q)] {
+                assert!(price.is_finite() && price >= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_greeks_test(){
+        for (s, k, r, t, v, q) in DEGENERATE_CASES {
+            for greek in GREEKS {
+                assert!(!greek(s, k, r, t, v, q).is_nan());
+            }
+            assert!(!gap_call_delta(s, 90.0, k, r, t, v, q).is_nan() && !gap_put_delta(s, 90.0, k, r, t, v, q).is_nan());
+            assert_eq!(gap_call_delta(s, k, k, r, t, v, q), call_delta(s, k, r, t, v, q));
+            let calculator = BsCalculator::new(s, k, r, t, v, q);
+            for (greeks, call) in [(calculator.call_greeks(), true), (calculator.put_greeks(), false)] {
+                let expected = if call { call_greeks(s, k, r, t, v, q) } else { put_greeks(s, k, r, t, v, q) };
+                assert_eq!(greeks, expected);
+                assert_eq!(greeks.delta, if call { call_delta(s, k, r, t, v, q) } else { put_delta(s, k, r, t, v, q) });
+                assert_eq!(greeks.gamma, call_gamma(s, k, r, t, v, q));
+                assert_eq!(greeks.theta, if call { call_theta(s, k, r, t, v, q) } else { put_theta(s, k, r, t, v, q) });
+            }
+        }
+        // Away from the money the greeks are those of the deterministic payoff, and the limits are continuous.
+        for (s, k) in [(120.0, 100.0), (80.0, 100.0)] {
+            for greek in GREEKS {
+                let limit = greek(s, k, 0.05, 1.0, 0.0, 0.02);
+                assert!((limit-greek(s, k, 0.05, 1.0, 1e-3, 0.02)).abs()<1e-9);
+                assert!((greek(s, k, 0.05, 0.0, 0.2, 0.02)-greek(s, k, 0.05, 1e-8, 0.2, 0.02)).abs()<1e-5);
+            }
+            let in_the_money = if s > k { 1.0 } else { 0.0 };
+            assert_eq!(call_delta(s, k, 0.05, 1.0, 0.0, 0.02), (-0.02_f64).exp()*in_the_money);
+            assert_eq!(call_gamma(s, k, 0.05, 1.0, 0.0, 0.02), 0.0);
+            assert_eq!(put_rho(s, k, 0.05, 1.0, 0.0, 0.02), -k*(-0.05_f64).exp()*(1.0-in_the_money));
+        }
+        // At the money forward the kink of the payoff makes the gamma infinite, and the time decay too at expiry.
+        assert_eq!(call_gamma(100.0, 100.0, 0.03, 1.0, 0.0, 0.03), f64::INFINITY);
+        assert_eq!(call_speed(100.0, 100.0, 0.03, 1.0, 0.0, 0.03), f64::NEG_INFINITY);
+        assert_eq!(call_theta(100.0, 100.0, 0.05, 0.0, 0.2, 0.02), f64::NEG_INFINITY);
+        assert_eq!(call_delta(100.0, 100.0, 0.03, 1.0, 0.0, 0.03), 0.5*(-0.03_f64).exp());
+        assert!((call_vega(100.0_f64, 100.0, 0.03, 1.0, 0.0, 0.03)-call_vega(100.0, 100.0, 0.03, 1.0, 1e-8, 0.03)).abs()<1e-6);
+        assert!((call_vanna(100.0_f64, 100.0, 0.03, 1.0, 0.0, 0.03)-call_vanna(100.0, 100.0, 0.03, 1.0, 1e-8, 0.03)).abs()<1e-6);
+        assert_eq!(asset_or_nothing_put_delta(100.0, 100.0, 0.03, 1.0, 0.0, 0.03), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn degenerate_batch_test(){
+        let spot: Vec<f64> = DEGENERATE_CASES.iter().map(|c| c.0).collect();
+        let strike: Vec<f64> = DEGENERATE_CASES.iter().map(|c| c.1).collect();
+        let rate: Vec<f64> = DEGENERATE_CASES.iter().map(|c| c.2).collect();
+        let time: Vec<f64> = DEGENERATE_CASES.iter().map(|c| c.3).collect();
+        let volatility: Vec<f64> = DEGENERATE_CASES.iter().map(|c| c.4).collect();
+        let divident: Vec<f64> = DEGENERATE_CASES.iter().map(|c| c.5).collect();
+        type BatchFormula = fn(&[f64], &[f64], &[f64], &[f64], &[f64], &[f64], &mut [f64]);
+        let batches: [(BatchFormula, Formula); 4] = [
+            (european_call_option_price_batch, european_call_option_price), (call_delta_batch, call_delta),
+            (put_delta_batch, put_delta), (gamma_batch, call_gamma)];
+        for (batch, formula) in batches {
+            let mut output = vec![0.0; spot.len()];
+            batch(&spot, &strike, &rate, &time, &volatility, &divident, &mut output);
+            for (i, (s, k, r, t, v, q)) in DEGENERATE_CASES.iter().enumerate() {
+                assert_eq!(output[i], formula(*s, *k, *r, *t, *v, *q));
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_touch_test(){
+        // Without volatility the stock grows from 100 to 100*exp(0.03) and touches 102 at ln(1.02)/0.03.
+        let hitting_time = 1.02_f64.ln()/0.03;
+        assert!((one_touch_price(100.0, 102.0, 0.05, 1.0, 0.0, 0.02, true)-(-0.05*hitting_time).exp()).abs()<1e-14);
+        assert_eq!(one_touch_price(100.0, 102.0, 0.05, 1.0, 0.0, 0.02, false), (-0.05_f64).exp());
+        assert_eq!(no_touch_price(100.0, 102.0, 0.05, 1.0, 0.0, 0.02), 0.0);
+        assert_eq!(one_touch_price(100.0, 98.0, 0.05, 1.0, 0.0, 0.02, true), 0.0);
+        assert_eq!(no_touch_price(100.0, 98.0, 0.05, 1.0, 0.0, 0.02), (-0.05_f64).exp());
+        assert_eq!(no_touch_price(100.0, 98.0, 0.05, 1.0, 0.0, 0.05), (-0.05_f64).exp());
+    }
+}
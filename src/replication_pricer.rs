@@ -0,0 +1,159 @@
+//! Provides a pricer for european options with twice differentiable payoffs, which replicates the payoff statically with a
+//! strip of calls and puts priced on a `VolSurface`, so that the prices are consistent with the smile.
+//!
+//! By the formula of Carr and Madan, a payoff `f` of the spot at expiry is
+//!
+//! `f(S) = f(F)+f'(F)*(S-F)+integral_0^F f''(K)*max(K-S, 0) dK+integral_F^infinity f''(K)*max(S-K, 0) dK`,
+//!
+//! so its price is `exp(-r*T)*f(F)` plus the prices of the out of the money puts and calls weighted by `f''(K)`, as the
+//! forward contract is worth nothing. The second derivative of the payoff is computed by finite differences, and the integrals
+//! over the strikes are truncated at a number of standard deviations of the log price from the forward, and computed with
+//! composite Gauss-Legendre quadrature in the log strike.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::replication_pricer::StaticReplicationPricer;
+//! use derivative_pricer::vol_surface::{Smile, VolSurface};
+//! let surface = VolSurface::new(100.0, 0.0, vec![1.0], vec![100.0], vec![Smile::new(vec![100.0], vec![0.2])]);
+//! let pricer = StaticReplicationPricer::default();
+//! // E[S^2] = F^2*exp(sigma^2*T) for a flat smile.
+//! let price = pricer.price(&|s| s*s, &surface, 1.0);
+//! assert!((price-10000.0*0.04_f64.exp()).abs() < 1e-4);
+//! ```
+
+use crate::numerics::integration;
+use crate::vol_surface::VolSurface;
+
+/// The default number of standard deviations of the log price at which the strip of options is truncated.
+pub const DEFAULT_NUMBER_OF_DEVIATIONS: f64 = 10.0;
+
+/// The default number of intervals of the quadrature on each side of the forward.
+pub const DEFAULT_NUMBER_OF_INTERVALS: usize = 64;
+
+/// The number of Gauss-Legendre nodes in every interval of the quadrature.
+const NODES_PER_INTERVAL: usize = 8;
+
+/// The relative size of the step of the finite differences giving the second derivative of the payoff.
+const RELATIVE_STEP: f64 = 1e-4;
+
+/// A static replication pricer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StaticReplicationPricer{
+    /// The number of standard deviations of the log price from the forward at which the strip of options is truncated.
+    number_of_deviations: f64,
+    /// The number of intervals of the quadrature on each side of the forward.
+    number_of_intervals: usize,
+}
+
+impl Default for StaticReplicationPricer {
+    fn default()->StaticReplicationPricer{
+        StaticReplicationPricer::new(DEFAULT_NUMBER_OF_DEVIATIONS, DEFAULT_NUMBER_OF_INTERVALS)
+    }
+}
+
+impl StaticReplicationPricer {
+    /// Returns a new pricer truncating the strip of options at `number_of_deviations` standard deviations of the log price,
+    /// and integrating over the strikes with `number_of_intervals` intervals on each side of the forward.
+    /// # Panics
+    /// Panics if `number_of_deviations` or `number_of_intervals` is not positive.
+    pub fn new(number_of_deviations: f64, number_of_intervals: usize)->StaticReplicationPricer{
+        if !(number_of_deviations > 0.0 && number_of_intervals > 0) {
+            panic!("The number of deviations and the number of intervals must be positive.");
+        }
+        StaticReplicationPricer{number_of_deviations, number_of_intervals}
+    }
+
+    /// Returns the number of standard deviations at which the strip of options is truncated.
+    pub fn get_number_of_deviations(&self)->f64{
+        self.number_of_deviations
+    }
+
+    /// Returns the number of intervals of the quadrature on each side of the forward.
+    pub fn get_number_of_intervals(&self)->usize{
+        self.number_of_intervals
+    }
+
+    /// Returns the price of a european option with the given payoff, replicated with the calls and puts of `surface`.
+    /// # Parameters
+    /// - `payoff` - the payoff of the option as a function of the spot at expiry, twice differentiable.
+    /// - `surface` - the implied volatility surface of the underlying.
+    /// - `time_to_expiry` - the time to expiry of the option.
+    /// # Panics
+    /// Panics if `time_to_expiry` is negative.
+    pub fn price(&self, payoff: &dyn Fn(f64)->f64, surface: &VolSurface, time_to_expiry: f64)->f64{
+        if time_to_expiry.is_nan() || time_to_expiry < 0.0 {
+            panic!("One of the parameters is negative")
+        }
+        let forward = surface.forward(time_to_expiry);
+        let discount = (-surface.get_short_rate_of_interest()*time_to_expiry).exp();
+        // The width of the strip uses the largest volatility at its ends and at the money, so that skewed wings are covered.
+        let width = |volatility: f64| self.number_of_deviations*volatility*time_to_expiry.sqrt();
+        let atm_width = width(surface.volatility(forward, time_to_expiry));
+        let volatility = [forward*(-atm_width).exp(), forward, forward*atm_width.exp()].iter()
+            .map(|k| surface.volatility(*k, time_to_expiry)).fold(0.0, f64::max);
+        let (log_forward, width) = (forward.ln(), width(volatility));
+        let second_derivative = |k: f64| {
+            let h = RELATIVE_STEP*k;
+            (payoff(k+h)-2.0*payoff(k)+payoff(k-h))/(h*h)
+        };
+        // The integrals run over the log strike `x`, with `dK = K*dx`.
+        let puts = self.integral(&|x| {
+            let k = x.exp();
+            second_derivative(k)*surface.put_price(k, time_to_expiry)*k
+        }, log_forward-width, log_forward);
+        let calls = self.integral(&|x| {
+            let k = x.exp();
+            second_derivative(k)*surface.call_price(k, time_to_expiry)*k
+        }, log_forward, log_forward+width);
+        discount*payoff(forward)+puts+calls
+    }
+
+    /// Returns the integral of `f` over `[a, b]` with the composite Gauss-Legendre rule of the pricer.
+    fn integral(&self, f: &dyn Fn(f64)->f64, a: f64, b: f64)->f64{
+        let length = (b-a)/self.number_of_intervals as f64;
+        (0..self.number_of_intervals).map(|i| {
+            let start = a+i as f64*length;
+            integration::gauss_legendre(f, start, start+length, NODES_PER_INTERVAL)
+        }).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quadrature_pricer::GaussHermitePricer;
+    use crate::vol_surface::Smile;
+
+    #[test]
+    fn replication_test(){
+        let (spot, r, q, t): (f64, f64, f64, f64) = (100.0, 0.04, 0.01, 0.75);
+        let forward = spot*((r-q)*t).exp();
+        let flat = VolSurface::new(spot, r, vec![t], vec![forward], vec![Smile::new(vec![100.0], vec![0.25])]);
+        let pricer = StaticReplicationPricer::default();
+        // With a flat smile the prices are the Black-Scholes ones.
+        let payoff = |s: f64| (s/100.0).ln().powi(2)+(-s/50.0).exp();
+        let black_scholes = GaussHermitePricer::new(40).price(&payoff, spot, r, t, 0.25, q);
+        assert!((pricer.price(&payoff, &flat, t)-black_scholes).abs() < 1e-6);
+        // A linear payoff is replicated by a bond and a forward alone.
+        assert!((pricer.price(&|s| 2.0*s+3.0, &flat, t)-(-r*t).exp()*(2.0*forward+3.0)).abs() < 1e-6);
+        // A skew makes out of the money puts more expensive, so a convex payoff in the downside is worth more.
+        let skewed = VolSurface::new(spot, r, vec![t], vec![forward], vec![Smile::new(vec![70.0, 100.0, 130.0], vec![0.35, 0.25, 0.2])]);
+        let downside = |s: f64| (-s/20.0).exp()*1000.0;
+        assert!(pricer.price(&downside, &skewed, t) > pricer.price(&downside, &flat, t));
+        assert!((pricer.price(&payoff, &flat, 0.0)-payoff(spot)).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic(expected = "One of the parameters is negative")]
+    fn negative_time_to_expiry_test(){
+        let surface = VolSurface::new(100.0, 0.0, vec![1.0], vec![100.0], vec![Smile::new(vec![100.0], vec![0.2])]);
+        StaticReplicationPricer::default().price(&|s| s*s, &surface, -0.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "The number of deviations and the number of intervals must be positive.")]
+    fn empty_strip_test(){
+        StaticReplicationPricer::new(10.0, 0);
+    }
+}
@@ -0,0 +1,333 @@
+//! Provides a Monte Carlo engine for the Value at Risk (VaR) and the Expected Shortfall (ES) of a portfolio of option strategies.
+//!
+//! The engine simulates joint moves of the risk factors (the spot, the volatility and the short rate of interest) over a
+//! horizon, revalues the portfolio in every scenario with the Black-Scholes formulas, and gathers the losses in a
+//! `ValueAtRiskGatherer`. The VaR at confidence level `a` is the `a`-quantile of the loss, and the ES is the mean loss beyond it.
+
+use crate::error::PricingError;
+use crate::random_number_generator::RandomNumberGeneratorTrait;
+use crate::statistics_gatherer::StatisticsGathererTrait;
+use crate::strategy::{Strategy, StrategyLeg};
+use crate::utils::CorrelationMatrix;
+
+/// The current values of the risk factors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MarketState{
+    /// The spot of the stock.
+    spot: f64,
+    /// The (implied) volatility of the stock.
+    volatility: f64,
+    /// The short rate of interest.
+    short_rate_of_interest: f64,
+    /// The continuous divident rate of the stock.
+    divident_rate: f64,
+}
+
+impl MarketState {
+    /// Returns a new market state.
+    pub fn new(spot: f64, volatility: f64, short_rate_of_interest: f64, divident_rate: f64)->MarketState{
+        MarketState{
+            spot,
+            volatility,
+            short_rate_of_interest,
+            divident_rate,
+        }
+    }
+
+    /// Returns the spot of the stock.
+    pub fn get_spot(&self)->f64{
+        self.spot
+    }
+
+    /// Returns the volatility of the stock.
+    pub fn get_volatility(&self)->f64{
+        self.volatility
+    }
+
+    /// Returns the short rate of interest.
+    pub fn get_short_rate_of_interest(&self)->f64{
+        self.short_rate_of_interest
+    }
+
+    /// Returns the continuous divident rate of the stock.
+    pub fn get_divident_rate(&self)->f64{
+        self.divident_rate
+    }
+}
+
+/// The joint dynamics of the risk factors over the horizon. The spot is lognormal without drift, the volatility is lognormal
+/// without drift, and the short rate of interest is normal, with correlated Gaussian shocks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RiskFactorModel{
+    /// The annualized volatility of the spot.
+    spot_volatility: f64,
+    /// The annualized volatility of the logarithm of the volatility.
+    volatility_of_volatility: f64,
+    /// The annualized (absolute) volatility of the short rate of interest.
+    rate_volatility: f64,
+    /// The correlation matrix of the shocks to the spot, the volatility and the rate, in this order.
+    correlation: CorrelationMatrix,
+}
+
+impl RiskFactorModel {
+    /// Returns a new risk factor model.
+    /// # Parameters
+    /// - `spot_volatility` - the annualized volatility of the spot.
+    /// - `volatility_of_volatility` - the annualized volatility of the logarithm of the volatility.
+    /// - `rate_volatility` - the annualized absolute volatility of the short rate of interest.
+    /// - `correlation` - the 3x3 correlation matrix of the shocks to the spot, the volatility and the rate.
+    /// # Panics
+    /// Panics if one of the volatilities is negative or if `correlation` is not 3x3.
+    pub fn new(spot_volatility: f64, volatility_of_volatility: f64, rate_volatility: f64, correlation: CorrelationMatrix)->RiskFactorModel{
+        if spot_volatility < 0.0 || volatility_of_volatility < 0.0 || rate_volatility < 0.0 {
+            panic!("One of the parameters is negative")
+        }
+        if correlation.get_dimension() != 3 {
+            panic!("The correlation matrix of the risk factors must be 3x3.");
+        }
+        RiskFactorModel{
+            spot_volatility,
+            volatility_of_volatility,
+            rate_volatility,
+            correlation,
+        }
+    }
+
+    /// Returns a model in which only the spot moves, with the given annualized volatility.
+    pub fn spot_only(spot_volatility: f64)->RiskFactorModel{
+        let identity = (0..3).map(|i| (0..3).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+        RiskFactorModel::new(spot_volatility, 0.0, 0.0, CorrelationMatrix::new(identity))
+    }
+
+    /// Returns the market state after a move over `horizon` years driven by the correlated standard Gaussian `shocks`.
+    fn scenario(&self, market: &MarketState, horizon: f64, shocks: &[f64])->MarketState{
+        let sqrt_horizon = horizon.sqrt();
+        let spot_variance = self.spot_volatility*self.spot_volatility*horizon;
+        let volatility_variance = self.volatility_of_volatility*self.volatility_of_volatility*horizon;
+        MarketState{
+            spot: market.spot*(-0.5*spot_variance+spot_variance.sqrt()*shocks[0]).exp(),
+            volatility: market.volatility*(-0.5*volatility_variance+volatility_variance.sqrt()*shocks[1]).exp(),
+            short_rate_of_interest: market.short_rate_of_interest+self.rate_volatility*sqrt_horizon*shocks[2],
+            divident_rate: market.divident_rate,
+        }
+    }
+}
+
+/// A portfolio of option strategies on a single stock, each held in a signed quantity.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Portfolio{
+    /// The quantities and the strategies of the portfolio.
+    positions: Vec<(f64, Strategy)>,
+}
+
+impl Portfolio {
+    /// Returns an empty portfolio, to which positions can be added with `with_position`.
+    pub fn new()->Portfolio{
+        Portfolio::default()
+    }
+
+    /// Returns the portfolio with an additional position of `quantity` units of `strategy`.
+    pub fn with_position(mut self, quantity: f64, strategy: Strategy)->Portfolio{
+        self.positions.push((quantity, strategy));
+        self
+    }
+
+    /// Returns the quantities and the strategies of the portfolio.
+    pub fn get_positions(&self)->&[(f64, Strategy)]{
+        &self.positions
+    }
+
+    /// Returns the value of the portfolio in the given market state, `elapsed_time` years from now.
+    /// Legs which expire within `elapsed_time` are valued at their payoff in the given state.
+    pub fn value(&self, market: &MarketState, elapsed_time: f64)->f64{
+        self.positions.iter().map(|(quantity, strategy)| {
+            quantity*strategy.get_legs().iter().map(|(leg_quantity, leg)| leg_quantity*leg_value(leg, market, elapsed_time)).sum::<f64>()
+        }).sum()
+    }
+}
+
+/// Returns the value of one unit of `leg` in the given market state, `elapsed_time` years from now.
+fn leg_value(leg: &StrategyLeg, market: &MarketState, elapsed_time: f64)->f64{
+    let aged_leg = match *leg {
+        StrategyLeg::Call{strike, time_to_expiry} => StrategyLeg::Call{strike, time_to_expiry: time_to_expiry-elapsed_time},
+        StrategyLeg::Put{strike, time_to_expiry} => StrategyLeg::Put{strike, time_to_expiry: time_to_expiry-elapsed_time},
+        StrategyLeg::Bond{time_to_maturity} => StrategyLeg::Bond{time_to_maturity: time_to_maturity-elapsed_time},
+        StrategyLeg::Stock => StrategyLeg::Stock,
+    };
+    match aged_leg {
+        StrategyLeg::Call{time_to_expiry, ..} | StrategyLeg::Put{time_to_expiry, ..} | StrategyLeg::Bond{time_to_maturity: time_to_expiry}
+            if time_to_expiry <= 0.0 => aged_leg.payoff(market.spot),
+        _ => aged_leg.greeks(market.spot, market.short_rate_of_interest, market.volatility, market.divident_rate).price,
+    }
+}
+
+/// A statistics gatherer which stores the simulated losses and computes their Value at Risk and Expected Shortfall at
+/// the given confidence levels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValueAtRiskGatherer{
+    /// The confidence levels, e.g. 0.99.
+    confidence_levels: Vec<f64>,
+    /// The gathered losses.
+    losses: Vec<f64>,
+}
+
+impl ValueAtRiskGatherer {
+    /// Returns a new gatherer for the given confidence levels.
+    /// # Panics
+    /// Panics if one of the confidence levels is not in (0,1).
+    pub fn new(confidence_levels: &[f64])->ValueAtRiskGatherer{
+        ValueAtRiskGatherer::try_new(confidence_levels).expect("The confidence levels must be in (0,1).")
+    }
+
+    /// Same as `new`, but returns `PricingError::InvalidInput` instead of panicking.
+    pub fn try_new(confidence_levels: &[f64])->Result<ValueAtRiskGatherer, PricingError>{
+        if confidence_levels.iter().any(|a| !(*a > 0.0 && *a < 1.0)) {
+            return Err(PricingError::InvalidInput);
+        }
+        Ok(ValueAtRiskGatherer{
+            confidence_levels: confidence_levels.to_vec(),
+            losses: Vec::new(),
+        })
+    }
+
+    /// Returns the losses sorted in increasing order, and the index of the VaR of each confidence level among them.
+    fn sorted_losses(&self)->(Vec<f64>, Vec<usize>){
+        let mut losses = self.losses.clone();
+        losses.sort_by(|a, b| a.total_cmp(b));
+        let n = losses.len();
+        let indices = self.confidence_levels.iter()
+            .map(|a| ((a*n as f64).ceil() as usize).clamp(1, n.max(1))-1).collect();
+        (losses, indices)
+    }
+
+    /// Returns the Value at Risk at each confidence level, i.e. the empirical quantile of the losses, or an empty vector if no
+    /// losses were gathered.
+    pub fn get_value_at_risk(&self)->Vec<f64>{
+        if self.losses.is_empty() {
+            return Vec::new();
+        }
+        let (losses, indices) = self.sorted_losses();
+        indices.into_iter().map(|i| losses[i]).collect()
+    }
+
+    /// Returns the Expected Shortfall at each confidence level, i.e. the mean of the losses from the Value at Risk up, or an empty
+    /// vector if no losses were gathered.
+    pub fn get_expected_shortfall(&self)->Vec<f64>{
+        if self.losses.is_empty() {
+            return Vec::new();
+        }
+        let (losses, indices) = self.sorted_losses();
+        indices.into_iter().map(|i| losses[i..].iter().sum::<f64>()/(losses.len()-i) as f64).collect()
+    }
+}
+
+impl StatisticsGathererTrait for ValueAtRiskGatherer {
+    /// Adds the loss `result` to the gatherer.
+    fn dump_one_result(&mut self, result: f64){
+        self.losses.push(result);
+    }
+
+    /// Returns the Value at Risk and the Expected Shortfall at each confidence level, as `vec![var, es]`.
+    fn get_results_so_far(&self)->Vec<Vec<f64>>{
+        vec![self.get_value_at_risk(), self.get_expected_shortfall()]
+    }
+}
+
+/// The Value at Risk and the Expected Shortfall of a portfolio at one confidence level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RiskReport{
+    /// The confidence level.
+    pub confidence_level: f64,
+    /// The Value at Risk.
+    pub value_at_risk: f64,
+    /// The Expected Shortfall.
+    pub expected_shortfall: f64,
+}
+
+/// Simulates the losses of `portfolio` over `horizon` years and returns its Value at Risk and Expected Shortfall at each of
+/// `confidence_levels`.
+///
+/// # Parameters
+///
+/// - `portfolio` - The portfolio.
+/// - `market` - The current market state.
+/// - `model` - The joint dynamics of the risk factors.
+/// - `horizon` - The horizon, in years.
+/// - `confidence_levels` - The confidence levels, e.g. `[0.95, 0.99]`.
+/// - `rng` - an object implementing the `RandomNumberGeneratorTrait`.
+/// - `number_of_scenarios` - The number of simulated scenarios.
+///
+/// # Errors
+///
+/// Returns `PricingError::NegativeInput` if `horizon` is negative, `PricingError::InvalidInput` if one of the confidence levels
+/// is not in (0,1), and `PricingError::InsufficientSamples` if there are no scenarios.
+pub fn monte_carlo_value_at_risk(portfolio: &Portfolio, market: &MarketState, model: &RiskFactorModel, horizon: f64,
+    confidence_levels: &[f64], rng: &mut impl RandomNumberGeneratorTrait, number_of_scenarios: usize)->Result<Vec<RiskReport>, PricingError>{
+    if horizon < 0.0 || horizon.is_nan() {
+        return Err(PricingError::NegativeInput);
+    }
+    if number_of_scenarios == 0 {
+        return Err(PricingError::InsufficientSamples);
+    }
+    let mut gatherer = ValueAtRiskGatherer::try_new(confidence_levels)?;
+    let current_value = portfolio.value(market, 0.0);
+    for shocks in rng.get_correlated_gaussians(&model.correlation, number_of_scenarios){
+        let scenario = model.scenario(market, horizon, &shocks);
+        gatherer.dump_one_result(current_value-portfolio.value(&scenario, horizon));
+    }
+    let results = gatherer.get_results_so_far();
+    Ok(confidence_levels.iter().enumerate().map(|(i, a)| RiskReport{
+        confidence_level: *a,
+        value_at_risk: results[0][i],
+        expected_shortfall: results[1][i],
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random_number_generator::RandomNumberGenerator;
+    use crate::utils::inverse_cumulative_normal_function;
+
+    #[test]
+    fn gatherer_test(){
+        let mut gatherer = ValueAtRiskGatherer::new(&[0.9]);
+        for i in 1..=100 {
+            gatherer.dump_one_result(i as f64);
+        }
+        assert_eq!(gatherer.get_value_at_risk(), vec![90.0]);
+        assert_eq!(gatherer.get_expected_shortfall(), vec![95.0]);
+        let empty = ValueAtRiskGatherer::new(&[0.9, 0.99]);
+        assert_eq!(empty.get_results_so_far(), vec![Vec::<f64>::new(), Vec::new()]);
+        assert_eq!(ValueAtRiskGatherer::try_new(&[0.9, 1.0]), Err(PricingError::InvalidInput));
+        assert_eq!(ValueAtRiskGatherer::try_new(&[f64::NAN]), Err(PricingError::InvalidInput));
+    }
+
+    #[test]
+    fn stock_value_at_risk_test(){
+        // The loss of a share of stock is lognormal, so its VaR is known in closed form.
+        let portfolio = Portfolio::new().with_position(1.0, Strategy::new().with_leg(1.0, StrategyLeg::Stock));
+        let market = MarketState::new(100.0, 0.2, 0.05, 0.0);
+        let mut rng = RandomNumberGenerator::new(Some(17));
+        let reports = monte_carlo_value_at_risk(&portfolio, &market, &RiskFactorModel::spot_only(0.3), 0.04, &[0.99], &mut rng, 200000).unwrap();
+        let volatility = 0.3*0.2;
+        let exact = 100.0*(1.0-(-0.5*volatility*volatility+volatility*inverse_cumulative_normal_function(0.01)).exp());
+        assert!((reports[0].value_at_risk-exact).abs() < 0.2);
+        assert!(reports[0].expected_shortfall > reports[0].value_at_risk);
+        assert_eq!(monte_carlo_value_at_risk(&portfolio, &market, &RiskFactorModel::spot_only(0.3), -1.0, &[0.99], &mut rng, 10),
+            Err(PricingError::NegativeInput));
+        assert_eq!(monte_carlo_value_at_risk(&portfolio, &market, &RiskFactorModel::spot_only(0.3), 0.04, &[0.0], &mut rng, 10),
+            Err(PricingError::InvalidInput));
+        assert_eq!(monte_carlo_value_at_risk(&portfolio, &market, &RiskFactorModel::spot_only(0.3), 0.04, &[f64::NAN], &mut rng, 10),
+            Err(PricingError::InvalidInput));
+    }
+
+    #[test]
+    fn portfolio_value_test(){
+        let straddle = Strategy::straddle(100.0, 0.5);
+        let portfolio = Portfolio::new().with_position(2.0, straddle.clone());
+        let market = MarketState::new(110.0, 0.2, 0.05, 0.0);
+        assert!((portfolio.value(&market, 0.0)-2.0*straddle.price(110.0, 0.05, 0.2, 0.0)).abs() < 1e-12);
+        assert_eq!(portfolio.value(&market, 1.0), 20.0);
+    }
+}
@@ -0,0 +1,579 @@
+//! Provides a general one dimensional SDE
+//!
+//! `dX = a(t,X)*dt + b(t,X)*dW + dJ`,
+//!
+//! whose drift `a` and diffusion `b` are closures, and whose optional jump part `J` has jumps arriving at a constant intensity,
+//! with sizes given by a closure. This allows Monte Carlo pricing under custom dynamics, e.g. a CEV or a local volatility
+//! stock, without a model specific implementation.
+//!
+//! The paths are simulated with the Euler or the Milstein scheme, splitting the intervals between the times of a schedule into
+//! equal steps no longer than a maximal time step. At most one jump is sampled per step, which is accurate as long as the
+//! expected number of jumps per step is small. As for the Ornstein-Uhlenbeck process, the closures are taken to define the
+//! dynamics under the pricing measure, i.e. the drift of a stock should include the short rate of interest.
+//!
+//! `SdeSystem` extends this to a vector of factors driven by correlated Brownian motions, e.g. a stock together with a
+//! stochastic rate and a stochastic volatility, simulated jointly with the Euler scheme for the payoffs of hybrid products.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use derivative_pricer::monte_carlo_pricer::monte_carlo_pricer;
+//! use derivative_pricer::sde::{Sde, SdeOption};
+//! use derivative_pricer::utils::TimeStamp;
+//! // A CEV stock with risk neutral drift, for a short rate of interest of 5%.
+//! let sde = Arc::new(Sde::new(100.0, TimeStamp::from(0.0), |_t, x| 0.05*x, |_t, x: f64| 2.0*x.max(0.0).sqrt()));
+//! let call = SdeOption::new(&sde, &[TimeStamp::from(1.0)], Box::new(|path| (path.get_values()[0]-100.0).max(0.0)));
+//! let price = monte_carlo_pricer(&call, 0.05, Some(1), 10000);
+//! assert!(price > 5.0 && price < 15.0);
+//! ```
+
+use std::sync::Arc;
+
+use crate::option::{DerivativeOption, Underlying};
+use crate::path_generator::{Path, PathGenerator};
+use crate::random_number_generator::RandomNumberGeneratorTrait;
+use crate::utils::{cumulative_normal_function, CorrelationMatrix, Duration, TimeStamp};
+
+/// A coefficient of an SDE, as a function of the time and the value of the process.
+pub type SdeCoefficient = Box<dyn Fn(f64, f64)->f64+Send+Sync>;
+
+/// The size of a jump of an SDE, as a function of the time, the value of the process before the jump and a sample of N(0,1).
+pub type SdeJumpSize = Box<dyn Fn(f64, f64, f64)->f64+Send+Sync>;
+
+/// A payoff of an option on an SDE, as a function of the path of the process at the schedule of the option.
+pub type SdePayoff = Box<dyn Fn(&Path)->f64+Send+Sync>;
+
+/// A coefficient of an SDE system, as a function of the time and the values of the factors, with one entry per factor.
+pub type SdeSystemCoefficient = Box<dyn Fn(f64, &[f64])->Vec<f64>+Send+Sync>;
+
+/// A payoff of an option on an SDE system, as a function of the paths of the factors at the schedule of the option.
+pub type SdeSystemPayoff = Box<dyn Fn(&[Path])->f64+Send+Sync>;
+
+/// The default maximal time step of an SDE.
+const DEFAULT_MAXIMAL_TIME_STEP: f64 = 0.01;
+
+/// The discretization scheme of an SDE.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SdeScheme{
+    /// The Euler scheme, `X += a*dt + b*dW`.
+    #[default]
+    Euler,
+    /// The Milstein scheme, which adds `b*b'*(dW^2-dt)/2` to the Euler step. The derivative `b'` of the diffusion with respect
+    /// to the value is computed by central differences.
+    Milstein,
+}
+
+/// The jump part of an SDE.
+struct SdeJumps{
+    /// The intensity of the arrival of the jumps.
+    intensity: f64,
+    /// The size of a jump.
+    jump_size: SdeJumpSize,
+}
+
+/// A process following an SDE given by closures.
+pub struct Sde{
+    /// The current value of the process.
+    value: f64,
+    /// The current time, i.e. the time at which the value was observed.
+    current_time: TimeStamp,
+    /// The drift `a(t,x)`.
+    drift: SdeCoefficient,
+    /// The diffusion `b(t,x)`.
+    diffusion: SdeCoefficient,
+    /// The jump part, if any.
+    jumps: Option<SdeJumps>,
+    /// The discretization scheme.
+    scheme: SdeScheme,
+    /// The longest step of the discretization.
+    maximal_time_step: f64,
+}
+
+impl Underlying for Sde {
+
+}
+
+impl Sde {
+    /// Returns a new process without jumps, simulated with the Euler scheme and a maximal time step of 0.01.
+    /// # Parameters
+    /// - `value` - The current value of the process.
+    /// - `current_time` - The time at which the value was observed.
+    /// - `drift` - The drift `a(t,x)`, as a function of the time and the value.
+    /// - `diffusion` - The diffusion `b(t,x)`, as a function of the time and the value.
+    pub fn new(value: f64, current_time: TimeStamp, drift: impl Fn(f64, f64)->f64+Send+Sync+'static,
+        diffusion: impl Fn(f64, f64)->f64+Send+Sync+'static)->Sde{
+        Sde{
+            value,
+            current_time,
+            drift: Box::new(drift),
+            diffusion: Box::new(diffusion),
+            jumps: None,
+            scheme: SdeScheme::default(),
+            maximal_time_step: DEFAULT_MAXIMAL_TIME_STEP,
+        }
+    }
+
+    /// Returns the process with jumps arriving at the constant `intensity`, whose sizes are given by `jump_size` as a function
+    /// of the time, the value before the jump and a sample of N(0,1). For example, `|_t, x, z| x*((m+s*z).exp()-1.0)` gives the
+    /// log-normal jumps of the Merton model.
+    /// # Panics
+    /// Panics if `intensity` is negative.
+    pub fn with_jumps(self, intensity: f64, jump_size: impl Fn(f64, f64, f64)->f64+Send+Sync+'static)->Sde{
+        if intensity.is_nan() || intensity < 0.0 {
+            panic!("The intensity of the jumps must be non negative.");
+        }
+        Sde{jumps: Some(SdeJumps{intensity, jump_size: Box::new(jump_size)}), ..self}
+    }
+
+    /// Returns the process simulated with `scheme`.
+    pub fn with_scheme(self, scheme: SdeScheme)->Sde{
+        Sde{scheme, ..self}
+    }
+
+    /// Returns the process simulated with steps no longer than `maximal_time_step`.
+    /// # Panics
+    /// Panics if `maximal_time_step` is not positive.
+    pub fn with_maximal_time_step(self, maximal_time_step: f64)->Sde{
+        if maximal_time_step.is_nan() || maximal_time_step <= 0.0 {
+            panic!("The maximal time step must be positive.");
+        }
+        Sde{maximal_time_step, ..self}
+    }
+
+    /// Returns the current value of the process.
+    pub fn get_value(&self)->f64{
+        self.value
+    }
+
+    /// Returns the current time of the process.
+    pub fn get_time(&self)->TimeStamp{
+        self.current_time
+    }
+
+    /// Returns the discretization scheme.
+    pub fn get_scheme(&self)->SdeScheme{
+        self.scheme
+    }
+
+    /// Returns the longest step of the discretization.
+    pub fn get_maximal_time_step(&self)->f64{
+        self.maximal_time_step
+    }
+
+    /// Returns the intensity of the jumps, which is 0 if the process has no jumps.
+    pub fn get_jump_intensity(&self)->f64{
+        self.jumps.as_ref().map_or(0.0, |jumps| jumps.intensity)
+    }
+
+    /// Returns the number of Gaussian samples needed to generate a path at `time_stamps` with
+    /// `generate_path_from_time_stamps`: one per step, or three per step if the process has jumps.
+    /// # Panics
+    /// Panics if `time_stamps` is not increasing or starts before the current time.
+    pub fn get_dimensionality(&self, time_stamps: &[TimeStamp])->usize{
+        let samples_per_step = if self.jumps.is_some() { 3 } else { 1 };
+        samples_per_step*self.steps(time_stamps).iter().sum::<usize>()
+    }
+
+    /// Generates a path of the process at the provided time stamps.
+    /// # Parameters
+    /// - `gaussians` - iid samples of N(0,1), at least `get_dimensionality(time_stamps)` of them.
+    /// - `time_stamps` - Increasing time stamps, the first one not before the current time.
+    /// # Panics
+    /// - If `time_stamps` is not increasing, or starts before the current time.
+    /// - If there are not enough samples.
+    pub fn generate_path_from_time_stamps(&self, gaussians: &[f64], time_stamps: &[TimeStamp])->Path{
+        let steps = self.steps(time_stamps);
+        let samples_per_step = if self.jumps.is_some() { 3 } else { 1 };
+        if gaussians.len() < samples_per_step*steps.iter().sum::<usize>() {
+            panic!("Not enough random samples to generate the path.");
+        }
+        let mut samples = gaussians.chunks(samples_per_step);
+        let (mut time, mut value) = (f64::from(self.current_time), self.value);
+        let values = time_stamps.iter().zip(steps).map(|(time_stamp, number_of_steps)| {
+            let time_step = (f64::from(*time_stamp)-time)/number_of_steps as f64;
+            for _ in 0..number_of_steps {
+                value = self.step(time, value, time_step, samples.next().expect("Not enough random samples."));
+                time += time_step;
+            }
+            time = f64::from(*time_stamp);
+            value
+        }).collect();
+        Path::new(time_stamps.to_vec(), values)
+    }
+
+    /// Returns the number of steps in each interval between the current time and the time stamps.
+    fn steps(&self, time_stamps: &[TimeStamp])->Vec<usize>{
+        steps(self.current_time, self.maximal_time_step, time_stamps)
+    }
+
+    /// Returns the value after a step of length `time_step` from `value` at `time`, given one Gaussian sample for the
+    /// diffusion, followed by two for the arrival and the size of a jump if the process has jumps.
+    fn step(&self, time: f64, value: f64, time_step: f64, gaussians: &[f64])->f64{
+        let increment = time_step.sqrt()*gaussians[0];
+        let diffusion = (self.diffusion)(time, value);
+        let mut next = value+(self.drift)(time, value)*time_step+diffusion*increment;
+        if self.scheme == SdeScheme::Milstein {
+            let bump = 1e-6*value.abs().max(1.0);
+            let derivative = ((self.diffusion)(time, value+bump)-(self.diffusion)(time, value-bump))/(2.0*bump);
+            next += 0.5*diffusion*derivative*(increment*increment-time_step);
+        }
+        if let Some(jumps) = &self.jumps {
+            if cumulative_normal_function(gaussians[1]) < 1.0-(-jumps.intensity*time_step).exp() {
+                next += (jumps.jump_size)(time, value, gaussians[2]);
+            }
+        }
+        next
+    }
+}
+
+impl PathGenerator for Sde {
+    fn get_current_time(&self)->TimeStamp{
+        self.current_time
+    }
+
+    fn get_current_value(&self)->f64{
+        self.value
+    }
+
+    /// Generates the path with the scheme of the process. The closures define the dynamics under the pricing measure, so `r`
+    /// is ignored.
+    fn generate<R: RandomNumberGeneratorTrait>(&self, rng: &mut R, schedule: &[TimeStamp], _r: f64)->Path{
+        self.generate_path_from_time_stamps(&rng.get_gaussians(self.get_dimensionality(schedule)), schedule)
+    }
+}
+
+/// Returns the number of steps no longer than `maximal_time_step` in each interval between `current_time` and the time stamps.
+/// # Panics
+/// Panics if `time_stamps` is not increasing or starts before `current_time`.
+fn steps(current_time: TimeStamp, maximal_time_step: f64, time_stamps: &[TimeStamp])->Vec<usize>{
+    if time_stamps.first().is_some_and(|t| *t < current_time) || time_stamps.windows(2).any(|w| w[1] < w[0]) {
+        panic!("The time stamps must be increasing, and must not start before the current time.");
+    }
+    let mut previous_time = f64::from(current_time);
+    time_stamps.iter().map(|t| {
+        let length = f64::from(*t)-previous_time;
+        previous_time = f64::from(*t);
+        (length/maximal_time_step).ceil() as usize
+    }).collect()
+}
+
+/// A vector of factors following the SDE system `dX_i = a_i(t,X)*dt + b_i(t,X)*dW_i`, where the Brownian motions `W_i` are
+/// correlated.
+pub struct SdeSystem{
+    /// The current values of the factors.
+    values: Vec<f64>,
+    /// The current time, i.e. the time at which the values were observed.
+    current_time: TimeStamp,
+    /// The correlation matrix of the Brownian motions.
+    correlation: CorrelationMatrix,
+    /// The drifts `a_i(t,x)`.
+    drift: SdeSystemCoefficient,
+    /// The diffusions `b_i(t,x)`.
+    diffusion: SdeSystemCoefficient,
+    /// The longest step of the discretization.
+    maximal_time_step: f64,
+}
+
+impl Underlying for SdeSystem {
+
+}
+
+impl SdeSystem {
+    /// Returns a new system, simulated with the Euler scheme and a maximal time step of 0.01.
+    /// # Parameters
+    /// - `values` - The current values of the factors.
+    /// - `current_time` - The time at which the values were observed.
+    /// - `correlation` - The correlation matrix of the Brownian motions driving the factors.
+    /// - `drift` - The drifts `a_i(t,x)`, as a function of the time and the values of the factors.
+    /// - `diffusion` - The diffusions `b_i(t,x)`, as a function of the time and the values of the factors.
+    /// # Panics
+    /// Panics if the dimension of `correlation` is not the number of factors.
+    pub fn new(values: Vec<f64>, current_time: TimeStamp, correlation: CorrelationMatrix,
+        drift: impl Fn(f64, &[f64])->Vec<f64>+Send+Sync+'static, diffusion: impl Fn(f64, &[f64])->Vec<f64>+Send+Sync+'static)->SdeSystem{
+        if correlation.get_dimension() != values.len() {
+            panic!("The correlation matrix must have one row for every factor.");
+        }
+        SdeSystem{
+            values,
+            current_time,
+            correlation,
+            drift: Box::new(drift),
+            diffusion: Box::new(diffusion),
+            maximal_time_step: DEFAULT_MAXIMAL_TIME_STEP,
+        }
+    }
+
+    /// Returns the system simulated with steps no longer than `maximal_time_step`.
+    /// # Panics
+    /// Panics if `maximal_time_step` is not positive.
+    pub fn with_maximal_time_step(self, maximal_time_step: f64)->SdeSystem{
+        if maximal_time_step.is_nan() || maximal_time_step <= 0.0 {
+            panic!("The maximal time step must be positive.");
+        }
+        SdeSystem{maximal_time_step, ..self}
+    }
+
+    /// Returns the current values of the factors.
+    pub fn get_values(&self)->&[f64]{
+        &self.values
+    }
+
+    /// Returns the current time of the system.
+    pub fn get_time(&self)->TimeStamp{
+        self.current_time
+    }
+
+    /// Returns the number of factors.
+    pub fn get_dimension(&self)->usize{
+        self.values.len()
+    }
+
+    /// Returns the correlation matrix of the Brownian motions.
+    pub fn get_correlation(&self)->&CorrelationMatrix{
+        &self.correlation
+    }
+
+    /// Returns the longest step of the discretization.
+    pub fn get_maximal_time_step(&self)->f64{
+        self.maximal_time_step
+    }
+
+    /// Returns the number of Gaussian samples needed to generate paths at `time_stamps` with `generate_paths_from_time_stamps`,
+    /// i.e. the number of factors for every step.
+    /// # Panics
+    /// Panics if `time_stamps` is not increasing or starts before the current time.
+    pub fn get_dimensionality(&self, time_stamps: &[TimeStamp])->usize{
+        self.get_dimension()*steps(self.current_time, self.maximal_time_step, time_stamps).iter().sum::<usize>()
+    }
+
+    /// Generates the joint paths of the factors at the provided time stamps, one path per factor.
+    /// # Parameters
+    /// - `gaussians` - iid samples of N(0,1), at least `get_dimensionality(time_stamps)` of them, which are correlated with the
+    ///   Cholesky factor of the correlation matrix.
+    /// - `time_stamps` - Increasing time stamps, the first one not before the current time.
+    /// # Panics
+    /// - If `time_stamps` is not increasing, or starts before the current time.
+    /// - If there are not enough samples.
+    /// - If the drift or the diffusion does not return one value per factor.
+    pub fn generate_paths_from_time_stamps(&self, gaussians: &[f64], time_stamps: &[TimeStamp])->Vec<Path>{
+        let dimension = self.get_dimension();
+        let steps = steps(self.current_time, self.maximal_time_step, time_stamps);
+        if gaussians.len() < dimension*steps.iter().sum::<usize>() {
+            panic!("Not enough random samples to generate the paths.");
+        }
+        let mut samples = gaussians.chunks(dimension);
+        let (mut time, mut values) = (f64::from(self.current_time), self.values.clone());
+        let mut paths: Vec<Vec<f64>> = vec![Vec::with_capacity(time_stamps.len()); dimension];
+        for (time_stamp, number_of_steps) in time_stamps.iter().zip(steps) {
+            let time_step = (f64::from(*time_stamp)-time)/number_of_steps as f64;
+            for _ in 0..number_of_steps {
+                let increments = self.correlation.correlate(samples.next().expect("Not enough random samples."));
+                let (drift, diffusion) = ((self.drift)(time, &values), (self.diffusion)(time, &values));
+                if drift.len() != dimension || diffusion.len() != dimension {
+                    panic!("The drift and the diffusion must have one value for every factor.");
+                }
+                for (i, value) in values.iter_mut().enumerate() {
+                    *value += drift[i]*time_step+diffusion[i]*time_step.sqrt()*increments[i];
+                }
+                time += time_step;
+            }
+            time = f64::from(*time_stamp);
+            paths.iter_mut().zip(&values).for_each(|(path, value)| path.push(*value));
+        }
+        paths.into_iter().map(|values| Path::new(time_stamps.to_vec(), values)).collect()
+    }
+}
+
+/// An option on an SDE whose payoff depends on the values of the process at a schedule of times, the last of which is the
+/// expiry.
+pub struct SdeOption{
+    /// A shared reference to the underlying process.
+    underlying: Arc<Sde>,
+    /// The times at which the process is observed.
+    schedule: Vec<TimeStamp>,
+    /// The payoff, as a function of the path of the process at the schedule.
+    payoff: SdePayoff,
+}
+
+impl SdeOption {
+    /// Returns a new option on `underlying`, paying `payoff` of the path of the process at `schedule`.
+    /// # Panics
+    /// Panics if `schedule` is empty or not strictly increasing.
+    pub fn new(underlying: &Arc<Sde>, schedule: &[TimeStamp], payoff: SdePayoff)->SdeOption{
+        if schedule.is_empty() || schedule.windows(2).any(|w| w[1] <= w[0]) {
+            panic!("The schedule must be non empty and strictly increasing.");
+        }
+        SdeOption{underlying: Arc::clone(underlying), schedule: schedule.to_vec(), payoff}
+    }
+
+    /// Returns the expiry of the option, the last time of its schedule.
+    pub fn get_expiry(&self)->TimeStamp{
+        self.schedule[self.schedule.len()-1]
+    }
+
+    /// Returns the schedule of the option.
+    pub fn get_schedule(&self)->&[TimeStamp]{
+        &self.schedule
+    }
+}
+
+impl DerivativeOption<Sde> for SdeOption {
+    /// Returns the time to expiry of the option, where the current time is considered to be the current time of the process.
+    fn get_time_to_expiry(&self)->Option<Duration>{
+        self.get_expiry().checked_duration_since(self.underlying.get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option.
+    fn get_dimensionality(&self)->usize{
+        self.underlying.get_dimensionality(&self.schedule)
+    }
+
+    /// Prices the option (not discounted) given the Gaussian samples of one path. The short rate of interest is not used, as
+    /// the closures of the process define the dynamics under the pricing measure.
+    /// # Panics
+    /// Panics if a time of the schedule is before the current time of the process.
+    fn price_path(&self, random_samples: &[f64], _r: f64)->f64{
+        (self.payoff)(&self.underlying.generate_path_from_time_stamps(random_samples, &self.schedule))
+    }
+}
+
+/// An option on an SDE system, e.g. a hybrid product, whose payoff depends on the values of the factors at a schedule of times,
+/// the last of which is the expiry.
+pub struct SdeSystemOption{
+    /// A shared reference to the underlying system.
+    underlying: Arc<SdeSystem>,
+    /// The times at which the factors are observed.
+    schedule: Vec<TimeStamp>,
+    /// The payoff, as a function of the paths of the factors at the schedule.
+    payoff: SdeSystemPayoff,
+}
+
+impl SdeSystemOption {
+    /// Returns a new option on `underlying`, paying `payoff` of the paths of the factors at `schedule`.
+    /// # Panics
+    /// Panics if `schedule` is empty or not strictly increasing.
+    pub fn new(underlying: &Arc<SdeSystem>, schedule: &[TimeStamp], payoff: SdeSystemPayoff)->SdeSystemOption{
+        if schedule.is_empty() || schedule.windows(2).any(|w| w[1] <= w[0]) {
+            panic!("The schedule must be non empty and strictly increasing.");
+        }
+        SdeSystemOption{underlying: Arc::clone(underlying), schedule: schedule.to_vec(), payoff}
+    }
+
+    /// Returns the expiry of the option, the last time of its schedule.
+    pub fn get_expiry(&self)->TimeStamp{
+        self.schedule[self.schedule.len()-1]
+    }
+
+    /// Returns the schedule of the option.
+    pub fn get_schedule(&self)->&[TimeStamp]{
+        &self.schedule
+    }
+}
+
+impl DerivativeOption<SdeSystem> for SdeSystemOption {
+    /// Returns the time to expiry of the option, where the current time is considered to be the current time of the system.
+    fn get_time_to_expiry(&self)->Option<Duration>{
+        self.get_expiry().checked_duration_since(self.underlying.get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option.
+    fn get_dimensionality(&self)->usize{
+        self.underlying.get_dimensionality(&self.schedule)
+    }
+
+    /// Prices the option (not discounted) given the Gaussian samples of one joint path. The short rate of interest is only used
+    /// for discounting, so a payoff depending on a stochastic rate factor should include its own discounting relative to `r`.
+    /// # Panics
+    /// Panics if a time of the schedule is before the current time of the system.
+    fn price_path(&self, random_samples: &[f64], _r: f64)->f64{
+        (self.payoff)(&self.underlying.generate_paths_from_time_stamps(random_samples, &self.schedule))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft_pricer::CarrMadanPricer;
+    use crate::models::{HestonModel, MertonJumpDiffusionModel};
+    use crate::monte_carlo_pricer::monte_carlo_pricer_with_error;
+    use crate::raw_formulas;
+
+    #[test]
+    fn geometric_brownian_motion_test(){
+        let (r, sigma) = (0.05, 0.2);
+        let expected = raw_formulas::european_call_option_price(100.0, 100.0, r, 1.0, sigma, 0.0);
+        for scheme in [SdeScheme::Euler, SdeScheme::Milstein] {
+            let sde = Arc::new(Sde::new(100.0, TimeStamp::from(0.0), move |_t, x| r*x, move |_t, x| sigma*x)
+                .with_scheme(scheme).with_maximal_time_step(0.05));
+            assert_eq!(sde.get_dimensionality(&[TimeStamp::from(0.5), TimeStamp::from(1.0)]), 20);
+            let call = SdeOption::new(&sde, &[TimeStamp::from(1.0)], Box::new(|path| (path.get_values()[0]-100.0).max(0.0)));
+            let result = monte_carlo_pricer_with_error(&call, r, Some(2), 20000);
+            assert!((result.get_price()-expected).abs() < 3.0*result.get_std_error());
+        }
+        let gaussians = [0.3, -1.2];
+        let milstein = Sde::new(1.0, TimeStamp::from(0.0), |_t, _x| 0.0, move |_t, x| sigma*x)
+            .with_scheme(SdeScheme::Milstein).with_maximal_time_step(0.5);
+        let path = milstein.generate_path_from_time_stamps(&gaussians, &[TimeStamp::from(1.0)]);
+        let mut expected_value = 1.0;
+        for z in gaussians {
+            expected_value *= 1.0+sigma*0.5_f64.sqrt()*z+0.5*sigma*sigma*0.5*(z*z-1.0);
+        }
+        assert!((path.get_values()[0]-expected_value).abs() < 1e-9);
+    }
+
+    #[test]
+    fn jump_test(){
+        let model = MertonJumpDiffusionModel::new(0.2, 0.5, -0.1, 0.15, 0.0);
+        let r = 0.05;
+        let mean_jump_size = (-0.1_f64+0.5*0.15*0.15).exp()-1.0;
+        let sde = Arc::new(Sde::new(100.0, TimeStamp::from(0.0), move |_t, x| (r-0.5*mean_jump_size)*x, |_t, x| 0.2*x)
+            .with_jumps(0.5, |_t, x, z| x*((-0.1+0.15*z).exp()-1.0)).with_maximal_time_step(0.01));
+        assert_eq!(sde.get_jump_intensity(), 0.5);
+        let call = SdeOption::new(&sde, &[TimeStamp::from(1.0)], Box::new(|path| (path.get_values()[0]-100.0).max(0.0)));
+        let result = monte_carlo_pricer_with_error(&call, r, Some(4), 20000);
+        let expected = CarrMadanPricer::default().call_prices(&model, 100.0, r, 1.0, &[100.0])[0];
+        assert!((result.get_price()-expected).abs() < 3.0*result.get_std_error());
+    }
+
+    #[test]
+    fn system_test(){
+        let (r, rho) = (0.05, 0.6);
+        let correlation = CorrelationMatrix::new(vec![vec![1.0, rho], vec![rho, 1.0]]);
+        let deterministic = SdeSystem::new(vec![1.0, 2.0], TimeStamp::from(0.0), correlation.clone(),
+            |_t, _x| vec![1.0, -1.0], |_t, _x| vec![0.0, 0.0]).with_maximal_time_step(0.25);
+        assert_eq!(deterministic.get_dimensionality(&[TimeStamp::from(0.5), TimeStamp::from(1.0)]), 8);
+        let paths = deterministic.generate_paths_from_time_stamps(&[0.0; 8], &[TimeStamp::from(0.5), TimeStamp::from(1.0)]);
+        assert_eq!(paths[0].get_values(), &[1.5, 2.0]);
+        assert_eq!(paths[1].get_values(), &[1.5, 1.0]);
+        // Two correlated stocks, whose product has the expectation x*y*exp((2r+rho*sigma_1*sigma_2)T).
+        let system = Arc::new(SdeSystem::new(vec![100.0, 50.0], TimeStamp::from(0.0), correlation,
+            move |_t, x| vec![r*x[0], r*x[1]], |_t, x| vec![0.2*x[0], 0.3*x[1]]));
+        let product = SdeSystemOption::new(&system, &[TimeStamp::from(1.0)],
+            Box::new(|paths| paths[0].get_values()[0]*paths[1].get_values()[0]));
+        let result = monte_carlo_pricer_with_error(&product, r, Some(6), 20000);
+        let expected = 5000.0*(r+rho*0.2*0.3_f64).exp();
+        assert!((result.get_price()-expected).abs() < 3.0*result.get_std_error());
+    }
+
+    #[test]
+    fn stochastic_volatility_system_test(){
+        let (r, kappa, theta, xi, rho) = (0.05, 1.5, 0.04, 0.3, -0.7);
+        let correlation = CorrelationMatrix::new(vec![vec![1.0, rho], vec![rho, 1.0]]);
+        let system = Arc::new(SdeSystem::new(vec![100.0, 0.04], TimeStamp::from(0.0), correlation,
+            move |_t, x| vec![r*x[0], kappa*(theta-x[1].max(0.0))],
+            move |_t, x| vec![x[1].max(0.0).sqrt()*x[0], xi*x[1].max(0.0).sqrt()]));
+        let call = SdeSystemOption::new(&system, &[TimeStamp::from(1.0)], Box::new(|paths| (paths[0].get_values()[0]-100.0).max(0.0)));
+        let result = monte_carlo_pricer_with_error(&call, r, Some(7), 20000);
+        let model = HestonModel::new(0.04, kappa, theta, xi, rho, 0.0);
+        let expected = CarrMadanPricer::default().call_prices(&model, 100.0, r, 1.0, &[100.0])[0];
+        assert!((result.get_price()-expected).abs() < 3.0*result.get_std_error());
+    }
+
+    #[test]
+    #[should_panic]
+    fn schedule_test(){
+        let sde = Arc::new(Sde::new(1.0, TimeStamp::from(0.0), |_t, _x| 0.0, |_t, _x| 1.0));
+        SdeOption::new(&sde, &[TimeStamp::from(1.0), TimeStamp::from(0.5)], Box::new(|path| path.get_values()[0]));
+    }
+}
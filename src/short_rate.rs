@@ -0,0 +1,483 @@
+//! Provides one-factor short rate models, with closed form zero coupon bond prices and exact simulation of the short rate.
+//!
+//! Times are measured in years from the valuation time, which is time 0, and the parameters are those of the pricing
+//! measure.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::random_number_generator::RandomNumberGenerator;
+//! use derivative_pricer::short_rate::CoxIngersollRossModel;
+//! let model = CoxIngersollRossModel::new(0.03, 0.5, 0.04, 0.1);
+//! let bond = model.zero_coupon_bond_price(5.0);
+//! assert!(bond > (-0.04*5.0_f64).exp() && bond < (-0.03*5.0_f64).exp());
+//! let path = model.generate_path(&[0.5, 1.0, 2.0], &mut RandomNumberGenerator::new(Some(1)));
+//! assert!(path.iter().all(|r| *r >= 0.0));
+//! ```
+
+use crate::payoff::VanillaType;
+use crate::error::PricingError;
+use crate::numerics::roots::brent;
+use crate::random_number_generator::RandomNumberGeneratorTrait;
+use crate::utils::cumulative_normal_function;
+
+/// The tolerance to which the critical rate of Jamshidian's decomposition is found.
+const CRITICAL_RATE_TOLERANCE: f64 = 1e-14;
+
+/// The Cox-Ingersoll-Ross model, in which the short rate follows `dr = kappa*(theta-r)*dt + sigma*sqrt(r)*dW`.
+///
+/// The rate stays non negative, and is zero only if the Feller condition `2*kappa*theta >= sigma^2` fails. The same dynamics
+/// are those of the variance in the Heston model, whose `HestonScheme::ExactVariance` samples the variance with
+/// `sample_transition`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoxIngersollRossModel{
+    /// The current short rate.
+    initial_rate: f64,
+    /// The speed `kappa` at which the rate reverts to its long term mean.
+    mean_reversion_speed: f64,
+    /// The long term mean `theta`.
+    long_term_mean: f64,
+    /// The volatility `sigma`.
+    volatility: f64,
+}
+
+impl CoxIngersollRossModel {
+    /// Returns a new CIR model.
+    /// # Parameters
+    /// - `initial_rate` - The current short rate.
+    /// - `mean_reversion_speed` - The speed `kappa` at which the rate reverts to its long term mean.
+    /// - `long_term_mean` - The long term mean `theta`.
+    /// - `volatility` - The volatility `sigma`.
+    /// # Panics
+    /// Panics if one of the parameters is negative.
+    pub fn new(initial_rate: f64, mean_reversion_speed: f64, long_term_mean: f64, volatility: f64)->CoxIngersollRossModel{
+        match CoxIngersollRossModel::try_new(initial_rate, mean_reversion_speed, long_term_mean, volatility) {
+            Ok(model) => model,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `new`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_new(initial_rate: f64, mean_reversion_speed: f64, long_term_mean: f64, volatility: f64)
+        ->Result<CoxIngersollRossModel, PricingError>{
+        if [initial_rate, mean_reversion_speed, long_term_mean, volatility].iter().any(|x| *x < 0.0 || x.is_nan()) {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(CoxIngersollRossModel{initial_rate, mean_reversion_speed, long_term_mean, volatility})
+    }
+
+    /// Returns the current short rate.
+    pub fn get_initial_rate(&self)->f64{
+        self.initial_rate
+    }
+
+    /// Returns the speed of mean reversion.
+    pub fn get_mean_reversion_speed(&self)->f64{
+        self.mean_reversion_speed
+    }
+
+    /// Returns the long term mean.
+    pub fn get_long_term_mean(&self)->f64{
+        self.long_term_mean
+    }
+
+    /// Returns the volatility.
+    pub fn get_volatility(&self)->f64{
+        self.volatility
+    }
+
+    /// Returns true if `2*kappa*theta >= sigma^2`, i.e. if the rate never reaches zero.
+    pub fn satisfies_feller_condition(&self)->bool{
+        2.0*self.mean_reversion_speed*self.long_term_mean >= self.volatility*self.volatility
+    }
+
+    /// Returns the expected short rate at `time`.
+    pub fn get_mean(&self, time: f64)->f64{
+        let decay = (-self.mean_reversion_speed*time).exp();
+        self.long_term_mean+(self.initial_rate-self.long_term_mean)*decay
+    }
+
+    /// Returns the variance of the short rate at `time`.
+    pub fn get_variance(&self, time: f64)->f64{
+        let (kappa, sigma_squared) = (self.mean_reversion_speed, self.volatility*self.volatility);
+        if kappa == 0.0 {
+            return sigma_squared*self.initial_rate*time;
+        }
+        let decay = (-kappa*time).exp();
+        sigma_squared/kappa*(self.initial_rate*(decay-decay*decay)+0.5*self.long_term_mean*(1.0-decay)*(1.0-decay))
+    }
+
+    /// Returns the price at time 0 of a zero coupon bond paying 1 at `maturity`.
+    pub fn zero_coupon_bond_price(&self, maturity: f64)->f64{
+        self.bond_price(self.initial_rate, maturity)
+    }
+
+    /// Returns the price of a zero coupon bond paying 1 `time_to_maturity` from now, when the short rate is `rate`.
+    pub fn bond_price(&self, rate: f64, time_to_maturity: f64)->f64{
+        let (log_a, b) = self.affine_coefficients(time_to_maturity);
+        (log_a-b*rate).exp()
+    }
+
+    /// Returns the continuously compounded yield of the zero coupon bond paying 1 at `maturity`.
+    pub fn zero_coupon_yield(&self, maturity: f64)->f64{
+        if maturity == 0.0 {
+            return self.initial_rate;
+        }
+        -self.zero_coupon_bond_price(maturity).ln()/maturity
+    }
+
+    /// Samples the short rate `time_step` after it had the value `rate`, exactly, as a scaled noncentral chi-squared sample.
+    pub fn sample_transition(&self, rate: f64, time_step: f64, rng: &mut impl RandomNumberGeneratorTrait)->f64{
+        let (kappa, theta, sigma) = (self.mean_reversion_speed, self.long_term_mean, self.volatility);
+        let decay = (-kappa*time_step).exp();
+        if sigma == 0.0 {
+            return theta+(rate-theta)*decay;
+        }
+        let scale = if kappa*time_step < 1e-8 {
+            0.25*sigma*sigma*time_step
+        }
+        else {
+            sigma*sigma*(1.0-decay)/(4.0*kappa)
+        };
+        let degrees_of_freedom = 4.0*kappa*theta/(sigma*sigma);
+        scale*rng.get_noncentral_chi_squareds(degrees_of_freedom, rate*decay/scale, 1)[0]
+    }
+
+    /// Generates the short rates at `times`, starting from the initial rate and sampling every step exactly.
+    /// # Panics
+    /// Panics if `times` is not increasing or contains negative times.
+    pub fn generate_path(&self, times: &[f64], rng: &mut impl RandomNumberGeneratorTrait)->Vec<f64>{
+        let mut previous_time = 0.0;
+        let mut rate = self.initial_rate;
+        times.iter().map(|t| {
+            assert!(*t >= previous_time, "The times must be increasing and non negative");
+            rate = self.sample_transition(rate, t-previous_time, rng);
+            previous_time = *t;
+            rate
+        }).collect()
+    }
+
+    /// Returns `ln(A)` and `B` such that the bond price is `A*exp(-B*r)`.
+    fn affine_coefficients(&self, time_to_maturity: f64)->(f64, f64){
+        let (kappa, theta, sigma) = (self.mean_reversion_speed, self.long_term_mean, self.volatility);
+        if sigma == 0.0 {
+            // The rate is deterministic, and its integral is theta*tau+(r-theta)*B.
+            let b = if kappa*time_to_maturity < 1e-8 { time_to_maturity } else { (1.0-(-kappa*time_to_maturity).exp())/kappa };
+            return (-theta*(time_to_maturity-b), b);
+        }
+        let h = (kappa*kappa+2.0*sigma*sigma).sqrt();
+        let growth = (h*time_to_maturity).exp_m1();
+        let denominator = (kappa+h)*growth+2.0*h;
+        let b = 2.0*growth/denominator;
+        let log_a = 2.0*kappa*theta/(sigma*sigma)*((2.0*h).ln()+0.5*(kappa+h)*time_to_maturity-denominator.ln());
+        (log_a, b)
+    }
+}
+
+/// The Vasicek model, in which the short rate follows `dr = kappa*(theta-r)*dt + sigma*dW`.
+///
+/// The rate is normally distributed and can be negative. Zero coupon bonds are lognormal, so options on them have Black-Scholes
+/// like prices, and options on coupon bonds are priced exactly with the decomposition of Jamshidian (1989).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VasicekModel{
+    /// The current short rate.
+    initial_rate: f64,
+    /// The speed `kappa` at which the rate reverts to its long term mean.
+    mean_reversion_speed: f64,
+    /// The long term mean `theta`.
+    long_term_mean: f64,
+    /// The volatility `sigma`.
+    volatility: f64,
+}
+
+impl VasicekModel {
+    /// Returns a new Vasicek model.
+    /// # Parameters
+    /// - `initial_rate` - The current short rate.
+    /// - `mean_reversion_speed` - The speed `kappa` at which the rate reverts to its long term mean.
+    /// - `long_term_mean` - The long term mean `theta`.
+    /// - `volatility` - The volatility `sigma`.
+    /// # Panics
+    /// Panics if `mean_reversion_speed` or `volatility` is negative.
+    pub fn new(initial_rate: f64, mean_reversion_speed: f64, long_term_mean: f64, volatility: f64)->VasicekModel{
+        match VasicekModel::try_new(initial_rate, mean_reversion_speed, long_term_mean, volatility) {
+            Ok(model) => model,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `new`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_new(initial_rate: f64, mean_reversion_speed: f64, long_term_mean: f64, volatility: f64)->Result<VasicekModel, PricingError>{
+        if [mean_reversion_speed, volatility].iter().any(|x| *x < 0.0 || x.is_nan()) || initial_rate.is_nan() || long_term_mean.is_nan() {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(VasicekModel{initial_rate, mean_reversion_speed, long_term_mean, volatility})
+    }
+
+    /// Returns the current short rate.
+    pub fn get_initial_rate(&self)->f64{
+        self.initial_rate
+    }
+
+    /// Returns the speed of mean reversion.
+    pub fn get_mean_reversion_speed(&self)->f64{
+        self.mean_reversion_speed
+    }
+
+    /// Returns the long term mean.
+    pub fn get_long_term_mean(&self)->f64{
+        self.long_term_mean
+    }
+
+    /// Returns the volatility.
+    pub fn get_volatility(&self)->f64{
+        self.volatility
+    }
+
+    /// Returns the expected short rate at `time`.
+    pub fn get_mean(&self, time: f64)->f64{
+        self.long_term_mean+(self.initial_rate-self.long_term_mean)*(-self.mean_reversion_speed*time).exp()
+    }
+
+    /// Returns the variance of the short rate at `time`.
+    pub fn get_variance(&self, time: f64)->f64{
+        self.volatility*self.volatility*self.decay_integral(2.0*self.mean_reversion_speed, time)
+    }
+
+    /// Returns the price at time 0 of a zero coupon bond paying 1 at `maturity`.
+    pub fn zero_coupon_bond_price(&self, maturity: f64)->f64{
+        self.bond_price(self.initial_rate, maturity)
+    }
+
+    /// Returns the price of a zero coupon bond paying 1 `time_to_maturity` from now, when the short rate is `rate`.
+    pub fn bond_price(&self, rate: f64, time_to_maturity: f64)->f64{
+        let (kappa, theta, sigma) = (self.mean_reversion_speed, self.long_term_mean, self.volatility);
+        let b = self.decay_integral(kappa, time_to_maturity);
+        // The integral of the rate is normal, with mean theta*tau+(r-theta)*B and the variance below.
+        let variance = if kappa*time_to_maturity < 1e-4 {
+            sigma*sigma*time_to_maturity.powi(3)/3.0
+        }
+        else {
+            sigma*sigma/(kappa*kappa)*(time_to_maturity-2.0*b+self.decay_integral(2.0*kappa, time_to_maturity))
+        };
+        (-theta*time_to_maturity-(rate-theta)*b+0.5*variance).exp()
+    }
+
+    /// Returns the continuously compounded yield of the zero coupon bond paying 1 at `maturity`.
+    pub fn zero_coupon_yield(&self, maturity: f64)->f64{
+        if maturity == 0.0 {
+            return self.initial_rate;
+        }
+        -self.zero_coupon_bond_price(maturity).ln()/maturity
+    }
+
+    /// Samples the short rate `time_step` after it had the value `rate`, exactly, given a sample of N(0,1).
+    pub fn sample_transition(&self, rate: f64, time_step: f64, gaussian_sample: f64)->f64{
+        let decay = (-self.mean_reversion_speed*time_step).exp();
+        let variance = self.volatility*self.volatility*self.decay_integral(2.0*self.mean_reversion_speed, time_step);
+        self.long_term_mean+(rate-self.long_term_mean)*decay+variance.sqrt()*gaussian_sample
+    }
+
+    /// Generates the short rates at `times`, starting from the initial rate and sampling every step exactly.
+    /// # Parameters
+    /// - `gaussians` - iid samples of N(0,1). Must be the same size or larger than `times`.
+    /// - `times` - Increasing, non negative times.
+    /// # Errors
+    /// Returns `PricingError::InsufficientSamples` if there are less samples than times, and `PricingError::InvalidTimeGrid`
+    /// if the times are negative or decreasing.
+    pub fn generate_path(&self, gaussians: &[f64], times: &[f64])->Result<Vec<f64>, PricingError>{
+        if gaussians.len() < times.len() {
+            return Err(PricingError::InsufficientSamples);
+        }
+        let mut previous_time = 0.0;
+        let mut rate = self.initial_rate;
+        times.iter().zip(gaussians).map(|(t, z)| {
+            if *t < previous_time || t.is_nan() {
+                return Err(PricingError::InvalidTimeGrid);
+            }
+            rate = self.sample_transition(rate, t-previous_time, *z);
+            previous_time = *t;
+            Ok(rate)
+        }).collect()
+    }
+
+    /// Returns the price of a european option expiring at `expiry` on the zero coupon bond paying 1 at `maturity`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `strike` or `expiry` is negative, or if `expiry` is after `maturity`.
+    pub fn zero_coupon_bond_option_price(&self, vanilla_type: VanillaType, strike: f64, expiry: f64, maturity: f64)->f64{
+        match self.try_zero_coupon_bond_option_price(vanilla_type, strike, expiry, maturity) {
+            Ok(price) => price,
+            Err(_) => panic!("One of the parameters is negative"),
+        }
+    }
+
+    /// Same as `zero_coupon_bond_option_price`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_zero_coupon_bond_option_price(&self, vanilla_type: VanillaType, strike: f64, expiry: f64, maturity: f64)
+        ->Result<f64, PricingError>{
+        if strike < 0.0 || expiry < 0.0 || maturity < expiry || strike.is_nan() || maturity.is_nan() {
+            return Err(PricingError::NegativeInput);
+        }
+        let (bond, discount_factor) = (self.zero_coupon_bond_price(maturity), self.zero_coupon_bond_price(expiry));
+        // The volatility of the log of the forward bond price P(T,S)/P(T,T) over [0,T].
+        let deviation = self.volatility*self.decay_integral(self.mean_reversion_speed, maturity-expiry)
+            *self.decay_integral(2.0*self.mean_reversion_speed, expiry).sqrt();
+        let (call, put) = if deviation > 0.0 && strike > 0.0 {
+            let h = (bond/(discount_factor*strike)).ln()/deviation+0.5*deviation;
+            (bond*cumulative_normal_function(h)-strike*discount_factor*cumulative_normal_function(h-deviation),
+                strike*discount_factor*cumulative_normal_function(deviation-h)-bond*cumulative_normal_function(-h))
+        }
+        else {
+            (f64::max(bond-strike*discount_factor, 0.0), f64::max(strike*discount_factor-bond, 0.0))
+        };
+        Ok(match vanilla_type {
+            VanillaType::Call => call,
+            VanillaType::Put => put,
+        })
+    }
+
+    /// Returns the price of a european option expiring at `expiry` on a coupon bond, with Jamshidian's decomposition into
+    /// options on the zero coupon bonds of its cash flows.
+    ///
+    /// # Parameters
+    /// - `vanilla_type` - Call or put.
+    /// - `strike` - The price at which the bond (without the cash flows paid before expiry) is bought or sold at expiry.
+    /// - `expiry` - The expiry of the option.
+    /// - `payment_times` - The times of the cash flows, including the repayment of the principal.
+    /// - `cash_flows` - The cash flows, which must be positive.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PricingError::NegativeInput` if `strike` or `expiry` is negative, and `PricingError::InvalidMarketData` if the
+    /// slices have different lengths or if a cash flow is not positive. Cash flows paid at or before expiry are ignored.
+    pub fn coupon_bond_option_price(&self, vanilla_type: VanillaType, strike: f64, expiry: f64, payment_times: &[f64], cash_flows: &[f64])
+        ->Result<f64, PricingError>{
+        if strike < 0.0 || expiry < 0.0 || strike.is_nan() || expiry.is_nan() {
+            return Err(PricingError::NegativeInput);
+        }
+        if payment_times.len() != cash_flows.len() || !cash_flows.iter().all(|c| *c > 0.0) {
+            return Err(PricingError::InvalidMarketData);
+        }
+        let remaining: Vec<(f64, f64)> = payment_times.iter().zip(cash_flows).filter(|(t, _)| **t > expiry).map(|(t, c)| (*t, *c)).collect();
+        if remaining.is_empty() || strike == 0.0 {
+            let value: f64 = remaining.iter().map(|(t, c)| c*self.zero_coupon_bond_price(*t)).sum();
+            return Ok(match vanilla_type {
+                VanillaType::Call => value,
+                VanillaType::Put => 0.0,
+            });
+        }
+        // The bond price at expiry decreases in the rate, so it equals the strike at a unique critical rate.
+        let excess = |rate: f64| remaining.iter().map(|(t, c)| c*self.bond_price(rate, t-expiry)).sum::<f64>()-strike;
+        let (mut low, mut high) = (-1.0, 1.0);
+        while excess(low) < 0.0 && low > -1e3 {
+            low *= 2.0;
+        }
+        while excess(high) > 0.0 && high < 1e3 {
+            high *= 2.0;
+        }
+        let critical_rate = brent(&excess, low, high, CRITICAL_RATE_TOLERANCE)?;
+        remaining.iter().map(|(t, c)| {
+            let strike = self.bond_price(critical_rate, t-expiry);
+            self.try_zero_coupon_bond_option_price(vanilla_type, strike, expiry, *t).map(|price| c*price)
+        }).sum()
+    }
+
+    /// Returns `(1-exp(-rate*time))/rate`, or its limit `time` for a vanishing rate.
+    fn decay_integral(&self, rate: f64, time: f64)->f64{
+        if rate*time < 1e-8 {
+            return time;
+        }
+        -(-rate*time).exp_m1()/rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random_number_generator::RandomNumberGenerator;
+
+    #[test]
+    fn cir_bond_test(){
+        let model = CoxIngersollRossModel::new(0.05, 0.8, 0.03, 0.15);
+        assert!((model.zero_coupon_bond_price(0.0)-1.0).abs() < 1e-15);
+        assert!((model.zero_coupon_yield(1e-6)-0.05).abs() < 1e-6);
+        // Monte Carlo estimate of E[exp(-integral of r)], with the integral approximated by the trapezoidal rule.
+        let mut rng = RandomNumberGenerator::new(Some(7));
+        let times: Vec<f64> = (1..=200).map(|i| i as f64*0.01).collect();
+        let paths = 4000;
+        let estimate = (0..paths).map(|_| {
+            let path = model.generate_path(&times, &mut rng);
+            let integral = 0.005*(model.get_initial_rate()+path[199])+0.01*path[..199].iter().sum::<f64>();
+            (-integral).exp()
+        }).sum::<f64>()/paths as f64;
+        assert!((estimate-model.zero_coupon_bond_price(2.0)).abs() < 1e-3);
+        let deterministic = CoxIngersollRossModel::new(0.05, 0.8, 0.03, 0.0);
+        let integral = 0.03*2.0+0.02*(1.0-(-1.6_f64).exp())/0.8;
+        assert!((deterministic.zero_coupon_bond_price(2.0)-(-integral).exp()).abs() < 1e-14);
+        assert_eq!(CoxIngersollRossModel::try_new(-0.01, 0.8, 0.03, 0.15), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn cir_exact_sampling_test(){
+        // The Feller condition fails, so the rate often gets close to zero, where an Euler scheme would need truncation.
+        let model = CoxIngersollRossModel::new(0.02, 0.5, 0.02, 0.3);
+        assert!(!model.satisfies_feller_condition());
+        let mut rng = RandomNumberGenerator::new(Some(3));
+        let samples: Vec<f64> = (0..100000).map(|_| model.sample_transition(model.get_initial_rate(), 2.0, &mut rng)).collect();
+        let mean = samples.iter().sum::<f64>()/samples.len() as f64;
+        let variance = samples.iter().map(|r| (r-mean).powi(2)).sum::<f64>()/samples.len() as f64;
+        assert!(samples.iter().all(|r| *r >= 0.0));
+        assert!((mean-model.get_mean(2.0)).abs() < 5e-4);
+        assert!((variance-model.get_variance(2.0)).abs() < 0.05*model.get_variance(2.0));
+    }
+
+    #[test]
+    fn vasicek_bond_test(){
+        let model = VasicekModel::new(0.02, 0.6, 0.05, 0.02);
+        let (kappa, theta, sigma): (f64, f64, f64) = (0.6, 0.05, 0.02);
+        let tau = 3.0;
+        let b = (1.0-(-kappa*tau).exp())/kappa;
+        let log_a = (theta-sigma*sigma/(2.0*kappa*kappa))*(b-tau)-sigma*sigma*b*b/(4.0*kappa);
+        assert!((model.zero_coupon_bond_price(tau)-(log_a-b*0.02).exp()).abs() < 1e-14);
+        let gaussians = RandomNumberGenerator::new(Some(5)).get_gaussians(100000);
+        let samples: Vec<f64> = gaussians.iter().map(|z| model.generate_path(&[*z], &[1.5]).unwrap()[0]).collect();
+        let mean = samples.iter().sum::<f64>()/samples.len() as f64;
+        let variance = samples.iter().map(|r| (r-mean).powi(2)).sum::<f64>()/samples.len() as f64;
+        assert!((mean-model.get_mean(1.5)).abs() < 1e-4);
+        assert!((variance-model.get_variance(1.5)).abs() < 0.02*model.get_variance(1.5));
+        assert_eq!(model.generate_path(&[0.0, 0.0], &[1.0, 0.5]), Err(PricingError::InvalidTimeGrid));
+    }
+
+    #[test]
+    fn vasicek_bond_option_test(){
+        let model = VasicekModel::new(0.03, 0.4, 0.045, 0.015);
+        let (strike, expiry, maturity) = (0.93, 1.0, 3.0);
+        let call = model.zero_coupon_bond_option_price(VanillaType::Call, strike, expiry, maturity);
+        let put = model.zero_coupon_bond_option_price(VanillaType::Put, strike, expiry, maturity);
+        let parity = model.zero_coupon_bond_price(maturity)-strike*model.zero_coupon_bond_price(expiry);
+        assert!((call-put-parity).abs() < 1e-14);
+        // A coupon bond with a single cash flow is a zero coupon bond.
+        let single = model.coupon_bond_option_price(VanillaType::Call, 2.0*strike, expiry, &[maturity], &[2.0]).unwrap();
+        assert!((single-2.0*call).abs() < 1e-12);
+        // Monte Carlo check of Jamshidian's decomposition, discounting with the trapezoidal integral of the simulated rates.
+        let (times, cash_flows) = ([1.5, 2.0, 2.5, 3.0], [0.02, 0.02, 0.02, 1.02]);
+        let bond_strike = 1.0;
+        let price = model.coupon_bond_option_price(VanillaType::Put, bond_strike, expiry, &times, &cash_flows).unwrap();
+        let steps = 50;
+        let grid: Vec<f64> = (1..=steps).map(|i| i as f64*expiry/steps as f64).collect();
+        let mut rng = RandomNumberGenerator::new(Some(9));
+        let paths = 20000;
+        let payoffs: Vec<f64> = (0..paths).map(|_| {
+            let path = model.generate_path(&rng.get_gaussians(steps), &grid).unwrap();
+            let dt = expiry/steps as f64;
+            let integral = 0.5*dt*(model.get_initial_rate()+path[steps-1])+dt*path[..steps-1].iter().sum::<f64>();
+            let bond: f64 = times.iter().zip(cash_flows).map(|(t, c)| c*model.bond_price(path[steps-1], t-expiry)).sum();
+            (-integral).exp()*f64::max(bond_strike-bond, 0.0)
+        }).collect();
+        let mean = payoffs.iter().sum::<f64>()/paths as f64;
+        let error = (payoffs.iter().map(|p| (p-mean).powi(2)).sum::<f64>()/paths as f64/paths as f64).sqrt();
+        assert!((mean-price).abs() < 4.0*error+1e-5);
+        assert!(model.coupon_bond_option_price(VanillaType::Put, 1.0, 1.0, &[2.0], &[-1.0]).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 //! Provides an interface for statistics gatherers for collecting results of Monte Carlo simulations.
 
+use crate::stock::StockState;
+
 ///An interface for statistics gatherers.
 pub trait StatisticsGathererTrait{
     ///This function collects statistics from the given `result`.
@@ -9,9 +11,14 @@ pub trait StatisticsGathererTrait{
 }
 
 ///A statistics gatherer that computes the mean of all gathered results.
+///The results are summed with Neumaier's compensated summation, so that the mean of very many results of similar magnitude
+///does not lose precision to rounding errors.
+#[derive(Clone, Debug, PartialEq)]
 pub struct MeanStatisticsGatherer{
     ///Sum of all results so far.
     running_sum: f64,
+    ///The rounding error lost by `running_sum`, which is added back to it when the mean is computed.
+    compensation: f64,
     ///Number of results collected.
     paths_done: usize,
 }
@@ -21,24 +28,158 @@ impl MeanStatisticsGatherer {
     pub fn new() -> MeanStatisticsGatherer{
         MeanStatisticsGatherer{
             running_sum: 0.0,
+            compensation: 0.0,
             paths_done: 0,
         }
     }
 }
 
+impl Default for MeanStatisticsGatherer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StatisticsGathererTrait for MeanStatisticsGatherer {
     ///Adds the given `result` to the gatherer.
     fn dump_one_result(&mut self, result: f64){
-        self.running_sum+=result;
+        let sum = self.running_sum+result;
+        if self.running_sum.abs() >= result.abs() {
+            self.compensation += (self.running_sum-sum)+result;
+        }
+        else {
+            self.compensation += (result-sum)+self.running_sum;
+        }
+        self.running_sum = sum;
         self.paths_done+=1;
     }
 
     ///Returns the current mean of all gathered results wraped in a two dimensional `Vec`.
     fn get_results_so_far(&self) -> Vec<Vec<f64>>{
-        vec![vec![(self.running_sum/self.paths_done as f64)]]
+        vec![vec![((self.running_sum+self.compensation)/self.paths_done as f64)]]
+    }
+}
+
+///A statistics gatherer that computes the mean, the variance and the standard error of the gathered results with Welford's
+///streaming updates, which are numerically stable even when the mean is much larger than the standard deviation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeanVarianceStatisticsGatherer{
+    ///The mean of the results so far.
+    mean: f64,
+    ///The sum of the squared deviations of the results so far from their mean.
+    sum_of_squared_deviations: f64,
+    ///Number of results collected.
+    paths_done: usize,
+}
+
+impl MeanVarianceStatisticsGatherer {
+    ///Returns a new statistics gatherer.
+    pub fn new() -> MeanVarianceStatisticsGatherer{
+        MeanVarianceStatisticsGatherer::default()
+    }
+
+    ///Returns the mean of the results so far.
+    pub fn get_mean(&self) -> f64{
+        self.mean
+    }
+
+    ///Returns the sample variance of the results so far (zero if there are less than two results).
+    pub fn get_variance(&self) -> f64{
+        if self.paths_done < 2 {
+            return 0.0;
+        }
+        self.sum_of_squared_deviations/(self.paths_done-1) as f64
+    }
+
+    ///Returns the standard error of the mean of the results so far.
+    pub fn get_standard_error(&self) -> f64{
+        (self.get_variance()/self.paths_done as f64).sqrt()
+    }
+
+    ///Returns the number of results collected.
+    pub fn get_paths_done(&self) -> usize{
+        self.paths_done
     }
 }
 
+impl StatisticsGathererTrait for MeanVarianceStatisticsGatherer {
+    ///Adds the given `result` to the gatherer.
+    fn dump_one_result(&mut self, result: f64){
+        self.paths_done+=1;
+        let deviation = result-self.mean;
+        self.mean += deviation/self.paths_done as f64;
+        self.sum_of_squared_deviations += deviation*(result-self.mean);
+    }
+
+    ///Returns the mean and the standard error of the gathered results as `vec![vec![mean, standard_error]]`.
+    fn get_results_so_far(&self) -> Vec<Vec<f64>>{
+        vec![vec![self.mean, self.get_standard_error()]]
+    }
+}
+
+///A statistics gatherer that computes the mean of all gathered results, and also stores the first simulated paths of the
+///underlying together with their discounted payoffs, for debugging payoff logic and for plotting.
+///Paths are recorded by `monte_carlo_simulation_storing_paths` in the `monte_carlo_pricer` module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathStoringGatherer{
+    ///The maximal number of paths to store.
+    maximal_number_of_paths: usize,
+    ///The stored paths.
+    paths: Vec<Vec<StockState>>,
+    ///The discounted payoffs of the stored paths.
+    path_results: Vec<f64>,
+    ///The mean of all results.
+    mean: MeanStatisticsGatherer,
+}
+
+impl PathStoringGatherer {
+    ///Returns a new statistics gatherer which stores up to `maximal_number_of_paths` paths.
+    pub fn new(maximal_number_of_paths: usize) -> PathStoringGatherer{
+        PathStoringGatherer{
+            maximal_number_of_paths,
+            paths: Vec::new(),
+            path_results: Vec::new(),
+            mean: MeanStatisticsGatherer::new(),
+        }
+    }
+
+    ///Returns `true` if the gatherer stores no more paths.
+    pub fn is_full(&self) -> bool{
+        self.paths.len() >= self.maximal_number_of_paths
+    }
+
+    ///Adds the given `result` to the gatherer, and stores `path` with it unless the gatherer is full.
+    pub fn dump_one_path(&mut self, path: Vec<StockState>, result: f64){
+        if !self.is_full() {
+            self.paths.push(path);
+            self.path_results.push(result);
+        }
+        self.mean.dump_one_result(result);
+    }
+
+    ///Returns the stored paths.
+    pub fn get_paths(&self) -> &[Vec<StockState>]{
+        &self.paths
+    }
+
+    ///Returns the discounted payoffs of the stored paths.
+    pub fn get_path_results(&self) -> &[f64]{
+        &self.path_results
+    }
+}
+
+impl StatisticsGathererTrait for PathStoringGatherer {
+    ///Adds the given `result` to the gatherer, without a path.
+    fn dump_one_result(&mut self, result: f64){
+        self.mean.dump_one_result(result);
+    }
+
+    ///Returns the mean of all gathered results, followed by the discounted payoffs of the stored paths, as
+    ///`vec![vec![mean], path_results]`.
+    fn get_results_so_far(&self) -> Vec<Vec<f64>>{
+        vec![self.mean.get_results_so_far()[0].clone(), self.path_results.clone()]
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -78,4 +219,29 @@ mod tests {
         }
         println!("{}",sg.get_results_so_far()[0][0]);
     }
+
+    #[test]
+    fn compensated_summation_test(){
+        let mut sg = MeanStatisticsGatherer::new();
+        sg.dump_one_result(1e16);
+        sg.dump_one_result(1.0);
+        sg.dump_one_result(-1e16);
+        assert_eq!(sg.get_results_so_far()[0][0], 1.0/3.0);
+        let mut sg = MeanStatisticsGatherer::new();
+        for _ in 0..1000000 {
+            sg.dump_one_result(0.1);
+        }
+        assert_eq!(sg.get_results_so_far()[0][0], 0.1);
+    }
+
+    #[test]
+    fn mean_variance_gatherer_test(){
+        let mut sg = MeanVarianceStatisticsGatherer::new();
+        for x in [1e9+4.0, 1e9+7.0, 1e9+13.0, 1e9+16.0] {
+            sg.dump_one_result(x);
+        }
+        assert_eq!(sg.get_mean(), 1e9+10.0);
+        assert_eq!(sg.get_variance(), 30.0);
+        assert_eq!(sg.get_results_so_far()[0][1], (7.5_f64).sqrt());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,265 @@
+//! Provides estimators of the volatility and the drift of a stock from its price history, whose results can be used to create
+//! a `GeometricBrownianMotionStock`.
+//!
+//! The close-to-close estimator uses a series of prices, given as `StockState`s or as dates and prices, whose times may be
+//! irregularly spaced. The range based estimators of Parkinson, Garman and Klass, and Yang and Zhang use the open, high, low
+//! and close prices of bars of equal length (e.g. trading days) and are several times more efficient. All volatilities and
+//! drifts are annualized.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::stats::close_to_close;
+//! use derivative_pricer::stock::StockState;
+//! use derivative_pricer::utils::{NonNegativeFloat, TimeStamp};
+//! let prices = [100.0, 101.0, 99.5, 102.0, 103.0];
+//! let states: Vec<StockState> = prices.iter().enumerate()
+//!     .map(|(i, price)| StockState::new(NonNegativeFloat::from(*price), TimeStamp::from(i as f64/252.0))).collect();
+//! let estimate = close_to_close(&states).unwrap();
+//! let stock = estimate.to_stock(NonNegativeFloat::from(103.0), TimeStamp::from(0.0), NonNegativeFloat::from(0.0));
+//! assert_eq!(f64::from(stock.get_volatility()), estimate.get_volatility());
+//! ```
+
+use crate::error::PricingError;
+use crate::stock::{GeometricBrownianMotionStock, StockState};
+use crate::time::{Date, TimeAxis};
+use crate::utils::{NonNegativeFloat, TimeStamp};
+
+/// An estimate of the annualized volatility and drift of a stock.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VolatilityEstimate{
+    /// The annualized volatility.
+    volatility: f64,
+    /// The annualized drift, i.e. the expected rate of return.
+    drift: f64,
+    /// The number of returns or bars the estimate is based on.
+    observations: usize,
+}
+
+impl VolatilityEstimate {
+    /// Returns the annualized volatility.
+    pub fn get_volatility(&self)->f64{
+        self.volatility
+    }
+
+    /// Returns the annualized drift, i.e. the expected rate of return `mu` of the stock, which exceeds the mean log return by
+    /// half the variance.
+    pub fn get_drift(&self)->f64{
+        self.drift
+    }
+
+    /// Returns the number of returns or bars the estimate is based on.
+    pub fn get_observations(&self)->usize{
+        self.observations
+    }
+
+    /// Returns a stock with the estimated volatility and drift and the given price, current time and divident rate.
+    pub fn to_stock(&self, price: NonNegativeFloat, current_time: TimeStamp, divident_rate: NonNegativeFloat)->GeometricBrownianMotionStock{
+        GeometricBrownianMotionStock::new(price, current_time, self.drift, NonNegativeFloat::from(self.volatility), divident_rate)
+    }
+}
+
+/// The open, high, low and close prices of a period of trading.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OhlcBar{
+    /// The first price of the period.
+    pub open: f64,
+    /// The highest price of the period.
+    pub high: f64,
+    /// The lowest price of the period.
+    pub low: f64,
+    /// The last price of the period.
+    pub close: f64,
+}
+
+impl OhlcBar {
+    /// Returns the bar with the given prices.
+    pub fn new(open: f64, high: f64, low: f64, close: f64)->OhlcBar{
+        OhlcBar{open, high, low, close}
+    }
+
+    /// Returns true if the prices are positive and the high and low bound the open and the close.
+    fn is_valid(&self)->bool{
+        self.low > 0.0 && self.low <= self.open.min(self.close) && self.high >= self.open.max(self.close)
+    }
+}
+
+/// The estimators of the volatility from bars.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VolatilityEstimator{
+    /// The sample standard deviation of the close-to-close log returns.
+    CloseToClose,
+    /// Parkinson's estimator from the high-low ranges, which assumes no drift and ignores overnight jumps.
+    Parkinson,
+    /// Garman and Klass' estimator from the ranges and the open-to-close returns, which assumes no drift and ignores overnight
+    /// jumps.
+    GarmanKlass,
+    /// Yang and Zhang's estimator, combining the overnight returns, the open-to-close returns and Rogers and Satchell's range
+    /// estimator. It is unbiased under drift and overnight jumps, and needs at least three bars.
+    YangZhang,
+}
+
+/// Returns the close-to-close estimate of the volatility and the drift from the prices `states`, whose times are year fractions.
+/// The log returns are weighted by the lengths of their periods, so the times may be irregularly spaced.
+///
+/// # Errors
+///
+/// Returns `PricingError::InsufficientSamples` if there are less than three prices, `PricingError::InvalidTimeGrid` if the times
+/// are not strictly increasing, and `PricingError::InvalidMarketData` if a price is not positive.
+pub fn close_to_close(states: &[StockState])->Result<VolatilityEstimate, PricingError>{
+    if states.len() < 3 {
+        return Err(PricingError::InsufficientSamples);
+    }
+    if states.windows(2).any(|w| w[0].get_time() >= w[1].get_time()) {
+        return Err(PricingError::InvalidTimeGrid);
+    }
+    if states.iter().any(|state| f64::from(state.get_value()) <= 0.0) {
+        return Err(PricingError::InvalidMarketData);
+    }
+    let returns: Vec<(f64, f64)> = states.windows(2).map(|w| {
+        let log_return = (f64::from(w[1].get_value())/f64::from(w[0].get_value())).ln();
+        (log_return, f64::from(w[1].get_time())-f64::from(w[0].get_time()))
+    }).collect();
+    let mean_log_return = returns.iter().map(|(r, _)| r).sum::<f64>()/returns.iter().map(|(_, dt)| dt).sum::<f64>();
+    let variance = returns.iter().map(|(r, dt)| (r-mean_log_return*dt).powi(2)/dt).sum::<f64>()/(returns.len()-1) as f64;
+    Ok(VolatilityEstimate{
+        volatility: variance.sqrt(),
+        drift: mean_log_return+0.5*variance,
+        observations: returns.len(),
+    })
+}
+
+/// Same as `close_to_close`, but with the prices given at dates, which are converted to year fractions by `time_axis`.
+///
+/// # Errors
+///
+/// Returns the errors of `close_to_close`, and `PricingError::InvalidDate` if a date is before the reference date of `time_axis`.
+pub fn close_to_close_from_dates<D: Into<Date>+Copy>(prices: &[(D, f64)], time_axis: &TimeAxis)->Result<VolatilityEstimate, PricingError>{
+    let states = prices.iter().map(|(date, price)| {
+        let price = if *price >= 0.0 { NonNegativeFloat::from(*price) } else { return Err(PricingError::InvalidMarketData) };
+        Ok(StockState::new(price, time_axis.time_stamp(*date)?))
+    }).collect::<Result<Vec<StockState>, PricingError>>()?;
+    close_to_close(&states)
+}
+
+/// Returns the estimate of the volatility and the drift from the consecutive `bars`, each covering `1/periods_per_year` years
+/// (e.g. `periods_per_year` is 252 for daily bars). The drift is estimated from the log return from the first open to the last
+/// close.
+///
+/// # Errors
+///
+/// Returns `PricingError::InsufficientSamples` if there are too few bars (two, or three for `YangZhang`), and
+/// `PricingError::InvalidMarketData` if a price is not positive or a bar's high and low do not bound its open and close.
+pub fn estimate_volatility(bars: &[OhlcBar], estimator: VolatilityEstimator, periods_per_year: f64)->Result<VolatilityEstimate, PricingError>{
+    let minimal_bars = if estimator == VolatilityEstimator::YangZhang { 3 } else { 2 };
+    if bars.len() < minimal_bars {
+        return Err(PricingError::InsufficientSamples);
+    }
+    if bars.iter().any(|bar| !bar.is_valid()) {
+        return Err(PricingError::InvalidMarketData);
+    }
+    let n = bars.len() as f64;
+    let squared_log_range = |bar: &OhlcBar| (bar.high/bar.low).ln().powi(2);
+    let period_variance = match estimator {
+        VolatilityEstimator::CloseToClose => {
+            let returns: Vec<f64> = bars.windows(2).map(|w| (w[1].close/w[0].close).ln()).collect();
+            sample_variance(&returns)
+        },
+        VolatilityEstimator::Parkinson => bars.iter().map(squared_log_range).sum::<f64>()/(4.0*core::f64::consts::LN_2*n),
+        VolatilityEstimator::GarmanKlass => bars.iter()
+            .map(|bar| 0.5*squared_log_range(bar)-(2.0*core::f64::consts::LN_2-1.0)*(bar.close/bar.open).ln().powi(2))
+            .sum::<f64>()/n,
+        VolatilityEstimator::YangZhang => {
+            let overnight: Vec<f64> = bars.windows(2).map(|w| (w[1].open/w[0].close).ln()).collect();
+            let open_to_close: Vec<f64> = bars[1..].iter().map(|bar| (bar.close/bar.open).ln()).collect();
+            let rogers_satchell = bars[1..].iter().map(|bar| (bar.high/bar.close).ln()*(bar.high/bar.open).ln()
+                +(bar.low/bar.close).ln()*(bar.low/bar.open).ln()).sum::<f64>()/overnight.len() as f64;
+            let m = overnight.len() as f64;
+            let k = 0.34/(1.34+(m+1.0)/(m-1.0));
+            sample_variance(&overnight)+k*sample_variance(&open_to_close)+(1.0-k)*rogers_satchell
+        },
+    };
+    let variance = period_variance*periods_per_year;
+    let mean_log_return = (bars[bars.len()-1].close/bars[0].open).ln()*periods_per_year/n;
+    Ok(VolatilityEstimate{
+        volatility: variance.sqrt(),
+        drift: mean_log_return+0.5*variance,
+        observations: bars.len(),
+    })
+}
+
+/// Returns the sample variance of `values`, which has at least two elements.
+fn sample_variance(values: &[f64])->f64{
+    let mean = values.iter().sum::<f64>()/values.len() as f64;
+    values.iter().map(|x| (x-mean).powi(2)).sum::<f64>()/(values.len()-1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random_number_generator::{RandomNumberGenerator, RandomNumberGeneratorTrait};
+    use crate::time::DayCountConvention;
+    use crate::utils::Duration;
+
+    /// Returns daily bars of a stock with volatility 0.3 and drift 0.1, simulated with 1000 steps a day and an overnight gap of
+    /// a third of a day.
+    fn simulated_bars(days: usize)->Vec<OhlcBar>{
+        let (volatility, drift, steps) = (0.3, 0.1, 1000);
+        let dt = 1.0/(252.0*1.5*steps as f64);
+        let mut rng = RandomNumberGenerator::new(Some(17));
+        let mut log_price = 100.0_f64.ln();
+        let step = |log_price: &mut f64, z: f64| *log_price += (drift-0.5*volatility*volatility)*dt+volatility*dt.sqrt()*z;
+        (0..days).map(|_| {
+            // The overnight gap, as long as half the trading day.
+            step(&mut log_price, rng.get_gaussians(1)[0]*(0.5*steps as f64).sqrt());
+            let open = log_price.exp();
+            let (mut high, mut low) = (open, open);
+            for z in rng.get_gaussians(steps) {
+                step(&mut log_price, z);
+                high = high.max(log_price.exp());
+                low = low.min(log_price.exp());
+            }
+            OhlcBar::new(open, high, low, log_price.exp())
+        }).collect()
+    }
+
+    #[test]
+    fn close_to_close_test(){
+        let mut rng = RandomNumberGenerator::new(Some(3));
+        let stock = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 0.1,
+            NonNegativeFloat::from(0.3), NonNegativeFloat::from(0.0));
+        let path = stock.generate_path_from_steps(&rng.get_gaussians(20100), TimeStamp::from(0.0), Duration::from(1.0/252.0),
+            TimeStamp::from(20000.0/252.0), false);
+        let estimate = close_to_close(&path).unwrap();
+        assert!((estimate.get_volatility()-0.3).abs() < 0.005);
+        assert!((estimate.get_drift()-0.1).abs() < 0.1);
+        assert_eq!(close_to_close(&path[..2]), Err(PricingError::InsufficientSamples));
+        let axis = TimeAxis::new(Date::new(2024, 1, 1), DayCountConvention::Actual365Fixed);
+        let prices = [(Date::new(2024, 1, 1), 100.0), (Date::new(2024, 1, 2), 101.0), (Date::new(2024, 1, 5), 99.0), (Date::new(2024, 1, 8), 100.0)];
+        let from_dates = close_to_close_from_dates(&prices, &axis).unwrap();
+        let states: Vec<StockState> = prices.iter()
+            .map(|(date, price)| StockState::new(NonNegativeFloat::from(*price), axis.time_stamp(*date).unwrap())).collect();
+        assert_eq!(from_dates, close_to_close(&states).unwrap());
+        assert_eq!(close_to_close_from_dates(&[(Date::new(2023, 12, 1), 100.0)], &axis), Err(PricingError::InvalidDate));
+    }
+
+    #[test]
+    fn range_estimators_test(){
+        let bars = simulated_bars(500);
+        // Parkinson's and Garman and Klass' estimators only see the trading day, two thirds of the daily variance, and are
+        // slightly biased down by the discrete monitoring of the simulated high and low.
+        let intraday = 0.3*(2.0_f64/3.0).sqrt();
+        let parkinson = estimate_volatility(&bars, VolatilityEstimator::Parkinson, 252.0).unwrap();
+        assert!((parkinson.get_volatility()-intraday).abs() < 0.01);
+        let garman_klass = estimate_volatility(&bars, VolatilityEstimator::GarmanKlass, 252.0).unwrap();
+        assert!((garman_klass.get_volatility()-intraday).abs() < 0.01);
+        let close_to_close = estimate_volatility(&bars, VolatilityEstimator::CloseToClose, 252.0).unwrap();
+        assert!((close_to_close.get_volatility()-0.3).abs() < 0.02);
+        let yang_zhang = estimate_volatility(&bars, VolatilityEstimator::YangZhang, 252.0).unwrap();
+        assert!((yang_zhang.get_volatility()-0.3).abs() < 0.01);
+        assert_eq!(yang_zhang.get_observations(), 500);
+        assert_eq!(estimate_volatility(&bars[..2], VolatilityEstimator::YangZhang, 252.0), Err(PricingError::InsufficientSamples));
+        let invalid = [OhlcBar::new(100.0, 99.0, 98.0, 98.5), OhlcBar::new(100.0, 101.0, 99.0, 100.0)];
+        assert_eq!(estimate_volatility(&invalid, VolatilityEstimator::Parkinson, 252.0), Err(PricingError::InvalidMarketData));
+    }
+}
@@ -1,19 +1,71 @@
 //! Implements a struct representing a stock.
-use crate::utils::{NonNegativeFloat,TimeStamp};
+use crate::error::PricingError;
+use crate::ornstein_uhlenbeck::OrnsteinUhlenbeckProcess;
+use crate::term_structure::{PiecewiseConstantCurve, RateCurve};
+use crate::time::{Date, TimeAxis};
+use crate::utils::{Duration, NonNegativeFloat, TimeStamp};
+
+/// The discretization used to evolve a stock and to generate its paths.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiscretizationScheme{
+    /// The exact solution of the geometric Brownian motion SDE, which has no discretization bias for any step.
+    #[default]
+    ExactGBM,
+    /// The Euler scheme `S' = S*(1+mu*dt+sigma*sqrt(dt)*Z)`, of strong order 1/2.
+    Euler,
+    /// The Milstein scheme, which adds `S*sigma^2*dt*(Z^2-1)/2` to the Euler step, of strong order 1.
+    Milstein,
+}
+
+/// The divident rate of a stock.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DividentYield{
+    /// A constant divident rate.
+    Constant(NonNegativeFloat),
+    /// A divident rate depending on time, as a piecewise constant curve on the clock of the stock.
+    TermStructure(PiecewiseConstantCurve),
+    /// A stochastic divident rate following an Ornstein-Uhlenbeck process independent of the price of the stock, started from
+    /// the current value of the process at the current time of the stock (the time of the process is not used).
+    OrnsteinUhlenbeck(OrnsteinUhlenbeckProcess),
+}
+
+/// The distribution of a stochastic divident rate given the prices simulated so far, i.e. a Kalman filter. Since the log price
+/// and the integral of an Ornstein-Uhlenbeck divident rate are jointly Gaussian, updating it after every step lets a path be
+/// sampled exactly with one Gaussian sample per step.
+struct DividentFilter{
+    /// The conditional mean of the divident rate.
+    mean: f64,
+    /// The conditional variance of the divident rate.
+    variance: f64,
+}
 
 ///A struct representing a stock that satisfies the geometric Brownian motion SDE.
-#[derive(Clone, Copy, Debug)]
+///
+/// The drift and the volatility may depend on time, as piecewise constant curves (see `with_term_structures`).
+/// The divident rate may be constant, a term structure or a stochastic process (see `with_divident_yield`), and a borrow cost
+/// may be added to it for hard-to-borrow stocks (see `with_borrow_cost`). Both reduce the growth of the stock under the risk
+/// neutral measure, i.e. its forward. Discrete cash dividents are subtracted from the price at their ex-times
+/// (see `with_cash_dividents`).
+/// Paths are generated with the exact solution of the SDE, or with the Euler or the Milstein scheme (see `with_scheme`),
+/// e.g. to measure the discretization bias of these schemes before using them for models without exact solutions.
+#[derive(Clone, Debug, PartialEq)]
 pub struct GeometricBrownianMotionStock{
     /// The current price of the stock.
     price: NonNegativeFloat,
     /// The current time, i.e. the time at which the price was observed.
     current_time: TimeStamp,
-    /// The drift of the stock.
-    drift: f64,
-    ///The volatility of the stock.
-    volatility: NonNegativeFloat,
+    /// The drift of the stock, as a function of time.
+    drift: PiecewiseConstantCurve,
+    ///The volatility of the stock, as a function of time.
+    volatility: PiecewiseConstantCurve,
     ///The rate at which the stock pays out dividents.
-    divident_rate: NonNegativeFloat,
+    divident_yield: DividentYield,
+    ///The cost of borrowing the stock, as a continuous rate.
+    borrow_cost: NonNegativeFloat,
+    ///The cash dividents, as pairs of ex-time and amount, sorted by ex-time.
+    cash_dividents: Vec<(TimeStamp, f64)>,
+    /// The discretization used by `evolve` and by the path generating methods.
+    scheme: DiscretizationScheme,
 }
 
 impl GeometricBrownianMotionStock {
@@ -23,25 +75,204 @@ impl GeometricBrownianMotionStock {
         GeometricBrownianMotionStock{
             price,
             current_time,
-            drift,
-            volatility,
-            divident_rate,
+            drift: PiecewiseConstantCurve::constant(drift),
+            volatility: PiecewiseConstantCurve::constant(f64::from(volatility)),
+            divident_yield: DividentYield::Constant(divident_rate),
+            borrow_cost: NonNegativeFloat::from(0.0),
+            cash_dividents: Vec::new(),
+            scheme: DiscretizationScheme::default(),
+        }
+    }
+
+    ///Returns a new stock with time dependent drift and volatility. The times of the curves are time stamps, on the same
+    ///clock as `current_time`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the values of `volatility` is negative.
+    pub fn with_term_structures(price: NonNegativeFloat, current_time: TimeStamp, drift: PiecewiseConstantCurve, volatility: PiecewiseConstantCurve,
+                divident_rate: NonNegativeFloat) ->GeometricBrownianMotionStock{
+        match GeometricBrownianMotionStock::try_with_term_structures(price, current_time, drift, volatility, divident_rate) {
+            Ok(stock) => stock,
+            Err(_) => panic!("One of the parameters is negative"),
         }
     }
+
+    ///Same as `with_term_structures`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_with_term_structures(price: NonNegativeFloat, current_time: TimeStamp, drift: PiecewiseConstantCurve, volatility: PiecewiseConstantCurve,
+                divident_rate: NonNegativeFloat) ->Result<GeometricBrownianMotionStock, PricingError>{
+        if volatility.get_values().iter().any(|v| *v < 0.0 || v.is_nan()) {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(GeometricBrownianMotionStock{price, current_time, drift, volatility, divident_yield: DividentYield::Constant(divident_rate),
+            borrow_cost: NonNegativeFloat::from(0.0), cash_dividents: Vec::new(), scheme: DiscretizationScheme::default()})
+    }
+
+    ///Returns the stock with the divident rate `divident_yield`, replacing its current one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the divident rate is invalid, see `try_with_divident_yield`.
+    pub fn with_divident_yield(self, divident_yield: DividentYield)->GeometricBrownianMotionStock{
+        self.try_with_divident_yield(divident_yield).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    ///Same as `with_divident_yield`, but returns `PricingError::NegativeInput` if a value of a term structure, or the current
+    ///value or the long term mean of an Ornstein-Uhlenbeck process, is negative.
+    pub fn try_with_divident_yield(self, divident_yield: DividentYield)->Result<GeometricBrownianMotionStock, PricingError>{
+        let negative = match &divident_yield {
+            DividentYield::Constant(_) => false,
+            DividentYield::TermStructure(curve) => curve.get_values().iter().any(|v| *v < 0.0 || v.is_nan()),
+            DividentYield::OrnsteinUhlenbeck(process) => {
+                let (value, long_term_mean) = (process.get_current_state().get_value(), process.get_long_term_mean());
+                value.is_nan() || value < 0.0 || long_term_mean.is_nan() || long_term_mean < 0.0
+            },
+        };
+        if negative {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(GeometricBrownianMotionStock{divident_yield, ..self})
+    }
+
+    ///Returns the stock with the borrow (or repo) cost `borrow_cost`, a continuous rate which reduces the growth of the stock
+    ///under the risk neutral measure in addition to its divident rate.
+    pub fn with_borrow_cost(self, borrow_cost: NonNegativeFloat)->GeometricBrownianMotionStock{
+        GeometricBrownianMotionStock{borrow_cost, ..self}
+    }
+
+    ///Returns the stock paying the cash dividents `dividents`, given as pairs of ex-time and amount, replacing its current ones.
+    ///
+    /// The path generating methods and `evolve` subtract every divident from the price at its ex-time, flooring the price at 0.
+    /// As a step takes one Gaussian sample, the price at an ex-time strictly inside a step is taken on the Brownian bridge
+    /// between the ends of the step, driven by its share of the sample; ex-times on the time stamps of a path are exact. The
+    /// closed form formulas do not take cash dividents into account, so options on such stocks should be priced by Monte Carlo.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an amount is negative.
+    pub fn with_cash_dividents(self, dividents: &[(TimeStamp, f64)])->GeometricBrownianMotionStock{
+        self.try_with_cash_dividents(dividents).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    ///Same as `with_cash_dividents`, but returns `PricingError::NegativeInput` instead of panicking.
+    pub fn try_with_cash_dividents(self, dividents: &[(TimeStamp, f64)])->Result<GeometricBrownianMotionStock, PricingError>{
+        if dividents.iter().any(|(_, amount)| amount.is_nan() || *amount < 0.0) {
+            return Err(PricingError::NegativeInput);
+        }
+        let mut cash_dividents = dividents.to_vec();
+        cash_dividents.sort_by_key(|(ex_time, _)| *ex_time);
+        Ok(GeometricBrownianMotionStock{cash_dividents, ..self})
+    }
+
+    ///Returns the stock with the divident rate, the borrow cost and the cash dividents of `other`, e.g. to keep them when a
+    ///stock is rebuilt with a bumped spot or volatility.
+    pub(crate) fn with_dividents_of(self, other: &GeometricBrownianMotionStock)->GeometricBrownianMotionStock{
+        GeometricBrownianMotionStock{divident_yield: other.divident_yield.clone(), borrow_cost: other.borrow_cost,
+            cash_dividents: other.cash_dividents.clone(), ..self}
+    }
+
+    ///Returns the stock evolved with `scheme` by `evolve` and by the path generating methods. The default is the exact solution.
+    pub fn with_scheme(self, scheme: DiscretizationScheme)->GeometricBrownianMotionStock{
+        GeometricBrownianMotionStock{scheme, ..self}
+    }
+
+    ///Returns the discretization used by `evolve` and by the path generating methods.
+    pub fn get_scheme(&self)->DiscretizationScheme{
+        self.scheme
+    }
     
-    ///Returns the stocks' volatility. 
+    ///Returns a new stock whose price was observed on `current_date`, converted to a time stamp by `time_axis`.
+    ///
+    /// With the `chrono` feature, `current_date` can be a `chrono::NaiveDate` or a `chrono::DateTime`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PricingError::InvalidDate` if `current_date` is before the reference date of `time_axis`.
+    pub fn at_date(price: NonNegativeFloat, time_axis: &TimeAxis, current_date: impl Into<Date>, drift: f64, volatility: NonNegativeFloat,
+                divident_rate: NonNegativeFloat) ->Result<GeometricBrownianMotionStock, PricingError>{
+        Ok(GeometricBrownianMotionStock::new(price, time_axis.time_stamp(current_date)?, drift, volatility, divident_rate))
+    }
+
+    ///Returns the stocks' volatility at its current time. 
     pub fn get_volatility(&self)->NonNegativeFloat{
-        self.volatility
+        NonNegativeFloat::from(self.volatility.value(f64::from(self.current_time)))
+    }
+
+    ///Returns the stocks' volatility as a function of time.
+    pub fn get_volatility_curve(&self)->&PiecewiseConstantCurve{
+        &self.volatility
+    }
+
+    ///Returns the root mean square of the stocks' volatility from its current time to `time_to_expiry` later, i.e. the constant
+    ///volatility giving the same distribution of the price at expiry. The variance of a stochastic divident rate is included,
+    ///as the log price at expiry is still normally distributed.
+    pub fn get_root_mean_square_volatility(&self, time_to_expiry: Duration)->NonNegativeFloat{
+        let now = f64::from(self.current_time);
+        let volatility = self.volatility.root_mean_square(now, now+f64::from(time_to_expiry));
+        match &self.divident_yield {
+            DividentYield::OrnsteinUhlenbeck(process) if f64::from(time_to_expiry) > 0.0 => {
+                NonNegativeFloat::from((volatility*volatility+process.get_integral_variance(time_to_expiry)/f64::from(time_to_expiry)).sqrt())
+            },
+            _ => NonNegativeFloat::from(volatility),
+        }
     }
 
-    ///Returns the stocks' divident rate.
+    ///Returns the variance of the log price accumulated between `from` and `to`, i.e. the integral of the squared volatility.
+    pub fn get_integrated_variance(&self, from: TimeStamp, to: TimeStamp)->f64{
+        self.volatility.integral_of_square(f64::from(from), f64::from(to))
+    }
+
+    ///Returns the stocks' divident rate at its current time. A stochastic divident rate is floored at 0.
     pub fn get_divident_rate(&self)->NonNegativeFloat{
-        self.divident_rate
+        match &self.divident_yield {
+            DividentYield::Constant(rate) => *rate,
+            DividentYield::TermStructure(curve) => NonNegativeFloat::from(curve.value(f64::from(self.current_time))),
+            DividentYield::OrnsteinUhlenbeck(process) => NonNegativeFloat::from(process.get_current_state().get_value().max(0.0)),
+        }
+    }
+
+    ///Returns the stocks' divident rate, as a constant, a term structure or a stochastic process.
+    pub fn get_divident_yield(&self)->&DividentYield{
+        &self.divident_yield
+    }
+
+    ///Returns the stocks' borrow cost.
+    pub fn get_borrow_cost(&self)->NonNegativeFloat{
+        self.borrow_cost
+    }
+
+    ///Returns the stocks' cash dividents, as pairs of ex-time and amount sorted by ex-time.
+    pub fn get_cash_dividents(&self)->&[(TimeStamp, f64)]{
+        &self.cash_dividents
+    }
+
+    ///Returns the constant divident rate which, together with the root mean square volatility, gives the same distribution of
+    ///the price `time_to_expiry` after the current time, including the borrow cost. This is the divident rate to use in the
+    ///Black-Scholes formulas, and the forward is `S*exp((r-q)*T)` with this rate `q`. The rate is negative if a stochastic
+    ///divident rate has a large variance compared to its mean.
+    pub fn get_average_divident_rate(&self, time_to_expiry: Duration)->f64{
+        let (now, tau) = (f64::from(self.current_time), f64::from(time_to_expiry));
+        if tau == 0.0 {
+            return f64::from(self.get_divident_rate())+f64::from(self.borrow_cost);
+        }
+        let integral = match &self.divident_yield {
+            DividentYield::Constant(rate) => f64::from(*rate)*tau,
+            DividentYield::TermStructure(curve) => curve.integral(now, now+tau),
+            DividentYield::OrnsteinUhlenbeck(process) => {
+                process.get_integral_mean(time_to_expiry)-0.5*process.get_integral_variance(time_to_expiry)
+            },
+        };
+        integral/tau+f64::from(self.borrow_cost)
     }
 
-    ///Returns the stocks' drift.
+    ///Returns the stocks' drift at its current time.
     pub fn get_drift(&self)->f64{
-        self.drift
+        self.drift.value(f64::from(self.current_time))
+    }
+
+    ///Returns the stocks' drift as a function of time.
+    pub fn get_drift_curve(&self)->&PiecewiseConstantCurve{
+        &self.drift
     }
 
     ///Returns the stocks' current state, describing its current price and time stamp.
@@ -52,17 +283,24 @@ impl GeometricBrownianMotionStock {
         }
     }
 
-    /// Evolves the stocks price according to geometrical Brownian motion.
+    /// Evolves the stocks price according to geometrical Brownian motion, with the scheme of the stock.
+    /// A stochastic divident rate is moved to its expected value given the move of the price.
     /// # Parameters
     /// `gaussian_sample` - The gaussian_sample that will be used to evolve the stock.
     /// `time_step` - the length of time by which the stock is evolved. After calling `evolve`, the current time of the stock will be `self.current_time+time_step`
-    pub fn evolve(&mut self, gaussian_sample: f64, time_step: NonNegativeFloat){
-        let root_of_time = f64::from(time_step).sqrt();
-        let half_sigma_squared = 0.5*f64::from(self.volatility)*f64::from(self.volatility);
-        let exponent = (self.drift-f64::from(self.divident_rate)-half_sigma_squared)*f64::from(time_step)+gaussian_sample*root_of_time*f64::from(self.volatility);
-        let moved_spot=f64::from(self.price)*exponent.exp();
+    pub fn evolve(&mut self, gaussian_sample: f64, time_step: Duration){
+        let (start, end) = (f64::from(self.current_time), f64::from(self.current_time+time_step));
+        let variance = self.volatility.integral_of_square(start, end);
+        let mut filter = self.divident_filter();
+        let (divident_mean, divident_variance) = self.divident_step(&mut filter, start, end, variance, gaussian_sample);
+        let growth = self.drift.integral(start, end)-f64::from(self.borrow_cost)*f64::from(time_step)-divident_mean+0.5*divident_variance;
+        let moved_spot = self.move_price(f64::from(self.price), start, end, growth, variance+divident_variance, gaussian_sample);
         self.price = NonNegativeFloat::from(moved_spot);
-        self.current_time = TimeStamp::from(f64::from(self.current_time)+f64::from(time_step));
+        self.current_time += time_step;
+        if let DividentYield::OrnsteinUhlenbeck(process) = &mut self.divident_yield {
+            *process = OrnsteinUhlenbeckProcess::new(filter.mean, self.current_time, process.get_mean_reversion_speed(),
+                process.get_long_term_mean(), process.get_volatility());
+        }
     }
 
     /// Generates a path of the stock at the provided time stamps.
@@ -74,36 +312,13 @@ impl GeometricBrownianMotionStock {
     /// # Panics
     /// - If `time_stamps` empty, not strictly increasing, or there are time stams before `self.current_time`.
     /// - If `gaussians.len()<time_stamps.len()`
-    pub fn generate_path_from_time_stamps(&self, gaussians: &Vec<f64>, time_stamps: &Vec<TimeStamp>)->Vec<StockState>{
-        if gaussians.len()<time_stamps.len(){
-            panic!("Not enough Gaussian samples.");
-        }
-        if time_stamps.len()==0 || time_stamps[0]<self.current_time{
-            panic!("Invalid time_stamp vector.");
-        }
-        let mut ans:Vec<StockState> = Vec::new();
-        let mut ct = f64::from(self.current_time);
-        let mut cv =f64::from(self.price);
-        let half_sigma_squared = 0.5*f64::from(self.volatility)*f64::from(self.volatility);
-        for i in 0..time_stamps.len(){
-            let ts = time_stamps[i];
-            let new_current_time = f64::from(ts);
-            if new_current_time - ct < 0.0{
-                panic!("Invalid time_stamp vector");
-            }
-            let time_step = new_current_time - ct;
-            let root_of_time = (time_step).sqrt();
-            let exponent = ((self.drift-f64::from(self.divident_rate)-half_sigma_squared)*time_step + gaussians[i]*root_of_time*f64::from(self.volatility)).exp();
-            
-            ans.push(
-                StockState{
-                    value: NonNegativeFloat::from(cv*exponent),
-                    time: ts,
-                });
-            cv*=exponent;
-            ct=new_current_time;
-        }
-        ans
+    pub fn generate_path_from_time_stamps(&self, gaussians: &[f64], time_stamps: &[TimeStamp])->Vec<StockState>{
+        self.try_generate_path_from_time_stamps(gaussians, time_stamps).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `generate_path_from_time_stamps`, but returns a `PricingError` instead of panicking.
+    pub fn try_generate_path_from_time_stamps(&self, gaussians: &[f64], time_stamps: &[TimeStamp])->Result<Vec<StockState>, PricingError>{
+        self.generate_path_with_drift(gaussians, time_stamps, |start, end| self.drift.integral(start, end))
     }
 
     /// Generates a path of the stock at the provided time stamps under the risk neutral measure.
@@ -111,40 +326,140 @@ impl GeometricBrownianMotionStock {
     /// # Parameters
     /// - `gaussians` - A vector of iid samples of N(0,1), i.e. the standard normal distribution. Must be the same size or larger than `time_stamps`.
     /// - `time_stamps` - A vector of time stamps. Must be strictly increasing, with the first time stamp greater or equal to `self.current_time`.
-    /// - `r` - Short rate of interest.
+    /// - `r` - Short rate of interest, either constant (an `f64`) or a `RateCurve`. The growth of every step is the integral of
+    ///   the rate over the step.
     /// # Panics
     /// - If `time_stamps` empty, not strictly increasing, or there are time stams before `self.current_time`.
     /// - If `gaussians.len()<time_stamps.len()`
-    pub fn generate_risk_neutral_path_from_time_stamps(&self, gaussians: &Vec<f64>, time_stamps: &Vec<TimeStamp>, r: f64)->Vec<StockState>{
+    pub fn generate_risk_neutral_path_from_time_stamps(&self, gaussians: &[f64], time_stamps: &[TimeStamp], r: impl RateCurve)->Vec<StockState>{
+        self.try_generate_risk_neutral_path_from_time_stamps(gaussians, time_stamps, r).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `generate_risk_neutral_path_from_time_stamps`, but returns a `PricingError` instead of panicking.
+    pub fn try_generate_risk_neutral_path_from_time_stamps(&self, gaussians: &[f64], time_stamps: &[TimeStamp], r: impl RateCurve)
+        ->Result<Vec<StockState>, PricingError>{
+        self.generate_path_with_drift(gaussians, time_stamps, |start, end| r.integral(start, end))
+    }
+
+    /// Returns the ratio of the price after a step to the price before it, given the integrated growth rate and variance of the
+    /// step and a sample of N(0,1). The Euler and the Milstein schemes are floored at 0, where the stock is absorbed.
+    fn step_factor(&self, growth: f64, variance: f64, gaussian_sample: f64)->f64{
+        match self.scheme {
+            DiscretizationScheme::ExactGBM => (growth-0.5*variance+gaussian_sample*variance.sqrt()).exp(),
+            DiscretizationScheme::Euler => (1.0+growth+gaussian_sample*variance.sqrt()).max(0.0),
+            DiscretizationScheme::Milstein => {
+                (1.0+growth+gaussian_sample*variance.sqrt()+0.5*variance*(gaussian_sample*gaussian_sample-1.0)).max(0.0)
+            },
+        }
+    }
+
+    /// Returns the price at `to` of the stock priced `price` at `from`, given the integrated growth rate and variance of the step
+    /// and a sample of N(0,1), subtracting the cash dividents with ex-times in `(from, to]`. The step is split at the ex-times,
+    /// and every piece gets the share of the growth, the variance and the Brownian increment of the step in proportion to its
+    /// length, so that a step without dividents is taken as a whole.
+    fn move_price(&self, price: f64, from: f64, to: f64, growth: f64, variance: f64, gaussian_sample: f64)->f64{
+        let mut dividents = self.cash_dividents.iter().filter(|(t, _)| f64::from(*t) > from && f64::from(*t) <= to).peekable();
+        if dividents.peek().is_none() {
+            return price*self.step_factor(growth, variance, gaussian_sample);
+        }
+        let piece_factor = |start: f64, end: f64| {
+            let share = (end-start)/(to-from);
+            self.step_factor(share*growth, share*variance, share.sqrt()*gaussian_sample)
+        };
+        let (mut price, mut start) = (price, from);
+        for (ex_time, amount) in dividents {
+            price = (price*piece_factor(start, f64::from(*ex_time))-amount).max(0.0);
+            start = f64::from(*ex_time);
+        }
+        price*piece_factor(start, to)
+    }
+
+    /// Returns the filter of a stochastic divident rate at the current time, where its value is known.
+    fn divident_filter(&self)->DividentFilter{
+        let mean = match &self.divident_yield {
+            DividentYield::OrnsteinUhlenbeck(process) => process.get_current_state().get_value(),
+            _ => f64::from(self.get_divident_rate()),
+        };
+        DividentFilter{mean, variance: 0.0}
+    }
+
+    /// Returns the mean and the variance of the integral of the divident rate over a step from `from` to `to`, given the prices
+    /// simulated so far through `filter`. For a stochastic divident rate, `filter` is then updated with the move of the log price
+    /// over the step, whose variance from the volatility is `stock_variance` and whose Gaussian sample is `gaussian_sample`.
+    fn divident_step(&self, filter: &mut DividentFilter, from: f64, to: f64, stock_variance: f64, gaussian_sample: f64)->(f64, f64){
+        let process = match &self.divident_yield {
+            DividentYield::Constant(rate) => return (f64::from(*rate)*(to-from), 0.0),
+            DividentYield::TermStructure(curve) => return (curve.integral(from, to), 0.0),
+            DividentYield::OrnsteinUhlenbeck(process) => process,
+        };
+        let long_term_mean = process.get_long_term_mean();
+        let (weight, decay, integral_variance, value_variance, covariance) = process.integral_transition(to-from);
+        let mean = long_term_mean*(to-from)+weight*(filter.mean-long_term_mean);
+        let variance = integral_variance+weight*weight*filter.variance;
+        // The log price moves by minus the integral, so its covariance with the divident rate at `to` is negative.
+        let price_covariance = -(covariance+decay*weight*filter.variance);
+        let total_variance = stock_variance+variance;
+        filter.mean = long_term_mean+decay*(filter.mean-long_term_mean);
+        filter.variance = value_variance+decay*decay*filter.variance;
+        if total_variance > 0.0 {
+            filter.mean += price_covariance/total_variance.sqrt()*gaussian_sample;
+            filter.variance -= price_covariance*price_covariance/total_variance;
+        }
+        (mean, variance)
+    }
+
+    /// Generates a path of the stock at the provided time stamps, where `integrated_drift(start, end)` is the integral of the
+    /// growth rate of the stock from `start` to `end`. The variance of each step is integrated exactly over the volatility curve,
+    /// and each step is taken with the scheme of the stock.
+    fn generate_path_with_drift(&self, gaussians: &[f64], time_stamps: &[TimeStamp], integrated_drift: impl Fn(f64, f64)->f64)
+        ->Result<Vec<StockState>, PricingError>{
         if gaussians.len()<time_stamps.len(){
-            panic!("Not enough Gaussian samples.");
+            return Err(PricingError::InsufficientSamples);
         }
-        if time_stamps.len()==0 || time_stamps[0]<self.current_time{
-            panic!("Invalid time_stamp vector.");
+        if time_stamps.is_empty() || time_stamps[0]<self.current_time{
+            return Err(PricingError::InvalidTimeGrid);
         }
         let mut ans:Vec<StockState> = Vec::new();
         let mut ct = f64::from(self.current_time);
         let mut cv =f64::from(self.price);
-        let half_sigma_squared = 0.5*f64::from(self.volatility)*f64::from(self.volatility);
-        for i in 0..time_stamps.len(){
-            let ts = time_stamps[i];
-            let new_current_time = f64::from(ts);
+        let mut filter = self.divident_filter();
+        for (ts, gaussian) in time_stamps.iter().zip(gaussians.iter()){
+            let new_current_time = f64::from(*ts);
             if new_current_time - ct < 0.0{
-                panic!("Invalid time_stamp vector");
+                return Err(PricingError::InvalidTimeGrid);
             }
             let time_step = new_current_time - ct;
-            let root_of_time = (time_step).sqrt();
-            let exponent = ((r-f64::from(self.divident_rate)-half_sigma_squared)*time_step + gaussians[i]*root_of_time*f64::from(self.volatility)).exp();
-            
+            let variance = self.volatility.integral_of_square(ct, new_current_time);
+            let (divident_mean, divident_variance) = self.divident_step(&mut filter, ct, new_current_time, variance, *gaussian);
+            let growth = integrated_drift(ct, new_current_time)-f64::from(self.borrow_cost)*time_step-divident_mean+0.5*divident_variance;
+            cv = self.move_price(cv, ct, new_current_time, growth, variance+divident_variance, *gaussian);
+
             ans.push(
                 StockState{
-                value: NonNegativeFloat::from(cv*exponent),
-                time: ts,
-            });
-            cv*=exponent;
+                    value: NonNegativeFloat::from(cv),
+                    time: *ts,
+                });
             ct=new_current_time;
         }
-        ans
+        Ok(ans)
+    }
+
+    /// Returns the time stamps starting with `begin` and increasing by `step` until `end`, including `end` if `inclusive` is `true`.
+    fn time_stamps_from_steps(&self, begin: TimeStamp, step: Duration, end: TimeStamp, inclusive: bool)->Result<Vec<TimeStamp>, PricingError>{
+        if begin < self.current_time || end < begin || f64::from(step) == 0.0{
+            return Err(PricingError::InvalidTimeGrid);
+        }
+        let mut time_stamps = Vec::new();
+        let mut ct = f64::from(begin);
+        let step = f64::from(step);
+        while ct < f64::from(end){
+            time_stamps.push(TimeStamp::from(ct));
+            ct += step;
+        }
+        if inclusive{
+            time_stamps.push(end);
+        }
+        Ok(time_stamps)
     }
 
     /// Generates a path of the stock with start time `begin` and increasing by `step`.
@@ -153,7 +468,7 @@ impl GeometricBrownianMotionStock {
     /// Note the path is generated under the actuall probability measure, not the risk neutral measure.
     /// # Parameters
     /// - `gaussians` - A vector of iid samples of N(0,1), i.e. the standard normal distribution. 
-    ///     Must be same size or larger than the number of time stamps in the returned vector.
+    ///   Must be same size or larger than the number of time stamps in the returned vector.
     /// - `begin` - The first time stamp of the returned path.
     /// - `step` - The size of increase in time stamps in the returned path.
     /// - `end` - The limit of time stamps.
@@ -162,22 +477,16 @@ impl GeometricBrownianMotionStock {
     /// # Panics
     /// - If `begin` is smaller than self.current_time.
     /// - If `end` is smaller or equal to `begin`.
+    /// - If `step` is zero.
     /// - If `gausians` is not large enough.
-    pub fn generate_path_from_steps(&self, gaussians: &Vec<f64>, begin: TimeStamp, step: NonNegativeFloat, end: TimeStamp, inclusive: bool)->Vec<StockState>{
-        if begin < self.current_time || end < begin{
-            panic!("Invalid time_stamp inputs");
-        }
-        let mut time_stamps = Vec::new();
-        let mut ct = f64::from(begin);
-        let step = f64::from(step);
-        while ct < f64::from(end){
-            time_stamps.push(TimeStamp::from(ct));
-            ct += step;
-        }
-        if inclusive{
-            time_stamps.push(end);
-        }
-        self.generate_path_from_time_stamps(gaussians, &time_stamps)
+    pub fn generate_path_from_steps(&self, gaussians: &[f64], begin: TimeStamp, step: Duration, end: TimeStamp, inclusive: bool)->Vec<StockState>{
+        self.try_generate_path_from_steps(gaussians, begin, step, end, inclusive).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `generate_path_from_steps`, but returns a `PricingError` instead of panicking.
+    pub fn try_generate_path_from_steps(&self, gaussians: &[f64], begin: TimeStamp, step: Duration, end: TimeStamp, inclusive: bool)->Result<Vec<StockState>, PricingError>{
+        let time_stamps = self.time_stamps_from_steps(begin, step, end, inclusive)?;
+        self.try_generate_path_from_time_stamps(gaussians, &time_stamps)
     }
 
     /// Generates a path of the stock under a risk neutral measure with start time `begin` and increasing by `step`.
@@ -185,8 +494,8 @@ impl GeometricBrownianMotionStock {
     /// If `inclusive` is `true`, the last time stamp will be `end`. If `inclusive` is false, the last time stamp will be strictly smaller than `end`.
     /// # Parameters
     /// - `gaussians` - A vector of iid samples of N(0,1), i.e. the standard normal distribution.
-    ///     Must be same size or larger than the number of time stamps in the returned vector.
-    /// - `r` - The short rate of interest.
+    ///   Must be same size or larger than the number of time stamps in the returned vector.
+    /// - `r` - The short rate of interest, either constant (an `f64`) or a `RateCurve`.
     /// - `begin` - The first time stamp of the returned path.
     /// - `step` - The size of increase in time stamps in the returned path.
     /// - `end` - The limit of time stamps.
@@ -195,38 +504,36 @@ impl GeometricBrownianMotionStock {
     /// # Panics
     /// - If `begin` is smaller than self.current_time.
     /// - If `end` is smaller or equal to `begin`.
+    /// - If `step` is zero.
     /// - If `gausians` is not large enough.
-    pub fn generate_risk_neutral_path_from_steps(&self, gaussians: &Vec<f64>, r: f64, begin: TimeStamp, 
-                                                    step: NonNegativeFloat, end: TimeStamp, inclusive: bool)->Vec<StockState>{
-        if begin < self.current_time || end < begin{
-            panic!("Invalid time_stamp inputs");
-        }
-        let mut time_stamps = Vec::new();
-        let mut ct = f64::from(begin);
-        let step = f64::from(step);
-        while ct < f64::from(end){
-            time_stamps.push(TimeStamp::from(ct));
-            ct += step;
-        }
-        if inclusive{
-            time_stamps.push(end);
-        }
-        self.generate_risk_neutral_path_from_time_stamps(gaussians, &time_stamps, r)
-    }    
-
-
+    pub fn generate_risk_neutral_path_from_steps(&self, gaussians: &[f64], r: impl RateCurve, begin: TimeStamp, 
+                                                    step: Duration, end: TimeStamp, inclusive: bool)->Vec<StockState>{
+        self.try_generate_risk_neutral_path_from_steps(gaussians, r, begin, step, end, inclusive).unwrap_or_else(|e| panic!("{}", e))
+    }
 
+    /// Same as `generate_risk_neutral_path_from_steps`, but returns a `PricingError` instead of panicking.
+    pub fn try_generate_risk_neutral_path_from_steps(&self, gaussians: &[f64], r: impl RateCurve, begin: TimeStamp, 
+                                                    step: Duration, end: TimeStamp, inclusive: bool)->Result<Vec<StockState>, PricingError>{
+        let time_stamps = self.time_stamps_from_steps(begin, step, end, inclusive)?;
+        self.try_generate_risk_neutral_path_from_time_stamps(gaussians, &time_stamps, r)
+    }
 }
 
 
 /// A type representing the state of a stock at some particular time. The first value  in the tuple is the stock price, 
 /// and the second is the time at which it is observed.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct  StockState{
     value: NonNegativeFloat, 
     time: TimeStamp,
 }
 
+impl PartialOrd for StockState{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Ord for StockState{
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.time.cmp(&other.time)
@@ -260,7 +567,7 @@ mod tests {
     fn stock_test1(){
         let mut s = GeometricBrownianMotionStock::new(NonNegativeFloat::from(5.0), TimeStamp::from(0.0), 
                 1.0, NonNegativeFloat::from(0.25), NonNegativeFloat::from(0.0));
-        s.evolve(1.0, NonNegativeFloat::from(0.0));
+        s.evolve(1.0, Duration::from(0.0));
         assert_eq!(s.get_current_state(),StockState::new(NonNegativeFloat::from(5.0),TimeStamp::from(0.0)));
     }
 
@@ -268,7 +575,7 @@ mod tests {
     fn stock_test2(){
         let mut s = GeometricBrownianMotionStock::new(NonNegativeFloat::from(5.0), TimeStamp::from(0.0), 
                 0.0, NonNegativeFloat::from(0.0), NonNegativeFloat::from(0.0));
-        s.evolve(1.0, NonNegativeFloat::from(2.0));
+        s.evolve(1.0, Duration::from(2.0));
         assert_eq!(s.get_current_state(), StockState::new(NonNegativeFloat::from(5.0),TimeStamp::from(2.0)));
     }
 
@@ -276,10 +583,209 @@ mod tests {
     fn stock_test3(){
         let s = GeometricBrownianMotionStock::new(NonNegativeFloat::from(5.0), TimeStamp::from(0.0), 
                 0.0, NonNegativeFloat::from(0.0), NonNegativeFloat::from(0.0));
-        let path = s.generate_path_from_steps(&vec![1.0;6], NonNegativeFloat::from(1.0), 
-                        NonNegativeFloat::from(0.5), NonNegativeFloat::from(3.2), true);
+        let path = s.generate_path_from_steps(&[1.0;6], TimeStamp::from(1.0), 
+                        Duration::from(0.5), TimeStamp::from(3.2), true);
 
         assert_eq!(path.len(),6);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn try_generate_path_test(){
+        let s = GeometricBrownianMotionStock::new(NonNegativeFloat::from(5.0), TimeStamp::from(1.0), 
+                0.0, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0));
+        assert_eq!(s.try_generate_path_from_time_stamps(&[1.0], &[TimeStamp::from(2.0), TimeStamp::from(3.0)]).unwrap_err(),
+            PricingError::InsufficientSamples);
+        assert_eq!(s.try_generate_risk_neutral_path_from_time_stamps(&[1.0], &[TimeStamp::from(0.5)], 0.05).unwrap_err(),
+            PricingError::InvalidTimeGrid);
+        assert_eq!(s.try_generate_path_from_steps(&[1.0; 4], TimeStamp::from(1.0), Duration::from(0.0), TimeStamp::from(2.0), true).unwrap_err(),
+            PricingError::InvalidTimeGrid);
+    }
+
+
+    #[test]
+    fn at_date_test(){
+        use crate::time::DayCountConvention;
+        let axis = TimeAxis::new(Date::new(2024, 1, 1), DayCountConvention::Actual365Fixed);
+        let s = GeometricBrownianMotionStock::at_date(NonNegativeFloat::from(5.0), &axis, Date::new(2024, 2, 7),
+                0.0, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)).unwrap();
+        assert_eq!(s.get_current_state().get_time(), TimeStamp::from(37.0/365.0));
+    }
+
+    #[test]
+    fn term_structure_test(){
+        let volatility = PiecewiseConstantCurve::new(vec![1.5], vec![0.1, 0.3]);
+        let drift = PiecewiseConstantCurve::new(vec![2.0], vec![0.05, -0.02]);
+        let s = GeometricBrownianMotionStock::with_term_structures(NonNegativeFloat::from(100.0), TimeStamp::from(1.0), drift,
+                volatility, NonNegativeFloat::from(0.01));
+        assert_eq!(f64::from(s.get_volatility()), 0.1);
+        assert_eq!(s.get_drift(), 0.05);
+        let variance = 0.5*0.01+1.5*0.09;
+        assert!((s.get_integrated_variance(TimeStamp::from(1.0), TimeStamp::from(3.0))-variance).abs() < 1e-12);
+        assert!((f64::from(s.get_root_mean_square_volatility(Duration::from(2.0)))-(variance/2.0).sqrt()).abs() < 1e-12);
+        let path = s.generate_path_from_time_stamps(&[0.5], &[TimeStamp::from(3.0)]);
+        let exponent = 0.05-0.02-0.01*2.0-0.5*variance+0.5*variance.sqrt();
+        assert!((f64::from(path[0].get_value())-100.0*exponent.exp()).abs() < 1e-10);
+        let mut evolved = s.clone();
+        evolved.evolve(0.5, Duration::from(2.0));
+        assert!((f64::from(evolved.get_current_state().get_value())-f64::from(path[0].get_value())).abs() < 1e-10);
+        assert_eq!(f64::from(evolved.get_volatility()), 0.3);
+        assert_eq!(GeometricBrownianMotionStock::try_with_term_structures(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+                PiecewiseConstantCurve::constant(0.0), PiecewiseConstantCurve::constant(-0.1), NonNegativeFloat::from(0.0)),
+            Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn rate_curve_path_test(){
+        use crate::term_structure::RateFunction;
+        let s = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+                0.0, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.01));
+        let time_stamps = [TimeStamp::from(0.5), TimeStamp::from(2.0)];
+        let rates = PiecewiseConstantCurve::new(vec![1.0], vec![0.02, 0.05]);
+        let path = s.generate_risk_neutral_path_from_time_stamps(&[0.0, 0.0], &time_stamps, &rates);
+        let exponent: f64 = 0.02+0.05-0.01*2.0-0.5*0.04*2.0;
+        assert!((f64::from(path[1].get_value())-100.0*exponent.exp()).abs() < 1e-10);
+        assert_eq!(s.generate_risk_neutral_path_from_time_stamps(&[0.3, -0.2], &time_stamps, PiecewiseConstantCurve::constant(0.03)),
+            s.generate_risk_neutral_path_from_time_stamps(&[0.3, -0.2], &time_stamps, 0.03));
+        let linear = s.generate_risk_neutral_path_from_time_stamps(&[0.0, 0.0], &time_stamps, RateFunction(|t: f64| 0.02*t));
+        let exponent: f64 = 0.04-0.01*2.0-0.5*0.04*2.0;
+        assert!((f64::from(linear[1].get_value())-100.0*exponent.exp()).abs() < 1e-8);
+    }
+
+    #[test]
+    fn divident_yield_test(){
+        use crate::{formulas, raw_formulas};
+        let curve = PiecewiseConstantCurve::new(vec![1.5], vec![0.01, 0.03]);
+        let s = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(1.0), 0.0, NonNegativeFloat::from(0.2),
+                NonNegativeFloat::from(0.0)).with_divident_yield(DividentYield::TermStructure(curve)).with_borrow_cost(NonNegativeFloat::from(0.02));
+        assert_eq!(f64::from(s.get_divident_rate()), 0.01);
+        assert!((s.get_average_divident_rate(Duration::from(2.0))-(0.005+0.045)/2.0-0.02).abs() < 1e-12);
+        let path = s.generate_risk_neutral_path_from_time_stamps(&[0.0], &[TimeStamp::from(3.0)], 0.05);
+        let exponent: f64 = 0.1-0.05-0.04-0.5*0.04*2.0;
+        assert!((f64::from(path[0].get_value())-100.0*exponent.exp()).abs() < 1e-10);
+        let forward = formulas::forward_price(&s, 0.05, Duration::from(2.0));
+        assert!((f64::from(forward)-raw_formulas::forward_price_with_borrow_cost(100.0, 0.05, 2.0, 0.025, 0.02)).abs() < 1e-10);
+        assert_eq!(s.clone().try_with_divident_yield(DividentYield::TermStructure(PiecewiseConstantCurve::constant(-0.01))),
+            Err(PricingError::NegativeInput));
+
+        // A stochastic divident rate: the log price at expiry is normal, with the moments used by the formulas, also when the
+        // path is generated in several steps.
+        let process = OrnsteinUhlenbeckProcess::new(0.03, TimeStamp::from(0.0), NonNegativeFloat::from(0.5), 0.02,
+            NonNegativeFloat::from(0.2));
+        let s = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 0.0, NonNegativeFloat::from(0.2),
+            NonNegativeFloat::from(0.0)).with_divident_yield(DividentYield::OrnsteinUhlenbeck(process));
+        let time_to_expiry = Duration::from(1.0);
+        let volatility = f64::from(s.get_root_mean_square_volatility(time_to_expiry));
+        assert!(volatility > 0.2);
+        let expected_mean = (0.05-s.get_average_divident_rate(time_to_expiry)-0.5*volatility*volatility)+100.0_f64.ln();
+        let time_stamps: Vec<TimeStamp> = (1..=4).map(|i| TimeStamp::from(i as f64/4.0)).collect();
+        let mut rng = crate::random_number_generator::RandomNumberGenerator::new(Some(11));
+        let number_of_paths = 20000;
+        let logs: Vec<f64> = (0..number_of_paths).map(|_| {
+            let gaussians = crate::random_number_generator::RandomNumberGeneratorTrait::get_gaussians(&mut rng, 4);
+            f64::from(s.generate_risk_neutral_path_from_time_stamps(&gaussians, &time_stamps, 0.05)[3].get_value()).ln()
+        }).collect();
+        let mean = logs.iter().sum::<f64>()/number_of_paths as f64;
+        let variance = logs.iter().map(|x| (x-mean)*(x-mean)).sum::<f64>()/(number_of_paths-1) as f64;
+        assert!((mean-expected_mean).abs() < 3.0*volatility/(number_of_paths as f64).sqrt());
+        assert!((variance/(volatility*volatility)-1.0).abs() < 3.0*(2.0/number_of_paths as f64).sqrt());
+        let mut evolved = s.clone();
+        evolved.evolve(0.0, Duration::from(1.0));
+        assert_eq!(evolved.get_current_state().get_time(), TimeStamp::from(1.0));
+        assert!(f64::from(evolved.get_divident_rate()) < 0.03 && f64::from(evolved.get_divident_rate()) > 0.02);
+    }
+
+    #[test]
+    fn negative_average_divident_rate_test(){
+        use crate::formulas;
+        // An Ornstein-Uhlenbeck divident rate with a large variance has a negative average divident rate, which the formulas
+        // accept, and the greeks by the spot are still the derivatives of the price.
+        let stock = |spot: f64| {
+            let process = OrnsteinUhlenbeckProcess::new(0.01, TimeStamp::from(0.0), NonNegativeFloat::from(0.1), 0.01,
+                NonNegativeFloat::from(0.5));
+            GeometricBrownianMotionStock::new(NonNegativeFloat::from(spot), TimeStamp::from(0.0), 0.0, NonNegativeFloat::from(0.2),
+                NonNegativeFloat::from(0.0)).with_divident_yield(DividentYield::OrnsteinUhlenbeck(process))
+        };
+        let (strike, r, time_to_expiry) = (NonNegativeFloat::from(100.0), 0.03, Duration::from(2.0));
+        let q = stock(100.0).get_average_divident_rate(time_to_expiry);
+        assert!(q < 0.0);
+        let forward = f64::from(formulas::forward_price(&stock(100.0), r, time_to_expiry));
+        assert!((forward-100.0*((r-q)*2.0).exp()).abs() < 1e-10);
+        let call = f64::from(formulas::european_call_option_price(&stock(100.0), strike, r, time_to_expiry));
+        let put = f64::from(formulas::european_put_option_price(&stock(100.0), strike, r, time_to_expiry));
+        assert!((call-put-(forward-100.0)*(-r*2.0).exp()).abs() < 1e-10);
+        let h = 1e-3;
+        let price = |spot: f64| f64::from(formulas::european_call_option_price(&stock(spot), strike, r, time_to_expiry));
+        let delta = f64::from(formulas::call_delta(&stock(100.0), strike, r, time_to_expiry));
+        let gamma = f64::from(formulas::call_gamma(&stock(100.0), strike, r, time_to_expiry));
+        assert!((delta-(price(100.0+h)-price(100.0-h))/(2.0*h)).abs() < 1e-6);
+        assert!((gamma-(price(100.0+h)-2.0*price(100.0)+price(100.0-h))/(h*h)).abs() < 1e-4);
+        let greeks = formulas::call_greeks(&stock(100.0), strike, r, time_to_expiry);
+        assert!((greeks.price-call).abs() < 1e-10);
+        assert!((greeks.delta-delta).abs() < 1e-10);
+        assert!((greeks.gamma-gamma).abs() < 1e-10);
+    }
+
+    #[test]
+    fn cash_divident_test(){
+        let s = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 0.0, NonNegativeFloat::from(0.0),
+            NonNegativeFloat::from(0.0)).with_cash_dividents(&[(TimeStamp::from(1.5), 200.0), (TimeStamp::from(0.5), 5.0)]);
+        assert_eq!(s.get_cash_dividents()[0], (TimeStamp::from(0.5), 5.0));
+        let time_stamps = [TimeStamp::from(0.5), TimeStamp::from(1.0), TimeStamp::from(2.0)];
+        let path = s.generate_risk_neutral_path_from_time_stamps(&[0.0; 3], &time_stamps, 0.05);
+        assert!((f64::from(path[0].get_value())-(100.0*0.025_f64.exp()-5.0)).abs() < 1e-10);
+        assert!((f64::from(path[1].get_value())-(100.0*0.025_f64.exp()-5.0)*0.025_f64.exp()).abs() < 1e-10);
+        assert_eq!(f64::from(path[2].get_value()), 0.0);
+        let mut evolved = s.clone();
+        evolved.evolve(0.0, Duration::from(1.0));
+        assert!((f64::from(evolved.get_current_state().get_value())-(100.0-5.0)).abs() < 1e-10);
+        assert_eq!(s.clone().try_with_cash_dividents(&[(TimeStamp::from(1.0), -1.0)]), Err(PricingError::NegativeInput));
+
+        // Without dividents inside them, steps are unchanged; with one inside, the forward is kept up to the bridge approximation.
+        let s = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 0.0, NonNegativeFloat::from(0.2),
+            NonNegativeFloat::from(0.0));
+        let with_dividents = s.clone().with_cash_dividents(&[(TimeStamp::from(2.0), 3.0)]);
+        assert_eq!(with_dividents.generate_risk_neutral_path_from_time_stamps(&[0.7], &[TimeStamp::from(1.0)], 0.05),
+            s.generate_risk_neutral_path_from_time_stamps(&[0.7], &[TimeStamp::from(1.0)], 0.05));
+        let with_dividents = s.clone().with_cash_dividents(&[(TimeStamp::from(0.5), 3.0)]);
+        let mut rng = crate::random_number_generator::RandomNumberGenerator::new(Some(3));
+        let number_of_paths = 20000;
+        let mean = (0..number_of_paths).map(|_| {
+            let gaussian = crate::random_number_generator::RandomNumberGeneratorTrait::get_gaussians(&mut rng, 1);
+            f64::from(with_dividents.generate_risk_neutral_path_from_time_stamps(&gaussian, &[TimeStamp::from(1.0)], 0.05)[0].get_value())
+        }).sum::<f64>()/number_of_paths as f64;
+        let forward = 100.0*0.05_f64.exp()-3.0*0.025_f64.exp();
+        assert!((mean-forward).abs() < 3.0*20.0/(number_of_paths as f64).sqrt());
+    }
+
+    #[test]
+    fn discretization_scheme_test(){
+        let exact = GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.05, NonNegativeFloat::from(0.4), NonNegativeFloat::from(0.01));
+        assert_eq!(exact.get_scheme(), DiscretizationScheme::ExactGBM);
+        let mut rng = crate::random_number_generator::RandomNumberGenerator::new(Some(5));
+        // The mean absolute error at expiry against the exact solution driven by the same Gaussians, with `steps` steps.
+        let strong_error = |scheme, steps: usize, rng: &mut crate::random_number_generator::RandomNumberGenerator| {
+            let stock = exact.clone().with_scheme(scheme);
+            let time_stamps: Vec<TimeStamp> = (1..=steps).map(|i| TimeStamp::from(i as f64/steps as f64)).collect();
+            let mut error = 0.0;
+            for _ in 0..2000 {
+                let gaussians = crate::random_number_generator::RandomNumberGeneratorTrait::get_gaussians(rng, steps);
+                let approximate = stock.generate_risk_neutral_path_from_time_stamps(&gaussians, &time_stamps, 0.05)[steps-1];
+                let exact = exact.generate_risk_neutral_path_from_time_stamps(&gaussians, &time_stamps, 0.05)[steps-1];
+                error += (f64::from(approximate.get_value())-f64::from(exact.get_value())).abs()/2000.0;
+            }
+            error
+        };
+        assert_eq!(strong_error(DiscretizationScheme::ExactGBM, 4, &mut rng), 0.0);
+        let (euler_coarse, euler_fine) = (strong_error(DiscretizationScheme::Euler, 16, &mut rng), strong_error(DiscretizationScheme::Euler, 64, &mut rng));
+        let (milstein_coarse, milstein_fine) = (strong_error(DiscretizationScheme::Milstein, 16, &mut rng),
+            strong_error(DiscretizationScheme::Milstein, 64, &mut rng));
+        // Four times more steps halve the error of Euler and divide the error of Milstein by four.
+        assert!(euler_coarse/euler_fine > 1.6 && euler_coarse/euler_fine < 2.5);
+        assert!(milstein_coarse/milstein_fine > 3.2 && milstein_coarse/milstein_fine < 5.0);
+        assert!(milstein_coarse < euler_coarse/3.0);
+        let mut stock = exact.clone().with_scheme(DiscretizationScheme::Euler);
+        stock.evolve(1.0, Duration::from(0.25));
+        assert!((f64::from(stock.get_current_state().get_value())-100.0*(1.0+0.04*0.25+0.4*0.5)).abs() < 1e-10);
+    }
+}
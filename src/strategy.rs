@@ -0,0 +1,258 @@
+//! Provides option strategies, i.e. portfolios of european options, stock and zero coupon bonds with signed quantities,
+//! such as spreads, straddles, butterflies and iron condors.
+//!
+//! A strategy is priced in one call, either with the Black-Scholes formulas of `raw_formulas` or by Monte Carlo simulation
+//! of all its option legs on shared paths, and its greeks and payoff diagram are the sums of those of its legs.
+
+use std::sync::Arc;
+
+use crate::greeks::automatic_differentiation::{european_call_greeks, european_put_greeks};
+use crate::greeks::Greeks;
+use crate::monte_carlo_pricer::{monte_carlo_simulation_multi, McResult};
+use crate::option::{DerivativeOption, VanillaStockOption};
+use crate::payoff::{CallPayoff, Payoff, PutPayoff};
+use crate::random_number_generator::RandomNumberGenerator;
+use crate::raw_formulas;
+use crate::stock::GeometricBrownianMotionStock;
+use crate::utils::{NonNegativeFloat, TimeStamp};
+
+/// A leg of a strategy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StrategyLeg{
+    /// A european call option.
+    Call{
+        /// The strike of the option.
+        strike: f64,
+        /// The time to expiry of the option.
+        time_to_expiry: f64,
+    },
+    /// A european put option.
+    Put{
+        /// The strike of the option.
+        strike: f64,
+        /// The time to expiry of the option.
+        time_to_expiry: f64,
+    },
+    /// One share of the stock (with its dividends reinvested).
+    Stock,
+    /// A zero coupon bond paying 1 at maturity.
+    Bond{
+        /// The time to maturity of the bond.
+        time_to_maturity: f64,
+    },
+}
+
+impl StrategyLeg {
+    /// Returns the value of the leg at its expiry (the spot for stock), given the value of the stock at that time.
+    pub fn payoff(&self, spot: f64)->f64{
+        match *self {
+            StrategyLeg::Call{strike, ..} => CallPayoff::new(strike).value(NonNegativeFloat::from(spot)),
+            StrategyLeg::Put{strike, ..} => PutPayoff::new(strike).value(NonNegativeFloat::from(spot)),
+            StrategyLeg::Stock => spot,
+            StrategyLeg::Bond{..} => 1.0,
+        }
+    }
+
+    /// Returns the price and greeks of one unit of the leg in the Black-Scholes model.
+    /// # Panics
+    /// Panics if one of the parameters is negative.
+    pub fn greeks(&self, spot: f64, short_rate_of_interest: f64, volatility: f64, divident_rate: f64)->Greeks{
+        match *self {
+            StrategyLeg::Call{strike, time_to_expiry} =>
+                european_call_greeks(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate),
+            StrategyLeg::Put{strike, time_to_expiry} =>
+                european_put_greeks(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate),
+            StrategyLeg::Stock => Greeks{price: spot, delta: 1.0, gamma: 0.0, vega: 0.0, theta: 0.0, rho: 0.0},
+            StrategyLeg::Bond{time_to_maturity} => {
+                let price = raw_formulas::zero_coupon_bond(short_rate_of_interest, time_to_maturity);
+                Greeks{price, delta: 0.0, gamma: 0.0, vega: 0.0, theta: short_rate_of_interest*price, rho: -time_to_maturity*price}
+            },
+        }
+    }
+}
+
+/// A strategy, i.e. a list of legs with signed quantities (positive for long positions and negative for short ones).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Strategy{
+    /// The quantities and the legs of the strategy.
+    legs: Vec<(f64, StrategyLeg)>,
+}
+
+impl Strategy {
+    /// Returns an empty strategy, to which legs can be added with `with_leg`.
+    pub fn new()->Strategy{
+        Strategy::default()
+    }
+
+    /// Returns the strategy with an additional leg of `quantity` units of `leg`.
+    pub fn with_leg(mut self, quantity: f64, leg: StrategyLeg)->Strategy{
+        self.legs.push((quantity, leg));
+        self
+    }
+
+    /// Returns the quantities and the legs of the strategy.
+    pub fn get_legs(&self)->&[(f64, StrategyLeg)]{
+        &self.legs
+    }
+
+    /// Returns a bull call spread: long a call with strike `lower_strike` and short a call with strike `upper_strike`.
+    pub fn bull_call_spread(lower_strike: f64, upper_strike: f64, time_to_expiry: f64)->Strategy{
+        Strategy::new()
+            .with_leg(1.0, StrategyLeg::Call{strike: lower_strike, time_to_expiry})
+            .with_leg(-1.0, StrategyLeg::Call{strike: upper_strike, time_to_expiry})
+    }
+
+    /// Returns a bear put spread: long a put with strike `upper_strike` and short a put with strike `lower_strike`.
+    pub fn bear_put_spread(lower_strike: f64, upper_strike: f64, time_to_expiry: f64)->Strategy{
+        Strategy::new()
+            .with_leg(1.0, StrategyLeg::Put{strike: upper_strike, time_to_expiry})
+            .with_leg(-1.0, StrategyLeg::Put{strike: lower_strike, time_to_expiry})
+    }
+
+    /// Returns a straddle: long a call and a put with the same strike.
+    pub fn straddle(strike: f64, time_to_expiry: f64)->Strategy{
+        Strategy::new()
+            .with_leg(1.0, StrategyLeg::Call{strike, time_to_expiry})
+            .with_leg(1.0, StrategyLeg::Put{strike, time_to_expiry})
+    }
+
+    /// Returns a strangle: long a put with strike `put_strike` and a call with strike `call_strike`.
+    pub fn strangle(put_strike: f64, call_strike: f64, time_to_expiry: f64)->Strategy{
+        Strategy::new()
+            .with_leg(1.0, StrategyLeg::Put{strike: put_strike, time_to_expiry})
+            .with_leg(1.0, StrategyLeg::Call{strike: call_strike, time_to_expiry})
+    }
+
+    /// Returns a call butterfly: long one call at each of the outer strikes and short two calls at the middle strike.
+    pub fn butterfly(lower_strike: f64, middle_strike: f64, upper_strike: f64, time_to_expiry: f64)->Strategy{
+        Strategy::new()
+            .with_leg(1.0, StrategyLeg::Call{strike: lower_strike, time_to_expiry})
+            .with_leg(-2.0, StrategyLeg::Call{strike: middle_strike, time_to_expiry})
+            .with_leg(1.0, StrategyLeg::Call{strike: upper_strike, time_to_expiry})
+    }
+
+    /// Returns an iron condor with strikes `k1<k2<k3<k4`: a short put spread on `k1,k2` and a short call spread on `k3,k4`.
+    pub fn iron_condor(k1: f64, k2: f64, k3: f64, k4: f64, time_to_expiry: f64)->Strategy{
+        Strategy::new()
+            .with_leg(1.0, StrategyLeg::Put{strike: k1, time_to_expiry})
+            .with_leg(-1.0, StrategyLeg::Put{strike: k2, time_to_expiry})
+            .with_leg(-1.0, StrategyLeg::Call{strike: k3, time_to_expiry})
+            .with_leg(1.0, StrategyLeg::Call{strike: k4, time_to_expiry})
+    }
+
+    /// Returns the value of the strategy at expiry, given the value of the stock at expiry.
+    /// All the legs are assumed to expire at the same time.
+    pub fn payoff(&self, spot: f64)->f64{
+        self.legs.iter().map(|(quantity, leg)| quantity*leg.payoff(spot)).sum()
+    }
+
+    /// Samples the payoff diagram of the strategy at `number_of_points` equally spaced spots from `min_spot` to `max_spot`,
+    /// returning pairs of spot and payoff.
+    /// # Panics
+    /// Panics if `number_of_points` is smaller than 2.
+    pub fn payoff_diagram(&self, min_spot: f64, max_spot: f64, number_of_points: usize)->Vec<(f64, f64)>{
+        if number_of_points < 2 {
+            panic!("A payoff diagram needs at least two points.");
+        }
+        let step = (max_spot-min_spot)/(number_of_points-1) as f64;
+        (0..number_of_points).map(|i| {
+            let spot = min_spot+step*i as f64;
+            (spot, self.payoff(spot))
+        }).collect()
+    }
+
+    /// Returns the Black-Scholes price and greeks of the strategy.
+    /// # Panics
+    /// Panics if one of the parameters is negative.
+    pub fn greeks(&self, spot: f64, short_rate_of_interest: f64, volatility: f64, divident_rate: f64)->Greeks{
+        let mut total = Greeks{price: 0.0, delta: 0.0, gamma: 0.0, vega: 0.0, theta: 0.0, rho: 0.0};
+        for (quantity, leg) in self.legs.iter() {
+            let greeks = leg.greeks(spot, short_rate_of_interest, volatility, divident_rate);
+            total.price += quantity*greeks.price;
+            total.delta += quantity*greeks.delta;
+            total.gamma += quantity*greeks.gamma;
+            total.vega += quantity*greeks.vega;
+            total.theta += quantity*greeks.theta;
+            total.rho += quantity*greeks.rho;
+        }
+        total
+    }
+
+    /// Returns the Black-Scholes price of the strategy.
+    /// # Panics
+    /// Panics if one of the parameters is negative.
+    pub fn price(&self, spot: f64, short_rate_of_interest: f64, volatility: f64, divident_rate: f64)->f64{
+        self.greeks(spot, short_rate_of_interest, volatility, divident_rate).price
+    }
+
+    /// Returns the Monte Carlo price of the strategy. The option legs are simulated on shared paths with
+    /// `monte_carlo_simulation_multi`, and the stock and bond legs are valued exactly. The standard error accounts for the
+    /// correlation between the legs.
+    /// # Parameters
+    /// - `spot`, `short_rate_of_interest`, `volatility`, `divident_rate` - the parameters of the stock.
+    /// - `seed` - An optional seed for the random number generation. If `None`, a random seed will be used.
+    /// - `number_of_paths` - The number of trials in the simulation.
+    pub fn monte_carlo_price(&self, spot: f64, short_rate_of_interest: f64, volatility: f64, divident_rate: f64, seed: Option<u64>,
+        number_of_paths: usize)->McResult{
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(spot), TimeStamp::from(0.0),
+            short_rate_of_interest, NonNegativeFloat::from(volatility), NonNegativeFloat::from(divident_rate)));
+        let mut calls = Vec::new();
+        let mut puts = Vec::new();
+        let mut call_quantities = Vec::new();
+        let mut put_quantities = Vec::new();
+        let mut exact_value = 0.0;
+        for (quantity, leg) in self.legs.iter() {
+            match *leg {
+                StrategyLeg::Call{strike, time_to_expiry} => {
+                    calls.push(VanillaStockOption::with_payoff(&stock, TimeStamp::from(time_to_expiry), CallPayoff::new(strike)));
+                    call_quantities.push(*quantity);
+                },
+                StrategyLeg::Put{strike, time_to_expiry} => {
+                    puts.push(VanillaStockOption::with_payoff(&stock, TimeStamp::from(time_to_expiry), PutPayoff::new(strike)));
+                    put_quantities.push(*quantity);
+                },
+                _ => exact_value += quantity*leg.greeks(spot, short_rate_of_interest, volatility, divident_rate).price,
+            }
+        }
+        let options: Vec<&dyn DerivativeOption<GeometricBrownianMotionStock>> = calls.iter()
+            .map(|c| c as &dyn DerivativeOption<GeometricBrownianMotionStock>)
+            .chain(puts.iter().map(|p| p as &dyn DerivativeOption<GeometricBrownianMotionStock>)).collect();
+        let quantities: Vec<f64> = call_quantities.into_iter().chain(put_quantities).collect();
+        let mut rng = RandomNumberGenerator::new(seed);
+        let result = monte_carlo_simulation_multi(&options, short_rate_of_interest, &mut rng, number_of_paths);
+        let price = exact_value+quantities.iter().zip(result.get_prices()).map(|(q, p)| q*p).sum::<f64>();
+        let variance: f64 = quantities.iter().zip(result.get_covariance())
+            .map(|(qi, row)| qi*quantities.iter().zip(row).map(|(qj, c)| qj*c).sum::<f64>()).sum();
+        McResult::new(price, variance.max(0.0).sqrt(), number_of_paths, rng.get_seed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategy_payoff_test(){
+        let condor = Strategy::iron_condor(80.0, 90.0, 110.0, 120.0, 1.0);
+        let diagram = condor.payoff_diagram(70.0, 130.0, 7);
+        assert_eq!(diagram.iter().map(|p| p.1).collect::<Vec<f64>>(), vec![-10.0, -10.0, 0.0, 0.0, 0.0, -10.0, -10.0]);
+        assert_eq!(Strategy::butterfly(90.0, 100.0, 110.0, 1.0).payoff(100.0), 10.0);
+    }
+
+    #[test]
+    fn strategy_price_test(){
+        // A long call, a short put and a bond paying the strike replicate a forward on the stock (put-call parity).
+        let synthetic = Strategy::new()
+            .with_leg(1.0, StrategyLeg::Call{strike: 100.0, time_to_expiry: 1.0})
+            .with_leg(-1.0, StrategyLeg::Put{strike: 100.0, time_to_expiry: 1.0})
+            .with_leg(100.0, StrategyLeg::Bond{time_to_maturity: 1.0});
+        let greeks = synthetic.greeks(105.0, 0.05, 0.2, 0.03);
+        assert!((greeks.price-105.0*(-0.03_f64).exp()).abs()<1e-10);
+        assert!((greeks.delta-(-0.03_f64).exp()).abs()<1e-10);
+        assert!(greeks.gamma.abs()<1e-10 && greeks.vega.abs()<1e-10);
+        let straddle = Strategy::straddle(100.0, 1.0);
+        let mc = straddle.monte_carlo_price(100.0, 0.05, 0.2, 0.0, Some(3), 50000);
+        let exact = straddle.price(100.0, 0.05, 0.2, 0.0);
+        assert!((mc.get_price()-exact).abs() < 4.0*mc.get_std_error());
+    }
+}
@@ -0,0 +1,204 @@
+//! Provides term structures: piecewise constant curves, used for time dependent volatilities and drifts, and deterministic
+//! short rates of interest, used to grow risk neutral paths and to discount.
+//!
+//! The times of a term structure are time stamps, i.e. they are measured on the same clock as the current time of a stock.
+
+use crate::error::PricingError;
+use crate::numerics::integration::adaptive_simpson;
+
+/// The absolute tolerance of the numerical integration of a `RateFunction`.
+const RATE_FUNCTION_TOLERANCE: f64 = 1e-12;
+
+/// A deterministic short rate of interest as a function of time.
+///
+/// Implemented by `f64` (a constant rate), by `PiecewiseConstantCurve`, and by `RateFunction`, which wraps a closure `r(t)`.
+pub trait RateCurve {
+    /// Returns the integral of the short rate from `from` to `to`.
+    fn integral(&self, from: f64, to: f64)->f64;
+
+    /// Returns the price at time `from` of a zero coupon bond paying 1 at time `to`.
+    fn discount_factor(&self, from: f64, to: f64)->f64{
+        (-self.integral(from, to)).exp()
+    }
+}
+
+/// A short rate of interest given by a closure `r(t)`, integrated numerically.
+///
+/// # Examples
+///
+/// ```
+/// use derivative_pricer::term_structure::{RateCurve, RateFunction};
+/// let rate = RateFunction(|t: f64| 0.02+0.01*t);
+/// assert!((rate.integral(0.0, 2.0)-0.06).abs() < 1e-12);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct RateFunction<F>(pub F);
+
+/// A piecewise constant function of time.
+///
+/// The curve takes the value `values[0]` before `breakpoints[0]`, the value `values[i]` on `[breakpoints[i-1], breakpoints[i])`,
+/// and the value `values[n]` from `breakpoints[n-1]` on, where `n` is the number of breakpoints.
+///
+/// # Examples
+///
+/// ```
+/// use derivative_pricer::term_structure::PiecewiseConstantCurve;
+/// let volatility = PiecewiseConstantCurve::new(vec![1.0], vec![0.2, 0.3]);
+/// assert_eq!(volatility.value(1.5), 0.3);
+/// assert!((volatility.integral_of_square(0.0, 2.0)-(0.04+0.09)).abs() < 1e-12);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PiecewiseConstantCurve{
+    /// The times at which the value changes, strictly increasing.
+    breakpoints: Vec<f64>,
+    /// The values on the pieces, one more than the breakpoints.
+    values: Vec<f64>,
+}
+
+impl PiecewiseConstantCurve {
+    /// Returns a curve changing value at `breakpoints`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `breakpoints` is not strictly increasing, or if there is not exactly one more value than breakpoints.
+    pub fn new(breakpoints: Vec<f64>, values: Vec<f64>)->PiecewiseConstantCurve{
+        PiecewiseConstantCurve::try_new(breakpoints, values).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `new`, but returns `PricingError::InvalidTimeGrid` instead of panicking.
+    pub fn try_new(breakpoints: Vec<f64>, values: Vec<f64>)->Result<PiecewiseConstantCurve, PricingError>{
+        if values.len() != breakpoints.len()+1 || breakpoints.iter().any(|t| t.is_nan()) || breakpoints.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(PricingError::InvalidTimeGrid);
+        }
+        Ok(PiecewiseConstantCurve{breakpoints, values})
+    }
+
+    /// Returns the curve with the constant value `value`.
+    pub fn constant(value: f64)->PiecewiseConstantCurve{
+        PiecewiseConstantCurve{breakpoints: Vec::new(), values: vec![value]}
+    }
+
+    /// Returns the times at which the value changes.
+    pub fn get_breakpoints(&self)->&[f64]{
+        &self.breakpoints
+    }
+
+    /// Returns the values on the pieces.
+    pub fn get_values(&self)->&[f64]{
+        &self.values
+    }
+
+    /// Returns true if the curve has a single piece.
+    pub fn is_constant(&self)->bool{
+        self.breakpoints.is_empty()
+    }
+
+    /// Returns the value of the curve at `time`.
+    pub fn value(&self, time: f64)->f64{
+        self.values[self.breakpoints.partition_point(|t| *t <= time)]
+    }
+
+    /// Returns the curve with `shift` added to every value.
+    pub fn shifted(&self, shift: f64)->PiecewiseConstantCurve{
+        PiecewiseConstantCurve{breakpoints: self.breakpoints.clone(), values: self.values.iter().map(|v| v+shift).collect()}
+    }
+
+    /// Returns the integral of the curve from `from` to `to`.
+    pub fn integral(&self, from: f64, to: f64)->f64{
+        self.integrate(from, to, |v| v)
+    }
+
+    /// Returns the integral of the square of the curve from `from` to `to`, e.g. the total variance of a volatility curve.
+    pub fn integral_of_square(&self, from: f64, to: f64)->f64{
+        self.integrate(from, to, |v| v*v)
+    }
+
+    /// Returns the root mean square of the curve on `[from, to]`, or its value at `from` if the interval is empty.
+    pub fn root_mean_square(&self, from: f64, to: f64)->f64{
+        if to == from {
+            return self.value(from).abs();
+        }
+        (self.integral_of_square(from, to)/(to-from)).sqrt()
+    }
+
+    /// Returns the integral of `f` applied to the curve from `from` to `to`.
+    fn integrate(&self, from: f64, to: f64, f: impl Fn(f64)->f64)->f64{
+        if to < from {
+            return -self.integrate(to, from, f);
+        }
+        let mut sum = 0.0;
+        let mut start = from;
+        for (i, value) in self.values.iter().enumerate() {
+            let end = self.breakpoints.get(i).map_or(to, |t| t.min(to));
+            if end > start {
+                sum += f(*value)*(end-start);
+                start = end;
+            }
+            if start >= to {
+                break;
+            }
+        }
+        sum
+    }
+}
+
+impl RateCurve for f64 {
+    fn integral(&self, from: f64, to: f64)->f64{
+        self*(to-from)
+    }
+}
+
+impl RateCurve for PiecewiseConstantCurve {
+    fn integral(&self, from: f64, to: f64)->f64{
+        PiecewiseConstantCurve::integral(self, from, to)
+    }
+}
+
+impl<F: Fn(f64)->f64> RateCurve for RateFunction<F> {
+    fn integral(&self, from: f64, to: f64)->f64{
+        adaptive_simpson(&|t| (self.0)(t), from, to, RATE_FUNCTION_TOLERANCE)
+    }
+}
+
+impl<T: RateCurve+?Sized> RateCurve for &T {
+    fn integral(&self, from: f64, to: f64)->f64{
+        (**self).integral(from, to)
+    }
+}
+
+impl From<f64> for PiecewiseConstantCurve {
+    fn from(value: f64) -> Self {
+        PiecewiseConstantCurve::constant(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piecewise_constant_curve_test(){
+        let curve = PiecewiseConstantCurve::new(vec![1.0, 2.5], vec![0.1, 0.2, 0.4]);
+        assert_eq!(curve.value(0.5), 0.1);
+        assert_eq!(curve.value(1.0), 0.2);
+        assert_eq!(curve.value(3.0), 0.4);
+        assert!((curve.integral(0.5, 3.0)-(0.05+0.3+0.2)).abs() < 1e-12);
+        assert!((curve.integral(3.0, 0.5)+curve.integral(0.5, 3.0)).abs() < 1e-12);
+        assert!((curve.integral_of_square(1.5, 2.0)-0.02).abs() < 1e-12);
+        assert!((curve.root_mean_square(0.0, 2.0)-((0.01+0.04)/2.0_f64).sqrt()).abs() < 1e-12);
+        assert_eq!(curve.root_mean_square(3.0, 3.0), 0.4);
+        assert!((curve.shifted(0.1).value(3.0)-0.5).abs() < 1e-12);
+        assert!(PiecewiseConstantCurve::constant(0.3).is_constant());
+        assert_eq!(PiecewiseConstantCurve::try_new(vec![1.0, 1.0], vec![0.0; 3]), Err(PricingError::InvalidTimeGrid));
+        assert_eq!(PiecewiseConstantCurve::try_new(vec![1.0], vec![0.0]), Err(PricingError::InvalidTimeGrid));
+    }
+
+    #[test]
+    fn rate_curve_test(){
+        let curve = PiecewiseConstantCurve::new(vec![1.0], vec![0.02, 0.04]);
+        assert!((RateCurve::integral(&curve, 0.0, 2.0)-0.06).abs() < 1e-12);
+        assert!((0.03.discount_factor(1.0, 3.0)-(-0.06_f64).exp()).abs() < 1e-15);
+        let function = RateFunction(|t: f64| 0.01*t*t);
+        assert!((function.integral(0.0, 3.0)-0.09).abs() < 1e-10);
+    }
+}
@@ -0,0 +1,371 @@
+//! Provides calendar dates, day count conventions and simple holiday calendars, so that maturities can be given as dates
+//! rather than as hand computed year fractions.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::time::{year_fraction, Date, DayCountConvention};
+//! let start = Date::new(2024, 1, 15);
+//! let end = Date::new(2024, 7, 15);
+//! assert_eq!(year_fraction(start, end, DayCountConvention::Actual360), 182.0/360.0);
+//! assert_eq!(year_fraction(start, end, DayCountConvention::Thirty360), 0.5);
+//! ```
+//!
+//! A `TimeAxis` converts dates to the `TimeStamp`s used by the stocks and options of this library. With the `chrono` feature,
+//! `chrono::NaiveDate` and `chrono::DateTime` convert into `Date`, so they can be used wherever a date is expected.
+
+use crate::error::PricingError;
+use crate::utils::TimeStamp;
+
+/// A date of the proleptic Gregorian calendar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date{
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+/// Returns true if `year` is a leap year.
+pub fn is_leap_year(year: i32)->bool{
+    (year%4 == 0 && year%100 != 0) || year%400 == 0
+}
+
+/// Returns the number of days in the month `month` (from 1 to 12) of `year`.
+fn days_in_month(year: i32, month: u32)->u32{
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 => if is_leap_year(year) { 29 } else { 28 },
+        _ => 31,
+    }
+}
+
+impl Date {
+    /// Returns the date with the given year, month (from 1 to 12) and day of the month.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the date does not exist.
+    pub fn new(year: i32, month: u32, day: u32)->Date{
+        Date::try_new(year, month, day).expect("Invalid date")
+    }
+
+    /// Returns the date with the given year, month (from 1 to 12) and day of the month, or `PricingError::InvalidDate` if the
+    /// date does not exist.
+    pub fn try_new(year: i32, month: u32, day: u32)->Result<Date, PricingError>{
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(PricingError::InvalidDate);
+        }
+        Ok(Date{year, month, day})
+    }
+
+    /// Returns the year.
+    pub fn get_year(&self)->i32{
+        self.year
+    }
+
+    /// Returns the month, from 1 to 12.
+    pub fn get_month(&self)->u32{
+        self.month
+    }
+
+    /// Returns the day of the month.
+    pub fn get_day(&self)->u32{
+        self.day
+    }
+
+    /// Returns the number of days from 1970-01-01 to the date, which is negative for earlier dates.
+    pub fn day_number(&self)->i64{
+        // Counts from 0000-03-01, so that the leap day is the last day of a year.
+        let year = self.year as i64-if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year-era*400;
+        let month = self.month as i64;
+        let day_of_year = (153*(month+if month > 2 { -3 } else { 9 })+2)/5+self.day as i64-1;
+        let day_of_era = year_of_era*365+year_of_era/4-year_of_era/100+day_of_year;
+        era*146097+day_of_era-719468
+    }
+
+    /// Returns the date `day_number` days after 1970-01-01.
+    pub fn from_day_number(day_number: i64)->Date{
+        let shifted = day_number+719468;
+        let era = shifted.div_euclid(146097);
+        let day_of_era = shifted-era*146097;
+        let year_of_era = (day_of_era-day_of_era/1460+day_of_era/36524-day_of_era/146096)/365;
+        let day_of_year = day_of_era-(365*year_of_era+year_of_era/4-year_of_era/100);
+        let shifted_month = (5*day_of_year+2)/153;
+        let day = (day_of_year-(153*shifted_month+2)/5+1) as u32;
+        let month = if shifted_month < 10 { shifted_month+3 } else { shifted_month-9 } as u32;
+        let year = (year_of_era+era*400+if month <= 2 { 1 } else { 0 }) as i32;
+        Date{year, month, day}
+    }
+
+    /// Returns the date `days` days after the date (or before it, if `days` is negative).
+    pub fn add_days(&self, days: i64)->Date{
+        Date::from_day_number(self.day_number()+days)
+    }
+
+    /// Returns the same day of the month `months` months after the date, or the last day of that month if it is shorter.
+    pub fn add_months(&self, months: i32)->Date{
+        let month_index = self.year*12+self.month as i32-1+months;
+        let year = month_index.div_euclid(12);
+        let month = (month_index.rem_euclid(12)+1) as u32;
+        Date{year, month, day: self.day.min(days_in_month(year, month))}
+    }
+
+    /// Returns true if the date is a Saturday or a Sunday.
+    pub fn is_weekend(&self)->bool{
+        // 1970-01-01 was a Thursday.
+        (self.day_number()+3).rem_euclid(7) >= 5
+    }
+}
+
+/// A convention for counting the fraction of a year between two dates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DayCountConvention{
+    /// The actual number of days divided by 365.
+    Actual365Fixed,
+    /// The actual number of days divided by 360.
+    Actual360,
+    /// Months of 30 days and years of 360 days (the US bond basis).
+    Thirty360,
+}
+
+/// Returns the fraction of a year from `start` to `end` under `convention`, which is negative if `end` is before `start`.
+pub fn year_fraction(start: Date, end: Date, convention: DayCountConvention)->f64{
+    let actual_days = (end.day_number()-start.day_number()) as f64;
+    match convention {
+        DayCountConvention::Actual365Fixed => actual_days/365.0,
+        DayCountConvention::Actual360 => actual_days/360.0,
+        DayCountConvention::Thirty360 => {
+            let start_day = start.day.min(30);
+            let end_day = if start_day == 30 { end.day.min(30) } else { end.day };
+            let days = 360*(end.year-start.year)+30*(end.month as i32-start.month as i32)+end_day as i32-start_day as i32;
+            days as f64/360.0
+        }
+    }
+}
+
+/// A convention for moving a date which is not a business day to one that is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BusinessDayConvention{
+    /// The date is left as is.
+    Unadjusted,
+    /// The first business day after the date.
+    Following,
+    /// The first business day after the date, unless it is in the next month, in which case the last business day before it.
+    ModifiedFollowing,
+    /// The last business day before the date.
+    Preceding,
+}
+
+/// A holiday calendar, in which the business days are the week days which are not holidays.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HolidayCalendar{
+    /// The holidays, in increasing order.
+    holidays: Vec<Date>,
+}
+
+impl HolidayCalendar {
+    /// Returns a calendar without holidays, in which the business days are the week days.
+    pub fn new()->HolidayCalendar{
+        HolidayCalendar{holidays: Vec::new()}
+    }
+
+    /// Returns the calendar with `date` added to the holidays.
+    pub fn with_holiday(mut self, date: Date)->HolidayCalendar{
+        if let Err(position) = self.holidays.binary_search(&date) {
+            self.holidays.insert(position, date);
+        }
+        self
+    }
+
+    /// Returns the holidays of the calendar, in increasing order.
+    pub fn get_holidays(&self)->&[Date]{
+        &self.holidays
+    }
+
+    /// Returns true if `date` is neither a weekend day nor a holiday.
+    pub fn is_business_day(&self, date: Date)->bool{
+        !date.is_weekend() && self.holidays.binary_search(&date).is_err()
+    }
+
+    /// Returns `date` moved to a business day according to `convention`.
+    pub fn adjust(&self, date: Date, convention: BusinessDayConvention)->Date{
+        let step_to_business_day = |step: i64| {
+            let mut adjusted = date;
+            while !self.is_business_day(adjusted) {
+                adjusted = adjusted.add_days(step);
+            }
+            adjusted
+        };
+        match convention {
+            BusinessDayConvention::Unadjusted => date,
+            BusinessDayConvention::Following => step_to_business_day(1),
+            BusinessDayConvention::Preceding => step_to_business_day(-1),
+            BusinessDayConvention::ModifiedFollowing => {
+                let following = step_to_business_day(1);
+                if following.month == date.month { following } else { step_to_business_day(-1) }
+            }
+        }
+    }
+
+    /// Returns the date `days` business days after `date` (or before it, if `days` is negative).
+    pub fn add_business_days(&self, date: Date, days: i64)->Date{
+        let step = days.signum();
+        let mut result = date;
+        for _ in 0..days.abs() {
+            result = result.add_days(step);
+            while !self.is_business_day(result) {
+                result = result.add_days(step);
+            }
+        }
+        result
+    }
+
+    /// Returns the number of business days in the interval `(start, end]`, or minus the number in `(end, start]` if `end`
+    /// is before `start`.
+    pub fn business_days_between(&self, start: Date, end: Date)->i64{
+        let (first, last, sign) = if start <= end { (start, end, 1) } else { (end, start, -1) };
+        let count = (first.day_number()+1..=last.day_number())
+            .filter(|day_number| self.is_business_day(Date::from_day_number(*day_number))).count() as i64;
+        sign*count
+    }
+}
+
+/// Converts dates to time stamps, measuring the time from a reference date with a day count convention.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeAxis{
+    /// The date of the time stamp 0.
+    reference_date: Date,
+    /// The convention used to compute the year fractions.
+    convention: DayCountConvention,
+}
+
+impl TimeAxis {
+    /// Returns a time axis whose time stamp 0 is `reference_date`.
+    pub fn new(reference_date: impl Into<Date>, convention: DayCountConvention)->TimeAxis{
+        TimeAxis{
+            reference_date: reference_date.into(),
+            convention,
+        }
+    }
+
+    /// Returns the date of the time stamp 0.
+    pub fn get_reference_date(&self)->Date{
+        self.reference_date
+    }
+
+    /// Returns the day count convention of the axis.
+    pub fn get_convention(&self)->DayCountConvention{
+        self.convention
+    }
+
+    /// Returns the time stamp of `date`, or `PricingError::InvalidDate` if it is before the reference date.
+    pub fn time_stamp(&self, date: impl Into<Date>)->Result<TimeStamp, PricingError>{
+        let date = date.into();
+        if date < self.reference_date {
+            return Err(PricingError::InvalidDate);
+        }
+        Ok(TimeStamp::from(year_fraction(self.reference_date, date, self.convention).max(0.0)))
+    }
+
+    /// Returns the time stamps of `dates`, e.g. of a monitoring schedule, or `PricingError::InvalidDate` if one of them is
+    /// before the reference date.
+    pub fn time_stamps<D: Into<Date>+Copy>(&self, dates: &[D])->Result<Vec<TimeStamp>, PricingError>{
+        dates.iter().map(|date| self.time_stamp(*date)).collect()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDate> for Date {
+    fn from(value: chrono::NaiveDate) -> Self {
+        use chrono::Datelike;
+        Date{year: value.year(), month: value.month(), day: value.day()}
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for Date {
+    /// Returns the local date of `value` in its time zone.
+    fn from(value: chrono::DateTime<Tz>) -> Self {
+        Date::from(value.date_naive())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<Date> for chrono::NaiveDate {
+    type Error = PricingError;
+
+    /// Returns the same date, or `PricingError::InvalidDate` if its year is out of the range of `chrono`.
+    fn try_from(value: Date) -> Result<Self, PricingError> {
+        chrono::NaiveDate::from_ymd_opt(value.year, value.month, value.day).ok_or(PricingError::InvalidDate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_test(){
+        assert_eq!(Date::new(1970, 1, 1).day_number(), 0);
+        assert_eq!(Date::new(2000, 3, 1).day_number(), 11017);
+        assert_eq!(Date::new(1969, 12, 31).day_number(), -1);
+        for day_number in -800000..800000 {
+            if day_number%997 == 0 {
+                assert_eq!(Date::from_day_number(day_number).day_number(), day_number);
+            }
+        }
+        assert_eq!(Date::new(2024, 1, 31).add_months(1), Date::new(2024, 2, 29));
+        assert_eq!(Date::new(2024, 11, 30).add_months(3), Date::new(2025, 2, 28));
+        assert_eq!(Date::try_new(2023, 2, 29), Err(PricingError::InvalidDate));
+        assert!(Date::new(2024, 6, 15).is_weekend());
+        assert!(!Date::new(2024, 6, 17).is_weekend());
+    }
+
+    #[test]
+    fn year_fraction_test(){
+        let start = Date::new(2023, 1, 1);
+        let end = Date::new(2024, 1, 1);
+        assert_eq!(year_fraction(start, end, DayCountConvention::Actual365Fixed), 1.0);
+        assert_eq!(year_fraction(start, end, DayCountConvention::Actual360), 365.0/360.0);
+        assert_eq!(year_fraction(Date::new(2024, 1, 31), Date::new(2024, 3, 31), DayCountConvention::Thirty360), 60.0/360.0);
+        assert_eq!(year_fraction(Date::new(2024, 1, 15), Date::new(2024, 3, 31), DayCountConvention::Thirty360), 76.0/360.0);
+        assert_eq!(year_fraction(end, start, DayCountConvention::Actual365Fixed), -1.0);
+    }
+
+    #[test]
+    fn calendar_test(){
+        let calendar = HolidayCalendar::new().with_holiday(Date::new(2024, 12, 25)).with_holiday(Date::new(2024, 12, 26));
+        assert!(!calendar.is_business_day(Date::new(2024, 12, 25)));
+        assert_eq!(calendar.adjust(Date::new(2024, 12, 25), BusinessDayConvention::Following), Date::new(2024, 12, 27));
+        assert_eq!(calendar.adjust(Date::new(2024, 12, 25), BusinessDayConvention::Preceding), Date::new(2024, 12, 24));
+        // 2024-08-31 is a Saturday and the following business day is in September.
+        assert_eq!(calendar.adjust(Date::new(2024, 8, 31), BusinessDayConvention::ModifiedFollowing), Date::new(2024, 8, 30));
+        assert_eq!(calendar.add_business_days(Date::new(2024, 12, 24), 1), Date::new(2024, 12, 27));
+        assert_eq!(calendar.business_days_between(Date::new(2024, 12, 20), Date::new(2024, 12, 31)), 5);
+        assert_eq!(calendar.business_days_between(Date::new(2024, 12, 31), Date::new(2024, 12, 20)), -5);
+    }
+
+    #[test]
+    fn time_axis_test(){
+        let axis = TimeAxis::new(Date::new(2024, 1, 1), DayCountConvention::Actual365Fixed);
+        assert_eq!(axis.time_stamp(Date::new(2024, 1, 1)), Ok(TimeStamp::from(0.0)));
+        assert_eq!(axis.time_stamps(&[Date::new(2024, 3, 1), Date::new(2025, 1, 1)]),
+            Ok(vec![TimeStamp::from(60.0/365.0), TimeStamp::from(366.0/365.0)]));
+        assert_eq!(axis.time_stamp(Date::new(2023, 12, 31)), Err(PricingError::InvalidDate));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_test(){
+        use chrono::{NaiveDate, TimeZone, Utc};
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(Date::from(date), Date::new(2024, 2, 29));
+        assert_eq!(NaiveDate::try_from(Date::new(2024, 2, 29)), Ok(date));
+        assert_eq!(NaiveDate::try_from(Date::new(300000, 1, 1)), Err(PricingError::InvalidDate));
+        assert_eq!(Date::from(Utc.with_ymd_and_hms(2024, 2, 29, 23, 0, 0).unwrap()), Date::new(2024, 2, 29));
+        let axis = TimeAxis::new(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), DayCountConvention::Actual360);
+        assert_eq!(axis.time_stamp(date), Ok(TimeStamp::from(59.0/360.0)));
+    }
+}
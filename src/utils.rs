@@ -1,6 +1,14 @@
 //! Provides various utilities.
 
-use std::{cmp::Ordering, f64::consts::PI};
+use core::{cmp::Ordering, f64::consts::PI};
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::error::PricingError;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::math::LibmFloat;
 
 
 ///Calculates the inverse cumulative normal function of `x`. `x` must be between 0 and 1, otherwise behaviour is undefined.
@@ -37,9 +45,9 @@ pub fn inverse_cumulative_normal_function(x: f64) -> f64{
     let s = (-(r.ln())).ln();
     let mut t=c[0];
     let mut s2=s;
-    for i in 1..9{
-        t+=c[i]*s2;
-        s2=s2*s;
+    for coefficient in c.iter().skip(1){
+        t+=coefficient*s2;
+        s2*=s;
     }
     if x>0.5{
         t
@@ -49,8 +57,93 @@ pub fn inverse_cumulative_normal_function(x: f64) -> f64{
     }
 }
 
+///The algorithm used to compute the inverse cumulative normal function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum InverseCumulativeNormalAlgorithm{
+    ///Moro's algorithm, with an absolute error of about 1e-9. Used by `inverse_cumulative_normal_function`.
+    #[default]
+    Moro,
+    ///Wichura's algorithm AS241, with a relative error of about 1e-16.
+    Wichura,
+}
+
+///Calculates the inverse cumulative normal function of `x` with the given algorithm. `x` must be between 0 and 1.
+pub fn inverse_cumulative_normal_function_with_algorithm(x: f64, algorithm: InverseCumulativeNormalAlgorithm) -> f64{
+    match algorithm {
+        InverseCumulativeNormalAlgorithm::Moro => inverse_cumulative_normal_function(x),
+        InverseCumulativeNormalAlgorithm::Wichura => wichura_inverse_cumulative_normal_function(x),
+    }
+}
+
+///Calculates the inverse cumulative normal function of `x` with Wichura's algorithm AS241 (PPND16), which has a relative
+///error of about 1e-16. Returns minus infinity at 0 and infinity at 1.
+///# Panics
+///Panics if `x` is not between 0 and 1.
+#[allow(clippy::excessive_precision)]
+pub fn wichura_inverse_cumulative_normal_function(x: f64) -> f64{
+    const A: [f64; 8] = [3.3871328727963666080e0, 1.3314166789178437745e+2, 1.9715909503065514427e+3, 1.3731693765509461125e+4,
+        4.5921953931549871457e+4, 6.7265770927008700853e+4, 3.3430575583588128105e+4, 2.5090809287301226727e+3];
+    const B: [f64; 8] = [1.0, 4.2313330701600911252e+1, 6.8718700749205790830e+2, 5.3941960214247511077e+3,
+        2.1213794301586595867e+4, 3.9307895800092710610e+4, 2.8729085735721942674e+4, 5.2264952788528545610e+3];
+    const C: [f64; 8] = [1.42343711074968357734e0, 4.63033784615654529590e0, 5.76949722146069140550e0, 3.64784832476320460504e0,
+        1.27045825245236838258e0, 2.41780725177450611770e-1, 2.27238449892691845833e-2, 7.74545014278341407640e-4];
+    const D: [f64; 8] = [1.0, 2.05319162663775882187e0, 1.67638483018380384940e0, 6.89767334985100004550e-1,
+        1.48103976427480074590e-1, 1.51986665636164571966e-2, 5.47593808499534494600e-4, 1.05075007164441684324e-9];
+    const E: [f64; 8] = [6.65790464350110377720e0, 5.46378491116411436990e0, 1.78482653991729133580e0, 2.96560571828504891230e-1,
+        2.65321895265761230930e-2, 1.24266094738807843860e-3, 2.71155556874348757815e-5, 2.01033439929228813265e-7];
+    const F: [f64; 8] = [1.0, 5.99832206555887937690e-1, 1.36929880922735805310e-1, 1.48753612908506148525e-2,
+        7.86869131145613259100e-4, 1.84631831751005468180e-5, 1.42151175831644588870e-7, 2.04426310338993978564e-15];
+    fn polynomial(coefficients: &[f64; 8], x: f64) -> f64{
+        coefficients.iter().rev().fold(0.0, |sum, c| sum*x+c)
+    }
+    if !(0.0..=1.0).contains(&x) {
+        panic!("The argument must be between 0 and 1.");
+    }
+    let q = x-0.5;
+    if q.abs() <= 0.425 {
+        let r = 0.180625-q*q;
+        return q*polynomial(&A, r)/polynomial(&B, r);
+    }
+    let r = if q < 0.0 { x } else { 1.0-x };
+    if r == 0.0 {
+        return if q < 0.0 { f64::NEG_INFINITY } else { f64::INFINITY };
+    }
+    let r = (-r.ln()).sqrt();
+    let value = if r <= 5.0 {
+        polynomial(&C, r-1.6)/polynomial(&D, r-1.6)
+    }
+    else {
+        polynomial(&E, r-5.0)/polynomial(&F, r-5.0)
+    };
+    if q < 0.0 { -value } else { value }
+}
+
+///The accuracy of the cumulative normal function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CumulativeNormalPrecision{
+    ///The Abramowitz-Stegun polynomial approximation, with an absolute error of about 1e-7.
+    Fast,
+    ///Cody's rational approximation of `erfc`, with a relative error of about 1e-15, also far in the tails.
+    High,
+}
+
 ///Calculates the cumulative normal function at x. Output will be between 0 and 1.
-pub fn cumulative_normal_function(x: f64) -> f64{
+///Uses the high precision approximation, see `cumulative_normal_function_with_precision`. Generic over `Real`, so that it can be
+///evaluated in single precision or with dual numbers.
+pub fn cumulative_normal_function<T: Real>(x: T) -> T{
+    x.cumulative_normal()
+}
+
+///Calculates the cumulative normal function at x with the given precision. Output will be between 0 and 1.
+pub fn cumulative_normal_function_with_precision(x: f64, precision: CumulativeNormalPrecision) -> f64{
+    match precision {
+        CumulativeNormalPrecision::Fast => fast_cumulative_normal_function(x),
+        CumulativeNormalPrecision::High => 0.5*complementary_error_function(-x/core::f64::consts::SQRT_2),
+    }
+}
+
+///The Abramowitz-Stegun polynomial approximation of the cumulative normal function.
+fn fast_cumulative_normal_function(x: f64) -> f64{
     let x2 = x.abs();
     let k = 1.0/(1.0+0.2316419*x2);
     let k2 = k*1.330274429-1.821255978;
@@ -66,38 +159,232 @@ pub fn cumulative_normal_function(x: f64) -> f64{
     }
 }
 
-///Calculates the standard normal pdf.
-pub fn normal_probability_density_function(x:f64)->f64{
-    let sqrt_two_pi = (2.0*std::f64::consts::PI).sqrt();
+///Calculates the complementary error function `erfc(x) = 2/sqrt(pi) * integral from x to infinity of exp(-t^2)`,
+///with W. J. Cody's rational Chebyshev approximations, which have a relative error of about 1e-15.
+// The coefficients are kept exactly as published by Cody.
+#[allow(clippy::excessive_precision)]
+pub fn complementary_error_function(x: f64) -> f64{
+    const A: [f64; 5] = [3.16112374387056560e00, 1.13864154151050156e02, 3.77485237685302021e02, 3.20937758913846947e03,
+        1.85777706184603153e-1];
+    const B: [f64; 4] = [2.36012909523441209e01, 2.44024637934444173e02, 1.28261652607737228e03, 2.84423683343917062e03];
+    const C: [f64; 9] = [5.64188496988670089e-1, 8.88314979438837594e00, 6.61191906371416295e01, 2.98635138197400131e02,
+        8.81952221241769090e02, 1.71204761263407058e03, 2.05107837782607147e03, 1.23033935479799725e03, 2.15311535474403846e-8];
+    const D: [f64; 8] = [1.57449261107098347e01, 1.17693950891312499e02, 5.37181101862009858e02, 1.62138957456669019e03,
+        3.29079923573345963e03, 4.36261909014324716e03, 3.43936767414372164e03, 1.23033935480374942e03];
+    const P: [f64; 6] = [3.05326634961232344e-1, 3.60344899949804439e-1, 1.25781726111229246e-1, 1.60837851487422766e-2,
+        6.58749161529837803e-4, 1.63153871373020978e-2];
+    const Q: [f64; 5] = [2.56852019228982242e00, 1.87295284992346725e00, 5.27905102951428412e-1, 6.05183413124413191e-2,
+        2.33520497626869185e-3];
+    const ONE_OVER_SQRT_PI: f64 = 5.6418958354775628695e-1;
+    let y = x.abs();
+    if y <= 0.46875 {
+        let y_squared = if y > 1.11e-16 { y*y } else { 0.0 };
+        let mut numerator = A[4]*y_squared;
+        let mut denominator = y_squared;
+        for i in 0..3{
+            numerator = (numerator+A[i])*y_squared;
+            denominator = (denominator+B[i])*y_squared;
+        }
+        return 1.0-x*(numerator+A[3])/(denominator+B[3]);
+    }
+    let tail = if y <= 4.0 {
+        let mut numerator = C[8]*y;
+        let mut denominator = y;
+        for i in 0..7{
+            numerator = (numerator+C[i])*y;
+            denominator = (denominator+D[i])*y;
+        }
+        (numerator+C[7])/(denominator+D[7])
+    }
+    else if y < 26.543 {
+        let inverse_square = 1.0/(y*y);
+        let mut numerator = P[5]*inverse_square;
+        let mut denominator = inverse_square;
+        for i in 0..4{
+            numerator = (numerator+P[i])*inverse_square;
+            denominator = (denominator+Q[i])*inverse_square;
+        }
+        (ONE_OVER_SQRT_PI-inverse_square*(numerator+P[4])/(denominator+Q[4]))/y
+    }
+    else {
+        // The tail underflows, and is returned directly so that infinite arguments do not give NaN.
+        return if x < 0.0 { 2.0 } else { 0.0 };
+    };
+    // exp(-y^2) is split in two factors to avoid cancellation.
+    let rounded = (y*16.0).trunc()/16.0;
+    let result = (-rounded*rounded).exp()*(-(y-rounded)*(y+rounded)).exp()*tail;
+    if x < 0.0 { 2.0-result } else { result }
+}
+
+///Calculates the standard normal pdf. Generic over `Real`, see `cumulative_normal_function`.
+pub fn normal_probability_density_function<T: Real>(x: T)->T{
+    x.normal_density()
+}
+
+///The standard normal pdf in double precision, which `Real::normal_density` evaluates.
+fn standard_normal_density(x: f64)->f64{
+    let sqrt_two_pi = (2.0*core::f64::consts::PI).sqrt();
     let ex = (-0.5*x*x).exp();
     ex*(1.0/sqrt_two_pi)
 }
 
+///Calculates the bivariate cumulative normal function, i.e. `P(X<a, Y<b)` for standard normal `X` and `Y` with correlation `rho`,
+///with Genz's implementation of the Drezner-Wesolowsky algorithm.
+///# Panics
+///Panics if `rho` is not in [-1,1].
+pub fn bivariate_cumulative_normal(a: f64, b: f64, rho: f64) -> f64{
+    if !(-1.0..=1.0).contains(&rho){
+        panic!("Correlation must be between -1 and 1.");
+    }
+    if a == f64::NEG_INFINITY || b == f64::NEG_INFINITY {
+        return 0.0;
+    }
+    if a == f64::INFINITY {
+        return cumulative_normal_function(b);
+    }
+    if b == f64::INFINITY {
+        return cumulative_normal_function(a);
+    }
+    if rho == 0.0 {
+        return cumulative_normal_function(a)*cumulative_normal_function(b);
+    }
+    // The algorithm computes P(X>h, Y>k).
+    let (h, mut k) = (-a, -b);
+    let mut hk = h*k;
+    let (half_weights, half_nodes): (&[f64], &[f64]) = if rho.abs() < 0.3 {
+        (&[0.1713244923791705, 0.3607615730481384, 0.4679139345726904],
+         &[0.9324695142031522, 0.6612093864662647, 0.238619186083197])
+    }
+    else if rho.abs() < 0.75 {
+        (&[0.04717533638651177, 0.1069393259953183, 0.1600783285433464, 0.2031674267230659, 0.2334925365383547, 0.2491470458134029],
+         &[0.9815606342467191, 0.904117256370475, 0.769902674194305, 0.5873179542866171, 0.3678314989981802, 0.1252334085114692])
+    }
+    else {
+        (&[0.01761400713915212, 0.04060142980038694, 0.06267204833410906, 0.08327674157670475, 0.1019301198172404,
+           0.1181945319615184, 0.1316886384491766, 0.1420961093183821, 0.1491729864726037, 0.1527533871307259],
+         &[0.9931285991850949, 0.9639719272779138, 0.912234428251326, 0.8391169718222188, 0.7463319064601508,
+           0.636053680726515, 0.5108670019508271, 0.3737060887154196, 0.2277858511416451, 0.07652652113349733])
+    };
+    // Gauss-Legendre nodes on [0, 2].
+    let nodes: Vec<(f64, f64)> = half_nodes.iter().zip(half_weights)
+        .flat_map(|(x, w)| [(1.0-x, *w), (1.0+x, *w)]).collect();
+    let two_pi = 2.0*PI;
+    let mut bvn = 0.0;
+    if rho.abs() < 0.925 {
+        let hs = 0.5*(h*h+k*k);
+        let asr = 0.5*rho.asin();
+        for (x, w) in nodes.iter(){
+            let sn = (asr*x).sin();
+            bvn += w*((sn*hk-hs)/(1.0-sn*sn)).exp();
+        }
+        return (bvn*asr/two_pi+cumulative_normal_function(-h)*cumulative_normal_function(-k)).clamp(0.0, 1.0);
+    }
+    if rho < 0.0 {
+        k = -k;
+        hk = -hk;
+    }
+    if rho.abs() < 1.0 {
+        let a_squared = 1.0-rho*rho;
+        let mut a = a_squared.sqrt();
+        let bs = (h-k)*(h-k);
+        let c = (4.0-hk)/8.0;
+        let d = (12.0-hk)/80.0;
+        let asr = -0.5*(bs/a_squared+hk);
+        if asr > -100.0 {
+            bvn = a*asr.exp()*(1.0-c*(bs-a_squared)*(1.0-d*bs)/3.0+c*d*a_squared*a_squared);
+        }
+        if hk > -100.0 {
+            let b = bs.sqrt();
+            let sp = two_pi.sqrt()*cumulative_normal_function(-b/a);
+            bvn -= (-0.5*hk).exp()*sp*b*(1.0-c*bs*(1.0-d*bs)/3.0);
+        }
+        a *= 0.5;
+        for (x, w) in nodes.iter(){
+            let xs = (a*x)*(a*x);
+            let asr = -0.5*(bs/xs+hk);
+            if asr > -100.0 {
+                let sp = 1.0+c*xs*(1.0+5.0*d*xs);
+                let rs = (1.0-xs).sqrt();
+                let ep = (-0.5*hk*xs/((1.0+rs)*(1.0+rs))).exp()/rs;
+                bvn += a*w*asr.exp()*(ep-sp);
+            }
+        }
+        bvn = -bvn/two_pi;
+    }
+    if rho > 0.0 {
+        bvn += cumulative_normal_function(-f64::max(h, k));
+    }
+    else if h >= k {
+        bvn = -bvn;
+    }
+    else {
+        let l = if h < 0.0 {
+            cumulative_normal_function(k)-cumulative_normal_function(h)
+        }
+        else {
+            cumulative_normal_function(-h)-cumulative_normal_function(-k)
+        };
+        bvn = l-bvn;
+    }
+    bvn.clamp(0.0, 1.0)
+}
+
+///Calculates the trivariate cumulative normal function, i.e. `P(X<a, Y<b, Z<c)` for standard normal `X`, `Y` and `Z` with
+///pairwise correlations `rho_xy`, `rho_xz` and `rho_yz`, by integrating the conditional bivariate cumulative normal function
+///over `X` with adaptive Simpson's rule.
+///# Panics
+///Panics if one of the correlations is not in [-1,1], or if `rho_xy` or `rho_xz` are -1 or 1.
+pub fn trivariate_cumulative_normal(a: f64, b: f64, c: f64, rho_xy: f64, rho_xz: f64, rho_yz: f64) -> f64{
+    if [rho_xy, rho_xz, rho_yz].iter().any(|r| !(-1.0..=1.0).contains(r)){
+        panic!("Correlation must be between -1 and 1.");
+    }
+    if rho_xy.abs() == 1.0 || rho_xz.abs() == 1.0 {
+        panic!("The first variable must not be perfectly correlated with the others.");
+    }
+    let sigma_y = (1.0-rho_xy*rho_xy).sqrt();
+    let sigma_z = (1.0-rho_xz*rho_xz).sqrt();
+    let conditional_correlation = ((rho_yz-rho_xy*rho_xz)/(sigma_y*sigma_z)).clamp(-1.0, 1.0);
+    let integrand = |x: f64| normal_probability_density_function(x)*
+        bivariate_cumulative_normal((b-rho_xy*x)/sigma_y, (c-rho_xz*x)/sigma_z, conditional_correlation);
+    let lower = -10.0;
+    if a <= lower {
+        return 0.0;
+    }
+    crate::numerics::integration::adaptive_simpson(&integrand, lower, a.min(10.0), 1e-10).clamp(0.0, 1.0)
+}
+
 ///A tuple like struct for storing non-negative f64s.
 /// 
+/// Supports arithmetic which keeps the result non-negative: addition, saturating subtraction (or `checked_sub`), multiplication and division,
+/// as well as multiplication and division by an `f64` (which return an `f64`) and comparison with `f64`.
+/// Since `NonNegativeFloat` implements `From<f64>`, the fallible conversion is the inherent `NonNegativeFloat::try_from`.
+/// 
 /// # Examples
 /// 
 /// ```
+/// use derivative_pricer::utils::NonNegativeFloat;
 /// let x =NonNegativeFloat::from(5.5);
 /// assert_eq!(5.5, f64::from(x));
+/// assert_eq!(x+NonNegativeFloat::from(1.0), 6.5);
+/// assert_eq!(NonNegativeFloat::from(1.0)-x, 0.0);
 /// ```
 #[derive(Clone, Copy, Debug)]
 pub struct NonNegativeFloat(f64);
 
-impl std::cmp::PartialOrd for NonNegativeFloat {
-    fn partial_cmp(&self, other: &NonNegativeFloat) -> std::option::Option<std::cmp::Ordering> {
-        Some(self.0.partial_cmp(&other.0).unwrap())
+impl core::cmp::PartialOrd for NonNegativeFloat {
+    fn partial_cmp(&self, other: &NonNegativeFloat) -> core::option::Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl std::cmp::PartialEq for NonNegativeFloat {
+impl core::cmp::PartialEq for NonNegativeFloat {
     fn eq(&self, other: &NonNegativeFloat) -> bool {
         self.0 == other.0
     }
 }
 
 impl Ord for NonNegativeFloat {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         let x = f64::from(*self);
         let y = f64::from(*other);
         if f64::abs(x-y)<1e-10{
@@ -120,26 +407,570 @@ impl From<f64> for NonNegativeFloat {
     /// 
     /// Panics if gets a negative value.
     fn from(value: f64) -> Self {
-        if value < 0.0 {
+        if value < 0.0 || value.is_nan() {
             panic!("Got a negative number.")
         }
         NonNegativeFloat(value)
     }
 }
 
-impl std::fmt::Display for NonNegativeFloat {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl NonNegativeFloat {
+    ///Creates a new NonNegativeFloat from an f64, returning `PricingError::NegativeInput` if `value` is negative or NaN.
+    pub fn try_from(value: f64) -> Result<NonNegativeFloat, PricingError> {
+        if value < 0.0 || value.is_nan() {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(NonNegativeFloat(value))
+    }
+}
+
+impl NonNegativeFloat {
+    ///Returns `self-rhs`, or `None` if the result would be negative.
+    pub fn checked_sub(self, rhs: NonNegativeFloat) -> Option<NonNegativeFloat> {
+        if self.0 < rhs.0 {
+            return None;
+        }
+        Some(NonNegativeFloat(self.0-rhs.0))
+    }
+}
+
+impl core::ops::Add for NonNegativeFloat {
+    type Output = NonNegativeFloat;
+    fn add(self, rhs: NonNegativeFloat) -> NonNegativeFloat {
+        NonNegativeFloat(self.0+rhs.0)
+    }
+}
+
+impl core::ops::AddAssign for NonNegativeFloat {
+    fn add_assign(&mut self, rhs: NonNegativeFloat) {
+        self.0 += rhs.0;
+    }
+}
+
+impl core::ops::Sub for NonNegativeFloat {
+    type Output = NonNegativeFloat;
+    ///Saturating subtraction, i.e. returns zero if `rhs` is larger than `self`. Use `checked_sub` to detect this case.
+    fn sub(self, rhs: NonNegativeFloat) -> NonNegativeFloat {
+        NonNegativeFloat(f64::max(self.0-rhs.0, 0.0))
+    }
+}
+
+impl core::ops::Mul for NonNegativeFloat {
+    type Output = NonNegativeFloat;
+    fn mul(self, rhs: NonNegativeFloat) -> NonNegativeFloat {
+        NonNegativeFloat::from(self.0*rhs.0)
+    }
+}
+
+impl core::ops::Mul<f64> for NonNegativeFloat {
+    type Output = f64;
+    fn mul(self, rhs: f64) -> f64 {
+        self.0*rhs
+    }
+}
+
+impl core::ops::Div for NonNegativeFloat {
+    type Output = NonNegativeFloat;
+    /// # Panics
+    /// 
+    /// Panics if both `self` and `rhs` are zero.
+    fn div(self, rhs: NonNegativeFloat) -> NonNegativeFloat {
+        NonNegativeFloat::from(self.0/rhs.0)
+    }
+}
+
+impl core::ops::Div<f64> for NonNegativeFloat {
+    type Output = f64;
+    fn div(self, rhs: f64) -> f64 {
+        self.0/rhs
+    }
+}
+
+impl core::iter::Sum for NonNegativeFloat {
+    fn sum<I: Iterator<Item = NonNegativeFloat>>(iter: I) -> NonNegativeFloat {
+        iter.fold(NonNegativeFloat(0.0), |a, b| a+b)
+    }
+}
+
+impl<'a> core::iter::Sum<&'a NonNegativeFloat> for NonNegativeFloat {
+    fn sum<I: Iterator<Item = &'a NonNegativeFloat>>(iter: I) -> NonNegativeFloat {
+        iter.fold(NonNegativeFloat(0.0), |a, b| a+*b)
+    }
+}
+
+impl core::cmp::PartialEq<f64> for NonNegativeFloat {
+    fn eq(&self, other: &f64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl core::cmp::PartialOrd<f64> for NonNegativeFloat {
+    fn partial_cmp(&self, other: &f64) -> core::option::Option<core::cmp::Ordering> {
+        self.0.partial_cmp(other)
+    }
+}
+
+impl core::fmt::Display for NonNegativeFloat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
-impl std::convert::From<NonNegativeFloat> for f64{
+impl core::convert::From<NonNegativeFloat> for f64{
     fn from(value: NonNegativeFloat) -> Self {
         value.0
     }
 }
 
-pub type TimeStamp = NonNegativeFloat;
+///A point in time, measured in years. Subtracting an earlier time stamp gives a `Duration` (`checked_duration_since` also
+///handles later time stamps), and adding a `Duration` to a time stamp gives a time stamp.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use derivative_pricer::utils::{Duration, TimeStamp};
+/// let t = TimeStamp::from(1.5);
+/// assert_eq!(t+Duration::from(0.5), TimeStamp::from(2.0));
+/// assert_eq!(TimeStamp::from(2.0)-t, Duration::from(0.5));
+/// assert_eq!(TimeStamp::from(2.0).checked_duration_since(t), Some(Duration::from(0.5)));
+/// assert_eq!(t.checked_duration_since(TimeStamp::from(2.0)), None);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeStamp(NonNegativeFloat);
+
+impl TimeStamp {
+    ///Creates a new TimeStamp from an f64, returning `PricingError::NegativeInput` if `value` is negative or NaN.
+    pub fn try_from(value: f64) -> Result<TimeStamp, PricingError> {
+        Ok(TimeStamp(NonNegativeFloat::try_from(value)?))
+    }
+
+    ///Returns the duration from `earlier` to `self`, or `None` if `earlier` is later than `self`.
+    pub fn checked_duration_since(self, earlier: TimeStamp) -> Option<Duration> {
+        self.0.checked_sub(earlier.0).map(Duration)
+    }
+}
+
+impl From<f64> for TimeStamp {
+    ///Creates a new TimeStamp from an f64.
+    /// 
+    /// #Panics
+    /// 
+    /// Panics if gets a negative value.
+    fn from(value: f64) -> Self {
+        TimeStamp(NonNegativeFloat::from(value))
+    }
+}
+
+impl core::convert::From<TimeStamp> for f64{
+    fn from(value: TimeStamp) -> Self {
+        f64::from(value.0)
+    }
+}
+
+impl core::fmt::Display for TimeStamp {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl core::ops::Sub for TimeStamp {
+    type Output = Duration;
+    ///Returns the duration from `rhs` to `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rhs` is later than `self`. Use `checked_duration_since` to handle this case.
+    fn sub(self, rhs: TimeStamp) -> Duration {
+        self.checked_duration_since(rhs).unwrap_or_else(|| panic!("Cannot subtract the time stamp {} from the earlier time stamp {}.", rhs, self))
+    }
+}
+
+impl core::ops::Add<Duration> for TimeStamp {
+    type Output = TimeStamp;
+    fn add(self, rhs: Duration) -> TimeStamp {
+        TimeStamp(self.0+rhs.0)
+    }
+}
+
+impl core::ops::AddAssign<Duration> for TimeStamp {
+    fn add_assign(&mut self, rhs: Duration) {
+        self.0 += rhs.0;
+    }
+}
+
+///A non-negative length of time, measured in years.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Duration(NonNegativeFloat);
+
+impl Duration {
+    ///Creates a new Duration from an f64, returning `PricingError::NegativeInput` if `value` is negative or NaN.
+    pub fn try_from(value: f64) -> Result<Duration, PricingError> {
+        Ok(Duration(NonNegativeFloat::try_from(value)?))
+    }
+
+    ///Returns `self-rhs`, or `None` if `rhs` is longer than `self`.
+    pub fn checked_sub(self, rhs: Duration) -> Option<Duration> {
+        self.0.checked_sub(rhs.0).map(Duration)
+    }
+}
+
+impl From<f64> for Duration {
+    ///Creates a new Duration from an f64.
+    /// 
+    /// #Panics
+    /// 
+    /// Panics if gets a negative value.
+    fn from(value: f64) -> Self {
+        Duration(NonNegativeFloat::from(value))
+    }
+}
+
+impl core::convert::From<Duration> for f64{
+    fn from(value: Duration) -> Self {
+        f64::from(value.0)
+    }
+}
+
+impl core::fmt::Display for Duration {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl core::ops::Add for Duration {
+    type Output = Duration;
+    fn add(self, rhs: Duration) -> Duration {
+        Duration(self.0+rhs.0)
+    }
+}
+
+impl core::ops::Sub for Duration {
+    type Output = Duration;
+    ///Returns `self-rhs`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rhs` is longer than `self`. Use `checked_sub` to handle this case.
+    fn sub(self, rhs: Duration) -> Duration {
+        self.checked_sub(rhs).unwrap_or_else(|| panic!("Cannot subtract the duration {} from the shorter duration {}.", rhs, self))
+    }
+}
+
+/// A trait for the numeric types the generic formulas in `raw_formulas` (the Black-Scholes prices and the greeks of european
+/// options), `cumulative_normal_function` and `normal_probability_density_function` can be evaluated with.
+/// Implemented for `f64`, `f32` and `Dual`, so that the same formula can return a price in double or single precision, or a
+/// price together with its derivatives. Implementing it for a higher precision type (e.g. a decimal or double-double type)
+/// makes the pricing formulas available in that type.
+///
+/// # Examples
+///
+/// ```
+/// use derivative_pricer::raw_formulas::european_call_option_price;
+/// let single = european_call_option_price(100.0_f32, 100.0, 0.05, 1.0, 0.2, 0.0);
+/// let double = european_call_option_price(100.0_f64, 100.0, 0.05, 1.0, 0.2, 0.0);
+/// assert!((single as f64-double).abs() < 1e-4);
+/// ```
+pub trait Real: Copy + Add<Output=Self> + Sub<Output=Self> + Mul<Output=Self> + Div<Output=Self> + Neg<Output=Self>{
+    /// Returns a constant, i.e. a number whose derivatives are all zero.
+    fn constant(x: f64)->Self;
+    /// Returns the underlying `f64` value, dropping any derivatives.
+    fn value(self)->f64;
+    fn exp(self)->Self;
+    fn ln(self)->Self;
+    fn sqrt(self)->Self;
+    /// Returns the cumulative normal function evaluated at `self`.
+    fn cumulative_normal(self)->Self;
+    /// Returns the standard normal pdf evaluated at `self`.
+    fn normal_density(self)->Self;
+}
+
+impl Real for f64 {
+    fn constant(x: f64)->Self {
+        x
+    }
+
+    fn value(self)->f64 {
+        self
+    }
+
+    fn exp(self)->Self {
+        crate::math::exp(self)
+    }
+
+    fn ln(self)->Self {
+        crate::math::ln(self)
+    }
+
+    fn sqrt(self)->Self {
+        crate::math::sqrt(self)
+    }
+
+    fn cumulative_normal(self)->Self {
+        cumulative_normal_function_with_precision(self, CumulativeNormalPrecision::High)
+    }
+
+    fn normal_density(self)->Self {
+        standard_normal_density(self)
+    }
+}
+
+/// Single precision. The normal distribution functions are evaluated in double precision and rounded.
+impl Real for f32 {
+    fn constant(x: f64)->Self {
+        x as f32
+    }
+
+    fn value(self)->f64 {
+        self as f64
+    }
+
+    fn exp(self)->Self {
+        crate::math::exp_f32(self)
+    }
+
+    fn ln(self)->Self {
+        crate::math::ln_f32(self)
+    }
+
+    fn sqrt(self)->Self {
+        crate::math::sqrt_f32(self)
+    }
+
+    fn cumulative_normal(self)->Self {
+        cumulative_normal_function_with_precision(self as f64, CumulativeNormalPrecision::High) as f32
+    }
+
+    fn normal_density(self)->Self {
+        standard_normal_density(self as f64) as f32
+    }
+}
+
+///A dual number `value + derivative*e`, where `e*e=0`, used for forward mode automatic differentiation.
+/// 
+/// Evaluating a function on `Dual::variable(x)` returns the value of the function at `x` together with its derivative at `x`.
+/// Nesting dual numbers, i.e. using `Dual<Dual<f64>>`, gives second derivatives.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use derivative_pricer::utils::{Dual, Real};
+/// let x = Dual::variable(3.0);
+/// let y = x*x+x.exp();
+/// assert_eq!(y.get_value(), 9.0+3.0_f64.exp());
+/// assert_eq!(y.get_derivative(), 6.0+3.0_f64.exp());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dual<T=f64>{
+    value: T,
+    derivative: T,
+}
+
+impl<T: Real> Dual<T> {
+    ///Returns a new dual number with given value and derivative.
+    pub fn new(value: T, derivative: T)->Dual<T>{
+        Dual{
+            value,
+            derivative,
+        }
+    }
+
+    ///Returns the dual number representing the variable being differentiated by, i.e. with derivative 1.
+    pub fn variable(value: T)->Dual<T>{
+        Dual::new(value, T::constant(1.0))
+    }
+
+    ///Returns the value.
+    pub fn get_value(&self)->T{
+        self.value
+    }
+
+    ///Returns the derivative.
+    pub fn get_derivative(&self)->T{
+        self.derivative
+    }
+}
+
+impl<T: Real> Add for Dual<T> {
+    type Output = Dual<T>;
+    fn add(self, rhs: Self) -> Self::Output {
+        Dual::new(self.value+rhs.value, self.derivative+rhs.derivative)
+    }
+}
+
+impl<T: Real> Sub for Dual<T> {
+    type Output = Dual<T>;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Dual::new(self.value-rhs.value, self.derivative-rhs.derivative)
+    }
+}
+
+impl<T: Real> Mul for Dual<T> {
+    type Output = Dual<T>;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Dual::new(self.value*rhs.value, self.derivative*rhs.value+self.value*rhs.derivative)
+    }
+}
+
+impl<T: Real> Div for Dual<T> {
+    type Output = Dual<T>;
+    fn div(self, rhs: Self) -> Self::Output {
+        Dual::new(self.value/rhs.value, (self.derivative*rhs.value-self.value*rhs.derivative)/(rhs.value*rhs.value))
+    }
+}
+
+impl<T: Real> Neg for Dual<T> {
+    type Output = Dual<T>;
+    fn neg(self) -> Self::Output {
+        Dual::new(-self.value, -self.derivative)
+    }
+}
+
+impl<T: Real> Add<f64> for Dual<T> {
+    type Output = Dual<T>;
+    fn add(self, rhs: f64) -> Self::Output {
+        Dual::new(self.value+T::constant(rhs), self.derivative)
+    }
+}
+
+impl<T: Real> Sub<f64> for Dual<T> {
+    type Output = Dual<T>;
+    fn sub(self, rhs: f64) -> Self::Output {
+        Dual::new(self.value-T::constant(rhs), self.derivative)
+    }
+}
+
+impl<T: Real> Mul<f64> for Dual<T> {
+    type Output = Dual<T>;
+    fn mul(self, rhs: f64) -> Self::Output {
+        Dual::new(self.value*T::constant(rhs), self.derivative*T::constant(rhs))
+    }
+}
+
+impl<T: Real> Div<f64> for Dual<T> {
+    type Output = Dual<T>;
+    fn div(self, rhs: f64) -> Self::Output {
+        Dual::new(self.value/T::constant(rhs), self.derivative/T::constant(rhs))
+    }
+}
+
+impl<T: Real> Real for Dual<T> {
+    fn constant(x: f64)->Self {
+        Dual::new(T::constant(x), T::constant(0.0))
+    }
+
+    fn value(self)->f64 {
+        self.value.value()
+    }
+
+    fn exp(self)->Self {
+        let e = self.value.exp();
+        Dual::new(e, e*self.derivative)
+    }
+
+    fn ln(self)->Self {
+        Dual::new(self.value.ln(), self.derivative/self.value)
+    }
+
+    fn sqrt(self)->Self {
+        let root = self.value.sqrt();
+        Dual::new(root, self.derivative/(root*T::constant(2.0)))
+    }
+
+    fn cumulative_normal(self)->Self {
+        Dual::new(self.value.cumulative_normal(), self.value.normal_density()*self.derivative)
+    }
+
+    fn normal_density(self)->Self {
+        let density = self.value.normal_density();
+        Dual::new(density, -(self.value*density*self.derivative))
+    }
+}
+
+///A correlation matrix, stored together with its Cholesky factor `L` (with `L*L^T` equal to the matrix), which maps independent
+///standard Gaussian samples to correlated ones.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CorrelationMatrix{
+    ///The entries of the matrix, row by row.
+    entries: Vec<Vec<f64>>,
+    ///The lower triangular Cholesky factor of the matrix.
+    cholesky_factor: Vec<Vec<f64>>,
+}
+
+impl CorrelationMatrix {
+    ///Returns a new correlation matrix.
+    ///# Panics
+    ///Panics if `entries` is not a symmetric positive semi-definite matrix with ones on the diagonal.
+    pub fn new(entries: Vec<Vec<f64>>)->CorrelationMatrix{
+        CorrelationMatrix::try_new(entries).expect("Invalid correlation matrix.")
+    }
+
+    ///Same as `new`, but returns `PricingError::InvalidMarketData` instead of panicking.
+    pub fn try_new(entries: Vec<Vec<f64>>)->Result<CorrelationMatrix, PricingError>{
+        const TOLERANCE: f64 = 1e-12;
+        let n = entries.len();
+        if n == 0 || entries.iter().any(|row| row.len() != n) {
+            return Err(PricingError::InvalidMarketData);
+        }
+        for (i, row) in entries.iter().enumerate(){
+            if (row[i]-1.0).abs() > TOLERANCE {
+                return Err(PricingError::InvalidMarketData);
+            }
+            for (j, entry) in row.iter().enumerate().take(i){
+                if (entry-entries[j][i]).abs() > TOLERANCE || !(-1.0..=1.0).contains(entry) {
+                    return Err(PricingError::InvalidMarketData);
+                }
+            }
+        }
+        let mut cholesky_factor = vec![vec![0.0; n]; n];
+        for j in 0..n{
+            let pivot = entries[j][j]-cholesky_factor[j][..j].iter().map(|x| x*x).sum::<f64>();
+            if pivot < -TOLERANCE {
+                return Err(PricingError::InvalidMarketData);
+            }
+            // A zero pivot means the matrix is singular, and the column is a combination of the previous ones, which is only
+            // positive semi-definite if the previous columns already give the correlations with the later variables.
+            let diagonal = pivot.max(0.0).sqrt();
+            cholesky_factor[j][j] = diagonal;
+            for i in j+1..n{
+                let dot: f64 = (0..j).map(|k| cholesky_factor[i][k]*cholesky_factor[j][k]).sum();
+                let residual = entries[i][j]-dot;
+                if diagonal > TOLERANCE {
+                    cholesky_factor[i][j] = residual/diagonal;
+                } else if residual.abs() > TOLERANCE {
+                    return Err(PricingError::InvalidMarketData);
+                }
+            }
+        }
+        Ok(CorrelationMatrix{entries, cholesky_factor})
+    }
+
+    ///Returns the dimension of the matrix.
+    pub fn get_dimension(&self)->usize{
+        self.entries.len()
+    }
+
+    ///Returns the correlation between the `i`-th and `j`-th variables.
+    pub fn get_correlation(&self, i: usize, j: usize)->f64{
+        self.entries[i][j]
+    }
+
+    ///Returns the lower triangular Cholesky factor of the matrix.
+    pub fn get_cholesky_factor(&self)->&[Vec<f64>]{
+        &self.cholesky_factor
+    }
+
+    ///Returns the correlated Gaussian vector `L*independent_gaussians`.
+    ///# Panics
+    ///Panics if the length of `independent_gaussians` is not the dimension of the matrix.
+    pub fn correlate(&self, independent_gaussians: &[f64])->Vec<f64>{
+        if independent_gaussians.len() != self.get_dimension() {
+            panic!("Incorrect length of random_samples");
+        }
+        self.cholesky_factor.iter()
+            .map(|row| row.iter().zip(independent_gaussians).map(|(l, z)| l*z).sum())
+            .collect()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -155,6 +986,32 @@ mod tests {
         println!("{}",inverse_cumulative_normal_function(0.93));
     }
 
+    #[test]
+    fn dual_test1(){
+        let x = Dual::variable(0.7);
+        let y = (x*x).ln()/x.sqrt()-x.cumulative_normal();
+        let expected = (2.0-0.5*(0.7_f64*0.7).ln())/0.7_f64.powf(1.5)-normal_probability_density_function(0.7);
+        assert!((y.get_derivative()-expected).abs()<1e-12);
+    }
+
+    #[test]
+    fn dual_second_derivative_test(){
+        let x = Dual::new(Dual::variable(0.3), Dual::constant(1.0));
+        let y = x.cumulative_normal();
+        assert!((y.get_derivative().get_derivative()+0.3*normal_probability_density_function(0.3_f64)).abs()<1e-15);
+    }
+
+    #[test]
+    fn single_precision_test(){
+        let price = crate::raw_formulas::european_put_option_price(101.2_f32, 123.0, 0.07, 1.43, 0.15, 0.03);
+        let expected = crate::raw_formulas::european_put_option_price(101.2_f64, 123.0, 0.07, 1.43, 0.15, 0.03);
+        assert!((price as f64-expected).abs()/expected < 1e-5);
+        let spot = Dual::variable(101.2_f32);
+        let c = |x: f32| Dual::new(x, 0.0);
+        let delta = crate::raw_formulas::european_call_option_price(spot, c(123.0), c(0.07), c(1.43), c(0.15), c(0.03)).get_derivative();
+        assert!((delta as f64-crate::raw_formulas::call_delta(101.2, 123.0, 0.07, 1.43, 0.15, 0.03)).abs() < 1e-5);
+    }
+
     #[test]
     fn non_negative_float_test1(){
         let nnf = NonNegativeFloat::from(6.4);
@@ -168,6 +1025,54 @@ mod tests {
         let _nnf = NonNegativeFloat::from(-6.4);
     }
 
+    #[test]
+    fn non_negative_float_try_from_test(){
+        assert_eq!(NonNegativeFloat::try_from(2.5), Ok(NonNegativeFloat::from(2.5)));
+        assert_eq!(NonNegativeFloat::try_from(-2.5), Err(PricingError::NegativeInput));
+        assert_eq!(NonNegativeFloat::try_from(f64::NAN), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn non_negative_float_arithmetic_test(){
+        let x = NonNegativeFloat::from(3.0);
+        let y = NonNegativeFloat::from(2.0);
+        assert_eq!(x+y, 5.0);
+        assert_eq!(x-y, 1.0);
+        assert_eq!(y-x, 0.0);
+        assert_eq!(y.checked_sub(x), None);
+        assert_eq!(x*y, 6.0);
+        assert_eq!(x/y, 1.5);
+        assert_eq!(x*(-1.0), -3.0);
+        assert_eq!([x, y, x].iter().sum::<NonNegativeFloat>(), 8.0);
+        assert!(x > 2.5 && x < 3.5);
+    }
+
+    #[test]
+    fn time_stamp_test(){
+        let t = TimeStamp::from(1.0);
+        let later = t+Duration::from(2.5);
+        assert_eq!(later, TimeStamp::from(3.5));
+        assert_eq!(later.checked_duration_since(t), Some(Duration::from(2.5)));
+        assert_eq!(later-t, Duration::from(2.5));
+        assert_eq!(t.checked_duration_since(later), None);
+        assert!(t < later);
+        assert_eq!(Duration::from(2.5)-Duration::from(1.0), Duration::from(1.5));
+        assert_eq!(Duration::from(2.5).checked_sub(Duration::from(1.0)), Some(Duration::from(1.5)));
+        assert_eq!(Duration::from(1.0).checked_sub(Duration::from(2.5)), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn time_stamp_sub_test(){
+        let _ = TimeStamp::from(1.0)-TimeStamp::from(3.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn duration_sub_test(){
+        let _ = Duration::from(1.0)-Duration::from(2.5);
+    }
+
     #[test]
     fn non_negative_float_test3(){
         let nnf = NonNegativeFloat::from(0.0);
@@ -179,4 +1084,83 @@ mod tests {
     fn non_negative_float_test4(){
         let _nnf = NonNegativeFloat::from(f64::NAN);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn bivariate_cumulative_normal_test(){
+        for rho in [-0.99_f64, -0.8, -0.5, -0.1, 0.2, 0.6, 0.9, 0.95]{
+            let expected = 0.25+rho.asin()/(2.0*PI);
+            assert!((bivariate_cumulative_normal(0.0, 0.0, rho)-expected).abs()<1e-12);
+            // Compare with a direct integration of P(Y<b | X=x) over x.
+            let (a, b) = (0.7, -0.4);
+            let direct = crate::numerics::integration::adaptive_simpson(&|x| normal_probability_density_function(x)*
+                cumulative_normal_function((b-rho*x)/(1.0-rho*rho).sqrt()), -12.0, a, 1e-12);
+            assert!((bivariate_cumulative_normal(a, b, rho)-direct).abs()<1e-6);
+        }
+        assert!((bivariate_cumulative_normal(0.3, 0.5, 0.0)-cumulative_normal_function(0.3)*cumulative_normal_function(0.5)).abs()<1e-15);
+        assert!((bivariate_cumulative_normal(0.3, 0.5, 1.0)-cumulative_normal_function(0.3)).abs()<1e-7);
+        assert!(bivariate_cumulative_normal(-0.3, 0.2, -1.0).abs()<1e-7);
+    }
+
+    #[test]
+    fn trivariate_cumulative_normal_test(){
+        let (r12, r13, r23) = (0.3, -0.2, 0.5);
+        let expected = 0.125+(f64::asin(r12)+f64::asin(r13)+f64::asin(r23))/(4.0*PI);
+        assert!((trivariate_cumulative_normal(0.0, 0.0, 0.0, r12, r13, r23)-expected).abs()<1e-6);
+        let product = cumulative_normal_function(0.5)*cumulative_normal_function(-0.2)*cumulative_normal_function(1.1);
+        assert!((trivariate_cumulative_normal(0.5, -0.2, 1.1, 0.0, 0.0, 0.0)-product).abs()<1e-6);
+        let bivariate = bivariate_cumulative_normal(0.5, -0.2, 0.4);
+        assert!((trivariate_cumulative_normal(0.5, -0.2, 20.0, 0.4, 0.1, 0.2)-bivariate).abs()<1e-6);
+    }
+
+    #[test]
+    fn complementary_error_function_test(){
+        let values = [(0.1, 0.8875370839817152), (0.3, 0.6713732405408726), (1.0, 0.15729920705028513), (3.0, 2.209049699858544e-05),
+            (6.0, 2.1519736712498913e-17), (-0.3, 1.3286267594591274), (-2.0, 1.9953222650189528)];
+        for (x, expected) in values{
+            assert!((complementary_error_function(x)-expected).abs()<=1e-15*expected);
+        }
+    }
+
+    #[test]
+    fn cumulative_normal_precision_test(){
+        assert!((cumulative_normal_function(-10.0_f64)-7.619853024160527e-24).abs()<1e-37);
+        assert!((cumulative_normal_function(1.5_f64)-0.9331927987311419).abs()<1e-15);
+        assert!((cumulative_normal_function_with_precision(1.5, CumulativeNormalPrecision::Fast)-0.9331927987311419).abs()<1e-7);
+    }
+
+    #[test]
+    fn wichura_inverse_cumulative_normal_test(){
+        // The round trip loses accuracy in the far tail because the cumulative normal function is flat there.
+        for x in [1e-300, 1e-20, 1e-5, 0.02, 0.3, 0.5, 0.6]{
+            let y = wichura_inverse_cumulative_normal_function(x);
+            assert!((cumulative_normal_function(y)-x).abs()<=1e-12*x);
+        }
+        assert!((wichura_inverse_cumulative_normal_function(0.97)+wichura_inverse_cumulative_normal_function(0.03)).abs()<1e-14);
+        assert_eq!(wichura_inverse_cumulative_normal_function(0.0), f64::NEG_INFINITY);
+        let moro = inverse_cumulative_normal_function_with_algorithm(0.9, InverseCumulativeNormalAlgorithm::Moro);
+        assert!((moro-wichura_inverse_cumulative_normal_function(0.9)).abs()<1e-8);
+    }
+
+    #[test]
+    fn correlation_matrix_test(){
+        let matrix = CorrelationMatrix::new(vec![vec![1.0, 0.5, 0.2], vec![0.5, 1.0, -0.3], vec![0.2, -0.3, 1.0]]);
+        let l = matrix.get_cholesky_factor();
+        for i in 0..3{
+            for j in 0..3{
+                let product: f64 = (0..3).map(|k| l[i][k]*l[j][k]).sum();
+                assert!((product-matrix.get_correlation(i, j)).abs()<1e-14);
+            }
+        }
+        assert_eq!(matrix.correlate(&[1.0, 0.0, 0.0]), vec![1.0, 0.5, 0.2]);
+        let singular = CorrelationMatrix::new(vec![vec![1.0, 1.0], vec![1.0, 1.0]]);
+        assert_eq!(singular.correlate(&[0.7, 0.3]), vec![0.7, 0.7]);
+        assert_eq!(CorrelationMatrix::try_new(vec![vec![1.0, 0.9, 0.9], vec![0.9, 1.0, -0.9], vec![0.9, -0.9, 1.0]]),
+            Err(PricingError::InvalidMarketData));
+        assert_eq!(CorrelationMatrix::try_new(vec![vec![1.0, 0.5], vec![0.4, 1.0]]), Err(PricingError::InvalidMarketData));
+        // A singular leading block with a correlation the previous columns cannot give, with determinant -1.
+        assert_eq!(CorrelationMatrix::try_new(vec![vec![1.0, 1.0, 0.0], vec![1.0, 1.0, 1.0], vec![0.0, 1.0, 1.0]]),
+            Err(PricingError::InvalidMarketData));
+        let singular = CorrelationMatrix::new(vec![vec![1.0, 1.0, 0.5], vec![1.0, 1.0, 0.5], vec![0.5, 0.5, 1.0]]);
+        assert_eq!(singular.correlate(&[0.7, 0.3, 0.0]), vec![0.7, 0.7, 0.35]);
+    }
+}
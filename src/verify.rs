@@ -0,0 +1,126 @@
+//! Provides arbitrage and sanity checks for chains of european call and put prices with a common expiry, whether they are
+//! market quotes or the output of a pricer.
+//!
+//! The checks are put-call parity, monotonicity and convexity of the prices in the strike, and the model-free lower
+//! (intrinsic) and upper bounds. Every failed check is reported as a `Violation`, so that all the problems of a chain can be
+//! inspected at once.
+
+use crate::error::PricingError;
+use crate::payoff::VanillaType;
+
+/// The kinds of checks a chain of option prices can fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViolationKind{
+    /// The call and put prices at a strike violate put-call parity.
+    PutCallParity,
+    /// A call price increases with the strike, or a put price decreases with it.
+    Monotonicity,
+    /// The call or put prices are not convex in the strike.
+    Convexity,
+    /// A price is below its discounted intrinsic value `max(F-K, 0)` (or `max(K-F, 0)`) times the discount factor.
+    LowerBound,
+    /// A call price is above the dividend discounted spot, or a put price is above the discounted strike.
+    UpperBound,
+}
+
+/// A failed check of a chain of option prices.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Violation{
+    /// The check that failed.
+    pub kind: ViolationKind,
+    /// The type of the option whose price failed the check, or `None` for a check involving both the call and the put (put-call
+    /// parity).
+    pub option_type: Option<VanillaType>,
+    /// The strike at which the check failed (the middle strike for convexity, and the larger strike for monotonicity).
+    pub strike: f64,
+    /// The amount by which the price violates the check.
+    pub amount: f64,
+}
+
+/// Returns the put-call parity residual `C-P-(S*exp(-q*T)-K*exp(-r*T))`, which is zero for arbitrage free prices.
+pub fn put_call_parity_residual(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, divident_rate: f64,
+    call_price: f64, put_price: f64)->f64{
+    call_price-put_price-(spot*(-divident_rate*time_to_expiry).exp()-strike*(-short_rate_of_interest*time_to_expiry).exp())
+}
+
+/// Checks a chain of call and put prices with a common expiry, and returns the checks it fails by more than `tolerance`.
+///
+/// # Parameters
+///
+/// - `spot` - the current spot of the stock.
+/// - `short_rate_of_interest` - the short rate of interest.
+/// - `time_to_expiry` - the time to expiry of the options.
+/// - `divident_rate` - the continuous divident rate of the stock.
+/// - `strikes` - the strikes of the chain, in increasing order.
+/// - `call_prices`, `put_prices` - the prices of the calls and puts with strikes `strikes`.
+/// - `tolerance` - the amount by which a check may fail before it is reported, e.g. to allow for bid-ask spreads.
+///
+/// # Errors
+///
+/// Returns `PricingError::NegativeInput` if the spot, the time to expiry or a strike is negative, and
+/// `PricingError::InvalidMarketData` if the strikes are not increasing or the price vectors have different lengths.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_chain(spot: f64, short_rate_of_interest: f64, time_to_expiry: f64, divident_rate: f64, strikes: &[f64],
+    call_prices: &[f64], put_prices: &[f64], tolerance: f64)->Result<Vec<Violation>, PricingError>{
+    if spot < 0.0 || time_to_expiry < 0.0 || strikes.iter().any(|k| *k < 0.0 || k.is_nan()) {
+        return Err(PricingError::NegativeInput);
+    }
+    if strikes.len() != call_prices.len() || strikes.len() != put_prices.len() || strikes.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(PricingError::InvalidMarketData);
+    }
+    let discount_factor = (-short_rate_of_interest*time_to_expiry).exp();
+    let discounted_spot = spot*(-divident_rate*time_to_expiry).exp();
+    let mut violations = Vec::new();
+    let mut report = |kind, option_type, strike, amount: f64| {
+        if amount > tolerance {
+            violations.push(Violation{kind, option_type, strike, amount});
+        }
+    };
+    for i in 0..strikes.len(){
+        let (strike, call, put) = (strikes[i], call_prices[i], put_prices[i]);
+        let residual = put_call_parity_residual(spot, strike, short_rate_of_interest, time_to_expiry, divident_rate, call, put);
+        report(ViolationKind::PutCallParity, None, strike, residual.abs());
+        report(ViolationKind::LowerBound, Some(VanillaType::Call), strike, f64::max(discounted_spot-strike*discount_factor, 0.0)-call);
+        report(ViolationKind::LowerBound, Some(VanillaType::Put), strike, f64::max(strike*discount_factor-discounted_spot, 0.0)-put);
+        report(ViolationKind::UpperBound, Some(VanillaType::Call), strike, call-discounted_spot);
+        report(ViolationKind::UpperBound, Some(VanillaType::Put), strike, put-strike*discount_factor);
+        if i > 0 {
+            report(ViolationKind::Monotonicity, Some(VanillaType::Call), strike, call-call_prices[i-1]);
+            report(ViolationKind::Monotonicity, Some(VanillaType::Put), strike, put_prices[i-1]-put);
+        }
+        if i > 0 && i+1 < strikes.len() {
+            let weight = (strikes[i+1]-strike)/(strikes[i+1]-strikes[i-1]);
+            report(ViolationKind::Convexity, Some(VanillaType::Call), strike, call-(weight*call_prices[i-1]+(1.0-weight)*call_prices[i+1]));
+            report(ViolationKind::Convexity, Some(VanillaType::Put), strike, put-(weight*put_prices[i-1]+(1.0-weight)*put_prices[i+1]));
+        }
+    }
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw_formulas::{european_call_option_price, european_put_option_price};
+
+    #[test]
+    fn verify_chain_test(){
+        let strikes = [80.0, 90.0, 100.0, 110.0, 120.0];
+        let mut calls: Vec<f64> = strikes.iter().map(|k| european_call_option_price(100.0, *k, 0.05, 1.0, 0.2, 0.01)).collect();
+        let puts: Vec<f64> = strikes.iter().map(|k| european_put_option_price(100.0, *k, 0.05, 1.0, 0.2, 0.01)).collect();
+        assert_eq!(verify_chain(100.0, 0.05, 1.0, 0.01, &strikes, &calls, &puts, 1e-10), Ok(vec![]));
+        // Raising the call at 100 breaks parity there and the convexity of the calls.
+        calls[2] += 3.0;
+        let violations = verify_chain(100.0, 0.05, 1.0, 0.01, &strikes, &calls, &puts, 1e-10).unwrap();
+        assert!(violations.iter().all(|v| v.strike == 100.0));
+        assert!(violations.iter().any(|v| v.kind == ViolationKind::PutCallParity && (v.amount-3.0).abs() < 1e-10));
+        assert!(violations.iter().any(|v| v.kind == ViolationKind::Convexity && v.option_type == Some(VanillaType::Call)));
+        assert_eq!(verify_chain(100.0, 0.05, 1.0, 0.01, &[90.0, 80.0], &calls[..2], &puts[..2], 0.0), Err(PricingError::InvalidMarketData));
+    }
+
+    #[test]
+    fn bounds_test(){
+        let violations = verify_chain(100.0, 0.0, 1.0, 0.0, &[50.0], &[40.0], &[-10.0+40.0-50.0], 1e-12).unwrap();
+        assert!(violations.iter().any(|v| v.kind == ViolationKind::LowerBound && v.option_type == Some(VanillaType::Call)));
+        assert!(violations.iter().any(|v| v.kind == ViolationKind::LowerBound && v.option_type == Some(VanillaType::Put)));
+    }
+}
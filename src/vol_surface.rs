@@ -0,0 +1,599 @@
+//! Provides implied volatility surfaces: a smile of implied volatilities against strike at every quoted expiry, together with
+//! the forward of the underlying at that expiry.
+//!
+//! Between the strikes of a smile the implied volatility is interpolated linearly, and it is flat beyond the first and the last
+//! strike. Between the expiries the total implied variance `sigma^2*T` is interpolated linearly in time at a fixed forward
+//! moneyness `K/F(T)`, and the volatility is flat in forward moneyness before the first and after the last expiry. The forward
+//! is interpolated log-linearly in time, starting from the spot, so the implied divident rate is piecewise constant.
+//!
+//! `VolSurface::check_arbitrage` reports the calendar spread arbitrages, where the total variance decreases with the expiry at a
+//! fixed forward moneyness, and the butterfly arbitrages, where the density implied by the call prices is negative.
+//! `VolSurface::with_arbitrage_repaired` removes small violations.
+//!
+//! FX smiles are quoted as an at-the-money volatility, and risk reversals and butterflies at standard deltas. `fx_smile` converts
+//! such `FxSmileQuotes` into a smile of strikes and volatilities, and `VolSurface::from_fx_quotes` builds a surface from them.
+//!
+//! # Examples
+//!
+//! ```
+//! use derivative_pricer::vol_surface::{Smile, VolSurface};
+//! let smiles = vec![Smile::new(vec![90.0, 110.0], vec![0.25, 0.2]), Smile::new(vec![80.0, 120.0], vec![0.25, 0.2])];
+//! let surface = VolSurface::new(100.0, 0.05, vec![0.5, 1.0], vec![102.0, 104.0], smiles);
+//! assert!((surface.volatility(102.0, 0.5)-0.22).abs() < 1e-12);
+//! assert!((surface.forward(0.75)-(102.0_f64*104.0).sqrt()).abs() < 1e-9);
+//! ```
+
+use crate::error::PricingError;
+use crate::greeks::DeltaConvention;
+use crate::raw_formulas;
+
+/// The implied volatilities of the options of one expiry against their strikes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Smile{
+    /// The strikes, positive and strictly increasing.
+    strikes: Vec<f64>,
+    /// The implied volatilities at the strikes.
+    volatilities: Vec<f64>,
+}
+
+impl Smile {
+    /// Returns the smile with implied volatilities `volatilities` at `strikes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the smile is invalid, see `try_new`.
+    pub fn new(strikes: Vec<f64>, volatilities: Vec<f64>)->Smile{
+        Smile::try_new(strikes, volatilities).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `new`, but returns `PricingError::NegativeInput` if a volatility is negative, and `PricingError::InvalidMarketData`
+    /// if the smile is empty, if there is not one volatility for every strike, or if the strikes are not positive and strictly
+    /// increasing.
+    pub fn try_new(strikes: Vec<f64>, volatilities: Vec<f64>)->Result<Smile, PricingError>{
+        if strikes.is_empty() || strikes.len() != volatilities.len() || strikes.iter().any(|k| k.is_nan() || *k <= 0.0)
+            || strikes.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(PricingError::InvalidMarketData);
+        }
+        if volatilities.iter().any(|v| v.is_nan() || *v < 0.0) {
+            return Err(PricingError::NegativeInput);
+        }
+        Ok(Smile{strikes, volatilities})
+    }
+
+    /// Returns the strikes of the smile.
+    pub fn get_strikes(&self)->&[f64]{
+        &self.strikes
+    }
+
+    /// Returns the implied volatilities at the strikes.
+    pub fn get_volatilities(&self)->&[f64]{
+        &self.volatilities
+    }
+
+    /// Returns the implied volatility at `strike`, interpolated linearly between the strikes and flat beyond them.
+    pub fn volatility(&self, strike: f64)->f64{
+        let last = self.strikes.len()-1;
+        if strike <= self.strikes[0] {
+            return self.volatilities[0];
+        }
+        if strike >= self.strikes[last] {
+            return self.volatilities[last];
+        }
+        let i = self.strikes.partition_point(|k| *k <= strike);
+        let weight = (strike-self.strikes[i-1])/(self.strikes[i]-self.strikes[i-1]);
+        (1.0-weight)*self.volatilities[i-1]+weight*self.volatilities[i]
+    }
+}
+
+/// The strike of the at-the-money volatility of an FX smile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AtmConvention{
+    /// The strike of the delta neutral straddle, whose call and put deltas sum to zero. This is the FX market standard.
+    #[default]
+    DeltaNeutral,
+    /// The forward.
+    Forward,
+}
+
+/// The volatility quotes of an FX smile at one expiry: the at-the-money volatility, and the risk reversals and (smile)
+/// butterflies at some deltas, e.g. 25 and 10 delta. The volatilities of the call and of the put with delta `delta` are then
+/// `atm+butterfly+risk_reversal/2` and `atm+butterfly-risk_reversal/2`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FxSmileQuotes{
+    /// The at-the-money volatility.
+    atm_volatility: f64,
+    /// The deltas of the quotes, between 0 and 0.5.
+    deltas: Vec<f64>,
+    /// The risk reversals, i.e. the volatility of the call less the one of the put, at the deltas.
+    risk_reversals: Vec<f64>,
+    /// The butterflies, i.e. the average volatility of the call and the put less the at-the-money volatility, at the deltas.
+    butterflies: Vec<f64>,
+    /// The convention of the deltas.
+    delta_convention: DeltaConvention,
+    /// The strike of the at-the-money volatility.
+    atm_convention: AtmConvention,
+}
+
+impl FxSmileQuotes {
+    /// Returns the quotes with at-the-money volatility `atm_volatility`, and risk reversals and butterflies at `deltas`, with spot
+    /// deltas and a delta neutral at-the-money strike.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the quotes are invalid, see `try_new`.
+    pub fn new(atm_volatility: f64, deltas: Vec<f64>, risk_reversals: Vec<f64>, butterflies: Vec<f64>)->FxSmileQuotes{
+        FxSmileQuotes::try_new(atm_volatility, deltas, risk_reversals, butterflies).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `new`, but returns `PricingError::NegativeInput` if the at-the-money volatility is negative, and
+    /// `PricingError::InvalidMarketData` if there is not one risk reversal and one butterfly for every delta, or if a delta is
+    /// not strictly between 0 and 0.5.
+    pub fn try_new(atm_volatility: f64, deltas: Vec<f64>, risk_reversals: Vec<f64>, butterflies: Vec<f64>)->Result<FxSmileQuotes, PricingError>{
+        if atm_volatility.is_nan() || atm_volatility < 0.0 {
+            return Err(PricingError::NegativeInput);
+        }
+        if deltas.len() != risk_reversals.len() || deltas.len() != butterflies.len() || deltas.iter().any(|d| !(*d > 0.0 && *d < 0.5)) {
+            return Err(PricingError::InvalidMarketData);
+        }
+        Ok(FxSmileQuotes{atm_volatility, deltas, risk_reversals, butterflies, delta_convention: DeltaConvention::default(),
+            atm_convention: AtmConvention::default()})
+    }
+
+    /// Returns the quotes with deltas in the convention `delta_convention`, e.g. premium adjusted for currency pairs whose
+    /// premium is paid in the foreign currency.
+    pub fn with_delta_convention(self, delta_convention: DeltaConvention)->FxSmileQuotes{
+        FxSmileQuotes{delta_convention, ..self}
+    }
+
+    /// Returns the quotes with the at-the-money strike `atm_convention`.
+    pub fn with_atm_convention(self, atm_convention: AtmConvention)->FxSmileQuotes{
+        FxSmileQuotes{atm_convention, ..self}
+    }
+
+    /// Returns the at-the-money volatility.
+    pub fn get_atm_volatility(&self)->f64{
+        self.atm_volatility
+    }
+
+    /// Returns the deltas of the quotes.
+    pub fn get_deltas(&self)->&[f64]{
+        &self.deltas
+    }
+
+    /// Returns the risk reversals at the deltas.
+    pub fn get_risk_reversals(&self)->&[f64]{
+        &self.risk_reversals
+    }
+
+    /// Returns the butterflies at the deltas.
+    pub fn get_butterflies(&self)->&[f64]{
+        &self.butterflies
+    }
+
+    /// Returns the convention of the deltas.
+    pub fn get_delta_convention(&self)->DeltaConvention{
+        self.delta_convention
+    }
+
+    /// Returns the strike of the at-the-money volatility.
+    pub fn get_atm_convention(&self)->AtmConvention{
+        self.atm_convention
+    }
+}
+
+/// Returns the smile at `time_to_expiry` given by the FX quotes `quotes`, where the domestic and foreign rates are the short
+/// rate of interest and the divident rate of the spot. The strikes of the quoted calls and puts are solved from their deltas
+/// at their volatilities.
+///
+/// # Panics
+///
+/// Panics if the quotes do not give a valid smile, see `try_fx_smile`.
+pub fn fx_smile(spot: f64, domestic_rate: f64, foreign_rate: f64, time_to_expiry: f64, quotes: &FxSmileQuotes)->Smile{
+    try_fx_smile(spot, domestic_rate, foreign_rate, time_to_expiry, quotes).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Same as `fx_smile`, but returns `PricingError::NegativeInput` if the spot, the time to expiry, the foreign rate or a
+/// volatility is negative (or the time to expiry or a volatility is zero), and `PricingError::InvalidMarketData` if no option
+/// has a quoted delta, or if the strikes are not in the order of the deltas.
+pub fn try_fx_smile(spot: f64, domestic_rate: f64, foreign_rate: f64, time_to_expiry: f64, quotes: &FxSmileQuotes)->Result<Smile, PricingError>{
+    let convention = quotes.delta_convention;
+    let atm = quotes.atm_volatility;
+    let forward = raw_formulas::try_forward_price(spot, domestic_rate, time_to_expiry, foreign_rate)?;
+    let atm_strike = match (quotes.atm_convention, convention) {
+        (AtmConvention::Forward, _) | (_, DeltaConvention::Driftless) => forward,
+        (_, DeltaConvention::Spot) | (_, DeltaConvention::Forward) => forward*(0.5*atm*atm*time_to_expiry).exp(),
+        _ => forward*(-0.5*atm*atm*time_to_expiry).exp(),
+    };
+    let mut points = vec![(atm_strike, atm)];
+    for ((delta, risk_reversal), butterfly) in quotes.deltas.iter().zip(&quotes.risk_reversals).zip(&quotes.butterflies) {
+        let (call_volatility, put_volatility) = (atm+butterfly+0.5*risk_reversal, atm+butterfly-0.5*risk_reversal);
+        points.push((raw_formulas::try_call_strike_from_delta(spot, *delta, domestic_rate, time_to_expiry, call_volatility, foreign_rate,
+            convention)?, call_volatility));
+        points.push((raw_formulas::try_put_strike_from_delta(spot, -delta, domestic_rate, time_to_expiry, put_volatility, foreign_rate,
+            convention)?, put_volatility));
+    }
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Smile::try_new(points.iter().map(|p| p.0).collect(), points.iter().map(|p| p.1).collect())
+}
+
+/// A static arbitrage of a `VolSurface`, found by `check_arbitrage`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArbitrageViolation{
+    /// The total implied variance at the forward moneyness `moneyness` (i.e. `K/F`) is smaller at the expiry with index
+    /// `expiry_index` than at the previous one, by `variance_decrease`, so a calendar spread has a negative price.
+    CalendarSpread{
+        expiry_index: usize,
+        moneyness: f64,
+        variance_decrease: f64,
+    },
+    /// The call prices of the smile at the expiry with index `expiry_index` are not convex at `strike`, where the density of the
+    /// underlying they imply is `density`, so a butterfly has a negative price.
+    Butterfly{
+        expiry_index: usize,
+        strike: f64,
+        density: f64,
+    },
+}
+
+/// An implied volatility surface, made of the smiles and forwards at a strip of expiries.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VolSurface{
+    /// The spot of the underlying.
+    spot: f64,
+    /// The short rate of interest.
+    short_rate_of_interest: f64,
+    /// The times to expiry of the smiles, positive and strictly increasing.
+    expiries: Vec<f64>,
+    /// The forwards of the underlying at the expiries.
+    forwards: Vec<f64>,
+    /// The smiles at the expiries.
+    smiles: Vec<Smile>,
+}
+
+impl VolSurface {
+    /// Returns the surface with the given smiles at the times to expiry `expiries`, at which the forwards of the underlying are
+    /// `forwards`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the surface is invalid, see `try_new`.
+    pub fn new(spot: f64, short_rate_of_interest: f64, expiries: Vec<f64>, forwards: Vec<f64>, smiles: Vec<Smile>)->VolSurface{
+        VolSurface::try_new(spot, short_rate_of_interest, expiries, forwards, smiles).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `new`, but returns `PricingError::InvalidMarketData` if there are no expiries, if the expiries are not positive and
+    /// strictly increasing, if there is not one forward and one smile for every expiry, or if the spot or a forward is not positive.
+    pub fn try_new(spot: f64, short_rate_of_interest: f64, expiries: Vec<f64>, forwards: Vec<f64>, smiles: Vec<Smile>)
+        ->Result<VolSurface, PricingError>{
+        if expiries.is_empty() || expiries.len() != forwards.len() || expiries.len() != smiles.len() || spot.is_nan() || spot <= 0.0
+            || expiries.iter().any(|t| t.is_nan() || *t <= 0.0) || expiries.windows(2).any(|w| w[0] >= w[1])
+            || forwards.iter().any(|f| f.is_nan() || *f <= 0.0) {
+            return Err(PricingError::InvalidMarketData);
+        }
+        Ok(VolSurface{spot, short_rate_of_interest, expiries, forwards, smiles})
+    }
+
+    /// Returns the surface of an FX rate with the given domestic and foreign rates, whose smiles at `expiries` are given by the FX
+    /// quotes `quotes`, see `fx_smile`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the quotes do not give a valid surface, see `try_from_fx_quotes`.
+    pub fn from_fx_quotes(spot: f64, domestic_rate: f64, foreign_rate: f64, expiries: Vec<f64>, quotes: &[FxSmileQuotes])->VolSurface{
+        VolSurface::try_from_fx_quotes(spot, domestic_rate, foreign_rate, expiries, quotes).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `from_fx_quotes`, but returns the error of `try_fx_smile` if the quotes of an expiry do not give a valid smile, and
+    /// `PricingError::InvalidMarketData` if there are not quotes for every expiry or the surface is invalid, see `try_new`.
+    pub fn try_from_fx_quotes(spot: f64, domestic_rate: f64, foreign_rate: f64, expiries: Vec<f64>, quotes: &[FxSmileQuotes])
+        ->Result<VolSurface, PricingError>{
+        if expiries.len() != quotes.len() {
+            return Err(PricingError::InvalidMarketData);
+        }
+        let smiles = expiries.iter().zip(quotes).map(|(t, quotes)| try_fx_smile(spot, domestic_rate, foreign_rate, *t, quotes))
+            .collect::<Result<Vec<Smile>, PricingError>>()?;
+        let forwards = expiries.iter().map(|t| spot*((domestic_rate-foreign_rate)*t).exp()).collect();
+        VolSurface::try_new(spot, domestic_rate, expiries, forwards, smiles)
+    }
+
+    /// Returns the spot of the underlying.
+    pub fn get_spot(&self)->f64{
+        self.spot
+    }
+
+    /// Returns the short rate of interest.
+    pub fn get_short_rate_of_interest(&self)->f64{
+        self.short_rate_of_interest
+    }
+
+    /// Returns the times to expiry of the smiles.
+    pub fn get_expiries(&self)->&[f64]{
+        &self.expiries
+    }
+
+    /// Returns the forwards of the underlying at the expiries.
+    pub fn get_forwards(&self)->&[f64]{
+        &self.forwards
+    }
+
+    /// Returns the smiles at the expiries.
+    pub fn get_smiles(&self)->&[Smile]{
+        &self.smiles
+    }
+
+    /// Returns the forward of the underlying at the time to expiry `time_to_expiry`, interpolated log-linearly between the
+    /// expiries and extrapolated with the growth rate of the last interval.
+    pub fn forward(&self, time_to_expiry: f64)->f64{
+        (self.spot.ln()+self.forward_growth_rate(time_to_expiry)*time_to_expiry).exp()
+    }
+
+    /// Returns the continuous divident rate implied by the forward at `time_to_expiry`, i.e. `r-ln(F(T)/S)/T`.
+    pub fn implied_divident_rate(&self, time_to_expiry: f64)->f64{
+        self.short_rate_of_interest-self.forward_growth_rate(time_to_expiry)
+    }
+
+    /// Returns the implied volatility at `strike` and `time_to_expiry`.
+    pub fn volatility(&self, strike: f64, time_to_expiry: f64)->f64{
+        let last = self.expiries.len()-1;
+        let moneyness = strike/self.forward(time_to_expiry);
+        if time_to_expiry <= self.expiries[0] {
+            return self.smiles[0].volatility(moneyness*self.forwards[0]);
+        }
+        if time_to_expiry >= self.expiries[last] {
+            return self.smiles[last].volatility(moneyness*self.forwards[last]);
+        }
+        let i = self.expiries.partition_point(|t| *t <= time_to_expiry);
+        let variance = |j: usize| self.smiles[j].volatility(moneyness*self.forwards[j]).powi(2)*self.expiries[j];
+        let weight = (time_to_expiry-self.expiries[i-1])/(self.expiries[i]-self.expiries[i-1]);
+        (((1.0-weight)*variance(i-1)+weight*variance(i))/time_to_expiry).sqrt()
+    }
+
+    /// Returns the total implied variance `sigma^2*T` at `strike` and `time_to_expiry`.
+    pub fn total_variance(&self, strike: f64, time_to_expiry: f64)->f64{
+        self.volatility(strike, time_to_expiry).powi(2)*time_to_expiry
+    }
+
+    /// Returns the price of the european call option with `strike` and `time_to_expiry` implied by the surface. The price is
+    /// computed from the forward (Black's formula), so it is also valid when the implied divident rate is negative.
+    pub fn call_price(&self, strike: f64, time_to_expiry: f64)->f64{
+        (-self.short_rate_of_interest*time_to_expiry).exp()*raw_formulas::european_call_option_price(self.forward(time_to_expiry), strike,
+            0.0, time_to_expiry, self.volatility(strike, time_to_expiry), 0.0)
+    }
+
+    /// Returns the price of the european put option with `strike` and `time_to_expiry` implied by the surface, from the forward
+    /// as in `call_price`.
+    pub fn put_price(&self, strike: f64, time_to_expiry: f64)->f64{
+        (-self.short_rate_of_interest*time_to_expiry).exp()*raw_formulas::european_put_option_price(self.forward(time_to_expiry), strike,
+            0.0, time_to_expiry, self.volatility(strike, time_to_expiry), 0.0)
+    }
+
+    /// Returns the static arbitrages of the surface larger than `tolerance`. Calendar spreads are checked at the forward
+    /// moneyness of the strikes of every pair of consecutive smiles, and butterflies at the strikes of every smile and midway
+    /// between them, with the density implied by the undiscounted call prices of the smile, i.e. their second derivative in the
+    /// strike, estimated by finite differences.
+    pub fn check_arbitrage(&self, tolerance: f64)->Vec<ArbitrageViolation>{
+        let mut violations = Vec::new();
+        for (i, smile) in self.smiles.iter().enumerate() {
+            let mut strikes = smile.strikes.clone();
+            strikes.extend(smile.strikes.windows(2).map(|w| 0.5*(w[0]+w[1])));
+            strikes.sort_by(|a, b| a.total_cmp(b));
+            let prices: Vec<f64> = strikes.iter().map(|k| self.smile_call_price(i, *k, smile.volatility(*k))).collect();
+            for (k, c) in strikes.windows(3).zip(prices.windows(3)) {
+                let density = 2.0*((c[2]-c[1])/(k[2]-k[1])-(c[1]-c[0])/(k[1]-k[0]))/(k[2]-k[0]);
+                if density < -tolerance {
+                    violations.push(ArbitrageViolation::Butterfly{expiry_index: i, strike: k[1], density});
+                }
+            }
+        }
+        for i in 1..self.expiries.len() {
+            let mut moneyness: Vec<f64> = self.smiles[i-1].strikes.iter().map(|k| k/self.forwards[i-1])
+                .chain(self.smiles[i].strikes.iter().map(|k| k/self.forwards[i])).collect();
+            moneyness.sort_by(|a, b| a.total_cmp(b));
+            moneyness.dedup();
+            for m in moneyness {
+                let variance_decrease = self.smile_total_variance(i-1, m)-self.smile_total_variance(i, m);
+                if variance_decrease > tolerance {
+                    violations.push(ArbitrageViolation::CalendarSpread{expiry_index: i, moneyness: m, variance_decrease});
+                }
+            }
+        }
+        violations
+    }
+
+    /// Returns the surface with small static arbitrages removed at the strikes of the smiles. The call prices of every smile are
+    /// replaced by their greatest convex minorant in the strike, and the total variance at every strike is then raised to the
+    /// one of the previous (repaired) smile at the same forward moneyness. Large violations usually point to bad quotes, and
+    /// should be fixed in the data; the repaired surface can be checked again with `check_arbitrage`.
+    pub fn with_arbitrage_repaired(&self)->VolSurface{
+        let mut smiles: Vec<Smile> = Vec::with_capacity(self.smiles.len());
+        for (i, smile) in self.smiles.iter().enumerate() {
+            let (forward, time_to_expiry) = (self.forwards[i], self.expiries[i]);
+            let prices: Vec<f64> = smile.strikes.iter().zip(&smile.volatilities).map(|(k, v)| self.smile_call_price(i, *k, *v)).collect();
+            let hull = convex_minorant(&smile.strikes, &prices);
+            let mut volatilities: Vec<f64> = smile.strikes.iter().enumerate().map(|(j, k)| if hull[j] < prices[j] {
+                    raw_formulas::try_call_implied_volatility(forward, *k, 0.0, time_to_expiry, 0.0, hull[j]).unwrap_or(smile.volatilities[j])
+                }
+                else {
+                    smile.volatilities[j]
+                }).collect();
+            if let Some(previous) = smiles.last() {
+                for (v, k) in volatilities.iter_mut().zip(&smile.strikes) {
+                    let previous_variance = previous.volatility(k/forward*self.forwards[i-1]).powi(2)*self.expiries[i-1];
+                    *v = v.max((previous_variance/time_to_expiry).sqrt());
+                }
+            }
+            smiles.push(Smile{strikes: smile.strikes.clone(), volatilities});
+        }
+        VolSurface{smiles, ..self.clone()}
+    }
+
+    /// Returns the undiscounted price of the call with `strike` at the expiry with index `i` and implied volatility `volatility`.
+    fn smile_call_price(&self, i: usize, strike: f64, volatility: f64)->f64{
+        raw_formulas::european_call_option_price(self.forwards[i], strike, 0.0, self.expiries[i], volatility, 0.0)
+    }
+
+    /// Returns the total implied variance of the smile at the expiry with index `i` at the forward moneyness `moneyness`.
+    fn smile_total_variance(&self, i: usize, moneyness: f64)->f64{
+        self.smiles[i].volatility(moneyness*self.forwards[i]).powi(2)*self.expiries[i]
+    }
+
+    /// Returns the average growth rate `ln(F(T)/S)/T` of the forward up to `time_to_expiry`.
+    fn forward_growth_rate(&self, time_to_expiry: f64)->f64{
+        forward_growth_rate(self.spot, &self.expiries, &self.forwards, time_to_expiry)
+    }
+
+    /// Returns the instantaneous growth rate `d ln(F(T))/dT` of the forward at `time_to_expiry`, constant between the expiries
+    /// and continuous from the right.
+    pub(crate) fn instantaneous_forward_growth_rate(&self, time_to_expiry: f64)->f64{
+        let log_forward = |j: usize| (self.forwards[j]/self.spot).ln();
+        match forward_interval(&self.expiries, time_to_expiry) {
+            None => log_forward(0)/self.expiries[0],
+            Some(i) => (log_forward(i)-log_forward(i-1))/(self.expiries[i]-self.expiries[i-1]),
+        }
+    }
+}
+
+/// Returns the index `i` of the interval `[times[i-1], times[i])` of the log-linear interpolation of the forwards containing
+/// `time`, the last interval beyond the last time, or `None` before the first time (where the forward grows at the rate
+/// of the first time) or if there is only one time.
+fn forward_interval(times: &[f64], time: f64)->Option<usize>{
+    if time < times[0] || times.len() == 1 {
+        return None;
+    }
+    Some(times.partition_point(|t| *t <= time).clamp(1, times.len()-1))
+}
+
+/// Returns the average growth rate `ln(F(T)/S)/T` up to `time` of the forwards `forwards` at `times`, interpolated log-linearly.
+fn forward_growth_rate(spot: f64, times: &[f64], forwards: &[f64], time: f64)->f64{
+    let log_forward = |j: usize| (forwards[j]/spot).ln();
+    match forward_interval(times, time) {
+        None => log_forward(0)/times[0],
+        Some(i) => {
+            let rate = (log_forward(i)-log_forward(i-1))/(times[i]-times[i-1]);
+            (log_forward(i-1)+rate*(time-times[i-1]))/time
+        },
+    }
+}
+
+/// Returns the greatest convex function below the points `(x, y)` at the sorted `x`, i.e. the lower convex hull of the points.
+fn convex_minorant(x: &[f64], y: &[f64])->Vec<f64>{
+    let mut hull: Vec<usize> = Vec::with_capacity(x.len());
+    for i in 0..x.len() {
+        while hull.len() >= 2 {
+            let (a, b) = (hull[hull.len()-2], hull[hull.len()-1]);
+            // Drop `b` if it lies on or above the chord from `a` to `i`.
+            if (y[b]-y[a])*(x[i]-x[a]) >= (y[i]-y[a])*(x[b]-x[a]) {
+                hull.pop();
+            }
+            else {
+                break;
+            }
+        }
+        hull.push(i);
+    }
+    let mut values = Vec::with_capacity(x.len());
+    for w in hull.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        values.extend((a..b).map(|j| y[a]+(y[b]-y[a])*(x[j]-x[a])/(x[b]-x[a])));
+    }
+    values.push(y[x.len()-1]);
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smile_test(){
+        let smile = Smile::new(vec![90.0, 100.0, 120.0], vec![0.3, 0.2, 0.25]);
+        assert_eq!(smile.volatility(50.0), 0.3);
+        assert!((smile.volatility(95.0)-0.25).abs() < 1e-12);
+        assert!((smile.volatility(110.0)-0.225).abs() < 1e-12);
+        assert_eq!(smile.volatility(200.0), 0.25);
+        assert_eq!(Smile::try_new(vec![100.0, 90.0], vec![0.2, 0.2]), Err(PricingError::InvalidMarketData));
+        assert_eq!(Smile::try_new(vec![100.0], vec![-0.2]), Err(PricingError::NegativeInput));
+    }
+
+    #[test]
+    fn surface_test(){
+        let (spot, r, q): (f64, f64, f64) = (100.0, 0.05, 0.02);
+        let expiries = vec![0.5, 1.0, 2.0];
+        let forwards: Vec<f64> = expiries.iter().map(|t| spot*((r-q)*t).exp()).collect();
+        let smiles = vec![Smile::new(vec![100.0], vec![0.3]), Smile::new(vec![100.0], vec![0.2]), Smile::new(vec![100.0], vec![0.25])];
+        let surface = VolSurface::new(spot, r, expiries, forwards, smiles);
+        for t in [0.1, 0.7, 1.5, 3.0] {
+            assert!((surface.forward(t)-spot*((r-q)*t).exp()).abs() < 1e-10);
+            assert!((surface.implied_divident_rate(t)-q).abs() < 1e-12);
+        }
+        assert_eq!(surface.volatility(80.0, 0.25), 0.3);
+        assert!((surface.total_variance(100.0, 1.5)-0.5*(0.04+0.125)).abs() < 1e-12);
+        assert!((surface.volatility(120.0, 3.0)-0.25).abs() < 1e-12);
+        let call = surface.call_price(110.0, 1.0);
+        assert!((call-raw_formulas::european_call_option_price(spot, 110.0, r, 1.0, 0.2, q)).abs() < 1e-10);
+        let put = surface.put_price(110.0, 1.0);
+        assert!((call-put-(spot*(-q).exp()-110.0*(-r).exp())).abs() < 1e-10);
+        assert_eq!(VolSurface::try_new(spot, r, vec![1.0], vec![100.0], vec![]), Err(PricingError::InvalidMarketData));
+    }
+
+    #[test]
+    fn arbitrage_test(){
+        let strikes = vec![80.0, 90.0, 100.0, 110.0, 120.0];
+        // The total variance at the money decreases from 0.045 to 0.04, and the spike at the money is a butterfly arbitrage.
+        let smiles = vec![Smile::new(strikes.clone(), vec![0.3; 5]), Smile::new(strikes.clone(), vec![0.2, 0.2, 0.35, 0.2, 0.2])];
+        let surface = VolSurface::new(100.0, 0.0, vec![0.5, 1.0], vec![100.0, 100.0], smiles);
+        let violations = surface.check_arbitrage(1e-10);
+        assert!(violations.iter().any(|v| matches!(v, ArbitrageViolation::CalendarSpread{expiry_index: 1, moneyness, variance_decrease}
+            if *moneyness == 0.8 && (variance_decrease-0.005).abs() < 1e-12)));
+        assert!(violations.iter().any(|v| matches!(v, ArbitrageViolation::Butterfly{expiry_index: 1, strike, density} if *strike == 100.0 && *density < 0.0)));
+        assert!(!violations.iter().any(|v| matches!(v, ArbitrageViolation::Butterfly{expiry_index: 0, ..})));
+        let repaired = surface.with_arbitrage_repaired();
+        assert_eq!(repaired.get_smiles()[0], surface.get_smiles()[0]);
+        assert!(repaired.get_smiles()[1].get_volatilities().iter().all(|v| (v-0.3_f64/2.0_f64.sqrt()).abs() < 1e-12 || *v > 0.3/2.0_f64.sqrt()));
+        assert!(repaired.check_arbitrage(1e-10).iter().all(|v| !matches!(v, ArbitrageViolation::CalendarSpread{..})));
+        assert_eq!(convex_minorant(&[0.0, 1.0, 2.0, 3.0], &[1.0, 2.0, 0.0, 1.0]), vec![1.0, 0.5, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn degenerate_arbitrage_test(){
+        // A single smile with a single strike has neither calendar spreads nor butterflies to check.
+        let single = VolSurface::new(100.0, 0.0, vec![1.0], vec![100.0], vec![Smile::new(vec![100.0], vec![0.2])]);
+        assert_eq!(single.check_arbitrage(0.0), vec![]);
+        assert_eq!(single.with_arbitrage_repaired(), single);
+        // Violations within the tolerance are not reported, and an arbitrage free surface is not changed by the repair.
+        let strikes = vec![90.0, 100.0, 110.0];
+        let calendar = VolSurface::new(100.0, 0.0, vec![0.5, 1.0], vec![100.0, 100.0],
+            vec![Smile::new(strikes.clone(), vec![0.3; 3]), Smile::new(strikes.clone(), vec![0.2; 3])]);
+        assert_eq!(calendar.check_arbitrage(0.01), vec![]);
+        assert_eq!(calendar.check_arbitrage(0.001).len(), 3);
+        let flat = VolSurface::new(100.0, 0.0, vec![0.5, 1.0], vec![100.0, 100.0],
+            vec![Smile::new(strikes.clone(), vec![0.2; 3]), Smile::new(strikes, vec![0.2; 3])]);
+        assert_eq!(flat.check_arbitrage(0.0), vec![]);
+        assert_eq!(flat.with_arbitrage_repaired(), flat);
+    }
+
+    #[test]
+    fn fx_smile_test(){
+        let (spot, domestic, foreign, t) = (1.3, 0.03, 0.01, 0.5);
+        let quotes = FxSmileQuotes::new(0.1, vec![0.25, 0.1], vec![-0.01, -0.02], vec![0.003, 0.01]);
+        let smile = fx_smile(spot, domestic, foreign, t, &quotes);
+        assert_eq!(smile.get_strikes().len(), 5);
+        assert_eq!(smile.get_volatilities(), [0.1+0.01+0.01, 0.1+0.003+0.005, 0.1, 0.1+0.003-0.005, 0.1+0.01-0.01]);
+        // The quoted options have the quoted deltas at their volatilities, and the straddle at the money is delta neutral.
+        let strikes = smile.get_strikes();
+        assert!((raw_formulas::call_delta(spot, strikes[3], domestic, t, 0.098, foreign)-0.25).abs() < 1e-8);
+        assert!((raw_formulas::put_delta(spot, strikes[0], domestic, t, 0.12, foreign)+0.1).abs() < 1e-8);
+        let straddle_delta = raw_formulas::call_delta(spot, strikes[2], domestic, t, 0.1, foreign)
+            +raw_formulas::put_delta(spot, strikes[2], domestic, t, 0.1, foreign);
+        assert!(straddle_delta.abs() < 1e-12);
+        let adjusted = fx_smile(spot, domestic, foreign, t, &quotes.clone().with_delta_convention(DeltaConvention::ForwardPremiumAdjusted)
+            .with_atm_convention(AtmConvention::Forward));
+        assert!((adjusted.get_strikes()[2]-spot*((domestic-foreign)*t).exp()).abs() < 1e-12);
+        let surface = VolSurface::from_fx_quotes(spot, domestic, foreign, vec![t, 1.0], &[quotes.clone(), quotes.clone()]);
+        assert!((surface.volatility(strikes[3], t)-0.098).abs() < 1e-12);
+        assert_eq!(FxSmileQuotes::try_new(0.1, vec![0.6], vec![0.0], vec![0.0]), Err(PricingError::InvalidMarketData));
+        // A risk reversal larger than twice the wing volatility gives a negative put volatility.
+        let skewed = FxSmileQuotes::new(0.02, vec![0.25], vec![0.1], vec![0.0]);
+        assert_eq!(try_fx_smile(spot, domestic, foreign, t, &skewed), Err(PricingError::NegativeInput));
+    }
+}
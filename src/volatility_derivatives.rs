@@ -0,0 +1,304 @@
+//! Provides swaps on the realized variance and volatility of a stock, which can be priced with the Monte Carlo pricers.
+//! The analytic fair strikes are in `raw_formulas`, see `variance_swap_strike` and `volatility_swap_strike`.
+
+use std::sync::Arc;
+
+use crate::greeks::HasGreeks;
+use crate::option::{fixings_at, fixings_on_path, future_times, rebased_history, DerivativeOption, PathPayoff};
+use crate::path_generator::Path;
+use crate::stock::{GeometricBrownianMotionStock, StockState};
+use crate::utils::{Duration, TimeStamp};
+
+/// Returns the annualized realized variance of `fixings` observed at `observation_times`, i.e. the sum of the squared log returns
+/// divided by the length of the observation period.
+///
+/// # Panics
+///
+/// Panics if the last observation time is before the first one.
+pub fn realized_variance(fixings: &[f64], observation_times: &[TimeStamp])->f64{
+    let period = f64::from(observation_times[observation_times.len()-1].checked_duration_since(observation_times[0])
+        .expect("The observation times are not sorted"));
+    let sum_of_squares: f64 = fixings.windows(2).map(|w| {
+        let log_return = (w[1]/w[0]).ln();
+        log_return*log_return
+    }).sum();
+    sum_of_squares/period
+}
+
+/// The observations of the underlying shared by the variance and volatility swaps: the observation times and the recorded
+/// states of the underlying at the past ones.
+struct RealizedVarianceLeg{
+    ///A shared reference to the underlying stock.
+    underlying_stock: Arc<GeometricBrownianMotionStock>,
+    /// The observation times, sorted with unique values. The last observation time is the expiry.
+    observation_times: Vec<TimeStamp>,
+    /// A vector of states of the underlying stock.
+    history: Vec<StockState>,
+}
+
+impl RealizedVarianceLeg {
+    /// Returns a new leg, with the current state of the stock as its history.
+    fn new(underlying_stock: &Arc<GeometricBrownianMotionStock>, observation_times: &[TimeStamp])->RealizedVarianceLeg{
+        RealizedVarianceLeg{
+            underlying_stock: Arc::clone(underlying_stock),
+            observation_times: observation_times.to_vec(),
+            history: vec![underlying_stock.get_current_state()],
+        }
+    }
+
+    /// Returns the same leg on `stock`, keeping the states recorded before its current time.
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->RealizedVarianceLeg{
+        RealizedVarianceLeg{history: rebased_history(&self.history, stock), ..RealizedVarianceLeg::new(stock, &self.observation_times)}
+    }
+
+    fn get_expiry(&self)->TimeStamp{
+        self.observation_times[self.observation_times.len()-1]
+    }
+
+    fn update(&mut self){
+        if self.history[self.history.len()-1].get_time() == self.underlying_stock.get_current_state().get_time() {
+            return;
+        }
+        self.history.push(self.underlying_stock.get_current_state());
+    }
+
+    fn get_time_to_expiry(&self)->Option<Duration>{
+        self.get_expiry().checked_duration_since(self.underlying_stock.get_current_state().get_time())
+    }
+
+    /// Returns the future observation times.
+    fn get_schedule(&self)->Vec<TimeStamp>{
+        future_times(&self.underlying_stock, &self.observation_times)
+    }
+
+    /// Returns the realized variance of one simulated path, using one sample of `random_samples` per future observation time.
+    fn simulated_variance(&self, random_samples: &[f64], r: f64)->f64{
+        realized_variance(&fixings_at(&self.underlying_stock, &self.history, &self.observation_times, random_samples, r), &self.observation_times)
+    }
+
+    /// Returns the realized variance with the values at the future observation times taken from `path`.
+    fn variance_on_path(&self, path: &Path)->f64{
+        realized_variance(&fixings_on_path(&self.underlying_stock, &self.history, &self.observation_times, path), &self.observation_times)
+    }
+}
+
+/// A variance swap, paying `notional*(realized_variance-strike)` at the last observation time, where the realized variance
+/// is computed from the values of the underlying at the observation times by `realized_variance`.
+pub struct VarianceSwap{
+    /// The observation times and the recorded states of the underlying.
+    leg: RealizedVarianceLeg,
+    /// The strike, in variance units.
+    strike: f64,
+    /// The variance notional of the swap.
+    notional: f64,
+}
+
+impl VarianceSwap {
+    /// Returns a new variance swap.
+    /// # Parameters
+    /// - `underlying_stock`: A shared reference to the underlying stock.
+    /// - `observation_times`: The observation times. Needs to be sorted with unique values, with at least two values. The last observation time is the expiry.
+    /// - `strike`: The strike, in variance units, e.g. `0.04` for a volatility strike of 20%.
+    /// - `notional`: The variance notional.
+    /// # Panics
+    /// Panics if there are less than two observation times, or if the strike is not positive.
+    pub fn new(underlying_stock: &Arc<GeometricBrownianMotionStock>, observation_times: &[TimeStamp], strike: f64, notional: f64)->VarianceSwap{
+        if observation_times.len()<2{
+            panic!("A variance swap needs at least two observation times.");
+        }
+        if strike.is_nan() || strike <= 0.0 {
+            panic!("The strike of a variance swap must be positive.");
+        }
+        VarianceSwap{leg: RealizedVarianceLeg::new(underlying_stock, observation_times), strike, notional}
+    }
+
+    /// Returns the expiry of the swap, i.e. the last observation time.
+    pub fn get_expiry(&self) -> TimeStamp{
+        self.leg.get_expiry()
+    }
+
+    /// Updates the swap with the current state of the underlying stock. Should be called at every past observation time.
+    pub fn update(&mut self){
+        self.leg.update();
+    }
+}
+
+impl DerivativeOption<GeometricBrownianMotionStock> for VarianceSwap {
+    /// Returns the time to expiry of the swap, or None if the swap expiered.
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.leg.get_time_to_expiry()
+    }
+
+    /// Returns the number of random samples needed to price one path of the swap, i.e. the number of future observation times.
+    fn get_dimensionality(&self)->usize {
+        self.leg.get_schedule().len()
+    }
+
+    /// Prices the swap (not discounted) given one path of the underlying.
+    /// #Parameters
+    /// - `random_samples` - a vector of iid Gaussian samples of length `self.get_dimensionality()`.
+    /// - `r` - the short rate of interest.
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        self.notional*(self.leg.simulated_variance(random_samples, r)-self.strike)
+    }
+}
+
+impl PathPayoff for VarianceSwap {
+    fn get_payment_time(&self)->TimeStamp{
+        self.get_expiry()
+    }
+
+    fn get_schedule(&self)->Vec<TimeStamp>{
+        self.leg.get_schedule()
+    }
+
+    fn path_payoff(&self, path: &Path, _r: f64)->f64{
+        self.notional*(self.leg.variance_on_path(path)-self.strike)
+    }
+}
+
+impl HasGreeks for VarianceSwap {
+    fn get_underlying(&self)->Arc<GeometricBrownianMotionStock>{
+        Arc::clone(&self.leg.underlying_stock)
+    }
+
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->VarianceSwap{
+        VarianceSwap{leg: self.leg.with_underlying(stock), ..*self}
+    }
+}
+
+
+/// A volatility swap, paying `notional*(realized_volatility-strike)` at the last observation time, where the realized volatility
+/// is the square root of the realized variance computed by `realized_variance`.
+pub struct VolatilitySwap{
+    /// The observation times and the recorded states of the underlying.
+    leg: RealizedVarianceLeg,
+    /// The strike, in volatility units.
+    strike: f64,
+    /// The volatility notional of the swap.
+    notional: f64,
+}
+
+impl VolatilitySwap {
+    /// Returns a new volatility swap.
+    /// # Parameters
+    /// - `underlying_stock`: A shared reference to the underlying stock.
+    /// - `observation_times`: The observation times. Needs to be sorted with unique values, with at least two values. The last observation time is the expiry.
+    /// - `strike`: The strike, in volatility units, e.g. `0.2` for 20%.
+    /// - `notional`: The volatility notional.
+    /// # Panics
+    /// Panics if there are less than two observation times, or if the strike is not positive.
+    pub fn new(underlying_stock: &Arc<GeometricBrownianMotionStock>, observation_times: &[TimeStamp], strike: f64, notional: f64)->VolatilitySwap{
+        if observation_times.len()<2{
+            panic!("A volatility swap needs at least two observation times.");
+        }
+        if strike.is_nan() || strike <= 0.0 {
+            panic!("The strike of a volatility swap must be positive.");
+        }
+        VolatilitySwap{leg: RealizedVarianceLeg::new(underlying_stock, observation_times), strike, notional}
+    }
+
+    /// Returns the expiry of the swap, i.e. the last observation time.
+    pub fn get_expiry(&self) -> TimeStamp{
+        self.leg.get_expiry()
+    }
+
+    /// Updates the swap with the current state of the underlying stock. Should be called at every past observation time.
+    pub fn update(&mut self){
+        self.leg.update();
+    }
+}
+
+impl DerivativeOption<GeometricBrownianMotionStock> for VolatilitySwap {
+    /// Returns the time to expiry of the swap, or None if the swap expiered.
+    fn get_time_to_expiry(&self)->Option<Duration> {
+        self.leg.get_time_to_expiry()
+    }
+
+    /// Returns the number of random samples needed to price one path of the swap, i.e. the number of future observation times.
+    fn get_dimensionality(&self)->usize {
+        self.leg.get_schedule().len()
+    }
+
+    /// Prices the swap (not discounted) given one path of the underlying.
+    /// #Parameters
+    /// - `random_samples` - a vector of iid Gaussian samples of length `self.get_dimensionality()`.
+    /// - `r` - the short rate of interest.
+    fn price_path(&self, random_samples: &[f64], r: f64)->f64 {
+        self.notional*(self.leg.simulated_variance(random_samples, r).sqrt()-self.strike)
+    }
+}
+
+impl PathPayoff for VolatilitySwap {
+    fn get_payment_time(&self)->TimeStamp{
+        self.get_expiry()
+    }
+
+    fn get_schedule(&self)->Vec<TimeStamp>{
+        self.leg.get_schedule()
+    }
+
+    fn path_payoff(&self, path: &Path, _r: f64)->f64{
+        self.notional*(self.leg.variance_on_path(path).sqrt()-self.strike)
+    }
+}
+
+impl HasGreeks for VolatilitySwap {
+    fn get_underlying(&self)->Arc<GeometricBrownianMotionStock>{
+        Arc::clone(&self.leg.underlying_stock)
+    }
+
+    fn with_underlying(&self, stock: &Arc<GeometricBrownianMotionStock>)->VolatilitySwap{
+        VolatilitySwap{leg: self.leg.with_underlying(stock), ..*self}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monte_carlo_pricer::{monte_carlo_pricer, monte_carlo_simulation_with_generator};
+    use crate::random_number_generator::RandomNumberGenerator;
+    use crate::statistics_gatherer::{MeanStatisticsGatherer, StatisticsGathererTrait};
+    use crate::utils::NonNegativeFloat;
+
+    #[test]
+    fn realized_variance_test(){
+        let times = [TimeStamp::from(0.0), TimeStamp::from(0.5), TimeStamp::from(1.0)];
+        let fixings = [100.0, 100.0*0.1_f64.exp(), 100.0];
+        assert!((realized_variance(&fixings, &times)-0.02).abs()<1e-15);
+    }
+
+    #[test]
+    fn variance_swap_fair_strike_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let times: Vec<TimeStamp> = (0..=52).map(|i| TimeStamp::from(i as f64/52.0)).collect();
+        let swap = VarianceSwap::new(&stock, &times, 0.04, 100.0);
+        assert!(monte_carlo_pricer(&swap, 0.03, Some(5), 20000).abs()<0.05);
+        let mut gatherer = MeanStatisticsGatherer::new();
+        monte_carlo_simulation_with_generator(&swap, &*stock, &mut gatherer, 0.03, &mut RandomNumberGenerator::new(Some(5)), 20000);
+        assert!((gatherer.get_results_so_far()[0][0]-monte_carlo_pricer(&swap, 0.03, Some(5), 20000)).abs()<1e-10);
+    }
+
+    #[test]
+    fn volatility_swap_convexity_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0), 
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        let times: Vec<TimeStamp> = (0..=12).map(|i| TimeStamp::from(i as f64/12.0)).collect();
+        let strike = crate::raw_formulas::volatility_swap_strike(0.04, 2.0*0.2_f64.powi(4)/12.0);
+        assert!(strike < 0.2);
+        let swap = VolatilitySwap::new(&stock, &times, strike, 100.0);
+        assert!(monte_carlo_pricer(&swap, 0.0, Some(5), 100000).abs()<0.03);
+        let naive_swap = VolatilitySwap::new(&stock, &times, 0.2, 100.0);
+        assert!(monte_carlo_pricer(&naive_swap, 0.0, Some(5), 100000) < -0.3);
+        assert_eq!(crate::raw_formulas::try_volatility_swap_strike(0.0, 0.01), Err(crate::error::PricingError::InvalidMarketData));
+    }
+
+    #[test]
+    #[should_panic(expected = "The strike of a volatility swap must be positive.")]
+    fn volatility_swap_zero_strike_test(){
+        let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(100.0), TimeStamp::from(0.0),
+            0.1, NonNegativeFloat::from(0.2), NonNegativeFloat::from(0.0)));
+        VolatilitySwap::new(&stock, &[TimeStamp::from(0.0), TimeStamp::from(1.0)], 0.0, 100.0);
+    }
+}
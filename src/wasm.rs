@@ -0,0 +1,156 @@
+//! Provides `wasm-bindgen` wrappers of the Black-Scholes formulas and of the Monte Carlo pricer, so that the library can be
+//! used from JavaScript when compiled to `wasm32-unknown-unknown`. This module is available with the `wasm` feature, which
+//! also takes the entropy for unseeded random number generators from the browser (`crypto.getRandomValues`).
+//!
+//! Invalid parameters are reported as JavaScript exceptions rather than panics.
+//!
+//! # Examples
+//!
+//! The crate type is left to the build command rather than set in `Cargo.toml`, since a `cdylib` cannot be linked by the
+//! `no_std` builds. Building with `cargo rustc --release --lib --target wasm32-unknown-unknown --features wasm --crate-type cdylib`
+//! and running `wasm-bindgen --target web` on the resulting `.wasm` file, the functions can be called as
+//!
+//! ```text
+//! import init, { europeanCallPrice } from "./pkg/derivative_pricer.js";
+//! await init();
+//! const price = europeanCallPrice(100, 100, 0.05, 1, 0.2, 0);
+//! ```
+
+use std::sync::Arc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::greeks::automatic_differentiation::{european_call_greeks, european_put_greeks};
+use crate::monte_carlo_pricer::monte_carlo_pricer_with_error;
+use crate::option::VanillaStockOption;
+use crate::payoff::{CallPayoff, PutPayoff};
+use crate::raw_formulas;
+use crate::stock::GeometricBrownianMotionStock;
+use crate::utils::{NonNegativeFloat, TimeStamp};
+
+/// The price and first order greeks of an option.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JsGreeks{
+    /// The price of the option.
+    pub price: f64,
+    /// The derivative of the price with respect to the spot.
+    pub delta: f64,
+    /// The second derivative of the price with respect to the spot.
+    pub gamma: f64,
+    /// The derivative of the price with respect to the volatility.
+    pub vega: f64,
+    /// The derivative of the price with respect to the current time.
+    pub theta: f64,
+    /// The derivative of the price with respect to the short rate of interest.
+    pub rho: f64,
+}
+
+impl From<crate::greeks::Greeks> for JsGreeks {
+    fn from(value: crate::greeks::Greeks) -> Self {
+        JsGreeks{price: value.price, delta: value.delta, gamma: value.gamma, vega: value.vega, theta: value.theta, rho: value.rho}
+    }
+}
+
+/// A Monte Carlo estimate of the price of an option.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JsMcResult{
+    /// The estimated price.
+    pub price: f64,
+    /// The standard error of the estimate.
+    pub std_error: f64,
+}
+
+/// Returns the price of a european call option, as in `raw_formulas::european_call_option_price`.
+#[wasm_bindgen(js_name = europeanCallPrice)]
+pub fn european_call_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)
+    ->Result<f64, JsError>{
+    Ok(raw_formulas::try_european_call_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)?)
+}
+
+/// Returns the price of a european put option, as in `raw_formulas::european_put_option_price`.
+#[wasm_bindgen(js_name = europeanPutPrice)]
+pub fn european_put_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)
+    ->Result<f64, JsError>{
+    Ok(raw_formulas::try_european_put_option_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)?)
+}
+
+/// Returns the price of a digital call option, as in `raw_formulas::digital_call_price`.
+#[wasm_bindgen(js_name = digitalCallPrice)]
+pub fn digital_call_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)
+    ->Result<f64, JsError>{
+    Ok(raw_formulas::try_digital_call_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)?)
+}
+
+/// Returns the price of a digital put option, as in `raw_formulas::digital_put_price`.
+#[wasm_bindgen(js_name = digitalPutPrice)]
+pub fn digital_put_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)
+    ->Result<f64, JsError>{
+    Ok(raw_formulas::try_digital_put_price(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate)?)
+}
+
+/// Returns the price and greeks of a european call option.
+#[wasm_bindgen(js_name = europeanCallGreeks)]
+pub fn european_call_greeks_js(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)
+    ->Result<JsGreeks, JsError>{
+    check_inputs(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(european_call_greeks(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate).into())
+}
+
+/// Returns the price and greeks of a european put option.
+#[wasm_bindgen(js_name = europeanPutGreeks)]
+pub fn european_put_greeks_js(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64, divident_rate: f64)
+    ->Result<JsGreeks, JsError>{
+    check_inputs(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    Ok(european_put_greeks(spot, strike, short_rate_of_interest, time_to_expiry, volatility, divident_rate).into())
+}
+
+/// Returns a Monte Carlo estimate of the price of a european call option (`is_call` true) or put option (`is_call` false).
+///
+/// # Parameters
+///
+/// - `seed` - An optional seed for the random number generation. If `undefined`, a random seed will be used.
+/// - `number_of_paths` - The number of trials in the simulation.
+#[wasm_bindgen(js_name = monteCarloEuropeanPrice)]
+#[allow(clippy::too_many_arguments)]
+pub fn monte_carlo_european_price(spot: f64, strike: f64, short_rate_of_interest: f64, time_to_expiry: f64, volatility: f64,
+    divident_rate: f64, is_call: bool, seed: Option<u64>, number_of_paths: u32)->Result<JsMcResult, JsError>{
+    check_inputs(&[spot, strike, time_to_expiry, volatility, divident_rate])?;
+    let stock = Arc::new(GeometricBrownianMotionStock::new(NonNegativeFloat::from(spot), TimeStamp::from(0.0), short_rate_of_interest,
+        NonNegativeFloat::from(volatility), NonNegativeFloat::from(divident_rate)));
+    let expiry = TimeStamp::from(time_to_expiry);
+    let result = if is_call {
+        monte_carlo_pricer_with_error(&VanillaStockOption::with_payoff(&stock, expiry, CallPayoff::new(strike)), short_rate_of_interest,
+            seed, number_of_paths as usize)
+    } else {
+        monte_carlo_pricer_with_error(&VanillaStockOption::with_payoff(&stock, expiry, PutPayoff::new(strike)), short_rate_of_interest,
+            seed, number_of_paths as usize)
+    };
+    Ok(JsMcResult{price: result.get_price(), std_error: result.get_std_error()})
+}
+
+/// Returns `PricingError::NegativeInput` if one of `values` is negative or NaN.
+fn check_inputs(values: &[f64])->Result<(), crate::error::PricingError>{
+    if values.iter().any(|x| *x < 0.0 || x.is_nan()) {
+        return Err(crate::error::PricingError::NegativeInput);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Only the successful calls are tested, since creating a `JsError` needs a JavaScript host.
+    #[test]
+    fn wasm_wrappers_test(){
+        let price = european_call_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01).unwrap();
+        assert_eq!(price, raw_formulas::european_call_option_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01));
+        let greeks = european_put_greeks_js(100.0, 100.0, 0.05, 1.0, 0.2, 0.01).unwrap();
+        assert!((greeks.delta-raw_formulas::put_delta(100.0, 100.0, 0.05, 1.0, 0.2, 0.01)).abs() < 1e-12);
+        let result = monte_carlo_european_price(100.0, 100.0, 0.05, 1.0, 0.2, 0.01, true, Some(1), 20000).unwrap();
+        assert!((result.price-price).abs() < 4.0*result.std_error);
+        assert!(check_inputs(&[1.0, -1.0]).is_err());
+    }
+}
@@ -0,0 +1,340 @@
+//! Provides a temperature underlying and options on degree day indices, the most traded weather derivatives.
+//!
+//! The daily average temperature is modelled as a deterministic seasonal mean plus mean reverting noise,
+//!
+//! `T_t = m(t) + X_t`, where `dX = -kappa*X*dt + sigma*dW`,
+//!
+//! i.e. `dT = (m'(t) + kappa*(m(t)-T))*dt + sigma*dW`, and `m(t) = level + trend*t + amplitude*sin(2*pi*(t-phase))` with `t`
+//! measured in years. The noise is simulated exactly, so a path only needs one Gaussian sample per observed day.
+//!
+//! A heating degree day index accumulates `max(base-T, 0)` and a cooling degree day index accumulates `max(T-base, 0)` over
+//! the days of a contract period; options on them pay a tick size per degree day above (call) or below (put) the strike.
+//! The parameters are taken to be those of the pricing measure, i.e. paths are used as is by the Monte Carlo pricer.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use derivative_pricer::payoff::VanillaType;
+//! use derivative_pricer::monte_carlo_pricer::monte_carlo_pricer;
+//! use derivative_pricer::utils::{NonNegativeFloat, TimeStamp};
+//! use derivative_pricer::weather::{DegreeDayIndex, DegreeDayOption, SeasonalMean, TemperatureProcess};
+//! let mean = SeasonalMean::new(12.0, 0.0, 9.0, 0.3);
+//! let temperature = Arc::new(TemperatureProcess::new(4.0, TimeStamp::from(0.0), mean, NonNegativeFloat::from(80.0),
+//!     NonNegativeFloat::from(40.0)));
+//! // A call on the heating degree days of January, paying 20 per degree day above 350.
+//! let option = DegreeDayOption::new(&temperature, DegreeDayIndex::Heating, VanillaType::Call, TimeStamp::from(0.0), 31,
+//!     18.0, 350.0, 20.0);
+//! let price = monte_carlo_pricer(&option, 0.02, Some(1), 1000);
+//! assert!(price > 0.0);
+//! ```
+
+use std::sync::Arc;
+
+use crate::payoff::VanillaType;
+use crate::error::PricingError;
+use crate::option::{DerivativeOption, Underlying};
+use crate::ornstein_uhlenbeck::OrnsteinUhlenbeckProcess;
+use crate::utils::{cumulative_normal_function, normal_probability_density_function, Duration, NonNegativeFloat, TimeStamp};
+
+/// The number of days in a year, used to place the daily observations of a degree day index.
+pub const DAYS_PER_YEAR: f64 = 365.0;
+
+/// The seasonal mean `m(t) = level + trend*t + amplitude*sin(2*pi*(t-phase))` of a temperature, with `t` in years.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SeasonalMean{
+    level: f64,
+    trend: f64,
+    amplitude: f64,
+    phase: f64,
+}
+
+impl SeasonalMean {
+    /// Returns a new seasonal mean.
+    /// # Parameters
+    /// - `level` - The mean temperature at time 0, averaged over a year.
+    /// - `trend` - The yearly change of the mean temperature, e.g. due to warming.
+    /// - `amplitude` - The amplitude of the seasonal cycle.
+    /// - `phase` - The time (as a fraction of a year) at which the seasonal cycle crosses the average upwards.
+    pub fn new(level: f64, trend: f64, amplitude: f64, phase: f64)->SeasonalMean{
+        SeasonalMean{level, trend, amplitude, phase}
+    }
+
+    /// Returns a seasonal mean with no trend and no seasonal cycle.
+    pub fn constant(level: f64)->SeasonalMean{
+        SeasonalMean::new(level, 0.0, 0.0, 0.0)
+    }
+
+    /// Returns the mean temperature at time `time`.
+    pub fn value(&self, time: f64)->f64{
+        self.level+self.trend*time+self.amplitude*(2.0*std::f64::consts::PI*(time-self.phase)).sin()
+    }
+}
+
+/// A daily average temperature following a seasonal mean plus Ornstein-Uhlenbeck noise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TemperatureProcess{
+    /// The deviation `X` of the temperature from its seasonal mean.
+    deviation: OrnsteinUhlenbeckProcess,
+    /// The seasonal mean `m`.
+    seasonal_mean: SeasonalMean,
+}
+
+/// The temperature at some time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TemperatureState{
+    temperature: f64,
+    time: TimeStamp,
+}
+
+impl TemperatureState {
+    pub fn new(temperature: f64, time: TimeStamp)->TemperatureState{
+        TemperatureState{temperature, time}
+    }
+
+    pub fn get_temperature(&self)->f64{
+        self.temperature
+    }
+
+    pub fn get_time(&self)->TimeStamp{
+        self.time
+    }
+}
+
+impl Underlying for TemperatureProcess {
+
+}
+
+impl TemperatureProcess {
+    /// Returns a new temperature process.
+    /// # Parameters
+    /// - `temperature` - The current temperature.
+    /// - `current_time` - The time at which the temperature was observed, in years.
+    /// - `seasonal_mean` - The seasonal mean to which the temperature reverts.
+    /// - `mean_reversion_speed` - The yearly speed `kappa` at which the temperature reverts to its seasonal mean.
+    /// - `volatility` - The yearly volatility `sigma` of the temperature.
+    pub fn new(temperature: f64, current_time: TimeStamp, seasonal_mean: SeasonalMean, mean_reversion_speed: NonNegativeFloat,
+                volatility: NonNegativeFloat)->TemperatureProcess{
+        let deviation = temperature-seasonal_mean.value(f64::from(current_time));
+        TemperatureProcess{
+            deviation: OrnsteinUhlenbeckProcess::new(deviation, current_time, mean_reversion_speed, 0.0, volatility),
+            seasonal_mean,
+        }
+    }
+
+    ///Returns the seasonal mean.
+    pub fn get_seasonal_mean(&self)->SeasonalMean{
+        self.seasonal_mean
+    }
+
+    ///Returns the speed of mean reversion.
+    pub fn get_mean_reversion_speed(&self)->NonNegativeFloat{
+        self.deviation.get_mean_reversion_speed()
+    }
+
+    ///Returns the volatility.
+    pub fn get_volatility(&self)->NonNegativeFloat{
+        self.deviation.get_volatility()
+    }
+
+    ///Returns the current temperature and time.
+    pub fn get_current_state(&self)->TemperatureState{
+        let state = self.deviation.get_current_state();
+        TemperatureState{temperature: self.seasonal_mean.value(f64::from(state.get_time()))+state.get_value(), time: state.get_time()}
+    }
+
+    ///Returns the expected temperature `time_step` after the current time.
+    pub fn get_mean(&self, time_step: Duration)->f64{
+        let time = self.deviation.get_current_state().get_time()+time_step;
+        self.seasonal_mean.value(f64::from(time))+self.deviation.get_mean(time_step)
+    }
+
+    ///Returns the variance of the temperature `time_step` after the current time.
+    pub fn get_variance(&self, time_step: Duration)->f64{
+        self.deviation.get_variance(time_step)
+    }
+
+    /// Generates the temperatures at the provided time stamps, sampling every step exactly.
+    /// # Parameters
+    /// - `gaussians` - iid samples of N(0,1). Must be the same size or larger than `time_stamps`.
+    /// - `time_stamps` - Increasing time stamps, the first one not before the current time.
+    /// # Panics
+    /// - If `time_stamps` is empty, not increasing, or there are time stamps before the current time.
+    /// - If `gaussians.len()<time_stamps.len()`.
+    pub fn generate_path_from_time_stamps(&self, gaussians: &[f64], time_stamps: &[TimeStamp])->Vec<TemperatureState>{
+        self.try_generate_path_from_time_stamps(gaussians, time_stamps).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `generate_path_from_time_stamps`, but returns a `PricingError` instead of panicking.
+    pub fn try_generate_path_from_time_stamps(&self, gaussians: &[f64], time_stamps: &[TimeStamp])
+        ->Result<Vec<TemperatureState>, PricingError>{
+        Ok(self.deviation.try_generate_path_from_time_stamps(gaussians, time_stamps)?.iter()
+            .map(|state| TemperatureState{
+                temperature: self.seasonal_mean.value(f64::from(state.get_time()))+state.get_value(),
+                time: state.get_time(),
+            })
+            .collect())
+    }
+}
+
+/// The kind of a degree day index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DegreeDayIndex{
+    /// Heating degree days, accumulating `max(base-T, 0)`.
+    Heating,
+    /// Cooling degree days, accumulating `max(T-base, 0)`.
+    Cooling,
+}
+
+impl DegreeDayIndex {
+    /// Returns the degree days of a day with the average temperature `temperature`.
+    pub fn degree_days(&self, temperature: f64, base_temperature: f64)->f64{
+        match self {
+            DegreeDayIndex::Heating => f64::max(base_temperature-temperature, 0.0),
+            DegreeDayIndex::Cooling => f64::max(temperature-base_temperature, 0.0),
+        }
+    }
+
+    /// Returns the value of the index over the days with the average temperatures `temperatures`.
+    pub fn accumulate(&self, temperatures: &[f64], base_temperature: f64)->f64{
+        temperatures.iter().map(|t| self.degree_days(*t, base_temperature)).sum()
+    }
+}
+
+/// An option on a heating or cooling degree day index over consecutive days, paying `tick_size*max(index-strike, 0)` for a
+/// call and `tick_size*max(strike-index, 0)` for a put at the last day, optionally capped.
+pub struct DegreeDayOption{
+    /// A shared reference to the underlying temperature.
+    underlying: Arc<TemperatureProcess>,
+    index: DegreeDayIndex,
+    vanilla_type: VanillaType,
+    /// The times at which the daily temperatures are observed.
+    observation_times: Vec<TimeStamp>,
+    base_temperature: f64,
+    strike: f64,
+    tick_size: f64,
+    /// The maximal payoff, if any.
+    cap: Option<f64>,
+}
+
+impl DegreeDayOption {
+    /// Returns a new degree day option.
+    /// # Parameters
+    /// - `underlying` - The temperature.
+    /// - `index` - Heating or cooling degree days.
+    /// - `vanilla_type` - Call or put on the index.
+    /// - `first_day` - The time at which the temperature of the first day of the period is observed.
+    /// - `days` - The number of days in the period, each observed `1/DAYS_PER_YEAR` after the previous one.
+    /// - `base_temperature` - The base temperature of the index, usually 18°C or 65°F.
+    /// - `strike` - The strike, in degree days.
+    /// - `tick_size` - The payment per degree day.
+    /// # Panics
+    /// Panics if `days` is zero, or if `strike` or `tick_size` is negative.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(underlying: &Arc<TemperatureProcess>, index: DegreeDayIndex, vanilla_type: VanillaType, first_day: TimeStamp, days: usize,
+                base_temperature: f64, strike: f64, tick_size: f64)->DegreeDayOption{
+        assert!(days > 0, "A degree day option needs at least one day");
+        if strike < 0.0 || tick_size < 0.0 || strike.is_nan() || tick_size.is_nan() {
+            panic!("One of the parameters is negative");
+        }
+        let observation_times = (0..days).map(|day| first_day+Duration::from(day as f64/DAYS_PER_YEAR)).collect();
+        DegreeDayOption{underlying: Arc::clone(underlying), index, vanilla_type, observation_times, base_temperature, strike, tick_size, cap: None}
+    }
+
+    /// Returns the option with its payoff capped at `cap`.
+    pub fn with_cap(self, cap: f64)->DegreeDayOption{
+        DegreeDayOption{cap: Some(cap), ..self}
+    }
+
+    /// Returns the times at which the daily temperatures are observed.
+    pub fn get_observation_times(&self)->&[TimeStamp]{
+        &self.observation_times
+    }
+
+    /// Returns the payoff of the option given the value of the index.
+    pub fn payoff(&self, index_value: f64)->f64{
+        let payoff = self.tick_size*match self.vanilla_type {
+            VanillaType::Call => f64::max(index_value-self.strike, 0.0),
+            VanillaType::Put => f64::max(self.strike-index_value, 0.0),
+        };
+        self.cap.map_or(payoff, |cap| payoff.min(cap))
+    }
+
+    /// Returns the expected value of the index, in closed form since every daily temperature is normally distributed.
+    pub fn expected_index(&self)->f64{
+        let now = self.underlying.get_current_state().get_time();
+        self.observation_times.iter().map(|time| {
+            let time_step = time.checked_duration_since(now).unwrap_or(Duration::from(0.0));
+            let (mean, deviation) = (self.underlying.get_mean(time_step), self.underlying.get_variance(time_step).sqrt());
+            let distance = match self.index {
+                DegreeDayIndex::Heating => self.base_temperature-mean,
+                DegreeDayIndex::Cooling => mean-self.base_temperature,
+            };
+            if deviation == 0.0 {
+                return f64::max(distance, 0.0);
+            }
+            let d = distance/deviation;
+            distance*cumulative_normal_function(d)+deviation*normal_probability_density_function(d)
+        }).sum()
+    }
+}
+
+impl DerivativeOption<TemperatureProcess> for DegreeDayOption {
+    /// Returns the time until the last day of the period, where the current time is considered to be the current time of the
+    /// temperature.
+    fn get_time_to_expiry(&self)->Option<Duration>{
+        self.observation_times[self.observation_times.len()-1].checked_duration_since(self.underlying.get_current_state().get_time())
+    }
+
+    /// Returns the number of random samples needed to price one path of the option, one per day.
+    fn get_dimensionality(&self)->usize{
+        self.observation_times.len()
+    }
+
+    /// Prices the option (not discounted) along one path of daily temperatures.
+    fn price_path(&self, random_samples: &[f64], _r: f64)->f64{
+        let path = self.underlying.generate_path_from_time_stamps(random_samples, &self.observation_times);
+        let temperatures: Vec<f64> = path.iter().map(|state| state.get_temperature()).collect();
+        self.payoff(self.index.accumulate(&temperatures, self.base_temperature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monte_carlo_pricer::monte_carlo_pricer_with_error;
+
+    #[test]
+    fn temperature_process_test(){
+        let mean = SeasonalMean::new(10.0, 0.1, 8.0, 0.25);
+        assert!((mean.value(0.5)-(10.05+8.0)).abs() < 1e-12);
+        let process = TemperatureProcess::new(3.0, TimeStamp::from(0.0), mean, NonNegativeFloat::from(50.0), NonNegativeFloat::from(30.0));
+        assert!((process.get_current_state().get_temperature()-3.0).abs() < 1e-12);
+        // Far in the future the temperature is back on its seasonal mean.
+        assert!((process.get_mean(Duration::from(0.5))-mean.value(0.5)).abs() < 1e-9);
+        assert!((process.get_variance(Duration::from(0.5))-9.0).abs() < 1e-9);
+        let path = process.generate_path_from_time_stamps(&[0.0, 1.0], &[TimeStamp::from(0.1), TimeStamp::from(0.2)]);
+        assert!((path[0].get_temperature()-process.get_mean(Duration::from(0.1))).abs() < 1e-12);
+        assert!(path[1].get_temperature() > process.get_mean(Duration::from(0.2)));
+        assert!(process.try_generate_path_from_time_stamps(&[0.0], &[]).is_err());
+    }
+
+    #[test]
+    fn degree_day_option_test(){
+        let mean = SeasonalMean::new(15.0, 0.0, 10.0, 0.3);
+        let process = Arc::new(TemperatureProcess::new(20.0, TimeStamp::from(0.5), mean, NonNegativeFloat::from(60.0),
+            NonNegativeFloat::from(35.0)));
+        let r = 0.02;
+        // A call struck at zero pays the index, whose expectation is known in closed form.
+        let index = DegreeDayOption::new(&process, DegreeDayIndex::Cooling, VanillaType::Call, TimeStamp::from(0.5), 30, 18.0, 0.0, 1.0);
+        let result = monte_carlo_pricer_with_error(&index, r, Some(11), 20000);
+        let discount_factor = (-r*f64::from(index.get_time_to_expiry().unwrap())).exp();
+        assert!((result.get_price()-discount_factor*index.expected_index()).abs() < 4.0*result.get_std_error());
+        let capped = DegreeDayOption::new(&process, DegreeDayIndex::Heating, VanillaType::Put, TimeStamp::from(0.5), 30, 18.0, 100.0, 10.0)
+            .with_cap(500.0);
+        assert_eq!(capped.payoff(0.0), 500.0);
+        assert_eq!(capped.payoff(120.0), 0.0);
+        assert_eq!(DegreeDayIndex::Heating.accumulate(&[10.0, 20.0, 17.0], 18.0), 9.0);
+        assert_eq!(capped.get_dimensionality(), 30);
+    }
+}